@@ -0,0 +1,452 @@
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::hash::{evaluator::MAX_DEPTH, tokens::TAB_WIDTH};
+use crate::repl::{
+    keymap::{Action, Binding, Key},
+    prompt::Prompt,
+};
+use crate::style::Theme;
+
+/// The cursor modes of the REPL a configuration can choose.
+const MODES: [&str; 3] = ["normal", "vi", "emacs"];
+
+/// Enum representing the errors of reading a configuration file
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file cannot be read: (path, error)
+    Io(PathBuf, io::Error),
+    /// A line of the file is not a known setting with a valid value: (line number, problem)
+    Invalid(usize, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            ConfigError::Invalid(line, problem) => write!(f, "line {}: {}", line, problem),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// The defaults of the REPL and of the interpreter, read from a configuration file
+///
+/// The file is written in TOML, with a `key = value` line per setting, like the following.
+/// Settings left out keep their defaults. Values are strings between quotes or whole numbers,
+/// and keys that are not made of letters, digits, `_`, and `-` go between quotes.
+///
+/// ```toml
+/// mode = "vi"            # normal, vi, or emacs
+/// prompt = "{line} {mode}{status} "  # {line}, {mode}, and {status} are filled in
+/// theme = "light"        # dark, light, or plain
+/// history_size = 500     # lines the REPL remembers
+/// max_depth = 2000       # how deeply calls can nest
+/// tab_width = 8          # columns between tab stops in diagnostics
+/// keys.ctrl-k = "clear-screen"
+/// keys.ctrl-d = "none"   # leaves the key without an action
+///
+/// [keys.normal]          # in the normal mode of the vi cursor mode
+/// q = "exit"
+/// "g g" = "line-start"   # keys pressed one after the other
+/// ```
+///
+/// # Examples
+/// ```
+/// use hydrogen::config::Config;
+///
+/// let config = Config::parse("mode = \"vi\"\nmax_depth = 50 # shallow").unwrap();
+/// assert_eq!(config.mode, "vi");
+/// assert_eq!(config.max_depth, 50);
+/// assert_eq!(config.prompt, "> ");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// The cursor mode of the REPL: `normal`, `vi`, or `emacs`.
+    pub mode: String,
+    /// The template of the text shown before every line of the REPL, see [`Prompt`].
+    pub prompt: String,
+    /// The colors of the REPL.
+    pub theme: Theme,
+    /// How many entered lines the REPL remembers.
+    pub history_size: usize,
+    /// How deeply calls of functions can nest.
+    pub max_depth: usize,
+    /// The number of columns between tab stops, for the columns of diagnostics.
+    pub tab_width: usize,
+    /// The keys bound to other actions of the REPL than by default, in order.
+    pub bindings: Vec<Binding>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            mode: "normal".to_string(),
+            prompt: "> ".to_string(),
+            theme: Theme::default(),
+            history_size: 100,
+            max_depth: MAX_DEPTH,
+            tab_width: TAB_WIDTH,
+            bindings: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Returns the path the configuration is read from by default:
+    /// `$XDG_CONFIG_HOME/hydrogen/config.toml`, or `~/.config/hydrogen/config.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        let directory = match env::var_os("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+            Some(directory) => PathBuf::from(directory),
+            None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        Some(directory.join("hydrogen").join("config.toml"))
+    }
+
+    /// Reads the configuration from a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file, which must exist, or None for the file at the default path, whose
+    ///   absence leaves every setting at its default.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Config, ConfigError>` - The configuration, or why the file cannot be used.
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Self::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text),
+            Err(error) if !required && error.kind() == io::ErrorKind::NotFound => {
+                Ok(Self::default())
+            }
+            Err(error) => Err(ConfigError::Io(path, error)),
+        }
+    }
+
+    /// Parses a configuration, with the defaults for the settings it leaves out.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text of the configuration file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Config, ConfigError>` - The configuration, or the first line that is not a
+    ///   known setting with a valid value.
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        let mut config = Self::default();
+        let mut table = Vec::new();
+        let mut set = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let invalid = |problem: String| ConfigError::Invalid(i + 1, problem);
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            // The keys of the lines after a table header start with the key of the table
+            if let Some(header) = line.strip_prefix('[') {
+                let (key, rest) = key(header).map_err(invalid)?;
+                if rest != "]" {
+                    return Err(invalid(format!("expected `[table]`, found `{}`", line)));
+                }
+                table = key;
+                continue;
+            }
+
+            let (key, rest) = key(line).map_err(invalid)?;
+            let Some(value) = rest.strip_prefix('=') else {
+                return Err(invalid(format!("expected `key = value`, found `{}`", line)));
+            };
+            let value = value.trim();
+            let key: Vec<String> = table.iter().cloned().chain(key).collect();
+            if set.contains(&key) {
+                return Err(invalid(format!("`{}` is set twice", key.join("."))));
+            }
+            set.push(key.clone());
+
+            let path: Vec<&str> = key.iter().map(String::as_str).collect();
+            match path.as_slice() {
+                ["mode"] => {
+                    let mode = string(value).map_err(invalid)?;
+                    if !MODES.contains(&mode.as_str()) {
+                        return Err(invalid(format!("unknown mode `{}`", mode)));
+                    }
+                    config.mode = mode;
+                }
+                ["prompt"] => {
+                    let template = string(value).map_err(invalid)?;
+                    Prompt::parse(&template).map_err(invalid)?;
+                    config.prompt = template;
+                }
+                ["theme"] => {
+                    let name = string(value).map_err(invalid)?;
+                    config.theme = Theme::named(&name)
+                        .ok_or_else(|| invalid(format!("unknown theme `{}`", name)))?;
+                }
+                ["history_size"] => config.history_size = number(value).map_err(invalid)?,
+                ["max_depth"] => config.max_depth = number(value).map_err(invalid)?,
+                ["tab_width"] => match number(value).map_err(invalid)? {
+                    0 => return Err(invalid("`tab_width` must be at least 1".to_string())),
+                    width => config.tab_width = width,
+                },
+                ["keys", "normal", keys] => {
+                    let binding = binding(true, keys, value).map_err(invalid)?;
+                    config.bindings.push(binding);
+                }
+                ["keys", keys] => {
+                    let binding = binding(false, keys, value).map_err(invalid)?;
+                    config.bindings.push(binding);
+                }
+                _ => return Err(invalid(format!("unknown setting `{}`", key.join(".")))),
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Returns a line without the comment ending it, leaving `#` inside strings alone.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quote == Some('"') => escaped = true,
+            '"' | '\'' if quote == Some(c) => quote = None,
+            '"' | '\'' if quote.is_none() => quote = Some(c),
+            '#' if quote.is_none() => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Reads the key at the start of a line, whose parts are joined by dots, like
+/// `keys.normal."g g"`.
+///
+/// # Arguments
+///
+/// * `text` - The text starting with the key.
+///
+/// # Returns
+///
+/// * `Result<(Vec<String>, &str), String>` - The parts of the key, and the text after it without
+///   leading spaces, or why the text does not start with a key.
+fn key(text: &str) -> Result<(Vec<String>, &str), String> {
+    let mut parts = Vec::new();
+    let mut rest = text.trim_start();
+    loop {
+        let (part, after) = match rest.chars().next() {
+            Some('"' | '\'') => quoted(rest)?,
+            // Bare keys are made of letters, digits, `_`, and `-`
+            _ => {
+                let bare = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+                let end = rest.find(|c| !bare(c)).unwrap_or(rest.len());
+                if end == 0 {
+                    return Err(format!("expected a key, found `{}`", text.trim()));
+                }
+                (rest[..end].to_string(), &rest[end..])
+            }
+        };
+        parts.push(part);
+        rest = after.trim_start();
+        match rest.strip_prefix('.') {
+            Some(after) => rest = after.trim_start(),
+            None => return Ok((parts, rest)),
+        }
+    }
+}
+
+/// Reads a string value between quotes.
+fn string(value: &str) -> Result<String, String> {
+    match quoted(value)? {
+        (string, "") => Ok(string),
+        _ => Err(format!(
+            "expected a string between quotes, found `{}`",
+            value
+        )),
+    }
+}
+
+/// Reads the string between quotes at the start of a text.
+///
+/// Strings between `"` have their escape sequences replaced, like `\"`, `\\`, `\n`, `\t`, and
+/// `\u00e9`, and strings between `'` are taken as they are.
+///
+/// # Arguments
+///
+/// * `text` - The text starting with the string.
+///
+/// # Returns
+///
+/// * `Result<(String, &str), String>` - The string, and the text after its closing quote, or why
+///   the text does not start with a string.
+fn quoted(text: &str) -> Result<(String, &str), String> {
+    let expected = || format!("expected a string between quotes, found `{}`", text);
+    let mut chars = text.char_indices();
+    let quote = match chars.next() {
+        Some((_, c @ ('"' | '\''))) => c,
+        _ => return Err(expected()),
+    };
+
+    let mut string = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            _ if c == quote => return Ok((string, &text[i + 1..])),
+            '\\' if quote == '"' => match chars.next().map(|(_, c)| c) {
+                Some(c @ ('"' | '\\')) => string.push(c),
+                Some('n') => string.push('\n'),
+                Some('t') => string.push('\t'),
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                    let c = Some(&hex)
+                        .filter(|hex| hex.len() == 4 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+                        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                        .and_then(char::from_u32);
+                    match c {
+                        Some(c) => string.push(c),
+                        None => return Err(format!("invalid escape sequence in `{}`", text)),
+                    }
+                }
+                _ => return Err(format!("invalid escape sequence in `{}`", text)),
+            },
+            c => string.push(c),
+        }
+    }
+    Err(expected())
+}
+
+/// Reads the binding of keys to an action of the REPL, or to `none`.
+///
+/// # Arguments
+///
+/// * `normal` - Whether the keys are bound in the normal mode of the Vi cursor mode.
+/// * `keys` - The keys, like `ctrl-l`, or `g g` for keys pressed one after the other.
+/// * `value` - The name of the action, between quotes.
+fn binding(normal: bool, keys: &str, value: &str) -> Result<Binding, String> {
+    let name = string(value)?;
+    let action = match name.as_str() {
+        "none" => None,
+        _ => Some(Action::named(&name).ok_or_else(|| format!("unknown action `{}`", name))?),
+    };
+    Ok(Binding {
+        normal,
+        keys: Key::parse_sequence(keys)?,
+        action,
+    })
+}
+
+/// Reads a whole number value.
+fn number(value: &str) -> Result<usize, String> {
+    value
+        .replace('_', "")
+        .parse()
+        .map_err(|_| format!("expected a whole number, found `{}`", value))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_config() {
+        let text = r##"
+            # The defaults of the REPL
+            mode = "emacs"
+            prompt = "# \"h\" > "  # with a comment
+            theme = "plain"
+            history_size = 1_000
+
+            tab_width = 2
+            keys.normal.ctrl-x = "none"
+            keys . "g g" = 'line-start' # literal strings keep \ as it is
+
+            [keys.normal]
+            "g\u0020x" = "exit"
+        "##;
+        let config = Config::parse(text).unwrap();
+        assert_eq!(
+            config,
+            Config {
+                mode: "emacs".to_string(),
+                prompt: "# \"h\" > ".to_string(),
+                theme: Theme::PLAIN,
+                history_size: 1000,
+                tab_width: 2,
+                bindings: vec![
+                    Binding {
+                        normal: true,
+                        keys: vec![Key::parse("ctrl-x").unwrap()],
+                        action: None,
+                    },
+                    Binding {
+                        normal: false,
+                        keys: Key::parse_sequence("g g").unwrap(),
+                        action: Action::named("line-start"),
+                    },
+                    Binding {
+                        normal: true,
+                        keys: Key::parse_sequence("g x").unwrap(),
+                        action: Action::named("exit"),
+                    },
+                ],
+                ..Config::default()
+            }
+        );
+
+        let problem = |text| Config::parse(text).unwrap_err().to_string();
+        assert_eq!(problem("\nmode = \"ed\""), "line 2: unknown mode `ed`");
+        assert_eq!(problem("colour = 1"), "line 1: unknown setting `colour`");
+        assert_eq!(
+            problem("prompt = >"),
+            "line 1: expected a string between quotes, found `>`"
+        );
+        assert_eq!(
+            problem("max_depth = -1"),
+            "line 1: expected a whole number, found `-1`"
+        );
+        assert!(Config::parse("tab_width = 0").is_err());
+        assert!(Config::parse("theme").is_err());
+        assert_eq!(
+            problem("keys.ctrl-k = \"paste\""),
+            "line 1: unknown action `paste`"
+        );
+        assert!(Config::parse("prompt = \"{line \"").is_err());
+        // Keys that are not bare go between quotes, and are set once
+        assert_eq!(
+            problem("keys.normal.g g = \"line-start\""),
+            "line 1: expected `key = value`, found `keys.normal.g g = \"line-start\"`"
+        );
+        assert_eq!(
+            problem("[keys]\nctrl-k = \"none\"\n[keys]\nctrl-k = \"none\""),
+            "line 4: `keys.ctrl-k` is set twice"
+        );
+        assert_eq!(
+            problem("[keys\nmode = \"vi\""),
+            "line 1: expected `[table]`, found `[keys`"
+        );
+        assert_eq!(
+            problem("[keys]\nmode = \"exit\""),
+            "line 2: unknown key `mode`"
+        );
+        assert!(Config::parse("prompt = \"\\u00\"").is_err());
+
+        // Only a file that was asked for must exist
+        let missing = Path::new("hydrogen_missing_config.toml");
+        assert!(matches!(
+            Config::load(Some(missing)),
+            Err(ConfigError::Io(_, _))
+        ));
+    }
+}
@@ -0,0 +1,327 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    hash::{
+        ast::{Item, Stmt, Tree},
+        checker::Checker,
+        parser::Parser,
+    },
+    HydrogenError,
+};
+
+/// Enum representing the formats documentation is rendered in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Markdown, with a heading per file and per definition
+    Markdown,
+    /// A standalone HTML page
+    Html,
+}
+
+/// A function, struct, interface, constant, or variable defined at the top level of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// How the definition is used, like `add(a: num, b: num): num` or `const limit: num`.
+    pub signature: String,
+    /// The text of the doc comments of the definition, if it has any.
+    pub doc: Option<String>,
+}
+
+/// The documentation of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    /// The file that is documented.
+    pub file: PathBuf,
+    /// The definitions of the file, in order.
+    pub entries: Vec<Entry>,
+}
+
+/// Returns the definitions the documentation of a program shows.
+///
+/// Every function, struct, interface, and constant is shown, with its doc comments if it has
+/// any, while variables are only shown where they are first given a value with doc comments. The
+/// types of variables declared without one are inferred by the checker.
+///
+/// # Arguments
+///
+/// * `tree` - The statements of the program.
+///
+/// # Returns
+///
+/// * `Vec<Entry>` - The definitions, in the order of the program.
+///
+/// # Examples
+/// ```
+/// use hydrogen::{doc::entries, hash::parser::Parser};
+///
+/// let tree = Parser::new("/// The answer\nanswer = 42\nanswer = 43\nf(x) { x }").parse_program();
+/// let entries = entries(&tree.unwrap());
+///
+/// assert_eq!(entries[0].signature, "answer: num");
+/// assert_eq!(entries[0].doc.as_deref(), Some("The answer"));
+/// assert_eq!(entries[1].signature, "f(x)");
+/// ```
+pub fn entries(tree: &Tree) -> Vec<Entry> {
+    let mut checker = Checker::new();
+    let mut shown = Vec::new();
+    let mut entries = Vec::new();
+    for statement in tree {
+        checker.check(statement);
+        let (name, signature, doc) = match statement {
+            Stmt::Item(Item::Function(definition)) => {
+                let parameters: Vec<String> = definition
+                    .parameters
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect();
+                let signature = format!("{}({})", definition.name, parameters.join(", "));
+                let signature = match definition.ret {
+                    Some(ret) => format!("{}: {}", signature, ret),
+                    None => signature,
+                };
+                (&definition.name, signature, &definition.doc)
+            }
+            Stmt::Item(Item::Struct(definition)) => {
+                let fields: Vec<String> = definition.fields.iter().map(|f| f.to_string()).collect();
+                let signature = format!("struct {}({})", definition.name, fields.join(", "));
+                (&definition.name, signature, &definition.doc)
+            }
+            Stmt::Item(Item::Interface(definition)) => {
+                let signatures: Vec<String> = definition
+                    .signatures
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect();
+                let signature = match signatures.is_empty() {
+                    true => format!("interface {} {{}}", definition.name),
+                    false => format!(
+                        "interface {} {{ {} }}",
+                        definition.name,
+                        signatures.join("; ")
+                    ),
+                };
+                (&definition.name, signature, &definition.doc)
+            }
+            Stmt::Definition(definition) if definition.constant || definition.doc.is_some() => {
+                let mut signature = match definition.constant {
+                    true => format!("const {}", definition.name),
+                    false => definition.name.clone(),
+                };
+                if let Some(ty) = definition.ty.or_else(|| checker.infer(&definition.value)) {
+                    signature = format!("{}: {}", signature, ty);
+                }
+                (&definition.name, signature, &definition.doc)
+            }
+            _ => continue,
+        };
+
+        if !shown.contains(name) {
+            shown.push(name.clone());
+            entries.push(Entry {
+                signature,
+                doc: doc.clone(),
+            });
+        }
+    }
+    entries
+}
+
+/// Reads the documentation of a file, or of every `.hy` file of the directory of a project.
+///
+/// # Arguments
+///
+/// * `path` - The file or the directory.
+///
+/// # Returns
+///
+/// * `Result<Vec<Page>, HydrogenError>` - The documentation of every file, sorted by name, or
+///   the error reading or parsing one of them.
+pub fn document(path: &Path) -> Result<Vec<Page>, HydrogenError> {
+    let files = if path.is_dir() {
+        let mut files = Vec::new();
+        for file in fs::read_dir(path)? {
+            let file = file?.path();
+            if file.is_file() && file.extension().is_some_and(|extension| extension == "hy") {
+                files.push(file);
+            }
+        }
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut pages = Vec::new();
+    for file in files {
+        let source = fs::read_to_string(&file)?;
+        match Parser::new(&source).parse_program() {
+            Ok(tree) => pages.push(Page {
+                entries: entries(&tree),
+                file,
+            }),
+            Err(errors) => {
+                return Err(HydrogenError::File(
+                    file,
+                    Box::new(HydrogenError::Parse(errors)),
+                ))
+            }
+        }
+    }
+    Ok(pages)
+}
+
+/// Renders the documentation of files.
+///
+/// # Arguments
+///
+/// * `pages` - The documentation of every file.
+/// * `format` - The format of the documentation.
+///
+/// # Returns
+///
+/// * `String` - The rendered documentation.
+pub fn render(pages: &[Page], format: Format) -> String {
+    match format {
+        Format::Markdown => markdown(pages),
+        Format::Html => html(pages),
+    }
+}
+
+/// Renders documentation as Markdown, where doc comments are already Markdown.
+fn markdown(pages: &[Page]) -> String {
+    let mut text = String::new();
+    for page in pages {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(&format!("# {}\n", page.file.display()));
+        for entry in &page.entries {
+            text.push_str(&format!("\n## `{}`\n", entry.signature));
+            if let Some(doc) = &entry.doc {
+                text.push_str(&format!("\n{}\n", doc));
+            }
+        }
+    }
+    text
+}
+
+/// Renders documentation as an HTML page, with paragraphs separated by blank lines in doc
+/// comments and `code` between backticks.
+fn html(pages: &[Page]) -> String {
+    let mut text = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Documentation</title>\n</head>\n<body>\n",
+    );
+    for page in pages {
+        text.push_str(&format!(
+            "<h1>{}</h1>\n",
+            escape(&page.file.display().to_string())
+        ));
+        for entry in &page.entries {
+            text.push_str(&format!(
+                "<h2><code>{}</code></h2>\n",
+                escape(&entry.signature)
+            ));
+            let doc = entry.doc.as_deref().unwrap_or_default();
+            for paragraph in doc.split("\n\n").filter(|p| !p.trim().is_empty()) {
+                text.push_str(&format!("<p>{}</p>\n", inline(paragraph.trim())));
+            }
+        }
+    }
+    text.push_str("</body>\n</html>\n");
+    text
+}
+
+/// Escapes the characters HTML gives a meaning to.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Escapes a paragraph for HTML, turning the text between backticks into code.
+fn inline(paragraph: &str) -> String {
+    let mut html = String::new();
+    for (i, part) in escape(paragraph).split('`').enumerate() {
+        match i % 2 {
+            1 => html.push_str(&format!("<code>{}</code>", part)),
+            _ => html.push_str(part),
+        }
+    }
+    html
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hash::format::format;
+
+    #[test]
+    fn test_render() {
+        let source = r#"
+            /// Adds two numbers.
+            ///
+            /// Both must be `num`s, so `a < b` is not.
+            add(a: num, b: num): num { a + b }
+
+            /// Starts at one
+            x = 1
+
+            const limit = "high"
+            name = "hidden"
+        "#;
+        let pages = vec![Page {
+            file: PathBuf::from("math.hy"),
+            entries: entries(&Parser::new(source).parse_program().unwrap()),
+        }];
+        assert_eq!(
+            pages[0].entries,
+            vec![
+                Entry {
+                    signature: "add(a: num, b: num): num".to_string(),
+                    doc: Some(
+                        "Adds two numbers.\n\nBoth must be `num`s, so `a < b` is not.".to_string()
+                    ),
+                },
+                Entry {
+                    signature: "x: num".to_string(),
+                    doc: Some("Starts at one".to_string()),
+                },
+                Entry {
+                    signature: "const limit: str".to_string(),
+                    doc: None,
+                },
+            ]
+        );
+
+        let markdown = render(&pages, Format::Markdown);
+        assert!(markdown.starts_with("# math.hy\n\n## `add(a: num, b: num): num`\n\nAdds"));
+        let html = render(&pages, Format::Html);
+        assert!(html
+            .contains("<p>Both must be <code>num</code>s, so <code>a &lt; b</code> is not.</p>"));
+        assert!(html.ends_with("</html>\n"));
+
+        assert!(matches!(
+            document(Path::new("test/broken")),
+            Err(HydrogenError::File(_, _))
+        ));
+    }
+    #[test]
+    fn test_format_round_trip() {
+        let source = "/// Doubles a number.\n/// Twice.\ndouble(n){\n// inner\nn*2 // trailing\n}\n\n/// Starts at one\nx=1 // one\n";
+        let formatted = format(source).unwrap();
+
+        let docs =
+            |source: &str| -> Vec<Entry> { entries(&Parser::new(source).parse_program().unwrap()) };
+        assert_eq!(docs(&formatted), docs(source));
+        assert_eq!(
+            docs(&formatted)[0].doc,
+            Some("Doubles a number.\nTwice.".to_string())
+        );
+        assert_eq!(docs(&formatted)[1].doc, Some("Starts at one".to_string()));
+    }
+}
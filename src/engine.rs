@@ -0,0 +1,1286 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use crate::hash::{
+    ast::{ASTError, Errors, Stmt, TypeExpr},
+    builtins::{describe, native, NativeResult},
+    checker::Checker,
+    compile::compile_at,
+    coverage::Coverage,
+    diagnostics::{Diagnostic, Diagnostics},
+    evaluator::{Arithmetic, Debugger, Evaluator, RuntimeError, StackTrace},
+    fold::Folder,
+    lint::Linter,
+    optimize::Optimizer,
+    parser::Parser,
+    profile::Profiler,
+    tokens::{Position, TAB_WIDTH},
+    value::Value,
+    vm::Vm,
+};
+
+/// Enum representing the ways the [`Engine`] can run programs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// Walk the abstract syntax tree of the program
+    #[default]
+    Tree,
+    /// Compile the program to bytecode and run it on a virtual machine
+    Vm,
+}
+
+/// Enum representing the errors returned by the [`Engine`]
+#[derive(Debug)]
+pub enum HydrogenError {
+    /// The source code could not be parsed: (errors)
+    Parse(Errors),
+    /// The checker found mistakes in the program, or the linter found warnings that are denied:
+    /// (diagnostics)
+    Check(Diagnostics),
+    /// The program failed while it was running: (error, calls active when it happened)
+    Runtime(RuntimeError, StackTrace),
+    /// The source file could not be read: (error)
+    Io(io::Error),
+    /// An error in one of the files of a project: (path, error)
+    File(PathBuf, Box<HydrogenError>),
+    /// The program called `exit`: (status)
+    Exit(i32),
+}
+
+impl fmt::Display for HydrogenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HydrogenError::Parse(errors) => {
+                let errors_str: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                write!(f, "{}", errors_str.join("\n"))
+            }
+            HydrogenError::Check(diagnostics) => write!(f, "{}", diagnostics),
+            HydrogenError::Runtime(error, trace) => {
+                write!(f, "ERROR: {}", error)?;
+                match self.shows_trace() {
+                    true => write!(f, "{}", trace),
+                    false => Ok(()),
+                }
+            }
+            HydrogenError::Io(error) => write!(f, "ERROR: {}", error),
+            HydrogenError::Exit(status) => write!(f, "exited with status {}", status),
+            // A stack trace already names the files of its calls
+            HydrogenError::File(_, error) if error.shows_trace() => write!(f, "{}", error),
+            HydrogenError::File(path, error) => {
+                let lines: Vec<String> = error
+                    .to_string()
+                    .lines()
+                    .map(|line| format!("{}: {}", path.display(), line))
+                    .collect();
+                write!(f, "{}", lines.join("\n"))
+            }
+        }
+    }
+}
+
+impl HydrogenError {
+    /// Returns the calls that were active when the program failed, if it failed while running.
+    ///
+    /// A call whose value the calling function returns replaces the caller in the trace.
+    pub fn stack_trace(&self) -> Option<&StackTrace> {
+        match self {
+            HydrogenError::Runtime(_, trace) => Some(trace),
+            HydrogenError::File(_, error) => error.stack_trace(),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the stack trace is written after the error.
+    fn shows_trace(&self) -> bool {
+        match self {
+            HydrogenError::Runtime(_, trace) => !trace.is_empty(),
+            HydrogenError::File(_, error) => error.shows_trace(),
+            _ => false,
+        }
+    }
+}
+
+impl std::error::Error for HydrogenError {}
+
+impl From<RuntimeError> for HydrogenError {
+    fn from(error: RuntimeError) -> Self {
+        match error {
+            RuntimeError::Exit(status) => HydrogenError::Exit(status),
+            error => HydrogenError::Runtime(error, StackTrace::default()),
+        }
+    }
+}
+
+impl From<io::Error> for HydrogenError {
+    fn from(error: io::Error) -> Self {
+        HydrogenError::Io(error)
+    }
+}
+
+/// The file a project starts running from, unless its manifest names another one.
+const ENTRY: &str = "main.hy";
+
+/// The file in the directory of a project that can name its entry point, with a line like
+/// `entry = "app.hy"`.
+pub(crate) const MANIFEST: &str = "hydrogen.toml";
+
+/// The builtins [`Engine::sandbox`] disables.
+const SANDBOXED: [&str; 8] = [
+    "read_file",
+    "write_file",
+    "append_file",
+    "read_line",
+    "input",
+    "env",
+    "run",
+    "exit",
+];
+
+/// How deeply calls can nest in a sandboxed engine, shallow enough for the stack of any thread.
+const SANDBOX_MAX_DEPTH: usize = 50;
+
+/// How long strings and arrays built by operators can be in a sandboxed engine.
+const SANDBOX_MAX_LENGTH: usize = 1 << 20;
+
+/// Engine is the entry point for embedding Hydrogen in a Rust application.
+///
+/// The variables and functions defined by every evaluated program are kept, so later calls can
+/// use what earlier ones defined.
+///
+/// # Examples
+/// ```
+/// use hydrogen::{Engine, Value};
+///
+/// let mut engine = Engine::new();
+/// engine.eval_str("double(x: num): num { x * 2 }").unwrap();
+///
+/// assert_eq!(engine.eval_str("double(21)").unwrap(), Value::Integer(42));
+/// ```
+#[derive(Debug)]
+pub struct Engine {
+    backend: Backend,
+    checker: Checker,
+    evaluator: Evaluator,
+    linter: Option<Linter>,
+    deny_warnings: bool,
+    optimize: bool,
+    streaming: bool,
+    tab_width: usize,
+    vm: Vm,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self {
+            backend: Backend::default(),
+            checker: Checker::default(),
+            evaluator: Evaluator::default(),
+            linter: None,
+            deny_warnings: false,
+            optimize: false,
+            streaming: false,
+            tab_width: TAB_WIDTH,
+            vm: Vm::default(),
+        }
+    }
+}
+
+impl Engine {
+    /// Creates a new Engine instance with an empty environment.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a Rust function that Hydrogen programs can call by name.
+    ///
+    /// The function receives the evaluated arguments of the call and returns either a [`Value`]
+    /// or a `Result<Value, RuntimeError>`. Registering a function with the name of a builtin
+    /// replaces the builtin.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name programs call the function with.
+    /// * `function` - The function to be called.
+    ///
+    /// # Examples
+    /// ```
+    /// use hydrogen::{Engine, Value};
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.register_fn("config", |args: &[Value]| -> Value {
+    ///     Value::from(format!("config for {}", args[0]))
+    /// });
+    ///
+    /// let value = engine.eval_str("config(\"app\")").unwrap();
+    /// assert_eq!(value, Value::from("config for app"));
+    /// ```
+    pub fn register_fn<F, R>(&mut self, name: &str, function: F)
+    where
+        F: Fn(&[Value]) -> R + 'static,
+        R: NativeResult,
+    {
+        let function = native(function);
+        self.evaluator.register(name, function.clone());
+        self.vm.register(name, function);
+    }
+
+    /// Creates a new Engine instance that runs untrusted programs, see [`Engine::sandbox`].
+    pub fn sandboxed() -> Self {
+        let mut engine = Self::new();
+        engine.sandbox();
+        engine
+    }
+
+    /// Takes away the access programs have to the system beyond the engine, and lowers their
+    /// limits.
+    ///
+    /// Calls of these builtins fail with [`RuntimeError::Disabled`]:
+    ///
+    /// * `read_file`, `write_file`, and `append_file`, which use files.
+    /// * `read_line` and `input`, which read the standard input.
+    /// * `env`, which reads environment variables.
+    /// * `run`, which starts processes.
+    /// * `exit`, which would end the program with a status for the application.
+    ///
+    /// Calls can nest 50 deep, which the stack of any thread has room for, see [`Engine::set_max_depth`], and operators can build strings
+    /// and arrays of up to 1048576 bytes or elements, see [`Engine::set_max_length`].
+    ///
+    /// Programs keep everything else: computing with values, defining functions, printing with
+    /// `print` and `eprint`, the array, map, and JSON builtins, `now`, `clock_ms`, `random`, and
+    /// the arguments from `args`. Functions registered with [`Engine::register_fn`] stay
+    /// available, including ones registered after this call under the names above. The limits
+    /// can be changed afterwards, but the disabled builtins cannot be restored.
+    pub fn sandbox(&mut self) {
+        for name in SANDBOXED {
+            self.register_fn(name, move |_: &[Value]| -> Result<Value, RuntimeError> {
+                Err(RuntimeError::Disabled(name.to_string()))
+            });
+        }
+        self.set_max_depth(SANDBOX_MAX_DEPTH);
+        self.set_max_length(SANDBOX_MAX_LENGTH);
+    }
+
+    /// Sets the arguments later programs get from the `args` builtin.
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The arguments, in order.
+    ///
+    /// # Examples
+    /// ```
+    /// use hydrogen::{Engine, Value};
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.set_args(vec!["input.txt".to_string()]);
+    ///
+    /// assert_eq!(engine.eval_str("args()").unwrap(), Value::from(vec!["input.txt"]));
+    /// ```
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.register_fn("args", move |_: &[Value]| Value::from(args.clone()));
+    }
+
+    /// Sets how later programs are run.
+    ///
+    /// Each backend keeps its own variables and functions, so a program does not see what was
+    /// defined by programs run with the other backend.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend` - The backend running programs.
+    pub fn set_backend(&mut self, backend: Backend) {
+        self.backend = backend;
+    }
+
+    /// Sets whether later programs are simplified by the [`Optimizer`] before they run.
+    ///
+    /// Programs are checked before they are simplified, so the checker reports the mistakes of
+    /// the code as it was written.
+    ///
+    /// # Arguments
+    ///
+    /// * `optimize` - Whether programs are simplified.
+    pub fn set_optimize(&mut self, optimize: bool) {
+        self.optimize = optimize;
+    }
+
+    /// Sets whether later programs are parsed, checked, and evaluated one top-level statement at
+    /// a time, instead of being parsed and checked whole before anything runs.
+    ///
+    /// Huge programs, like generated ones, start running at once, and the parse tree of only one
+    /// statement is kept at a time. The statements before a parse error or a mistake found by the
+    /// checker have already run when the error is returned, and programs are not linted.
+    ///
+    /// # Arguments
+    ///
+    /// * `streaming` - Whether programs are run one statement at a time.
+    ///
+    /// # Examples
+    /// ```
+    /// use hydrogen::{Engine, HydrogenError, Value};
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.set_streaming(true);
+    ///
+    /// let error = engine.eval_str("x = 1\nx = x + 1\n)").unwrap_err();
+    /// assert!(matches!(error, HydrogenError::Parse(_)));
+    /// assert_eq!(engine.eval_str("x").unwrap(), Value::Integer(2));
+    /// ```
+    pub fn set_streaming(&mut self, streaming: bool) {
+        self.streaming = streaming;
+    }
+
+    /// Sets how deeply calls of functions defined by programs can nest.
+    ///
+    /// See [`Evaluator::set_max_depth`].
+    ///
+    /// # Arguments
+    ///
+    /// * `max_depth` - The number of calls that can be active at the same time.
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.evaluator.set_max_depth(max_depth);
+        self.vm.set_max_depth(max_depth);
+    }
+
+    /// Sets how long the strings and arrays built by operators can be.
+    ///
+    /// See [`Evaluator::set_max_length`].
+    ///
+    /// # Arguments
+    ///
+    /// * `max_length` - The number of elements or bytes a value can hold.
+    pub fn set_max_length(&mut self, max_length: usize) {
+        self.evaluator.set_max_length(max_length);
+        self.vm.set_max_length(max_length);
+    }
+
+    /// Sets what arithmetic on whole numbers does when its result is too large for a whole
+    /// number, instead of failing with [`RuntimeError::Overflow`].
+    ///
+    /// See [`Evaluator::set_arithmetic`].
+    ///
+    /// # Arguments
+    ///
+    /// * `arithmetic` - What overflowing arithmetic does.
+    ///
+    /// # Examples
+    /// ```
+    /// use hydrogen::{hash::evaluator::Arithmetic, Engine, Value};
+    ///
+    /// let mut engine = Engine::new();
+    /// assert!(engine.eval_str("9223372036854775807 + 1").is_err());
+    ///
+    /// engine.set_arithmetic(Arithmetic::Wrapping);
+    /// assert_eq!(
+    ///     engine.eval_str("9223372036854775807 + 1").unwrap(),
+    ///     Value::Integer(i64::MIN)
+    /// );
+    /// ```
+    pub fn set_arithmetic(&mut self, arithmetic: Arithmetic) {
+        self.evaluator.set_arithmetic(arithmetic);
+        self.vm.set_arithmetic(arithmetic);
+    }
+
+    /// Sets how many steps each later program can run for.
+    ///
+    /// See [`Evaluator::set_max_steps`]. Every program evaluated gets all of the steps again.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_steps` - The number of steps allowed, or None for no limit.
+    ///
+    /// # Examples
+    /// ```
+    /// use hydrogen::{Engine, HydrogenError};
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.set_max_steps(Some(1000));
+    ///
+    /// let error = engine.eval_str("while true {}").unwrap_err();
+    /// assert!(matches!(error, HydrogenError::Runtime(_, _)));
+    /// ```
+    pub fn set_max_steps(&mut self, max_steps: Option<u64>) {
+        self.evaluator.set_max_steps(max_steps);
+        self.vm.set_max_steps(max_steps);
+    }
+
+    /// Sets how long each later program can run for.
+    ///
+    /// See [`Evaluator::set_timeout`]. Every program evaluated gets all of the time again.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The time allowed, or None for no limit.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.evaluator.set_timeout(timeout);
+        self.vm.set_timeout(timeout);
+    }
+
+    /// Sets whether later programs write what they run to the standard error.
+    ///
+    /// The tree backend writes every statement it evaluates followed by its value, see
+    /// [`Evaluator::set_trace`], and the virtual machine writes every instruction it executes.
+    ///
+    /// # Arguments
+    ///
+    /// * `trace` - Whether programs are traced.
+    pub fn set_trace(&mut self, trace: bool) {
+        let writer = || -> Option<Box<dyn io::Write>> {
+            trace.then(|| Box::new(io::stderr()) as Box<dyn io::Write>)
+        };
+        self.evaluator.set_trace(writer());
+        self.vm.set_trace(writer());
+    }
+
+    /// Sets whether later programs are linted before they run, with their warnings written to
+    /// the standard error.
+    ///
+    /// Warnings do not stop a program from running, unless they are denied with
+    /// [`Engine::set_deny_warnings`].
+    ///
+    /// # Arguments
+    ///
+    /// * `warnings` - Whether programs are linted.
+    pub fn set_warnings(&mut self, warnings: bool) {
+        self.linter = warnings.then(|| self.linter());
+    }
+
+    /// Sets the number of columns between tab stops, which the columns of the positions in the
+    /// errors and warnings of later programs are counted with.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The number of columns, at least 1.
+    ///
+    /// # Examples
+    /// ```
+    /// use hydrogen::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.set_tab_width(8);
+    /// engine.set_deny_warnings(true);
+    /// let error = engine.eval_str("\tf(x) { 1 }").unwrap_err();
+    /// assert!(error.to_string().contains("`x` is never used at 11:1"));
+    /// ```
+    pub fn set_tab_width(&mut self, width: usize) {
+        self.tab_width = width.max(1);
+        if let Some(linter) = &mut self.linter {
+            linter.set_tab_width(self.tab_width);
+        }
+    }
+
+    /// Returns a new linter with every rule on, counting columns with the tab stops of the engine.
+    fn linter(&self) -> Linter {
+        let mut linter = Linter::new();
+        linter.set_tab_width(self.tab_width);
+        linter
+    }
+
+    /// Returns a new parser of a program, counting columns with the tab stops of the engine.
+    fn parser<'a>(&self, source: &'a str) -> Parser<'a> {
+        let mut parser = Parser::new(source);
+        parser.set_tab_width(self.tab_width);
+        parser
+    }
+
+    /// Sets whether the warnings of later programs are errors, so programs with warnings fail
+    /// with [`HydrogenError::Check`] instead of running.
+    ///
+    /// Programs are linted when warnings are denied, even if [`Engine::set_warnings`] is off.
+    ///
+    /// # Arguments
+    ///
+    /// * `deny` - Whether warnings are errors.
+    ///
+    /// # Examples
+    /// ```
+    /// use hydrogen::{Engine, HydrogenError};
+    ///
+    /// let mut engine = Engine::new();
+    /// assert!(engine.eval_str("f(x) { 1 }").is_ok());
+    ///
+    /// engine.set_deny_warnings(true);
+    /// let error = engine.eval_str("g(y) { 1 }").unwrap_err();
+    /// assert!(matches!(error, HydrogenError::Check(_)));
+    /// ```
+    pub fn set_deny_warnings(&mut self, deny: bool) {
+        self.deny_warnings = deny;
+    }
+
+    /// Sets the debugger pausing later programs before each statement.
+    ///
+    /// Only the tree backend pauses for the debugger, see [`Evaluator::set_debugger`].
+    ///
+    /// # Arguments
+    ///
+    /// * `debugger` - The debugger.
+    pub fn set_debugger(&mut self, debugger: Box<dyn Debugger>) {
+        self.evaluator.set_debugger(Some(debugger));
+    }
+
+    /// Sets whether later programs count and time the calls of the functions they define.
+    ///
+    /// Only the tree backend profiles calls, see [`Evaluator::set_profiling`].
+    ///
+    /// # Arguments
+    ///
+    /// * `profiling` - Whether calls are profiled.
+    pub fn set_profiling(&mut self, profiling: bool) {
+        self.evaluator.set_profiling(profiling);
+    }
+
+    /// Returns the calls recorded since profiling was turned on, if it is on.
+    ///
+    /// # Examples
+    /// ```
+    /// use hydrogen::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.set_profiling(true);
+    /// engine.eval_str("f(n) { n }\nf(1)\nf(2)").unwrap();
+    ///
+    /// let calls = engine.profile().unwrap().calls();
+    /// assert_eq!((calls[0].0.as_str(), calls[0].1.count), ("f", 2));
+    /// ```
+    pub fn profile(&self) -> Option<&Profiler> {
+        self.evaluator.profile()
+    }
+
+    /// Sets whether later programs count the lines whose statements run.
+    ///
+    /// Only the tree backend counts coverage, see [`Evaluator::set_coverage`]. The rows of every
+    /// program evaluated are counted together, so the report is only meaningful for one file.
+    ///
+    /// # Arguments
+    ///
+    /// * `coverage` - Whether coverage is counted.
+    pub fn set_coverage(&mut self, coverage: bool) {
+        self.evaluator.set_coverage(coverage);
+    }
+
+    /// Returns the coverage counted since it was turned on, if it is on.
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.evaluator.coverage()
+    }
+
+    /// Sets the flag that stops the running program when it is set, like from a Ctrl-C handler.
+    ///
+    /// See [`Evaluator::set_interrupt`]. The engine stays usable after a program is interrupted.
+    ///
+    /// # Arguments
+    ///
+    /// * `interrupt` - The flag, or None to stop watching one.
+    pub fn set_interrupt(&mut self, interrupt: Option<Arc<AtomicBool>>) {
+        self.evaluator.set_interrupt(interrupt.clone());
+        self.vm.set_interrupt(interrupt);
+    }
+
+    /// Forgets the variables and functions every program evaluated so far defined, as if the
+    /// engine was new, keeping its settings and the functions registered with
+    /// [`Engine::register_fn`].
+    ///
+    /// # Examples
+    /// ```
+    /// use hydrogen::{Engine, Value};
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.register_fn("answer", |_: &[Value]| Value::Integer(42));
+    /// engine.eval_str("x = 1").unwrap();
+    ///
+    /// engine.reset();
+    /// assert!(engine.eval_str("x").is_err());
+    /// assert_eq!(engine.eval_str("x = answer(); x").unwrap(), Value::Integer(42));
+    /// ```
+    pub fn reset(&mut self) {
+        self.checker = Checker::new();
+        self.evaluator.reset();
+        self.vm.reset();
+    }
+
+    /// Returns how long the last program ran: the expressions the tree walker evaluated, or the
+    /// instructions the virtual machine ran.
+    ///
+    /// # Examples
+    /// ```
+    /// use hydrogen::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.eval_str("1 + 2").unwrap();
+    /// let short = engine.steps();
+    /// engine.eval_str("x = 0; while x < 10 { x = x + 1 }").unwrap();
+    /// assert!(engine.steps() > short);
+    /// ```
+    pub fn steps(&self) -> u64 {
+        match self.backend {
+            Backend::Tree => self.evaluator.steps(),
+            Backend::Vm => self.vm.steps(),
+        }
+    }
+
+    /// Returns the type of the value an expression computes, as far as the checker can tell
+    /// from the names defined so far, without evaluating it.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source code of a single expression.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<TypeExpr>, HydrogenError>` - The type, None if it is not known, or the
+    ///   error if the source is not a single expression.
+    ///
+    /// # Examples
+    /// ```
+    /// use hydrogen::{hash::ast::TypeExpr, Engine};
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.eval_str("var1 = 1").unwrap();
+    ///
+    /// assert_eq!(engine.infer("var1 + 2").unwrap(), Some(TypeExpr::Number));
+    /// assert_eq!(engine.infer("print(var1)").unwrap(), None);
+    /// assert!(engine.infer("var2 = 2").is_err());
+    /// ```
+    pub fn infer(&self, source: &str) -> Result<Option<TypeExpr>, HydrogenError> {
+        let tree = self
+            .parser(source)
+            .parse_program()
+            .map_err(HydrogenError::Parse)?;
+        let mut statements = tree.into_iter();
+        match (statements.next(), statements.next()) {
+            (Some(Stmt::Expr(expression)), None) => Ok(self.checker.infer(&expression)),
+            (None, _) => Ok(None),
+            (Some(Stmt::Expr(_)), Some(statement)) | (Some(statement), _) => {
+                let error = ASTError::UnexpectedStatement(Box::new(statement));
+                Err(HydrogenError::Parse(vec![Box::new(error)]))
+            }
+        }
+    }
+
+    /// Returns the signature and the description of a function the programs defined or of a
+    /// builtin, like `help` does.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the function is called with.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<String>` - The signature, followed by the description on an indented line if
+    ///   there is one, or None if no function has that name.
+    ///
+    /// # Examples
+    /// ```
+    /// use hydrogen::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.eval_str("add(a: num, b: num): num { a + b }").unwrap();
+    ///
+    /// assert_eq!(engine.doc("add").unwrap(), "add(a: num, b: num): num");
+    /// assert!(engine.doc("print").unwrap().starts_with("print(values...)\n"));
+    /// assert_eq!(engine.doc("nothing"), None);
+    /// ```
+    pub fn doc(&self, name: &str) -> Option<String> {
+        match self.backend {
+            Backend::Tree => describe(&self.evaluator, name),
+            Backend::Vm => describe(&self.vm, name),
+        }
+    }
+
+    /// Parses, checks, and evaluates a program.
+    ///
+    /// Nothing is evaluated if the program does not parse or the checker finds mistakes in it,
+    /// unless the engine is streaming, see [`Engine::set_streaming`]. Warnings of the linter, when
+    /// it is on, are written before the program runs.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source code of the program.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Value, HydrogenError>` - The value of the last statement, or the error that
+    ///   stopped the program.
+    pub fn eval_str(&mut self, source: &str) -> Result<Value, HydrogenError> {
+        if self.streaming {
+            return self.eval_streaming(source);
+        }
+
+        let parsed = self.parser(source).parse_partial();
+        if !parsed.is_complete() {
+            return Err(HydrogenError::Parse(parsed.errors));
+        }
+
+        let mut diagnostics = Diagnostics::default();
+        for (position, statement) in parsed.positions.iter().zip(&parsed.tree) {
+            for error in self.checker.check(statement) {
+                diagnostics.push(Diagnostic::check(position.clone(), error));
+            }
+        }
+        let lints = match (&self.linter, self.deny_warnings) {
+            (Some(linter), _) => linter.lint(source),
+            (None, true) => self.linter().lint(source),
+            (None, false) => Vec::new(),
+        };
+        diagnostics.extend(lints);
+        if self.deny_warnings {
+            diagnostics.deny_warnings();
+        }
+        if diagnostics.has_errors() {
+            return Err(HydrogenError::Check(diagnostics));
+        }
+        for warning in &diagnostics {
+            eprintln!("{}", warning);
+        }
+
+        let statements = if self.optimize {
+            Optimizer.fold_tree(parsed.tree)
+        } else {
+            parsed.tree
+        };
+
+        self.evaluator.restart_limits();
+        self.run(&parsed.positions, &statements)
+    }
+
+    /// Parses, checks, and evaluates a program one top-level statement at a time, stopping at the
+    /// first statement that does not parse, has mistakes, or fails.
+    fn eval_streaming(&mut self, source: &str) -> Result<Value, HydrogenError> {
+        let mut parser = self.parser(source);
+        let mut result = Value::None;
+
+        self.evaluator.restart_limits();
+        while let Some(parsed) = parser.parse_located() {
+            let statement = parsed
+                .statement
+                .map_err(|error| HydrogenError::Parse(vec![error]))?;
+
+            let diagnostics: Diagnostics = self
+                .checker
+                .check(&statement)
+                .into_iter()
+                .map(|error| Diagnostic::check(parsed.start.clone(), error))
+                .collect();
+            if diagnostics.has_errors() {
+                return Err(HydrogenError::Check(diagnostics));
+            }
+
+            let statement = if self.optimize {
+                Optimizer.fold_stmt(statement)
+            } else {
+                statement
+            };
+            result = self.run(&[parsed.start], &[statement])?;
+        }
+        Ok(result)
+    }
+
+    /// Runs checked statements with the backend of the engine.
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - The positions where the statements start.
+    /// * `statements` - The statements.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Value, HydrogenError>` - The value of the last statement, or the error that
+    ///   stopped them.
+    fn run(&mut self, positions: &[Position], statements: &[Stmt]) -> Result<Value, HydrogenError> {
+        match self.backend {
+            Backend::Tree => {
+                for (position, statement) in positions.iter().zip(statements) {
+                    self.evaluator.cover(statement, position.row);
+                }
+                let mut result = Value::None;
+                for (position, statement) in positions.iter().zip(statements) {
+                    result = match self.evaluator.evaluate_at(statement, position.row) {
+                        Ok(value) => value,
+                        Err(RuntimeError::Exit(status)) => return Err(HydrogenError::Exit(status)),
+                        Err(error) => {
+                            let trace = self.evaluator.take_stack_trace();
+                            return Err(HydrogenError::Runtime(error, trace));
+                        }
+                    };
+                }
+                Ok(result)
+            }
+            Backend::Vm => {
+                let rows: Vec<usize> = positions.iter().map(|position| position.row).collect();
+                match self.vm.run(compile_at(statements, &rows)?) {
+                    Ok(value) => Ok(value),
+                    Err(RuntimeError::Exit(status)) => Err(HydrogenError::Exit(status)),
+                    Err(error) => Err(HydrogenError::Runtime(error, self.vm.take_stack_trace())),
+                }
+            }
+        }
+    }
+
+    /// Reads, parses, and evaluates a program from a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the source file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Value, HydrogenError>` - The value of the last statement, or the error that
+    ///   stopped the program.
+    pub fn eval_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Value, HydrogenError> {
+        let source = fs::read_to_string(&path)?;
+        // Stack traces name the file the functions it defines are written in
+        self.evaluator.set_file(Some(path.as_ref()));
+        self.vm.set_file(Some(path.as_ref()));
+        let result = self.eval_str(&source);
+        self.evaluator.set_file(None);
+        self.vm.set_file(None);
+        result
+    }
+
+    /// Runs a project made of the Hydrogen files of a directory.
+    ///
+    /// The other `.hy` files next to the entry point of the project are its modules. They are
+    /// evaluated first, in the order of their names, so the entry point can use the functions and
+    /// variables they define. Errors name the file they come from.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The directory of the project, holding a `main.hy`, or a `hydrogen.toml` whose
+    ///   `entry = "app.hy"` line names another entry point.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Value, HydrogenError>` - The value of the last statement of the entry point, or
+    ///   the error that stopped the program.
+    pub fn eval_project<P: AsRef<Path>>(&mut self, path: P) -> Result<Value, HydrogenError> {
+        let entry = entry_point(path.as_ref())?;
+        let directory = entry.parent().unwrap_or(Path::new("."));
+
+        let mut modules = Vec::new();
+        for file in fs::read_dir(directory)? {
+            let file = file?.path();
+            if file.extension().is_some_and(|extension| extension == "hy") && file != entry {
+                modules.push(file);
+            }
+        }
+        modules.sort();
+        modules.push(entry);
+
+        let mut result = Value::None;
+        for module in modules {
+            result = self.eval_file(&module).map_err(|error| match error {
+                HydrogenError::Exit(_) => error,
+                error => HydrogenError::File(module, Box::new(error)),
+            })?;
+        }
+        Ok(result)
+    }
+}
+
+/// Finds the file a project starts running from.
+///
+/// # Arguments
+///
+/// * `directory` - The directory of the project.
+///
+/// # Returns
+///
+/// * `io::Result<PathBuf>` - The entry named by the manifest, or else the `main.hy` of the
+///   directory, or an error if the file does not exist.
+fn entry_point(directory: &Path) -> io::Result<PathBuf> {
+    let manifest = directory.join(MANIFEST);
+    let mut entry = directory.join(ENTRY);
+    if manifest.is_file() {
+        for line in fs::read_to_string(manifest)?.lines() {
+            if let Some(("entry", value)) = line.split_once('=').map(|(k, v)| (k.trim(), v)) {
+                entry = directory.join(value.trim().trim_matches('"'));
+            }
+        }
+    }
+
+    if !entry.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("the project has no entry point {}", entry.display()),
+        ));
+    }
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_engine() {
+        let mut engine = Engine::new();
+
+        assert_eq!(engine.eval_str("x = 40").unwrap(), Value::None);
+        assert_eq!(engine.eval_str("x + 2").unwrap(), Value::Integer(42));
+        assert!(matches!(
+            engine.eval_str("y + 1"),
+            Err(HydrogenError::Runtime(
+                RuntimeError::UndefinedVariable(_),
+                _
+            ))
+        ));
+        assert!(matches!(
+            engine.eval_str("x = ~"),
+            Err(HydrogenError::Parse(_))
+        ));
+        assert!(matches!(
+            engine.eval_file("test/missing.hy"),
+            Err(HydrogenError::Io(_))
+        ));
+        assert_eq!(engine.eval_file("test/hello.hy").unwrap(), Value::None);
+
+        engine.eval_str("const pi = 3").unwrap();
+        assert!(matches!(
+            engine.eval_str("pi = 4"),
+            Err(HydrogenError::Check(_))
+        ));
+        assert_eq!(engine.eval_str("pi").unwrap(), Value::Integer(3));
+
+        // Warnings let the program run, unless they are denied
+        let unused = "f(n) { if false { n } }\nf(1)";
+        engine.set_warnings(true);
+        assert_eq!(engine.eval_str(unused).unwrap(), Value::None);
+        engine.set_deny_warnings(true);
+        let Err(HydrogenError::Check(diagnostics)) = engine.eval_str(unused) else {
+            panic!("denied warnings should stop the program");
+        };
+        assert_eq!(
+            diagnostics.to_string(),
+            "ERROR[H0206 constant-condition]: the condition of this `if` is always false at 1:1"
+        );
+        engine.set_deny_warnings(false);
+        engine.set_warnings(false);
+        assert_eq!(engine.eval_str(unused).unwrap(), Value::None);
+    }
+
+    #[test]
+    fn test_register_fn() {
+        let mut engine = Engine::new();
+        engine.register_fn("sum", |args: &[Value]| {
+            let mut total = 0;
+            for arg in args {
+                total += i64::try_from(arg.clone())?;
+            }
+            Ok(Value::from(total))
+        });
+
+        assert_eq!(engine.eval_str("sum(1, 2, 3)").unwrap(), Value::Integer(6));
+        assert!(matches!(
+            engine.eval_str("sum(1, true)"),
+            Err(HydrogenError::Runtime(RuntimeError::TypeMismatch(_, _), _))
+        ));
+
+        engine.eval_str("sum(a: num): num { a }").unwrap();
+        assert_eq!(engine.eval_str("sum(5)").unwrap(), Value::Integer(5));
+        assert!(matches!(
+            engine.eval_str("print(1)\nsum(5, 6)"),
+            Err(HydrogenError::Check(diagnostics)) if diagnostics.iter().next().unwrap().position.row == 2
+        ));
+
+        // Builtins can be replaced, for example to make the clock deterministic
+        engine.register_fn("now", |_: &[Value]| Value::Float(0.5));
+        assert_eq!(engine.eval_str("now()").unwrap(), Value::Float(0.5));
+    }
+
+    #[test]
+    fn test_exit() {
+        // The test executable itself is the one program sure to exist, and `--list` keeps it
+        // from running the tests again
+        let program = std::env::current_exe().unwrap();
+        let run = format!("run({:?}, [\"--list\"])", program.display().to_string());
+
+        let mut engine = Engine::new();
+        assert!(matches!(
+            engine.eval_str("print(1)\nexit(4)\nprint(2)"),
+            Err(HydrogenError::Exit(4))
+        ));
+        assert!(matches!(engine.eval_str(&run), Ok(Value::Map(_))));
+
+        engine.sandbox();
+        assert!(matches!(
+            engine.eval_str(&run),
+            Err(HydrogenError::Runtime(RuntimeError::Disabled(name), _)) if name == "run"
+        ));
+    }
+
+    #[test]
+    fn test_sandbox() {
+        for backend in [Backend::Tree, Backend::Vm] {
+            let mut engine = Engine::sandboxed();
+            engine.set_backend(backend);
+
+            for name in SANDBOXED {
+                assert!(matches!(
+                    engine.eval_str(&format!("try {{ {}() }} catch e {{ e }}", name)),
+                    Ok(Value::String(message)) if *message == format!("`{}` is disabled", name)
+                ));
+            }
+            assert_eq!(
+                engine
+                    .eval_str("len(json_encode(map([1, 2], fn(x) { x * 2 })))")
+                    .unwrap(),
+                Value::Integer(5)
+            );
+
+            // Ranges hold no elements, so only what is built from them counts
+            assert_eq!(
+                engine.eval_str("len(0..2000000)").unwrap(),
+                Value::Integer(2000000)
+            );
+            assert!(matches!(
+                engine.eval_str("s = \"ab\"\nwhile true { s = s + s }"),
+                Err(HydrogenError::Runtime(
+                    RuntimeError::TooLarge(SANDBOX_MAX_LENGTH),
+                    _
+                ))
+            ));
+            assert!(matches!(
+                engine.eval_str("f(n) { 1 + f(n) }\nf(0)"),
+                Err(HydrogenError::Runtime(
+                    RuntimeError::RecursionLimit(SANDBOX_MAX_DEPTH),
+                    _
+                ))
+            ));
+
+            // A value holding itself is written, and walks that cannot end fail
+            assert_eq!(
+                engine.eval_str("x = [1]\npush(x, x)\nto_str(x)").unwrap(),
+                Value::from("[1, [...]]")
+            );
+            for walk in ["json_encode(x)", "clone(x)"] {
+                assert!(matches!(
+                    engine.eval_str(walk),
+                    Err(HydrogenError::Runtime(RuntimeError::CyclicValue(_), _))
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        for backend in [Backend::Tree, Backend::Vm] {
+            let mut engine = Engine::new();
+            engine.set_backend(backend);
+            engine.set_max_steps(Some(10000));
+
+            assert!(matches!(
+                engine.eval_str("while true {}"),
+                Err(HydrogenError::Runtime(RuntimeError::StepLimit(10000), _))
+            ));
+            assert!(matches!(
+                engine.eval_str("try { while true {} } catch e { 0 }"),
+                Err(HydrogenError::Runtime(RuntimeError::StepLimit(10000), _))
+            ));
+            assert_eq!(engine.eval_str("1 + 2").unwrap(), Value::Integer(3));
+
+            engine.set_max_steps(None);
+            engine.set_timeout(Some(Duration::from_millis(20)));
+            assert!(matches!(
+                engine.eval_str("while true {}"),
+                Err(HydrogenError::Runtime(RuntimeError::Timeout(_), _))
+            ));
+            assert_eq!(engine.eval_str("1 + 2").unwrap(), Value::Integer(3));
+
+            let interrupt = Arc::new(AtomicBool::new(false));
+            engine.set_timeout(None);
+            engine.set_interrupt(Some(Arc::clone(&interrupt)));
+            engine.register_fn("stop", move |_: &[Value]| {
+                interrupt.store(true, std::sync::atomic::Ordering::Relaxed);
+                Value::None
+            });
+            assert!(matches!(
+                engine.eval_str("x = 1\ntry { stop()\nwhile true {} } catch e { 0 }"),
+                Err(HydrogenError::Runtime(RuntimeError::Interrupted, _))
+            ));
+            assert_eq!(engine.eval_str("x + 2").unwrap(), Value::Integer(3));
+        }
+    }
+
+    #[test]
+    fn test_project() {
+        let mut engine = Engine::new();
+        assert_eq!(
+            engine.eval_project("test/project").unwrap(),
+            Value::from("Hello, project!")
+        );
+
+        let mut engine = Engine::new();
+        assert_eq!(
+            engine.eval_project("test/manifest").unwrap(),
+            Value::Integer(42)
+        );
+
+        let mut engine = Engine::new();
+        let error = engine.eval_project("test/broken").unwrap_err();
+        assert!(matches!(
+            &error,
+            HydrogenError::File(path, error)
+                if path.ends_with("broken/parse.hy") && matches!(**error, HydrogenError::Parse(_))
+        ));
+        assert!(error
+            .to_string()
+            .starts_with("test/broken/parse.hy: ERROR[H0002]:"));
+
+        assert!(matches!(
+            Engine::new().eval_project("test"),
+            Err(HydrogenError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_stack_trace() {
+        // Both backends report the same calls
+        for backend in [Backend::Tree, Backend::Vm] {
+            let mut engine = Engine::new();
+            engine.set_backend(backend);
+            let program = "inner(x) {\n    x + missing\n}\nouter(x) {\n    y = inner(x)\n    y\n}\nz = try { outer(1) } catch e { 0 }\nouter(2)";
+            let error = engine.eval_str(program).unwrap_err();
+            let rows: Vec<_> = error
+                .stack_trace()
+                .unwrap()
+                .frames()
+                .iter()
+                .map(|frame| frame.row)
+                .collect();
+            assert_eq!(rows, vec![Some(2), Some(5), Some(9)]);
+            assert_eq!(
+                error.to_string(),
+                "ERROR: undefined variable `missing`\nstack trace, innermost call first:\n    in `inner` at line 2\n    in `outer` at line 5\n    at line 9"
+            );
+
+            let program =
+                "down(n) {\n    x = if n == 0 { 1 + true } else { down(n - 1) }\n    x\n}\ndown(3)";
+            assert!(engine
+                .eval_str(program)
+                .unwrap_err()
+                .to_string()
+                .ends_with("\n    in `down` at line 2 (4 calls)\n    at line 5"));
+
+            engine.set_max_depth(10);
+            let program =
+                "deep(n) {\n    1 + deep(n + 1)\n}\ncheck() {\n    assert(false, \"never\")\n}\n";
+            engine.eval_str(program).unwrap();
+            assert_eq!(
+                engine.eval_str("deep(0)").unwrap_err().to_string(),
+                "ERROR: maximum recursion depth of 10 exceeded\nstack trace, innermost call first:\n    in `deep` at line 2 (10 calls)\n    at line 1"
+            );
+            assert_eq!(
+                engine.eval_str("x = 1\ncheck()").unwrap_err().to_string(),
+                "ERROR: assertion failed: never\nstack trace, innermost call first:\n    in `check` at line 5\n    at line 2"
+            );
+
+            let error = engine.eval_str("1\nnope").unwrap_err();
+            let frames = error.stack_trace().unwrap().frames();
+            assert_eq!(frames.len(), 1);
+            assert_eq!((&frames[0].function, frames[0].row), (&None, Some(2)));
+            assert!(engine
+                .eval_str("x = ~")
+                .unwrap_err()
+                .stack_trace()
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn test_optimize() {
+        let mut engine = Engine::new();
+        engine.set_optimize(true);
+
+        assert_eq!(
+            engine
+                .eval_str("if 1 < 2 { 2 * 3 + 4 } else { 1 / 0 }")
+                .unwrap(),
+            Value::Integer(10)
+        );
+        assert!(matches!(
+            engine.eval_str("x = 1 / 0"),
+            Err(HydrogenError::Runtime(RuntimeError::DivisionByZero, _))
+        ));
+        engine.eval_str("const k = 1").unwrap();
+        assert!(matches!(
+            engine.eval_str("if false { k = 2 }"),
+            Err(HydrogenError::Check(_))
+        ));
+    }
+
+    #[test]
+    fn test_backend() {
+        let mut engine = Engine::new();
+        engine.set_backend(Backend::Vm);
+        engine.register_fn("twice", |args: &[Value]| {
+            Ok(Value::from(i64::try_from(args[0].clone())? * 2))
+        });
+
+        engine
+            .eval_str(
+                "x = 20
+add(a, b) { a + b }",
+            )
+            .unwrap();
+        assert_eq!(
+            engine.eval_str("add(twice(x), 2)").unwrap(),
+            Value::Integer(42)
+        );
+        assert!(matches!(
+            engine.eval_str("add(x, \"a\")"),
+            Err(HydrogenError::Runtime(
+                RuntimeError::InvalidOperands(_, _),
+                _
+            ))
+        ));
+        assert_eq!(engine.eval_str("x").unwrap(), Value::Integer(20));
+
+        engine.set_backend(Backend::Tree);
+        assert!(matches!(
+            engine.eval_str("x"),
+            Err(HydrogenError::Runtime(
+                RuntimeError::UndefinedVariable(_),
+                _
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_streaming() {
+        let source = "x = 1\nx = x + 1\nconst k = x\nk = 3\nx = 10";
+
+        // The statements before a mistake run when the program is streamed
+        let mut engine = Engine::new();
+        assert!(matches!(
+            engine.eval_str(source),
+            Err(HydrogenError::Check(_))
+        ));
+        assert!(engine.eval_str("x").is_err());
+        let mut engine = Engine::new();
+        engine.set_streaming(true);
+        let error = engine.eval_str(source).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "ERROR[H0103]: cannot assign twice to constant `k` at 1:4"
+        );
+        assert_eq!(engine.eval_str("x").unwrap(), Value::Integer(2));
+
+        // Functions defined by earlier statements are checked and called by later ones
+        for backend in [Backend::Tree, Backend::Vm] {
+            let mut engine = Engine::new();
+            engine.set_backend(backend);
+            engine.set_streaming(true);
+            engine.set_optimize(true);
+            let value = engine
+                .eval_str("double(n: num): num { n * 2 }\ndouble(2 + 1)")
+                .unwrap();
+            assert_eq!(value, Value::Integer(6));
+            assert!(matches!(
+                engine.eval_str("double(\"a\")"),
+                Err(HydrogenError::Check(_))
+            ));
+        }
+    }
+}
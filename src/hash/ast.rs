@@ -1,15 +1,9 @@
 use std::fmt;
 
-use super::tokens::Token;
+use super::{print, tokens::Token};
 
-/// Alias for Nodes type
-pub type Tree = Nodes;
-
-/// Alias for Boxed ASTNode
-pub type Node = Box<ASTNode>;
-
-/// Vector of AST nodes
-pub type Nodes = Vec<Node>;
+/// The statements of a program
+pub type Tree = Vec<Stmt>;
 
 /// Alias for boxed ASTError
 pub type Error = Box<ASTError>;
@@ -17,141 +11,606 @@ pub type Error = Box<ASTError>;
 /// Vector of AST errors
 pub type Errors = Vec<Error>;
 
-/// Enum representing different types of AST nodes
+/// Enum representing the types a value can be declared with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeExpr {
+    /// Number type: `num`
+    Number,
+    /// String type: `str`
+    String,
+    /// Boolean type: `bool`
+    Boolean,
+    /// Function type: `fn`
+    Function,
+}
+
+impl TypeExpr {
+    /// Returns the name the type is written with.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TypeExpr::Number => "num",
+            TypeExpr::String => "str",
+            TypeExpr::Boolean => "bool",
+            TypeExpr::Function => "fn",
+        }
+    }
+}
+
+/// Enum representing the values written directly in source code
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub enum ASTNode {
-    StringType,
-    StringLiteral(String),
-    BooleanType,
-    BooleanLiteral(bool),
-    NumberType,
-    NumberLiteral(String),
+pub enum Literal {
+    /// Number literal: (digits)
+    Number(String),
+    /// String literal: (content)
+    String(String),
+    /// Boolean literal: (value)
+    Boolean(bool),
+}
+
+/// Enum representing expressions, which compute a value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    /// Literal: (value)
+    Literal(Literal),
+    /// Identifier: (name)
     Identifier(String),
-    Operator(String),
+    /// Parenthesized expressions: (elements)
+    Group(Vec<Expr>),
+    /// Array: (elements)
+    Array(Vec<Expr>),
+    /// Unary expression: (expression)
+    Unary(UnaryExpression),
+    /// Binary expression: (expression)
+    Binary(BinaryExpression),
+    /// Function call: (call)
+    Call(FunctionCall),
+    /// Block: (block)
+    Block(Block),
+    /// If: (expression)
+    If(If),
+    /// While: (expression)
+    While(While),
+    /// For: (expression)
+    For(For),
+    /// Range: (expression)
+    Range(Range),
+    /// Cast: (expression)
+    Cast(Cast),
+    /// Try: (expression)
+    Try(Try),
+    /// Anonymous function: (lambda)
+    Lambda(Lambda),
+    /// Element of an array or string, or value of a key of a map: (index)
+    Index(Index),
+    /// Value of a key of a map written as a name: (member)
+    Member(Member),
+    /// Call of a function a struct implements: (call)
+    MethodCall(MethodCall),
+}
+
+/// An operator applied to a single operand, like `-x`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnaryExpression {
+    /// The symbol of the operator.
+    pub op: String,
+    /// The operand.
+    pub expr: Box<Expr>,
+}
+
+/// An operator applied to two operands, like `a + b`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinaryExpression {
+    /// The operand on the left of the operator.
+    pub left: Box<Expr>,
+    /// The symbol of the operator.
+    pub op: String,
+    /// The operand on the right of the operator.
+    pub right: Box<Expr>,
+}
+
+/// A call of a function by name, like `add(1, 2)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionCall {
+    /// The name of the function called.
+    pub name: String,
+    /// The arguments passed to the function, in order.
+    pub arguments: Vec<Expr>,
+}
+
+/// Statements between braces, evaluated in a scope of their own
+#[derive(Debug, Clone, Default)]
+pub struct Block {
+    /// The statements of the block, in order.
+    pub statements: Vec<Stmt>,
+    /// The row each statement starts on in the source code, empty if the block was not parsed.
+    pub rows: Vec<usize>,
+    /// The row of the closing brace in the source code, 0 if the block was not parsed.
+    pub end: usize,
+}
+
+impl Block {
+    /// Creates a new block holding the given statements, which were not parsed from source code.
+    pub fn new(statements: Vec<Stmt>) -> Self {
+        Self {
+            statements,
+            rows: Vec::new(),
+            end: 0,
+        }
+    }
+}
+
+/// Blocks are the same if they hold the same statements, wherever they were written.
+impl PartialEq for Block {
+    fn eq(&self, other: &Self) -> bool {
+        self.statements == other.statements
+    }
+}
+
+impl Eq for Block {}
+
+/// A conditional, like `if a { b } else { c }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct If {
+    /// The condition choosing the branch.
+    pub condition: Box<Expr>,
+    /// The block evaluated when the condition is true.
+    pub then: Block,
+    /// The block or `if` evaluated when the condition is false, if there is an `else`.
+    pub otherwise: Option<Box<Expr>>,
+}
 
-    // Placeholder for potential future node types
-    // NumberLiteral(Box<Node>),
-    // IntegerLiteral(i32),
-    // FloatLiteral(f32),
-    /// Variable definition: (identifier, type, expression)
-    VariableDefinition(Node, Node, Node),
-    VariableDeclaration(Node, Node),
+/// A loop, like `while a { b }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct While {
+    /// The condition checked before every iteration.
+    pub condition: Box<Expr>,
+    /// The block evaluated while the condition is true.
+    pub body: Block,
+}
 
-    /// Type: (type)
-    Type(Option<Node>),
+/// A loop over the elements of a collection, like `for x in [1, 2] { b }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct For {
+    /// The name each element is bound to.
+    pub variable: String,
+    /// The range, array, string, or number iterated over.
+    pub iterable: Box<Expr>,
+    /// The block evaluated for every element.
+    pub body: Block,
+}
 
-    Array(Nodes),
+/// The whole numbers from a start up to an end it excludes, like `0..10`, or up to an end it
+/// includes, like `1..=10`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Range {
+    /// The first number of the range.
+    pub start: Box<Expr>,
+    /// The number right after the last one of the range, or the last one if it is inclusive.
+    pub end: Box<Expr>,
+    /// Whether the end is part of the range, which is written with `..=`.
+    pub inclusive: bool,
+}
 
-    /// Unary expression: (operator, expression)
-    UnaryExpression(Node, Node),
+/// A function without a name, like `fn(x: num): num { x * 2 }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lambda {
+    /// The parameters of the function, in order.
+    pub parameters: Vec<Parameter>,
+    /// The type the function returns, if it is declared.
+    pub ret: Option<TypeExpr>,
+    /// The block evaluated when the function is called.
+    pub body: Block,
+}
 
-    /// Binary expression: (expression, operator, expression)
-    BinaryExpression(Node, Node, Node),
+/// A block whose runtime errors are caught, like `try { a } catch e { b }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Try {
+    /// The block whose errors are caught.
+    pub body: Block,
+    /// The name the message of a caught error is bound to.
+    pub variable: String,
+    /// The block evaluated when the body fails.
+    pub handler: Block,
+}
 
-    /// Function definition: (identifier, parameters, return, body)
-    FunctionDefinition(Node, Node, Node, Node),
+/// A conversion of a value to another type, like `x as str`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cast {
+    /// The expression whose value is converted.
+    pub expr: Box<Expr>,
+    /// The type the value is converted to.
+    pub ty: TypeExpr,
+}
 
-    /// Parameters: (variable declarations)
-    Parameters(Nodes),
+/// An element of a collection, like `items[0]` or `scores["ann"]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Index {
+    /// The array, string, or map holding the element.
+    pub target: Box<Expr>,
+    /// The position of the element, or its key.
+    pub index: Box<Expr>,
+}
 
-    /// Return: (type)
-    Return(Option<Node>),
+/// A key of a map written as a name, like `point.x`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    /// The map holding the key.
+    pub target: Box<Expr>,
+    /// The key.
+    pub name: String,
+}
 
-    /// Block: (statements)
-    Block(Nodes),
+/// A call of a function a struct implements for an interface, like `shape.area()`, which is
+/// given the struct as its first argument
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodCall {
+    /// The value whose function is called.
+    pub target: Box<Expr>,
+    /// The name of the function called.
+    pub name: String,
+    /// The arguments passed to the function after the value, in order.
+    pub arguments: Vec<Expr>,
+}
 
-    /// Function call: (identifier, arguments)
-    FunctionCall(Node, Node),
+/// Enum representing statements, the units a program and its blocks are made of
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stmt {
+    /// Item: (item)
+    Item(Item),
+    /// Variable definition: (definition)
+    Definition(VariableDefinition),
+    /// Variable declaration: (declaration)
+    Declaration(VariableDeclaration),
+    /// Destructuring: (destructuring)
+    Destructure(Destructuring),
+    /// Element or key given a value: (store)
+    Store(Store),
+    /// Global declaration: (names assigned in the global scope from the function)
+    Global(Vec<String>),
+    /// Nonlocal declaration: (names assigned in the closest enclosing function from the function)
+    Nonlocal(Vec<String>),
+    /// Expression: (expression)
+    Expr(Expr),
+    /// Return: (value)
+    Return(Option<Expr>),
+    /// Break: leaves the innermost loop
+    Break,
+    /// Continue: skips to the next iteration of the innermost loop
+    Continue,
+}
 
-    /// Arguments: (variables)
-    Arguments(Nodes),
+/// A variable given a value, like `x: num = 1`, `x = 1`, or `const x = 1`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariableDefinition {
+    /// Whether the variable is a constant, which can never be given another value.
+    pub constant: bool,
+    /// The name of the variable.
+    pub name: String,
+    /// The type the variable is declared with, if there is one.
+    pub ty: Option<TypeExpr>,
+    /// The value given to the variable.
+    pub value: Expr,
+    /// Whether the definition is a compound assignment like `x += 1`, whose value is then
+    /// `x + 1`, which can only update a variable that already exists.
+    pub compound: bool,
+    /// The text of the `///` comments right above the definition, if it has any.
+    pub doc: Option<String>,
+}
 
-    If(Node, Node, Node),
+/// An element of an array or a key of a map given a value, like `point.x = 3` or
+/// `items[0] += 1`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Store {
+    /// The element given the value, an [`Expr::Index`] or an [`Expr::Member`].
+    pub target: Expr,
+    /// The value given to the element.
+    pub value: Expr,
+    /// Whether the store is a compound assignment like `items[0] += 1`, whose value is then
+    /// `items[0] + 1`, which can only update an element that already exists.
+    pub compound: bool,
+}
 
-    While(Node, Node),
+/// A variable declared without a value, like `x: num`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariableDeclaration {
+    /// The name of the variable.
+    pub name: String,
+    /// The type the variable is declared with.
+    pub ty: TypeExpr,
+}
 
-    /// Delimiter end the parsing of the current statement
-    ParenDelimiter,
-    BraceDelimiter,
-    BracketDelimiter,
+/// The components of a value given to variables, like `[a, b] = pair` or `{x, y} = point`
+///
+/// Every variable is assigned like in `x = 1`: the closest variable with the name is updated, or
+/// else one is defined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Destructuring {
+    /// The variables the components are given to.
+    pub pattern: Pattern,
+    /// The value taken apart.
+    pub value: Expr,
+}
 
-    /// Delimiter end the parsing of the current statement
-    Separator,
+/// Enum representing the shapes values are taken apart by
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// The elements of an array with as many elements, in order: (names)
+    Array(Vec<String>),
+    /// The values of the keys of a map named like the variables: (names)
+    Map(Vec<String>),
+}
 
-    End,
+impl Pattern {
+    /// Returns the names of the variables the components are given to, in order.
+    pub fn names(&self) -> &[String] {
+        match self {
+            Pattern::Array(names) | Pattern::Map(names) => names,
+        }
+    }
 }
 
-impl fmt::Display for ASTNode {
+impl fmt::Display for Pattern {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ASTNode::StringLiteral(value) => write!(f, "\"{}\"", value),
-            ASTNode::BooleanLiteral(value) => write!(f, "{}", value),
-            ASTNode::NumberLiteral(value) => write!(f, "{}", value),
-            ASTNode::Identifier(name) => write!(f, "{}", name),
-            ASTNode::Operator(op) => write!(f, "{}", op),
-            ASTNode::UnaryExpression(op, expr) => write!(f, "({} {})", op, expr),
-            ASTNode::BinaryExpression(left, op, right) => write!(f, "({} {} {})", left, op, right),
-            ASTNode::VariableDefinition(name, t, expr) => write!(f, "{}: {} = {}", name, t, expr),
-            ASTNode::FunctionDefinition(name, params, ret, body) => {
-                write!(f, "{}({}): {} {}", name, params, ret, body)
-            }
-            ASTNode::Parameters(params) => {
-                let params_str: Vec<String> = params.iter().map(|p| p.to_string()).collect();
-                write!(f, "({})", params_str.join(", "))
-            }
-            ASTNode::Block(statements) => {
-                let statements_str: Vec<String> =
-                    statements.iter().map(|s| s.to_string()).collect();
-                write!(f, "{{\n{}\n}}", statements_str.join("\n"))
-            }
-            ASTNode::FunctionCall(name, args) => {
-                write!(f, "{}({})", name, args)
-            }
-            ASTNode::Arguments(args) => {
-                let args_str: Vec<String> = args.iter().map(|a| a.to_string()).collect();
-                write!(f, "({})", args_str.join(", "))
-            }
-            ASTNode::Type(t) => match t {
-                Some(t) => write!(f, "{}", t),
-                None => write!(f, "none"),
-            },
-            ASTNode::Return(ret) => match ret {
-                Some(ret) => write!(f, "{}", ret),
-                None => write!(f, "none"),
-            },
-            ASTNode::StringType => write!(f, "str"),
-            ASTNode::BooleanType => write!(f, "bool"),
-            ASTNode::NumberType => write!(f, "num"),
-            ASTNode::If(_, _, _) => write!(f, "if"),
-            ASTNode::While(_, _) => write!(f, "while"),
-            ASTNode::Separator => write!(f, ","),
-            ASTNode::VariableDeclaration(name, t) => write!(f, "{}: {}", name, t),
-            ASTNode::Array(elements) => {
-                let elements_str: Vec<String> = elements.iter().map(|p| p.to_string()).collect();
-                write!(f, "({})", elements_str.join(", "))
-            }
-            ASTNode::ParenDelimiter => write!(f, ")"),
-            ASTNode::BraceDelimiter => write!(f, "}}"),
-            ASTNode::BracketDelimiter => write!(f, "]"),
-            ASTNode::End => todo!(),
+            Pattern::Array(names) => write!(f, "[{}]", names.join(", ")),
+            Pattern::Map(names) => write!(f, "{{{}}}", names.join(", ")),
+        }
+    }
+}
+
+/// Enum representing the named definitions a program is built from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    /// Function definition: (definition)
+    Function(FunctionDefinition),
+    /// Struct definition: (definition)
+    Struct(StructDefinition),
+    /// Interface definition: (definition)
+    Interface(InterfaceDefinition),
+    /// Functions of an interface implemented for a struct: (implementation)
+    Impl(Implementation),
+}
+
+/// A function definition, like `add(a: num, b: num): num { a + b }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionDefinition {
+    /// The name of the function.
+    pub name: String,
+    /// The parameters of the function, in order.
+    pub parameters: Vec<Parameter>,
+    /// The type the function returns, if it is declared.
+    pub ret: Option<TypeExpr>,
+    /// The block evaluated when the function is called.
+    pub body: Block,
+    /// The text of the `///` comments right above the definition, if it has any.
+    pub doc: Option<String>,
+}
+
+/// A struct definition, like `struct Point { x: num, y: num }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructDefinition {
+    /// The name of the struct, which its values are created by calling, like `Point(1, 2)`.
+    pub name: String,
+    /// The fields of the struct, in the order they are given values when one is created.
+    pub fields: Vec<Parameter>,
+    /// The text of the `///` comments right above the definition, if it has any.
+    pub doc: Option<String>,
+}
+
+/// An interface definition, like `interface Shape { area(self): num }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceDefinition {
+    /// The name of the interface.
+    pub name: String,
+    /// The functions a struct must implement to implement the interface, in order.
+    pub signatures: Vec<Signature>,
+    /// The text of the `///` comments right above the definition, if it has any.
+    pub doc: Option<String>,
+}
+
+/// A function an interface requires, like `area(self): num`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    /// The name of the function.
+    pub name: String,
+    /// The parameters of the function, in order, the first one being the struct itself.
+    pub parameters: Vec<Parameter>,
+    /// The type the function returns, if the interface declares one.
+    pub ret: Option<TypeExpr>,
+}
+
+impl Signature {
+    /// Tells if a function implements the signature: it takes as many parameters, and declares
+    /// the types the signature declares for them and for its value.
+    ///
+    /// # Arguments
+    ///
+    /// * `parameters` - The parameters of the function.
+    /// * `ret` - The type the function returns, if it is declared.
+    pub fn accepts(&self, parameters: &[Parameter], ret: Option<TypeExpr>) -> bool {
+        let declared = |expected: Option<TypeExpr>, found| expected.is_none() || expected == found;
+        self.parameters.len() == parameters.len()
+            && (self.parameters.iter().zip(parameters)).all(|(p, f)| declared(p.ty, f.ty))
+            && declared(self.ret, ret)
+    }
+}
+
+/// The functions of an interface implemented for a struct, like
+/// `impl Shape for Square { area(self) { self.side ^ 2 } }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Implementation {
+    /// The name of the interface implemented.
+    pub interface: String,
+    /// The name of the struct implementing it.
+    pub ty: String,
+    /// The block between the braces, whose statements all define functions of the interface.
+    pub body: Block,
+}
+
+impl Implementation {
+    /// Returns the functions defined by the block, in order.
+    pub fn functions(&self) -> impl Iterator<Item = &FunctionDefinition> {
+        self.body
+            .statements
+            .iter()
+            .filter_map(|statement| match statement {
+                Stmt::Item(Item::Function(definition)) => Some(definition),
+                _ => None,
+            })
+    }
+}
+
+/// A parameter of a function definition, like `a: num` or `[x, y]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parameter {
+    /// The name the argument is bound to, or the pattern as it is written.
+    pub name: String,
+    /// The type the parameter is declared with, if there is one.
+    pub ty: Option<TypeExpr>,
+    /// The pattern the argument is taken apart by instead of being bound to a name, if there is
+    /// one.
+    pub pattern: Option<Pattern>,
+}
+
+impl fmt::Display for TypeExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl fmt::Display for Parameter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ty {
+            Some(ty) => write!(f, "{}: {}", self.name, ty),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parameters: Vec<String> = self.parameters.iter().map(|p| p.to_string()).collect();
+        write!(f, "{}({})", self.name, parameters.join(", "))?;
+        match self.ret {
+            Some(ret) => write!(f, ": {}", ret),
+            None => Ok(()),
         }
     }
 }
 
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::Number(value) => write!(f, "{}", value),
+            Literal::String(value) => write!(f, "\"{}\"", value),
+            Literal::Boolean(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", print::expr_source(self, 0))
+    }
+}
+
+impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", print::stmt_source(self, 0))
+    }
+}
+
 /// Enum representing different types of AST errors
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ASTError {
-    UnknownToken(Token),
-    UnexpectedToken(Token),
+    /// A token that is not part of the language: (token)
+    UnknownToken(Token<'static>),
+    /// A valid token in a place where it is not allowed: (token)
+    UnexpectedToken(Token<'static>),
+    /// A statement in a place where only an expression is allowed: (statement)
+    UnexpectedStatement(Box<Stmt>),
+    /// A function parameter that is not a name with an optional type: (parameter)
+    InvalidParameter(Box<Stmt>),
+    /// A keyword used where it has no meaning, like `return` outside a function: (token)
+    MisplacedKeyword(Token<'static>),
+    /// A constant declared without a value: (name)
+    UninitializedConstant(String),
+    /// A construct nested deeper than the parser allows: (token)
+    TooDeep(Token<'static>),
+    /// A statement followed by another one on the same line without a `;`: (token starting the
+    /// second statement)
+    MissingSeparator(Token<'static>),
+    /// A string literal or a block comment that is never closed: (token)
+    Unterminated(Token<'static>),
+    /// An expression that is not a variable, an element, or a key given a value with `=`, like
+    /// `f() = 1`: (target)
+    InvalidAssignment(Box<Expr>),
+    /// A statement in an `impl` block that does not define a function: (statement)
+    InvalidImplementation(Box<Stmt>),
+    /// Several errors collected while parsing a group: (errors)
     Errors(Errors),
 }
 
+impl ASTError {
+    /// Returns the code explaining the error with `hydrogen explain`, see
+    /// [`codes`](super::codes). Several errors have the code of the first one.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ASTError::UnknownToken(_) => "H0001",
+            ASTError::UnexpectedToken(_) => "H0002",
+            ASTError::UnexpectedStatement(_) => "H0003",
+            ASTError::InvalidParameter(_) => "H0004",
+            ASTError::MisplacedKeyword(_) => "H0005",
+            ASTError::UninitializedConstant(_) => "H0006",
+            ASTError::TooDeep(_) => "H0007",
+            ASTError::MissingSeparator(_) => "H0008",
+            ASTError::Unterminated(_) => "H0009",
+            ASTError::InvalidAssignment(_) => "H0010",
+            ASTError::InvalidImplementation(_) => "H0011",
+            ASTError::Errors(errors) => errors.first().map_or("H0002", |error| error.code()),
+        }
+    }
+}
+
 impl fmt::Display for ASTError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Each of several errors is written on its own line, with its own code
+        if let ASTError::Errors(errors) = self {
+            let lines: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+            return write!(f, "{}", lines.join("\n"));
+        }
+        write!(f, "ERROR[{}]: ", self.code())?;
         match self {
-            ASTError::UnknownToken(error) => write!(f, "ERROR: {}", error),
-            ASTError::UnexpectedToken(error) => write!(f, "ERROR: {}", error),
-            ASTError::Errors(errors) => write!(f, "ERROR: {:?}", errors),
+            ASTError::UnknownToken(error) => write!(f, "{}", error),
+            ASTError::UnexpectedToken(error) => write!(f, "{}", error),
+            ASTError::UnexpectedStatement(statement) => {
+                write!(f, "expected an expression, found `{}`", statement)
+            }
+            ASTError::InvalidParameter(parameter) => {
+                write!(f, "invalid parameter `{}`", parameter)
+            }
+            ASTError::MisplacedKeyword(token) => write!(f, "{} is not allowed here", token),
+            ASTError::UninitializedConstant(name) => {
+                write!(f, "constant `{}` must be given a value", name)
+            }
+            ASTError::TooDeep(token) => write!(f, "nesting too deep at {}", token),
+            ASTError::MissingSeparator(token) => {
+                write!(f, "expected a new line or `;` before {}", token)
+            }
+            ASTError::Unterminated(token) => write!(f, "{}", token),
+            ASTError::InvalidAssignment(target) => {
+                write!(
+                    f,
+                    "cannot assign to `{}`, only variables, elements, and keys can be given values",
+                    target
+                )
+            }
+            ASTError::InvalidImplementation(statement) => write!(
+                f,
+                "expected a function, found `{}`, an `impl` block only defines functions",
+                statement
+            ),
+            ASTError::Errors(_) => unreachable!(),
         }
     }
 }
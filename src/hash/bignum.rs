@@ -0,0 +1,158 @@
+use std::{cmp::Ordering, rc::Rc};
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{Signed, ToPrimitive, Zero};
+
+use super::{
+    evaluator::{binary as checked, RuntimeError},
+    value::Value,
+};
+
+/// Returns the value of a whole number that fits in an `i64` as an integer, and of any other as
+/// a big integer.
+pub fn normalize(number: BigInt) -> Value {
+    match i64::try_from(&number) {
+        Ok(number) => Value::Integer(number),
+        Err(_) => Value::BigInteger(Rc::new(number)),
+    }
+}
+
+/// Reads a number literal made of digits only, which can be too large for an `i64`.
+pub(crate) fn parse(literal: &str) -> Option<Value> {
+    match literal.bytes().all(|byte| byte.is_ascii_digit()) {
+        true => literal.parse::<BigInt>().ok().map(normalize),
+        false => None,
+    }
+}
+
+/// Returns a whole number as a big integer, or None for any other value.
+fn whole(value: &Value) -> Option<BigInt> {
+    match value {
+        Value::Integer(number) => Some(BigInt::from(*number)),
+        Value::BigInteger(number) => Some(BigInt::clone(number)),
+        _ => None,
+    }
+}
+
+/// Returns a big integer as a float, which is infinite when it is too large for one.
+pub(crate) fn to_float(number: &BigInt) -> f64 {
+    number.to_f64().unwrap_or(match number.is_negative() {
+        true => f64::NEG_INFINITY,
+        false => f64::INFINITY,
+    })
+}
+
+/// Tells if two numbers, one of them a big integer, are equal.
+pub(crate) fn equal(left: &Value, right: &Value) -> bool {
+    compare(left, right) == Some(Ordering::Equal)
+}
+
+/// Orders two numbers, one of them a big integer, or returns None if one is not a number.
+pub(crate) fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (whole(left), whole(right)) {
+        (Some(l), Some(r)) => Some(l.cmp(&r)),
+        _ => left.as_float()?.partial_cmp(&right.as_float()?),
+    }
+}
+
+/// Applies a unary operator to a value, with whole numbers that never overflow.
+pub(crate) fn unary(op: &str, value: Value) -> Result<Value, RuntimeError> {
+    match (op, &value) {
+        ("-", Value::Integer(number)) => Ok(normalize(-BigInt::from(*number))),
+        ("-", Value::BigInteger(number)) => Ok(normalize(-BigInt::clone(number))),
+        ("+", Value::BigInteger(_)) => Ok(value),
+        _ => super::evaluator::unary(op, value),
+    }
+}
+
+/// Applies a binary operator to two values, with whole numbers that never overflow.
+///
+/// Whole numbers are computed with `i64` arithmetic while their results fit in it, and with big
+/// integers otherwise, so arithmetic on small numbers stays fast.
+pub(crate) fn binary(op: &str, left: Value, right: Value) -> Result<Value, RuntimeError> {
+    let big = matches!(left, Value::BigInteger(_)) || matches!(right, Value::BigInteger(_));
+    if !big {
+        match checked(op, left.clone(), right.clone()) {
+            Err(RuntimeError::Overflow(_)) => {}
+            result => return result,
+        }
+    }
+    let (Some(l), Some(r)) = (whole(&left), whole(&right)) else {
+        return checked(op, left, right);
+    };
+
+    let result = match op {
+        "+" => l + r,
+        "-" => l - r,
+        "*" => l * r,
+        "/" | "%" if r.is_zero() => return Err(RuntimeError::DivisionByZero),
+        "/" => match l.div_rem(&r) {
+            (quotient, remainder) if remainder.is_zero() => quotient,
+            _ => return Ok(Value::Float(to_float(&l) / to_float(&r))),
+        },
+        "%" => l.mod_floor(&r),
+        "^" if !r.is_negative() => match r.to_u32() {
+            Some(exponent) => l.pow(exponent),
+            // Only the powers of -1, 0, and 1 are small enough to hold
+            None if l.abs() <= BigInt::from(1) => match r.is_even() && l.is_negative() {
+                true => -l,
+                false => l,
+            },
+            None => return Err(RuntimeError::Overflow(op.to_string())),
+        },
+        _ => return checked(op, left, right),
+    };
+    Ok(normalize(result))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hash::{
+        evaluator::{Arithmetic, Evaluator},
+        parser::Parser,
+    };
+
+    fn run(program: &str) -> Result<Value, RuntimeError> {
+        let mut evaluator = Evaluator::new();
+        evaluator.set_arithmetic(Arithmetic::Big);
+        let mut result = Ok(Value::None);
+        for statement in Parser::new(program).parse_program().unwrap() {
+            result = evaluator.evaluate(&statement);
+        }
+        result
+    }
+
+    #[test]
+    fn test_bignum() {
+        let big = |digits: &str| parse(digits).unwrap();
+
+        assert_eq!(
+            run("9223372036854775807 + 1").map(|v| v.to_string()),
+            Ok("9223372036854775808".to_string())
+        );
+        assert_eq!(
+            run("2 ^ 100 / 2 ^ 99"),
+            Ok(Value::Integer(2)),
+            "results that fit are integers again"
+        );
+        assert_eq!(run("2 ^ 70"), Ok(big("1180591620717411303424")));
+        assert_eq!(run("-(2 ^ 70) % 3"), Ok(Value::Integer(2)));
+        assert_eq!(run("(-1) ^ 4294967296"), Ok(Value::Integer(1)));
+        assert_eq!(run("2 ^ 70 / 0"), Err(RuntimeError::DivisionByZero));
+        assert_eq!(
+            run("2 ^ 4294967296"),
+            Err(RuntimeError::Overflow("^".to_string()))
+        );
+        assert_eq!(run("(2 ^ 64 + 1) / 2"), Ok(Value::Float(2f64.powi(63))));
+
+        let program = "f(n) { if n < 2 { 1 } else { n * f(n - 1) } }\nf(25)";
+        assert_eq!(run(program), Ok(big("15511210043330985984000000")));
+        assert_eq!(
+            run("[99999999999999999999 == 10 ^ 20 - 1, 2 ^ 64 > 2 ^ 63, 2 ^ 64 == 2 ^ 64]"),
+            Ok(Value::from(vec![true, true, true]))
+        );
+        assert_eq!(run("typeof(2 ^ 64)"), Ok(Value::from("num")));
+    }
+}
@@ -0,0 +1,1517 @@
+use std::{
+    cell::{Cell, RefCell},
+    cmp::Ordering,
+    collections::BTreeMap,
+    env,
+    fs::{self, OpenOptions},
+    io::{self, BufRead, Write},
+    process::Command,
+    rc::Rc,
+    sync::OnceLock,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crossterm::terminal;
+
+use super::{
+    ast::{Parameter, Signature, TypeExpr},
+    environment::{Function, Interface},
+    evaluator::{
+        self, binary, bounds, cast, equal, method, numbers, range_length, truthy, RuntimeError,
+    },
+    json,
+    value::Value,
+};
+
+/// Signature of the functions provided by the language itself, which receive the backend running
+/// the program so they can call the functions they are given
+pub type Builtin = fn(&mut dyn Caller, &[Value]) -> Result<Value, RuntimeError>;
+
+/// Trait for the backends running programs, through which builtins call functions.
+pub trait Caller {
+    /// Calls a function value with the given arguments.
+    ///
+    /// # Arguments
+    ///
+    /// * `function` - The function to be called.
+    /// * `args` - The arguments passed to the function.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Value, RuntimeError>` - The value the function returns, or the error that
+    ///   stopped it.
+    fn call_value(&mut self, function: &Value, args: Vec<Value>) -> Result<Value, RuntimeError>;
+
+    /// Returns the closest function defined by the program with the given name, or held by a
+    /// variable with that name.
+    fn function(&self, name: &str) -> Option<Rc<Function>>;
+}
+
+/// Signature of the functions registered by the application embedding the language
+pub type Native = Rc<dyn Fn(&[Value]) -> Result<Value, RuntimeError>>;
+
+/// Trait for the values a native function may return.
+///
+/// Native functions can return a plain [`Value`] when they cannot fail, or a
+/// `Result<Value, RuntimeError>` to stop the program with an error.
+pub trait NativeResult {
+    /// Converts the returned value into the result of the call.
+    fn into_result(self) -> Result<Value, RuntimeError>;
+}
+
+impl NativeResult for Value {
+    fn into_result(self) -> Result<Value, RuntimeError> {
+        Ok(self)
+    }
+}
+
+impl NativeResult for Result<Value, RuntimeError> {
+    fn into_result(self) -> Result<Value, RuntimeError> {
+        self
+    }
+}
+
+/// Wraps a Rust closure so it can be called from Hydrogen programs.
+///
+/// # Arguments
+///
+/// * `function` - The closure receiving the evaluated arguments of the call.
+///
+/// # Returns
+///
+/// * `Native` - The closure with its result converted for the evaluator.
+pub fn native<F, R>(function: F) -> Native
+where
+    F: Fn(&[Value]) -> R + 'static,
+    R: NativeResult,
+{
+    Rc::new(move |args| function(args).into_result())
+}
+
+/// Returns the builtin function with the given name, if there is one.
+///
+/// # Arguments
+///
+/// * `name` - The name the function is called with.
+///
+/// # Returns
+///
+/// * `Option<Builtin>` - The builtin function, or None if no builtin has that name.
+pub fn lookup(name: &str) -> Option<Builtin> {
+    match name {
+        "print" => Some(print),
+        "clone" => Some(clone),
+        "push" => Some(push),
+        "pop" => Some(pop),
+        "len" => Some(len),
+        "contains" => Some(contains),
+        "sort" => Some(sort),
+        "reverse" => Some(reverse),
+        "map" => Some(map),
+        "filter" => Some(filter),
+        "reduce" => Some(reduce),
+        "slice" => Some(slice),
+        "read_file" => Some(read_file),
+        "write_file" => Some(write_file),
+        "append_file" => Some(append_file),
+        "read_line" => Some(read_line),
+        "input" => Some(input),
+        "eprint" => Some(eprint),
+        "now" => Some(now),
+        "clock_ms" => Some(clock_ms),
+        "random" => Some(random),
+        "rand_range" => Some(rand_range),
+        "seed" => Some(seed),
+        "get" => Some(get),
+        "set" => Some(set),
+        "keys" => Some(keys),
+        "json_parse" => Some(json_parse),
+        "json_encode" => Some(json_encode),
+        "args" => Some(args),
+        "env" => Some(env_var),
+        "exit" => Some(exit),
+        "run" => Some(run),
+        "assert" => Some(assert),
+        "assert_eq" => Some(assert_eq),
+        "expect" => Some(expect),
+        "help" => Some(help),
+        "typeof" => Some(type_of),
+        "is_num" => Some(|_, args| is("is_num", "num", args)),
+        "is_str" => Some(|_, args| is("is_str", "str", args)),
+        "is_bool" => Some(|_, args| is("is_bool", "bool", args)),
+        "is_array" => Some(|_, args| is("is_array", "array", args)),
+        "is_map" => Some(|_, args| is("is_map", "map", args)),
+        "is_fn" => Some(|_, args| is("is_fn", "fn", args)),
+        "is_none" => Some(|_, args| is("is_none", "none", args)),
+        "to_str" => Some(to_str),
+        "to_num" => Some(to_num),
+        "parse_num" => Some(parse_num),
+        "format" => Some(format),
+        "printf" => Some(printf),
+        _ => None,
+    }
+}
+
+/// The signature and a short description of every builtin, by name.
+const DOCS: [(&str, &str, &str); 49] = [
+    ("print", "print(values...)", "Prints the values separated by spaces, followed by a newline."),
+    ("clone", "clone(value)", "Returns a deep copy of a value, which shares no array with it."),
+    ("push", "push(array, values...)", "Appends the values to the end of an array."),
+    ("pop", "pop(array)", "Removes the last element of an array and returns it."),
+    ("len", "len(value): num", "Returns the number of elements of an array or range, of entries of a map, or of characters of a string."),
+    ("contains", "contains(collection, value): bool", "Tells if an array or range holds a value, or if a map has a key, like `in`."),
+    ("sort", "sort(array)", "Sorts an array of numbers, of strings, or of values of structs that implement `Compare` in place, and returns it."),
+    ("reverse", "reverse(value)", "Reverses an array in place and returns it, or returns a string reversed."),
+    ("map", "map(array, f: fn)", "Returns a new array of what a function returns for every element of an array or range."),
+    ("filter", "filter(array, f: fn)", "Returns a new array of the elements of an array or range for which a function returns true."),
+    ("reduce", "reduce(array, f: fn, initial)", "Combines the elements of an array or range into one value, starting from `initial` or the first element."),
+    ("slice", "slice(value, range)", "Returns the elements of an array, or the characters of a string, at the positions of a range."),
+    ("read_file", "read_file(path: str): str", "Returns the contents of a file."),
+    ("write_file", "write_file(path: str, value)", "Replaces the contents of a file with a value, creating the file if needed."),
+    ("append_file", "append_file(path: str, value)", "Adds a value to the end of a file, creating the file if needed."),
+    ("read_line", "read_line(): str", "Reads a line from the standard input with its line ending, or none at its end."),
+    ("input", "input(prompt: str): str", "Prints a prompt and returns the line typed after it without its line ending, or none at the end of the input."),
+    ("eprint", "eprint(values...)", "Prints the values separated by spaces, followed by a newline, to the standard error."),
+    ("now", "now(): num", "Returns the number of seconds since the Unix epoch, with their fraction."),
+    ("clock_ms", "clock_ms(): num", "Returns the milliseconds since the first call, for timing parts of a program."),
+    ("random", "random(): num", "Returns a random number from 0 up to 1, which it excludes."),
+    ("rand_range", "rand_range(start: num, end: num): num", "Returns a random whole number from a start up to an end it excludes."),
+    ("seed", "seed(n: num)", "Seeds `random` and `rand_range`, so they return the same numbers every run."),
+    ("get", "get(map, key: str)", "Returns the value a map holds for a key, or none if it has no such key."),
+    ("set", "set(map, key: str, value)", "Gives a key of a map a value, adding the key if the map does not have it."),
+    ("keys", "keys(map)", "Returns the keys of a map, in order."),
+    ("json_parse", "json_parse(text: str)", "Reads a value from JSON text, with objects as maps."),
+    ("json_encode", "json_encode(value): str", "Writes a value as JSON text."),
+    ("args", "args()", "Returns the arguments given to the program."),
+    ("env", "env(name: str)", "Returns the value of an environment variable, or none if it is not set."),
+    ("exit", "exit(status: num)", "Ends the program with a status, 0 unless one is given."),
+    ("run", "run(command: str, arguments)", "Runs a command and returns a map of its `status`, `stdout`, and `stderr`."),
+    ("assert", "assert(condition: bool, message)", "Fails unless a condition is true, with the message if one is given."),
+    ("assert_eq", "assert_eq(left, right, message)", "Fails unless two values are equal, with the message if one is given."),
+    ("expect", "expect(value, message: str)", "Returns a value, failing with the message if it is none or false."),
+    ("help", "help(function)", "Prints the signature and the description of a function, given by name or as a value."),
+    ("typeof", "typeof(value): str", "Returns the name of the type of a value: `num`, `str`, `bool`, `array`, `range`, `map`, `fn`, or `none`, or the name of the struct of a value of a struct."),
+    ("is_num", "is_num(value): bool", "Tells if a value is a number."),
+    ("is_str", "is_str(value): bool", "Tells if a value is a string."),
+    ("is_bool", "is_bool(value): bool", "Tells if a value is a boolean."),
+    ("is_array", "is_array(value): bool", "Tells if a value is an array."),
+    ("is_map", "is_map(value): bool", "Tells if a value is a map."),
+    ("is_fn", "is_fn(value): bool", "Tells if a value is a function."),
+    ("is_none", "is_none(value): bool", "Tells if a value is none."),
+    ("to_str", "to_str(value): str", "Returns the text of any value, as `print` writes it."),
+    ("to_num", "to_num(value): num", "Converts a string or a boolean to a number like `as num`, failing with an error `try` catches."),
+    ("parse_num", "parse_num(text: str)", "Returns the number a string holds, or none if it does not hold one."),
+    ("format", "format(template: str, values...): str", "Returns the template with every `{}` replaced by the next value, and `{{` and `}}` by braces."),
+    ("printf", "printf(template: str, values...)", "Prints the template with every `{}` replaced by the next value, without a newline."),
+];
+
+/// Returns an interface built into the language, which programs implement for their structs
+/// without defining it.
+///
+/// `Compare` is required by `sort` to order values of structs: its `compare` function returns a
+/// negative number when the value comes before the other one, a positive number when it comes
+/// after it, and zero when their order does not matter.
+///
+/// # Arguments
+///
+/// * `name` - The name of the interface.
+pub(crate) fn interface(name: &str) -> Option<Interface> {
+    let parameter = |name: &str| Parameter {
+        name: name.to_string(),
+        ty: None,
+        pattern: None,
+    };
+    match name {
+        "Compare" => Some(Interface {
+            name: name.to_string(),
+            signatures: vec![Signature {
+                name: "compare".to_string(),
+                parameters: vec![parameter("self"), parameter("other")],
+                ret: Some(TypeExpr::Number),
+            }],
+        }),
+        _ => None,
+    }
+}
+
+/// Returns the type of the values a builtin always returns, if the checker can rely on one.
+///
+/// # Arguments
+///
+/// * `name` - The name of the builtin.
+pub fn returns(name: &str) -> Option<TypeExpr> {
+    match name {
+        "len" | "now" | "clock_ms" | "random" | "rand_range" | "to_num" => Some(TypeExpr::Number),
+        "typeof" | "read_file" | "json_encode" | "to_str" | "format" => Some(TypeExpr::String),
+        "contains" | "is_num" | "is_str" | "is_bool" | "is_array" | "is_map" | "is_fn"
+        | "is_none" => Some(TypeExpr::Boolean),
+        _ => None,
+    }
+}
+
+/// Returns the types the first argument of a builtin may have, among the types the checker can
+/// infer, if the checker checks them.
+///
+/// # Arguments
+///
+/// * `name` - The name of the builtin.
+pub fn operands(name: &str) -> Option<&'static [TypeExpr]> {
+    match name {
+        // Arrays and maps have no type the checker infers
+        "len" => Some(&[TypeExpr::String]),
+        _ => None,
+    }
+}
+
+/// Splits a template of `format` into the texts around its `{}` placeholders, with `{{` and `}}`
+/// standing for braces, or returns None if a brace is not part of a placeholder.
+fn pieces(template: &str) -> Option<Vec<String>> {
+    let mut pieces = vec![String::new()];
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (
+            c,
+            chars.next_if(|&next| next == c || (c, next) == ('{', '}')),
+        ) {
+            ('{', Some('}')) => pieces.push(String::new()),
+            ('{' | '}', Some(brace)) => pieces.last_mut()?.push(brace),
+            ('{' | '}', None) => return None,
+            (c, _) => pieces.last_mut()?.push(c),
+        }
+    }
+    Some(pieces)
+}
+
+/// Returns the number of `{}` placeholders of a template of `format`, or None if the template is
+/// invalid.
+///
+/// # Examples
+/// ```
+/// use hydrogen::hash::builtins::placeholders;
+///
+/// assert_eq!(placeholders("x={} y={} {{z}}"), Some(2));
+/// assert_eq!(placeholders("{x}"), None);
+/// ```
+pub fn placeholders(template: &str) -> Option<usize> {
+    pieces(template).map(|pieces| pieces.len() - 1)
+}
+
+/// Returns the names of the builtins, in the order they are documented.
+pub fn names() -> impl Iterator<Item = &'static str> {
+    DOCS.iter().map(|(name, _, _)| *name)
+}
+
+/// Returns the signature and the description of a function the program defines or of a builtin.
+///
+/// # Arguments
+///
+/// * `caller` - The backend running the program, which knows the functions it defines.
+/// * `name` - The name the function is called with.
+///
+/// # Returns
+///
+/// * `Option<String>` - The signature, followed by the description on an indented line if there
+///   is one, or None if no function has that name.
+pub fn describe(caller: &dyn Caller, name: &str) -> Option<String> {
+    if let Some(function) = caller.function(name) {
+        let mut text = function.signature(name);
+        for line in function.doc.iter().flat_map(|doc| doc.lines()) {
+            text.push_str("\n    ");
+            text.push_str(line);
+        }
+        return Some(text);
+    }
+    DOCS.iter()
+        .find(|(builtin, _, _)| *builtin == name)
+        .map(|(_, signature, description)| format!("{}\n    {}", signature, description))
+}
+
+/// Checks that a builtin was given the number of arguments it takes.
+fn arity(name: &str, args: &[Value], count: usize) -> Result<(), RuntimeError> {
+    if args.len() != count {
+        return Err(RuntimeError::ArgumentCount(
+            name.to_string(),
+            count,
+            args.len(),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the text of a string argument.
+fn string(value: &Value) -> Result<&str, RuntimeError> {
+    match value {
+        Value::String(string) => Ok(string),
+        _ => Err(RuntimeError::TypeMismatch(
+            "str".to_string(),
+            value.type_name().to_string(),
+        )),
+    }
+}
+
+/// Returns the error for a file or stream the operating system could not use.
+fn io_error(file: &str, error: io::Error) -> RuntimeError {
+    RuntimeError::Io(file.to_string(), error.to_string())
+}
+
+/// Returns the elements of an array argument.
+fn elements(value: &Value) -> Result<Rc<RefCell<Vec<Value>>>, RuntimeError> {
+    match value {
+        Value::Array(elements) => Ok(Rc::clone(elements)),
+        _ => Err(RuntimeError::TypeMismatch(
+            "array".to_string(),
+            value.type_name().to_string(),
+        )),
+    }
+}
+
+/// Returns the elements of an array argument, or the numbers of a range argument without building
+/// an array of them.
+fn items(value: &Value) -> Result<Box<dyn Iterator<Item = Value>>, RuntimeError> {
+    match value {
+        // The caller sees the elements the array holds when the call starts
+        Value::Array(elements) => Ok(Box::new(elements.borrow().clone().into_iter())),
+        Value::Range(start, end, inclusive) => Ok(Box::new(
+            numbers(*start, *end, *inclusive).map(Value::Integer),
+        )),
+        _ => Err(RuntimeError::TypeMismatch(
+            "array or range".to_string(),
+            value.type_name().to_string(),
+        )),
+    }
+}
+
+/// Returns the entries of a map argument.
+fn entries(value: &Value) -> Result<Rc<RefCell<BTreeMap<String, Value>>>, RuntimeError> {
+    match value {
+        Value::Map(entries) => Ok(Rc::clone(entries)),
+        _ => Err(RuntimeError::TypeMismatch(
+            "map".to_string(),
+            value.type_name().to_string(),
+        )),
+    }
+}
+
+/// Prints the arguments separated by spaces, followed by a newline.
+fn print(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    let args_str: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+    println!("{}", args_str.join(" "));
+    Ok(Value::None)
+}
+
+/// Prints the arguments separated by spaces, followed by a newline, to the standard error.
+fn eprint(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    let args_str: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+    eprintln!("{}", args_str.join(" "));
+    Ok(Value::None)
+}
+
+/// Returns the name of the type of a value.
+fn type_of(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("typeof", args, 1)?;
+    match &args[0] {
+        Value::Instance(instance) => Ok(Value::from(instance.ty.name.as_str())),
+        value => Ok(Value::from(value.type_name())),
+    }
+}
+
+/// Tells if the value given to the predicate `name` has the type named `ty`.
+fn is(name: &str, ty: &str, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity(name, args, 1)?;
+    Ok(Value::Boolean(args[0].type_name() == ty))
+}
+
+/// Returns the text of a value, as `print` writes it.
+fn to_str(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("to_str", args, 1)?;
+    Ok(Value::from(args[0].to_string()))
+}
+
+/// Converts a value to a number like `as num` does, failing if it cannot.
+fn to_num(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("to_num", args, 1)?;
+    cast(args[0].clone(), TypeExpr::Number)
+}
+
+/// Returns the number a string holds, or none if it does not hold one.
+fn parse_num(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("parse_num", args, 1)?;
+    let text = string(&args[0])?;
+    Ok(cast(Value::from(text), TypeExpr::Number).unwrap_or(Value::None))
+}
+
+/// Replaces the placeholders of the template given first with the text of the other arguments.
+fn formatted(name: &str, args: &[Value]) -> Result<String, RuntimeError> {
+    let Some(template) = args.first() else {
+        return Err(RuntimeError::ArgumentCount(name.to_string(), 1, 0));
+    };
+    let template = string(template)?;
+    let pieces =
+        pieces(template).ok_or_else(|| RuntimeError::InvalidFormat(template.to_string()))?;
+    if pieces.len() != args.len() {
+        return Err(RuntimeError::ArgumentCount(
+            name.to_string(),
+            pieces.len(),
+            args.len(),
+        ));
+    }
+
+    let mut text = pieces[0].clone();
+    for (piece, value) in pieces[1..].iter().zip(&args[1..]) {
+        text.push_str(&value.to_string());
+        text.push_str(piece);
+    }
+    Ok(text)
+}
+
+/// Returns a template with its `{}` placeholders replaced by the text of the other arguments.
+fn format(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(Value::from(formatted("format", args)?))
+}
+
+/// Prints a template with its `{}` placeholders replaced by the text of the other arguments,
+/// without a newline.
+fn printf(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    let mut stdout = io::stdout();
+    write!(stdout, "{}", formatted("printf", args)?)
+        .and_then(|_| stdout.flush())
+        .map_err(|error| io_error("stdout", error))?;
+    Ok(Value::None)
+}
+
+/// Returns a deep copy of its argument, which shares no array with it, or an error if it holds
+/// itself.
+fn clone(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    match args {
+        [value] => value.deep_clone(),
+        _ => Err(RuntimeError::ArgumentCount(
+            "clone".to_string(),
+            1,
+            args.len(),
+        )),
+    }
+}
+
+/// Appends the values after the first argument to the array given first.
+fn push(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    let Some((array, values)) = args.split_first() else {
+        return Err(RuntimeError::ArgumentCount("push".to_string(), 2, 0));
+    };
+    elements(array)?.borrow_mut().extend_from_slice(values);
+    Ok(Value::None)
+}
+
+/// Removes the last element of an array and returns it.
+fn pop(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("pop", args, 1)?;
+    let element = elements(&args[0])?.borrow_mut().pop();
+    element.ok_or_else(|| RuntimeError::EmptyArray("pop".to_string()))
+}
+
+/// Returns the number of elements of an array or range, of entries of a map, or of characters
+/// of a string.
+fn len(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("len", args, 1)?;
+    let len = match &args[0] {
+        Value::String(string) => string.chars().count(),
+        Value::Map(entries) => entries.borrow().len(),
+        Value::Array(elements) => elements.borrow().len(),
+        Value::Range(start, end, inclusive) => range_length(*start, *end, *inclusive),
+        value => {
+            return Err(RuntimeError::TypeMismatch(
+                "str, array, range, or map".to_string(),
+                value.type_name().to_string(),
+            ))
+        }
+    };
+    Ok(Value::Integer(len as i64))
+}
+
+/// Tells if an array or range holds a value, comparing numbers by value, or if a map has a key,
+/// like the `in` operator.
+fn contains(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("contains", args, 2)?;
+    if !matches!(args[0], Value::Map(_) | Value::Range(..)) {
+        elements(&args[0])?;
+    }
+    binary("in", args[1].clone(), args[0].clone())
+}
+
+/// Sorts an array of numbers or of strings in place, and returns it.
+///
+/// Values of structs are sorted by the `compare` function of the `Compare` interface their structs
+/// implement, and equal values keep their order.
+fn sort(caller: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("sort", args, 1)?;
+    let elements = elements(&args[0])?;
+
+    if matches!(elements.borrow().first(), Some(Value::Instance(_))) {
+        // The functions can look at the array, so it is not borrowed while they run
+        let unsorted = elements.borrow().clone();
+        if let Some(Value::Instance(instance)) = (unsorted.iter()).find(|element| match element {
+            Value::Instance(instance) => !instance.ty.implements("Compare"),
+            _ => true,
+        }) {
+            return Err(RuntimeError::Unimplemented(
+                instance.ty.name.clone(),
+                "Compare".to_string(),
+                "compare".to_string(),
+            ));
+        }
+        if let Some(element) = unsorted.iter().find(|e| !matches!(e, Value::Instance(_))) {
+            return Err(RuntimeError::InvalidOperands(
+                "sort".to_string(),
+                vec![
+                    unsorted[0].type_name().to_string(),
+                    element.type_name().to_string(),
+                ],
+            ));
+        }
+
+        let sorted = merge_sort(unsorted, &mut |a, b| {
+            let compare = Value::Function(method(a, "compare")?);
+            let order = caller.call_value(&compare, vec![a.clone(), b.clone()])?;
+            match order.as_float() {
+                Some(order) => Ok(order.partial_cmp(&0.0).unwrap_or(Ordering::Equal)),
+                None => Err(RuntimeError::TypeMismatch(
+                    "num".to_string(),
+                    order.type_name().to_string(),
+                )),
+            }
+        })?;
+        *elements.borrow_mut() = sorted;
+        return Ok(args[0].clone());
+    }
+
+    // Every element must be comparable with the first one
+    let mut elements = elements.borrow_mut();
+    let comparable = |element: &Value| match (&elements[0], element) {
+        (Value::String(_), Value::String(_)) => true,
+        (first, element) => first.as_float().is_some() && element.as_float().is_some(),
+    };
+    if let Some(element) = elements.iter().find(|element| !comparable(element)) {
+        return Err(RuntimeError::InvalidOperands(
+            "sort".to_string(),
+            vec![
+                elements[0].type_name().to_string(),
+                element.type_name().to_string(),
+            ],
+        ));
+    }
+
+    elements.sort_by(|a, b| match (a, b) {
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        _ => a
+            .as_float()
+            .partial_cmp(&b.as_float())
+            .unwrap_or(Ordering::Equal),
+    });
+    drop(elements);
+    Ok(args[0].clone())
+}
+
+/// Sorts values by a function ordering two of them, keeping equal values in their order.
+///
+/// Unlike the sorts of the standard library, it never panics when the function does not order
+/// the values consistently, and it stops at the first error of the function.
+fn merge_sort(
+    mut values: Vec<Value>,
+    order: &mut dyn FnMut(&Value, &Value) -> Result<Ordering, RuntimeError>,
+) -> Result<Vec<Value>, RuntimeError> {
+    if values.len() < 2 {
+        return Ok(values);
+    }
+    let right = values.split_off(values.len() / 2);
+    let left = merge_sort(values, order)?;
+    let right = merge_sort(right, order)?;
+
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let (mut left, mut right) = (left.into_iter().peekable(), right.into_iter().peekable());
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        let next = match order(l, r)? {
+            Ordering::Greater => right.next(),
+            _ => left.next(),
+        };
+        merged.extend(next);
+    }
+    merged.extend(left.chain(right));
+    Ok(merged)
+}
+
+/// Reverses an array in place and returns it, or returns a string with its characters reversed.
+fn reverse(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("reverse", args, 1)?;
+    match &args[0] {
+        Value::String(string) => Ok(Value::from(string.chars().rev().collect::<String>())),
+        array => {
+            elements(array)?.borrow_mut().reverse();
+            Ok(array.clone())
+        }
+    }
+}
+
+/// Returns a new array holding the values a function returns for every element of an array or
+/// range.
+fn map(caller: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("map", args, 2)?;
+    let mapped = items(&args[0])?
+        .map(|element| caller.call_value(&args[1], vec![element]))
+        .collect::<Result<_, _>>()?;
+    Ok(Value::array(mapped))
+}
+
+/// Returns a new array holding the elements of an array or range for which a function returns
+/// true.
+fn filter(caller: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("filter", args, 2)?;
+    let mut kept = Vec::new();
+    for element in items(&args[0])? {
+        if truthy(caller.call_value(&args[1], vec![element.clone()])?)? {
+            kept.push(element);
+        }
+    }
+    Ok(Value::array(kept))
+}
+
+/// Combines the elements of an array or range into one value, by calling a function with the
+/// value so far and each element in turn.
+///
+/// The value starts as the third argument, or as the first element if there is no third argument.
+fn reduce(caller: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    if !(2..=3).contains(&args.len()) {
+        return Err(RuntimeError::ArgumentCount(
+            "reduce".to_string(),
+            3,
+            args.len(),
+        ));
+    }
+    let mut elements = items(&args[0])?;
+
+    let mut value = match args.get(2) {
+        Some(initial) => initial.clone(),
+        None => elements
+            .next()
+            .ok_or_else(|| RuntimeError::EmptyArray("reduce".to_string()))?,
+    };
+    for element in elements {
+        value = caller.call_value(&args[1], vec![value, element])?;
+    }
+    Ok(value)
+}
+
+/// Returns the elements of an array, or the characters of a string, at the positions of a range,
+/// like indexing them with the range does.
+///
+/// The positions the array or string does not have are left out, so a range reaching past its
+/// end stops at the end.
+fn slice(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("slice", args, 2)?;
+    let Value::Range(start, end, inclusive) = args[1] else {
+        return Err(RuntimeError::TypeMismatch(
+            "range".to_string(),
+            args[1].type_name().to_string(),
+        ));
+    };
+    evaluator::slice(&args[0], start, end.saturating_add(inclusive as i64))
+}
+
+/// Returns the contents of a file.
+fn read_file(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("read_file", args, 1)?;
+    let path = string(&args[0])?;
+    let contents = fs::read_to_string(path).map_err(|error| io_error(path, error))?;
+    Ok(Value::from(contents))
+}
+
+/// Replaces the contents of a file with a value, creating the file if it does not exist.
+fn write_file(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("write_file", args, 2)?;
+    let path = string(&args[0])?;
+    fs::write(path, args[1].to_string()).map_err(|error| io_error(path, error))?;
+    Ok(Value::None)
+}
+
+/// Adds a value to the end of a file, creating the file if it does not exist.
+fn append_file(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("append_file", args, 2)?;
+    let path = string(&args[0])?;
+    OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)
+        .and_then(|mut file| write!(file, "{}", args[1]))
+        .map_err(|error| io_error(path, error))?;
+    Ok(Value::None)
+}
+
+/// Reads a line from the standard input, with its line ending.
+///
+/// Returns an empty string once the input has ended, so a blank line, which still has its line
+/// ending, can be told apart from the end of the input.
+fn read_line(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("read_line", args, 0)?;
+    let mut line = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|error| io_error("stdin", error))?;
+    Ok(Value::from(line))
+}
+
+/// Prints a prompt and reads a line from the standard input, without its line ending.
+///
+/// The terminal is taken out of raw mode while the line is typed, as the REPL may leave it in
+/// raw mode, so the line is echoed and edited by the terminal as usual. Returns none once the
+/// input has ended.
+fn input(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    let prompt = match args {
+        [] => "",
+        [prompt] => string(prompt)?,
+        _ => {
+            return Err(RuntimeError::ArgumentCount(
+                "input".to_string(),
+                1,
+                args.len(),
+            ))
+        }
+    };
+
+    let raw = terminal::is_raw_mode_enabled().unwrap_or(false);
+    if raw {
+        terminal::disable_raw_mode().map_err(|error| io_error("stdin", error))?;
+    }
+    let mut stdout = io::stdout();
+    let mut line = String::new();
+    let read = write!(stdout, "{}", prompt)
+        .and_then(|_| stdout.flush())
+        .map_err(|error| io_error("stdout", error))
+        .and_then(|_| {
+            io::stdin()
+                .lock()
+                .read_line(&mut line)
+                .map_err(|error| io_error("stdin", error))
+        });
+    if raw {
+        terminal::enable_raw_mode().map_err(|error| io_error("stdin", error))?;
+    }
+
+    if read? == 0 {
+        return Ok(Value::None);
+    }
+    let line = line.strip_suffix('\n').unwrap_or(&line);
+    Ok(Value::from(line.strip_suffix('\r').unwrap_or(line)))
+}
+
+/// Returns the number of seconds since the Unix epoch, with their fraction.
+fn now(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("now", args, 0)?;
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Ok(Value::Float(elapsed.as_secs_f64()))
+}
+
+/// Returns the number of milliseconds since the first call of `clock_ms`, from a clock that never
+/// goes back, for timing parts of a program.
+fn clock_ms(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    static START: OnceLock<Instant> = OnceLock::new();
+
+    arity("clock_ms", args, 0)?;
+    let elapsed = START.get_or_init(Instant::now).elapsed();
+    Ok(Value::Integer(elapsed.as_millis() as i64))
+}
+
+thread_local! {
+    /// The state of the generator behind `random`, seeded from the clock until a program seeds it.
+    static RANDOM: Cell<u64> = Cell::new(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64,
+    );
+}
+
+/// Returns the next number of the generator behind `random`, with the SplitMix64 algorithm.
+fn next_random() -> u64 {
+    RANDOM.with(|state| {
+        let next = state.get().wrapping_add(0x9e37_79b9_7f4a_7c15);
+        state.set(next);
+
+        let mut z = next;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    })
+}
+
+/// Returns a random number from 0 up to 1, which it excludes.
+fn random(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("random", args, 0)?;
+    // The top 53 bits fill the mantissa of the float exactly
+    Ok(Value::Float(
+        (next_random() >> 11) as f64 / (1u64 << 53) as f64,
+    ))
+}
+
+/// Returns a random whole number from a start up to an end it excludes, like the range `a..b`.
+fn rand_range(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("rand_range", args, 2)?;
+    let (start, end) = bounds(&args[0], &args[1])?;
+    if start >= end {
+        return Err(RuntimeError::EmptyRange(start, end));
+    }
+
+    let span = (end as i128 - start as i128) as u64;
+    Ok(Value::Integer(
+        (start as i128 + (next_random() % span) as i128) as i64,
+    ))
+}
+
+/// Seeds the generator behind `random` and `rand_range`, so they return the same numbers every
+/// time the program runs.
+fn seed(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("seed", args, 1)?;
+    let Value::Integer(seed) = args[0] else {
+        return Err(RuntimeError::TypeMismatch(
+            "num".to_string(),
+            args[0].type_name().to_string(),
+        ));
+    };
+    RANDOM.with(|state| state.set(seed as u64));
+    Ok(Value::None)
+}
+
+/// Returns the value a map holds for a key, or none if it has no such key.
+fn get(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("get", args, 2)?;
+    let value = entries(&args[0])?.borrow().get(string(&args[1])?).cloned();
+    Ok(value.unwrap_or(Value::None))
+}
+
+/// Gives a key of a map a value, adding the key if the map does not have it.
+fn set(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("set", args, 3)?;
+    let key = string(&args[1])?.to_string();
+    entries(&args[0])?.borrow_mut().insert(key, args[2].clone());
+    Ok(Value::None)
+}
+
+/// Returns the keys of a map, in order.
+fn keys(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("keys", args, 1)?;
+    let keys = entries(&args[0])?
+        .borrow()
+        .keys()
+        .map(|key| Value::from(key.as_str()))
+        .collect();
+    Ok(Value::array(keys))
+}
+
+/// Reads a value from JSON text, with objects as maps.
+fn json_parse(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("json_parse", args, 1)?;
+    json::parse(string(&args[0])?)
+}
+
+/// Writes a value as JSON text.
+fn json_encode(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("json_encode", args, 1)?;
+    Ok(Value::from(json::encode(&args[0])?))
+}
+
+/// Returns the arguments given to the program, which the application running it provides.
+///
+/// Without an application providing them there are none, see
+/// [`Engine::set_args`](crate::Engine::set_args).
+fn args(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("args", args, 0)?;
+    Ok(Value::array(Vec::new()))
+}
+
+/// Returns the value of an environment variable, or none if it is not set.
+fn env_var(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("env", args, 1)?;
+    Ok(Value::from(env::var(string(&args[0])?).ok()))
+}
+
+/// Ends the program with a status, 0 unless one is given, that no `try` block can catch.
+fn exit(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    let status = match args {
+        [] => 0,
+        [Value::Integer(status)] => i32::try_from(*status)
+            .map_err(|_| RuntimeError::InvalidCast(status.to_string(), "status".to_string()))?,
+        [status] => {
+            return Err(RuntimeError::TypeMismatch(
+                "num".to_string(),
+                status.type_name().to_string(),
+            ))
+        }
+        _ => {
+            return Err(RuntimeError::ArgumentCount(
+                "exit".to_string(),
+                1,
+                args.len(),
+            ))
+        }
+    };
+    Err(RuntimeError::Exit(status))
+}
+
+/// Runs a command with an array of arguments, waiting for it to end.
+///
+/// Returns a map with the `status` the command exited with, or none if a signal ended it, and
+/// the text it wrote to its `stdout` and `stderr`.
+fn run(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    if !(1..=2).contains(&args.len()) {
+        return Err(RuntimeError::ArgumentCount(
+            "run".to_string(),
+            2,
+            args.len(),
+        ));
+    }
+    let program = string(&args[0])?;
+    let arguments = match args.get(1) {
+        Some(arguments) => Vec::<String>::try_from(arguments.clone())?,
+        None => Vec::new(),
+    };
+
+    let output = Command::new(program)
+        .args(arguments)
+        .output()
+        .map_err(|error| io_error(program, error))?;
+    Ok(Value::map(BTreeMap::from([
+        (
+            "status".to_string(),
+            Value::from(output.status.code().map(i64::from)),
+        ),
+        (
+            "stdout".to_string(),
+            Value::from(String::from_utf8_lossy(&output.stdout).into_owned()),
+        ),
+        (
+            "stderr".to_string(),
+            Value::from(String::from_utf8_lossy(&output.stderr).into_owned()),
+        ),
+    ])))
+}
+
+/// Returns the heading of a failure, with the message given to the check if there is one.
+fn heading(check: &str, message: Option<&Value>) -> String {
+    match message {
+        Some(message) => format!("{} failed: {}", check, message),
+        None => format!("{} failed", check),
+    }
+}
+
+/// Fails unless a condition is true, with a message describing the failure if one is given.
+fn assert(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    let (condition, message) = match args {
+        [condition] => (condition, None),
+        [condition, message] => (condition, Some(message)),
+        _ => {
+            return Err(RuntimeError::ArgumentCount(
+                "assert".to_string(),
+                2,
+                args.len(),
+            ))
+        }
+    };
+    match (condition, message) {
+        (Value::Boolean(true), _) => Ok(Value::None),
+        (Value::Boolean(false), message) => {
+            Err(RuntimeError::Assertion(heading("assertion", message)))
+        }
+        (condition, _) => Err(RuntimeError::TypeMismatch(
+            "bool".to_string(),
+            condition.type_name().to_string(),
+        )),
+    }
+}
+
+/// Fails unless two values are equal, showing both of them and the message if one is given.
+fn assert_eq(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    let (left, right, message) = match args {
+        [left, right] => (left, right, None),
+        [left, right, message] => (left, right, Some(message)),
+        _ => {
+            return Err(RuntimeError::ArgumentCount(
+                "assert_eq".to_string(),
+                3,
+                args.len(),
+            ))
+        }
+    };
+    if equal(left, right) {
+        return Ok(Value::None);
+    }
+    let heading = match message {
+        Some(_) => heading("assertion", message),
+        None => "assertion failed: left == right".to_string(),
+    };
+    Err(RuntimeError::Assertion(format!(
+        "{}\n     left: {}\n    right: {}",
+        heading,
+        left.repr(),
+        right.repr()
+    )))
+}
+
+/// Returns a value, failing with the given message if it is none or false.
+fn expect(_: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("expect", args, 2)?;
+    match &args[0] {
+        Value::None | Value::Boolean(false) => Err(RuntimeError::Assertion(format!(
+            "{}\n    value: {}",
+            heading("expectation", Some(&args[1])),
+            args[0].repr()
+        ))),
+        value => Ok(value.clone()),
+    }
+}
+
+/// Prints the signature and the description of a function, given by name or as a value.
+fn help(caller: &mut dyn Caller, args: &[Value]) -> Result<Value, RuntimeError> {
+    arity("help", args, 1)?;
+    let text = match &args[0] {
+        Value::Function(function) => function.signature(&function.name),
+        value => {
+            let name = string(value)?;
+            describe(caller, name)
+                .ok_or_else(|| RuntimeError::UndefinedFunction(name.to_string()))?
+        }
+    };
+    println!("{}", text);
+    Ok(Value::None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hash::{evaluator::Evaluator, parser::Parser};
+
+    /// Evaluates a program and returns the value of its last statement.
+    fn run(source: &str) -> Result<Value, RuntimeError> {
+        let mut evaluator = Evaluator::new();
+        let mut value = Ok(Value::None);
+        for statement in Parser::new(source).parse_program().unwrap() {
+            value = evaluator.evaluate(&statement);
+        }
+        value
+    }
+
+    #[test]
+    fn test_arrays() {
+        let array =
+            |elements: Vec<i64>| Value::array(elements.into_iter().map(Value::from).collect());
+
+        assert_eq!(
+            run("xs = [1]\npush(xs, 2, 3)\nxs"),
+            Ok(array(vec![1, 2, 3]))
+        );
+        assert_eq!(
+            run("xs = [1, 2]\n[pop(xs), len(xs)]"),
+            Ok(array(vec![2, 1]))
+        );
+        assert_eq!(
+            run("pop([])"),
+            Err(RuntimeError::EmptyArray("pop".to_string()))
+        );
+        assert_eq!(run("len(\"héllo\")"), Ok(Value::Integer(5)));
+        assert_eq!(
+            run("[len(1..=4), contains(1..4, 3), reduce(1..=4, fn(a, b) { a * b })]"),
+            Ok(Value::array(vec![
+                Value::Integer(4),
+                Value::Boolean(true),
+                Value::Integer(24)
+            ]))
+        );
+        assert_eq!(
+            run("filter(map(0..6, fn(x) { x * x }), fn(x) { x % 2 == 0 })"),
+            Ok(array(vec![0, 4, 16]))
+        );
+        assert_eq!(
+            run("xs = [1, 2, 3, 4]\n[slice(xs, 1..3), slice(xs, 2..10), slice(xs, 3..1)]"),
+            Ok(Value::array(vec![
+                array(vec![2, 3]),
+                array(vec![3, 4]),
+                array(vec![])
+            ]))
+        );
+        assert_eq!(run("slice(\"héllo\", 1..=3)"), Ok(Value::from("éll")));
+        assert_eq!(
+            run("slice([1], 0)"),
+            Err(RuntimeError::TypeMismatch(
+                "range".to_string(),
+                "num".to_string()
+            ))
+        );
+        assert_eq!(
+            run("len(fn() { 1 })"),
+            Err(RuntimeError::TypeMismatch(
+                "str, array, range, or map".to_string(),
+                "fn".to_string()
+            ))
+        );
+        assert_eq!(
+            run("contains([1, 2], 2) && !contains([], 1)"),
+            Ok(Value::Boolean(true))
+        );
+        assert_eq!(
+            run("xs = [3, 1, 2]\nsort(xs)\nxs"),
+            Ok(array(vec![1, 2, 3]))
+        );
+        assert_eq!(
+            run("sort([\"b\", \"a\"])"),
+            Ok(Value::array(vec![Value::from("a"), Value::from("b")]))
+        );
+        assert!(matches!(
+            run("sort([1, \"a\"])"),
+            Err(RuntimeError::InvalidOperands(_, _))
+        ));
+        // Instances are sorted by the `compare` of their struct
+        let numbers = "struct N { v: num }\nimpl Compare for N {\n    compare(self, other): num { other.v - self.v }\n}\n";
+        assert_eq!(
+            run(&format!(
+                "{}xs = [N(1), N(3), N(2)]\nsort(xs)\nmap(xs, fn(n) {{ n.v }})",
+                numbers
+            )),
+            Ok(array(vec![3, 2, 1]))
+        );
+        assert_eq!(
+            run("struct P { x }\nsort([P(2), P(1)])"),
+            Err(RuntimeError::Unimplemented(
+                "P".to_string(),
+                "Compare".to_string(),
+                "compare".to_string()
+            ))
+        );
+        assert!(matches!(
+            run(&format!("{}sort([N(1), 2])", numbers)),
+            Err(RuntimeError::InvalidOperands(_, _))
+        ));
+        assert_eq!(run("reverse([1, 2, 3])"), Ok(array(vec![3, 2, 1])));
+        assert_eq!(run("reverse(\"abc\")"), Ok(Value::from("cba")));
+
+        assert_eq!(
+            run("map([1, 2], fn(x) { x * 10 })"),
+            Ok(array(vec![10, 20]))
+        );
+        assert_eq!(
+            run("filter(0..5, fn(x) { x == 1 || x == 3 })"),
+            Ok(array(vec![1, 3]))
+        );
+        assert_eq!(
+            run("add(a, b) { a + b }\nreduce([1, 2, 3], add)"),
+            Ok(Value::Integer(6))
+        );
+        assert_eq!(
+            run("reduce([], fn(a, b) { a + b }, 10)"),
+            Ok(Value::Integer(10))
+        );
+        assert_eq!(
+            run("reduce([], fn(a, b) { a + b })"),
+            Err(RuntimeError::EmptyArray("reduce".to_string()))
+        );
+        assert!(matches!(
+            run("map([1], 2)"),
+            Err(RuntimeError::TypeMismatch(_, _))
+        ));
+        assert!(matches!(
+            run("len(1, 2)"),
+            Err(RuntimeError::ArgumentCount(_, 1, 2))
+        ));
+    }
+
+    #[test]
+    fn test_cycles() {
+        assert_eq!(
+            run("x = [1]\npush(x, x)\ny = [1]\npush(y, [y])\n[to_str(x), x == x, x == [1, x], y < [1, [y]], y]")
+                .unwrap()
+                .to_string(),
+            "[\"[1, [...]]\", true, true, false, [1, [[...]]]]"
+        );
+        assert_eq!(
+            run("x = [1]\npush(x, x)\nclone(x)"),
+            Err(RuntimeError::CyclicValue("clone".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_files() {
+        let path = std::env::temp_dir().join(format!("hydrogen_files_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap().replace('\\', "/");
+
+        assert_eq!(
+            run(&format!(
+                "path = \"{}\"\nwrite_file(path, \"a\")\nappend_file(path, 1 + 2)\nread_file(path)",
+                path
+            )),
+            Ok(Value::from("a3"))
+        );
+        fs::remove_file(&path).unwrap();
+
+        let error = run(&format!("read_file(\"{}\")", path)).unwrap_err();
+        assert!(matches!(&error, RuntimeError::Io(file, _) if *file == path));
+        assert!(error.to_string().starts_with(&format!("`{}`: ", path)));
+        assert!(matches!(
+            run("read_file(1)"),
+            Err(RuntimeError::TypeMismatch(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_maps() {
+        // String literals cannot hold quotes, so the JSON text comes from a native function
+        let mut evaluator = Evaluator::new();
+        evaluator.register(
+            "text",
+            native(|_: &[Value]| Value::from(r#"{"name": "app", "ports": [80]}"#)),
+        );
+        let mut run = |source: &str| {
+            let mut value = Ok(Value::None);
+            for statement in Parser::new(source).parse_program().unwrap() {
+                value = evaluator.evaluate(&statement);
+            }
+            value
+        };
+
+        assert_eq!(
+            run("config = json_parse(text())
+push(get(config, \"ports\"), 443)
+set(config, \"debug\", true)
+[len(config), keys(config), contains(config, \"name\"), \"port\" in config, get(config, \"x\")]")
+            .unwrap()
+            .to_string(),
+            r#"[3, ["debug", "name", "ports"], true, false, none]"#
+        );
+        assert_eq!(
+            run("json_encode(config)"),
+            Ok(Value::from(
+                r#"{"debug":true,"name":"app","ports":[80,443]}"#
+            ))
+        );
+        assert_eq!(
+            run("copy = clone(config)\nset(copy, \"debug\", false)\nget(config, \"debug\")"),
+            Ok(Value::Boolean(true))
+        );
+
+        // A map holding itself is written with `{...}` inside itself, and cannot be copied or
+        // encoded
+        assert_eq!(
+            run("set(config, \"self\", config)\n[to_str(config), try { json_encode(config) } catch e { e }, try { clone(config) } catch e { e }]")
+                .unwrap()
+                .to_string(),
+            r#"["{"debug": true, "name": "app", "ports": [80, 443], "self": {...}}", "cannot encode a cyclic value", "cannot clone a cyclic value"]"#
+        );
+
+        assert!(matches!(
+            run("json_parse(\"[1,\")"),
+            Err(RuntimeError::InvalidJson(_, 3))
+        ));
+        assert!(matches!(
+            run("json_encode(fn() { 1 })"),
+            Err(RuntimeError::InvalidCast(_, _))
+        ));
+        assert!(matches!(
+            run("get([1], \"a\")"),
+            Err(RuntimeError::TypeMismatch(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_process() {
+        assert_eq!(
+            run("try { exit(2) } catch e { 1 }"),
+            Err(RuntimeError::Exit(2))
+        );
+        assert_eq!(run("exit()"), Err(RuntimeError::Exit(0)));
+        assert!(matches!(
+            run("exit(\"1\")"),
+            Err(RuntimeError::TypeMismatch(_, _))
+        ));
+
+        // The test executable is sure to exist, and `--list` keeps it from running the tests
+        let program = std::env::current_exe().unwrap().display().to_string();
+        assert_eq!(
+            run(&format!(
+                "result = run({:?}, [\"--list\"])\n[get(result, \"status\"), get(result, \"stderr\")]",
+                program
+            )),
+            Ok(Value::array(vec![Value::Integer(0), Value::from("")]))
+        );
+        assert!(matches!(
+            run("run(\"hydrogen-missing-command\")"),
+            Err(RuntimeError::Io(command, _)) if command == "hydrogen-missing-command"
+        ));
+
+        assert_eq!(run("args()"), Ok(Value::array(Vec::new())));
+        assert_eq!(
+            run("env(\"CARGO_PKG_NAME\")"),
+            Ok(Value::from(env!("CARGO_PKG_NAME")))
+        );
+        assert_eq!(run("env(\"HYDROGEN_UNSET_VARIABLE\")"), Ok(Value::None));
+        assert!(matches!(
+            run("input(1)"),
+            Err(RuntimeError::TypeMismatch(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_types() {
+        assert_eq!(
+            run("[typeof(1), typeof(\"a\"), typeof([]), typeof(fn() { 1 }), typeof(env(\"HYDROGEN_UNSET\"))]"),
+            Ok(Value::array(
+                ["num", "str", "array", "fn", "none"]
+                    .into_iter()
+                    .map(Value::from)
+                    .collect()
+            ))
+        );
+        assert_eq!(
+            run("x = fn() { 1 }\n[is_fn(x), is_num(x), is_none(x()), is_map(json_parse(\"{}\"))]"),
+            Ok(Value::array(
+                [true, false, false, true]
+                    .into_iter()
+                    .map(Value::Boolean)
+                    .collect()
+            ))
+        );
+        assert_eq!(
+            run("is_str()"),
+            Err(RuntimeError::ArgumentCount("is_str".to_string(), 1, 0))
+        );
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(
+            run("[to_str(42), to_str([1, true]), to_num(\" 7 \"), to_num(true)]"),
+            Ok(Value::array(vec![
+                Value::from("42"),
+                Value::from("[1, true]"),
+                Value::Integer(7),
+                Value::Integer(1),
+            ]))
+        );
+        assert_eq!(
+            run("try { to_num(\"many\") } catch e { e }"),
+            Ok(Value::from("cannot convert `\"many\"` to `num`"))
+        );
+        assert_eq!(
+            run("[parse_num(\"3\"), parse_num(\"three\")]"),
+            Ok(Value::array(vec![Value::Integer(3), Value::None]))
+        );
+        assert!(matches!(
+            run("parse_num(3)"),
+            Err(RuntimeError::TypeMismatch(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(
+            run("format(\"x={} y={}{{}}\", 1, [true])"),
+            Ok(Value::from("x=1 y=[true]{}"))
+        );
+        assert_eq!(
+            run("format(\"{}\")"),
+            Err(RuntimeError::ArgumentCount("format".to_string(), 2, 1))
+        );
+        assert_eq!(
+            run("format(\"{x}\", 1)"),
+            Err(RuntimeError::InvalidFormat("{x}".to_string()))
+        );
+        assert_eq!(placeholders("}"), None);
+        assert_eq!(run("printf(\"\")"), Ok(Value::None));
+    }
+
+    #[test]
+    fn test_help() {
+        assert!(DOCS.iter().all(|(name, _, _)| lookup(name).is_some()));
+
+        let mut evaluator = Evaluator::new();
+        let program = "/// Multiplies `x`\n/// by `by`\nscale(x: num, by): num { x * by }\nhalf = fn(x) { x / 2 }";
+        for statement in Parser::new(program).parse_program().unwrap() {
+            evaluator.evaluate(&statement).unwrap();
+        }
+        assert_eq!(
+            describe(&evaluator, "scale").as_deref(),
+            Some("scale(x: num, by): num\n    Multiplies `x`\n    by `by`")
+        );
+        assert_eq!(describe(&evaluator, "half").as_deref(), Some("half(x)"));
+        assert_eq!(
+            describe(&evaluator, "pop").as_deref(),
+            Some("pop(array)\n    Removes the last element of an array and returns it.")
+        );
+        assert_eq!(describe(&evaluator, "split"), None);
+
+        assert_eq!(run("help(\"print\")"), Ok(Value::None));
+        assert_eq!(
+            run("help(\"split\")"),
+            Err(RuntimeError::UndefinedFunction("split".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_assert() {
+        assert_eq!(run("assert(1 < 2, \"math\")"), Ok(Value::None));
+        assert_eq!(
+            run("assert(false)"),
+            Err(RuntimeError::Assertion("assertion failed".to_string()))
+        );
+        assert_eq!(
+            run("try { assert(1 > 2, \"math\") } catch e { e }"),
+            Ok(Value::from("assertion failed: math"))
+        );
+        assert!(matches!(
+            run("assert(1)"),
+            Err(RuntimeError::TypeMismatch(_, _))
+        ));
+
+        assert_eq!(run("assert_eq([1, 2], [1, 2])"), Ok(Value::None));
+        let error = run("check(x) { assert_eq(x * 2, 5, \"doubled\") }\ncheck(2)").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "assertion failed: doubled\n     left: 4\n    right: 5"
+        );
+        assert_eq!(
+            run("assert_eq(\"a\", 1)").unwrap_err().to_string(),
+            "assertion failed: left == right\n     left: \"a\"\n    right: 1"
+        );
+
+        assert_eq!(run("expect(3, \"a number\")"), Ok(Value::Integer(3)));
+        assert_eq!(
+            run("expect(env(\"HYDROGEN_UNSET_VARIABLE\"), \"set\")")
+                .unwrap_err()
+                .to_string(),
+            "expectation failed: set\n    value: none"
+        );
+    }
+
+    #[test]
+    fn test_time_and_random() {
+        assert!(matches!(run("now()"), Ok(Value::Float(seconds)) if seconds > 1.7e9));
+        assert_eq!(
+            run("start = clock_ms()\nclock_ms() >= start"),
+            Ok(Value::Boolean(true))
+        );
+
+        let numbers = "seed(42)\n[random(), random(), rand_range(-3, 3), rand_range(0, 1)]";
+        let first = run(numbers).unwrap();
+        assert_eq!(run(numbers).unwrap(), first);
+        let Value::Array(elements) = first else {
+            panic!("expected an array");
+        };
+        let elements = elements.borrow();
+        assert!(matches!(elements[0], Value::Float(x) if (0.0..1.0).contains(&x)));
+        assert_ne!(elements[0], elements[1]);
+        assert!(matches!(elements[2], Value::Integer(x) if (-3..3).contains(&x)));
+        assert_eq!(elements[3], Value::Integer(0));
+
+        assert_eq!(run("rand_range(2, 2)"), Err(RuntimeError::EmptyRange(2, 2)));
+        assert!(matches!(
+            run("seed(\"a\")"),
+            Err(RuntimeError::TypeMismatch(_, _))
+        ));
+    }
+}
@@ -0,0 +1,1088 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use super::{
+    ast::{
+        BinaryExpression, Block, Expr, FunctionCall, Implementation, Index, Item, Literal, Member,
+        Parameter, Pattern, Signature, Stmt, Store, TypeExpr, VariableDefinition,
+    },
+    builtins,
+};
+
+/// Enum representing the problems the checker finds before a program runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckError {
+    /// A call with the wrong number of arguments: (function, expected, found)
+    ArgumentCount(String, usize, usize),
+    /// An argument whose type does not match its parameter: (function, parameter, expected, found)
+    ArgumentType(String, String, TypeExpr, TypeExpr),
+    /// A constant given another value: (name)
+    AssignConstant(String),
+    /// A builtin given an argument of a type it cannot work with: (builtin, found)
+    UnsupportedArgument(String, TypeExpr),
+    /// A compound assignment like `x += 1` to a variable that is not defined: (name)
+    UndefinedAssignment(String),
+    /// An operator applied to values of types it does not work with, like a compound assignment
+    /// or a comparison: (operator, left type, right type)
+    InvalidOperands(String, TypeExpr, TypeExpr),
+    /// A value that cannot have the shape of the pattern it is taken apart by: (pattern, value)
+    InvalidDestructuring(String, String),
+    /// A template of `format` or `printf` given another number of values than it has
+    /// placeholders: (builtin, placeholders, values)
+    PlaceholderCount(String, usize, usize),
+    /// A name declared `nonlocal` that no enclosing function has a variable for: (name)
+    UndefinedNonlocal(String),
+    /// An element or a key of the array or map a constant holds given a value: (name)
+    MutateConstant(String),
+    /// A function of an interface an `impl` block leaves out: (struct, interface, function)
+    Unimplemented(String, String, String),
+    /// A function of an `impl` block that does not match the signature the interface requires:
+    /// (interface, expected signature, found signature)
+    SignatureMismatch(String, String, String),
+    /// A function defined in an `impl` block of an interface that does not require it:
+    /// (interface, function)
+    UndefinedMethod(String, String),
+}
+
+impl CheckError {
+    /// Returns the code explaining the error with `hydrogen explain`, see
+    /// [`codes`](super::codes).
+    pub fn code(&self) -> &'static str {
+        match self {
+            CheckError::ArgumentType(..) => "H0101",
+            CheckError::ArgumentCount(..) => "H0102",
+            CheckError::AssignConstant(_) => "H0103",
+            CheckError::UnsupportedArgument(..) => "H0104",
+            CheckError::UndefinedAssignment(_) => "H0105",
+            CheckError::InvalidOperands(..) => "H0106",
+            CheckError::InvalidDestructuring(..) => "H0107",
+            CheckError::PlaceholderCount(..) => "H0108",
+            CheckError::UndefinedNonlocal(_) => "H0109",
+            CheckError::MutateConstant(_) => "H0110",
+            CheckError::Unimplemented(..) => "H0111",
+            CheckError::SignatureMismatch(..) => "H0112",
+            CheckError::UndefinedMethod(..) => "H0113",
+        }
+    }
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckError::ArgumentCount(name, expected, found) => write!(
+                f,
+                "`{}` takes {} argument(s) but {} were given",
+                name, expected, found
+            ),
+            CheckError::ArgumentType(name, parameter, expected, found) => write!(
+                f,
+                "parameter `{}` of `{}` expects a value of type `{}`, found `{}`",
+                parameter, name, expected, found
+            ),
+            CheckError::AssignConstant(name) => {
+                write!(f, "cannot assign twice to constant `{}`", name)
+            }
+            CheckError::UnsupportedArgument(name, found) => {
+                write!(f, "`{}` cannot take a value of type `{}`", name, found)
+            }
+            CheckError::UndefinedAssignment(name) => write!(
+                f,
+                "cannot update undefined variable `{}`, define it with `=` first",
+                name
+            ),
+            CheckError::InvalidOperands(op, left, right) => {
+                write!(f, "cannot apply `{}` to `{}` and `{}`", op, left, right)
+            }
+            CheckError::InvalidDestructuring(pattern, value) => {
+                write!(f, "cannot destructure {} into `{}`", value, pattern)
+            }
+            CheckError::PlaceholderCount(name, placeholders, values) => write!(
+                f,
+                "the template of `{}` has {} placeholder(s) but {} value(s) were given",
+                name, placeholders, values
+            ),
+            CheckError::UndefinedNonlocal(name) => write!(
+                f,
+                "`{}` is declared `nonlocal` but no enclosing function defines it",
+                name
+            ),
+            CheckError::MutateConstant(name) => {
+                write!(f, "cannot change the elements of constant `{}`", name)
+            }
+            CheckError::Unimplemented(ty, interface, name) => write!(
+                f,
+                "`{}` does not implement `{}` of `{}`",
+                ty, name, interface
+            ),
+            CheckError::SignatureMismatch(interface, expected, found) => write!(
+                f,
+                "`{}` requires `{}`, found `{}`",
+                interface, expected, found
+            ),
+            CheckError::UndefinedMethod(interface, name) => {
+                write!(f, "`{}` has no function `{}`", interface, name)
+            }
+        }
+    }
+}
+
+/// What the checker knows about a variable.
+#[derive(Debug, Clone, Copy, Default)]
+struct Binding {
+    /// The type the variable is declared with, if there is one.
+    ty: Option<TypeExpr>,
+    /// The type of every value given to a variable declared without one, if they all agree.
+    inferred: Option<TypeExpr>,
+    /// Whether the variable is a constant.
+    constant: bool,
+}
+
+impl Binding {
+    /// Creates a binding for a variable that can be given another value.
+    fn variable(ty: Option<TypeExpr>) -> Self {
+        Self {
+            ty,
+            inferred: None,
+            constant: false,
+        }
+    }
+}
+
+/// The names defined by a block.
+#[derive(Debug, Default)]
+struct Scope {
+    /// The parameters of the functions defined in the block, and the fields of its structs,
+    /// which are called like functions to create their values.
+    functions: HashMap<String, Vec<Parameter>>,
+    /// The signatures of the interfaces defined in the block.
+    interfaces: HashMap<String, Vec<Signature>>,
+    /// The variables defined in the block.
+    variables: HashMap<String, Binding>,
+    /// The names declared `global` in the block.
+    globals: HashSet<String>,
+    /// The names declared `nonlocal` in the block.
+    nonlocals: HashSet<String>,
+    /// Whether the block is the body of a function, which assignments do not reach past.
+    function: bool,
+}
+
+/// Checker finds mistakes in a program without running it.
+///
+/// Calls to functions the program defines are checked against their parameter lists: the
+/// number of arguments must match, and an argument whose type is known must have the type its
+/// parameter declares. Constants must never be given another value in the scope that defines
+/// them, in the blocks inside it, or by the functions declaring them `global`. Functions and
+/// variables are remembered between calls, so a program can be checked one statement at a time.
+///
+/// # Examples
+/// ```
+/// use hydrogen::hash::{checker::{CheckError, Checker}, parser::Parser};
+///
+/// let tree = Parser::new("hello() { 1 }\nhello(1, 2)").parse_program().unwrap();
+/// let mut checker = Checker::new();
+///
+/// assert!(checker.check(&tree[0]).is_empty());
+/// assert_eq!(
+///     checker.check(&tree[1]),
+///     vec![CheckError::ArgumentCount("hello".to_string(), 0, 2)]
+/// );
+/// ```
+#[derive(Debug)]
+pub struct Checker {
+    scopes: Vec<Scope>,
+    errors: Vec<CheckError>,
+    functions: usize, // How many function bodies are being checked
+}
+
+impl Checker {
+    /// Creates a new Checker instance that knows no names.
+    pub fn new() -> Self {
+        Self {
+            scopes: vec![Scope::default()],
+            errors: Vec::new(),
+            functions: 0,
+        }
+    }
+
+    /// Checks a statement, remembering the names it defines for the statements checked after it.
+    ///
+    /// # Arguments
+    ///
+    /// * `statement` - The statement to be checked.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<CheckError>` - The problems found in the statement, empty if there are none.
+    pub fn check(&mut self, statement: &Stmt) -> Vec<CheckError> {
+        self.statement(statement);
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Checks a statement of the program or of one of its blocks.
+    fn statement(&mut self, statement: &Stmt) {
+        match statement {
+            Stmt::Item(Item::Function(definition)) => {
+                // Defined before the body is checked, so that recursive calls are checked too
+                self.scope()
+                    .functions
+                    .insert(definition.name.clone(), definition.parameters.clone());
+                self.function(&definition.parameters, &definition.body);
+            }
+
+            Stmt::Item(Item::Struct(definition)) => {
+                self.scope()
+                    .functions
+                    .insert(definition.name.clone(), definition.fields.clone());
+            }
+
+            Stmt::Item(Item::Interface(definition)) => {
+                self.scope()
+                    .interfaces
+                    .insert(definition.name.clone(), definition.signatures.clone());
+            }
+
+            Stmt::Item(Item::Impl(implementation)) => self.implementation(implementation),
+
+            Stmt::Definition(definition) if definition.compound => self.update(definition),
+
+            Stmt::Definition(definition) => {
+                self.expression(&definition.value);
+                let inferred = self.infer(&definition.value);
+                let binding = Binding {
+                    ty: definition.ty,
+                    inferred,
+                    constant: definition.constant,
+                };
+                match definition.ty.is_some() || definition.constant {
+                    // Defines a variable in the current scope
+                    true => self.define(&definition.name, binding),
+                    // Assigns to the closest variable it can, or defines one if there is none
+                    false => {
+                        let scope = self.assigned(&definition.name);
+                        let variables = &mut self.scopes[scope].variables;
+                        match variables.get_mut(&definition.name) {
+                            Some(existing) if existing.constant => self
+                                .errors
+                                .push(CheckError::AssignConstant(definition.name.clone())),
+                            // A value of another type leaves the type of the variable unknown
+                            Some(existing) if existing.inferred != inferred => {
+                                existing.inferred = None;
+                            }
+                            Some(_) => {}
+                            None => {
+                                variables.insert(definition.name.clone(), binding);
+                            }
+                        }
+                    }
+                }
+            }
+
+            Stmt::Declaration(declaration) => {
+                self.define(&declaration.name, Binding::variable(Some(declaration.ty)));
+            }
+
+            Stmt::Destructure(destructuring) => {
+                self.expression(&destructuring.value);
+                self.destructure(&destructuring.pattern, &destructuring.value);
+                // The elements of an array literal have the types they are inferred to have
+                let names = destructuring.pattern.names();
+                let inferred: Vec<Option<TypeExpr>> = match &destructuring.value {
+                    Expr::Array(elements) if elements.len() == names.len() => {
+                        elements.iter().map(|element| self.infer(element)).collect()
+                    }
+                    _ => vec![None; names.len()],
+                };
+                for (name, inferred) in names.iter().zip(inferred) {
+                    let scope = self.assigned(name);
+                    let variables = &mut self.scopes[scope].variables;
+                    match variables.get_mut(name) {
+                        Some(existing) if existing.constant => {
+                            self.errors.push(CheckError::AssignConstant(name.clone()))
+                        }
+                        Some(existing) if existing.inferred != inferred => {
+                            existing.inferred = None;
+                        }
+                        Some(_) => {}
+                        None => {
+                            let binding = Binding {
+                                inferred,
+                                ..Binding::default()
+                            };
+                            variables.insert(name.clone(), binding);
+                        }
+                    }
+                }
+            }
+
+            Stmt::Store(store) => self.store(store),
+
+            Stmt::Global(names) => {
+                let globals = &mut self.scope().globals;
+                globals.extend(names.iter().cloned());
+            }
+
+            Stmt::Nonlocal(names) => {
+                let scope = self.scopes.len() - 1;
+                for name in names {
+                    match self.nonlocal(scope, name) {
+                        Some(_) => {
+                            self.scope().nonlocals.insert(name.clone());
+                        }
+                        None => self
+                            .errors
+                            .push(CheckError::UndefinedNonlocal(name.clone())),
+                    }
+                }
+            }
+
+            Stmt::Expr(expression) | Stmt::Return(Some(expression)) => self.expression(expression),
+
+            Stmt::Return(None) | Stmt::Break | Stmt::Continue => {}
+        }
+    }
+
+    /// Checks the expressions an expression is made of.
+    fn expression(&mut self, expression: &Expr) {
+        match expression {
+            Expr::Literal(_) | Expr::Identifier(_) => {}
+            Expr::Group(elements) | Expr::Array(elements) => {
+                elements.iter().for_each(|e| self.expression(e))
+            }
+            Expr::Unary(unary) => self.expression(&unary.expr),
+            Expr::Binary(binary) => {
+                self.expression(&binary.left);
+                self.expression(&binary.right);
+                self.comparison(binary);
+            }
+            Expr::Call(call) => self.call(call),
+            Expr::MethodCall(call) => {
+                self.expression(&call.target);
+                call.arguments.iter().for_each(|a| self.expression(a));
+            }
+            Expr::Block(block) => self.block(block, Scope::default()),
+            Expr::If(statement) => {
+                self.expression(&statement.condition);
+                self.block(&statement.then, Scope::default());
+                if let Some(otherwise) = &statement.otherwise {
+                    self.expression(otherwise);
+                }
+            }
+            Expr::While(statement) => {
+                self.expression(&statement.condition);
+                self.block(&statement.body, Scope::default());
+            }
+            Expr::For(statement) => {
+                self.expression(&statement.iterable);
+                let mut scope = Scope::default();
+                scope
+                    .variables
+                    .insert(statement.variable.clone(), Binding::default());
+                self.block(&statement.body, scope);
+            }
+            Expr::Range(range) => {
+                self.expression(&range.start);
+                self.expression(&range.end);
+            }
+            Expr::Index(index) => {
+                self.expression(&index.target);
+                self.expression(&index.index);
+                // Only strings can be indexed among the types known before running, by positions
+                if let (Some(target), Some(key)) =
+                    (self.infer(&index.target), self.infer(&index.index))
+                {
+                    if (target, key) != (TypeExpr::String, TypeExpr::Number) {
+                        self.errors.push(CheckError::InvalidOperands(
+                            "[]".to_string(),
+                            target,
+                            key,
+                        ));
+                    }
+                }
+            }
+            Expr::Member(member) => {
+                self.expression(&member.target);
+                if let Some(target) = self.infer(&member.target) {
+                    self.errors.push(CheckError::InvalidOperands(
+                        "[]".to_string(),
+                        target,
+                        TypeExpr::String,
+                    ));
+                }
+            }
+            Expr::Cast(cast) => self.expression(&cast.expr),
+            Expr::Try(statement) => {
+                self.block(&statement.body, Scope::default());
+                let mut scope = Scope::default();
+                scope.variables.insert(
+                    statement.variable.clone(),
+                    Binding::variable(Some(TypeExpr::String)),
+                );
+                self.block(&statement.handler, scope);
+            }
+            Expr::Lambda(lambda) => self.function(&lambda.parameters, &lambda.body),
+        }
+    }
+
+    /// Checks a call against the parameters of the function it calls, if the program defines it.
+    fn call(&mut self, call: &FunctionCall) {
+        call.arguments.iter().for_each(|a| self.expression(a));
+
+        let Some(parameters) = self.function_parameters(&call.name) else {
+            self.builtin(call);
+            return;
+        };
+
+        if parameters.len() != call.arguments.len() {
+            self.errors.push(CheckError::ArgumentCount(
+                call.name.clone(),
+                parameters.len(),
+                call.arguments.len(),
+            ));
+            return;
+        }
+
+        for (parameter, argument) in parameters.iter().zip(&call.arguments) {
+            if let Some(pattern) = &parameter.pattern {
+                self.destructure(pattern, argument);
+            }
+            if let (Some(expected), Some(found)) = (parameter.ty, self.infer(argument)) {
+                if expected != found {
+                    self.errors.push(CheckError::ArgumentType(
+                        call.name.clone(),
+                        parameter.name.clone(),
+                        expected,
+                        found,
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Checks the first argument of a call to a builtin against the types the builtin works
+    /// with, and the number of arguments of a call to `format` or `printf` against the
+    /// placeholders of a literal template, unless a variable of the program has the name of the
+    /// builtin.
+    fn builtin(&mut self, call: &FunctionCall) {
+        if self.variable(&call.name).is_some() {
+            return;
+        }
+        let first = call.arguments.first();
+        if let (Some(supported), Some(argument)) = (builtins::operands(&call.name), first) {
+            if let Some(found) = self.infer(argument) {
+                if !supported.contains(&found) {
+                    self.errors
+                        .push(CheckError::UnsupportedArgument(call.name.clone(), found));
+                }
+            }
+        }
+
+        let formats = matches!(call.name.as_str(), "format" | "printf");
+        if let Some(Expr::Literal(Literal::String(template))) = first.filter(|_| formats) {
+            let values = call.arguments.len() - 1;
+            if let Some(placeholders) = builtins::placeholders(template) {
+                if placeholders != values {
+                    self.errors.push(CheckError::PlaceholderCount(
+                        call.name.clone(),
+                        placeholders,
+                        values,
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Checks the functions of an `impl` block against the signatures of the interface it
+    /// implements, if the interface is known, and checks their bodies.
+    fn implementation(&mut self, implementation: &Implementation) {
+        let interface = &implementation.interface;
+        let signatures = (self.scopes.iter().rev())
+            .find_map(|scope| scope.interfaces.get(interface).cloned())
+            .or_else(|| builtins::interface(interface).map(|interface| interface.signatures));
+
+        if let Some(signatures) = signatures {
+            for definition in implementation.functions() {
+                match signatures.iter().find(|s| s.name == definition.name) {
+                    None => self.errors.push(CheckError::UndefinedMethod(
+                        interface.clone(),
+                        definition.name.clone(),
+                    )),
+                    Some(signature)
+                        if !signature.accepts(&definition.parameters, definition.ret) =>
+                    {
+                        let found = Signature {
+                            name: definition.name.clone(),
+                            parameters: definition.parameters.clone(),
+                            ret: definition.ret,
+                        };
+                        self.errors.push(CheckError::SignatureMismatch(
+                            interface.clone(),
+                            signature.to_string(),
+                            found.to_string(),
+                        ));
+                    }
+                    Some(_) => {}
+                }
+            }
+            for signature in &signatures {
+                if !implementation.functions().any(|f| f.name == signature.name) {
+                    self.errors.push(CheckError::Unimplemented(
+                        implementation.ty.clone(),
+                        interface.clone(),
+                        signature.name.clone(),
+                    ));
+                }
+            }
+        }
+
+        for definition in implementation.functions() {
+            self.function(&definition.parameters, &definition.body);
+        }
+    }
+
+    /// Checks the body of a function with its parameters in scope.
+    fn function(&mut self, parameters: &[Parameter], body: &Block) {
+        let mut scope = Scope::default();
+        for parameter in parameters {
+            match &parameter.pattern {
+                Some(pattern) => scope.variables.extend(
+                    pattern
+                        .names()
+                        .iter()
+                        .map(|name| (name.clone(), Binding::default())),
+                ),
+                None => {
+                    scope
+                        .variables
+                        .insert(parameter.name.clone(), Binding::variable(parameter.ty));
+                }
+            }
+        }
+        scope.function = true;
+        self.functions += 1;
+        self.block(body, scope);
+        self.functions -= 1;
+    }
+
+    /// Checks that a value can be taken apart by a pattern, as far as its shape or its type is
+    /// known: an array literal must have as many elements as an array pattern has names, and
+    /// numbers, strings, booleans, and functions cannot be taken apart.
+    fn destructure(&mut self, pattern: &Pattern, value: &Expr) {
+        let found = match (pattern, value) {
+            (Pattern::Array(names), Expr::Array(elements)) if names.len() != elements.len() => {
+                format!("an array of {} elements", elements.len())
+            }
+            (Pattern::Map(_), Expr::Array(_)) => "a value of type `array`".to_string(),
+            _ => match self.infer(value) {
+                Some(ty) => format!("a value of type `{}`", ty),
+                None => return,
+            },
+        };
+        self.errors
+            .push(CheckError::InvalidDestructuring(pattern.to_string(), found));
+    }
+
+    /// Checks that `<`, `<=`, `>`, and `>=` compare two numbers or two strings.
+    ///
+    /// `==` and `!=` take values of any types, since values of different types are simply never
+    /// equal.
+    fn comparison(&mut self, binary: &BinaryExpression) {
+        if !matches!(binary.op.as_str(), "<" | "<=" | ">" | ">=") {
+            return;
+        }
+        let (Some(left), Some(right)) = (self.infer(&binary.left), self.infer(&binary.right))
+        else {
+            return;
+        };
+        if left != right || !matches!(left, TypeExpr::Number | TypeExpr::String) {
+            self.errors
+                .push(CheckError::InvalidOperands(binary.op.clone(), left, right));
+        }
+    }
+
+    /// Checks a compound assignment like `x += 1`, which updates a variable that already exists
+    /// with `x + 1`, reading the variable before the value on the right is computed.
+    ///
+    /// The variable keeps its type, since the operators give values of the type of their
+    /// operands, so the operator has to apply to the type of the variable and of the value.
+    fn update(&mut self, definition: &VariableDefinition) {
+        self.expression(&definition.value);
+        let name = &definition.name;
+        let scope = self.assigned(name);
+        let Some(existing) = self.scopes[scope].variables.get(name).copied() else {
+            // Functions can update global variables the program defines after them
+            if scope != 0 || self.functions == 0 {
+                self.errors
+                    .push(CheckError::UndefinedAssignment(name.clone()));
+            }
+            return;
+        };
+        if existing.constant {
+            self.errors.push(CheckError::AssignConstant(name.clone()));
+            return;
+        }
+
+        let Expr::Binary(binary) = &definition.value else {
+            return;
+        };
+        let left = existing.ty.or(existing.inferred);
+        if let (Some(left), Some(right)) = (left, self.infer(&binary.right)) {
+            if !applies(&binary.op, left, right) {
+                let op = format!("{}=", binary.op);
+                self.errors
+                    .push(CheckError::InvalidOperands(op, left, right));
+            }
+        }
+    }
+
+    /// Checks an assignment to an element or a key, like `items[0] = 1` or `point.x += 1`.
+    ///
+    /// The array or map holding the element must not be held by a constant, even through the
+    /// elements of other arrays and maps, as in `points[0].x = 1`, and it cannot be a number, a
+    /// string, a boolean, or a function.
+    fn store(&mut self, store: &Store) {
+        // The element itself is not read, so only the expressions around it are checked
+        let (collection, key) = match &store.target {
+            Expr::Index(index) => {
+                self.expression(&index.target);
+                self.expression(&index.index);
+                (&index.target, self.infer(&index.index))
+            }
+            Expr::Member(member) => {
+                self.expression(&member.target);
+                (&member.target, Some(TypeExpr::String))
+            }
+            _ => {
+                self.expression(&store.target);
+                self.expression(&store.value);
+                return;
+            }
+        };
+        self.expression(&store.value);
+        if let (Some(found), Some(key)) = (self.infer(collection), key) {
+            self.errors
+                .push(CheckError::InvalidOperands("[]=".to_string(), found, key));
+            return;
+        }
+
+        let mut root: &Expr = collection;
+        while let Expr::Index(Index { target, .. }) | Expr::Member(Member { target, .. }) = root {
+            root = target;
+        }
+        if let Expr::Identifier(name) = root {
+            if self.variable(name).is_some_and(|binding| binding.constant) {
+                self.errors.push(CheckError::MutateConstant(name.clone()));
+            }
+        }
+    }
+
+    /// Checks the statements of a block inside the given scope.
+    fn block(&mut self, block: &Block, scope: Scope) {
+        self.scopes.push(scope);
+        block.statements.iter().for_each(|s| self.statement(s));
+        self.scopes.pop();
+    }
+
+    /// Returns the innermost scope.
+    fn scope(&mut self) -> &mut Scope {
+        self.scopes
+            .last_mut()
+            .expect("the global scope is never popped")
+    }
+
+    /// Defines a variable in the innermost scope, unless it already holds a constant of that name.
+    fn define(&mut self, name: &str, binding: Binding) {
+        if self.scope().variables.get(name).is_some_and(|b| b.constant) {
+            self.errors
+                .push(CheckError::AssignConstant(name.to_string()));
+            return;
+        }
+        self.scope().variables.insert(name.to_string(), binding);
+    }
+
+    /// Returns the closest variable with the given name, if there is one.
+    fn variable(&self, name: &str) -> Option<Binding> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.variables.get(name).copied())
+    }
+
+    /// Returns the index of the scope holding the variable an assignment to a name gives a
+    /// value to, or of the scope a new variable is defined in if there is no such variable.
+    ///
+    /// Assignments in a function only reach the variables of the function, the global variables
+    /// of the names it declares `global`, and the variables of enclosing functions of the names
+    /// it declares `nonlocal`.
+    fn assigned(&self, name: &str) -> usize {
+        for (i, scope) in self.scopes.iter().enumerate().rev() {
+            if scope.variables.contains_key(name) {
+                return i;
+            }
+            if scope.globals.contains(name) {
+                return 0;
+            }
+            if scope.nonlocals.contains(name) {
+                return self.nonlocal(i, name).unwrap_or(self.scopes.len() - 1);
+            }
+            if scope.function {
+                break;
+            }
+        }
+        self.scopes.len() - 1
+    }
+
+    /// Returns the index of the closest scope of the functions enclosing the function a scope is
+    /// part of that holds a variable with the given name, if there is one.
+    fn nonlocal(&self, scope: usize, name: &str) -> Option<usize> {
+        let function = (1..=scope).rev().find(|&i| self.scopes[i].function)?;
+        (1..function)
+            .rev()
+            .find(|&i| self.scopes[i].variables.contains_key(name))
+    }
+
+    /// Returns the parameters of the closest function with the given name, or None if a
+    /// variable of that name is closer, as a parameter holding a function is.
+    fn function_parameters(&self, name: &str) -> Option<Vec<Parameter>> {
+        for scope in self.scopes.iter().rev() {
+            if scope.variables.contains_key(name) {
+                return None;
+            }
+            if let Some(parameters) = scope.functions.get(name) {
+                return Some(parameters.clone());
+            }
+        }
+        None
+    }
+
+    /// Returns the type of the value an expression computes, if it can be known without
+    /// running it, using the names the checker knows.
+    ///
+    /// # Arguments
+    ///
+    /// * `expression` - The expression.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<TypeExpr>` - The type of the value, or None if it is not known.
+    pub fn infer(&self, expression: &Expr) -> Option<TypeExpr> {
+        match expression {
+            Expr::Literal(Literal::Number(_)) => Some(TypeExpr::Number),
+            Expr::Literal(Literal::String(_)) => Some(TypeExpr::String),
+            Expr::Literal(Literal::Boolean(_)) => Some(TypeExpr::Boolean),
+            Expr::Identifier(name) => match self.variable(name) {
+                Some(binding) => binding.ty.or(binding.inferred),
+                None => self.function_parameters(name).map(|_| TypeExpr::Function),
+            },
+            Expr::Group(elements) if elements.len() == 1 => self.infer(&elements[0]),
+            Expr::Unary(unary) => match unary.op.as_str() {
+                "!" => Some(TypeExpr::Boolean),
+                _ => Some(TypeExpr::Number),
+            },
+            Expr::Binary(binary) => match binary.op.as_str() {
+                "==" | "!=" | "<" | "<=" | ">" | ">=" | "&&" | "||" | "in" => {
+                    Some(TypeExpr::Boolean)
+                }
+                "+" | "-" | "*" | "/" | "%" | "^" => {
+                    let left = self.infer(&binary.left)?;
+                    let right = self.infer(&binary.right)?;
+                    match (left, right) {
+                        (TypeExpr::String, TypeExpr::String) if binary.op == "+" => {
+                            Some(TypeExpr::String)
+                        }
+                        (TypeExpr::Number, TypeExpr::Number) => Some(TypeExpr::Number),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            },
+            // Builtins are called unless a function or a variable of the program has their name
+            Expr::Call(call)
+                if self.function_parameters(&call.name).is_none()
+                    && self.variable(&call.name).is_none() =>
+            {
+                builtins::returns(&call.name)
+            }
+            Expr::Cast(cast) => Some(cast.ty),
+            Expr::Lambda(_) => Some(TypeExpr::Function),
+            // The characters of a string at a position or a range of positions
+            Expr::Index(index) => match self.infer(&index.target)? {
+                TypeExpr::String => Some(TypeExpr::String),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Returns whether an arithmetic operator applies to values of two types: numbers for all of
+/// them, and strings for `+`, which joins them.
+fn applies(op: &str, left: TypeExpr, right: TypeExpr) -> bool {
+    match (left, right) {
+        (TypeExpr::Number, TypeExpr::Number) => true,
+        (TypeExpr::String, TypeExpr::String) => op == "+",
+        _ => false,
+    }
+}
+
+impl Default for Checker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hash::parser::Parser;
+
+    fn check(checker: &mut Checker, program: &str) -> Vec<CheckError> {
+        let tree = Parser::new(program).parse_program().unwrap();
+        tree.iter()
+            .flat_map(|statement| checker.check(statement))
+            .collect()
+    }
+
+    #[test]
+    fn test_checker() {
+        let mut checker = Checker::new();
+
+        let program = r#"
+            hello() { print("hi") }
+            add(a: num, b: num): num { a + add(a) }
+            name: str = "x"
+        "#;
+        assert_eq!(
+            check(&mut checker, program),
+            vec![CheckError::ArgumentCount("add".to_string(), 2, 1)]
+        );
+        // A parameter holding a function hides the function of the same name
+        assert!(check(&mut checker, "apply(hello, x) { hello(x) }").is_empty());
+
+        assert_eq!(
+            check(&mut checker, "hello(1, 2, 3)\nprint(1, 2, 3)"),
+            vec![CheckError::ArgumentCount("hello".to_string(), 0, 3)]
+        );
+        assert_eq!(
+            check(&mut checker, "add(1, name)\nadd(true && x, (2 - 1))"),
+            vec![
+                CheckError::ArgumentType(
+                    "add".to_string(),
+                    "b".to_string(),
+                    TypeExpr::Number,
+                    TypeExpr::String
+                ),
+                CheckError::ArgumentType(
+                    "add".to_string(),
+                    "a".to_string(),
+                    TypeExpr::Number,
+                    TypeExpr::Boolean
+                ),
+            ]
+        );
+        assert_eq!(
+            check(&mut checker, "if true { hello() { 1 }\nhello(2) }\nhello()"),
+            vec![CheckError::ArgumentCount("hello".to_string(), 0, 1)]
+        );
+        assert!(check(
+            &mut checker,
+            "add(x, name as num)\nf = fn(n: num) { add(n, n) }"
+        )
+        .is_empty());
+
+        let program = r#"
+            const limit: num = 10
+            limit = 11
+            limit: num
+            if true { limit += 1 }
+            if true { const limit = "shadowed" }
+            count = 0
+            count = 1
+        "#;
+        assert_eq!(
+            check(&mut checker, program),
+            vec![
+                CheckError::AssignConstant("limit".to_string()),
+                CheckError::AssignConstant("limit".to_string()),
+                CheckError::AssignConstant("limit".to_string()),
+            ]
+        );
+        assert_eq!(check(&mut checker, "add(limit, 1)\nadd(\"a\", 1)").len(), 1);
+
+        // Variables declared without a type have the type of their values while those agree
+        let count = Expr::Identifier("count".to_string());
+        assert_eq!(checker.infer(&count), Some(TypeExpr::Number));
+        assert_eq!(
+            check(&mut checker, "add(count, 1)\ncount = \"many\""),
+            vec![]
+        );
+        assert_eq!(checker.infer(&count), None);
+
+        // Calls to builtins have the type the builtin returns, unless the program shadows it
+        let tree = Parser::new("typeof(count)").parse_program().unwrap();
+        let Stmt::Expr(call) = &tree[0] else {
+            panic!("expected an expression");
+        };
+        assert_eq!(checker.infer(call), Some(TypeExpr::String));
+        check(&mut checker, "typeof(x) { 1 }");
+        assert_eq!(checker.infer(call), None);
+
+        assert_eq!(
+            check(&mut checker, "len(\"é\") + len([1]) + len(2 > 1)"),
+            vec![CheckError::UnsupportedArgument(
+                "len".to_string(),
+                TypeExpr::Boolean
+            )]
+        );
+        assert_eq!(
+            check(
+                &mut checker,
+                "format(\"{} {{}}\", 1, 2)\nprintf(\"{}\", 1)\nformat(name, 1)"
+            ),
+            vec![CheckError::PlaceholderCount("format".to_string(), 1, 2)]
+        );
+        assert_eq!(
+            CheckError::PlaceholderCount("printf".to_string(), 2, 1).to_string(),
+            "the template of `printf` has 2 placeholder(s) but 1 value(s) were given"
+        );
+
+        let program = r#"
+            total += 1
+            tick() { global ticks; ticks += 1 }
+            tock() { ticks += 1 }
+            counter() {
+                count = 0
+                fn() { nonlocal count; count += 1 }
+            }
+            broken() { nonlocal ticks; ticks += 1 }
+            ticks = 0
+            ticks *= 2
+            label = "x"
+            label += "y"
+            label -= "y"
+            label += 1
+        "#;
+        assert_eq!(
+            check(&mut checker, program),
+            vec![
+                CheckError::UndefinedAssignment("total".to_string()),
+                CheckError::UndefinedAssignment("ticks".to_string()),
+                CheckError::UndefinedNonlocal("ticks".to_string()),
+                CheckError::UndefinedAssignment("ticks".to_string()),
+                CheckError::InvalidOperands("-=".to_string(), TypeExpr::String, TypeExpr::String),
+                CheckError::InvalidOperands("+=".to_string(), TypeExpr::String, TypeExpr::Number),
+            ]
+        );
+
+        let program = r#"
+            label == 1
+            ticks != "a"
+            label < "z"
+            true >= false
+            fn() { 1 } == tick
+            ticks <= len(label)
+            label > 1
+        "#;
+        assert_eq!(
+            check(&mut checker, program),
+            vec![
+                CheckError::InvalidOperands(">=".to_string(), TypeExpr::Boolean, TypeExpr::Boolean),
+                CheckError::InvalidOperands(">".to_string(), TypeExpr::String, TypeExpr::Number),
+            ]
+        );
+
+        // The collection a constant holds cannot change, nor the collections inside it
+        let program = r#"
+            const origin = json_parse("{}")
+            const grid = [[0]]
+            grid[0][0] = 1
+            move() { origin.x += 1 }
+            origin[0] = 1
+            label.size = 2
+            label[0] = "a"
+            first = label[0] + label[1..3]
+            label.size + 1
+            (1)[0] + true[label]
+        "#;
+        assert_eq!(
+            check(&mut checker, program),
+            vec![
+                CheckError::MutateConstant("grid".to_string()),
+                CheckError::MutateConstant("origin".to_string()),
+                CheckError::MutateConstant("origin".to_string()),
+                CheckError::InvalidOperands("[]=".to_string(), TypeExpr::String, TypeExpr::String),
+                CheckError::InvalidOperands("[]=".to_string(), TypeExpr::String, TypeExpr::Number),
+                CheckError::InvalidOperands("[]".to_string(), TypeExpr::String, TypeExpr::String),
+                CheckError::InvalidOperands("[]".to_string(), TypeExpr::Number, TypeExpr::Number),
+                CheckError::InvalidOperands("[]".to_string(), TypeExpr::Boolean, TypeExpr::String),
+            ]
+        );
+
+        let program = r#"
+            [first, second] = [1, "a"]
+            [a, b] = [1, 2, 3]
+            {x, y} = ticks
+            {x} = [1]
+            [limit, rest] = pair
+            point({x, y}) { x + y }
+            point(label)
+        "#;
+        let invalid = |pattern: &str, value: &str| {
+            CheckError::InvalidDestructuring(pattern.to_string(), value.to_string())
+        };
+        assert_eq!(
+            check(&mut checker, program),
+            vec![
+                invalid("[a, b]", "an array of 3 elements"),
+                invalid("{x, y}", "a value of type `num`"),
+                invalid("{x}", "a value of type `array`"),
+                CheckError::AssignConstant("limit".to_string()),
+                invalid("{x, y}", "a value of type `str`"),
+            ]
+        );
+        let second = Expr::Identifier("second".to_string());
+        assert_eq!(checker.infer(&second), Some(TypeExpr::String));
+
+        // The functions of an `impl` block match the signatures of the interface
+        let program = r#"
+            struct Point { x: num, y: num }
+            interface Shape {
+                area(self): num
+                scale(self, k: num)
+            }
+            impl Shape for Point {
+                area(self) { self.x * self.y }
+                perimeter(self) { 2 * (self.x + self.y) }
+            }
+            impl Compare for Point {
+                compare(self, other): num { self.x - other.x }
+            }
+            Point(1)
+            Point("a", 2)
+        "#;
+        assert_eq!(
+            check(&mut checker, program),
+            vec![
+                CheckError::SignatureMismatch(
+                    "Shape".to_string(),
+                    "area(self): num".to_string(),
+                    "area(self)".to_string()
+                ),
+                CheckError::UndefinedMethod("Shape".to_string(), "perimeter".to_string()),
+                CheckError::Unimplemented(
+                    "Point".to_string(),
+                    "Shape".to_string(),
+                    "scale".to_string()
+                ),
+                CheckError::ArgumentCount("Point".to_string(), 2, 1),
+                CheckError::ArgumentType(
+                    "Point".to_string(),
+                    "x".to_string(),
+                    TypeExpr::Number,
+                    TypeExpr::String
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,438 @@
+/// The longer description of a diagnostic code, shown by `hydrogen explain`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Explanation {
+    /// The code, like `H0101`.
+    pub code: &'static str,
+    /// What the code is about, in a few words.
+    pub title: &'static str,
+    /// The identifier of the rule of the linter reporting the code, if a rule does.
+    pub rule: Option<&'static str>,
+    /// What the problem is, how to fix it, and examples.
+    pub text: &'static str,
+}
+
+impl Explanation {
+    /// Creates the explanation of a code reported by the parser or the checker.
+    const fn new(code: &'static str, title: &'static str, text: &'static str) -> Self {
+        Self {
+            code,
+            title,
+            rule: None,
+            text,
+        }
+    }
+
+    /// Creates the explanation of a code reported by a rule of the linter.
+    const fn lint(code: &'static str, rule: &'static str, text: &'static str) -> Self {
+        Self {
+            code,
+            title: rule,
+            rule: Some(rule),
+            text,
+        }
+    }
+}
+
+/// Every code, in order: `H00xx` for parse errors, `H01xx` for the mistakes of the checker, and
+/// `H02xx` for the warnings of the linter.
+pub const EXPLANATIONS: [Explanation; 30] = [
+    Explanation::new(
+        "H0001",
+        "unknown token",
+        "The source code holds a character, or a sequence of characters, that is not part of the
+language, or a type name where a value is expected.
+
+    x = 1 @ 2
+
+Remove the character, or write the operator the code meant, like `x = 1 * 2`.",
+    ),
+    Explanation::new(
+        "H0002",
+        "unexpected token",
+        "A token of the language is in a place where it is not allowed, like an operator without
+a right operand, or a bracket that is never closed.
+
+    x = (1 +
+
+Finish the expression, here with `x = (1 + 2)`.",
+    ),
+    Explanation::new(
+        "H0003",
+        "expected an expression",
+        "A statement, like a variable definition, is written where only an expression is
+allowed, like an argument of a call.
+
+    print(x = 1)
+
+Define the variable first, and pass its name:
+
+    x = 1
+    print(x)",
+    ),
+    Explanation::new(
+        "H0004",
+        "invalid parameter",
+        "A parameter of a function definition is not a name with an optional type.
+
+    double(2) { 2 * 2 }
+
+Name the parameter, and use the name in the body: `double(n) { n * 2 }`.",
+    ),
+    Explanation::new(
+        "H0005",
+        "misplaced keyword",
+        "A keyword is used where it has no meaning: `return`, `global`, or `nonlocal` outside of a
+function, or `break` and `continue` outside of a loop.
+
+    x = 1
+    return x
+
+Move the statement into a function or a loop, or remove it.",
+    ),
+    Explanation::new(
+        "H0006",
+        "uninitialized constant",
+        "A constant is declared without a value. Constants can never be given a value later, so
+they must get one where they are declared.
+
+    const limit: num
+
+Give the constant its value: `const limit: num = 10`.",
+    ),
+    Explanation::new(
+        "H0007",
+        "nesting too deep",
+        "Expressions or blocks are nested inside each other more deeply than the parser allows,
+usually because of code generated by another program.
+
+Split the expression into variables holding its parts.",
+    ),
+    Explanation::new(
+        "H0008",
+        "statements run together",
+        "A statement ends at the end of its line, at a `;`, or at the `}` closing its block.
+Another statement starts on the same line without a `;` between them.
+
+    x = 1 y = 2
+
+Put the second statement on its own line, or separate them with a `;`: `x = 1; y = 2`.
+Inside parentheses and brackets, lines do not end statements, so long expressions can be split
+there:
+
+    total = (first
+        + second)",
+    ),
+    Explanation::new(
+        "H0009",
+        "unterminated literal",
+        "A string literal has no closing `\"`, or a block comment has no closing `*/`, so it runs
+to the end of the file. The error points at where the string or the comment starts.
+
+    greeting = \"hello
+    print(greeting)
+
+Close the string where it was meant to end: `greeting = \"hello\"`.",
+    ),
+    Explanation::new(
+        "H0010",
+        "invalid assignment target",
+        "Only a variable, an element of an array, or a key of a map can be given a value with `=`,
+`+=`, or another compound assignment. The expression on the left of the operator is something
+else, like a call or a literal.
+
+    last(items) = 0
+
+Give the value to a variable or an element instead, like `items[len(items) - 1] = 0`, or to a
+key, like `point.x = 0`.",
+    ),
+    Explanation::new(
+        "H0011",
+        "invalid implementation",
+        "An `impl` block holds a statement that does not define a function. The block only defines
+the functions of the interface it implements for a struct.
+
+    impl Shape for Square {
+        sides = 4
+        area(self) { self.side ^ 2 }
+    }
+
+Move the statement out of the block, or into one of its functions.",
+    ),
+    Explanation::new(
+        "H0101",
+        "type mismatch",
+        "An argument of a call does not have the type its parameter is declared with.
+
+    double(n: num): num { n * 2 }
+    double(\"two\")
+
+Pass a value of the declared type, like `double(2)`, or convert the value with `as`, like
+`double(\"2\" as num)`.",
+    ),
+    Explanation::new(
+        "H0102",
+        "wrong number of arguments",
+        "A function is called with more or fewer arguments than it has parameters.
+
+    add(a, b) { a + b }
+    add(1)
+
+Pass one argument for every parameter: `add(1, 2)`.",
+    ),
+    Explanation::new(
+        "H0103",
+        "assignment to a constant",
+        "A constant is given another value after it was defined.
+
+    const limit = 10
+    limit = 20
+
+Define the name as a variable, without `const`, if its value has to change.",
+    ),
+    Explanation::new(
+        "H0104",
+        "unsupported argument",
+        "A builtin is given a value of a type it cannot work with, like the length of a number.
+
+    len(42)
+
+Give the builtin a value it works with: `len` counts the characters of a string, the elements
+of an array, and the entries of a map.",
+    ),
+    Explanation::new(
+        "H0105",
+        "undefined assignment",
+        "A compound assignment like `x += 1` updates a variable that is not defined. It reads the
+variable before giving it a new value, so it never defines one.
+
+    total += 1
+
+Give the variable its first value with `=` before updating it.
+
+    total = 0
+    total += 1",
+    ),
+    Explanation::new(
+        "H0106",
+        "invalid operands",
+        "An operator is applied to values of types it does not work with. A compound assignment
+like `+=` works on two numbers or two strings, and the others on two numbers.
+
+    name = \"x\"
+    name += 1
+
+`<`, `<=`, `>`, and `>=` compare two numbers or two strings, while `==` and `!=` compare values
+of any types, which are never equal when their types differ.
+
+    if name < 1 {}
+
+Convert one of the values to the type of the other, like `name += to_str(1)`.",
+    ),
+    Explanation::new(
+        "H0107",
+        "invalid destructuring",
+        "A value is taken apart by a pattern it cannot have the shape of. An array pattern like
+`[a, b]` takes an array with exactly as many elements as it has names, and a map pattern like
+`{x, y}` a map with a key for each of its names.
+
+    [first, second] = [1, 2, 3]
+
+Name every element, or take the elements apart one by one with `pop`:
+
+    [first, second, third] = [1, 2, 3]",
+    ),
+    Explanation::new(
+        "H0108",
+        "wrong number of placeholders",
+        "The template of `format` or `printf` has another number of `{}` placeholders than the
+values given after it, so some values would be left out or some placeholders left empty.
+
+    format(\"{} and {}\", 1)
+
+Give a value for every placeholder, or write a brace that is not a placeholder as `{{` or `}}`:
+`format(\"{} and {}\", 1, 2)`.",
+    ),
+    Explanation::new(
+        "H0109",
+        "undefined nonlocal variable",
+        "A function declares a name `nonlocal`, but none of the functions enclosing it defines a
+variable with that name before it. `nonlocal` only reaches the variables of enclosing functions,
+not the global ones.
+
+    count = 0
+    bump() { nonlocal count; count += 1 }
+
+Declare a global variable `global` instead, or define the variable in an enclosing function:
+
+    counter() {
+        count = 0
+        fn() { nonlocal count; count += 1 }
+    }",
+    ),
+    Explanation::new(
+        "H0110",
+        "mutated constant",
+        "An element of an array or a key of a map is given a value, but the collection is held
+by a constant, directly or inside another collection. A constant keeps its value, and the
+elements of that value too.
+
+    const origin = json_parse(\"{}\")
+    origin.x = 1
+
+Define the collection with `=` instead of `const` if it has to change.",
+    ),
+    Explanation::new(
+        "H0111",
+        "missing implementation",
+        "An `impl` block leaves out a function the interface it implements requires, so the
+function could not be called on the values of the struct.
+
+    interface Shape { area(self): num; perimeter(self): num }
+    impl Shape for Square { area(self) { self.side ^ 2 } }
+
+Define every function of the interface in the block.",
+    ),
+    Explanation::new(
+        "H0112",
+        "signature mismatch",
+        "A function of an `impl` block does not match the signature the interface requires: it
+takes another number of parameters, or declares other types for them or for its value.
+
+    interface Shape { area(self): num }
+    impl Shape for Square { area(self, scale) { self.side ^ 2 * scale } }
+
+Take the parameters of the signature, with the types it declares.",
+    ),
+    Explanation::new(
+        "H0113",
+        "undefined method",
+        "An `impl` block defines a function the interface it implements does not require. Only the
+functions of the interface can be called on the values of the struct.
+
+    interface Shape { area(self): num }
+    impl Shape for Square { area(self) { self.side ^ 2 }; side(self) { self.side } }
+
+Add the function to the interface, or implement it in an interface of its own.",
+    ),
+    Explanation::lint(
+        "H0201",
+        "unused-variable",
+        "A variable of a function or a block is defined but never used, which often means the
+code uses the wrong name, or that the variable can be removed.
+
+    f() {
+        total = 1
+        2
+    }
+
+Use the variable, or remove it. Names starting with `_` are never reported. Turn the rule off
+with `hydrogen lint --allow unused-variable`.",
+    ),
+    Explanation::lint(
+        "H0202",
+        "unused-parameter",
+        "A parameter of a function is never used in its body.
+
+    area(width, height) { width * width }
+
+Use the parameter, or remove it from the definition and from the calls. Names starting with `_`
+are never reported.",
+    ),
+    Explanation::lint(
+        "H0203",
+        "shadowed-binding",
+        "A definition hides another definition of the same name, so the code inside it can no
+longer use the outer one.
+
+    count = 0
+    bump(count) { count + 1 }
+
+Give one of the definitions another name.",
+    ),
+    Explanation::lint(
+        "H0204",
+        "unreachable-code",
+        "A statement comes after a `return`, `break`, or `continue` of the same block, so it
+never runs.
+
+    f() {
+        return 1
+        print(\"done\")
+    }
+
+Move the statement before the `return`, or remove it.",
+    ),
+    Explanation::lint(
+        "H0205",
+        "empty-block",
+        "A block has no statements, which usually means code is missing.
+
+    if x > 1 {} else { print(x) }
+
+Write the missing code, or change the condition so the block is not needed:
+`if x <= 1 { print(x) }`.",
+    ),
+    Explanation::lint(
+        "H0206",
+        "constant-condition",
+        "The condition of an `if` or a `while` is always true or always false, so a branch never
+runs, or the loop never ends or never starts.
+
+    if 1 > 2 { print(\"never\") }
+
+Write the condition the code meant, or remove the branch. `while true` is allowed, since it
+is how loops left with `break` are written.",
+    ),
+];
+
+/// Returns the explanation of a code.
+///
+/// # Arguments
+///
+/// * `code` - The code, like `H0101`, in either case.
+///
+/// # Returns
+///
+/// * `Option<&Explanation>` - The explanation, or None if no diagnostic has the code.
+///
+/// # Examples
+/// ```
+/// use hydrogen::hash::codes::explain;
+///
+/// assert_eq!(explain("h0101").unwrap().title, "type mismatch");
+/// assert!(explain("H9999").is_none());
+/// ```
+pub fn explain(code: &str) -> Option<&'static Explanation> {
+    EXPLANATIONS
+        .iter()
+        .find(|explanation| explanation.code.eq_ignore_ascii_case(code))
+}
+
+/// Returns the code of a rule of the linter, or None for rules added by applications.
+pub fn of_rule(rule: &str) -> Option<&'static str> {
+    EXPLANATIONS
+        .iter()
+        .find(|explanation| explanation.rule == Some(rule))
+        .map(|explanation| explanation.code)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hash::lint::Linter;
+
+    #[test]
+    fn test_codes() {
+        let mut codes: Vec<_> = EXPLANATIONS.iter().map(|e| e.code).collect();
+        codes.dedup();
+        assert_eq!(codes.len(), EXPLANATIONS.len());
+        assert!(codes.windows(2).all(|pair| pair[0] < pair[1]));
+
+        // Every rule of the language has a code
+        for (rule, _) in Linter::new().rules() {
+            let code = of_rule(rule.id()).unwrap();
+            assert_eq!(explain(code).unwrap().title, rule.id());
+        }
+        assert_eq!(of_rule("string-literal"), None);
+    }
+}
@@ -0,0 +1,873 @@
+use std::rc::Rc;
+
+use super::{
+    ast::{
+        Block, Expr, InterfaceDefinition, Item, Literal, Parameter, Pattern, Stmt,
+        StructDefinition, TypeExpr,
+    },
+    evaluator::{number, RuntimeError},
+    value::Value,
+};
+
+/// Enum representing the instructions of the virtual machine
+///
+/// Instructions work on a stack of values. Operands are indexes into the pools of the chunk
+/// holding the instruction, or positions in its code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// Pushes a constant: (constant)
+    Constant(usize),
+    /// Pops a value
+    Pop,
+    /// Pops several values: (count)
+    Drop(usize),
+    /// Pushes copies of the top values, in the same order: (count)
+    Copy(usize),
+    /// Pushes the value of a variable, or else a function used as a value: (name)
+    Get(usize),
+    /// Pops a value into a new variable of the current scope: (name)
+    Define(usize),
+    /// Pops a value into the closest variable the scope can assign, defining it if there is
+    /// none: (name)
+    Assign(usize),
+    /// Declares a name `global` in the current scope: (name)
+    Global(usize),
+    /// Declares a name `nonlocal` in the current scope: (name)
+    Nonlocal(usize),
+    /// Fails unless a variable the scope can assign is defined, before a compound assignment
+    /// updates it: (name)
+    Require(usize),
+    /// Pops a value and pushes the values a pattern takes it apart into: (pattern)
+    Unpack(usize),
+    /// Defines a function in the current scope: (function)
+    Function(usize),
+    /// Pushes a function without a name: (function)
+    Closure(usize),
+    /// Defines a struct in the current scope: (struct)
+    Struct(usize),
+    /// Defines an interface in the current scope: (interface)
+    Interface(usize),
+    /// Pops the functions of an `impl` block and implements an interface for a struct with them:
+    /// (interface name, struct name, functions)
+    Implement(usize, usize, usize),
+    /// Checks that the top value has a type
+    CheckType(TypeExpr),
+    /// Converts the top value to a type
+    Cast(TypeExpr),
+    /// Applies an operator to the top value: (operator)
+    Unary(usize),
+    /// Applies an operator to the two top values: (operator)
+    Binary(usize),
+    /// Checks that the top value is a boolean
+    Test,
+    /// Continues at a position: (target)
+    Jump(usize),
+    /// Pops a condition and continues at a position if it is false: (target)
+    JumpIfFalse(usize),
+    /// Pops values into an array: (count)
+    Array(usize),
+    /// Pops the end and start of a range and pushes the range: (inclusive)
+    Range(bool),
+    /// Pops an index or key and a collection and pushes the element
+    Index,
+    /// Pops a value, an index or key, and a collection, and gives the element the value
+    Store,
+    /// Pops a value and pushes the state of a loop over its elements
+    Iterate,
+    /// Pushes the next element of a loop, or continues at a position if there is none: (target)
+    Next(usize),
+    /// Enters a new scope nested inside the current one
+    PushScope,
+    /// Leaves scopes: (count)
+    PopScope(usize),
+    /// Catches the errors raised until the matching `EndTry` at a position: (target)
+    Try(usize),
+    /// Stops catching errors: (count)
+    EndTry(usize),
+    /// Pops arguments and calls a function with them: (name, arguments)
+    Call(usize, usize),
+    /// Calls a function in place of the current one: (name, arguments)
+    TailCall(usize, usize),
+    /// Pops arguments and a value, and calls the function of its struct with the value followed
+    /// by the arguments: (name, arguments)
+    Method(usize, usize),
+    /// Calls the function of the struct of a value in place of the current one: (name,
+    /// arguments)
+    TailMethod(usize, usize),
+    /// Leaves the current function with the top value
+    Return,
+}
+
+/// Compiled code, with the values and names it refers to
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Chunk {
+    /// The instructions, in order.
+    pub code: Vec<Op>,
+    /// The values pushed by `Constant` instructions.
+    pub constants: Vec<Value>,
+    /// The names of variables, functions, and operators.
+    pub names: Vec<String>,
+    /// The functions defined by the code.
+    pub functions: Vec<Rc<Prototype>>,
+    /// The patterns of `Unpack` instructions.
+    pub patterns: Vec<Pattern>,
+    /// The structs defined by the code.
+    pub structs: Vec<StructDefinition>,
+    /// The interfaces defined by the code.
+    pub interfaces: Vec<InterfaceDefinition>,
+    /// The position of the first instruction of each statement whose row is known, and that
+    /// row, in the order of the positions.
+    pub rows: Vec<(usize, usize)>,
+}
+
+impl Chunk {
+    /// Returns the row of the statement an instruction belongs to, if it is known.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position of the instruction.
+    pub fn row(&self, position: usize) -> Option<usize> {
+        let after = self.rows.partition_point(|(start, _)| *start <= position);
+        after.checked_sub(1).map(|i| self.rows[i].1)
+    }
+}
+
+/// A function as it is compiled, before it captures the scope it is defined in
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prototype {
+    /// The name of the function.
+    pub name: String,
+    /// The parameters, in order.
+    pub parameters: Vec<Parameter>,
+    /// The type the function returns, if it is declared.
+    pub ret: Option<TypeExpr>,
+    /// The block of the function, kept for the tree-walking evaluator.
+    pub body: Block,
+    /// The compiled block of the function.
+    pub code: Rc<Chunk>,
+    /// The doc comments of the definition of the function, if it has any.
+    pub doc: Option<Rc<str>>,
+}
+
+/// What `break` and `continue` need to leave a loop.
+#[derive(Debug)]
+struct Loop {
+    /// The position `continue` jumps to.
+    start: usize,
+    /// The `break` jumps to patch with the end of the loop.
+    breaks: Vec<usize>,
+    /// The number of values on the stack around the body.
+    height: usize,
+    /// The number of scopes entered around the body.
+    scopes: usize,
+    /// The number of `try` blocks entered around the body.
+    handlers: usize,
+}
+
+/// Compiler lowers the abstract syntax tree of a program into bytecode.
+///
+/// Every statement and expression leaves exactly one value on the stack. The compiler keeps
+/// track of how many values are on the stack, and of the scopes and `try` blocks it is inside,
+/// so that `break`, `continue`, and `return` can leave them.
+#[derive(Debug, Default)]
+struct Compiler {
+    chunk: Chunk,
+    height: usize,
+    scopes: usize,
+    handlers: usize,
+    loops: Vec<Loop>,
+}
+
+/// Compiles the statements of a program.
+///
+/// # Arguments
+///
+/// * `tree` - The statements to be compiled.
+///
+/// # Returns
+///
+/// * `Result<Chunk, RuntimeError>` - The compiled program, which returns the value of its last
+///   statement, or the error for a number literal that does not fit in a number.
+///
+/// # Examples
+/// ```
+/// use hydrogen::hash::{compile::{compile, Op}, parser::Parser};
+///
+/// let tree = Parser::new("1 + 2").parse_program().unwrap();
+/// let chunk = compile(&tree).unwrap();
+///
+/// assert_eq!(
+///     chunk.code,
+///     vec![Op::Constant(0), Op::Constant(1), Op::Binary(0), Op::Return]
+/// );
+/// ```
+pub fn compile(tree: &[Stmt]) -> Result<Chunk, RuntimeError> {
+    compile_at(tree, &[])
+}
+
+/// Compiles the statements of a program written on the given rows of the source code, which
+/// the chunk keeps for stack traces, see [`compile`].
+///
+/// # Arguments
+///
+/// * `tree` - The statements to be compiled.
+/// * `rows` - The row each statement starts on.
+///
+/// # Returns
+///
+/// * `Result<Chunk, RuntimeError>` - The compiled program.
+///
+/// # Examples
+/// ```
+/// use hydrogen::hash::{compile::compile_at, parser::Parser};
+///
+/// let tree = Parser::new("x = 1\nx + 2").parse_program().unwrap();
+/// let chunk = compile_at(&tree, &[1, 2]).unwrap();
+///
+/// assert_eq!(chunk.row(0), Some(1));
+/// assert_eq!(chunk.row(chunk.code.len() - 1), Some(2));
+/// ```
+pub fn compile_at(tree: &[Stmt], rows: &[usize]) -> Result<Chunk, RuntimeError> {
+    let mut compiler = Compiler::default();
+    compiler.statements(tree, rows, false)?;
+    compiler.emit(Op::Return);
+    Ok(compiler.chunk)
+}
+
+/// Compiles the block of a function, which returns the value of its last statement.
+///
+/// # Arguments
+///
+/// * `body` - The block to be compiled.
+///
+/// # Returns
+///
+/// * `Result<Chunk, RuntimeError>` - The compiled block.
+pub fn compile_body(body: &Block) -> Result<Chunk, RuntimeError> {
+    let mut compiler = Compiler::default();
+    compiler.statements(&body.statements, &body.rows, true)?;
+    compiler.emit(Op::Return);
+    Ok(compiler.chunk)
+}
+
+impl Compiler {
+    /// Appends an instruction, keeping track of the values on the stack.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The position of the instruction.
+    fn emit(&mut self, op: Op) -> usize {
+        let (popped, pushed) = match op {
+            Op::Constant(_) | Op::Get(_) | Op::Closure(_) => (0, 1),
+            Op::Pop | Op::Define(_) | Op::Assign(_) | Op::JumpIfFalse(_) => (1, 0),
+            Op::Drop(count) => (count, 0),
+            Op::Copy(count) => (0, count),
+            Op::Binary(_) | Op::Range(_) | Op::Index => (2, 1),
+            Op::Store => (3, 0),
+            Op::Array(count) => (count, 1),
+            Op::Unpack(pattern) => (1, self.chunk.patterns[pattern].names().len()),
+            Op::Iterate => (1, 3),
+            Op::Next(_) => (0, 1),
+            Op::Call(_, count) | Op::TailCall(_, count) => (count, 1),
+            Op::Method(_, count) | Op::TailMethod(_, count) => (count + 1, 1),
+            Op::Implement(_, _, count) => (count, 0),
+            Op::Return => (1, 0),
+            Op::Function(_)
+            | Op::Struct(_)
+            | Op::Interface(_)
+            | Op::CheckType(_)
+            | Op::Cast(_)
+            | Op::Unary(_)
+            | Op::Test
+            | Op::Require(_)
+            | Op::Global(_)
+            | Op::Nonlocal(_)
+            | Op::Jump(_)
+            | Op::PushScope
+            | Op::PopScope(_)
+            | Op::Try(_)
+            | Op::EndTry(_) => (0, 0),
+        };
+        self.height = self.height - popped + pushed;
+
+        self.chunk.code.push(op);
+        self.chunk.code.len() - 1
+    }
+
+    /// Returns the position of the next instruction.
+    fn here(&self) -> usize {
+        self.chunk.code.len()
+    }
+
+    /// Makes a jump emitted earlier continue at the next instruction.
+    fn patch(&mut self, jump: usize) {
+        let target = self.here();
+        match &mut self.chunk.code[jump] {
+            Op::Jump(to) | Op::JumpIfFalse(to) | Op::Next(to) | Op::Try(to) => *to = target,
+            op => unreachable!("{:?} is not a jump", op),
+        }
+    }
+
+    /// Adds a value to the constants unless it is already there, returning its index.
+    fn constant(&mut self, value: Value) -> usize {
+        match self.chunk.constants.iter().position(|c| *c == value) {
+            Some(index) => index,
+            None => {
+                self.chunk.constants.push(value);
+                self.chunk.constants.len() - 1
+            }
+        }
+    }
+
+    /// Pushes none.
+    fn none(&mut self) {
+        let none = self.constant(Value::None);
+        self.emit(Op::Constant(none));
+    }
+
+    /// Adds a name to the names, returning its index.
+    fn name(&mut self, name: &str) -> usize {
+        match self.chunk.names.iter().position(|n| n == name) {
+            Some(index) => index,
+            None => {
+                self.chunk.names.push(name.to_string());
+                self.chunk.names.len() - 1
+            }
+        }
+    }
+
+    /// Compiles a function, returning its index.
+    fn function(
+        &mut self,
+        name: &str,
+        parameters: &[Parameter],
+        ret: Option<TypeExpr>,
+        body: &Block,
+        doc: Option<&str>,
+    ) -> Result<usize, RuntimeError> {
+        self.chunk.functions.push(Rc::new(Prototype {
+            name: name.to_string(),
+            parameters: parameters.to_vec(),
+            ret,
+            body: body.clone(),
+            code: Rc::new(compile_body(body)?),
+            doc: doc.map(Rc::from),
+        }));
+        Ok(self.chunk.functions.len() - 1)
+    }
+
+    /// Compiles statements, leaving the value of the last one.
+    ///
+    /// When `tail` is true, the value of the last statement is returned by the function.
+    fn statements(
+        &mut self,
+        statements: &[Stmt],
+        rows: &[usize],
+        tail: bool,
+    ) -> Result<(), RuntimeError> {
+        if statements.is_empty() {
+            self.none();
+        }
+
+        for (i, statement) in statements.iter().enumerate() {
+            if i > 0 {
+                self.emit(Op::Pop);
+            }
+            let row = rows.get(i).copied();
+            self.row(row);
+            self.statement(statement, tail && i + 1 == statements.len())?;
+            // The rest of the statement follows the blocks nested in it
+            self.row(row);
+        }
+        Ok(())
+    }
+
+    /// Marks the instructions emitted next as part of a statement on a row, if it is known.
+    fn row(&mut self, row: Option<usize>) {
+        let Some(row) = row else {
+            return;
+        };
+        let here = self.here();
+        match self.chunk.rows.last_mut() {
+            Some(last) if last.1 == row => {}
+            Some(last) if last.0 == here => last.1 = row,
+            _ => self.chunk.rows.push((here, row)),
+        }
+    }
+
+    /// Compiles a block in a scope of its own.
+    fn block(&mut self, block: &Block, tail: bool) -> Result<(), RuntimeError> {
+        self.emit(Op::PushScope);
+        self.scopes += 1;
+        self.statements(&block.statements, &block.rows, tail)?;
+        self.scopes -= 1;
+        self.emit(Op::PopScope(1));
+        Ok(())
+    }
+
+    fn statement(&mut self, statement: &Stmt, tail: bool) -> Result<(), RuntimeError> {
+        match statement {
+            Stmt::Item(Item::Function(definition)) => {
+                let function = self.function(
+                    &definition.name,
+                    &definition.parameters,
+                    definition.ret,
+                    &definition.body,
+                    definition.doc.as_deref(),
+                )?;
+                self.emit(Op::Function(function));
+            }
+
+            Stmt::Item(Item::Struct(definition)) => {
+                self.chunk.structs.push(definition.clone());
+                self.emit(Op::Struct(self.chunk.structs.len() - 1));
+            }
+
+            Stmt::Item(Item::Interface(definition)) => {
+                self.chunk.interfaces.push(definition.clone());
+                self.emit(Op::Interface(self.chunk.interfaces.len() - 1));
+            }
+
+            // The functions are pushed in order, and the interface is implemented with all of
+            // them at once
+            Stmt::Item(Item::Impl(implementation)) => {
+                let mut count = 0;
+                for definition in implementation.functions() {
+                    let function = self.function(
+                        &definition.name,
+                        &definition.parameters,
+                        definition.ret,
+                        &definition.body,
+                        definition.doc.as_deref(),
+                    )?;
+                    self.emit(Op::Closure(function));
+                    count += 1;
+                }
+                let interface = self.name(&implementation.interface);
+                let ty = self.name(&implementation.ty);
+                self.emit(Op::Implement(interface, ty, count));
+            }
+
+            Stmt::Definition(definition) => {
+                let name = self.name(&definition.name);
+                if definition.compound {
+                    self.emit(Op::Require(name));
+                }
+                self.expression(&definition.value, false)?;
+                match definition.ty {
+                    Some(ty) => {
+                        self.emit(Op::CheckType(ty));
+                        self.emit(Op::Define(name));
+                    }
+                    None if definition.constant => {
+                        self.emit(Op::Define(name));
+                    }
+                    None => {
+                        self.emit(Op::Assign(name));
+                    }
+                }
+            }
+
+            Stmt::Declaration(declaration) => {
+                self.none();
+                let name = self.name(&declaration.name);
+                self.emit(Op::Define(name));
+            }
+
+            // The values are pushed in order, so they are assigned from the last one
+            Stmt::Destructure(destructuring) => {
+                self.expression(&destructuring.value, false)?;
+                self.chunk.patterns.push(destructuring.pattern.clone());
+                self.emit(Op::Unpack(self.chunk.patterns.len() - 1));
+                for name in destructuring.pattern.names().iter().rev() {
+                    let name = self.name(name);
+                    self.emit(Op::Assign(name));
+                }
+            }
+
+            // A compound assignment reads the element with copies of the collection and the
+            // index, so they are only evaluated once
+            Stmt::Store(store) => {
+                self.place(&store.target)?;
+                match &store.value {
+                    Expr::Binary(binary) if store.compound => {
+                        self.emit(Op::Copy(2));
+                        self.emit(Op::Index);
+                        self.expression(&binary.right, false)?;
+                        let op = self.name(&binary.op);
+                        self.emit(Op::Binary(op));
+                    }
+                    value => self.expression(value, false)?,
+                }
+                self.emit(Op::Store);
+            }
+
+            Stmt::Global(names) => {
+                for name in names {
+                    let name = self.name(name);
+                    self.emit(Op::Global(name));
+                }
+            }
+            Stmt::Nonlocal(names) => {
+                for name in names {
+                    let name = self.name(name);
+                    self.emit(Op::Nonlocal(name));
+                }
+            }
+
+            Stmt::Expr(expression) => return self.expression(expression, tail),
+
+            // The call a `return` makes can replace the function, unless its errors are caught
+            Stmt::Return(value) => {
+                let height = self.height;
+                match value {
+                    Some(value) => self.expression(value, self.handlers == 0)?,
+                    None => {
+                        self.none();
+                    }
+                }
+                self.emit(Op::Return);
+                self.height = height;
+            }
+
+            Stmt::Break | Stmt::Continue => {
+                let Some(looping) = self.loops.last() else {
+                    unreachable!("the parser only allows `break` and `continue` in loops");
+                };
+                let (height, scopes, handlers, start) = (
+                    looping.height,
+                    looping.scopes,
+                    looping.handlers,
+                    looping.start,
+                );
+                let current = self.height;
+
+                if current > height {
+                    self.emit(Op::Drop(current - height));
+                }
+                if self.handlers > handlers {
+                    self.emit(Op::EndTry(self.handlers - handlers));
+                }
+                if self.scopes > scopes {
+                    self.emit(Op::PopScope(self.scopes - scopes));
+                }
+                match statement {
+                    Stmt::Break => {
+                        let jump = self.emit(Op::Jump(0));
+                        if let Some(looping) = self.loops.last_mut() {
+                            looping.breaks.push(jump);
+                        }
+                    }
+                    _ => {
+                        self.emit(Op::Jump(start));
+                    }
+                }
+                self.height = current;
+            }
+        }
+
+        // Statements that are not expressions leave none
+        if !matches!(statement, Stmt::Expr(_)) {
+            self.none();
+        }
+        Ok(())
+    }
+
+    /// Compiles an expression.
+    ///
+    /// When `tail` is true, the value of the expression is returned by the function, so a call
+    /// computing it can replace the function.
+    fn expression(&mut self, expression: &Expr, tail: bool) -> Result<(), RuntimeError> {
+        match expression {
+            Expr::Literal(literal) => {
+                let value = match literal {
+                    Literal::String(value) => Value::from(value.as_str()),
+                    Literal::Boolean(value) => Value::Boolean(*value),
+                    Literal::Number(value) => number(value)?,
+                };
+                let constant = self.constant(value);
+                self.emit(Op::Constant(constant));
+            }
+
+            Expr::Identifier(name) => {
+                let name = self.name(name);
+                self.emit(Op::Get(name));
+            }
+
+            Expr::Group(elements) if elements.len() == 1 => {
+                self.expression(&elements[0], tail)?;
+            }
+
+            Expr::Group(elements) | Expr::Array(elements) => {
+                for element in elements {
+                    self.expression(element, false)?;
+                }
+                self.emit(Op::Array(elements.len()));
+            }
+
+            Expr::Unary(unary) => {
+                self.expression(&unary.expr, false)?;
+                let op = self.name(&unary.op);
+                self.emit(Op::Unary(op));
+            }
+
+            // The right operand of `&&` and `||` is only evaluated if the left one does not
+            // already decide the result
+            Expr::Binary(binary) if matches!(binary.op.as_str(), "&&" | "||") => {
+                self.expression(&binary.left, false)?;
+                let decided = self.emit(Op::JumpIfFalse(0));
+                let and = binary.op == "&&";
+
+                if and {
+                    self.expression(&binary.right, false)?;
+                    self.emit(Op::Test);
+                } else {
+                    let result = self.constant(Value::Boolean(true));
+                    self.emit(Op::Constant(result));
+                }
+                let end = self.emit(Op::Jump(0));
+                self.height -= 1;
+
+                self.patch(decided);
+                if and {
+                    let result = self.constant(Value::Boolean(false));
+                    self.emit(Op::Constant(result));
+                } else {
+                    self.expression(&binary.right, false)?;
+                    self.emit(Op::Test);
+                }
+                self.patch(end);
+            }
+
+            Expr::Binary(binary) => {
+                self.expression(&binary.left, false)?;
+                self.expression(&binary.right, false)?;
+                let op = self.name(&binary.op);
+                self.emit(Op::Binary(op));
+            }
+
+            Expr::Call(call) => {
+                for argument in &call.arguments {
+                    self.expression(argument, false)?;
+                }
+                let name = self.name(&call.name);
+                match tail {
+                    true => self.emit(Op::TailCall(name, call.arguments.len())),
+                    false => self.emit(Op::Call(name, call.arguments.len())),
+                };
+            }
+
+            Expr::MethodCall(call) => {
+                self.expression(&call.target, false)?;
+                for argument in &call.arguments {
+                    self.expression(argument, false)?;
+                }
+                let name = self.name(&call.name);
+                match tail {
+                    true => self.emit(Op::TailMethod(name, call.arguments.len())),
+                    false => self.emit(Op::Method(name, call.arguments.len())),
+                };
+            }
+
+            Expr::Block(block) => self.block(block, tail)?,
+
+            Expr::If(statement) => {
+                self.expression(&statement.condition, false)?;
+                let otherwise = self.emit(Op::JumpIfFalse(0));
+                self.block(&statement.then, tail)?;
+                let end = self.emit(Op::Jump(0));
+                self.height -= 1;
+
+                self.patch(otherwise);
+                match &statement.otherwise {
+                    Some(expression) => self.expression(expression, tail)?,
+                    None => {
+                        self.none();
+                    }
+                }
+                self.patch(end);
+            }
+
+            Expr::While(statement) => {
+                let start = self.here();
+                self.expression(&statement.condition, false)?;
+                let exit = self.emit(Op::JumpIfFalse(0));
+
+                self.enter_loop(start);
+                self.block(&statement.body, false)?;
+                self.emit(Op::Pop);
+                self.emit(Op::Jump(start));
+                self.leave_loop(exit, 0);
+            }
+
+            Expr::For(statement) => {
+                self.expression(&statement.iterable, false)?;
+                self.emit(Op::Iterate);
+
+                let start = self.here();
+                let exit = self.emit(Op::Next(0));
+                self.height -= 1;
+                self.enter_loop(start);
+                self.height += 1;
+
+                // The variable lives in the scope of the body
+                self.emit(Op::PushScope);
+                self.scopes += 1;
+                let variable = self.name(&statement.variable);
+                self.emit(Op::Define(variable));
+                self.statements(&statement.body.statements, &statement.body.rows, false)?;
+                self.scopes -= 1;
+                self.emit(Op::PopScope(1));
+                self.emit(Op::Pop);
+                self.emit(Op::Jump(start));
+
+                self.leave_loop(exit, 3);
+            }
+
+            Expr::Range(range) => {
+                self.expression(&range.start, false)?;
+                self.expression(&range.end, false)?;
+                self.emit(Op::Range(range.inclusive));
+            }
+
+            Expr::Index(_) | Expr::Member(_) => {
+                self.place(expression)?;
+                self.emit(Op::Index);
+            }
+
+            Expr::Cast(cast) => {
+                self.expression(&cast.expr, false)?;
+                self.emit(Op::Cast(cast.ty));
+            }
+
+            // Only errors are caught, a `return`, `break`, or `continue` leaves the body as usual
+            Expr::Try(statement) => {
+                let handler = self.emit(Op::Try(0));
+                self.handlers += 1;
+                self.block(&statement.body, false)?;
+                self.handlers -= 1;
+                self.emit(Op::EndTry(1));
+                let end = self.emit(Op::Jump(0));
+
+                // The message of the caught error is on the stack
+                self.patch(handler);
+                self.emit(Op::PushScope);
+                self.scopes += 1;
+                let variable = self.name(&statement.variable);
+                self.emit(Op::Define(variable));
+                self.statements(
+                    &statement.handler.statements,
+                    &statement.handler.rows,
+                    false,
+                )?;
+                self.scopes -= 1;
+                self.emit(Op::PopScope(1));
+                self.patch(end);
+            }
+
+            Expr::Lambda(lambda) => {
+                let function =
+                    self.function("lambda", &lambda.parameters, lambda.ret, &lambda.body, None)?;
+                self.emit(Op::Closure(function));
+            }
+        }
+        Ok(())
+    }
+
+    /// Pushes the collection and the index or key of an element, the name of a member being
+    /// its key.
+    fn place(&mut self, expression: &Expr) -> Result<(), RuntimeError> {
+        match expression {
+            Expr::Index(index) => {
+                self.expression(&index.target, false)?;
+                self.expression(&index.index, false)?;
+            }
+            Expr::Member(member) => {
+                self.expression(&member.target, false)?;
+                let key = self.constant(Value::from(member.name.as_str()));
+                self.emit(Op::Constant(key));
+            }
+            _ => unreachable!("only elements and keys are places"),
+        }
+        Ok(())
+    }
+
+    /// Starts compiling the body of a loop, which `continue` jumps back to the start of.
+    fn enter_loop(&mut self, start: usize) {
+        self.loops.push(Loop {
+            start,
+            breaks: Vec::new(),
+            height: self.height,
+            scopes: self.scopes,
+            handlers: self.handlers,
+        });
+    }
+
+    /// Finishes compiling a loop, whose value is none.
+    ///
+    /// # Arguments
+    ///
+    /// * `exit` - The jump taken when the loop ends on its own.
+    /// * `state` - The number of values the loop keeps on the stack while it runs.
+    fn leave_loop(&mut self, exit: usize, state: usize) {
+        let Some(looping) = self.loops.pop() else {
+            unreachable!("every loop left was entered");
+        };
+
+        self.patch(exit);
+        for jump in looping.breaks {
+            self.patch(jump);
+        }
+        self.height = looping.height;
+
+        if state > 0 {
+            self.emit(Op::Drop(state));
+        }
+        self.none();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hash::parser::Parser;
+
+    #[test]
+    fn test_compile() {
+        let tree = Parser::new("x = 1\nwhile x < 3 { x = x + 1 }")
+            .parse_program()
+            .unwrap();
+        let chunk = compile(&tree).unwrap();
+
+        assert_eq!(chunk.names, vec!["x", "<", "+"]);
+        assert_eq!(
+            chunk.constants,
+            vec![Value::Integer(1), Value::None, Value::Integer(3)]
+        );
+        assert_eq!(
+            chunk.code,
+            vec![
+                Op::Constant(0),
+                Op::Assign(0),
+                Op::Constant(1),
+                Op::Pop,
+                // while x < 3
+                Op::Get(0),
+                Op::Constant(2),
+                Op::Binary(1),
+                Op::JumpIfFalse(17),
+                Op::PushScope,
+                Op::Get(0),
+                Op::Constant(0),
+                Op::Binary(2),
+                Op::Assign(0),
+                Op::Constant(1),
+                Op::PopScope(1),
+                Op::Pop,
+                Op::Jump(4),
+                Op::Constant(1),
+                Op::Return,
+            ]
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+
+use super::{
+    ast::{Block, Stmt},
+    fold::{walk_block, Folder},
+};
+
+/// Coverage counting how many times the statements on each line of a program ran.
+///
+/// The statements of a program are recorded with [`Coverage::add`] before it runs, so lines
+/// whose statements never run show up in the report.
+///
+/// # Examples
+/// ```
+/// use hydrogen::hash::{coverage::Coverage, parser::Parser};
+///
+/// let source = "x = 1\nif x > 1 {\n    x = 2\n}";
+/// let tree = Parser::new(source).parse_program().unwrap();
+///
+/// let mut coverage = Coverage::default();
+/// coverage.add(&tree[0], 1);
+/// coverage.add(&tree[1], 2);
+/// coverage.hit(1);
+/// coverage.hit(2);
+///
+/// assert_eq!(coverage.covered(), (2, 3));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    rows: BTreeMap<usize, u64>,
+}
+
+/// Pass collecting the rows of the statements inside blocks, leaving the tree as it is.
+struct Rows<'a>(&'a mut BTreeMap<usize, u64>);
+
+impl Folder for Rows<'_> {
+    fn fold_block(&mut self, block: Block) -> Block {
+        for row in &block.rows {
+            self.0.entry(*row).or_default();
+        }
+        walk_block(self, block)
+    }
+}
+
+impl Coverage {
+    /// Records a statement of a program, and the statements nested in it, as not run yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `statement` - The statement.
+    /// * `row` - The row the statement starts on.
+    pub fn add(&mut self, statement: &Stmt, row: usize) {
+        self.rows.entry(row).or_default();
+        Rows(&mut self.rows).fold_stmt(statement.clone());
+    }
+
+    /// Counts a statement starting on the given row that ran.
+    pub fn hit(&mut self, row: usize) {
+        *self.rows.entry(row).or_default() += 1;
+    }
+
+    /// Returns the number of lines whose statements ran, and the number of lines holding
+    /// statements.
+    pub fn covered(&self) -> (usize, usize) {
+        let hit = self.rows.values().filter(|count| **count > 0).count();
+        (hit, self.rows.len())
+    }
+
+    /// Writes the source code of the program with the number of times the statements of each
+    /// line ran, followed by a summary.
+    ///
+    /// Lines holding statements that never ran are marked with `#####`, and lines without
+    /// statements with `-`.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source code of the program.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The report, one line per line of the source code.
+    pub fn report(&self, source: &str) -> String {
+        let mut report = String::new();
+        for (i, line) in source.lines().enumerate() {
+            let count = match self.rows.get(&(i + 1)) {
+                Some(0) => "#####".to_string(),
+                Some(count) => count.to_string(),
+                None => "-".to_string(),
+            };
+            report.push_str(&format!("{:>8}: {:>4}: {}\n", count, i + 1, line));
+        }
+
+        let (hit, total) = self.covered();
+        let percent = if total == 0 {
+            100.0
+        } else {
+            hit as f64 * 100.0 / total as f64
+        };
+        report.push_str(&format!(
+            "{} of {} lines with statements ran ({:.1}%)\n",
+            hit, total, percent
+        ));
+        report
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Engine;
+
+    #[test]
+    fn test_coverage() {
+        let source = "double(n) {\n    n * 2\n}\nx = double(2)\n\nif x > 5 {\n    x = 0\n}";
+        let mut engine = Engine::new();
+        engine.set_coverage(true);
+        engine.eval_str(source).unwrap();
+
+        let coverage = engine.coverage().unwrap();
+        assert_eq!(coverage.covered(), (4, 5));
+        assert_eq!(
+            coverage.report(source),
+            "       1:    1: double(n) {\n       \
+                    1:    2:     n * 2\n       \
+                    -:    3: }\n       \
+                    1:    4: x = double(2)\n       \
+                    -:    5: \n       \
+                    1:    6: if x > 5 {\n   \
+                #####:    7:     x = 0\n       \
+                    -:    8: }\n\
+             4 of 5 lines with statements ran (80.0%)\n"
+        );
+    }
+}
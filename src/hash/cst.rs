@@ -0,0 +1,523 @@
+use std::{fmt, ops::Range, rc::Rc};
+
+use super::{
+    ast::{Errors, Stmt, Tree},
+    lexer::Lexer,
+    parser::Parser,
+    print::stmt_source,
+    tokens::Token,
+};
+
+/// Enum representing the kinds of the nodes and tokens of a concrete syntax tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    /// Spaces, tabs, and newlines
+    Whitespace,
+    /// A line or block comment
+    Comment,
+    /// A name
+    Identifier,
+    /// A keyword, including `in` and `as`
+    Keyword,
+    /// A builtin type name
+    Type,
+    /// A string literal, with its quotes
+    String,
+    /// A number literal
+    Number,
+    /// A boolean literal
+    Boolean,
+    /// An operator or a delimiter
+    Punctuation,
+    /// Text that is not a valid token
+    Unknown,
+    /// The whole source code
+    Program,
+    /// A statement that parses
+    Statement,
+    /// A statement that does not parse
+    Error,
+    /// Tokens between `(` and `)`, with the parentheses
+    Parenthesized,
+    /// Tokens between `{` and `}`, with the braces
+    Braced,
+    /// Tokens between `[` and `]`, with the brackets
+    Bracketed,
+}
+
+impl SyntaxKind {
+    /// Returns whether the kind is whitespace or a comment, which the parser skips.
+    pub fn is_trivia(self) -> bool {
+        matches!(self, SyntaxKind::Whitespace | SyntaxKind::Comment)
+    }
+
+    /// Returns the kind of the node grouping the tokens a delimiter opens, if it opens any.
+    fn opened_by(text: &str) -> Option<Self> {
+        match text {
+            "(" => Some(SyntaxKind::Parenthesized),
+            "{" => Some(SyntaxKind::Braced),
+            "[" => Some(SyntaxKind::Bracketed),
+            _ => None,
+        }
+    }
+
+    /// Returns the kind of the node grouping the tokens a delimiter closes, if it closes any.
+    fn closed_by(text: &str) -> Option<Self> {
+        match text {
+            ")" => Some(SyntaxKind::Parenthesized),
+            "}" => Some(SyntaxKind::Braced),
+            "]" => Some(SyntaxKind::Bracketed),
+            _ => None,
+        }
+    }
+}
+
+impl From<&Token<'_>> for SyntaxKind {
+    fn from(token: &Token<'_>) -> Self {
+        match token {
+            Token::Identifier(_, _) => SyntaxKind::Identifier,
+            Token::Keyword(_, _) | Token::In(_) | Token::As(_) => SyntaxKind::Keyword,
+            Token::Type(_, _) => SyntaxKind::Type,
+            Token::String(_, _) => SyntaxKind::String,
+            Token::Number(_, _) => SyntaxKind::Number,
+            Token::Boolean(_, _) => SyntaxKind::Boolean,
+            Token::Comment(_, _) => SyntaxKind::Comment,
+            Token::Unknown(_, _)
+            | Token::UnterminatedString(_, _)
+            | Token::UnterminatedComment(_) => SyntaxKind::Unknown,
+            _ => SyntaxKind::Punctuation,
+        }
+    }
+}
+
+/// A token of a concrete syntax tree, with its exact text
+///
+/// Green tokens do not know where they are, so equal tokens can be shared.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreenToken {
+    /// The kind of the token.
+    pub kind: SyntaxKind,
+    /// The text of the token in the source code.
+    pub text: String,
+}
+
+/// A node of a concrete syntax tree, made of nodes and tokens
+///
+/// Green nodes do not know where they are, so the nodes of unchanged source code can be reused
+/// in the tree of an edited one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreenNode {
+    /// The kind of the node.
+    pub kind: SyntaxKind,
+    /// The number of bytes of source code the node covers.
+    pub len: usize,
+    /// The nodes and tokens of the node, in order.
+    pub children: Vec<GreenElement>,
+}
+
+/// Enum representing the children of a green node
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GreenElement {
+    /// A node: (node)
+    Node(Rc<GreenNode>),
+    /// A token: (token)
+    Token(Rc<GreenToken>),
+}
+
+impl GreenElement {
+    /// Returns the number of bytes of source code the element covers.
+    pub fn len(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.len,
+            GreenElement::Token(token) => token.text.len(),
+        }
+    }
+
+    /// Returns whether the element covers no source code.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl GreenNode {
+    /// Creates a new GreenNode covering its children.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The kind of the node.
+    /// * `children` - The nodes and tokens of the node, in order.
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> Self {
+        let len = children.iter().map(GreenElement::len).sum();
+        Self {
+            kind,
+            len,
+            children,
+        }
+    }
+}
+
+/// A node of a concrete syntax tree together with the byte offset where it starts
+///
+/// Syntax nodes are cheap to clone, and are made on the fly while the tree is walked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxNode {
+    green: Rc<GreenNode>,
+    offset: usize,
+}
+
+/// A token of a concrete syntax tree together with the byte offset where it starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxToken {
+    green: Rc<GreenToken>,
+    offset: usize,
+}
+
+/// Enum representing the children of a syntax node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxElement {
+    /// A node: (node)
+    Node(SyntaxNode),
+    /// A token: (token)
+    Token(SyntaxToken),
+}
+
+impl SyntaxToken {
+    /// Returns the kind of the token.
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind
+    }
+
+    /// Returns the text of the token.
+    pub fn text(&self) -> &str {
+        &self.green.text
+    }
+
+    /// Returns the bytes of the source code the token covers.
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.green.text.len()
+    }
+}
+
+impl SyntaxNode {
+    /// Creates the root of a tree from its green node, starting at the first byte.
+    pub fn new_root(green: Rc<GreenNode>) -> Self {
+        Self { green, offset: 0 }
+    }
+
+    /// Returns the kind of the node.
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind
+    }
+
+    /// Returns the green node, which can be reused by another tree.
+    pub fn green(&self) -> &Rc<GreenNode> {
+        &self.green
+    }
+
+    /// Returns the bytes of the source code the node covers.
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.green.len
+    }
+
+    /// Returns the nodes and tokens of the node, in order.
+    pub fn children(&self) -> Vec<SyntaxElement> {
+        let mut offset = self.offset;
+        let mut children = Vec::new();
+        for child in &self.green.children {
+            children.push(match child {
+                GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode {
+                    green: Rc::clone(green),
+                    offset,
+                }),
+                GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                    green: Rc::clone(green),
+                    offset,
+                }),
+            });
+            offset += child.len();
+        }
+        children
+    }
+
+    /// Returns the nodes of the node, leaving out its tokens.
+    pub fn nodes(&self) -> Vec<SyntaxNode> {
+        self.children()
+            .into_iter()
+            .filter_map(|child| match child {
+                SyntaxElement::Node(node) => Some(node),
+                SyntaxElement::Token(_) => None,
+            })
+            .collect()
+    }
+
+    /// Returns every token inside the node, in the order of the source code.
+    pub fn tokens(&self) -> Vec<SyntaxToken> {
+        let mut tokens = Vec::new();
+        for child in self.children() {
+            match child {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+
+    /// Returns the token covering a byte offset, if the node covers it.
+    pub fn token_at(&self, offset: usize) -> Option<SyntaxToken> {
+        self.tokens()
+            .into_iter()
+            .find(|token| token.range().contains(&offset))
+    }
+
+    /// Returns the exact source code the node covers.
+    pub fn text(&self) -> String {
+        self.tokens().iter().map(SyntaxToken::text).collect()
+    }
+
+    /// Converts a statement node to the abstract syntax tree of its statement.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Result<Stmt, Errors>>` - The statement, the errors of a node that does not
+    ///   parse, or None if the node is not a statement.
+    pub fn to_stmt(&self) -> Option<Result<Stmt, Errors>> {
+        match self.kind() {
+            SyntaxKind::Statement | SyntaxKind::Error => {
+                let tree = Parser::new(&self.text()).parse_program();
+                Some(tree.map(|mut tree| tree.remove(0)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Writes the exact source code the node covers.
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.tokens()
+            .iter()
+            .try_for_each(|token| write!(f, "{}", token.text()))
+    }
+}
+
+/// Parses a program into a lossless concrete syntax tree, and into its abstract syntax tree.
+///
+/// Every byte of the source code is in a token of the concrete syntax tree, so its text is the
+/// source code exactly, with its whitespace and comments. The tokens of each statement are in a
+/// `Statement` node, or an `Error` node if it does not parse, with the tokens between
+/// parentheses, braces, and brackets grouped in nodes of their own. Whitespace and comments
+/// between statements belong to the `Program` node.
+///
+/// # Arguments
+///
+/// * `source` - The source code of the program.
+///
+/// # Returns
+///
+/// * `(SyntaxNode, Result<Tree, Errors>)` - The `Program` node, and the abstract syntax tree or
+///   the errors found parsing it.
+///
+/// # Examples
+/// ```
+/// use hydrogen::hash::cst::{parse, SyntaxKind};
+///
+/// let source = "// answer\nx = (1 + 2)  \n";
+/// let (cst, ast) = parse(source);
+///
+/// assert_eq!(cst.to_string(), source);
+/// assert_eq!(cst.nodes()[0].kind(), SyntaxKind::Statement);
+/// assert_eq!(cst.nodes()[0].to_stmt(), Some(Ok(ast.unwrap().remove(0))));
+/// ```
+pub fn parse(source: &str) -> (SyntaxNode, Result<Tree, Errors>) {
+    let mut parser = Parser::new(source);
+    let mut statements = Vec::new();
+    let mut tree = Vec::new();
+    let mut errors: Errors = Vec::new();
+    loop {
+        let start = parser.next_position().offset;
+        let kind = match parser.parse() {
+            Ok(Some(statement)) => {
+                tree.push(statement);
+                SyntaxKind::Statement
+            }
+            Ok(None) => break,
+            Err(error) => {
+                errors.push(error);
+                SyntaxKind::Error
+            }
+        };
+        let end = parser.position().offset;
+        if end > start {
+            statements.push((kind, start..end));
+        }
+    }
+    let ast = if errors.is_empty() {
+        Ok(tree)
+    } else {
+        Err(errors)
+    };
+
+    let tokens = lex(source);
+    let mut children = Vec::new();
+    let mut statements = statements.into_iter().peekable();
+    let mut index = 0;
+    while index < tokens.len() {
+        let (_, range) = &tokens[index];
+        match statements.next_if(|(_, statement)| statement.start <= range.start) {
+            Some((kind, statement)) => {
+                let count = tokens[index..]
+                    .iter()
+                    .take_while(|(_, range)| range.end <= statement.end)
+                    .count()
+                    .max(1);
+                let group = &tokens[index..index + count];
+                children.push(GreenElement::Node(group_tokens(kind, source, group)));
+                index += count;
+            }
+            None => {
+                children.push(green_token(source, &tokens[index]));
+                index += 1;
+            }
+        }
+    }
+
+    let program = GreenNode::new(SyntaxKind::Program, children);
+    (SyntaxNode::new_root(Rc::new(program)), ast)
+}
+
+/// Converts an abstract syntax tree to a concrete syntax tree, with the source code the
+/// statements are printed as.
+///
+/// # Arguments
+///
+/// * `tree` - The statements of the program.
+///
+/// # Returns
+///
+/// * `SyntaxNode` - The `Program` node, one statement per line.
+pub fn from_tree(tree: &Tree) -> SyntaxNode {
+    let source: Vec<_> = tree
+        .iter()
+        .map(|statement| stmt_source(statement, 0))
+        .collect();
+    parse(&source.join("\n")).0
+}
+
+/// Lexes the source code into the kinds and ranges of its tokens, the whitespace between them
+/// included.
+fn lex(source: &str) -> Vec<(SyntaxKind, Range<usize>)> {
+    let mut lexer = Lexer::with_comments(source);
+    let mut tokens = Vec::new();
+    let mut end = 0;
+    loop {
+        let (token, span) = lexer.lex_span();
+        if span.start > end {
+            tokens.push((SyntaxKind::Whitespace, end..span.start));
+        }
+        if let Token::Eof(_) = token {
+            break;
+        }
+        tokens.push((SyntaxKind::from(&token), span.start..span.end()));
+        end = span.end();
+    }
+    tokens
+}
+
+/// Returns the green token of a lexed token.
+fn green_token(source: &str, (kind, range): &(SyntaxKind, Range<usize>)) -> GreenElement {
+    GreenElement::Token(Rc::new(GreenToken {
+        kind: *kind,
+        text: source[range.clone()].to_string(),
+    }))
+}
+
+/// Returns a node holding the given tokens, with the tokens between delimiters grouped in nodes.
+///
+/// Delimiters left open are closed at the end of the tokens, and closing delimiters that open
+/// nothing are kept as tokens.
+fn group_tokens(
+    kind: SyntaxKind,
+    source: &str,
+    tokens: &[(SyntaxKind, Range<usize>)],
+) -> Rc<GreenNode> {
+    let mut stack = vec![(kind, Vec::new())];
+    for token in tokens {
+        let text = &source[token.1.clone()];
+        let punctuation = token.0 == SyntaxKind::Punctuation;
+        if let Some(group) = SyntaxKind::opened_by(text).filter(|_| punctuation) {
+            stack.push((group, Vec::new()));
+        }
+        let top = stack.last_mut().expect("the node itself is never popped");
+        top.1.push(green_token(source, token));
+        let open = top.0;
+
+        let closed = SyntaxKind::closed_by(text).filter(|_| punctuation);
+        if stack.len() > 1 && closed == Some(open) {
+            let (group, children) = stack.pop().expect("a group is open");
+            let node = GreenElement::Node(Rc::new(GreenNode::new(group, children)));
+            stack.last_mut().expect("a node is open").1.push(node);
+        }
+    }
+
+    while stack.len() > 1 {
+        let (group, children) = stack.pop().expect("a group is open");
+        let node = GreenElement::Node(Rc::new(GreenNode::new(group, children)));
+        stack.last_mut().expect("a node is open").1.push(node);
+    }
+    let (kind, children) = stack.pop().expect("the node itself is never popped");
+    Rc::new(GreenNode::new(kind, children))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cst() {
+        let source = "// double a number\ndouble(n) {\n    n * 2 /* twice */\n}\n\n\
+                      xs = [double(1),   2]\ny = )\n\"unterminated";
+        let (cst, ast) = parse(source);
+        assert_eq!(cst.kind(), SyntaxKind::Program);
+        assert_eq!(cst.text(), source);
+        assert_eq!(cst.range(), 0..source.len());
+        assert!(ast.is_err());
+
+        let statements = cst.nodes();
+        let kinds: Vec<_> = statements.iter().map(SyntaxNode::kind).collect();
+        assert_eq!(kinds[..2], [SyntaxKind::Statement, SyntaxKind::Statement]);
+        assert!(kinds[2..].contains(&SyntaxKind::Error));
+        assert_eq!(
+            statements[0].text(),
+            "double(n) {\n    n * 2 /* twice */\n}"
+        );
+        assert_eq!(statements[0].range(), 19..54);
+
+        let body = &statements[0].nodes()[1];
+        assert_eq!(body.kind(), SyntaxKind::Braced);
+        let comment = body
+            .tokens()
+            .into_iter()
+            .find(|t| t.kind() == SyntaxKind::Comment);
+        assert_eq!(comment.unwrap().text(), "/* twice */");
+
+        let array = &statements[1].nodes()[0];
+        assert_eq!(array.kind(), SyntaxKind::Bracketed);
+        assert_eq!(array.text(), "[double(1),   2]");
+        assert_eq!(cst.token_at(62).unwrap().text(), "double");
+
+        let (_, ast) = parse("double(n) {\n    n * 2\n}\nxs = [double(1), 2]");
+        let ast = ast.unwrap();
+        let converted: Vec<_> = statements[..2]
+            .iter()
+            .map(|statement| statement.to_stmt().unwrap().unwrap())
+            .collect();
+        assert_eq!(converted, ast);
+
+        let printed = from_tree(&ast);
+        assert_eq!(
+            printed.text(),
+            "double(n) {\n    n * 2\n}\nxs = [double(1), 2]"
+        );
+        assert_eq!(parse(&printed.text()).0, printed);
+    }
+}
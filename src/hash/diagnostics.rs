@@ -0,0 +1,163 @@
+use std::fmt;
+
+use super::{checker::CheckError, codes, lint::Lint, tokens::Position};
+
+/// Enum representing how serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// A likely mistake, which does not stop the program from running
+    Warning,
+    /// A mistake that stops the program from running
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "WARNING"),
+            Severity::Error => write!(f, "ERROR"),
+        }
+    }
+}
+
+/// A problem found in a program before it runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// How serious the problem is.
+    pub severity: Severity,
+    /// The position the problem is about.
+    pub position: Position,
+    /// The problem found.
+    pub message: String,
+    /// The code explaining the problem with `hydrogen explain`, if it has one.
+    pub code: Option<&'static str>,
+    /// The identifier of the rule of the linter that found the problem, if one did.
+    pub rule: Option<&'static str>,
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic for a mistake the checker found in a statement.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position where the statement starts.
+    /// * `error` - The mistake.
+    pub fn check(position: Position, error: CheckError) -> Self {
+        Self {
+            severity: Severity::Error,
+            position,
+            code: Some(error.code()),
+            message: error.to_string(),
+            rule: None,
+        }
+    }
+}
+
+impl From<Lint> for Diagnostic {
+    fn from(lint: Lint) -> Self {
+        Self {
+            severity: Severity::Warning,
+            position: lint.position,
+            message: lint.message,
+            code: codes::of_rule(lint.rule),
+            rule: Some(lint.rule),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Like `ERROR[H0101]` or `WARNING[H0201 unused-variable]`
+        let labels: Vec<_> = self.code.iter().chain(&self.rule).copied().collect();
+        match labels.is_empty() {
+            true => write!(f, "{}", self.severity)?,
+            false => write!(f, "{}[{}]", self.severity, labels.join(" "))?,
+        }
+        write!(f, ": {} at {}", self.message, self.position)
+    }
+}
+
+/// The problems found in a program before it runs, which only stop it if one is an error.
+///
+/// # Examples
+/// ```
+/// use hydrogen::hash::{
+///     diagnostics::{Diagnostics, Severity},
+///     lint::Linter,
+/// };
+///
+/// let mut diagnostics: Diagnostics = Linter::new().lint("f(x) { 1 }").into_iter().collect();
+/// assert!(!diagnostics.has_errors());
+///
+/// diagnostics.deny_warnings();
+/// assert!(diagnostics.has_errors());
+/// assert_eq!(diagnostics.iter().next().unwrap().severity, Severity::Error);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    /// Adds a diagnostic.
+    pub fn push(&mut self, diagnostic: impl Into<Diagnostic>) {
+        self.diagnostics.push(diagnostic.into());
+    }
+
+    /// Returns the diagnostics, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter()
+    }
+
+    /// Returns the diagnostics of a severity, in the order they were added.
+    pub fn of(&self, severity: Severity) -> impl Iterator<Item = &Diagnostic> {
+        self.iter().filter(move |d| d.severity == severity)
+    }
+
+    /// Returns whether there are no diagnostics.
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    /// Returns whether any diagnostic is an error, which stops the program from running.
+    pub fn has_errors(&self) -> bool {
+        self.of(Severity::Error).next().is_some()
+    }
+
+    /// Makes every warning an error, for programs that must not have likely mistakes.
+    pub fn deny_warnings(&mut self) {
+        for diagnostic in &mut self.diagnostics {
+            diagnostic.severity = Severity::Error;
+        }
+    }
+}
+
+impl<D: Into<Diagnostic>> FromIterator<D> for Diagnostics {
+    fn from_iter<I: IntoIterator<Item = D>>(iter: I) -> Self {
+        Self {
+            diagnostics: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<D: Into<Diagnostic>> Extend<D> for Diagnostics {
+    fn extend<I: IntoIterator<Item = D>>(&mut self, iter: I) {
+        self.diagnostics.extend(iter.into_iter().map(Into::into));
+    }
+}
+
+impl<'a> IntoIterator for &'a Diagnostics {
+    type Item = &'a Diagnostic;
+    type IntoIter = std::slice::Iter<'a, Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.diagnostics.iter()
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines: Vec<String> = self.iter().map(|d| d.to_string()).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
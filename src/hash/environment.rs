@@ -0,0 +1,393 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt,
+    path::Path,
+    rc::Rc,
+};
+
+use super::{
+    ast::{Block, Parameter, Signature, TypeExpr},
+    compile::Chunk,
+    value::Value,
+};
+
+/// Shared, mutable handle to an environment
+pub type Env = Rc<RefCell<Environment>>;
+
+/// A function defined by a Hydrogen program
+#[derive(Clone)]
+pub struct Function {
+    /// The name the function was defined with.
+    pub name: String,
+    /// The parameters, in order.
+    pub parameters: Vec<Parameter>,
+    /// The type the function returns, if it is declared.
+    pub ret: Option<TypeExpr>,
+    /// The block executed when the function is called.
+    pub body: Block,
+    /// The compiled block, if the function was created by the virtual machine.
+    pub code: Option<Rc<Chunk>>,
+    /// The environment the function was defined in.
+    pub closure: Env,
+    /// The file the function was defined in, if it is known.
+    pub file: Option<Rc<Path>>,
+    /// The doc comments of the definition of the function, if it has any.
+    pub doc: Option<Rc<str>>,
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The closure is left out, as it usually holds the function itself
+        f.debug_struct("Function")
+            .field("name", &self.name)
+            .field("parameters", &self.parameters)
+            .field("ret", &self.ret)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Function {
+    /// Returns how the function is called, like `add(a: num, b: num): num`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the function is called with, which differs from the name it was
+    ///   defined with for a lambda held by a variable.
+    pub fn signature(&self, name: &str) -> String {
+        let parameters: Vec<String> = self.parameters.iter().map(|p| p.to_string()).collect();
+        match self.ret {
+            Some(ret) => format!("{}({}): {}", name, parameters.join(", "), ret),
+            None => format!("{}({})", name, parameters.join(", ")),
+        }
+    }
+}
+
+/// Functions are the same only if they come from the same definition.
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// A struct defined by a Hydrogen program, with the functions of the interfaces implemented for
+/// it
+#[derive(Debug)]
+pub struct Struct {
+    /// The name the struct was defined with, which its values are created by calling.
+    pub name: String,
+    /// The fields, in the order they are given values when a value is created.
+    pub fields: Vec<Parameter>,
+    /// The functions of the interfaces implemented for the struct, by name.
+    pub methods: RefCell<HashMap<String, Rc<Function>>>,
+    /// The names of the interfaces implemented for the struct.
+    pub interfaces: RefCell<BTreeSet<String>>,
+    /// The doc comments of the definition of the struct, if it has any.
+    pub doc: Option<Rc<str>>,
+}
+
+impl Struct {
+    /// Returns the position of the field with the given name, if the struct has one.
+    pub fn field(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field.name == name)
+    }
+
+    /// Tells if the interface with the given name is implemented for the struct.
+    pub fn implements(&self, interface: &str) -> bool {
+        self.interfaces.borrow().contains(interface)
+    }
+}
+
+/// Structs are the same only if they come from the same definition.
+impl PartialEq for Struct {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// An interface defined by a Hydrogen program, or built into the language
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interface {
+    /// The name of the interface.
+    pub name: String,
+    /// The functions a struct must implement to implement the interface.
+    pub signatures: Vec<Signature>,
+}
+
+/// A scope holding variables and functions, chained to the scope enclosing it.
+///
+/// Every scope can read the variables of the scopes enclosing it, but assignments inside a
+/// function only reach the variables of the function itself. Giving a value to any other name
+/// defines a local variable, unless the name is declared `global`, in which case the variable of
+/// the global scope is given the value, or `nonlocal`, in which case the closest variable of the
+/// functions enclosing the function is.
+#[derive(Debug, Default)]
+pub struct Environment {
+    variables: HashMap<String, Value>,
+    functions: HashMap<String, Rc<Function>>,
+    structs: HashMap<String, Rc<Struct>>,
+    interfaces: HashMap<String, Rc<Interface>>,
+    /// The names declared `global` in this scope.
+    globals: HashSet<String>,
+    /// The names declared `nonlocal` in this scope.
+    nonlocals: HashSet<String>,
+    /// Whether this is the outermost scope of a call, which assignments do not reach past.
+    call: bool,
+    parent: Option<Env>,
+}
+
+impl Environment {
+    /// Creates a new global environment.
+    pub fn new() -> Env {
+        Rc::new(RefCell::new(Environment::default()))
+    }
+
+    /// Creates a new environment nested inside the given one.
+    pub fn with_parent(parent: &Env) -> Env {
+        Rc::new(RefCell::new(Environment {
+            parent: Some(Rc::clone(parent)),
+            ..Environment::default()
+        }))
+    }
+
+    /// Creates the environment of a call to a function, nested inside the environment the
+    /// function was defined in.
+    pub fn with_caller(closure: &Env) -> Env {
+        Rc::new(RefCell::new(Environment {
+            parent: Some(Rc::clone(closure)),
+            call: true,
+            ..Environment::default()
+        }))
+    }
+
+    /// Returns the environment enclosing this one, if there is one.
+    pub fn parent(&self) -> Option<Env> {
+        self.parent.clone()
+    }
+
+    /// Defines a variable in this scope, shadowing any variable of the same name outside it.
+    ///
+    /// A function of the same name defined in this scope is replaced by the variable.
+    pub fn define(&mut self, name: &str, value: Value) {
+        self.functions.remove(name);
+        self.variables.insert(name.to_string(), value);
+    }
+
+    /// Declares a name `global` in this scope, so assignments to it from this scope and the
+    /// scopes nested in it give a value to the variable of the global scope.
+    pub fn declare_global(&mut self, name: &str) {
+        self.globals.insert(name.to_string());
+    }
+
+    /// Declares a name `nonlocal` in this scope, so assignments to it from this scope and the
+    /// scopes nested in it give a value to the closest variable of the functions enclosing the
+    /// function this scope is part of.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if the name was declared, false if no enclosing function has a variable
+    ///   with that name.
+    pub fn declare_nonlocal(&mut self, name: &str) -> bool {
+        let declared = self
+            .enclosing()
+            .is_some_and(|enclosing| Self::defining(&enclosing, name).is_some());
+        if declared {
+            self.nonlocals.insert(name.to_string());
+        }
+        declared
+    }
+
+    /// Assigns to the closest variable with the given name that can be assigned from this scope.
+    ///
+    /// The variables of the current function can be assigned, and so can the variables declared
+    /// `global`, which are defined in the global scope if they do not exist yet, and the
+    /// variables of enclosing functions declared `nonlocal`.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if the variable was given the value, false if a new variable must be
+    ///   defined for it.
+    pub fn assign(&mut self, name: &str, value: Value) -> bool {
+        if let Some(variable) = self.variables.get_mut(name) {
+            *variable = value;
+            return true;
+        }
+        if self.globals.contains(name) {
+            match &self.parent {
+                Some(parent) => Self::root(parent).borrow_mut().define(name, value),
+                None => self.define(name, value),
+            }
+            return true;
+        }
+        if self.nonlocals.contains(name) {
+            let defining = self
+                .enclosing()
+                .and_then(|enclosing| Self::defining(&enclosing, name));
+            return match defining {
+                Some(scope) => scope.borrow_mut().assign(name, value),
+                None => false,
+            };
+        }
+
+        match &self.parent {
+            Some(parent) if !self.call => parent.borrow_mut().assign(name, value),
+            _ => false,
+        }
+    }
+
+    /// Tells if a variable with the given name exists and can be assigned from this scope, as
+    /// [`Environment::assign`] does.
+    pub fn assignable(&self, name: &str) -> bool {
+        if self.variables.contains_key(name) {
+            return true;
+        }
+        if self.globals.contains(name) {
+            return match &self.parent {
+                Some(parent) => Self::root(parent).borrow().variables.contains_key(name),
+                None => false,
+            };
+        }
+        if self.nonlocals.contains(name) {
+            return self
+                .enclosing()
+                .is_some_and(|enclosing| Self::defining(&enclosing, name).is_some());
+        }
+
+        match &self.parent {
+            Some(parent) if !self.call => parent.borrow().assignable(name),
+            _ => false,
+        }
+    }
+
+    /// Returns the scope the function this scope is part of was defined in, or None outside of
+    /// functions.
+    fn enclosing(&self) -> Option<Env> {
+        if self.call {
+            return self.parent.clone();
+        }
+        let mut scope = self.parent.clone();
+        while let Some(environment) = scope {
+            let environment = environment.borrow();
+            if environment.call {
+                return environment.parent.clone();
+            }
+            scope = environment.parent.clone();
+        }
+        None
+    }
+
+    /// Returns the closest scope from an environment outward holding a variable with the given
+    /// name, leaving out the global scope.
+    fn defining(environment: &Env, name: &str) -> Option<Env> {
+        let mut scope = Rc::clone(environment);
+        loop {
+            let parent = scope.borrow().parent.clone()?;
+            if scope.borrow().variables.contains_key(name) {
+                return Some(scope);
+            }
+            scope = parent;
+        }
+    }
+
+    /// Returns the global scope enclosing an environment.
+    fn root(environment: &Env) -> Env {
+        let mut environment = Rc::clone(environment);
+        loop {
+            let parent = environment.borrow().parent.clone();
+            match parent {
+                Some(parent) => environment = parent,
+                None => return environment,
+            }
+        }
+    }
+
+    /// Returns the value of the closest variable with the given name.
+    pub fn get(&self, name: &str) -> Option<Value> {
+        match self.variables.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.parent.as_ref()?.borrow().get(name),
+        }
+    }
+
+    /// Returns the value of the closest variable or function with the given name.
+    ///
+    /// Each scope is searched for both before the scope enclosing it, so a variable holding a
+    /// function hides a function of the same name defined outside its scope. In the same scope,
+    /// the variable is found first.
+    pub fn lookup(&self, name: &str) -> Option<Value> {
+        if let Some(value) = self.variables.get(name) {
+            return Some(value.clone());
+        }
+        match self.functions.get(name) {
+            Some(function) => Some(Value::Function(Rc::clone(function))),
+            None => self.parent.as_ref()?.borrow().lookup(name),
+        }
+    }
+
+    /// Returns every variable visible from this scope, sorted by name.
+    ///
+    /// A variable shadowed by another of the same name in a closer scope is left out.
+    pub fn variables(&self) -> Vec<(String, Value)> {
+        let mut variables = BTreeMap::new();
+        let mut scope = self.parent.clone();
+        for (name, value) in &self.variables {
+            variables.insert(name.clone(), value.clone());
+        }
+        while let Some(environment) = scope {
+            let environment = environment.borrow();
+            for (name, value) in &environment.variables {
+                variables
+                    .entry(name.clone())
+                    .or_insert_with(|| value.clone());
+            }
+            scope = environment.parent.clone();
+        }
+        variables.into_iter().collect()
+    }
+
+    /// Defines a function in this scope.
+    ///
+    /// A variable of the same name defined in this scope is replaced by the function.
+    pub fn define_function(&mut self, function: Function) {
+        self.variables.remove(&function.name);
+        self.functions
+            .insert(function.name.clone(), Rc::new(function));
+    }
+
+    /// Returns the closest function with the given name.
+    pub fn get_function(&self, name: &str) -> Option<Rc<Function>> {
+        match self.functions.get(name) {
+            Some(function) => Some(Rc::clone(function)),
+            None => self.parent.as_ref()?.borrow().get_function(name),
+        }
+    }
+
+    /// Defines a struct in this scope, replacing any struct of the same name defined in it.
+    pub fn define_struct(&mut self, definition: Struct) {
+        self.structs
+            .insert(definition.name.clone(), Rc::new(definition));
+    }
+
+    /// Returns the closest struct with the given name.
+    pub fn get_struct(&self, name: &str) -> Option<Rc<Struct>> {
+        match self.structs.get(name) {
+            Some(definition) => Some(Rc::clone(definition)),
+            None => self.parent.as_ref()?.borrow().get_struct(name),
+        }
+    }
+
+    /// Defines an interface in this scope, replacing any interface of the same name defined in
+    /// it.
+    pub fn define_interface(&mut self, interface: Interface) {
+        self.interfaces
+            .insert(interface.name.clone(), Rc::new(interface));
+    }
+
+    /// Returns the closest interface with the given name.
+    pub fn get_interface(&self, name: &str) -> Option<Rc<Interface>> {
+        match self.interfaces.get(name) {
+            Some(interface) => Some(Rc::clone(interface)),
+            None => self.parent.as_ref()?.borrow().get_interface(name),
+        }
+    }
+}
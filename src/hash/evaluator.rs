@@ -1,81 +1,2997 @@
-use super::ast::{ASTNode, Error, Errors, Node, Nodes};
-use super::parser::Parser;
-use super::print::{print_ast, print_error};
+use std::{
+    cmp,
+    collections::HashMap,
+    fmt,
+    io::Write,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
-pub struct Evaluator<'a> {
-    parser: Parser<'a>,
+#[cfg(feature = "bignum")]
+use super::bignum;
+use super::{
+    ast::{Block, Expr, Item, Literal, MethodCall, Parameter, Pattern, Stmt, TypeExpr},
+    builtins::{self, Caller, Native},
+    coverage::Coverage,
+    environment::{Env, Environment, Function, Interface, Struct},
+    print::stmt_source,
+    profile::Profiler,
+    value::Value,
+};
+
+/// How deeply calls of functions defined by the program can nest by default, so that runaway
+/// recursion reports an error instead of overflowing the stack.
+pub const MAX_DEPTH: usize = 1000;
+
+/// Enum representing what arithmetic on whole numbers does when its result is too large for a
+/// whole number
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Arithmetic {
+    /// Fail with [`RuntimeError::Overflow`], which programs can catch
+    #[default]
+    Checked,
+    /// Wrap around modulo 2^64, like the wrapping operations of `i64` in Rust
+    Wrapping,
+    /// Grow into a big integer, which never overflows but is slower
+    #[cfg(feature = "bignum")]
+    Big,
+}
+
+impl Arithmetic {
+    /// Applies a unary operator to a value.
+    pub(crate) fn unary(self, op: &str, value: Value) -> Result<Value, RuntimeError> {
+        match self {
+            Arithmetic::Checked => unary(op, value),
+            Arithmetic::Wrapping => wrapping_unary(op, value),
+            #[cfg(feature = "bignum")]
+            Arithmetic::Big => bignum::unary(op, value),
+        }
+    }
+
+    /// Applies a binary operator to two values.
+    pub(crate) fn binary(self, op: &str, left: Value, right: Value) -> Result<Value, RuntimeError> {
+        match self {
+            Arithmetic::Checked => binary(op, left, right),
+            Arithmetic::Wrapping => wrapping_binary(op, left, right),
+            #[cfg(feature = "bignum")]
+            Arithmetic::Big => bignum::binary(op, left, right),
+        }
+    }
+}
+
+/// Enum representing the errors that can happen while evaluating a program
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeError {
+    /// A variable that is not defined: (name)
+    UndefinedVariable(String),
+    /// A compound assignment like `x += 1` to a variable that is not defined: (name)
+    UndefinedAssignment(String),
+    /// A function that is not defined: (name)
+    UndefinedFunction(String),
+    /// A value of the wrong type: (expected, found)
+    TypeMismatch(String, String),
+    /// An operator applied to operands it does not support: (operator, operand types)
+    InvalidOperands(String, Vec<String>),
+    /// An operator the evaluator does not implement: (operator)
+    UnsupportedOperator(String),
+    /// A call with the wrong number of arguments: (function, expected, found)
+    ArgumentCount(String, usize, usize),
+    /// A division whose divisor is zero
+    DivisionByZero,
+    /// Arithmetic on whole numbers whose result is too large for one: (operator)
+    Overflow(String),
+    /// A number literal that does not fit in a number: (literal)
+    InvalidNumber(String),
+    /// A value that does not have the shape of the pattern it is taken apart by: (pattern,
+    /// value)
+    PatternMismatch(String, String),
+    /// A value that cannot be converted to a type: (value, type)
+    InvalidCast(String, String),
+    /// A `break` or `continue` outside of a loop: (keyword)
+    OutsideLoop(String),
+    /// Calls nested deeper than the evaluator allows: (limit)
+    RecursionLimit(usize),
+    /// An error raised by a native function: (message)
+    Native(String),
+    /// An element taken from an array that has none: (function)
+    EmptyArray(String),
+    /// A file or stream that could not be used: (file, message of the operating system)
+    Io(String, String),
+    /// A range to pick a number from that holds no number: (start, end)
+    EmptyRange(i64, i64),
+    /// Text that is not valid JSON: (problem, byte offset)
+    InvalidJson(String, usize),
+    /// A template of `format` or `printf` with a brace that is not part of a placeholder:
+    /// (template)
+    InvalidFormat(String),
+    /// A call of `exit`, which ends the program without any `try` block catching it: (status)
+    Exit(i32),
+    /// A builtin the application running the program has disabled: (name)
+    Disabled(String),
+    /// A failed check of `assert`, `assert_eq` or `expect`: (message)
+    Assertion(String),
+    /// A string or array longer than the evaluator allows: (limit)
+    TooLarge(usize),
+    /// An array or a map holding itself, given to an operation that would walk it forever:
+    /// (operation)
+    CyclicValue(String),
+    /// A position past the elements of an array, a string, or a range: (index, length)
+    IndexOutOfBounds(i64, usize),
+    /// A key a map does not have: (key)
+    MissingKey(String),
+    /// A program that ran for more steps than it is allowed: (limit)
+    StepLimit(u64),
+    /// A program that ran for longer than it is allowed: (limit)
+    Timeout(Duration),
+    /// A program stopped by its interrupt flag, like when Ctrl-C is pressed
+    Interrupted,
+    /// A struct that is not defined: (name)
+    UndefinedStruct(String),
+    /// An interface that is not defined: (name)
+    UndefinedInterface(String),
+    /// A field a struct does not have: (struct, field)
+    UndefinedField(String, String),
+    /// A function called on a value whose struct does not implement it, or defined in an
+    /// `impl` block of an interface that does not require it: (struct or interface, function)
+    UndefinedMethod(String, String),
+    /// A function of an interface an `impl` block leaves out: (struct, interface, function)
+    Unimplemented(String, String, String),
+    /// A function of an `impl` block that does not match the signature the interface requires:
+    /// (interface, expected signature, found signature)
+    SignatureMismatch(String, String, String),
 }
 
-impl<'a> Evaluator<'a> {
-    /// Creates a new Evaluator instance with the given program source code.
-    pub fn new(program: &'a str) -> Self {
-        Self {
-            parser: Parser::new(program),
+impl RuntimeError {
+    /// Tells if a `try` block can catch the error.
+    ///
+    /// A call of `exit`, a program running out of steps or time, and an interrupted program end
+    /// the program wherever they happen.
+    pub fn catchable(&self) -> bool {
+        !matches!(
+            self,
+            RuntimeError::Exit(_)
+                | RuntimeError::StepLimit(_)
+                | RuntimeError::Timeout(_)
+                | RuntimeError::Interrupted
+        )
+    }
+}
+
+/// How often the clock is read against a timeout, in steps.
+const CLOCK_INTERVAL: u64 = 256;
+
+/// The number of steps and the time a program can run for, and the flag that interrupts it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Budget {
+    /// The number of steps allowed, if there is a limit.
+    max_steps: Option<u64>,
+    /// The time allowed, if there is a limit.
+    timeout: Option<Duration>,
+    /// The number of steps run since the budget was restarted.
+    steps: u64,
+    /// The moment the time runs out, if there is a timeout.
+    deadline: Option<Instant>,
+    /// The flag set from outside the program to stop it, if there is one.
+    interrupt: Option<Arc<AtomicBool>>,
+}
+
+impl Budget {
+    /// Sets the number of steps allowed.
+    pub(crate) fn set_max_steps(&mut self, max_steps: Option<u64>) {
+        self.max_steps = max_steps;
+    }
+
+    /// Sets the time allowed, counted from now.
+    pub(crate) fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+        self.restart();
+    }
+
+    /// Sets the flag that stops the program when it is set.
+    pub(crate) fn set_interrupt(&mut self, interrupt: Option<Arc<AtomicBool>>) {
+        self.interrupt = interrupt;
+    }
+
+    /// Starts counting steps and time from zero, and forgets earlier interrupts.
+    pub(crate) fn restart(&mut self) {
+        if let Some(interrupt) = &self.interrupt {
+            interrupt.store(false, Ordering::Relaxed);
+        }
+        self.steps = 0;
+        self.deadline = self
+            .timeout
+            .and_then(|timeout| Instant::now().checked_add(timeout));
+    }
+
+    /// Returns the number of steps run since the budget was restarted.
+    pub(crate) fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Counts a step, failing if the program ran out of steps or time or was interrupted.
+    pub(crate) fn tick(&mut self) -> Result<(), RuntimeError> {
+        if let Some(interrupt) = &self.interrupt {
+            if interrupt.load(Ordering::Relaxed) {
+                interrupt.store(false, Ordering::Relaxed);
+                return Err(RuntimeError::Interrupted);
+            }
+        }
+        self.steps += 1;
+        if let Some(max_steps) = self.max_steps {
+            if self.steps > max_steps {
+                return Err(RuntimeError::StepLimit(max_steps));
+            }
+        }
+        if let (Some(deadline), Some(timeout)) = (self.deadline, self.timeout) {
+            if self.steps.is_multiple_of(CLOCK_INTERVAL) && Instant::now() >= deadline {
+                return Err(RuntimeError::Timeout(timeout));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Trait for tools pausing a program before each statement the [`Evaluator`] evaluates, like a
+/// debugger.
+pub trait Debugger {
+    /// Called before a statement is evaluated.
+    ///
+    /// # Arguments
+    ///
+    /// * `evaluator` - The evaluator, to inspect its variables and calls, or to evaluate
+    ///   expressions where the program is paused.
+    /// * `row` - The row the statement starts on in the source code, if it is known.
+    /// * `statement` - The statement about to be evaluated.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), RuntimeError>` - Ok(()) to evaluate the statement, or the error stopping
+    ///   the program.
+    fn pause(
+        &mut self,
+        evaluator: &mut Evaluator,
+        row: Option<usize>,
+        statement: &Stmt,
+    ) -> Result<(), RuntimeError>;
+}
+
+/// Enum representing why the evaluation of a statement stopped before reaching its end
+#[derive(Debug)]
+enum Flow {
+    /// An error stopped the program: (error)
+    Error(RuntimeError),
+    /// A `return` statement left the function: (value)
+    Return(Value),
+    /// A `break` statement left the loop
+    Break,
+    /// A `continue` statement skipped the rest of the iteration
+    Continue,
+    /// A call whose value the function returns, left for the caller to make: (function, arguments)
+    TailCall(Rc<Function>, Vec<Value>),
+}
+
+impl From<RuntimeError> for Flow {
+    fn from(error: RuntimeError) -> Self {
+        Flow::Error(error)
+    }
+}
+
+/// Result of evaluating a node that can leave the enclosing function early
+type Flowing = Result<Value, Flow>;
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeError::UndefinedVariable(name) => write!(f, "undefined variable `{}`", name),
+            RuntimeError::UndefinedAssignment(name) => write!(
+                f,
+                "cannot update undefined variable `{}`, define it with `=` first",
+                name
+            ),
+            RuntimeError::UndefinedFunction(name) => write!(f, "undefined function `{}`", name),
+            RuntimeError::TypeMismatch(expected, found) => {
+                write!(
+                    f,
+                    "expected a value of type `{}`, found `{}`",
+                    expected, found
+                )
+            }
+            RuntimeError::InvalidOperands(op, types) => {
+                write!(f, "cannot apply `{}` to `{}`", op, types.join("` and `"))
+            }
+            RuntimeError::UnsupportedOperator(op) => write!(f, "unsupported operator `{}`", op),
+            RuntimeError::ArgumentCount(name, expected, found) => write!(
+                f,
+                "`{}` takes {} argument(s) but {} were given",
+                name, expected, found
+            ),
+            RuntimeError::DivisionByZero => write!(f, "division by zero"),
+            RuntimeError::Overflow(op) => {
+                write!(f, "the result of `{}` is too large for a whole number", op)
+            }
+            RuntimeError::InvalidNumber(number) => write!(f, "invalid number `{}`", number),
+            RuntimeError::PatternMismatch(pattern, value) => {
+                write!(f, "cannot destructure {} into `{}`", value, pattern)
+            }
+            RuntimeError::InvalidCast(value, ty) => {
+                write!(f, "cannot convert `{}` to `{}`", value, ty)
+            }
+            RuntimeError::OutsideLoop(keyword) => write!(f, "`{}` outside of a loop", keyword),
+            RuntimeError::RecursionLimit(limit) => {
+                write!(f, "maximum recursion depth of {} exceeded", limit)
+            }
+            RuntimeError::Native(message) => write!(f, "{}", message),
+            RuntimeError::EmptyArray(name) => write!(f, "`{}` of an empty array", name),
+            RuntimeError::Io(file, message) => write!(f, "`{}`: {}", file, message),
+            RuntimeError::InvalidJson(problem, offset) => {
+                write!(f, "invalid JSON: {} at byte {}", problem, offset)
+            }
+            RuntimeError::InvalidFormat(template) => write!(
+                f,
+                "invalid template `{}`, write `{{{{` and `}}}}` for braces",
+                template
+            ),
+            RuntimeError::Exit(status) => write!(f, "exited with status {}", status),
+            RuntimeError::Disabled(name) => write!(f, "`{}` is disabled", name),
+            RuntimeError::Assertion(message) => write!(f, "{}", message),
+            RuntimeError::StepLimit(limit) => write!(f, "ran for more than {} steps", limit),
+            RuntimeError::Timeout(limit) => write!(f, "ran for longer than {:?}", limit),
+            RuntimeError::Interrupted => write!(f, "interrupted"),
+            RuntimeError::TooLarge(limit) => {
+                write!(f, "string or array longer than the limit of {}", limit)
+            }
+            RuntimeError::EmptyRange(start, end) => {
+                write!(f, "the range {}..{} is empty", start, end)
+            }
+            RuntimeError::IndexOutOfBounds(index, length) => write!(
+                f,
+                "index {} is out of bounds for a length of {}",
+                index, length
+            ),
+            RuntimeError::MissingKey(key) => write!(f, "the map has no key `{}`", key),
+            RuntimeError::CyclicValue(operation) => {
+                write!(f, "cannot {} a cyclic value", operation)
+            }
+            RuntimeError::UndefinedStruct(name) => write!(f, "undefined struct `{}`", name),
+            RuntimeError::UndefinedInterface(name) => write!(f, "undefined interface `{}`", name),
+            RuntimeError::UndefinedField(ty, name) => {
+                write!(f, "`{}` has no field `{}`", ty, name)
+            }
+            RuntimeError::UndefinedMethod(ty, name) => {
+                write!(f, "`{}` has no function `{}`", ty, name)
+            }
+            RuntimeError::Unimplemented(ty, interface, name) => write!(
+                f,
+                "`{}` does not implement `{}` of `{}`",
+                ty, name, interface
+            ),
+            RuntimeError::SignatureMismatch(interface, expected, found) => write!(
+                f,
+                "`{}` requires `{}`, found `{}`",
+                interface, expected, found
+            ),
+        }
+    }
+}
+
+/// A place the program was running at when an error stopped it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// The name of the function being called, or None for the statements of the program itself.
+    pub function: Option<String>,
+    /// The file the function or the program is written in, if it is known.
+    pub file: Option<PathBuf>,
+    /// The row of the statement being evaluated, if it is known.
+    pub row: Option<usize>,
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(function) = &self.function {
+            write!(f, "in `{}` ", function)?;
+        }
+        match (&self.file, self.row) {
+            (Some(file), Some(row)) => write!(f, "at {}:{}", file.display(), row),
+            (Some(file), None) => write!(f, "at {}", file.display()),
+            (None, Some(row)) => write!(f, "at line {}", row),
+            (None, None) => write!(f, "at an unknown line"),
+        }
+    }
+}
+
+/// The calls active when an error stopped the program, innermost first, ending with the
+/// statement of the program making the outermost call.
+///
+/// # Examples
+/// ```
+/// use hydrogen::{Engine, HydrogenError};
+///
+/// let mut engine = Engine::new();
+/// let error = engine.eval_str("f() {\n    missing\n}\nf()").unwrap_err();
+///
+/// let trace = error.stack_trace().unwrap();
+/// assert_eq!(trace.frames()[0].function.as_deref(), Some("f"));
+/// assert_eq!(trace.frames()[0].row, Some(2));
+/// assert_eq!(trace.frames()[1].row, Some(4));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StackTrace {
+    frames: Vec<Frame>,
+}
+
+impl StackTrace {
+    /// Creates a trace of the given frames, leaving out the ones that tell nothing about where
+    /// the program was.
+    ///
+    /// # Arguments
+    ///
+    /// * `frames` - The frames, innermost first.
+    pub fn new(mut frames: Vec<Frame>) -> Self {
+        // A statement run without a row outside of any call tells nothing
+        frames.retain(|frame| frame.function.is_some() || frame.row.is_some());
+        Self { frames }
+    }
+
+    /// Returns the frames, innermost first.
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// Returns whether nothing is known about where the error happened.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+/// Written on the lines after the error it belongs to.
+impl fmt::Display for StackTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return Ok(());
+        }
+        write!(f, "\nstack trace, innermost call first:")?;
+        // Recursive calls made from the same line are reported once, with their count
+        let mut frames = self.frames.iter().peekable();
+        while let Some(frame) = frames.next() {
+            let mut count = 1;
+            while frames.next_if_eq(&frame).is_some() {
+                count += 1;
+            }
+            match count {
+                1 => write!(f, "\n    {}", frame)?,
+                _ => write!(f, "\n    {} ({} calls)", frame, count)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Evaluator walks the abstract syntax tree of a program and computes its value.
+///
+/// Variables and functions defined by evaluated statements are kept between calls, so the same
+/// evaluator can run a program one statement at a time.
+///
+/// # Examples
+/// ```
+/// use hydrogen::hash::{evaluator::Evaluator, parser::Parser, value::Value};
+///
+/// let mut evaluator = Evaluator::new();
+/// let tree = Parser::new("1 + 2 * 3").parse_program().unwrap();
+///
+/// assert_eq!(evaluator.evaluate(&tree[0]), Ok(Value::Integer(7)));
+/// ```
+pub struct Evaluator {
+    environment: Env,
+    natives: HashMap<String, Native>,
+    frames: Vec<String>,
+    // The file and row each function being called was called from, in the order of `frames`
+    calls: Vec<(Option<Rc<Path>>, Option<usize>)>,
+    // The row of the statement being evaluated, if it is known
+    row: Option<usize>,
+    // The file of the statement being evaluated, if it is known
+    file: Option<Rc<Path>>,
+    // The calls active when the error being propagated happened, until it is caught or reported
+    unwound: Option<StackTrace>,
+    max_depth: usize,
+    max_length: usize,
+    arithmetic: Arithmetic,
+    budget: Budget,
+    trace: Option<Box<dyn Write>>,
+    debugger: Option<Box<dyn Debugger>>,
+    profiler: Option<Profiler>,
+    coverage: Option<Coverage>,
+    // Whether a `return` leaves the function being called, so the call it returns can be left
+    // to the caller
+    tail_calls: bool,
+}
+
+impl fmt::Debug for Evaluator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Evaluator")
+            .field("environment", &self.environment)
+            .field("natives", &self.natives.keys().collect::<Vec<_>>())
+            .field("frames", &self.frames)
+            .field("calls", &self.calls)
+            .field("row", &self.row)
+            .field("file", &self.file)
+            .field("max_depth", &self.max_depth)
+            .field("max_length", &self.max_length)
+            .field("arithmetic", &self.arithmetic)
+            .field("budget", &self.budget)
+            .field("trace", &self.trace.is_some())
+            .field("debugger", &self.debugger.is_some())
+            .field("profiler", &self.profiler)
+            .field("coverage", &self.coverage)
+            .finish()
+    }
+}
+
+impl Evaluator {
+    /// Creates a new Evaluator instance with an empty global environment.
+    pub fn new() -> Self {
+        Self {
+            environment: Environment::new(),
+            natives: HashMap::new(),
+            frames: Vec::new(),
+            calls: Vec::new(),
+            row: None,
+            file: None,
+            unwound: None,
+            max_depth: MAX_DEPTH,
+            max_length: usize::MAX,
+            arithmetic: Arithmetic::Checked,
+            budget: Budget::default(),
+            trace: None,
+            debugger: None,
+            profiler: None,
+            coverage: None,
+            tail_calls: false,
+        }
+    }
+
+    /// Forgets the variables and functions programs defined, keeping the native functions, the
+    /// limits, and the tools watching programs.
+    pub fn reset(&mut self) {
+        self.environment = Environment::new();
+        self.frames.clear();
+        self.calls.clear();
+        self.unwound = None;
+    }
+
+    /// Sets how deeply calls of functions defined by the program can nest.
+    ///
+    /// A call beyond the limit fails with [`RuntimeError::RecursionLimit`], which programs can
+    /// catch. A call whose value the calling function returns replaces the caller instead of
+    /// nesting inside it, so tail recursion never reaches the limit. Every nested call uses the
+    /// stack of the Rust thread evaluating the program, so a limit much higher than
+    /// [`MAX_DEPTH`] needs a thread with a larger stack.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_depth` - The number of calls that can be active at the same time.
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// Sets how long the strings and arrays built by operators can be, which is unlimited unless
+    /// it is set.
+    ///
+    /// An operator building a longer value, like a range or the concatenation of two strings,
+    /// fails with [`RuntimeError::TooLarge`]. Strings are measured in bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_length` - The number of elements or bytes a value can hold.
+    pub fn set_max_length(&mut self, max_length: usize) {
+        self.max_length = max_length;
+    }
+
+    /// Sets what arithmetic on whole numbers does when its result is too large for a whole
+    /// number, which is to fail unless it is set.
+    ///
+    /// `+`, `-`, `*`, `/`, `^`, and negation can overflow.
+    ///
+    /// # Arguments
+    ///
+    /// * `arithmetic` - What overflowing arithmetic does.
+    pub fn set_arithmetic(&mut self, arithmetic: Arithmetic) {
+        self.arithmetic = arithmetic;
+    }
+
+    /// Sets how many steps programs can run for, which is unlimited unless it is set.
+    ///
+    /// Every expression evaluated is a step. A program running out of steps fails with
+    /// [`RuntimeError::StepLimit`], which no `try` block catches. Steps are counted from the
+    /// last call of [`Evaluator::restart_limits`].
+    ///
+    /// # Arguments
+    ///
+    /// * `max_steps` - The number of steps allowed, or None for no limit.
+    pub fn set_max_steps(&mut self, max_steps: Option<u64>) {
+        self.budget.set_max_steps(max_steps);
+    }
+
+    /// Sets how long programs can run for, which is unlimited unless it is set.
+    ///
+    /// A program running out of time fails with [`RuntimeError::Timeout`], which no `try` block
+    /// catches. The time is counted from this call, and then from every call of
+    /// [`Evaluator::restart_limits`].
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The time allowed, or None for no limit.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.budget.set_timeout(timeout);
+    }
+
+    /// Sets the flag that stops programs when it is set, from another thread or a signal
+    /// handler.
+    ///
+    /// The running program fails with [`RuntimeError::Interrupted`], which no `try` block
+    /// catches, and the flag is cleared. Setting the flag while nothing runs has no effect, since
+    /// [`Evaluator::restart_limits`] clears it.
+    ///
+    /// # Arguments
+    ///
+    /// * `interrupt` - The flag, or None to stop watching one.
+    pub fn set_interrupt(&mut self, interrupt: Option<Arc<AtomicBool>>) {
+        self.budget.set_interrupt(interrupt);
+    }
+
+    /// Sets where every evaluated statement is written, followed by its value.
+    ///
+    /// The statements evaluated by a call are indented one level deeper than the statement
+    /// making the call. A statement spanning several lines is written as its first line.
+    ///
+    /// # Arguments
+    ///
+    /// * `trace` - The writer of the trace, or None to stop tracing.
+    pub fn set_trace(&mut self, trace: Option<Box<dyn Write>>) {
+        self.trace = trace;
+    }
+
+    /// Sets the debugger pausing the program before each statement.
+    ///
+    /// # Arguments
+    ///
+    /// * `debugger` - The debugger, or None to run without one.
+    pub fn set_debugger(&mut self, debugger: Option<Box<dyn Debugger>>) {
+        self.debugger = debugger;
+    }
+
+    /// Sets whether the calls of functions defined by programs are counted and timed.
+    ///
+    /// Turning profiling on starts a new [`Profiler`], and turning it off drops the calls
+    /// recorded so far.
+    ///
+    /// # Arguments
+    ///
+    /// * `profiling` - Whether calls are profiled.
+    pub fn set_profiling(&mut self, profiling: bool) {
+        self.profiler = profiling.then(Profiler::default);
+    }
+
+    /// Returns the calls recorded since profiling was turned on, if it is on.
+    pub fn profile(&self) -> Option<&Profiler> {
+        self.profiler.as_ref()
+    }
+
+    /// Sets whether the lines whose statements run are counted.
+    ///
+    /// Turning coverage on starts a new [`Coverage`], and turning it off drops the counts so far.
+    /// Only statements with a known row are counted, see [`Evaluator::evaluate_at`].
+    ///
+    /// # Arguments
+    ///
+    /// * `coverage` - Whether coverage is counted.
+    pub fn set_coverage(&mut self, coverage: bool) {
+        self.coverage = coverage.then(Coverage::default);
+    }
+
+    /// Returns the coverage counted since it was turned on, if it is on.
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    /// Records a statement about to be evaluated, and the statements nested in it, as not run
+    /// yet, if coverage is on.
+    ///
+    /// # Arguments
+    ///
+    /// * `statement` - The statement.
+    /// * `row` - The row the statement starts on.
+    pub fn cover(&mut self, statement: &Stmt, row: usize) {
+        if let Some(coverage) = &mut self.coverage {
+            coverage.add(statement, row);
+        }
+    }
+
+    /// Returns the names of the functions being called, from the outermost call to the
+    /// innermost.
+    pub fn call_stack(&self) -> &[String] {
+        &self.frames
+    }
+
+    /// Sets the file the statements evaluated next are written in, which the functions they
+    /// define remember for stack traces.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The path of the file, or None for source code that is not in a file.
+    pub fn set_file(&mut self, file: Option<&Path>) {
+        self.file = file.map(Rc::from);
+    }
+
+    /// Returns the calls that were active when the last error returned by
+    /// [`Evaluator::evaluate`] or [`Evaluator::evaluate_at`] happened, forgetting them.
+    ///
+    /// # Examples
+    /// ```
+    /// use hydrogen::hash::{evaluator::Evaluator, parser::Parser};
+    ///
+    /// let mut evaluator = Evaluator::new();
+    /// let tree = Parser::new("half(n) { n / 0 }\nhalf(1)").parse_program().unwrap();
+    /// evaluator.evaluate_at(&tree[0], 1).unwrap();
+    /// assert!(evaluator.evaluate_at(&tree[1], 2).is_err());
+    ///
+    /// let trace = evaluator.take_stack_trace();
+    /// assert_eq!(trace.to_string().lines().nth(2), Some("    in `half` at line 1"));
+    /// ```
+    pub fn take_stack_trace(&mut self) -> StackTrace {
+        self.unwound.take().unwrap_or_default()
+    }
+
+    /// Returns the variables visible from the statement being evaluated, sorted by name.
+    pub fn variables(&self) -> Vec<(String, Value)> {
+        self.environment.borrow().variables()
+    }
+
+    /// Returns the number of steps run since the last call of [`Evaluator::restart_limits`].
+    pub fn steps(&self) -> u64 {
+        self.budget.steps()
+    }
+
+    /// Gives the statements evaluated next all of the steps and time programs are allowed.
+    pub fn restart_limits(&mut self) {
+        self.budget.restart();
+    }
+
+    /// Registers a native function that programs can call by name.
+    ///
+    /// Native functions take precedence over builtins of the same name, but not over functions
+    /// defined by the program.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name programs call the function with.
+    /// * `function` - The function receiving the evaluated arguments of the call.
+    pub fn register(&mut self, name: &str, function: Native) {
+        self.natives.insert(name.to_string(), function);
+    }
+
+    /// Evaluates a statement in the current environment.
+    ///
+    /// # Arguments
+    ///
+    /// * `statement` - The statement to be evaluated.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Value, RuntimeError>` - The value of the statement, or the error that stopped it.
+    pub fn evaluate(&mut self, statement: &Stmt) -> Result<Value, RuntimeError> {
+        self.forget_unwound();
+        finish(self.traced(statement, None, false))
+    }
+
+    /// Evaluates a statement written on the given row of the source code, which the debugger is
+    /// told about, see [`Evaluator::evaluate`].
+    ///
+    /// # Arguments
+    ///
+    /// * `statement` - The statement to be evaluated.
+    /// * `row` - The row the statement starts on.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Value, RuntimeError>` - The value of the statement, or the error that stopped it.
+    pub fn evaluate_at(&mut self, statement: &Stmt, row: usize) -> Result<Value, RuntimeError> {
+        self.forget_unwound();
+        finish(self.traced(statement, Some(row), false))
+    }
+
+    /// Forgets the calls of an error that was never taken, when a statement of the program
+    /// starts.
+    fn forget_unwound(&mut self) {
+        // Statements evaluated by a debugger while a function is paused keep the error of the
+        // program
+        if self.frames.is_empty() {
+            self.unwound = None;
+        }
+    }
+
+    /// Returns the calls active at the statement being evaluated, innermost first.
+    fn stack_trace(&self) -> StackTrace {
+        let path = |file: &Option<Rc<Path>>| file.as_deref().map(Path::to_path_buf);
+        let mut frames = vec![Frame {
+            function: self.frames.last().cloned(),
+            file: path(&self.file),
+            row: self.row,
+        }];
+        for (i, (file, row)) in self.calls.iter().enumerate().rev() {
+            frames.push(Frame {
+                function: i.checked_sub(1).map(|caller| self.frames[caller].clone()),
+                file: path(file),
+                row: *row,
+            });
+        }
+        StackTrace::new(frames)
+    }
+
+    /// Evaluates a statement, pausing for the debugger first and writing the statement and its
+    /// value to the trace if there is one.
+    ///
+    /// # Arguments
+    ///
+    /// * `statement` - The statement to be evaluated.
+    /// * `row` - The row the statement starts on, if it is known.
+    /// * `tail` - Whether a call making up the statement is left to the caller.
+    fn traced(&mut self, statement: &Stmt, row: Option<usize>, tail: bool) -> Flowing {
+        if let Some(mut debugger) = self.debugger.take() {
+            let paused = debugger.pause(self, row, statement);
+            self.debugger = Some(debugger);
+            paused?;
+        }
+        if let (Some(coverage), Some(row)) = (&mut self.coverage, row) {
+            coverage.hit(row);
+        }
+
+        let indent = "  ".repeat(self.frames.len());
+        if let Some(trace) = &mut self.trace {
+            let source = stmt_source(statement, 0);
+            let mut lines = source.lines();
+            let first = lines.next().unwrap_or_default();
+            let more = if lines.next().is_some() { " ..." } else { "" };
+            let _ = writeln!(trace, "{}{}{}", indent, first, more);
+        }
+
+        let outer = self.row;
+        self.row = row.or(outer);
+        let result = match statement {
+            Stmt::Expr(expression) if tail => self.tail(expression),
+            _ => self.execute(statement),
+        };
+        // The statement an error first leaves is the innermost one it happened in
+        if let (Err(Flow::Error(_)), None) = (&result, &self.unwound) {
+            self.unwound = Some(self.stack_trace());
+        }
+        self.row = outer;
+
+        if let Some(trace) = &mut self.trace {
+            let _ = match &result {
+                Ok(value) => writeln!(trace, "{}=> {}", indent, value.repr()),
+                Err(Flow::Return(value)) => writeln!(trace, "{}=> return {}", indent, value.repr()),
+                Err(Flow::Error(error)) => writeln!(trace, "{}=> error: {}", indent, error),
+                Err(_) => Ok(()),
+            };
+        }
+        result
+    }
+
+    /// Evaluates a statement, stopping early at a `return`.
+    fn execute(&mut self, statement: &Stmt) -> Flowing {
+        match statement {
+            Stmt::Item(Item::Function(definition)) => {
+                let function = self.closure(
+                    &definition.name,
+                    &definition.parameters,
+                    definition.ret,
+                    &definition.body,
+                    definition.doc.as_deref(),
+                );
+                self.environment.borrow_mut().define_function(function);
+                Ok(Value::None)
+            }
+
+            Stmt::Item(Item::Struct(definition)) => {
+                self.environment.borrow_mut().define_struct(Struct {
+                    name: definition.name.clone(),
+                    fields: definition.fields.clone(),
+                    methods: Default::default(),
+                    interfaces: Default::default(),
+                    doc: definition.doc.as_deref().map(Rc::from),
+                });
+                Ok(Value::None)
+            }
+
+            Stmt::Item(Item::Interface(definition)) => {
+                self.environment.borrow_mut().define_interface(Interface {
+                    name: definition.name.clone(),
+                    signatures: definition.signatures.clone(),
+                });
+                Ok(Value::None)
+            }
+
+            Stmt::Item(Item::Impl(implementation)) => {
+                let functions = implementation
+                    .functions()
+                    .map(|definition| {
+                        Rc::new(self.closure(
+                            &definition.name,
+                            &definition.parameters,
+                            definition.ret,
+                            &definition.body,
+                            definition.doc.as_deref(),
+                        ))
+                    })
+                    .collect();
+                implement(
+                    &self.environment,
+                    &implementation.interface,
+                    &implementation.ty,
+                    functions,
+                )?;
+                Ok(Value::None)
+            }
+
+            Stmt::Definition(definition) => {
+                let name = &definition.name;
+                if definition.compound && !self.environment.borrow().assignable(name) {
+                    return Err(RuntimeError::UndefinedAssignment(name.clone()).into());
+                }
+                let value = self.expression(&definition.value)?;
+
+                match definition.ty {
+                    Some(ty) => {
+                        check_type(ty, &value)?;
+                        self.environment.borrow_mut().define(name, value);
+                    }
+                    None if definition.constant => {
+                        self.environment.borrow_mut().define(name, value);
+                    }
+                    None => {
+                        let assigned = self.environment.borrow_mut().assign(name, value.clone());
+                        if !assigned {
+                            self.environment.borrow_mut().define(name, value);
+                        }
+                    }
+                }
+
+                Ok(Value::None)
+            }
+
+            Stmt::Declaration(declaration) => {
+                self.environment
+                    .borrow_mut()
+                    .define(&declaration.name, Value::None);
+                Ok(Value::None)
+            }
+
+            // The element is read before the value on the right of a compound assignment is
+            // computed, and the target and the index are only evaluated once
+            Stmt::Store(store) => {
+                let (target, index) = self.place(&store.target)?;
+                let value = match &store.value {
+                    Expr::Binary(binary) if store.compound => {
+                        let current = element(&target, &index)?;
+                        let right = self.expression(&binary.right)?;
+                        let value = self.arithmetic.binary(&binary.op, current, right)?;
+                        limited(value, self.max_length)?
+                    }
+                    value => self.expression(value)?,
+                };
+                self::store(&target, index, value)?;
+                Ok(Value::None)
+            }
+
+            Stmt::Destructure(destructuring) => {
+                let value = self.expression(&destructuring.value)?;
+                let values = destructure(&destructuring.pattern, &value)?;
+                let mut environment = self.environment.borrow_mut();
+                for (name, value) in destructuring.pattern.names().iter().zip(values) {
+                    if !environment.assign(name, value.clone()) {
+                        environment.define(name, value);
+                    }
+                }
+                Ok(Value::None)
+            }
+
+            Stmt::Global(names) => {
+                let mut environment = self.environment.borrow_mut();
+                names
+                    .iter()
+                    .for_each(|name| environment.declare_global(name));
+                Ok(Value::None)
+            }
+
+            Stmt::Nonlocal(names) => {
+                let mut environment = self.environment.borrow_mut();
+                match names
+                    .iter()
+                    .find(|name| !environment.declare_nonlocal(name))
+                {
+                    Some(name) => Err(RuntimeError::UndefinedVariable(name.clone()).into()),
+                    None => Ok(Value::None),
+                }
+            }
+
+            Stmt::Expr(expression) => self.expression(expression),
+
+            Stmt::Return(value) => {
+                let value = match value {
+                    Some(value) if self.tail_calls => self.tail(value)?,
+                    Some(value) => self.expression(value)?,
+                    None => Value::None,
+                };
+                Err(Flow::Return(value))
+            }
+
+            Stmt::Break => Err(Flow::Break),
+            Stmt::Continue => Err(Flow::Continue),
+        }
+    }
+
+    /// Evaluates the collection and the index or key of an element, like `items` and `0` for
+    /// `items[0]`, or `point` and `"x"` for `point.x`.
+    fn place(&mut self, expression: &Expr) -> Result<(Value, Value), Flow> {
+        match expression {
+            Expr::Index(index) => {
+                let target = self.expression(&index.target)?;
+                Ok((target, self.expression(&index.index)?))
+            }
+            Expr::Member(member) => {
+                let target = self.expression(&member.target)?;
+                Ok((target, Value::from(member.name.as_str())))
+            }
+            _ => unreachable!("only elements and keys are places"),
+        }
+    }
+
+    /// Evaluates an expression in the current environment.
+    fn expression(&mut self, expression: &Expr) -> Flowing {
+        self.budget.tick()?;
+        match expression {
+            Expr::Literal(Literal::String(value)) => Ok(Value::from(value.as_str())),
+            Expr::Literal(Literal::Boolean(value)) => Ok(Value::Boolean(*value)),
+            Expr::Literal(Literal::Number(value)) => Ok(number(value)?),
+
+            // A name refers to the closest variable, or function used as a value
+            Expr::Identifier(name) => match self.environment.borrow().lookup(name) {
+                Some(value) => Ok(value),
+                None => Err(RuntimeError::UndefinedVariable(name.clone()).into()),
+            },
+
+            Expr::Array(elements) => Ok(Value::array(self.expressions(elements)?)),
+
+            // A parenthesized expression, or a list of them
+            Expr::Group(elements) => {
+                if elements.len() == 1 {
+                    self.expression(&elements[0])
+                } else {
+                    Ok(Value::array(self.expressions(elements)?))
+                }
+            }
+
+            Expr::Unary(unary_expression) => {
+                let value = self.expression(&unary_expression.expr)?;
+                Ok(self.arithmetic.unary(&unary_expression.op, value)?)
+            }
+
+            // The right operand of `&&` and `||` is only evaluated if the left one does not
+            // already decide the result
+            Expr::Binary(binary_expression)
+                if matches!(binary_expression.op.as_str(), "&&" | "||") =>
+            {
+                let left = truthy(self.expression(&binary_expression.left)?)?;
+                if left == (binary_expression.op == "||") {
+                    return Ok(Value::Boolean(left));
+                }
+                Ok(Value::Boolean(truthy(
+                    self.expression(&binary_expression.right)?,
+                )?))
+            }
+
+            Expr::Binary(binary_expression) => {
+                let left = self.expression(&binary_expression.left)?;
+                let right = self.expression(&binary_expression.right)?;
+                let value = self.arithmetic.binary(&binary_expression.op, left, right)?;
+                Ok(limited(value, self.max_length)?)
+            }
+
+            Expr::Call(call) => {
+                let values = self.expressions(&call.arguments)?;
+                Ok(self.call(&call.name, values)?)
+            }
+
+            // The value is given to the function of its struct as the first argument
+            Expr::MethodCall(call) => {
+                let (function, values) = self.method_call(call)?;
+                Ok(self.call_function(function, values)?)
+            }
+
+            Expr::Block(block) => self.block(block),
+
+            Expr::If(statement) => {
+                if truthy(self.expression(&statement.condition)?)? {
+                    self.block(&statement.then)
+                } else {
+                    match &statement.otherwise {
+                        Some(otherwise) => self.expression(otherwise),
+                        None => Ok(Value::None),
+                    }
+                }
+            }
+
+            Expr::For(statement) => {
+                for value in self.iterate(&statement.iterable)? {
+                    let environment = Environment::with_parent(&self.environment);
+                    environment.borrow_mut().define(&statement.variable, value);
+                    match self.evaluate_in(&statement.body, environment) {
+                        Ok(_) | Err(Flow::Continue) => {}
+                        Err(Flow::Break) => break,
+                        Err(flow) => return Err(flow),
+                    }
+                }
+                Ok(Value::None)
+            }
+
+            // Only errors are caught, a `return`, `break`, or `continue` leaves the body as usual
+            Expr::Lambda(lambda) => Ok(Value::Function(Rc::new(self.closure(
+                "lambda",
+                &lambda.parameters,
+                lambda.ret,
+                &lambda.body,
+                None,
+            )))),
+
+            Expr::Try(statement) => match self.guarded(&statement.body) {
+                Err(Flow::Error(error)) if error.catchable() => {
+                    self.unwound = None;
+                    let environment = Environment::with_parent(&self.environment);
+                    environment
+                        .borrow_mut()
+                        .define(&statement.variable, Value::from(error.to_string()));
+                    self.evaluate_in(&statement.handler, environment)
+                }
+                result => result,
+            },
+
+            Expr::Cast(cast_expression) => {
+                let value = self.expression(&cast_expression.expr)?;
+                Ok(cast(value, cast_expression.ty)?)
+            }
+
+            Expr::Range(range) => {
+                let start = self.expression(&range.start)?;
+                let end = self.expression(&range.end)?;
+                Ok(self::range(&start, &end, range.inclusive)?)
+            }
+
+            Expr::Index(_) | Expr::Member(_) => {
+                let (target, index) = self.place(expression)?;
+                Ok(element(&target, &index)?)
+            }
+
+            Expr::While(statement) => {
+                while truthy(self.expression(&statement.condition)?)? {
+                    match self.block(&statement.body) {
+                        Ok(_) | Err(Flow::Continue) => {}
+                        Err(Flow::Break) => break,
+                        Err(flow) => return Err(flow),
+                    }
+                }
+                Ok(Value::None)
+            }
+        }
+    }
+
+    /// Evaluates what a `for` loop iterates over and returns its elements.
+    ///
+    /// Ranges are iterated without building an array, a number `n` is iterated like the range
+    /// `0..n`, and a string is iterated one character at a time.
+    fn iterate(&mut self, iterable: &Expr) -> Result<Box<dyn Iterator<Item = Value>>, Flow> {
+        match self.expression(iterable)? {
+            Value::Integer(count) => Ok(Box::new((0..count).map(Value::Integer))),
+            Value::Range(start, end, inclusive) => {
+                Ok(Box::new(numbers(start, end, inclusive).map(Value::Integer)))
+            }
+            // The loop sees the elements the array holds when it starts
+            Value::Array(elements) => Ok(Box::new(elements.borrow().clone().into_iter())),
+            Value::String(string) => Ok(Box::new(
+                string
+                    .chars()
+                    .map(|c| Value::from(c.to_string()))
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            )),
+            value => Err(RuntimeError::TypeMismatch(
+                "iterable".to_string(),
+                value.type_name().to_string(),
+            )
+            .into()),
+        }
+    }
+
+    /// Creates a function that captures the current environment.
+    fn closure(
+        &self,
+        name: &str,
+        parameters: &[Parameter],
+        ret: Option<TypeExpr>,
+        body: &Block,
+        doc: Option<&str>,
+    ) -> Function {
+        Function {
+            name: name.to_string(),
+            parameters: parameters.to_vec(),
+            ret,
+            body: body.clone(),
+            code: None,
+            closure: Rc::clone(&self.environment),
+            file: self.file.clone(),
+            doc: doc.map(Rc::from),
+        }
+    }
+
+    /// Evaluates expressions in order.
+    fn expressions(&mut self, expressions: &[Expr]) -> Result<Vec<Value>, Flow> {
+        expressions
+            .iter()
+            .map(|expression| self.expression(expression))
+            .collect()
+    }
+
+    /// Evaluates the value and the arguments of a call of a function a struct implements, and
+    /// returns the function of the struct of the value with the arguments it is called with.
+    fn method_call(&mut self, call: &MethodCall) -> Result<(Rc<Function>, Vec<Value>), Flow> {
+        let target = self.expression(&call.target)?;
+        let mut values = vec![target];
+        values.extend(self.expressions(&call.arguments)?);
+        Ok((method(&values[0], &call.name)?, values))
+    }
+
+    /// Evaluates an expression whose value the current function returns.
+    ///
+    /// A call of a function defined by the program is not made, but left to the caller of the
+    /// current function, so that it does not nest inside the current call.
+    fn tail(&mut self, expression: &Expr) -> Flowing {
+        match expression {
+            Expr::Call(call) => {
+                let values = self.expressions(&call.arguments)?;
+                match self.function(&call.name) {
+                    Some(function) => Err(Flow::TailCall(function, values)),
+                    None => Ok(self.call(&call.name, values)?),
+                }
+            }
+
+            Expr::MethodCall(call) => {
+                let (function, values) = self.method_call(call)?;
+                Err(Flow::TailCall(function, values))
+            }
+
+            Expr::Group(elements) if elements.len() == 1 => self.tail(&elements[0]),
+
+            Expr::Block(block) => {
+                let environment = Environment::with_parent(&self.environment);
+                self.evaluate_tail(block, environment)
+            }
+
+            Expr::If(statement) => {
+                if truthy(self.expression(&statement.condition)?)? {
+                    let environment = Environment::with_parent(&self.environment);
+                    self.evaluate_tail(&statement.then, environment)
+                } else {
+                    match &statement.otherwise {
+                        Some(otherwise) => self.tail(otherwise),
+                        None => Ok(Value::None),
+                    }
+                }
+            }
+
+            _ => self.expression(expression),
         }
     }
 
-    /// Advances the parser and returns the next ast node.
-    fn next(&mut self) -> Result<Node, Error> {
-        self.parser.parse()
+    /// Evaluates the body of a `try`, where a `return` cannot leave its call to the caller,
+    /// since the errors of the call must be caught.
+    fn guarded(&mut self, body: &Block) -> Flowing {
+        let tail_calls = std::mem::replace(&mut self.tail_calls, false);
+        let result = self.block(body);
+        self.tail_calls = tail_calls;
+        result
     }
 
-    /// Validates the input content and returns the AST or errors.
+    /// Evaluates a block in a new scope nested inside the current one.
+    fn block(&mut self, block: &Block) -> Flowing {
+        let environment = Environment::with_parent(&self.environment);
+        self.evaluate_in(block, environment)
+    }
+
+    /// Evaluates the statements of a block inside the given environment, restoring the current
+    /// one afterwards.
     ///
-    /// # Arguments
+    /// # Returns
     ///
-    /// * `content` - The content to be parsed and validated.
+    /// * `Flowing` - The value of the last statement, or none if there are no statements.
+    fn evaluate_in(&mut self, block: &Block, environment: Env) -> Flowing {
+        self.statements(block, environment, false)
+    }
+
+    /// Evaluates statements whose value the current function returns, like
+    /// [`Evaluator::evaluate_in`], but leaves a call in the last statement to the caller.
+    fn evaluate_tail(&mut self, block: &Block, environment: Env) -> Flowing {
+        self.statements(block, environment, true)
+    }
+
+    /// Evaluates statements inside the given environment, restoring the current one afterwards.
+    fn statements(&mut self, block: &Block, environment: Env, tail: bool) -> Flowing {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let statements = &block.statements;
+
+        let mut result = Ok(Value::None);
+        for (i, statement) in statements.iter().enumerate() {
+            let row = block.rows.get(i).copied();
+            result = self.traced(statement, row, tail && i + 1 == statements.len());
+            if result.is_err() {
+                break;
+            }
+        }
+
+        self.environment = previous;
+        result
+    }
+
+    /// Calls the function with the given name.
     ///
-    /// # Returns
+    /// The closest function defined by the program or held by a variable takes precedence over
+    /// structs, whose values are created by calling them, which take precedence over native
+    /// functions, which take precedence over builtins of the same name.
+    fn call(&mut self, name: &str, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        if let Some(function) = self.function(name) {
+            return self.call_function(function, args);
+        }
+        if let Some(definition) = self.environment.borrow().get_struct(name) {
+            return construct(&definition, args);
+        }
+
+        match self.natives.get(name) {
+            Some(native) => native(&args),
+            None => match builtins::lookup(name) {
+                Some(builtin) => builtin(self, &args),
+                None => Err(RuntimeError::UndefinedFunction(name.to_string())),
+            },
+        }
+    }
+
+    /// Calls a function defined by the program.
     ///
-    /// * `Result<Tree, Errors>` - Ok(Tree) if parsing is successful, Err(Errors) if there are errors.
-    fn validate(&mut self) -> bool {
-        match self.next() {
-            Ok(statement) => self.check_types(statement),
-            Err(_) => false,
+    /// The value the function returns, early or from its last statement, is checked against its
+    /// declared return type. A call the function leaves to its caller is made here, in place of
+    /// the function, and its value is checked against the return types of both.
+    fn call_function(
+        &mut self,
+        mut function: Rc<Function>,
+        mut args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let mut returns = Vec::new();
+
+        let value = loop {
+            if function.parameters.len() != args.len() {
+                return Err(RuntimeError::ArgumentCount(
+                    function.name.clone(),
+                    function.parameters.len(),
+                    args.len(),
+                ));
+            }
+
+            if self.frames.len() >= self.max_depth {
+                return Err(RuntimeError::RecursionLimit(self.max_depth));
+            }
+
+            let environment = Environment::with_caller(&function.closure);
+            bind(&environment, &function.parameters, args)?;
+            if let Some(ret) = function.ret {
+                if !returns.contains(&ret) {
+                    returns.push(ret);
+                }
+            }
+
+            self.frames.push(function.name.clone());
+            self.calls.push((self.file.clone(), self.row));
+            let file = std::mem::replace(&mut self.file, function.file.clone());
+            if let Some(profiler) = &mut self.profiler {
+                profiler.enter(&function.name);
+            }
+            let tail_calls = std::mem::replace(&mut self.tail_calls, true);
+            let result = self.evaluate_tail(&function.body, environment);
+            self.tail_calls = tail_calls;
+            if let Some(profiler) = &mut self.profiler {
+                profiler.leave();
+            }
+            self.file = file;
+            self.calls.pop();
+            self.frames.pop();
+
+            match result {
+                Err(Flow::TailCall(next, next_args)) => {
+                    function = next;
+                    args = next_args;
+                }
+                result => break finish(result)?,
+            }
+        };
+
+        for ret in returns {
+            check_type(ret, &value)?;
+        }
+        Ok(value)
+    }
+}
+
+impl Caller for Evaluator {
+    fn call_value(&mut self, function: &Value, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        match function {
+            Value::Function(function) => self.call_function(Rc::clone(function), args),
+            _ => Err(RuntimeError::TypeMismatch(
+                "fn".to_string(),
+                function.type_name().to_string(),
+            )),
+        }
+    }
+
+    fn function(&self, name: &str) -> Option<Rc<Function>> {
+        match self.environment.borrow().lookup(name) {
+            Some(Value::Function(function)) => Some(function),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the value a function body or a program statement ends with.
+///
+/// A `return` ends it with its value, and a `break` or `continue` that no loop caught is an
+/// error.
+fn finish(result: Flowing) -> Result<Value, RuntimeError> {
+    match result {
+        Ok(value) | Err(Flow::Return(value)) => Ok(value),
+        Err(Flow::Error(error)) => Err(error),
+        Err(Flow::Break) => Err(RuntimeError::OutsideLoop("break".to_string())),
+        Err(Flow::Continue) => Err(RuntimeError::OutsideLoop("continue".to_string())),
+        Err(Flow::TailCall(..)) => unreachable!("only the body of a function leaves calls"),
+    }
+}
+
+/// Returns the whole numbers from a start up to an end, in order.
+///
+/// # Arguments
+///
+/// * `start` - The first number.
+/// * `end` - The number the numbers stop before, or the last number if it is included.
+/// * `inclusive` - Whether the end is one of the numbers, as with `..=`.
+///
+/// # Returns
+///
+/// * `RangeInclusive<i64>` - The numbers, empty if the end comes before the start.
+pub(crate) fn numbers(start: i64, end: i64, inclusive: bool) -> RangeInclusive<i64> {
+    match inclusive {
+        true => start..=end,
+        // The last number is before the end, which cannot underflow if the end is after the start
+        false if end > start => start..=end - 1,
+        false => RangeInclusive::new(1, 0),
+    }
+}
+
+/// Returns how many whole numbers there are from a start up to an end, as with [`numbers`].
+pub(crate) fn range_length(start: i64, end: i64, inclusive: bool) -> usize {
+    let length = end as i128 - start as i128 + inclusive as i128;
+    usize::try_from(length.max(0)).unwrap_or(usize::MAX)
+}
+
+/// Checks that a length is within a limit.
+pub(crate) fn limit(length: usize, max_length: usize) -> Result<(), RuntimeError> {
+    if length > max_length {
+        return Err(RuntimeError::TooLarge(max_length));
+    }
+    Ok(())
+}
+
+/// Checks that a string or array is within a length limit, and returns it.
+pub(crate) fn limited(value: Value, max_length: usize) -> Result<Value, RuntimeError> {
+    match &value {
+        Value::String(string) => limit(string.len(), max_length)?,
+        Value::Array(elements) => limit(elements.borrow().len(), max_length)?,
+        _ => {}
+    }
+    Ok(value)
+}
+
+/// Returns the range from a start up to an end, which must be whole numbers.
+///
+/// # Arguments
+///
+/// * `start` - The first number of the range.
+/// * `end` - The number the range stops before, or the last number of an inclusive range.
+/// * `inclusive` - Whether the range holds its end, as with `..=`.
+///
+/// # Returns
+///
+/// * `Result<Value, RuntimeError>` - The range, or an error if a bound is not a whole number.
+pub(crate) fn range(start: &Value, end: &Value, inclusive: bool) -> Result<Value, RuntimeError> {
+    let op = match inclusive {
+        true => "..=",
+        false => "..",
+    };
+    match (start, end) {
+        (Value::Integer(start), Value::Integer(end)) => Ok(Value::Range(*start, *end, inclusive)),
+        _ => Err(RuntimeError::InvalidOperands(
+            op.to_string(),
+            vec![start.type_name().to_string(), end.type_name().to_string()],
+        )),
+    }
+}
+
+/// Returns the element of an array, a string, or a range at a position, the value of a key of a
+/// map, or the elements of an array or the characters of a string at the positions of a range.
+///
+/// A position must be inside the collection, but a range of positions is clamped to it like
+/// [`slice`] does, as `slice()` is the same as indexing with the range.
+///
+/// # Arguments
+///
+/// * `target` - The collection holding the element.
+/// * `index` - The position of the element, counted from 0, the key, or the range of positions.
+///
+/// # Returns
+///
+/// * `Result<Value, RuntimeError>` - The element, or an error if there is none.
+pub(crate) fn element(target: &Value, index: &Value) -> Result<Value, RuntimeError> {
+    match (target, index) {
+        (Value::Array(_) | Value::String(_), Value::Range(start, end, inclusive)) => {
+            slice(target, *start, end.saturating_add(*inclusive as i64))
+        }
+        (Value::Array(elements), Value::Integer(i)) => {
+            let elements = elements.borrow();
+            position(*i, elements.len()).map(|i| elements[i].clone())
+        }
+        (Value::String(string), Value::Integer(i)) => {
+            let length = string.chars().count();
+            let i = position(*i, length)?;
+            Ok(Value::from(
+                string.chars().nth(i).unwrap_or_default().to_string(),
+            ))
+        }
+        (Value::Range(start, end, inclusive), Value::Integer(i)) => {
+            let i = position(*i, range_length(*start, *end, *inclusive))?;
+            Ok(Value::Integer(start + i as i64))
+        }
+        (Value::Map(entries), Value::String(key)) => match entries.borrow().get(key.as_ref()) {
+            Some(value) => Ok(value.clone()),
+            None => Err(RuntimeError::MissingKey(key.to_string())),
+        },
+        (Value::Instance(instance), Value::String(key)) => match instance.ty.field(key) {
+            Some(i) => Ok(instance.fields.borrow()[i].clone()),
+            None => Err(RuntimeError::UndefinedField(
+                instance.ty.name.clone(),
+                key.to_string(),
+            )),
+        },
+        _ => Err(RuntimeError::InvalidOperands(
+            "[]".to_string(),
+            vec![
+                target.type_name().to_string(),
+                index.type_name().to_string(),
+            ],
+        )),
+    }
+}
+
+/// Returns the elements of an array, or the characters of a string, at the positions from a start
+/// up to an end.
+///
+/// The positions the array or string does not have are left out, so a range reaching past its
+/// end stops at the end, and an empty range gives an empty array or string.
+///
+/// # Arguments
+///
+/// * `target` - The array or string.
+/// * `start` - The first position, counted from 0.
+/// * `end` - The position after the last one.
+///
+/// # Returns
+///
+/// * `Result<Value, RuntimeError>` - The elements or characters, or an error if the target is
+///   neither an array nor a string.
+pub(crate) fn slice(target: &Value, start: i64, end: i64) -> Result<Value, RuntimeError> {
+    let start = usize::try_from(start).unwrap_or(0);
+    let end = usize::try_from(end).unwrap_or(0).max(start);
+
+    match target {
+        Value::String(string) => Ok(Value::from(
+            string
+                .chars()
+                .skip(start)
+                .take(end - start)
+                .collect::<String>(),
+        )),
+        Value::Array(elements) => {
+            let elements = elements.borrow();
+            let end = end.min(elements.len());
+            let start = start.min(end);
+            Ok(Value::array(elements[start..end].to_vec()))
+        }
+        _ => Err(RuntimeError::TypeMismatch(
+            "array".to_string(),
+            target.type_name().to_string(),
+        )),
+    }
+}
+
+/// Gives an element of an array a value, a key of a map, adding the key if the map does not have
+/// it, or a field of a struct, which must be given a value of the type it is declared with.
+///
+/// # Arguments
+///
+/// * `target` - The collection holding the element.
+/// * `index` - The position of the element, counted from 0, or the key.
+/// * `value` - The value given to the element.
+///
+/// # Returns
+///
+/// * `Result<(), RuntimeError>` - Ok(()), or an error if the array or the struct has no such
+///   element or the collection cannot be changed, as strings and ranges cannot.
+pub(crate) fn store(target: &Value, index: Value, value: Value) -> Result<(), RuntimeError> {
+    match (target, index) {
+        (Value::Array(elements), Value::Integer(i)) => {
+            let mut elements = elements.borrow_mut();
+            let i = position(i, elements.len())?;
+            elements[i] = value;
+            Ok(())
+        }
+        (Value::Map(entries), Value::String(key)) => {
+            entries.borrow_mut().insert(key.to_string(), value);
+            Ok(())
+        }
+        (Value::Instance(instance), Value::String(key)) => {
+            let i = instance.ty.field(&key).ok_or_else(|| {
+                RuntimeError::UndefinedField(instance.ty.name.clone(), key.to_string())
+            })?;
+            if let Some(ty) = instance.ty.fields[i].ty {
+                check_type(ty, &value)?;
+            }
+            instance.fields.borrow_mut()[i] = value;
+            Ok(())
+        }
+        (_, index) => Err(RuntimeError::InvalidOperands(
+            "[]=".to_string(),
+            vec![
+                target.type_name().to_string(),
+                index.type_name().to_string(),
+            ],
+        )),
+    }
+}
+
+/// Creates a value of a struct, giving its fields the arguments in order.
+///
+/// # Arguments
+///
+/// * `ty` - The struct the value is created from.
+/// * `args` - The values of the fields, which must be of the types they are declared with.
+///
+/// # Returns
+///
+/// * `Result<Value, RuntimeError>` - The value, or an error if there are not as many arguments
+///   as fields, or an argument is not of the type of its field.
+pub(crate) fn construct(ty: &Rc<Struct>, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if ty.fields.len() != args.len() {
+        return Err(RuntimeError::ArgumentCount(
+            ty.name.clone(),
+            ty.fields.len(),
+            args.len(),
+        ));
+    }
+    for (field, value) in ty.fields.iter().zip(&args) {
+        if let Some(ty) = field.ty {
+            check_type(ty, value)?;
+        }
+    }
+    Ok(Value::instance(Rc::clone(ty), args))
+}
+
+/// Returns the function with the given name the struct of a value implements, which is looked up
+/// when it is called, so the same call runs the function of whichever struct the value has.
+///
+/// # Returns
+///
+/// * `Result<Rc<Function>, RuntimeError>` - The function, or an error if the value is not of a
+///   struct or its struct does not implement the function.
+pub(crate) fn method(target: &Value, name: &str) -> Result<Rc<Function>, RuntimeError> {
+    let function = match target {
+        Value::Instance(instance) => instance.ty.methods.borrow().get(name).cloned(),
+        _ => None,
+    };
+    function.ok_or_else(|| {
+        let ty = match target {
+            Value::Instance(instance) => instance.ty.name.clone(),
+            _ => target.type_name().to_string(),
+        };
+        RuntimeError::UndefinedMethod(ty, name.to_string())
+    })
+}
+
+/// Implements an interface for a struct, after checking that the functions of an `impl` block
+/// are exactly the ones the interface requires.
+///
+/// # Arguments
+///
+/// * `environment` - The environment the struct and the interface are looked up in. An
+///   interface it does not define may be one built into the language, like `Compare`.
+/// * `interface` - The name of the interface.
+/// * `ty` - The name of the struct.
+/// * `functions` - The functions of the `impl` block.
+///
+/// # Returns
+///
+/// * `Result<(), RuntimeError>` - Ok(()), or an error if the struct or the interface is not
+///   defined, or the functions do not match the signatures of the interface.
+pub(crate) fn implement(
+    environment: &Env,
+    interface: &str,
+    ty: &str,
+    functions: Vec<Rc<Function>>,
+) -> Result<(), RuntimeError> {
+    let definition = environment
+        .borrow()
+        .get_struct(ty)
+        .ok_or_else(|| RuntimeError::UndefinedStruct(ty.to_string()))?;
+    let required = environment
+        .borrow()
+        .get_interface(interface)
+        .or_else(|| builtins::interface(interface).map(Rc::new))
+        .ok_or_else(|| RuntimeError::UndefinedInterface(interface.to_string()))?;
+
+    for function in &functions {
+        let Some(signature) = (required.signatures.iter()).find(|s| s.name == function.name) else {
+            return Err(RuntimeError::UndefinedMethod(
+                interface.to_string(),
+                function.name.clone(),
+            ));
+        };
+        if !signature.accepts(&function.parameters, function.ret) {
+            return Err(RuntimeError::SignatureMismatch(
+                interface.to_string(),
+                signature.to_string(),
+                function.signature(&function.name),
+            ));
+        }
+    }
+    if let Some(signature) = (required.signatures.iter())
+        .find(|signature| !functions.iter().any(|f| f.name == signature.name))
+    {
+        return Err(RuntimeError::Unimplemented(
+            ty.to_string(),
+            interface.to_string(),
+            signature.name.clone(),
+        ));
+    }
+
+    let mut methods = definition.methods.borrow_mut();
+    for function in functions {
+        methods.insert(function.name.clone(), function);
+    }
+    definition
+        .interfaces
+        .borrow_mut()
+        .insert(interface.to_string());
+    Ok(())
+}
+
+/// Returns a position as an index into a collection of the given length, if it is inside it.
+fn position(index: i64, length: usize) -> Result<usize, RuntimeError> {
+    match usize::try_from(index) {
+        Ok(i) if i < length => Ok(i),
+        _ => Err(RuntimeError::IndexOutOfBounds(index, length)),
+    }
+}
+
+/// Returns the start and end of a range, which must be whole numbers.
+pub(crate) fn bounds(start: &Value, end: &Value) -> Result<(i64, i64), RuntimeError> {
+    match (start, end) {
+        (Value::Integer(start), Value::Integer(end)) => Ok((*start, *end)),
+        _ => Err(RuntimeError::InvalidOperands(
+            "..".to_string(),
+            vec![start.type_name().to_string(), end.type_name().to_string()],
+        )),
+    }
+}
+
+/// Returns the value of a condition, which must be a boolean.
+pub(crate) fn truthy(value: Value) -> Result<bool, RuntimeError> {
+    match value {
+        Value::Boolean(value) => Ok(value),
+        _ => Err(RuntimeError::TypeMismatch(
+            "bool".to_string(),
+            value.type_name().to_string(),
+        )),
+    }
+}
+
+/// Checks that a value matches the type it is declared with.
+pub(crate) fn check_type(ty: TypeExpr, value: &Value) -> Result<(), RuntimeError> {
+    let expected = ty.name();
+
+    if value.type_name() == expected {
+        Ok(())
+    } else {
+        Err(RuntimeError::TypeMismatch(
+            expected.to_string(),
+            value.type_name().to_string(),
+        ))
+    }
+}
+
+/// Converts a value to the given type.
+///
+/// Numbers, strings, and booleans convert to each other: a boolean is the number 1 or 0, a
+/// number is true unless it is zero, and a string must spell the number or boolean it becomes.
+pub(crate) fn cast(value: Value, ty: TypeExpr) -> Result<Value, RuntimeError> {
+    let invalid = |value: &Value| RuntimeError::InvalidCast(value.repr(), ty.name().to_string());
+
+    match (ty, &value) {
+        (TypeExpr::String, _) if matches!(value.type_name(), "num" | "bool") => {
+            Ok(Value::from(value.to_string()))
+        }
+        (TypeExpr::Number, Value::Boolean(boolean)) => Ok(Value::Integer(*boolean as i64)),
+        (TypeExpr::Number, Value::String(string)) => match string.trim().parse::<i64>() {
+            Ok(number) => Ok(Value::Integer(number)),
+            Err(_) => match string.trim().parse::<f64>() {
+                Ok(number) if number.is_finite() => Ok(Value::Float(number)),
+                _ => Err(invalid(&value)),
+            },
+        },
+        (TypeExpr::Boolean, _) if value.type_name() == "num" => {
+            Ok(Value::Boolean(value.as_float() != Some(0.0)))
+        }
+        (TypeExpr::Boolean, Value::String(string)) => match string.as_ref() {
+            "true" => Ok(Value::Boolean(true)),
+            "false" => Ok(Value::Boolean(false)),
+            _ => Err(invalid(&value)),
+        },
+        _ if value.type_name() == ty.name() => Ok(value),
+        _ => Err(invalid(&value)),
+    }
+}
+
+/// Takes a value apart into the values a pattern gives its variables, in the order of its names.
+///
+/// An array pattern takes an array with exactly as many elements as it has names, and a map
+/// pattern a map holding every key it names.
+pub(crate) fn destructure(pattern: &Pattern, value: &Value) -> Result<Vec<Value>, RuntimeError> {
+    let mismatch = |value: String| RuntimeError::PatternMismatch(pattern.to_string(), value);
+
+    match (pattern, value) {
+        (Pattern::Array(names), Value::Array(elements)) => {
+            let elements = elements.borrow();
+            match elements.len() == names.len() {
+                true => Ok(elements.clone()),
+                false => Err(mismatch(format!("an array of {} elements", elements.len()))),
+            }
+        }
+        (Pattern::Map(names), Value::Map(entries)) => {
+            let entries = entries.borrow();
+            names
+                .iter()
+                .map(|name| {
+                    entries
+                        .get(name)
+                        .cloned()
+                        .ok_or_else(|| mismatch(format!("a map without `{}`", name)))
+                })
+                .collect()
+        }
+        _ => Err(mismatch(format!("a value of type `{}`", value.type_name()))),
+    }
+}
+
+/// Defines the parameters of a function in the environment of a call, taking apart the
+/// arguments of parameters that are patterns.
+pub(crate) fn bind(
+    environment: &Env,
+    parameters: &[Parameter],
+    args: Vec<Value>,
+) -> Result<(), RuntimeError> {
+    let mut environment = environment.borrow_mut();
+    for (parameter, value) in parameters.iter().zip(args) {
+        match &parameter.pattern {
+            Some(pattern) => {
+                for (name, value) in pattern.names().iter().zip(destructure(pattern, &value)?) {
+                    environment.define(name, value);
+                }
+            }
+            None => environment.define(&parameter.name, value),
+        }
+    }
+    Ok(())
+}
+
+/// Reads the value of a number literal.
+///
+/// A literal too large for a whole number is a fractional number, unless the `bignum` feature
+/// makes it a big integer.
+pub(crate) fn number(literal: &str) -> Result<Value, RuntimeError> {
+    if let Ok(number) = literal.parse::<i64>() {
+        return Ok(Value::Integer(number));
+    }
+    #[cfg(feature = "bignum")]
+    if let Some(number) = bignum::parse(literal) {
+        return Ok(number);
+    }
+    literal
+        .parse::<f64>()
+        .map(Value::Float)
+        .map_err(|_| RuntimeError::InvalidNumber(literal.to_string()))
+}
+
+/// Applies a unary operator to a value.
+pub(crate) fn unary(op: &str, value: Value) -> Result<Value, RuntimeError> {
+    match (op, &value) {
+        ("-", Value::Integer(value)) => match value.checked_neg() {
+            Some(value) => Ok(Value::Integer(value)),
+            None => Err(RuntimeError::Overflow("-".to_string())),
+        },
+        ("-", Value::Float(value)) => Ok(Value::Float(-value)),
+        ("+", Value::Integer(_) | Value::Float(_)) => Ok(value),
+        ("!", Value::Boolean(value)) => Ok(Value::Boolean(!value)),
+        _ => Err(RuntimeError::InvalidOperands(
+            op.to_string(),
+            vec![value.type_name().to_string()],
+        )),
+    }
+}
+
+/// Applies a unary operator to a value like [`unary`], except that negating the smallest whole
+/// number wraps around to itself.
+fn wrapping_unary(op: &str, value: Value) -> Result<Value, RuntimeError> {
+    match (op, &value) {
+        ("-", Value::Integer(value)) => Ok(Value::Integer(value.wrapping_neg())),
+        _ => unary(op, value),
+    }
+}
+
+/// Tells if two values are equal, the way `==` does.
+///
+/// Numbers are equal when they have the same value, whether they are whole or fractional, and
+/// strings, booleans, and none when they are the same. Arrays are equal when their elements are
+/// equal in turn, maps when they have the same keys with equal values, and functions only to
+/// themselves. Values of different types are never equal.
+///
+/// An array or a map is equal to itself without comparing its elements, and two arrays or maps
+/// already being compared are taken to be equal where they appear inside themselves, which keeps
+/// arrays that hold themselves from being compared forever.
+pub(crate) fn equal(left: &Value, right: &Value) -> bool {
+    equal_in(left, right, &mut Vec::new())
+}
+
+/// Tells if two values are equal inside the pairs of arrays and maps being compared, whose
+/// addresses are `open`, see [`equal`].
+fn equal_in(left: &Value, right: &Value, open: &mut Vec<(*const (), *const ())>) -> bool {
+    match (left, right) {
+        (Value::Integer(l), Value::Integer(r)) => l == r,
+        (Value::Array(l), Value::Array(r)) if Rc::ptr_eq(l, r) => true,
+        (Value::Map(l), Value::Map(r)) if Rc::ptr_eq(l, r) => true,
+        (Value::Array(_), Value::Array(_)) | (Value::Map(_), Value::Map(_)) => {
+            let pair = (left.address().unwrap(), right.address().unwrap());
+            if open.contains(&pair) {
+                return true;
+            }
+            open.push(pair);
+            let equal = match (left, right) {
+                (Value::Array(l), Value::Array(r)) => {
+                    let (l, r) = (l.borrow(), r.borrow());
+                    l.len() == r.len() && l.iter().zip(r.iter()).all(|(l, r)| equal_in(l, r, open))
+                }
+                (Value::Map(l), Value::Map(r)) => {
+                    let (l, r) = (l.borrow(), r.borrow());
+                    l.len() == r.len()
+                        && l.iter()
+                            .zip(r.iter())
+                            .all(|((lk, lv), (rk, rv))| lk == rk && equal_in(lv, rv, open))
+                }
+                _ => unreachable!("both values are arrays or both are maps"),
+            };
+            open.pop();
+            equal
+        }
+        (Value::Function(l), Value::Function(r)) => Rc::ptr_eq(l, r),
+        // Values of structs are equal if they come from the same struct and their fields are
+        (Value::Instance(l), Value::Instance(r)) if Rc::ptr_eq(l, r) => true,
+        (Value::Instance(l), Value::Instance(r)) => {
+            let pair = (left.address().unwrap(), right.address().unwrap());
+            if l.ty != r.ty || open.contains(&pair) {
+                return l.ty == r.ty;
+            }
+            open.push(pair);
+            let (lf, rf) = (l.fields.borrow(), r.fields.borrow());
+            let equal = lf.iter().zip(rf.iter()).all(|(l, r)| equal_in(l, r, open));
+            open.pop();
+            equal
+        }
+        // `0..=2` is the same range as `0..3`
+        (Value::Range(ls, le, li), Value::Range(rs, re, ri)) => {
+            ls == rs && *le as i128 + *li as i128 == *re as i128 + *ri as i128
         }
+        #[cfg(feature = "bignum")]
+        (Value::BigInteger(_), _) | (_, Value::BigInteger(_)) => bignum::equal(left, right),
+        _ => match (left.as_float(), right.as_float()) {
+            (Some(l), Some(r)) => l == r,
+            _ => left == right,
+        },
     }
+}
+
+/// Orders two values, the way `<`, `<=`, `>`, and `>=` do.
+///
+/// Numbers are ordered by value and strings by their characters, in the order of Unicode. Arrays
+/// are ordered by their first elements that differ, and an array that another one starts with
+/// comes before it. Two arrays already being compared are taken to be equal where they appear
+/// inside themselves.
+///
+/// # Returns
+///
+/// * `Result<Option<cmp::Ordering>, RuntimeError>` - How the left value is ordered against the
+///   right one, None if a number is not a number, or the error for values that cannot be
+///   ordered, like booleans or values of different types.
+fn compare(op: &str, left: &Value, right: &Value) -> Result<Option<cmp::Ordering>, RuntimeError> {
+    compare_in(op, left, right, &mut Vec::new())
+}
 
-    fn check_types(&mut self, statement: Node) -> bool {
-        println!("{:?}", statement);
-        true
+/// Orders two values inside the pairs of arrays being compared, whose addresses are `open`, see
+/// [`compare`].
+fn compare_in(
+    op: &str,
+    left: &Value,
+    right: &Value,
+    open: &mut Vec<(*const (), *const ())>,
+) -> Result<Option<cmp::Ordering>, RuntimeError> {
+    match (left, right) {
+        (Value::Integer(l), Value::Integer(r)) => Ok(Some(l.cmp(r))),
+        (Value::String(l), Value::String(r)) => Ok(Some(l.cmp(r))),
+        (Value::Array(l), Value::Array(r)) if Rc::ptr_eq(l, r) => Ok(Some(cmp::Ordering::Equal)),
+        (Value::Array(l), Value::Array(r)) => {
+            let pair = (Rc::as_ptr(l) as *const (), Rc::as_ptr(r) as *const ());
+            if open.contains(&pair) {
+                return Ok(Some(cmp::Ordering::Equal));
+            }
+            open.push(pair);
+            let ordering = (|| {
+                let (l, r) = (l.borrow(), r.borrow());
+                for (l, r) in l.iter().zip(r.iter()) {
+                    match compare_in(op, l, r, open)? {
+                        Some(cmp::Ordering::Equal) => {}
+                        ordering => return Ok(ordering),
+                    }
+                }
+                Ok(Some(l.len().cmp(&r.len())))
+            })();
+            open.pop();
+            ordering
+        }
+        #[cfg(feature = "bignum")]
+        (Value::BigInteger(_), _) | (_, Value::BigInteger(_))
+            if left.as_float().is_some() && right.as_float().is_some() =>
+        {
+            Ok(bignum::compare(left, right))
+        }
+        _ => match (left.as_float(), right.as_float()) {
+            (Some(l), Some(r)) => Ok(l.partial_cmp(&r)),
+            _ => Err(RuntimeError::InvalidOperands(
+                op.to_string(),
+                vec![left.type_name().to_string(), right.type_name().to_string()],
+            )),
+        },
     }
+}
+
+/// Applies a binary operator to two values.
+///
+/// Arithmetic on two whole numbers gives a whole number when the result is one, and a
+/// fractional number otherwise:
+///
+/// * `%` gives the remainder of the division of the left operand by the right one, with the sign
+///   of the right one, so `-7 % 3` is `2` and `7 % -3` is `-2`, for fractional numbers too.
+/// * `^` raises the left operand to the power of the right one. It is a whole number for a whole
+///   base and exponent that is not negative.
+///
+/// A zero right operand of `/` and `%`, or a zero base raised to a negative power, is a
+/// [`RuntimeError::DivisionByZero`], and a whole number result too large for a whole number is
+/// a [`RuntimeError::Overflow`], see [`wrapping_binary`] for arithmetic that wraps around.
+pub(crate) fn binary(op: &str, left: Value, right: Value) -> Result<Value, RuntimeError> {
+    let invalid = |left: &Value, right: &Value| {
+        RuntimeError::InvalidOperands(
+            op.to_string(),
+            vec![left.type_name().to_string(), right.type_name().to_string()],
+        )
+    };
 
-    pub fn eval(&mut self) {
-        let mut results: Nodes = Vec::new();
-        let mut errors: Errors = Vec::new();
+    match op {
+        "+" | "-" | "*" | "/" => match (&left, &right) {
+            (Value::String(l), Value::String(r)) if op == "+" => {
+                Ok(Value::from(format!("{}{}", l, r)))
+            }
+
+            (Value::Integer(l), Value::Integer(r)) => {
+                let result = match op {
+                    "+" => l.checked_add(*r),
+                    "-" => l.checked_sub(*r),
+                    "*" => l.checked_mul(*r),
+                    _ if *r == 0 => return Err(RuntimeError::DivisionByZero),
+                    _ => match l.checked_rem(*r) {
+                        Some(0) | None => l.checked_div(*r),
+                        Some(_) => return Ok(Value::Float(*l as f64 / *r as f64)),
+                    },
+                };
+                result
+                    .map(Value::Integer)
+                    .ok_or_else(|| RuntimeError::Overflow(op.to_string()))
+            }
 
-        loop {
-            match self.next() {
-                Ok(node) => match *node {
-                    ASTNode::End => break,
+            _ => match (left.as_float(), right.as_float()) {
+                (Some(l), Some(r)) => match op {
+                    "+" => Ok(Value::Float(l + r)),
+                    "-" => Ok(Value::Float(l - r)),
+                    "*" => Ok(Value::Float(l * r)),
                     _ => {
-                        results.push(node);
+                        if r == 0.0 {
+                            Err(RuntimeError::DivisionByZero)
+                        } else {
+                            Ok(Value::Float(l / r))
+                        }
                     }
                 },
-                Err(error) => {
-                    errors.push(error);
+                _ => Err(invalid(&left, &right)),
+            },
+        },
+
+        "%" => match (&left, &right) {
+            (Value::Integer(_), Value::Integer(0)) => Err(RuntimeError::DivisionByZero),
+            (Value::Integer(l), Value::Integer(r)) => {
+                let remainder = l.wrapping_rem(*r);
+                match remainder != 0 && (remainder < 0) != (*r < 0) {
+                    true => Ok(Value::Integer(remainder + r)),
+                    false => Ok(Value::Integer(remainder)),
+                }
+            }
+            _ => match (left.as_float(), right.as_float()) {
+                (Some(_), Some(0.0)) => Err(RuntimeError::DivisionByZero),
+                (Some(l), Some(r)) => {
+                    let remainder = l % r;
+                    match remainder != 0.0 && (remainder < 0.0) != (r < 0.0) {
+                        true => Ok(Value::Float(remainder + r)),
+                        false => Ok(Value::Float(remainder)),
+                    }
+                }
+                _ => Err(invalid(&left, &right)),
+            },
+        },
+
+        "^" => {
+            if let (Value::Integer(l), Value::Integer(r)) = (&left, &right) {
+                if *r >= 0 {
+                    return l
+                        .checked_pow(exponent(*r))
+                        .map(Value::Integer)
+                        .ok_or_else(|| RuntimeError::Overflow(op.to_string()));
+                }
+            }
+            match (left.as_float(), right.as_float()) {
+                (Some(l), Some(r)) if l == 0.0 && r < 0.0 => Err(RuntimeError::DivisionByZero),
+                (Some(l), Some(r)) => Ok(Value::Float(l.powf(r))),
+                _ => Err(invalid(&left, &right)),
+            }
+        }
+
+        "==" => Ok(Value::Boolean(equal(&left, &right))),
+        "!=" => Ok(Value::Boolean(!equal(&left, &right))),
+
+        // Membership of an element in an array or range, of a key in a map, or of a substring in
+        // a string
+        "in" => match (&left, &right) {
+            (_, Value::Range(start, end, inclusive)) => Ok(Value::Boolean(match left {
+                Value::Integer(number) => numbers(*start, *end, *inclusive).contains(&number),
+                Value::Float(number) => {
+                    number.fract() == 0.0
+                        && *start as f64 <= number
+                        && (number < *end as f64 || *inclusive && number == *end as f64)
                 }
+                _ => false,
+            })),
+            (_, Value::Array(elements)) => Ok(Value::Boolean(
+                elements
+                    .borrow()
+                    .iter()
+                    .any(|element| equal(&left, element)),
+            )),
+            (Value::String(key), Value::Map(entries)) => {
+                Ok(Value::Boolean(entries.borrow().contains_key(key.as_ref())))
+            }
+            (Value::String(needle), Value::String(haystack)) => {
+                Ok(Value::Boolean(haystack.contains(needle.as_ref())))
             }
+            _ => Err(invalid(&left, &right)),
+        },
+
+        // A number that is not a number is neither smaller, equal, nor larger than any other
+        "<" | "<=" | ">" | ">=" => {
+            let ordering = compare(op, &left, &right)?;
+            Ok(Value::Boolean(ordering.is_some_and(|ordering| match op {
+                "<" => ordering.is_lt(),
+                "<=" => ordering.is_le(),
+                ">" => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            })))
         }
 
-        if errors.is_empty() && self.validate() {
-            let _ = print_ast(&results);
-        } else {
-            let _ = print_error(errors);
+        _ => Err(RuntimeError::UnsupportedOperator(op.to_string())),
+    }
+}
+
+/// Applies a binary operator to two values like [`binary`], except that arithmetic on whole
+/// numbers wraps around modulo 2^64 instead of overflowing.
+fn wrapping_binary(op: &str, left: Value, right: Value) -> Result<Value, RuntimeError> {
+    let (Value::Integer(l), Value::Integer(r)) = (&left, &right) else {
+        return binary(op, left, right);
+    };
+    match op {
+        "+" => Ok(Value::Integer(l.wrapping_add(*r))),
+        "-" => Ok(Value::Integer(l.wrapping_sub(*r))),
+        "*" => Ok(Value::Integer(l.wrapping_mul(*r))),
+        // The only whole division that overflows
+        "/" if *r == -1 => Ok(Value::Integer(l.wrapping_neg())),
+        "^" if *r >= 0 => {
+            // By squaring, as the exponent can be too large for `i64::wrapping_pow`
+            let (mut base, mut exponent, mut power) = (*l, *r, 1i64);
+            while exponent > 0 {
+                if exponent & 1 == 1 {
+                    power = power.wrapping_mul(base);
+                }
+                base = base.wrapping_mul(base);
+                exponent >>= 1;
+            }
+            Ok(Value::Integer(power))
         }
+        _ => binary(op, left, right),
     }
 }
 
+/// Returns an exponent that is not negative as a `u32`, replacing one too large for it by the
+/// largest one of the same parity, which gives the same power for the bases whose powers fit in
+/// a whole number, and overflows for the others.
+fn exponent(exponent: i64) -> u32 {
+    u32::try_from(exponent).unwrap_or(u32::MAX - 1 + (exponent % 2) as u32)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use std::{fs, path::Path};
+    use crate::hash::parser::Parser;
+    use std::collections::BTreeMap;
+
+    fn run(evaluator: &mut Evaluator, program: &str) -> Result<Value, RuntimeError> {
+        let mut parser = Parser::new(program);
+        let mut result = Ok(Value::None);
+        while let Some(statement) = parser.parse().unwrap() {
+            result = evaluator.evaluate(&statement);
+        }
+        result
+    }
 
     #[test]
     fn test_evaluator() {
-        // Read and validate code from the specified script file.
-        let path = fs::read_to_string(Path::new("test/hello.hy")).unwrap();
-        let mut evaluator = Evaluator::new(&path);
-        evaluator.eval();
+        let mut evaluator = Evaluator::new();
+
+        let program = r#"
+            count: num = 0
+            total = 0
+            message = ""
+
+            add(a: num, b: num): num {
+                a + b
+            }
+
+            while count < 5 {
+                count += 1
+                total = add(total, count * 2)
+            }
+
+            if total == 30 {
+                message = "done"
+            } else {
+                message = "wrong"
+            }
+        "#;
+
+        assert_eq!(run(&mut evaluator, program), Ok(Value::None));
+        assert_eq!(run(&mut evaluator, "total"), Ok(Value::Integer(30)));
+        assert_eq!(
+            run(&mut evaluator, "message + \"!\""),
+            Ok(Value::from("done!"))
+        );
+        assert_eq!(run(&mut evaluator, "1 + 2 * 3 - 4"), Ok(Value::Integer(3)));
+        assert_eq!(run(&mut evaluator, "(1 + 2) * 3"), Ok(Value::Integer(9)));
+        assert_eq!(run(&mut evaluator, "7 / 2"), Ok(Value::Float(3.5)));
+        assert_eq!(
+            run(&mut evaluator, "1 / 0"),
+            Err(RuntimeError::DivisionByZero)
+        );
+        assert_eq!(
+            run(&mut evaluator, "x: str = 1"),
+            Err(RuntimeError::TypeMismatch(
+                "str".to_string(),
+                "num".to_string()
+            ))
+        );
+        assert_eq!(
+            run(&mut evaluator, "add(1)"),
+            Err(RuntimeError::ArgumentCount("add".to_string(), 2, 1))
+        );
+        assert_eq!(
+            run(
+                &mut evaluator,
+                "sign(x: num): num { if x < 0 { return -1 }\n while true { return 1 } }"
+            ),
+            Ok(Value::None)
+        );
+        assert_eq!(run(&mut evaluator, "sign(-5)"), Ok(Value::Integer(-1)));
+        assert_eq!(run(&mut evaluator, "sign(5)"), Ok(Value::Integer(1)));
+        assert_eq!(
+            run(&mut evaluator, "name(): str { return 1 }\nname()"),
+            Err(RuntimeError::TypeMismatch(
+                "str".to_string(),
+                "num".to_string()
+            ))
+        );
+        assert_eq!(
+            run(
+                &mut evaluator,
+                "i = 0\nsum = 0\nwhile true { i += 1\n if i > 5 { break }\n if i == 3 { continue }\n sum += i }\nsum"
+            ),
+            Ok(Value::Integer(12))
+        );
+        assert_eq!(
+            run(
+                &mut evaluator,
+                "sum = 0\nfor i in 1..4 { sum += i }\nfor x in [10, 20] { sum += x }\nfor i in 3 { if i == 1 { continue }\n sum += 100 }\nsum"
+            ),
+            Ok(Value::Integer(236))
+        );
+        assert_eq!(
+            run(
+                &mut evaluator,
+                "s = \"\"\nfor c in \"abc\" { if c == \"c\" { break }\n s = c + s }\ns"
+            ),
+            Ok(Value::from("ba"))
+        );
+        assert_eq!(run(&mut evaluator, "0..3"), Ok(Value::Range(0, 3, false)));
+        assert_eq!(
+            run(
+                &mut evaluator,
+                "n = 0\nfor i in 1..=3 { n += i }\n[n, 3 in 1..=3, 3 in 1..3, (3 / 2) in 0..3, len(2..=1)]"
+            ),
+            Ok(Value::array(vec![
+                Value::Integer(6),
+                Value::Boolean(true),
+                Value::Boolean(false),
+                Value::Boolean(false),
+                Value::Integer(0)
+            ]))
+        );
+        // An inclusive range can end at the largest whole number
+        assert_eq!(
+            run(
+                &mut evaluator,
+                "max = 9223372036854775807
+n = 0
+for i in max - 2..=max { n += 1 }
+[n, max in 0..=max, max in 0..max, len(max - 1..=max), (max - 1..=max)[1], to_str(0..=max), 0..=2 == 0..3]"
+            ),
+            Ok(Value::array(vec![
+                Value::Integer(3),
+                Value::Boolean(true),
+                Value::Boolean(false),
+                Value::Integer(2),
+                Value::Integer(i64::MAX),
+                Value::from("0..=9223372036854775807"),
+                Value::Boolean(true)
+            ]))
+        );
+        assert_eq!(
+            run(&mut evaluator, "0..=\"a\""),
+            Err(RuntimeError::InvalidOperands(
+                "..=".to_string(),
+                vec!["num".to_string(), "str".to_string()]
+            ))
+        );
+        assert_eq!(
+            run(&mut evaluator, "for x in true {}"),
+            Err(RuntimeError::TypeMismatch(
+                "iterable".to_string(),
+                "bool".to_string()
+            ))
+        );
+        assert_eq!(
+            run(&mut evaluator, "false && missing || true && !false"),
+            Ok(Value::Boolean(true))
+        );
+        assert_eq!(
+            run(&mut evaluator, "true || missing"),
+            Ok(Value::Boolean(true))
+        );
+        assert_eq!(
+            run(&mut evaluator, "true && 1"),
+            Err(RuntimeError::TypeMismatch(
+                "bool".to_string(),
+                "num".to_string()
+            ))
+        );
+        assert_eq!(
+            run(
+                &mut evaluator,
+                "(\"4\" as num + 1 / 2) as str + (true as num) as str"
+            ),
+            Ok(Value::from("4.51"))
+        );
+        assert_eq!(
+            run(&mut evaluator, "0 as bool || \"true\" as bool"),
+            Ok(Value::Boolean(true))
+        );
+        assert_eq!(
+            run(&mut evaluator, "\"four\" as num"),
+            Err(RuntimeError::InvalidCast(
+                "\"four\"".to_string(),
+                "num".to_string()
+            ))
+        );
+        assert_eq!(
+            run(&mut evaluator, "[1] as str"),
+            Err(RuntimeError::InvalidCast(
+                "[1]".to_string(),
+                "str".to_string()
+            ))
+        );
+        assert_eq!(
+            run(
+                &mut evaluator,
+                "2 in [1, 2 as str, 2 / 1] && \"ab\" in \"stab\""
+            ),
+            Ok(Value::Boolean(true))
+        );
+        assert_eq!(
+            run(&mut evaluator, "5 in 0..5 || \"x\" in [\"a\"]"),
+            Ok(Value::Boolean(false))
+        );
+        assert_eq!(
+            run(&mut evaluator, "1 in \"1\""),
+            Err(RuntimeError::InvalidOperands(
+                "in".to_string(),
+                vec!["num".to_string(), "str".to_string()]
+            ))
+        );
+        assert_eq!(
+            run(&mut evaluator, "1 in 1"),
+            Err(RuntimeError::InvalidOperands(
+                "in".to_string(),
+                vec!["num".to_string(), "num".to_string()]
+            ))
+        );
+        assert_eq!(
+            run(&mut evaluator, "try { 1 / 0 } catch e { \"caught \" + e }"),
+            Ok(Value::from("caught division by zero"))
+        );
+        assert_eq!(
+            run(&mut evaluator, "try { 1 } catch e { 2 }"),
+            Ok(Value::Integer(1))
+        );
+        assert_eq!(
+            run(
+                &mut evaluator,
+                "first(): num { try { return 1 } catch e { 2 }\n 3 }\nfirst()"
+            ),
+            Ok(Value::Integer(1))
+        );
+        assert_eq!(
+            run(&mut evaluator, "try { missing } catch e { e + 1 }"),
+            Err(RuntimeError::InvalidOperands(
+                "+".to_string(),
+                vec!["str".to_string(), "num".to_string()]
+            ))
+        );
+        assert_eq!(
+            run(
+                &mut evaluator,
+                "double(x) { x * 2 }\napply(f: fn, x) { f(x) }\ng: fn = double\napply(g, 3) + apply(add, 1)"
+            ),
+            Err(RuntimeError::ArgumentCount("add".to_string(), 2, 1))
+        );
+        assert_eq!(run(&mut evaluator, "apply(g, 3)"), Ok(Value::Integer(6)));
+        assert_eq!(
+            run(
+                &mut evaluator,
+                "f() { 1 }\ncall(f) { f() }\n[call(fn() { 2 }), f(), call(f)]"
+            ),
+            Ok(Value::from(vec![2, 1, 1])),
+            "a parameter holding a function hides a function of the same name"
+        );
+        assert_eq!(
+            run(&mut evaluator, "g == double && g != add"),
+            Ok(Value::Boolean(true))
+        );
+        assert_eq!(
+            run(
+                &mut evaluator,
+                "scale(k) { fn(x: num): num { x * k } }\ntriple = scale(3)\napply(triple, 4) + apply(fn(x) { x - 1 }, 1)"
+            ),
+            Ok(Value::Integer(12))
+        );
+        assert_eq!(
+            run(
+                &mut evaluator,
+                "n = 1\nf() { n = 2\nn }\ng() { global n\nif true { n += 2 } }\n[f(), n, g(), n]"
+            ),
+            Ok(Value::array(vec![
+                Value::Integer(2),
+                Value::Integer(1),
+                Value::None,
+                Value::Integer(3)
+            ])),
+            "functions assign their own variables, unless they are declared global"
+        );
+        assert_eq!(
+            run(
+                &mut evaluator,
+                "counter() { c = 0\nfn() { nonlocal c\nc += 1\nc } }\ninc = counter()\nother = counter()\n[inc(), inc(), inc(), other()]"
+            ),
+            Ok(Value::array(vec![
+                Value::Integer(1),
+                Value::Integer(2),
+                Value::Integer(3),
+                Value::Integer(1)
+            ])),
+            "nonlocal variables are assigned in the enclosing function"
+        );
+        assert_eq!(
+            run(
+                &mut evaluator,
+                "c = 0\nbump() { nonlocal c\nc = 1 }\nbump()"
+            ),
+            Err(RuntimeError::UndefinedVariable("c".to_string()))
+        );
+        assert_eq!(
+            run(&mut evaluator, "if true { n = 5 }\nn"),
+            Ok(Value::Integer(5))
+        );
+        assert_eq!(
+            run(&mut evaluator, "h: fn = 1"),
+            Err(RuntimeError::TypeMismatch(
+                "fn".to_string(),
+                "num".to_string()
+            ))
+        );
+        assert_eq!(
+            evaluator.evaluate(&Stmt::Break),
+            Err(RuntimeError::OutsideLoop("break".to_string()))
+        );
+        assert_eq!(
+            run(&mut evaluator, "missing"),
+            Err(RuntimeError::UndefinedVariable("missing".to_string()))
+        );
+        assert_eq!(
+            run(&mut evaluator, "missing -= 1"),
+            Err(RuntimeError::UndefinedAssignment("missing".to_string()))
+        );
+        // The variable is read before the value on the right is computed
+        assert_eq!(
+            run(&mut evaluator, "n = 1\nn += { n = 5\n1 }\nn"),
+            Ok(Value::Integer(2))
+        );
+
+        evaluator.set_max_depth(20);
+        run(
+            &mut evaluator,
+            "down(n) { if n == 0 { 0 } else { 1 + down(n - 1) } }\nouter(n) { 1 + down(n) }",
+        )
+        .unwrap();
+        assert_eq!(run(&mut evaluator, "down(19)"), Ok(Value::Integer(19)));
+        assert_eq!(
+            run(&mut evaluator, "outer(50)"),
+            Err(RuntimeError::RecursionLimit(20))
+        );
+        assert_eq!(
+            evaluator.take_stack_trace().to_string(),
+            "\nstack trace, innermost call first:\n    in `down` at line 1 (19 calls)\n    in `outer` at line 2"
+        );
+        assert_eq!(
+            run(&mut evaluator, "try { down(50) } catch e { down(3) }"),
+            Ok(Value::Integer(3))
+        );
+
+        let program = r#"
+            count(n, total) { if n == 0 { total } else { count(n - 1, total + n) } }
+            even(n): bool { if n == 0 { return true }
+                odd(n - 1) }
+            odd(n) { if n == 0 { false } else { even(n - 1) } }
+            safe(n) { try { return down(n) } catch e { return -1 } }
+        "#;
+        run(&mut evaluator, program).unwrap();
+        assert_eq!(
+            run(&mut evaluator, "count(100000, 0)"),
+            Ok(Value::Integer(5000050000))
+        );
+        assert_eq!(
+            run(&mut evaluator, "even(100001)"),
+            Ok(Value::Boolean(false))
+        );
+        assert_eq!(run(&mut evaluator, "safe(50)"), Ok(Value::Integer(-1)));
+        assert_eq!(
+            run(&mut evaluator, "odd(1)\nwrong(n): num { odd(n) }\nwrong(3)"),
+            Err(RuntimeError::TypeMismatch(
+                "num".to_string(),
+                "bool".to_string()
+            ))
+        );
+
+        assert_eq!(
+            run(&mut evaluator, "a = [1, [2]]\nclone(a) == a"),
+            Ok(Value::Boolean(true))
+        );
+        assert_eq!(
+            run(&mut evaluator, "clone()"),
+            Err(RuntimeError::ArgumentCount("clone".to_string(), 1, 0))
+        );
+    }
+
+    #[test]
+    fn test_power_and_modulo() {
+        let mut evaluator = Evaluator::new();
+        let mut eval = |program| run(&mut evaluator, program);
+
+        // The remainder has the sign of the divisor
+        assert_eq!(eval("7 % 3"), Ok(Value::Integer(1)));
+        assert_eq!(eval("-7 % 3"), Ok(Value::Integer(2)));
+        assert_eq!(eval("7 % -3"), Ok(Value::Integer(-2)));
+        assert_eq!(eval("-7 % -3"), Ok(Value::Integer(-1)));
+        assert_eq!(eval("6 % -3"), Ok(Value::Integer(0)));
+        assert_eq!(eval("7 / 2 % 2"), Ok(Value::Float(1.5)));
+        assert_eq!(eval("-7 / 2 % 2"), Ok(Value::Float(0.5)));
+        assert_eq!(
+            binary("%", Value::Integer(i64::MIN), Value::Integer(-1)),
+            Ok(Value::Integer(0))
+        );
+        assert_eq!(eval("1 % 0"), Err(RuntimeError::DivisionByZero));
+        assert_eq!(eval("1 / 2 % 0"), Err(RuntimeError::DivisionByZero));
+
+        // `^` is right associative and binds tighter than unary operators and `*`
+        assert_eq!(eval("2 ^ 10"), Ok(Value::Integer(1024)));
+        assert_eq!(eval("2 ^ 3 ^ 2"), Ok(Value::Integer(512)));
+        assert_eq!(eval("-2 ^ 2"), Ok(Value::Integer(-4)));
+        assert_eq!(eval("(-2) ^ 3"), Ok(Value::Integer(-8)));
+        assert_eq!(eval("3 * 2 ^ 2 % 5"), Ok(Value::Integer(2)));
+        assert_eq!(eval("2 ^ -1"), Ok(Value::Float(0.5)));
+        assert_eq!(eval("4 ^ (1 / 2)"), Ok(Value::Float(2.0)));
+        assert_eq!(eval("0 ^ 0"), Ok(Value::Integer(1)));
+        assert_eq!(eval("(-1) ^ 4294967296"), Ok(Value::Integer(1)));
+        assert_eq!(eval("2 ^ 63"), Err(RuntimeError::Overflow("^".to_string())));
+        assert_eq!(eval("0 ^ -1"), Err(RuntimeError::DivisionByZero));
+        assert_eq!(
+            eval("\"a\" ^ 2"),
+            Err(RuntimeError::InvalidOperands(
+                "^".to_string(),
+                vec!["str".to_string(), "num".to_string()]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut evaluator = Evaluator::new();
+        let overflow = |op: &str| Err(RuntimeError::Overflow(op.to_string()));
+
+        assert_eq!(
+            run(&mut evaluator, "max = 9223372036854775807\nmax + 1"),
+            overflow("+")
+        );
+        assert_eq!(
+            run(&mut evaluator, "min = -max - 1\nmin - 1"),
+            overflow("-")
+        );
+        assert_eq!(run(&mut evaluator, "max * 2"), overflow("*"));
+        assert_eq!(run(&mut evaluator, "min / -1"), overflow("/"));
+        assert_eq!(run(&mut evaluator, "-min"), overflow("-"));
+        assert_eq!(run(&mut evaluator, "min % -1"), Ok(Value::Integer(0)));
+        assert_eq!(
+            run(&mut evaluator, "try { max + 1 } catch e { e }"),
+            Ok(Value::from(
+                "the result of `+` is too large for a whole number"
+            ))
+        );
+        // Only whole numbers overflow
+        assert_eq!(
+            run(&mut evaluator, "max / 2 * 4"),
+            Ok(Value::Float(i64::MAX as f64 / 2.0 * 4.0))
+        );
+
+        evaluator.set_arithmetic(Arithmetic::Wrapping);
+        assert_eq!(
+            run(&mut evaluator, "max + 1 == min"),
+            Ok(Value::Boolean(true))
+        );
+        assert_eq!(
+            run(&mut evaluator, "[max * 2, min / -1, -min]"),
+            Ok(Value::array(vec![
+                Value::Integer(-2),
+                Value::Integer(i64::MIN),
+                Value::Integer(i64::MIN)
+            ]))
+        );
+        assert_eq!(
+            run(&mut evaluator, "[2 ^ 64, 3 ^ 40, 3 ^ 4294967296]"),
+            Ok(Value::array(vec![
+                Value::Integer(0),
+                Value::Integer(3i64.wrapping_pow(40)),
+                Value::Integer((0..32).fold(3i64, |power, _| power.wrapping_mul(power)))
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let mut evaluator = Evaluator::new();
+        let mut eval = |program| run(&mut evaluator, program);
+
+        assert_eq!(eval("1 == 2 / 2"), Ok(Value::Boolean(true)));
+        assert_eq!(
+            eval("[1, [\"a\"]] == [2 / 2, [\"a\"]]"),
+            Ok(Value::Boolean(true))
+        );
+        assert_eq!(eval("[1, 2] != [1]"), Ok(Value::Boolean(true)));
+        let entries = || BTreeMap::from([("a".to_string(), Value::array(vec![Value::Integer(1)]))]);
+        assert_eq!(
+            binary("==", Value::map(entries()), Value::map(entries())),
+            Ok(Value::Boolean(true))
+        );
+        assert_eq!(
+            eval("f = fn() { 1 }\n[f == f, f == fn() { 1 }]"),
+            Ok(Value::array(vec![
+                Value::Boolean(true),
+                Value::Boolean(false)
+            ]))
+        );
+        // Values of different types are never equal
+        assert_eq!(
+            eval("[1 == \"1\", true != 1, [1] == 1]"),
+            Ok(Value::array(vec![
+                Value::Boolean(false),
+                Value::Boolean(true),
+                Value::Boolean(false)
+            ]))
+        );
+        assert_eq!(
+            eval("xs = [1]\npush(xs, xs)\nxs == xs"),
+            Ok(Value::Boolean(true))
+        );
+
+        assert_eq!(eval("\"apple\" < \"banana\""), Ok(Value::Boolean(true)));
+        assert_eq!(eval("\"Z\" < \"a\""), Ok(Value::Boolean(true)));
+        assert_eq!(eval("\"ab\" >= \"abc\""), Ok(Value::Boolean(false)));
+        assert_eq!(eval("[1, 2] < [1, 3]"), Ok(Value::Boolean(true)));
+        assert_eq!(eval("[1, 2] < [1, 2, 0]"), Ok(Value::Boolean(true)));
+        assert_eq!(eval("[[2]] > [[1, 5]]"), Ok(Value::Boolean(true)));
+        assert_eq!(eval("1 <= 2 / 2"), Ok(Value::Boolean(true)));
+        assert_eq!(
+            eval("true < false"),
+            Err(RuntimeError::InvalidOperands(
+                "<".to_string(),
+                vec!["bool".to_string(), "bool".to_string()]
+            ))
+        );
+        assert_eq!(
+            eval("[1] < [\"a\"]"),
+            Err(RuntimeError::InvalidOperands(
+                "<".to_string(),
+                vec!["num".to_string(), "str".to_string()]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_destructuring() {
+        let mut evaluator = Evaluator::new();
+        let mut eval = |program| run(&mut evaluator, program);
+
+        assert_eq!(
+            eval("[a, b] = [1, \"x\"]\n[b, a] = [a, b]\n[a, b]"),
+            Ok(Value::array(vec![Value::from("x"), Value::Integer(1)]))
+        );
+        assert_eq!(
+            eval("f() { global a\n[a, c] = [2, 3] }\nf()\n[a, try { c } catch e { 0 }]"),
+            Ok(Value::array(vec![Value::Integer(2), Value::Integer(0)])),
+            "global variables are assigned, and new ones are local"
+        );
+        eval("point = json_parse(\"{}\")\nset(point, \"x\", 3)\nset(point, \"y\", 4)").unwrap();
+        assert_eq!(
+            eval("norm({x, y}, [scale]) { (x * x + y * y) * scale }\nnorm(point, [2])"),
+            Ok(Value::Integer(50))
+        );
+        assert_eq!(
+            eval("[a, b] = [1, 2, 3]"),
+            Err(RuntimeError::PatternMismatch(
+                "[a, b]".to_string(),
+                "an array of 3 elements".to_string()
+            ))
+        );
+        assert_eq!(
+            eval("{x, z} = point").map_err(|e| e.to_string()),
+            Err("cannot destructure a map without `z` into `{x, z}`".to_string())
+        );
+        assert_eq!(
+            eval("f([x]) { x }\nf(1)"),
+            Err(RuntimeError::PatternMismatch(
+                "[x]".to_string(),
+                "a value of type `num`".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_elements() {
+        let mut evaluator = Evaluator::new();
+        let mut eval = |program| run(&mut evaluator, program);
+
+        assert_eq!(
+            eval("point = json_parse(\"{}\")\npoint.x = 3\nitems = [point, 0]\nitems[0].x += 1\nitems[1] = \"hi\"\n[point.x, items[0][\"x\"], items[1][1], (2..5)[2]]"),
+            Ok(Value::array(vec![
+                Value::Integer(4),
+                Value::Integer(4),
+                Value::from("i"),
+                Value::Integer(4)
+            ])),
+            "the elements of a collection are shared by the values holding it"
+        );
+        assert_eq!(
+            eval("items[2] = 1"),
+            Err(RuntimeError::IndexOutOfBounds(2, 2))
+        );
+        assert_eq!(
+            eval("items[-1]").map_err(|e| e.to_string()),
+            Err("index -1 is out of bounds for a length of 2".to_string())
+        );
+        assert_eq!(
+            eval("point.y"),
+            Err(RuntimeError::MissingKey("y".to_string()))
+        );
+        assert_eq!(
+            eval("s = \"abc\"\ns[0] = \"x\""),
+            Err(RuntimeError::InvalidOperands(
+                "[]=".to_string(),
+                vec!["str".to_string(), "num".to_string()]
+            ))
+        );
+        // Indexing with a range is the same as slice()
+        assert_eq!(
+            eval("xs = [1, 2, 3, 4]\n[xs[1..3], xs[2..10], xs[3..1], \"héllo\"[1..=3]]"),
+            eval("[slice(xs, 1..3), slice(xs, 2..10), slice(xs, 3..1), slice(\"héllo\", 1..=3)]")
+        );
+        assert_eq!(
+            eval("xs[1..3]"),
+            Ok(Value::array(vec![Value::Integer(2), Value::Integer(3)]))
+        );
+        assert_eq!(eval("\"héllo\"[-5..2]"), Ok(Value::from("hé")));
+        assert_eq!(
+            eval("point[0..1]"),
+            Err(RuntimeError::InvalidOperands(
+                "[]".to_string(),
+                vec!["map".to_string(), "range".to_string()]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_structs() {
+        let mut evaluator = Evaluator::new();
+        let mut eval = |program| run(&mut evaluator, program);
+
+        assert_eq!(
+            eval("struct Point { x: num, y }\ninterface Shape {\n    area(self): num\n}\nimpl Shape for Point {\n    area(self): num { self.x * self.y }\n}\np = Point(2, 3)\np.y = 4\n[p.area(), typeof(p), p == Point(2, 4), p == Point(2, 3)]"),
+            Ok(Value::array(vec![
+                Value::Integer(8),
+                Value::from("Point"),
+                Value::Boolean(true),
+                Value::Boolean(false)
+            ]))
+        );
+        assert_eq!(
+            eval("p.z"),
+            Err(RuntimeError::UndefinedField(
+                "Point".to_string(),
+                "z".to_string()
+            ))
+        );
+        assert_eq!(
+            eval("p.x = \"a\""),
+            Err(RuntimeError::TypeMismatch(
+                "num".to_string(),
+                "str".to_string()
+            ))
+        );
+        assert_eq!(
+            eval("p.perimeter()"),
+            Err(RuntimeError::UndefinedMethod(
+                "Point".to_string(),
+                "perimeter".to_string()
+            ))
+        );
+        assert_eq!(
+            eval("interface Named {\n    name(self): str\n    rename(self, name)\n}\nimpl Named for Point {\n    name(self): str { \"point\" }\n}"),
+            Err(RuntimeError::Unimplemented(
+                "Point".to_string(),
+                "Named".to_string(),
+                "rename".to_string()
+            ))
+        );
+        assert_eq!(
+            eval("impl Named for Point {\n    name(self) { \"point\" }\n    rename(self, name) {}\n}")
+                .map_err(|e| e.to_string()),
+            Err("`Named` requires `name(self): str`, found `name(self)`".to_string())
+        );
+        assert_eq!(
+            eval("impl Shape for Circle {}"),
+            Err(RuntimeError::UndefinedStruct("Circle".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_trace() {
+        /// Writer keeping what is written where the test can read it.
+        #[derive(Clone, Default)]
+        struct Shared(Rc<std::cell::RefCell<Vec<u8>>>);
+
+        impl Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.borrow_mut().write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let output = Shared::default();
+        let mut evaluator = Evaluator::new();
+        evaluator.set_trace(Some(Box::new(output.clone())));
+        run(
+            &mut evaluator,
+            "double(n) { n * 2 }\nx = double(1 + 2)\nif x > 5 {\n    \"big\"\n}",
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output.0.take()).unwrap(),
+            "double(n) { ...\n=> none\nx = double(1 + 2)\n  n * 2\n  => 6\n=> none\n\
+             if x > 5 { ...\n\"big\"\n=> \"big\"\n=> \"big\"\n"
+        );
+
+        evaluator.set_trace(None);
+        run(&mut evaluator, "y = 1").unwrap();
+        assert!(output.0.borrow().is_empty());
     }
 }
@@ -0,0 +1,264 @@
+use super::ast::{
+    Block, Expr, For, FunctionDefinition, If, Implementation, Index, Item, Member, MethodCall,
+    Range, Stmt, Tree, Try, While,
+};
+
+/// Trait for passes that rewrite the abstract syntax tree (AST) node by node.
+///
+/// Every method has a default implementation that rebuilds the node with its children folded, so
+/// a pass only overrides the methods for the nodes it changes and calls the matching `walk_*`
+/// function to keep walking the rest of the tree.
+///
+/// # Example
+/// ```
+/// use hydrogen::hash::{
+///     ast::Expr,
+///     fold::{walk_expr, Folder},
+///     parser::Parser,
+///     print,
+/// };
+///
+/// /// Renames every variable `x` to `y`.
+/// struct Rename;
+///
+/// impl Folder for Rename {
+///     fn fold_expr(&mut self, expression: Expr) -> Expr {
+///         match expression {
+///             Expr::Identifier(name) if name == "x" => Expr::Identifier("y".to_string()),
+///             _ => walk_expr(self, expression),
+///         }
+///     }
+/// }
+///
+/// let tree = Parser::new("z = x + 1").parse_program().unwrap();
+/// let tree = Rename.fold_tree(tree);
+///
+/// assert_eq!(print::to_source(&tree), "z = y + 1\n");
+/// ```
+pub trait Folder {
+    /// Folds every statement of a program.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - The abstract syntax tree to be folded.
+    ///
+    /// # Returns
+    ///
+    /// * `Tree` - The rewritten abstract syntax tree.
+    fn fold_tree(&mut self, tree: Tree) -> Tree {
+        tree.into_iter()
+            .map(|statement| self.fold_stmt(statement))
+            .collect()
+    }
+
+    /// Folds a statement.
+    fn fold_stmt(&mut self, statement: Stmt) -> Stmt {
+        walk_stmt(self, statement)
+    }
+
+    /// Folds an expression.
+    fn fold_expr(&mut self, expression: Expr) -> Expr {
+        walk_expr(self, expression)
+    }
+
+    /// Folds a block.
+    fn fold_block(&mut self, block: Block) -> Block {
+        walk_block(self, block)
+    }
+}
+
+/// Rebuilds a statement with each of its children folded.
+///
+/// # Arguments
+///
+/// * `folder` - The pass folding the children.
+/// * `statement` - The statement whose children are folded.
+///
+/// # Returns
+///
+/// * `Stmt` - The same kind of statement holding the folded children.
+pub fn walk_stmt<F: Folder + ?Sized>(folder: &mut F, statement: Stmt) -> Stmt {
+    match statement {
+        Stmt::Item(Item::Function(definition)) => Stmt::Item(Item::Function(FunctionDefinition {
+            body: folder.fold_block(definition.body),
+            ..definition
+        })),
+        Stmt::Item(Item::Struct(_) | Item::Interface(_)) => statement,
+        Stmt::Item(Item::Impl(implementation)) => Stmt::Item(Item::Impl(Implementation {
+            body: folder.fold_block(implementation.body),
+            ..implementation
+        })),
+        Stmt::Definition(mut definition) => {
+            definition.value = folder.fold_expr(definition.value);
+            Stmt::Definition(definition)
+        }
+        Stmt::Declaration(declaration) => Stmt::Declaration(declaration),
+        Stmt::Global(names) => Stmt::Global(names),
+        Stmt::Nonlocal(names) => Stmt::Nonlocal(names),
+        Stmt::Destructure(mut destructuring) => {
+            destructuring.value = folder.fold_expr(destructuring.value);
+            Stmt::Destructure(destructuring)
+        }
+        // The children of the target are folded, so it stays an element
+        Stmt::Store(mut store) => {
+            store.target = walk_expr(folder, store.target);
+            store.value = folder.fold_expr(store.value);
+            Stmt::Store(store)
+        }
+        Stmt::Expr(expression) => Stmt::Expr(folder.fold_expr(expression)),
+        Stmt::Return(value) => Stmt::Return(value.map(|value| folder.fold_expr(value))),
+        Stmt::Break | Stmt::Continue => statement,
+    }
+}
+
+/// Rebuilds a block with each of its statements folded.
+///
+/// # Arguments
+///
+/// * `folder` - The pass folding the statements.
+/// * `block` - The block whose statements are folded.
+///
+/// # Returns
+///
+/// * `Block` - The block holding the folded statements.
+pub fn walk_block<F: Folder + ?Sized>(folder: &mut F, block: Block) -> Block {
+    Block {
+        statements: block
+            .statements
+            .into_iter()
+            .map(|statement| folder.fold_stmt(statement))
+            .collect(),
+        rows: block.rows,
+        end: block.end,
+    }
+}
+
+/// Rebuilds an expression with each of its children folded.
+///
+/// # Arguments
+///
+/// * `folder` - The pass folding the children.
+/// * `expression` - The expression whose children are folded.
+///
+/// # Returns
+///
+/// * `Expr` - The same kind of expression holding the folded children.
+pub fn walk_expr<F: Folder + ?Sized>(folder: &mut F, expression: Expr) -> Expr {
+    match expression {
+        Expr::Unary(mut unary) => {
+            unary.expr = Box::new(folder.fold_expr(*unary.expr));
+            Expr::Unary(unary)
+        }
+        Expr::Binary(mut binary) => {
+            binary.left = Box::new(folder.fold_expr(*binary.left));
+            binary.right = Box::new(folder.fold_expr(*binary.right));
+            Expr::Binary(binary)
+        }
+        Expr::Group(elements) => Expr::Group(walk_list(folder, elements)),
+        Expr::Array(elements) => Expr::Array(walk_list(folder, elements)),
+        Expr::Call(mut call) => {
+            call.arguments = walk_list(folder, call.arguments);
+            Expr::Call(call)
+        }
+        Expr::Block(block) => Expr::Block(folder.fold_block(block)),
+        Expr::If(statement) => Expr::If(If {
+            condition: Box::new(folder.fold_expr(*statement.condition)),
+            then: folder.fold_block(statement.then),
+            otherwise: statement
+                .otherwise
+                .map(|otherwise| Box::new(folder.fold_expr(*otherwise))),
+        }),
+        Expr::While(statement) => Expr::While(While {
+            condition: Box::new(folder.fold_expr(*statement.condition)),
+            body: folder.fold_block(statement.body),
+        }),
+        Expr::For(statement) => Expr::For(For {
+            iterable: Box::new(folder.fold_expr(*statement.iterable)),
+            body: folder.fold_block(statement.body),
+            ..statement
+        }),
+        Expr::Lambda(mut lambda) => {
+            lambda.body = folder.fold_block(lambda.body);
+            Expr::Lambda(lambda)
+        }
+        Expr::Try(statement) => Expr::Try(Try {
+            body: folder.fold_block(statement.body),
+            handler: folder.fold_block(statement.handler),
+            ..statement
+        }),
+        Expr::Cast(mut cast) => {
+            cast.expr = Box::new(folder.fold_expr(*cast.expr));
+            Expr::Cast(cast)
+        }
+        Expr::Range(range) => Expr::Range(Range {
+            start: Box::new(folder.fold_expr(*range.start)),
+            end: Box::new(folder.fold_expr(*range.end)),
+            inclusive: range.inclusive,
+        }),
+        Expr::Index(index) => Expr::Index(Index {
+            target: Box::new(folder.fold_expr(*index.target)),
+            index: Box::new(folder.fold_expr(*index.index)),
+        }),
+        Expr::Member(member) => Expr::Member(Member {
+            target: Box::new(folder.fold_expr(*member.target)),
+            name: member.name,
+        }),
+        Expr::MethodCall(call) => Expr::MethodCall(MethodCall {
+            target: Box::new(folder.fold_expr(*call.target)),
+            name: call.name,
+            arguments: walk_list(folder, call.arguments),
+        }),
+        Expr::Literal(_) | Expr::Identifier(_) => expression,
+    }
+}
+
+/// Folds a list of expressions in order.
+fn walk_list<F: Folder + ?Sized>(folder: &mut F, elements: Vec<Expr>) -> Vec<Expr> {
+    elements
+        .into_iter()
+        .map(|element| folder.fold_expr(element))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hash::{
+        ast::{BinaryExpression, Literal},
+        parser::Parser,
+        print,
+    };
+
+    /// Adds up additions of two number literals.
+    struct ConstantFolder;
+
+    impl Folder for ConstantFolder {
+        fn fold_expr(&mut self, expression: Expr) -> Expr {
+            let expression = walk_expr(self, expression);
+            if let Expr::Binary(BinaryExpression { left, op, right }) = &expression {
+                if let (Expr::Literal(Literal::Number(l)), Expr::Literal(Literal::Number(r))) =
+                    (&**left, &**right)
+                {
+                    if op == "+" {
+                        let sum = l.parse::<i64>().unwrap() + r.parse::<i64>().unwrap();
+                        return Expr::Literal(Literal::Number(sum.to_string()));
+                    }
+                }
+            }
+            expression
+        }
+    }
+
+    #[test]
+    fn test_fold() {
+        let tree = Parser::new("f(x) { x = 1 + 2 + x }\nprint([1 + 2, f(3 + 4)])")
+            .parse_program()
+            .unwrap();
+        let tree = ConstantFolder.fold_tree(tree);
+
+        assert_eq!(
+            print::to_source(&tree),
+            "f(x) {\n    x = 3 + x\n}\nprint([3, f(7)])\n"
+        );
+    }
+}
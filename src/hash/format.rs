@@ -0,0 +1,233 @@
+use super::{
+    ast::Errors,
+    lexer::Lexer,
+    parser::Parser,
+    print::{self, Comments},
+    tokens::Token,
+};
+
+/// A comment found in the source code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Comment {
+    /// The line the comment starts on.
+    row: usize,
+    /// The line the comment ends on.
+    end: usize,
+    /// true if there is code before the comment on its first line.
+    trailing: bool,
+    /// The text of the comment, delimiters included.
+    text: String,
+}
+
+/// Finds every comment in the source code.
+fn comments(source: &str) -> Vec<Comment> {
+    let mut lexer = Lexer::with_comments(source);
+    let mut comments = Vec::new();
+    let mut code = 0;
+
+    loop {
+        match lexer.lex() {
+            Token::Eof(_) => break,
+            Token::Comment(position, text) => comments.push(Comment {
+                row: position.row,
+                end: position.row + text.matches('\n').count(),
+                trailing: code == position.row,
+                text: text.trim_end().to_string(),
+            }),
+            _ => code = lexer.position().row,
+        }
+    }
+
+    comments
+}
+
+/// The comments of the source code not written yet, in order.
+struct Pending<I: Iterator<Item = Comment>>(std::iter::Peekable<I>);
+
+impl<I: Iterator<Item = Comment>> print::Comments for Pending<I> {
+    fn before(&mut self, row: usize) -> Vec<String> {
+        let mut lines = Vec::new();
+        while let Some(comment) = self
+            .0
+            .next_if(|comment| comment.row < row || (comment.row == row && !comment.trailing))
+        {
+            lines.push(comment.text);
+        }
+        lines
+    }
+
+    fn after(&mut self, row: usize) -> (Vec<String>, Vec<String>) {
+        let (mut trailing, mut own) = (Vec::new(), Vec::new());
+        while let Some(comment) = self.0.next_if(|comment| comment.row < row) {
+            match comment.trailing {
+                true => trailing.push(comment.text),
+                false => own.push(comment.text),
+            }
+        }
+        (trailing, own)
+    }
+}
+
+/// Formats Hydrogen source code.
+///
+/// Statements are regenerated with the pretty-printer, which normalizes indentation, spacing
+/// around operators, and brace placement. Comments between statements are kept in place, blank
+/// lines separating statements are collapsed to one, and comments inside a block are written
+/// with the statement of the block they are next to.
+///
+/// # Arguments
+///
+/// * `source` - The source code to be formatted.
+///
+/// # Returns
+///
+/// * `Result<String, Errors>` - Ok(String) with the formatted source code, Err(Errors) if the
+///   source code does not parse.
+pub fn format(source: &str) -> Result<String, Errors> {
+    let lines: Vec<&str> = source.lines().collect();
+    let blank = |from: usize, to: usize| -> bool {
+        (from + 1..to).any(|row| {
+            lines
+                .get(row - 1)
+                .is_some_and(|line| line.trim().is_empty())
+        })
+    };
+
+    let mut comments = Pending(comments(source).into_iter().peekable());
+    let mut parser = Parser::new(source);
+    let mut errors: Errors = Vec::new();
+    let mut formatted = String::new();
+    let mut last = 0;
+
+    loop {
+        let start = parser.next_position().row;
+        let statement = match parser.parse() {
+            Ok(statement) => statement,
+            Err(error) => {
+                errors.push(error);
+                continue;
+            }
+        };
+        let end = match statement {
+            Some(_) => parser.position().row,
+            None => usize::MAX,
+        };
+
+        // The comments above the statement, or every comment left at the end of the source code
+        while let Some(comment) = comments.0.next_if(|comment| {
+            comment.row < start || (comment.row == start && !comment.trailing) || end == usize::MAX
+        }) {
+            if last != 0 && blank(last, comment.row) {
+                formatted.push('\n');
+            }
+            formatted.push_str(&comment.text);
+            formatted.push('\n');
+            last = comment.end;
+        }
+
+        let Some(statement) = statement else {
+            break;
+        };
+
+        if last != 0 && blank(last, start) {
+            formatted.push('\n');
+        }
+        formatted.push_str(&print::stmt_source_with(&statement, 0, &mut comments));
+        let (trailing, own) = comments.after(end + 1);
+        for comment in trailing {
+            formatted.push(' ');
+            formatted.push_str(&comment);
+        }
+        formatted.push('\n');
+        for comment in own {
+            formatted.push_str(&comment);
+            formatted.push('\n');
+        }
+        last = end;
+    }
+
+    if errors.is_empty() {
+        Ok(formatted)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Compares two versions of a source code line by line.
+///
+/// # Arguments
+///
+/// * `original` - The source code before formatting.
+/// * `formatted` - The source code after formatting.
+///
+/// # Returns
+///
+/// * `String` - The lines only in the original prefixed by `-`, and the lines only in the
+///   formatted source code prefixed by `+`, in order; empty if both are the same.
+pub fn diff(original: &str, formatted: &str) -> String {
+    let old: Vec<&str> = original.lines().collect();
+    let new: Vec<&str> = formatted.lines().collect();
+
+    // Length of the longest common subsequence of the suffixes starting at i and j
+    let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && common[i + 1][j] >= common[i][j + 1]) {
+            diff.push_str(&format!("-{}\n", old[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("+{}\n", new[j]));
+            j += 1;
+        }
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let source = "// header\n\n\nx:num=1 // one\n/* block\n   comment */\nf(a){\n// inner\na*2}\n\n\nprint(f(x))\n// end\n";
+        let formatted = format(source).unwrap();
+
+        assert_eq!(
+            formatted,
+            "// header\n\nx: num = 1 // one\n/* block\n   comment */\nf(a) {\n    // inner\n    a * 2\n}\n\nprint(f(x))\n// end\n"
+        );
+        assert_eq!(format(&formatted), Ok(formatted.clone()));
+        // Comments inside blocks stay next to their statements
+        let source = "/// Doubles a number.\ndouble(n) {\nif n { // open\n// first\nx = 1\ny = 2 /* two */\n// last\n}\ng() {\n// nothing\n}\nn * 2 // twice\n}\n";
+        let formatted = format(source).unwrap();
+        assert_eq!(
+            formatted,
+            "/// Doubles a number.\ndouble(n) {\n    if n {\n        // open\n        // first\n        x = 1\n        y = 2 /* two */\n        // last\n    }\n    g() {\n        // nothing\n    }\n    n * 2 // twice\n}\n"
+        );
+        assert_eq!(format(&formatted), Ok(formatted.clone()));
+        assert!(format("x = ~").is_err());
+        // Trailing commas are dropped
+        assert_eq!(
+            format("xs = [\n    1,\n    2,\n]\nf(xs,)"),
+            Ok("xs = [1, 2]\nf(xs)\n".to_string())
+        );
+
+        assert_eq!(diff(&formatted, &formatted), "");
+        assert_eq!(diff("a\nb\nc\n", "a\nc\nd\n"), "-b\n+d\n");
+    }
+}
@@ -0,0 +1,432 @@
+use std::{collections::BTreeMap, fmt::Write, iter::Peekable, str::CharIndices};
+
+use super::{evaluator::RuntimeError, value::Value};
+
+/// How deeply arrays and objects can nest in the text read by [`parse`].
+pub const MAX_DEPTH: usize = 256;
+
+/// Reads a value from JSON text.
+///
+/// Objects become maps, arrays become arrays, and `null` becomes none. Numbers without a fraction
+/// or an exponent become whole numbers when they fit in one.
+///
+/// # Arguments
+///
+/// * `text` - The JSON text.
+///
+/// # Returns
+///
+/// * `Result<Value, RuntimeError>` - The value, or an error telling where the text is not valid.
+///
+/// # Example
+/// ```
+/// use hydrogen::hash::{json, value::Value};
+///
+/// let value = json::parse(r#"{"ids": [1, 2.5], "ok": true}"#).unwrap();
+///
+/// assert_eq!(value.to_string(), r#"{"ids": [1, 2.5], "ok": true}"#);
+/// ```
+pub fn parse(text: &str) -> Result<Value, RuntimeError> {
+    let mut reader = Reader {
+        text,
+        chars: text.char_indices().peekable(),
+        depth: 0,
+    };
+
+    let value = reader.value()?;
+    reader.whitespace();
+    match reader.chars.peek() {
+        None => Ok(value),
+        Some(_) => Err(reader.error("unexpected text after the value")),
+    }
+}
+
+/// Writes a value as JSON text.
+///
+/// Maps become objects with their keys in order, values of structs objects with their fields in
+/// the order the struct defines them, and none becomes `null`.
+///
+/// # Arguments
+///
+/// * `value` - The value to be written.
+///
+/// # Returns
+///
+/// * `Result<String, RuntimeError>` - The JSON text, or an error if the value holds a function
+///   or a number JSON cannot represent, or holds itself.
+pub fn encode(value: &Value) -> Result<String, RuntimeError> {
+    let mut text = String::new();
+    write_value(&mut text, value, &mut Vec::new())?;
+    Ok(text)
+}
+
+/// Appends the JSON text of a value inside the collections being written, whose addresses are
+/// `open`.
+fn write_value(
+    text: &mut String,
+    value: &Value,
+    open: &mut Vec<*const ()>,
+) -> Result<(), RuntimeError> {
+    if let Some(address) = value.address() {
+        if open.contains(&address) {
+            return Err(RuntimeError::CyclicValue("encode".to_string()));
+        }
+        open.push(address);
+    }
+
+    match value {
+        Value::None => text.push_str("null"),
+        Value::Boolean(value) => text.push_str(if *value { "true" } else { "false" }),
+        Value::Integer(value) => text.push_str(&value.to_string()),
+        #[cfg(feature = "bignum")]
+        Value::BigInteger(value) => text.push_str(&value.to_string()),
+        // Whole floats keep a fraction, so they are read back as floats
+        Value::Float(value) if value.is_finite() => text.push_str(&format!("{:?}", value)),
+        Value::Float(value) => {
+            return Err(RuntimeError::InvalidCast(
+                value.to_string(),
+                "json".to_string(),
+            ))
+        }
+        Value::String(value) => write_string(text, value),
+        Value::Array(elements) => {
+            text.push('[');
+            for (index, element) in elements.borrow().iter().enumerate() {
+                if index > 0 {
+                    text.push(',');
+                }
+                write_value(text, element, open)?;
+            }
+            text.push(']');
+        }
+        Value::Map(entries) => {
+            text.push('{');
+            for (index, (key, value)) in entries.borrow().iter().enumerate() {
+                if index > 0 {
+                    text.push(',');
+                }
+                write_string(text, key);
+                text.push(':');
+                write_value(text, value, open)?;
+            }
+            text.push('}');
+        }
+        Value::Instance(instance) => {
+            text.push('{');
+            let fields = instance.fields.borrow();
+            for (index, (field, value)) in instance.ty.fields.iter().zip(fields.iter()).enumerate()
+            {
+                if index > 0 {
+                    text.push(',');
+                }
+                write_string(text, &field.name);
+                text.push(':');
+                write_value(text, value, open)?;
+            }
+            text.push('}');
+        }
+        Value::Range(..) | Value::Function(_) => {
+            return Err(RuntimeError::InvalidCast(
+                value.to_string(),
+                "json".to_string(),
+            ))
+        }
+    }
+
+    if value.address().is_some() {
+        open.pop();
+    }
+    Ok(())
+}
+
+/// Appends a string between quotes, escaping the characters JSON does not allow in it.
+fn write_string(text: &mut String, string: &str) {
+    text.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => text.push_str("\\\""),
+            '\\' => text.push_str("\\\\"),
+            '\n' => text.push_str("\\n"),
+            '\r' => text.push_str("\\r"),
+            '\t' => text.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(text, "\\u{:04x}", c as u32);
+            }
+            c => text.push(c),
+        }
+    }
+    text.push('"');
+}
+
+/// Reads values from JSON text, one character at a time.
+struct Reader<'a> {
+    /// The whole text.
+    text: &'a str,
+    /// The characters left to read, with their byte offsets.
+    chars: Peekable<CharIndices<'a>>,
+    /// The number of arrays and objects being read.
+    depth: usize,
+}
+
+impl Reader<'_> {
+    /// Returns the error for text that is not valid where the reader is.
+    fn error(&mut self, message: &str) -> RuntimeError {
+        let offset = self
+            .chars
+            .peek()
+            .map_or(self.text.len(), |(offset, _)| *offset);
+        RuntimeError::InvalidJson(message.to_string(), offset)
+    }
+
+    /// Skips the whitespace before the next token.
+    fn whitespace(&mut self) {
+        while self
+            .chars
+            .next_if(|(_, c)| matches!(c, ' ' | '\t' | '\n' | '\r'))
+            .is_some()
+        {}
+    }
+
+    /// Reads the given character, or fails.
+    fn expect(&mut self, expected: char) -> Result<(), RuntimeError> {
+        match self.chars.next_if(|(_, c)| *c == expected) {
+            Some(_) => Ok(()),
+            None => Err(self.error(&format!("expected `{}`", expected))),
+        }
+    }
+
+    /// Reads a value and the whitespace before it.
+    fn value(&mut self) -> Result<Value, RuntimeError> {
+        self.whitespace();
+        match self.chars.peek().map(|(_, c)| *c) {
+            Some('{') => self.nested(Self::object),
+            Some('[') => self.nested(Self::array),
+            Some('"') => Ok(Value::from(self.string()?)),
+            Some('-' | '0'..='9') => self.number(),
+            Some('t') => self.word("true", Value::Boolean(true)),
+            Some('f') => self.word("false", Value::Boolean(false)),
+            Some('n') => self.word("null", Value::None),
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("unexpected end of the text")),
+        }
+    }
+
+    /// Reads an array or an object, unless too many are already being read.
+    fn nested(
+        &mut self,
+        read: fn(&mut Self) -> Result<Value, RuntimeError>,
+    ) -> Result<Value, RuntimeError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+        self.depth += 1;
+        let value = read(self);
+        self.depth -= 1;
+        value
+    }
+
+    /// Reads the elements of an array.
+    fn array(&mut self) -> Result<Value, RuntimeError> {
+        self.expect('[')?;
+        let mut elements = Vec::new();
+        self.whitespace();
+        if self.chars.next_if(|(_, c)| *c == ']').is_some() {
+            return Ok(Value::array(elements));
+        }
+
+        loop {
+            elements.push(self.value()?);
+            self.whitespace();
+            if self.chars.next_if(|(_, c)| *c == ']').is_some() {
+                return Ok(Value::array(elements));
+            }
+            self.expect(',')?;
+        }
+    }
+
+    /// Reads the entries of an object.
+    fn object(&mut self) -> Result<Value, RuntimeError> {
+        self.expect('{')?;
+        let mut entries = BTreeMap::new();
+        self.whitespace();
+        if self.chars.next_if(|(_, c)| *c == '}').is_some() {
+            return Ok(Value::map(entries));
+        }
+
+        loop {
+            self.whitespace();
+            let key = self.string()?;
+            self.whitespace();
+            self.expect(':')?;
+            entries.insert(key, self.value()?);
+            self.whitespace();
+            if self.chars.next_if(|(_, c)| *c == '}').is_some() {
+                return Ok(Value::map(entries));
+            }
+            self.expect(',')?;
+        }
+    }
+
+    /// Reads a string between quotes, replacing its escape sequences.
+    fn string(&mut self) -> Result<String, RuntimeError> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(string),
+                Some((_, '\\')) => {
+                    let escaped = match self.chars.next() {
+                        Some((_, '"')) => '"',
+                        Some((_, '\\')) => '\\',
+                        Some((_, '/')) => '/',
+                        Some((_, 'b')) => '\u{8}',
+                        Some((_, 'f')) => '\u{c}',
+                        Some((_, 'n')) => '\n',
+                        Some((_, 'r')) => '\r',
+                        Some((_, 't')) => '\t',
+                        Some((_, 'u')) => self.unicode()?,
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    string.push(escaped);
+                }
+                Some((_, c)) if (c as u32) < 0x20 => {
+                    return Err(self.error("control character in a string"))
+                }
+                Some((_, c)) => string.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    /// Reads the code of a `\u` escape sequence, joining a pair of surrogates into one character.
+    fn unicode(&mut self) -> Result<char, RuntimeError> {
+        let high = self.hex()?;
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("invalid unicode escape"));
+        }
+
+        if self.chars.next_if(|(_, c)| *c == '\\').is_none()
+            || self.chars.next_if(|(_, c)| *c == 'u').is_none()
+        {
+            return Err(self.error("unpaired surrogate"));
+        }
+        let low = self.hex()?;
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err(self.error("unpaired surrogate"));
+        }
+        char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))
+            .ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    /// Reads the four hexadecimal digits of a `\u` escape sequence.
+    fn hex(&mut self) -> Result<u32, RuntimeError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            match self.chars.next().and_then(|(_, c)| c.to_digit(16)) {
+                Some(digit) => code = code * 16 + digit,
+                None => return Err(self.error("invalid unicode escape")),
+            }
+        }
+        Ok(code)
+    }
+
+    /// Reads a number.
+    fn number(&mut self) -> Result<Value, RuntimeError> {
+        let start = self
+            .chars
+            .peek()
+            .map_or(self.text.len(), |(offset, _)| *offset);
+        while self
+            .chars
+            .next_if(|(_, c)| matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9'))
+            .is_some()
+        {}
+        let end = self
+            .chars
+            .peek()
+            .map_or(self.text.len(), |(offset, _)| *offset);
+        let digits = &self.text[start..end];
+
+        if let Ok(number) = digits.parse::<i64>() {
+            return Ok(Value::Integer(number));
+        }
+        // Rust accepts a few forms JSON does not, like `1.` or `.5`
+        let valid = !digits.contains(['.', 'e', 'E'])
+            || digits
+                .split(['.', 'e', 'E'])
+                .all(|part| !part.trim_start_matches(['-', '+']).is_empty());
+        match digits.parse::<f64>() {
+            Ok(number) if valid && number.is_finite() => Ok(Value::Float(number)),
+            _ => Err(RuntimeError::InvalidJson(
+                format!("invalid number `{}`", digits),
+                start,
+            )),
+        }
+    }
+
+    /// Reads a word standing for a value, like `true`.
+    fn word(&mut self, word: &str, value: Value) -> Result<Value, RuntimeError> {
+        for expected in word.chars() {
+            self.expect(expected)?;
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_json() {
+        let text = r#" { "name": "H\u00e9\n\ud83d\ude00", "list": [1, -2.5, 3e2, true, null, {}],
+            "empty": [] } "#;
+        let value = parse(text).unwrap();
+        assert_eq!(
+            encode(&value).unwrap(),
+            r#"{"empty":[],"list":[1,-2.5,300.0,true,null,{}],"name":"Hé\n😀"}"#
+        );
+        assert_eq!(parse(&encode(&value).unwrap()).unwrap(), value);
+
+        assert_eq!(
+            parse("12345678901234567890"),
+            Ok(Value::Float(1.2345678901234567e19))
+        );
+        assert_eq!(
+            encode(&Value::from("\"\\\u{1}")).unwrap(),
+            r#""\"\\\u0001""#
+        );
+
+        for (text, offset) in [
+            ("", 0),
+            ("[1,]", 3),
+            ("{\"a\" 1}", 5),
+            ("[1] 2", 4),
+            ("\"abc", 4),
+            ("tru", 3),
+            ("1.", 0),
+            ("\"\\ud800\"", 7),
+        ] {
+            assert!(
+                matches!(parse(text), Err(RuntimeError::InvalidJson(_, at)) if at == offset),
+                "{}",
+                text
+            );
+        }
+        assert!(parse(&"[".repeat(MAX_DEPTH + 1)).is_err());
+
+        let array = Value::from(vec![1]);
+        let Value::Array(elements) = &array else {
+            unreachable!()
+        };
+        elements
+            .borrow_mut()
+            .push(Value::array(vec![array.clone()]));
+        assert_eq!(
+            encode(&array).map_err(|error| error.to_string()),
+            Err("cannot encode a cyclic value".to_string())
+        );
+        let shared = Value::array(vec![Value::from(vec![2]); 2]);
+        assert_eq!(encode(&shared).unwrap(), "[[2],[2]]");
+    }
+}
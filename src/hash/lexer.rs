@@ -1,6 +1,8 @@
-use std::{char, iter::Peekable, str::Chars};
+use std::{borrow::Cow, iter::FusedIterator};
 
-use super::tokens::{Position, Token};
+use unicode_ident::{is_xid_continue, is_xid_start};
+
+use super::tokens::{Keyword, Position, Span, Token, TypeName, TAB_WIDTH};
 
 /// Lexer struct responsible for tokenizing the source code.
 /// # TODO:
@@ -10,10 +12,10 @@ use super::tokens::{Position, Token};
 /// - [x] tokenize operators
 /// - [ ] fix the number tokinizing to parse multiple formats of numbers
 /// - [ ] fix the string tokinizing to parse escaped characters
-/// - [ ] MAKE A ZERO COPY parser stop using String and use &str
+/// - [x] MAKE A ZERO COPY parser stop using String and use &str
 ///
 /// # Example of number formats
-/// ```
+/// ```text
 /// 1234        // integer
 /// 3.14159     // float
 /// 3E2         // scientific notation
@@ -24,59 +26,125 @@ use super::tokens::{Position, Token};
 /// ```
 #[derive(Debug, Clone)]
 pub struct Lexer<'a> {
-    source: Peekable<Chars<'a>>, // Peekable iterator over characters in the source code
+    source: &'a str,             // The source code the tokens borrow their text from
+    offset: usize,               // Byte offset of the next character in the source code
     position: Position,          // Current position in the source code
+    comments: bool,              // Whether comments are returned as tokens or skipped
+    tab_width: usize,            // Number of columns between tab stops
+    docs: Vec<(usize, &'a str)>, // The last `///` comments on consecutive lines, with their rows
 }
 
 impl<'a> Lexer<'a> {
     /// Creates a new Lexer instance from the given source code.
     pub fn new(source: &'a str) -> Self {
         Self {
-            source: source.chars().peekable(),
-            position: Position { col: 1, row: 1 },
+            source,
+            offset: 0,
+            position: Position {
+                col: 1,
+                row: 1,
+                offset: 0,
+            },
+            comments: false,
+            tab_width: TAB_WIDTH,
+            docs: Vec::new(),
+        }
+    }
+
+    /// Creates a new Lexer instance that starts at a position of the source code, which must not
+    /// be inside a token.
+    pub fn starting_at(source: &'a str, position: Position) -> Self {
+        Self {
+            offset: position.offset,
+            position,
+            ..Self::new(source)
+        }
+    }
+
+    /// Creates a new Lexer instance that returns comments as `Token::Comment` instead of skipping
+    /// them, for tools that need to keep or inspect them.
+    pub fn with_comments(source: &'a str) -> Self {
+        Self {
+            comments: true,
+            ..Self::new(source)
         }
     }
 
     /// Retrieves the next character from the source code and updates the position.
     fn next_char(&mut self) -> (Position, char) {
-        let current = self.source.next().unwrap_or_default();
         let position = self.position.clone();
-        self.position.col += 1;
-        if current == '\n' {
-            self.position.row += 1;
-            self.position.col = 1;
-        }
+        let Some(current) = self.peek_char() else {
+            return (position, char::default());
+        };
+        self.offset += current.len_utf8();
+        self.position.advance(current, self.tab_width);
         (position, current)
     }
 
     /// Peeks at the next character in the source code without consuming it.
-    fn peek_char(&mut self) -> Option<&char> {
-        self.source.peek()
+    fn peek_char(&self) -> Option<char> {
+        self.source[self.offset..].chars().next()
+    }
+
+    /// Sets the number of columns between tab stops, which the columns of the positions of the
+    /// tokens lexed afterwards are counted with, instead of [`TAB_WIDTH`].
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The number of columns, at least 1.
+    pub fn set_tab_width(&mut self, width: usize) {
+        self.tab_width = width.max(1);
+    }
+
+    /// Returns the current position of the lexer in the source code.
+    pub fn position(&self) -> Position {
+        self.position.clone()
     }
 
     /// Peeks at the next token without consuming it.
-    pub fn peek(&mut self) -> Token {
-        let mut peek_lexer = Lexer {
-            source: self.source.clone(),
-            position: self.position.clone(),
+    pub fn peek(&mut self) -> Token<'a> {
+        self.clone().lex()
+    }
+
+    /// Lexes the next token from the source code together with the bytes it covers.
+    ///
+    /// # Returns
+    ///
+    /// * `(Token, Span)` - The next token and its span, which is empty at the end of the source
+    ///   code.
+    pub fn lex_span(&mut self) -> (Token<'a>, Span) {
+        let token = self.lex();
+        let start = token.position().offset;
+        let span = Span {
+            start,
+            len: self.offset - start,
         };
+        (token, span)
+    }
 
-        peek_lexer.lex()
+    /// Returns an iterator over the rest of the tokens together with the bytes they cover, which
+    /// ends before `Token::Eof`.
+    pub fn spans(mut self) -> impl Iterator<Item = (Token<'a>, Span)> {
+        std::iter::from_fn(move || match self.lex_span() {
+            (Token::Eof(_), _) => None,
+            lexeme => Some(lexeme),
+        })
     }
 
     /// Lexes and returns the next token from the source code.
-    pub fn lex(&mut self) -> Token {
+    pub fn lex(&mut self) -> Token<'a> {
         match self.peek_char() {
-            Some(&c) => {
+            Some(c) => {
                 if c.is_whitespace() {
                     self.consume_whitespace()
-                } else if c.is_alphabetic() {
+                } else if is_xid_start(c) || c == '_' {
                     self.collect_id()
                 } else if c == '"' {
                     self.collect_string()
                 } else if c.is_numeric() {
                     self.collect_number()
                 } else {
+                    let start = self.offset;
                     let (position, current) = self.next_char();
 
                     match current {
@@ -91,6 +159,8 @@ impl<'a> Lexer<'a> {
 
                         ',' => Token::Comma(position),
 
+                        ';' => Token::Semicolon(position),
+
                         '?' => Token::QuestionMark(position),
 
                         '$' => Token::DollarSign(position),
@@ -99,12 +169,24 @@ impl<'a> Lexer<'a> {
 
                         ':' => Token::Colon(position),
 
-                        '.' => Token::Dot(position),
+                        '.' => match self.peek_char() {
+                            Some('.') => {
+                                self.next_char();
+                                match self.peek_char() {
+                                    Some('=') => {
+                                        self.next_char();
+                                        Token::DotDotEqual(position)
+                                    }
+                                    _ => Token::DotDot(position),
+                                }
+                            }
+                            _ => Token::Dot(position),
+                        },
 
                         '@' => Token::At(position),
 
                         '^' => match self.peek_char() {
-                            Some(&c) => {
+                            Some(c) => {
                                 if c == '=' {
                                     self.next_char();
                                     Token::CaretEqual(position)
@@ -116,7 +198,7 @@ impl<'a> Lexer<'a> {
                         },
 
                         '%' => match self.peek_char() {
-                            Some(&c) => {
+                            Some(c) => {
                                 if c == '=' {
                                     self.next_char();
                                     Token::PercentEqual(position)
@@ -128,7 +210,7 @@ impl<'a> Lexer<'a> {
                         },
 
                         '+' => match self.peek_char() {
-                            Some(&c) => {
+                            Some(c) => {
                                 if c == '=' {
                                     self.next_char();
                                     Token::PlusEqual(position)
@@ -140,7 +222,7 @@ impl<'a> Lexer<'a> {
                         },
 
                         '-' => match self.peek_char() {
-                            Some(&c) => {
+                            Some(c) => {
                                 if c == '=' {
                                     self.next_char();
                                     Token::MinusEqual(position)
@@ -152,7 +234,7 @@ impl<'a> Lexer<'a> {
                         },
 
                         '*' => match self.peek_char() {
-                            Some(&c) => {
+                            Some(c) => {
                                 if c == '=' {
                                     self.next_char();
                                     Token::AsteriskEqual(position)
@@ -164,11 +246,11 @@ impl<'a> Lexer<'a> {
                         },
 
                         '/' => match self.peek_char() {
-                            Some(&c) => {
+                            Some(c) => {
                                 if c == '/' {
-                                    self.consume_comment()
+                                    self.consume_comment(position)
                                 } else if c == '*' {
-                                    self.consume_multiline_comment()
+                                    self.consume_multiline_comment(position)
                                 } else if c == '=' {
                                     self.next_char();
                                     Token::SlashEqual(position)
@@ -180,7 +262,7 @@ impl<'a> Lexer<'a> {
                         },
 
                         '=' => match self.peek_char() {
-                            Some(&c) => {
+                            Some(c) => {
                                 if c == '=' {
                                     self.next_char();
                                     Token::Equals(position)
@@ -192,7 +274,7 @@ impl<'a> Lexer<'a> {
                         },
 
                         '!' => match self.peek_char() {
-                            Some(&c) => {
+                            Some(c) => {
                                 if c == '=' {
                                     self.next_char();
                                     Token::NotEqual(position)
@@ -204,7 +286,7 @@ impl<'a> Lexer<'a> {
                         },
 
                         '>' => match self.peek_char() {
-                            Some(&c) => {
+                            Some(c) => {
                                 if c == '=' {
                                     self.next_char();
                                     Token::GreaterThanOrEqual(position)
@@ -216,7 +298,7 @@ impl<'a> Lexer<'a> {
                         },
 
                         '<' => match self.peek_char() {
-                            Some(&c) => {
+                            Some(c) => {
                                 if c == '=' {
                                     self.next_char();
                                     Token::LessThanOrEqual(position)
@@ -228,7 +310,7 @@ impl<'a> Lexer<'a> {
                         },
 
                         '&' => match self.peek_char() {
-                            Some(&c) => {
+                            Some(c) => {
                                 if c == '&' {
                                     self.next_char();
                                     Token::And(position)
@@ -240,18 +322,14 @@ impl<'a> Lexer<'a> {
                         },
 
                         '|' => match self.peek_char() {
-                            Some(&c) => {
-                                if c == '|' {
-                                    self.next_char();
-                                    Token::Or(position)
-                                } else {
-                                    Token::Unknown(position, current.to_string())
-                                }
+                            Some('|') => {
+                                self.next_char();
+                                Token::Or(position)
                             }
-                            None => Token::Unknown(position, current.to_string()),
+                            _ => Token::Unknown(position, self.source[start..self.offset].into()),
                         },
 
-                        _ => Token::Unknown(position, current.to_string()),
+                        _ => Token::Unknown(position, self.source[start..self.offset].into()),
                     }
                 }
             }
@@ -260,8 +338,8 @@ impl<'a> Lexer<'a> {
     }
 
     /// Consumes whitespace characters until a non-whitespace character is encountered.
-    fn consume_whitespace(&mut self) -> Token {
-        while let Some(&c) = self.peek_char() {
+    fn consume_whitespace(&mut self) -> Token<'a> {
+        while let Some(c) = self.peek_char() {
             if !c.is_whitespace() {
                 break;
             }
@@ -271,103 +349,213 @@ impl<'a> Lexer<'a> {
     }
 
     /// Consumes characters until a newline character is encountered, indicating the end of a line comment.
-    fn consume_comment(&mut self) -> Token {
-        while let Some(&c) = self.peek_char() {
-            if c == '\n' {
-                break;
+    fn consume_comment(&mut self, position: Position) -> Token<'a> {
+        let start = self.offset - 1;
+        // The `\r` of a line ending with `\r\n` is not part of the comment
+        self.collect(|c| c != '\n' && c != '\r');
+        self.document(start, position.row);
+        self.comment(position, start)
+    }
+
+    /// Remembers the text of a `///` comment starting at the byte offset `start`, if nothing
+    /// comes before it on its line, after the comments of the lines right above it.
+    fn document(&mut self, start: usize, row: usize) {
+        let text = &self.source[start..self.offset];
+        let Some(doc) = text.strip_prefix("///").filter(|doc| !doc.starts_with('/')) else {
+            return;
+        };
+        let line = self.source[..start].rsplit('\n').next().unwrap_or_default();
+        if !line.trim().is_empty() {
+            return;
+        }
+        if self.docs.last().is_some_and(|(last, _)| last + 1 != row) {
+            self.docs.clear();
+        }
+        self.docs.push((row, doc.strip_prefix(' ').unwrap_or(doc)));
+    }
+
+    /// Returns the text of the `///` doc comments on the lines right above a row, one line of
+    /// text per comment, if the lexer has read past them.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The row of the definition the comments document.
+    ///
+    /// # Examples
+    /// ```
+    /// use hydrogen::hash::lexer::Lexer;
+    ///
+    /// let mut lexer = Lexer::new("/// Doubles\n/// a number\ndouble(x) { x * 2 }");
+    /// lexer.lex();
+    ///
+    /// assert_eq!(lexer.doc(3).as_deref(), Some("Doubles\na number"));
+    /// assert_eq!(lexer.doc(4), None);
+    /// ```
+    pub fn doc(&self, row: usize) -> Option<String> {
+        match self.docs.last() {
+            Some((last, _)) if last + 1 == row => {
+                let lines: Vec<&str> = self.docs.iter().map(|(_, line)| *line).collect();
+                Some(lines.join("\n"))
             }
-            self.next_char();
+            _ => None,
         }
-        self.lex()
     }
 
     /// Consumes characters until the closing delimiter of a multiline comment is encountered.
-    fn consume_multiline_comment(&mut self) -> Token {
-        while let Some(&c) = self.peek_char() {
-            if c == '*' {
-                self.next_char();
-                if let Some(&c) = self.peek_char() {
-                    if c == '/' {
-                        self.next_char();
+    fn consume_multiline_comment(&mut self, position: Position) -> Token<'a> {
+        let start = self.offset - 1;
+        let mut previous = '\0';
+        // Skip the opening asterisk so that `/*/` does not close the comment
+        self.next_char();
+
+        loop {
+            match self.peek_char() {
+                Some(c) => {
+                    self.next_char();
+                    if previous == '*' && c == '/' {
                         break;
                     }
+                    previous = c;
                 }
+                // Skipping the comment would hide that it swallowed the rest of the program
+                None => return Token::UnterminatedComment(position),
             }
-            self.next_char();
         }
-        self.lex()
+
+        self.comment(position, start)
+    }
+
+    /// Returns a comment token starting at the byte offset `start` if the lexer keeps comments,
+    /// or the token after the comment otherwise.
+    fn comment(&mut self, position: Position, start: usize) -> Token<'a> {
+        if self.comments {
+            Token::Comment(position, self.source[start..self.offset].into())
+        } else {
+            self.lex()
+        }
     }
 
     /// Collects characters that satisfy the provided condition until a character that does not satisfy the condition is encountered.
-    fn collect<F>(&mut self, condition: F) -> String
+    fn collect<F>(&mut self, condition: F) -> &'a str
     where
         F: Fn(char) -> bool,
     {
-        let mut buffer = String::new();
-        while let Some(&c) = self.peek_char() {
+        let start = self.offset;
+        while let Some(c) = self.peek_char() {
             if condition(c) {
-                buffer.push(self.next_char().1);
+                self.next_char();
             } else {
                 break;
             }
         }
-        buffer
+        &self.source[start..self.offset]
     }
 
     /// Collects characters to form an identifier or a keyword.
-    fn collect_id(&mut self) -> Token {
-        let buffer = self.collect(|c| c.is_alphanumeric());
-        let mut current = self.position.clone();
-        current.col -= buffer.len();
-
-        match buffer.as_str() {
-            "if" => Token::Keyword(current, buffer),
-            "else" => Token::Keyword(current, buffer),
-            "while" => Token::Keyword(current, buffer),
-            "break" => Token::Keyword(current, buffer),
-            "continue" => Token::Keyword(current, buffer),
+    ///
+    /// Identifiers follow the Unicode rules for identifiers of programming languages: they start
+    /// with a letter or `_`, and go on with letters, digits, `_`, and combining marks.
+    fn collect_id(&mut self) -> Token<'a> {
+        let current = self.position.clone();
+        let buffer = self.collect(is_xid_continue);
+
+        if let Some(keyword) = Keyword::from_word(buffer) {
+            return Token::Keyword(current, keyword);
+        }
+        if let Some(ty) = TypeName::from_word(buffer) {
+            return Token::Type(current, ty);
+        }
+        match buffer {
             "in" => Token::In(current),
             "as" => Token::As(current),
-            "num" => Token::Type(current, buffer),
-            "str" => Token::Type(current, buffer),
-            "bool" => Token::Type(current, buffer),
-            "true" => Token::Boolean(current, buffer),
-            "false" => Token::Boolean(current, buffer),
-            _ => Token::Identifier(current, buffer),
+            "true" => Token::Boolean(current, buffer.into()),
+            "false" => Token::Boolean(current, buffer.into()),
+            _ => Token::Identifier(current, buffer.into()),
         }
     }
 
     /// Collects characters to form a numeric literal.
-    fn collect_number(&mut self) -> Token {
+    fn collect_number(&mut self) -> Token<'a> {
+        let current = self.position.clone();
         let buffer = self.collect(|c| c.is_numeric());
-        let mut current = self.position.clone();
-        current.col -= buffer.len();
-        Token::Number(current, buffer)
+        Token::Number(current, buffer.into())
     }
 
     /// Collects characters to form a string literal.
-    fn collect_string(&mut self) -> Token {
+    ///
+    /// Line breaks written as `\r\n` in the string are read as `\n`, so a program gets the same
+    /// strings whatever line endings its file is saved with.
+    fn collect_string(&mut self) -> Token<'a> {
         let (current, _) = self.next_char();
 
         let buffer = self.collect(|c| c != '"');
+        let buffer: Cow<'a, str> = match buffer.contains("\r\n") {
+            true => buffer.replace("\r\n", "\n").into(),
+            false => buffer.into(),
+        };
 
-        // Check if we reached the end of the string or if there is no closing double quote
-        if let Some(&c) = self.peek_char() {
-            if c == '"' {
+        // The string runs to the end of the source code if there is no closing double quote
+        match self.peek_char() {
+            Some(_) => {
                 // Consume the closing double quote
                 self.next_char();
-                Token::String(current.clone(), buffer)
-            } else {
-                // If there is no closing double quote, return an Unknown token
-                Token::Unknown(current.clone(), buffer)
+                Token::String(current, buffer)
             }
-        } else {
-            // If there are no more characters, return an Unknown token
-            Token::Unknown(self.position.clone(), buffer)
+            None => Token::UnterminatedString(current, buffer),
         }
     }
 }
 
+/// Tokens of the source code are returned until `Token::Eof`, which ends the iteration.
+///
+/// # Examples
+/// ```
+/// use hydrogen::hash::{lexer::Lexer, tokens::Token};
+///
+/// let names: Vec<_> = Lexer::new("x = y + 1")
+///     .filter_map(|token| match token {
+///         Token::Identifier(_, name) => Some(name),
+///         _ => None,
+///     })
+///     .collect();
+/// assert_eq!(names, ["x", "y"]);
+/// ```
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.lex() {
+            Token::Eof(_) => None,
+            token => Some(token),
+        }
+    }
+}
+
+// The lexer keeps returning `Token::Eof` at the end of the source code
+impl FusedIterator for Lexer<'_> {}
+
+/// Lexes the whole source code, without comments.
+///
+/// # Arguments
+///
+/// * `source` - The source code.
+///
+/// # Returns
+///
+/// * `Vec<Token>` - The tokens of the source code, without the final `Token::Eof`.
+///
+/// # Examples
+/// ```
+/// use hydrogen::hash::lexer::tokenize;
+///
+/// let tokens = tokenize("print(1) // one");
+/// assert_eq!(tokens.len(), 4);
+/// assert_eq!(tokens[0].to_string(), "Identifier(print)");
+/// ```
+pub fn tokenize(source: &str) -> Vec<Token<'_>> {
+    Lexer::new(source).collect()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -390,7 +578,7 @@ mod test {
                 var1: num = 1234
                 var2 = var1 + 1234
 
-                var3: num = lambda() {
+                var3: fn = fn() {
                     var: str = "Hello, World!"
                 }
 
@@ -398,18 +586,239 @@ mod test {
             }
         "#;
 
+        let tokens = tokenize(program);
+
+        // TODO: Add more specific assertions based on the expected tokens
+        // For example, you can assert the types and positions of tokens.
+
+        // The text of the tokens is borrowed from the program
+        for token in tokens {
+            if let Token::Identifier(_, text) | Token::String(_, text) | Token::Number(_, text) =
+                token
+            {
+                assert!(matches!(text, std::borrow::Cow::Borrowed(_)));
+            }
+        }
+
+        // Identifiers can hold underscores anywhere
+        let mut lexer = Lexer::new("read_file _tmp");
+        assert!(matches!(lexer.lex(), Token::Identifier(_, name) if name == "read_file"));
+        assert!(matches!(lexer.lex(), Token::Identifier(_, name) if name == "_tmp"));
+
+        // Every keyword and type name is lexed as its own variant, and longer words are not
+        for keyword in Keyword::ALL {
+            let token = Lexer::new(keyword.name()).lex();
+            assert!(matches!(token, Token::Keyword(_, k) if k == keyword));
+        }
+        for ty in TypeName::ALL {
+            assert!(matches!(Lexer::new(ty.name()).lex(), Token::Type(_, t) if t == ty));
+        }
+        let mut lexer = Lexer::new("iffy number");
+        assert!(matches!(lexer.lex(), Token::Identifier(_, name) if name == "iffy"));
+        assert!(matches!(lexer.lex(), Token::Identifier(_, name) if name == "number"));
+
+        // Ranges are lexed apart from the numbers around them
+        let mut lexer = Lexer::new("0..=9 . ..");
+        assert!(matches!(lexer.lex(), Token::Number(_, n) if n == "0"));
+        assert!(matches!(lexer.lex(), Token::DotDotEqual(_)));
+        assert!(matches!(lexer.lex(), Token::Number(_, n) if n == "9"));
+        assert!(matches!(lexer.lex(), Token::Dot(_)));
+        assert!(matches!(lexer.lex(), Token::DotDot(_)));
+    }
+
+    #[test]
+    fn test_comments() {
+        let program = "x = 1 // one\n/* two **/ y";
+
+        let mut lexer = Lexer::with_comments(program);
+        assert!(matches!(lexer.lex(), Token::Identifier(_, _)));
+        assert!(matches!(lexer.lex(), Token::Equal(_)));
+        assert!(matches!(lexer.lex(), Token::Number(_, _)));
+        assert_eq!(
+            lexer.lex(),
+            Token::Comment(
+                Position {
+                    col: 7,
+                    row: 1,
+                    offset: 6
+                },
+                "// one".into()
+            )
+        );
+        assert_eq!(
+            lexer.peek(),
+            Token::Comment(
+                Position {
+                    col: 1,
+                    row: 2,
+                    offset: 13
+                },
+                "/* two **/".into()
+            )
+        );
+        lexer.lex();
+        assert!(matches!(lexer.lex(), Token::Identifier(_, _)));
+
         let mut lexer = Lexer::new(program);
-        let mut tokens = Vec::new();
+        lexer.lex();
+        lexer.lex();
+        lexer.lex();
+        assert!(matches!(lexer.lex(), Token::Identifier(_, _)));
+    }
+
+    #[test]
+    fn test_unterminated() {
+        let mut lexer = Lexer::new("x = \"abc\ndef");
+        lexer.lex();
+        lexer.lex();
+        assert_eq!(
+            lexer.lex(),
+            Token::UnterminatedString(
+                Position {
+                    col: 5,
+                    row: 1,
+                    offset: 4
+                },
+                "abc\ndef".into()
+            )
+        );
+        assert!(matches!(lexer.lex(), Token::Eof(_)));
+
+        // Comments that are not closed are not skipped
+        let mut lexer = Lexer::new("x /* a */ /* b");
+        lexer.lex();
+        assert_eq!(
+            lexer.lex().to_string(),
+            "unterminated block comment starting at 11:1"
+        );
+        let mut lexer = Lexer::with_comments("x /* b");
+        lexer.lex();
+        assert!(matches!(lexer.lex(), Token::UnterminatedComment(_)));
+    }
+
+    #[test]
+    fn test_spans() {
+        let mut lexer = Lexer::new("é = \"ñu\"\nπ2");
+
+        let (token, span) = lexer.lex_span();
+        assert_eq!(token.to_string(), "Identifier(é)");
+        assert_eq!(span, Span { start: 0, len: 2 });
+        lexer.lex();
+
+        let (token, span) = lexer.lex_span();
+        assert_eq!(
+            token.position(),
+            &Position {
+                col: 5,
+                row: 1,
+                offset: 5
+            }
+        );
+        assert_eq!(span, Span { start: 5, len: 5 });
+
+        let (token, span) = lexer.lex_span();
+        assert_eq!(
+            token.position(),
+            &Position {
+                col: 1,
+                row: 2,
+                offset: 11
+            }
+        );
+        assert_eq!(span.end(), 14);
+
+        let (token, span) = lexer.lex_span();
+        assert!(matches!(token, Token::Eof(_)));
+        assert_eq!(span, Span { start: 14, len: 0 });
+
+        // The iterators end at the end of the source code, and stay there
+        let mut lexer = Lexer::new("a b");
+        assert_eq!(lexer.by_ref().count(), 2);
+        assert_eq!(lexer.next(), None);
+        let spans: Vec<_> = Lexer::new("é π").spans().map(|(_, span)| span).collect();
+        assert_eq!(
+            spans,
+            [Span { start: 0, len: 2 }, Span { start: 3, len: 2 }]
+        );
+    }
 
+    #[test]
+    fn test_unicode() {
+        // Wide characters take two columns, and combining marks none
+        let program = "名前 = café_2 + e\u{301}x ~";
+        let mut lexer = Lexer::new(program);
+        let mut identifiers = Vec::new();
         loop {
-            let token = lexer.lex();
-            if let Token::Eof(_) = token {
-                break;
+            match lexer.lex() {
+                Token::Identifier(position, name) => identifiers.push((name, position.col)),
+                Token::Unknown(position, _) => {
+                    assert_eq!(position.col, 20);
+                    assert_eq!(
+                        Position::locate(program, position.offset, TAB_WIDTH),
+                        position
+                    );
+                    break;
+                }
+                token => assert!(!matches!(token, Token::Eof(_))),
             }
-            tokens.push(token);
         }
+        assert_eq!(
+            identifiers,
+            [
+                ("名前".into(), 1),
+                ("café_2".into(), 8),
+                ("e\u{301}x".into(), 17),
+            ]
+        );
+
+        // Digits and marks do not start identifiers
+        let mut lexer = Lexer::new("2x \u{301}");
+        assert!(matches!(lexer.lex(), Token::Number(_, _)));
+        assert!(matches!(lexer.lex(), Token::Identifier(_, name) if name == "x"));
+        assert!(matches!(lexer.lex(), Token::Unknown(_, _)));
+    }
 
-        // TODO: Add more specific assertions based on the expected tokens
-        // For example, you can assert the types and positions of tokens.
+    #[test]
+    fn test_line_endings() {
+        let program = "a = 1 // one\r\n\tb = \"x\r\ny\"\n  \tc\r\nab\td";
+        let positions: Vec<_> = Lexer::with_comments(program)
+            .map(|token| {
+                let position = token.position().clone();
+                assert_eq!(
+                    Position::locate(program, position.offset, TAB_WIDTH),
+                    position
+                );
+                (token.to_string(), position.row, position.col)
+            })
+            .collect();
+        assert_eq!(
+            positions,
+            [
+                ("Identifier(a)".into(), 1, 1),
+                ("=".into(), 1, 3),
+                ("Number(1)".into(), 1, 5),
+                ("// one".into(), 1, 7),
+                // Tabs move to the next tab stop
+                ("Identifier(b)".into(), 2, 5),
+                ("=".into(), 2, 7),
+                ("String(\"x\ny\")".into(), 2, 9),
+                ("Identifier(c)".into(), 4, 5),
+                ("Identifier(ab)".into(), 5, 1),
+                ("Identifier(d)".into(), 5, 5),
+            ]
+        );
+
+        // The tab stops can be farther apart
+        let mut lexer = Lexer::new(program);
+        lexer.set_tab_width(8);
+        let columns: Vec<_> = lexer
+            .filter(|token| matches!(token, Token::Identifier(..)))
+            .map(|token| {
+                let position = token.position().clone();
+                assert_eq!(Position::locate(program, position.offset, 8), position);
+                position.col
+            })
+            .collect();
+        assert_eq!(columns, [1, 9, 9, 1, 9]);
     }
 }
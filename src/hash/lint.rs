@@ -0,0 +1,566 @@
+use std::fmt;
+
+use super::{
+    ast::{Block, Expr, Item, Literal, Stmt},
+    diagnostics::Diagnostic,
+    fold::{walk_expr, Folder},
+    optimize::Optimizer,
+    parser::Program,
+    resolve::{DefinitionKind, Names},
+    tokens::{Position, TAB_WIDTH},
+};
+
+/// A problem found by a rule of the linter, in a program that can still run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    /// The identifier of the rule that found the problem.
+    pub rule: &'static str,
+    /// The position the problem is about.
+    pub position: Position,
+    /// The problem found.
+    pub message: String,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Diagnostic::from(self.clone()))
+    }
+}
+
+/// A part of a program a rule looks at
+#[derive(Debug, Clone, Copy)]
+pub enum Node<'a> {
+    /// Block: (block)
+    Block(&'a Block),
+    /// Expression: (expression)
+    Expr(&'a Expr),
+}
+
+/// A program being linted, with what the rules need to know about it.
+pub struct Context<'a> {
+    /// The source code of the program.
+    pub source: &'a str,
+    /// The statements of the program, some of which may not parse.
+    pub program: &'a Program,
+    /// Where the names of the program are defined and used.
+    pub names: &'a Names,
+}
+
+impl Context<'_> {
+    /// Calls a function with every block and expression of the program that parses, in the
+    /// order of the source code, and the row of the statement they are in.
+    pub fn walk(&self, visit: &mut dyn FnMut(Node, usize)) {
+        for parsed in &self.program.statements {
+            if let Ok(statement) = &parsed.statement {
+                let mut walk = Walk {
+                    row: parsed.start.row,
+                    visit: &mut *visit,
+                };
+                walk.fold_stmt(statement.clone());
+            }
+        }
+    }
+
+    /// Returns the position of the first character of a row that is not a space.
+    pub fn row_start(&self, row: usize) -> Position {
+        let mut offset = 0;
+        for line in self.source.split_inclusive('\n').take(row - 1) {
+            offset += line.len();
+        }
+        let line = &self.source[offset..];
+        offset += line.len() - line.trim_start_matches([' ', '\t']).len();
+        Position::locate(self.source, offset, TAB_WIDTH)
+    }
+}
+
+/// Pass calling a function with the blocks and expressions of a statement, leaving it as it is.
+struct Walk<'a> {
+    row: usize,
+    visit: &'a mut dyn FnMut(Node, usize),
+}
+
+impl Folder for Walk<'_> {
+    fn fold_block(&mut self, block: Block) -> Block {
+        (self.visit)(Node::Block(&block), self.row);
+        let outer = self.row;
+        let Block {
+            statements,
+            rows,
+            end,
+        } = block;
+        let statements = statements
+            .into_iter()
+            .enumerate()
+            .map(|(i, statement)| {
+                self.row = rows.get(i).copied().unwrap_or(outer);
+                self.fold_stmt(statement)
+            })
+            .collect();
+        self.row = outer;
+        Block {
+            statements,
+            rows,
+            end,
+        }
+    }
+
+    fn fold_expr(&mut self, expression: Expr) -> Expr {
+        (self.visit)(Node::Expr(&expression), self.row);
+        walk_expr(self, expression)
+    }
+}
+
+/// A check the linter runs on programs.
+///
+/// # Examples
+/// ```
+/// use hydrogen::hash::{
+///     ast::{Expr, Literal},
+///     lint::{Context, Lint, Linter, Node, Rule},
+/// };
+///
+/// struct StringLiterals;
+///
+/// impl Rule for StringLiterals {
+///     fn id(&self) -> &'static str {
+///         "string-literal"
+///     }
+///
+///     fn description(&self) -> &'static str {
+///         "strings written in the source code"
+///     }
+///
+///     fn check(&self, context: &Context, lints: &mut Vec<Lint>) {
+///         context.walk(&mut |node, row| {
+///             if let Node::Expr(Expr::Literal(Literal::String(_))) = node {
+///                 lints.push(Lint {
+///                     rule: self.id(),
+///                     position: context.row_start(row),
+///                     message: "a string is written here".to_string(),
+///                 });
+///             }
+///         });
+///     }
+/// }
+///
+/// let mut linter = Linter::new();
+/// linter.register(Box::new(StringLiterals));
+/// let lints = linter.lint("print(\"hi\")");
+///
+/// assert_eq!(lints.len(), 1);
+/// assert_eq!(lints[0].rule, "string-literal");
+/// ```
+pub trait Rule {
+    /// Returns the identifier the rule is reported and toggled with, like `unused-variable`.
+    fn id(&self) -> &'static str;
+
+    /// Returns what the rule finds, shown when the rules are listed.
+    fn description(&self) -> &'static str;
+
+    /// Adds the problems the rule finds in a program.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - The program.
+    /// * `lints` - The problems found so far.
+    fn check(&self, context: &Context, lints: &mut Vec<Lint>);
+}
+
+/// Linter running a registry of rules that can each be turned on and off.
+///
+/// # Examples
+/// ```
+/// use hydrogen::hash::lint::Linter;
+///
+/// let mut linter = Linter::new();
+/// let lints = linter.lint("f(x) { 1 }");
+/// assert_eq!(lints[0].rule, "unused-parameter");
+///
+/// assert!(linter.set_enabled("unused-parameter", false));
+/// assert!(linter.lint("f(x) { 1 }").is_empty());
+/// ```
+pub struct Linter {
+    rules: Vec<(Box<dyn Rule>, bool)>,
+    tab_width: usize, // Number of columns between tab stops in the positions of lints
+}
+
+impl fmt::Debug for Linter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rules: Vec<_> = self.rules().map(|(rule, on)| (rule.id(), on)).collect();
+        f.debug_struct("Linter").field("rules", &rules).finish()
+    }
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Linter {
+    /// Creates a linter with every rule of the language turned on.
+    pub fn new() -> Self {
+        let rules: [Box<dyn Rule>; 6] = [
+            Box::new(UnusedVariable),
+            Box::new(UnusedParameter),
+            Box::new(Shadowing),
+            Box::new(Unreachable),
+            Box::new(EmptyBlock),
+            Box::new(ConstantCondition),
+        ];
+        Self {
+            rules: rules.into_iter().map(|rule| (rule, true)).collect(),
+            tab_width: TAB_WIDTH,
+        }
+    }
+
+    /// Sets the number of columns between tab stops, which the columns of the positions of lints
+    /// are counted with.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The number of columns, at least 1.
+    pub fn set_tab_width(&mut self, width: usize) {
+        self.tab_width = width.max(1);
+    }
+
+    /// Adds a rule, turned on.
+    pub fn register(&mut self, rule: Box<dyn Rule>) {
+        self.rules.push((rule, true));
+    }
+
+    /// Turns a rule on or off.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The identifier of the rule.
+    /// * `enabled` - Whether the rule runs.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the linter has a rule with the identifier.
+    pub fn set_enabled(&mut self, id: &str, enabled: bool) -> bool {
+        let rule = self.rules.iter_mut().find(|(rule, _)| rule.id() == id);
+        rule.map(|(_, on)| *on = enabled).is_some()
+    }
+
+    /// Returns the rules of the linter, in the order they run, and whether each one is on.
+    pub fn rules(&self) -> impl Iterator<Item = (&dyn Rule, bool)> {
+        self.rules
+            .iter()
+            .map(|(rule, enabled)| (rule.as_ref(), *enabled))
+    }
+
+    /// Runs the rules that are on over a program.
+    ///
+    /// The statements that do not parse are skipped, so the rest of the program is linted.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source code of the program.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Lint>` - The problems found, in the order of the source code.
+    pub fn lint(&self, source: &str) -> Vec<Lint> {
+        let program = Program::new(source);
+        let names = Names::new(source, &program.statements);
+        let context = Context {
+            source,
+            program: &program,
+            names: &names,
+        };
+
+        let mut lints = Vec::new();
+        for (rule, _) in self.rules.iter().filter(|(_, enabled)| *enabled) {
+            rule.check(&context, &mut lints);
+        }
+        lints.sort_by_key(|lint| lint.position.offset);
+        // Rules find positions with the default tab stops, which only the columns depend on
+        if self.tab_width != TAB_WIDTH {
+            for lint in &mut lints {
+                lint.position = Position::locate(source, lint.position.offset, self.tab_width);
+            }
+        }
+        lints
+    }
+}
+
+/// Adds a lint for every definition of a kind that is never used, except names starting with
+/// `_`, the variables of the program itself, which other programs run after it can use, and the
+/// parameters of the functions of `impl` blocks.
+fn unused(context: &Context, kind: DefinitionKind, rule: &'static str, lints: &mut Vec<Lint>) {
+    let globals: Vec<_> = (context.program.statements.iter())
+        .filter_map(|parsed| {
+            let start = parsed.start.offset;
+            match parsed.statement {
+                Ok(Stmt::Definition(_) | Stmt::Declaration(_)) => Some(start..start + 1),
+                // The names of a destructuring are in its pattern, closed by its first bracket
+                Ok(Stmt::Destructure(_)) => {
+                    let length = context.source[start..].find([']', '}'])?;
+                    Some(start..start + length)
+                }
+                // The parameters of the functions of an `impl` block are the ones the interface
+                // requires, so they cannot be removed
+                Ok(Stmt::Item(Item::Impl(_))) if kind == DefinitionKind::Parameter => {
+                    Some(start..parsed.end.offset)
+                }
+                _ => None,
+            }
+        })
+        .collect();
+    for (i, definition) in context.names.definitions.iter().enumerate() {
+        if definition.kind == kind
+            && !definition.name.starts_with('_')
+            && !globals
+                .iter()
+                .any(|global| global.contains(&definition.position.offset))
+            && context.names.references_of(i).is_empty()
+        {
+            let what = match kind {
+                DefinitionKind::Parameter => "parameter",
+                _ => "variable",
+            };
+            lints.push(Lint {
+                rule,
+                position: definition.position.clone(),
+                message: format!("{} `{}` is never used", what, definition.name),
+            });
+        }
+    }
+}
+
+/// Rule finding variables of functions and blocks that are never used.
+struct UnusedVariable;
+
+impl Rule for UnusedVariable {
+    fn id(&self) -> &'static str {
+        "unused-variable"
+    }
+
+    fn description(&self) -> &'static str {
+        "variables of functions and blocks that are never used"
+    }
+
+    fn check(&self, context: &Context, lints: &mut Vec<Lint>) {
+        unused(context, DefinitionKind::Variable, self.id(), lints);
+    }
+}
+
+/// Rule finding parameters that are never used.
+struct UnusedParameter;
+
+impl Rule for UnusedParameter {
+    fn id(&self) -> &'static str {
+        "unused-parameter"
+    }
+
+    fn description(&self) -> &'static str {
+        "parameters that are never used"
+    }
+
+    fn check(&self, context: &Context, lints: &mut Vec<Lint>) {
+        unused(context, DefinitionKind::Parameter, self.id(), lints);
+    }
+}
+
+/// Rule finding definitions that hide another definition of the same name.
+struct Shadowing;
+
+impl Rule for Shadowing {
+    fn id(&self) -> &'static str {
+        "shadowed-binding"
+    }
+
+    fn description(&self) -> &'static str {
+        "definitions hiding another definition of the same name"
+    }
+
+    fn check(&self, context: &Context, lints: &mut Vec<Lint>) {
+        for definition in &context.names.definitions {
+            if let Some(shadowed) = definition.shadows {
+                let shadowed = &context.names.definitions[shadowed];
+                let mut message = format!(
+                    "`{}` hides the definition at {}",
+                    definition.name, shadowed.position
+                );
+                if definition.hides_global {
+                    message.push_str(&format!(
+                        ", declare `global {}` to assign to it instead",
+                        definition.name
+                    ));
+                }
+                lints.push(Lint {
+                    rule: self.id(),
+                    position: definition.position.clone(),
+                    message,
+                });
+            }
+        }
+    }
+}
+
+/// Rule finding statements after a `return`, `break`, or `continue` of the same block.
+struct Unreachable;
+
+impl Rule for Unreachable {
+    fn id(&self) -> &'static str {
+        "unreachable-code"
+    }
+
+    fn description(&self) -> &'static str {
+        "statements after a `return`, `break`, or `continue`"
+    }
+
+    fn check(&self, context: &Context, lints: &mut Vec<Lint>) {
+        context.walk(&mut |node, row| {
+            let Node::Block(block) = node else {
+                return;
+            };
+            let jump = block.statements.iter().position(|statement| {
+                matches!(statement, Stmt::Return(_) | Stmt::Break | Stmt::Continue)
+            });
+            if let Some(i) = jump.filter(|i| i + 1 < block.statements.len()) {
+                let keyword = match block.statements[i] {
+                    Stmt::Break => "break",
+                    Stmt::Continue => "continue",
+                    _ => "return",
+                };
+                lints.push(Lint {
+                    rule: self.id(),
+                    position: context.row_start(block.rows.get(i + 1).copied().unwrap_or(row)),
+                    message: format!("unreachable statement after `{}`", keyword),
+                });
+            }
+        });
+    }
+}
+
+/// Rule finding blocks without statements.
+struct EmptyBlock;
+
+impl Rule for EmptyBlock {
+    fn id(&self) -> &'static str {
+        "empty-block"
+    }
+
+    fn description(&self) -> &'static str {
+        "blocks without statements"
+    }
+
+    fn check(&self, context: &Context, lints: &mut Vec<Lint>) {
+        context.walk(&mut |node, row| {
+            if let Node::Block(block) = node {
+                if block.statements.is_empty() {
+                    lints.push(Lint {
+                        rule: self.id(),
+                        position: context.row_start(row),
+                        message: "empty block".to_string(),
+                    });
+                }
+            }
+        });
+    }
+}
+
+/// Rule finding conditions of `if` and `while` that are always true or always false.
+///
+/// `while true` is how loops left with `break` are written, so it is allowed.
+struct ConstantCondition;
+
+impl Rule for ConstantCondition {
+    fn id(&self) -> &'static str {
+        "constant-condition"
+    }
+
+    fn description(&self) -> &'static str {
+        "conditions of `if` and `while` that are always true or always false"
+    }
+
+    fn check(&self, context: &Context, lints: &mut Vec<Lint>) {
+        context.walk(&mut |node, row| {
+            let (keyword, condition) = match node {
+                Node::Expr(Expr::If(statement)) => ("if", &statement.condition),
+                Node::Expr(Expr::While(statement))
+                    if *statement.condition != Expr::Literal(Literal::Boolean(true)) =>
+                {
+                    ("while", &statement.condition)
+                }
+                _ => return,
+            };
+            if let Expr::Literal(Literal::Boolean(value)) = Optimizer.fold_expr(*condition.clone())
+            {
+                lints.push(Lint {
+                    rule: self.id(),
+                    position: context.row_start(row),
+                    message: format!("the condition of this `{}` is always {}", keyword, value),
+                });
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lint() {
+        let source = "limit = 10\n\
+                      scale(x, _unused) {\n    \
+                          return x * 2\n    \
+                          print(x)\n\
+                      }\n\
+                      for i in 0..3 {\n    \
+                          limit = limit + 1\n\
+                      }\n\
+                      if 1 > 2 { print(limit) } else {}\n\
+                      while true { break }\n\
+                      while !true { scale(1, 2) }\n\
+                      check(limit) { limit }\n\
+                      try { scale(limit, 1) } catch e { print(\"failed\") }";
+        let mut linter = Linter::new();
+        let found: Vec<_> = linter
+            .lint(source)
+            .iter()
+            .map(|lint| (lint.rule, lint.position.row, lint.position.col))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("unreachable-code", 4, 5),
+                ("unused-variable", 6, 5),
+                ("empty-block", 9, 1),
+                ("constant-condition", 9, 1),
+                ("constant-condition", 11, 1),
+                ("shadowed-binding", 12, 7),
+                ("unused-variable", 13, 31),
+            ]
+        );
+
+        let lints = linter.lint(source);
+        assert_eq!(
+            lints[0].to_string(),
+            "WARNING[H0204 unreachable-code]: unreachable statement after `return` at 5:4"
+        );
+        assert_eq!(lints[5].message, "`limit` hides the definition at 1:1");
+        assert_eq!(
+            lints[4].message,
+            "the condition of this `while` is always false"
+        );
+
+        assert!(linter.set_enabled("unused-variable", false));
+        assert!(!linter.set_enabled("no-such-rule", false));
+        assert_eq!(linter.lint(source).len(), 5);
+        let enabled: Vec<_> = linter.rules().map(|(rule, on)| (rule.id(), on)).collect();
+        assert_eq!(enabled[0], ("unused-variable", false));
+        assert_eq!(enabled.len(), 6);
+
+        let lints = linter.lint("x = 1\nf() { x = 2 }\ng() { global x\nx = 3 }");
+        assert_eq!(lints.len(), 1);
+        assert_eq!(
+            lints[0].message,
+            "`x` hides the definition at 1:1, declare `global x` to assign to it instead"
+        );
+    }
+}
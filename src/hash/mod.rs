@@ -1,12 +1,49 @@
 /// Module containing abstract syntax tree (AST) definitions.
 pub mod ast;
+/// Module containing the big integers whole numbers grow into when they are too large.
+#[cfg(feature = "bignum")]
+pub mod bignum;
+/// Module containing the functions provided by the language itself.
+pub mod builtins;
+/// Module containing the checks run on a program before it is evaluated.
+pub mod checker;
+/// Module containing the codes of diagnostics and their explanations.
+pub mod codes;
+/// Module containing the compiler that lowers programs into bytecode.
+pub mod compile;
+/// Module containing the coverage of the lines of a program by the statements that ran.
+pub mod coverage;
+/// Module containing the lossless concrete syntax tree used by tools that rewrite source code.
+pub mod cst;
+/// Module containing the problems found in programs before they run, errors and warnings.
+pub mod diagnostics;
+/// Module containing the scopes variables and functions live in.
+pub mod environment;
 /// Module containing evaluator implementation.
 pub mod evaluator;
+/// Module containing the API for passes that rewrite the AST.
+pub mod fold;
+/// Module containing the source code formatter.
+pub mod format;
+/// Module containing the reading and writing of JSON text.
+pub mod json;
 /// Module containing lexer implementation.
 pub mod lexer;
+/// Module containing the linter and the rules it checks programs with.
+pub mod lint;
+/// Module containing the pass that simplifies programs before they run.
+pub mod optimize;
 /// Module containing parser implementation.
 pub mod parser;
 /// Module contianing printing methods used for testing purposes
 pub mod print;
+/// Module containing the profiler timing the functions of a program.
+pub mod profile;
+/// Module containing the pass finding where the names of a program are defined and used.
+pub mod resolve;
 /// Module containing token definitions.
 pub mod tokens;
+/// Module containing the values programs compute with.
+pub mod value;
+/// Module containing the virtual machine that runs bytecode.
+pub mod vm;
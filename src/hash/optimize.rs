@@ -0,0 +1,256 @@
+use super::{
+    ast::{Block, Expr, If, Literal, Tree, TypeExpr, UnaryExpression},
+    evaluator::{binary, unary},
+    fold::{walk_expr, Folder},
+    value::Value,
+};
+
+/// Pass that simplifies a program without changing what it computes.
+///
+/// Operators applied to literals are computed ahead of time, branches of an `if` whose condition
+/// is a literal are replaced by the branch that is taken, and unary operators that leave their
+/// operand unchanged are removed. Operations that would fail, like a division by zero, are left
+/// for the program to fail on when it runs.
+///
+/// # Example
+/// ```
+/// use hydrogen::hash::{fold::Folder, optimize::Optimizer, parser::Parser, print};
+///
+/// let tree = Parser::new("x = 2 * 3 + 4").parse_program().unwrap();
+/// let tree = Optimizer.fold_tree(tree);
+///
+/// assert_eq!(print::to_source(&tree), "x = 10\n");
+/// ```
+#[derive(Debug, Default)]
+pub struct Optimizer;
+
+impl Folder for Optimizer {
+    fn fold_expr(&mut self, expression: Expr) -> Expr {
+        match walk_expr(self, expression) {
+            Expr::Unary(unary_expression) => simplify_unary(unary_expression),
+
+            Expr::Binary(binary_expression) => {
+                let (Some(left), Some(right)) = (
+                    value(&binary_expression.left),
+                    value(&binary_expression.right),
+                ) else {
+                    return short_circuit(Expr::Binary(binary_expression));
+                };
+
+                let result = match binary_expression.op.as_str() {
+                    "&&" | "||" => match (left, right) {
+                        (Value::Boolean(l), Value::Boolean(r)) => {
+                            Ok(Value::Boolean(if binary_expression.op == "&&" {
+                                l && r
+                            } else {
+                                l || r
+                            }))
+                        }
+                        _ => return Expr::Binary(binary_expression),
+                    },
+                    op => binary(op, left, right),
+                };
+                match result.ok().and_then(literal) {
+                    Some(folded) => folded,
+                    None => Expr::Binary(binary_expression),
+                }
+            }
+
+            // Only the branch a literal condition chooses is kept, in a block so the variables it
+            // defines stay in a scope of their own
+            Expr::If(If {
+                condition,
+                then,
+                otherwise,
+            }) => match *condition {
+                Expr::Literal(Literal::Boolean(true)) => Expr::Block(then),
+                Expr::Literal(Literal::Boolean(false)) => match otherwise {
+                    Some(otherwise) => *otherwise,
+                    None => Expr::Block(Block::default()),
+                },
+                condition => Expr::If(If {
+                    condition: Box::new(condition),
+                    then,
+                    otherwise,
+                }),
+            },
+
+            // Parentheses around a single literal are no longer needed
+            Expr::Group(mut elements) if elements.len() == 1 && value(&elements[0]).is_some() => {
+                elements.remove(0)
+            }
+
+            expression => expression,
+        }
+    }
+}
+
+/// Optimizes a program.
+///
+/// # Arguments
+///
+/// * `tree` - The abstract syntax tree of the program.
+///
+/// # Returns
+///
+/// * `Tree` - The simplified abstract syntax tree.
+pub fn optimize(tree: Tree) -> Tree {
+    Optimizer.fold_tree(tree)
+}
+
+/// Computes a unary operator applied to a literal, or removes one that leaves its operand
+/// unchanged, like `+x` or `!!x`.
+fn simplify_unary(unary_expression: UnaryExpression) -> Expr {
+    if let Some(operand) = value(&unary_expression.expr) {
+        if let Ok(Some(folded)) = unary(&unary_expression.op, operand).map(literal) {
+            return folded;
+        }
+        return Expr::Unary(unary_expression);
+    }
+
+    let UnaryExpression { op, expr } = unary_expression;
+    match (op.as_str(), *expr) {
+        ("+", operand) if kind(&operand) == Some(TypeExpr::Number) => operand,
+        ("-", Expr::Unary(inner))
+            if inner.op == "-" && kind(&inner.expr) == Some(TypeExpr::Number) =>
+        {
+            *inner.expr
+        }
+        ("!", Expr::Unary(inner))
+            if inner.op == "!" && kind(&inner.expr) == Some(TypeExpr::Boolean) =>
+        {
+            *inner.expr
+        }
+        (_, expr) => Expr::Unary(UnaryExpression {
+            op,
+            expr: Box::new(expr),
+        }),
+    }
+}
+
+/// Replaces `true || x` with `true` and `false && x` with `false`, since `x` is never evaluated.
+fn short_circuit(expression: Expr) -> Expr {
+    match &expression {
+        Expr::Binary(binary_expression) => {
+            match (&*binary_expression.left, binary_expression.op.as_str()) {
+                (Expr::Literal(Literal::Boolean(true)), "||") => {
+                    Expr::Literal(Literal::Boolean(true))
+                }
+                (Expr::Literal(Literal::Boolean(false)), "&&") => {
+                    Expr::Literal(Literal::Boolean(false))
+                }
+                _ => expression,
+            }
+        }
+        _ => expression,
+    }
+}
+
+/// Returns the value of an expression made only of a literal, or of a negated number literal.
+fn value(expression: &Expr) -> Option<Value> {
+    match expression {
+        Expr::Literal(Literal::String(value)) => Some(Value::from(value.as_str())),
+        Expr::Literal(Literal::Boolean(value)) => Some(Value::Boolean(*value)),
+        Expr::Literal(Literal::Number(value)) => value.parse::<i64>().ok().map(Value::Integer),
+        Expr::Unary(unary_expression) if unary_expression.op == "-" => {
+            match value(&unary_expression.expr)? {
+                Value::Integer(number) => number.checked_neg().map(Value::Integer),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Writes a value as a literal, negating the literal of a negative number.
+///
+/// Fractions are never written as literals, since number literals only have digits.
+///
+/// # Returns
+///
+/// * `Option<Expr>` - The literal, or None if the value cannot be written as one.
+fn literal(value: Value) -> Option<Expr> {
+    let negate = |expr| {
+        Expr::Unary(UnaryExpression {
+            op: "-".to_string(),
+            expr: Box::new(expr),
+        })
+    };
+
+    match value {
+        Value::Boolean(value) => Some(Expr::Literal(Literal::Boolean(value))),
+        Value::String(value) => Some(Expr::Literal(Literal::String(value.to_string()))),
+        Value::Integer(number) if number < 0 => {
+            literal(Value::Integer(number.checked_neg()?)).map(negate)
+        }
+        Value::Integer(number) => Some(Expr::Literal(Literal::Number(number.to_string()))),
+        _ => None,
+    }
+}
+
+/// Returns the type an expression always has when it succeeds, if it can be told without running
+/// it.
+fn kind(expression: &Expr) -> Option<TypeExpr> {
+    match expression {
+        Expr::Literal(Literal::Number(_)) => Some(TypeExpr::Number),
+        Expr::Literal(Literal::String(_)) => Some(TypeExpr::String),
+        Expr::Literal(Literal::Boolean(_)) => Some(TypeExpr::Boolean),
+        Expr::Unary(unary_expression) => match unary_expression.op.as_str() {
+            "!" => Some(TypeExpr::Boolean),
+            _ => Some(TypeExpr::Number),
+        },
+        Expr::Binary(binary_expression) => match binary_expression.op.as_str() {
+            "==" | "!=" | "<" | "<=" | ">" | ">=" | "&&" | "||" | "in" => Some(TypeExpr::Boolean),
+            "-" | "*" | "/" | "%" | "^" => Some(TypeExpr::Number),
+            _ => None,
+        },
+        Expr::Cast(cast) => Some(cast.ty),
+        Expr::Group(elements) if elements.len() == 1 => kind(&elements[0]),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hash::{parser::Parser, print};
+
+    /// Optimizes a program and writes it back as source code.
+    fn optimized(source: &str) -> String {
+        print::to_source(&optimize(Parser::new(source).parse_program().unwrap()))
+    }
+
+    #[test]
+    fn test_optimize() {
+        assert_eq!(optimized("2 * 3 + 4"), "10\n");
+        assert_eq!(optimized("(2 + 3) * 4"), "20\n");
+        assert_eq!(optimized("x = 1 - 4"), "x = -3\n");
+        assert_eq!(optimized("x = -(-1 * 3)"), "x = 3\n");
+        assert_eq!(optimized("x = 8 / 4"), "x = 2\n");
+        assert_eq!(optimized("x = 1 / 4"), "x = 1 / 4\n");
+        assert_eq!(optimized("\"a\" + \"b\" == \"ab\""), "true\n");
+        assert_eq!(optimized("x = 1 + y * 2"), "x = 1 + y * 2\n");
+        assert_eq!(optimized("1 / 0"), "1 / 0\n");
+        assert_eq!(optimized("1 + \"a\""), "1 + \"a\"\n");
+        assert_eq!(
+            optimized("9223372036854775807 + 1"),
+            "9223372036854775807 + 1\n"
+        );
+
+        assert_eq!(optimized("if 1 < 2 { a } else { b }"), "{\n    a\n}\n");
+        assert_eq!(optimized("if false { a } else { b }"), "{\n    b\n}\n");
+        assert_eq!(
+            optimized("if false { a } else if x { b }"),
+            "if x {\n    b\n}\n"
+        );
+        assert_eq!(optimized("if false { a }"), "{}\n");
+        assert_eq!(optimized("true || f()"), "true\n");
+        assert_eq!(optimized("false && f()"), "false\n");
+
+        assert_eq!(optimized("+(x * 2)"), "(x * 2)\n");
+        assert_eq!(optimized("!!(x < 2)"), "(x < 2)\n");
+        assert_eq!(optimized("- -(x as num)"), "(x as num)\n");
+        assert_eq!(optimized("!!x"), "!!x\n");
+        assert_eq!(optimized("+x"), "+x\n");
+    }
+}
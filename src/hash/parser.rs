@@ -1,13 +1,24 @@
+use std::collections::VecDeque;
+
 use super::{
-    ast::{ASTError, ASTNode, Error, Node, Nodes},
+    ast::{
+        ASTError, BinaryExpression, Block, Cast, Destructuring, Error, Errors, Expr, For,
+        FunctionCall, FunctionDefinition, If, Implementation, Index, InterfaceDefinition, Item,
+        Lambda, Literal, Member, MethodCall, Parameter, Pattern, Range, Signature, Stmt, Store,
+        StructDefinition, Tree, Try, TypeExpr, UnaryExpression, VariableDeclaration,
+        VariableDefinition, While,
+    },
+    fold::{walk_block, Folder},
     lexer::Lexer,
-    tokens::Token,
+    tokens::{Keyword, Position, Token, TypeName, TAB_WIDTH},
 };
 
 /// Parser Generates an abstract syntax tree from a program source code
 ///
 /// # Eamples
 /// ```
+/// use hydrogen::hash::parser::Parser;
+///
 /// let mut parser = Parser::new("1 + 2");
 /// let ast = parser.parse();
 ///
@@ -31,7 +42,7 @@ use super::{
 ///   var1: num = 1234
 ///   var2 = var1 + 1234
 ///
-///   var3: num = () {
+///   var3: fn = fn() {
 ///       var: str = "Hello, World!"
 ///   }
 ///
@@ -51,12 +62,12 @@ use super::{
 /// - [x] add `operation equal` assignment
 /// - [ ] fix the parsing of functions to include =
 /// - [ ] change the parsing of function body to
-///       parce_node instead of parse_block
+///   parce_node instead of parse_block
 /// - [ ] change variable declaration's expression to be optional
 /// - [ ] fix the parser's error propagation
 /// - [ ] clean the api of the parser
 /// - [ ] add user defined types
-/// - [ ] convert the parser into a ZERO COPY
+/// - [x] convert the parser into a ZERO COPY
 ///
 /// # References to zero copy parser
 /// <https://itnext.io/rust-the-joy-of-safe-zero-copy-parsers-8c8581db8ab2>
@@ -66,286 +77,631 @@ use super::{
 #[derive(Debug, Clone)]
 pub struct Parser<'a> {
     lexer: Lexer<'a>,
+    lookahead: VecDeque<(Token<'a>, Position)>, // Tokens lexed ahead, with the position after each
+    position: Position,                         // Position after the last consumed token
+    depth: usize,                               // How many statements and factors are open
+    function: bool,                             // Whether a function body is being parsed
+    looping: bool,                              // Whether a loop body is being parsed
+    grouped: bool, // Whether line breaks are ignored, inside parentheses or brackets
 }
 
+/// How deeply statements and expressions can nest before parsing fails, so that malformed input
+/// reports an error instead of overflowing the stack.
+pub const MAX_DEPTH: usize = 256;
+
 impl<'a> Parser<'a> {
     /// Creates a new Parser instance with the given program source code.
     pub fn new(program: &'a str) -> Self {
         Self {
             lexer: Lexer::new(program),
+            lookahead: VecDeque::new(),
+            position: Position {
+                col: 1,
+                row: 1,
+                offset: 0,
+            },
+            depth: 0,
+            function: false,
+            looping: false,
+            grouped: false,
         }
     }
 
-    /// Advances the lexer and returns the next token.
-    fn next(&mut self) -> Token {
-        self.lexer.lex()
+    /// Returns the next token and advances past it.
+    fn next(&mut self) -> Token<'a> {
+        match self.lookahead.pop_front() {
+            Some((token, position)) => {
+                self.position = position;
+                token
+            }
+            None => {
+                let token = self.lexer.lex();
+                self.position = self.lexer.position();
+                token
+            }
+        }
     }
 
-    /// Peeks at the next token without advancing the lexer.
-    fn peek(&mut self) -> Token {
-        self.lexer.peek()
+    /// Peeks at the next token without advancing past it.
+    fn peek(&mut self) -> &Token<'a> {
+        self.peek_nth(0)
+    }
+
+    /// Peeks at the token `n` tokens after the next one without advancing past any.
+    fn peek_nth(&mut self, n: usize) -> &Token<'a> {
+        self.fill(n);
+        &self.lookahead[n].0
+    }
+
+    /// Consumes the next token, which is known to be an identifier, and returns its name.
+    fn identifier(&mut self) -> String {
+        match self.next() {
+            Token::Identifier(_, name) => name.into_owned(),
+            token => token.to_string(),
+        }
+    }
+
+    /// Lexes tokens until the lookahead holds at least `n + 1` of them.
+    fn fill(&mut self, n: usize) {
+        while self.lookahead.len() <= n {
+            let token = self.lexer.lex();
+            self.lookahead.push_back((token, self.lexer.position()));
+        }
     }
 
-    /// Parses the entire program and returns the abstract syntax tree.
-    pub fn parse(&mut self) -> Result<Node, Error> {
+    /// Returns whether the next token is on a later line than the last one consumed, where
+    /// line breaks end statements.
+    fn line_break(&mut self) -> bool {
+        let row = self.position.row;
+        !self.grouped && self.peek().position().row > row
+    }
+
+    /// Ends a statement of a program or a block, which must be followed by a `;`, a line break,
+    /// the end of its block, or the end of the program.
+    fn terminate(&mut self) -> Result<(), Error> {
+        let line_break = self.line_break();
+        match self.peek() {
+            Token::Semicolon(_) => {
+                self.next();
+                Ok(())
+            }
+            // Tokens that cannot start a statement are reported by the statement parsed next
+            Token::RightBrace(_)
+            | Token::RightParenthesis(_)
+            | Token::RightBracket(_)
+            | Token::Unknown(_, _)
+            | Token::UnterminatedString(_, _)
+            | Token::UnterminatedComment(_)
+            | Token::Eof(_) => Ok(()),
+            _ if line_break => Ok(()),
+            token => Err(Box::new(ASTError::MissingSeparator(
+                token.clone().into_owned(),
+            ))),
+        }
+    }
+
+    /// Sets the number of columns between tab stops, which the columns of the positions parsed
+    /// afterwards are counted with, see [`Lexer::set_tab_width`].
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The number of columns, at least 1.
+    pub fn set_tab_width(&mut self, width: usize) {
+        self.lexer.set_tab_width(width);
+    }
+
+    /// Returns the position right after the last token consumed by the parser.
+    pub fn position(&self) -> Position {
+        self.position.clone()
+    }
+
+    /// Returns the position of the next token the parser will consume.
+    pub fn next_position(&mut self) -> Position {
+        self.peek().position().clone()
+    }
+
+    /// Parses the next statement of the program.
+    ///
+    /// A statement ends at a line break or a `;`, except inside parentheses and brackets, so an
+    /// operator or a parenthesis starting a line starts another statement.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<Stmt>, Error>` - Ok(Some(Stmt)) with the statement, Ok(None) at the end
+    ///   of the program, or Err(Error) if the statement does not parse.
+    pub fn parse(&mut self) -> Result<Option<Stmt>, Error> {
+        // Empty statements are skipped
+        while let Token::Semicolon(_) = self.peek() {
+            self.next();
+        }
+
         match self.peek() {
             Token::Unknown(_, _) => {
                 let token = self.next();
-                Err(Box::new(ASTError::UnknownToken(token)))
+                Err(Box::new(ASTError::UnknownToken(token.into_owned())))
             }
 
-            Token::Eof(_) => Ok(Box::new(ASTNode::End)),
+            Token::UnterminatedString(_, _) | Token::UnterminatedComment(_) => {
+                let token = self.next();
+                Err(Box::new(ASTError::Unterminated(token.into_owned())))
+            }
 
-            _ => match self.parse_node() {
-                Ok(node) => Ok(node),
+            Token::Eof(_) => Ok(None),
 
-                Err(error) => Err(error),
-            },
+            _ => {
+                let statement = self.parse_statement()?;
+                self.terminate()?;
+                Ok(Some(statement))
+            }
         }
     }
 
-    fn parse_node(&mut self) -> Result<Node, Error> {
-        let token = self.next();
-        match token.clone() {
-            Token::LeftParenthesis(_) => self.parse_set(),
-            Token::RightParenthesis(_) => Ok(Box::new(ASTNode::ParenDelimiter)),
-
-            Token::LeftBrace(_) => self.parse_scope(),
-            Token::RightBrace(_) => Ok(Box::new(ASTNode::BraceDelimiter)),
-
-            Token::LeftBracket(_) => self.parse_array(),
-            Token::RightBracket(_) => Ok(Box::new(ASTNode::BracketDelimiter)),
-
-            Token::Comma(_) => Ok(Box::new(ASTNode::Separator)),
-
-            Token::String(_, string) => Ok(Box::new(ASTNode::StringLiteral(string))),
-            Token::Number(_, number) => Ok(Box::new(ASTNode::NumberLiteral(number))),
-            Token::Boolean(_, boolean) => Ok(Box::new(ASTNode::BooleanLiteral(boolean == "true"))),
-
-            Token::Type(_, t) => {
-                if t == "num" {
-                    Ok(Box::new(ASTNode::NumberType))
-                } else if t == "str" {
-                    Ok(Box::new(ASTNode::StringType))
-                } else if t == "bool" {
-                    Ok(Box::new(ASTNode::BooleanType))
-                } else {
-                    Err(Box::new(ASTError::UnknownToken(token)))
-                }
+    /// Parses every statement of the program, see [`Parser::parse_partial`].
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Tree, Errors>` - Ok(Tree) if parsing is successful, Err(Errors) with every error
+    ///   found otherwise.
+    pub fn parse_program(&mut self) -> Result<Tree, Errors> {
+        self.parse_partial().into_result()
+    }
+
+    /// Parses every statement of the program, keeping the statements that parse when others do
+    /// not.
+    ///
+    /// # Returns
+    ///
+    /// * `ParseResult` - The statements that parse, and the errors of those that do not.
+    ///
+    /// # Examples
+    /// ```
+    /// use hydrogen::hash::parser::Parser;
+    ///
+    /// let parsed = Parser::new("a = 1\nb = ~\nc = 3").parse_partial();
+    ///
+    /// assert_eq!(parsed.tree.len(), 2);
+    /// assert_eq!(parsed.positions[1].row, 3);
+    /// assert_eq!(parsed.errors.len(), 1);
+    /// ```
+    pub fn parse_partial(&mut self) -> ParseResult {
+        std::iter::from_fn(|| self.parse_located()).collect()
+    }
+
+    /// Runs a parsing function one level deeper, failing once the input nests more than
+    /// `MAX_DEPTH` levels.
+    fn nested<T, F>(&mut self, parse: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Self) -> Result<T, Error>,
+    {
+        if self.depth >= MAX_DEPTH {
+            return Err(Box::new(ASTError::TooDeep(self.next().into_owned())));
+        }
+
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_statement(&mut self) -> Result<Stmt, Error> {
+        let row = self.peek().position().row;
+        let mut statement = self.nested(Self::statement)?;
+        // The doc comments right above a definition document it
+        match &mut statement {
+            Stmt::Item(Item::Function(definition)) => definition.doc = self.lexer.doc(row),
+            Stmt::Item(Item::Struct(definition)) => definition.doc = self.lexer.doc(row),
+            Stmt::Item(Item::Interface(definition)) => definition.doc = self.lexer.doc(row),
+            Stmt::Definition(definition) => definition.doc = self.lexer.doc(row),
+            _ => {}
+        }
+        Ok(statement)
+    }
+
+    fn statement(&mut self) -> Result<Stmt, Error> {
+        if let Token::Keyword(_, keyword) = self.peek() {
+            match keyword {
+                Keyword::Return => return self.parse_return(),
+                Keyword::Break | Keyword::Continue => return self.parse_jump(),
+                Keyword::Const => return self.parse_constant(),
+                Keyword::Global | Keyword::Nonlocal => return self.parse_global(),
+                Keyword::Struct => return self.parse_struct(),
+                Keyword::Interface => return self.parse_interface(),
+                Keyword::Impl => return self.parse_impl(),
+                _ => {}
             }
+        }
 
-            Token::Asterisk(_) | Token::Slash(_) | Token::Plus(_) | Token::Minus(_) => {
-                match self.parse_expression() {
-                    Ok(expression) => Ok(Box::new(ASTNode::UnaryExpression(
-                        Box::new(ASTNode::Operator(token.to_string())),
-                        expression,
-                    ))),
-                    Err(_) => todo!(),
-                }
+        if self.map_pattern() {
+            return self.parse_destructuring();
+        }
+
+        if !matches!(self.peek(), Token::Identifier(_, _)) {
+            let expression = self.parse_expression()?;
+            return self.parse_expression_statement(expression);
+        }
+
+        // A parenthesis starting the next line does not call the name
+        let row = self.peek().position().row;
+        let split = !self.grouped && self.peek_nth(1).position().row > row;
+        match self.peek_nth(1) {
+            Token::Colon(_)
+            | Token::Equal(_)
+            | Token::PlusEqual(_)
+            | Token::MinusEqual(_)
+            | Token::AsteriskEqual(_)
+            | Token::SlashEqual(_)
+            | Token::PercentEqual(_)
+            | Token::CaretEqual(_) => {
+                let name = self.identifier();
+                self.parse_variable(name)
             }
 
-            Token::Identifier(_, id) => match self.peek() {
-                Token::LeftParenthesis(_) => {
-                    if let Ok(value) = self.parse_function() {
-                        if value.len() == 1 {
-                            Ok(Box::new(ASTNode::FunctionCall(
-                                Box::new(ASTNode::Identifier(id)),
-                                value[0].clone(),
-                            )))
-                        } else {
-                            Ok(Box::new(ASTNode::FunctionDefinition(
-                                Box::new(ASTNode::Identifier(id)),
-                                value[0].clone(),
-                                value[1].clone(),
-                                value[2].clone(),
-                            )))
-                        }
-                    } else {
-                        Err(Box::new(ASTError::UnexpectedToken(token.clone())))
-                    }
+            Token::LeftParenthesis(_) if !split => {
+                let name = self.identifier();
+                if self.defines() {
+                    let parameters = self.parse_parameters()?;
+                    return self.parse_function(name, parameters);
                 }
 
-                Token::Colon(_) => {
-                    if let Ok(value) = self.parse_variable() {
-                        if value.len() == 1 {
-                            Ok(Box::new(ASTNode::VariableDeclaration(
-                                Box::new(ASTNode::Identifier(id)),
-                                value[0].clone(),
-                            )))
-                        } else {
-                            Ok(Box::new(ASTNode::VariableDefinition(
-                                Box::new(ASTNode::Identifier(id)),
-                                value[0].clone(),
-                                value[1].clone(),
-                            )))
-                        }
-                    } else {
-                        Err(Box::new(ASTError::UnexpectedToken(token.clone())))
-                    }
-                }
+                let call = Expr::Call(FunctionCall {
+                    name,
+                    arguments: expressions(self.parse_set()?)?,
+                });
+                let call = self.parse_postfix(call)?;
+                let expression = self.parse_operations(call, 0)?;
+                self.parse_expression_statement(expression)
+            }
+
+            _ => {
+                let expression = self.parse_expression()?;
+                self.parse_expression_statement(expression)
+            }
+        }
+    }
+
+    /// Ends a statement made of an expression, which can only be given a value if it is an
+    /// element or a key, like `items[0] = 1` or `point.x += 1`, or an array of names, which is the
+    /// target of a destructuring like `[a, b] = pair`.
+    fn parse_expression_statement(&mut self, expression: Expr) -> Result<Stmt, Error> {
+        if let (Expr::Array(elements), Token::Equal(_)) = (&expression, self.peek()) {
+            let names: Option<Vec<String>> = elements
+                .iter()
+                .map(|element| match element {
+                    Expr::Identifier(name) => Some(name.clone()),
+                    _ => None,
+                })
+                .collect();
+            if let Some(names) = names.filter(|_| !self.line_break()) {
+                self.next();
+                return Ok(Stmt::Destructure(Destructuring {
+                    pattern: Pattern::Array(names),
+                    value: self.parse_expression()?,
+                }));
+            }
+        }
 
-                Token::PlusEqual(_)
+        let assignment = matches!(
+            self.peek(),
+            Token::Equal(_)
+                | Token::PlusEqual(_)
                 | Token::MinusEqual(_)
                 | Token::AsteriskEqual(_)
                 | Token::SlashEqual(_)
                 | Token::PercentEqual(_)
                 | Token::CaretEqual(_)
-                | Token::Equal(_) => {
-                    if let Ok(value) = self.parse_variable() {
-                        if value.len() == 2 {
-                            Ok(Box::new(ASTNode::VariableDefinition(
-                                Box::new(ASTNode::Identifier(id)),
-                                value[0].clone(),
-                                value[1].clone(),
-                            )))
-                        } else {
-                            Ok(Box::new(ASTNode::VariableDefinition(
-                                Box::new(ASTNode::Identifier(id.clone())),
-                                value[0].clone(),
-                                Box::new(ASTNode::BinaryExpression(
-                                    Box::new(ASTNode::Identifier(id)),
-                                    value[1].clone(),
-                                    value[2].clone(),
-                                )),
-                            )))
-                        }
-                    } else {
-                        Err(Box::new(ASTError::UnexpectedToken(token.clone())))
-                    }
-                }
+        );
+        if !assignment || self.line_break() {
+            return Ok(Stmt::Expr(expression));
+        }
+
+        // The value is parsed too, so parsing goes on after the whole assignment
+        let op = match self.next() {
+            Token::PlusEqual(_) => Some("+"),
+            Token::MinusEqual(_) => Some("-"),
+            Token::AsteriskEqual(_) => Some("*"),
+            Token::SlashEqual(_) => Some("/"),
+            Token::PercentEqual(_) => Some("%"),
+            Token::CaretEqual(_) => Some("^"),
+            _ => None,
+        };
+        let value = self.parse_expression()?;
+        if !matches!(expression, Expr::Index(_) | Expr::Member(_)) {
+            return Err(Box::new(ASTError::InvalidAssignment(Box::new(expression))));
+        }
 
-                _ => Ok(Box::new(ASTNode::Identifier(id))),
+        // `a[i] op= y` is a shorthand for `a[i] = a[i] op y`
+        Ok(Stmt::Store(Store {
+            value: match op {
+                Some(op) => Expr::Binary(BinaryExpression {
+                    left: Box::new(expression.clone()),
+                    op: op.to_string(),
+                    right: Box::new(value),
+                }),
+                None => value,
             },
+            target: expression,
+            compound: op.is_some(),
+        }))
+    }
 
-            Token::Keyword(_, word) => {
-                if word == "if" {
-                    let expression = self.parse_expression()?;
-                    let body = self.parse_scope()?;
+    /// Tells if the braces starting the next statement hold a pattern like `{x, y}`, rather than
+    /// a block: names separated by commas, or a single name followed by `=`.
+    fn map_pattern(&mut self) -> bool {
+        matches!(self.peek(), Token::LeftBrace(_))
+            && matches!(self.peek_nth(1), Token::Identifier(_, _))
+            && match self.peek_nth(2) {
+                Token::Comma(_) => true,
+                Token::RightBrace(_) => matches!(self.peek_nth(3), Token::Equal(_)),
+                _ => false,
+            }
+    }
 
-                    match self.peek() {
-                        Token::Keyword(_, word) => {
-                            if word == "else" {
-                                Ok(Box::new(ASTNode::If(
-                                    expression,
-                                    body,
-                                    self.parse_node().unwrap(),
-                                )))
-                            } else {
-                                Err(Box::new(ASTError::UnknownToken(self.next())))
-                            }
-                        }
+    /// Parses a destructuring of a map, like `{x, y} = point`.
+    fn parse_destructuring(&mut self) -> Result<Stmt, Error> {
+        let pattern = self.parse_pattern()?;
+        match self.next() {
+            Token::Equal(_) => Ok(Stmt::Destructure(Destructuring {
+                pattern,
+                value: self.parse_expression()?,
+            })),
+            token => Err(Box::new(ASTError::UnexpectedToken(token.into_owned()))),
+        }
+    }
 
-                        _ => Ok(Box::new(ASTNode::If(
-                            expression,
-                            body,
-                            self.parse_node().unwrap(),
-                        ))),
-                    }
-                } else if word == "while" {
-                    let expression = self.parse_expression()?;
-                    let body = self.parse_scope()?;
+    /// Parses a pattern, the names between brackets or braces a value is taken apart into.
+    fn parse_pattern(&mut self) -> Result<Pattern, Error> {
+        match self.peek() {
+            Token::LeftBracket(_) => Ok(Pattern::Array(self.separated(
+                |token| matches!(token, Token::RightBracket(_)),
+                Self::parse_name,
+            )?)),
+            _ => Ok(Pattern::Map(self.separated(
+                |token| matches!(token, Token::RightBrace(_)),
+                Self::parse_name,
+            )?)),
+        }
+    }
 
-                    Ok(Box::new(ASTNode::While(expression, body)))
-                } else {
-                    Err(Box::new(ASTError::UnexpectedToken(token)))
+    /// Parses the name of a variable.
+    fn parse_name(&mut self) -> Result<String, Error> {
+        match self.next() {
+            Token::Identifier(_, name) => Ok(name.into_owned()),
+            token => Err(Box::new(ASTError::UnexpectedToken(token.into_owned()))),
+        }
+    }
+
+    /// Tells if the parenthesis after the next token opens the parameters of a function
+    /// definition rather than the arguments of a call, which is when the closing parenthesis is
+    /// followed by the `{` of a body or the `:` of a return type.
+    fn defines(&mut self) -> bool {
+        let mut depth = 0;
+        for n in 0.. {
+            match self.peek_nth(n) {
+                Token::LeftParenthesis(_) => depth += 1,
+                Token::RightParenthesis(_) if depth == 1 => {
+                    return matches!(self.peek_nth(n + 1), Token::LeftBrace(_) | Token::Colon(_));
                 }
+                Token::RightParenthesis(_) => depth -= 1,
+                Token::Eof(_) => return false,
+                _ => {}
             }
-
-            // Token::Equal(_) => todo!(),
-            // Token::Colon(_) => todo!(),
-            // Token::Dot(_) => todo!(),
-            // Token::Ampersand(_) => todo!(),
-            // Token::DollarSign(_) => todo!(),
-            // Token::Hash(_) => todo!(),
-            _ => Err(Box::new(ASTError::UnexpectedToken(token))),
         }
+        false
     }
 
-    fn parse_function(&mut self) -> Result<Nodes, Error> {
-        match self.parse_set() {
-            Ok(param) => match self.peek() {
-                Token::LeftBrace(_) => match self.parse_scope() {
-                    Ok(body) => Ok(vec![param, Box::new(ASTNode::Return(None)), body]),
-                    Err(errors) => Err(errors),
-                },
-
-                Token::Colon(_) => match self.parse_return() {
-                    Ok(ret) => match self.peek() {
-                        Token::LeftBrace(_) => match self.parse_scope() {
-                            Ok(body) => Ok(vec![param, Box::new(ASTNode::Return(Some(ret))), body]),
-                            Err(errors) => Err(errors),
-                        },
+    /// Parses the parameters of a function definition, between parentheses.
+    fn parse_parameters(&mut self) -> Result<Vec<Parameter>, Error> {
+        let grouped = std::mem::replace(&mut self.grouped, true);
+        let parameters = self.separated(
+            |token| matches!(token, Token::RightParenthesis(_)),
+            Self::parse_parameter,
+        );
+        self.grouped = grouped;
+        parameters
+    }
 
-                        _ => Err(Box::new(ASTError::UnexpectedToken(self.next()))),
-                    },
-                    Err(errors) => Err(errors),
-                },
+    /// Parses a parameter: a name with an optional type, or a pattern like `[x, y]`.
+    fn parse_parameter(&mut self) -> Result<Parameter, Error> {
+        if matches!(self.peek(), Token::LeftBracket(_) | Token::LeftBrace(_)) {
+            let pattern = self.parse_pattern()?;
+            return Ok(Parameter {
+                name: pattern.to_string(),
+                ty: None,
+                pattern: Some(pattern),
+            });
+        }
 
-                _ => Ok(vec![param]),
-            },
-            Err(error) => Err(error),
+        match self.parse_statement()? {
+            Stmt::Expr(Expr::Identifier(name)) => Ok(Parameter {
+                name,
+                ty: None,
+                pattern: None,
+            }),
+            Stmt::Declaration(VariableDeclaration { name, ty }) => Ok(Parameter {
+                name,
+                ty: Some(ty),
+                pattern: None,
+            }),
+            element => Err(Box::new(ASTError::InvalidParameter(Box::new(element)))),
         }
     }
 
-    fn parse_return(&mut self) -> Result<Node, Error> {
-        self.next();
-        let ret = self.parse_node()?;
+    fn parse_function(&mut self, name: String, parameters: Vec<Parameter>) -> Result<Stmt, Error> {
+        let (ret, body) = self.parse_signature()?;
 
-        Ok(ret)
+        Ok(Stmt::Item(Item::Function(FunctionDefinition {
+            name,
+            parameters,
+            ret,
+            body,
+            doc: None,
+        })))
     }
 
-    fn parse_set(&mut self) -> Result<Node, Error> {
-        let mut parameters = Vec::new();
-        let mut errors = Vec::new();
-
+    /// Parses an anonymous function, like `fn(x: num): num { x * 2 }`.
+    fn parse_lambda(&mut self) -> Result<Expr, Error> {
         self.next();
-        loop {
-            match self.peek() {
-                Token::RightParenthesis(_) => {
-                    self.next();
-                    break;
-                }
+        if !matches!(self.peek(), Token::LeftParenthesis(_)) {
+            return Err(Box::new(ASTError::UnexpectedToken(
+                self.next().into_owned(),
+            )));
+        }
 
-                _ => match self.parse_node() {
-                    Ok(parameter) => match *parameter {
-                        ASTNode::ParenDelimiter => {
-                            self.next();
-                            break;
-                        }
+        let parameters = self.parse_parameters()?;
+        let (ret, body) = self.parse_signature()?;
 
-                        ASTNode::Separator => {
-                            continue;
-                        }
+        Ok(Expr::Lambda(Lambda {
+            parameters,
+            ret,
+            body,
+        }))
+    }
 
-                        _ => {
-                            parameters.push(parameter);
-                        }
-                    },
-                    Err(error) => {
-                        errors.push(error);
-                    }
-                },
+    /// Parses the return type and body of a function whose parameters were already parsed.
+    fn parse_signature(&mut self) -> Result<(Option<TypeExpr>, Block), Error> {
+        let ret = match self.peek() {
+            Token::Colon(_) => {
+                self.next();
+                Some(self.parse_type()?)
             }
+            _ => None,
+        };
+
+        // Loops around a definition do not extend into its body
+        let function = std::mem::replace(&mut self.function, true);
+        let looping = std::mem::replace(&mut self.looping, false);
+        let body = self.parse_scope();
+        self.function = function;
+        self.looping = looping;
+
+        Ok((ret, body?))
+    }
+
+    /// Parses a `return` statement, with a value unless it ends its block.
+    fn parse_return(&mut self) -> Result<Stmt, Error> {
+        let token = self.next();
+        if !self.function {
+            return Err(Box::new(ASTError::MisplacedKeyword(token.into_owned())));
         }
 
-        if errors.is_empty() {
-            match self.peek() {
-                Token::LeftBrace(_) | Token::Colon(_) => {
-                    Ok(Box::new(ASTNode::Parameters(parameters)))
-                }
+        let line_break = self.line_break();
+        match self.peek() {
+            Token::RightBrace(_) | Token::Semicolon(_) | Token::Eof(_) => Ok(Stmt::Return(None)),
+            _ if line_break => Ok(Stmt::Return(None)),
+            _ => Ok(Stmt::Return(Some(self.parse_expression()?))),
+        }
+    }
+
+    /// Parses a `const` definition, which must give the constant a value.
+    fn parse_constant(&mut self) -> Result<Stmt, Error> {
+        self.next();
+        let name = match self.next() {
+            Token::Identifier(_, name) => name.into_owned(),
+            token => return Err(Box::new(ASTError::UnexpectedToken(token.into_owned()))),
+        };
+        if !matches!(self.peek(), Token::Equal(_) | Token::Colon(_)) {
+            let token = self.next();
+            return Err(Box::new(ASTError::UnexpectedToken(token.into_owned())));
+        }
 
-                _ => Ok(Box::new(ASTNode::Arguments(parameters))),
+        match self.parse_variable(name)? {
+            Stmt::Definition(definition) => Ok(Stmt::Definition(VariableDefinition {
+                constant: true,
+                ..definition
+            })),
+            Stmt::Declaration(declaration) => {
+                Err(Box::new(ASTError::UninitializedConstant(declaration.name)))
             }
-        } else {
-            Err(Box::new(ASTError::Errors(errors)))
+            statement => Ok(statement),
         }
     }
 
-    fn parse_scope(&mut self) -> Result<Node, Error> {
-        let mut statements = Vec::new();
-        let mut errors = Vec::new();
+    /// Parses a `global` declaration of the names a function assigns in the global scope, like
+    /// `global count, total`, or a `nonlocal` declaration of the names it assigns in the closest
+    /// enclosing function, like `nonlocal count`.
+    fn parse_global(&mut self) -> Result<Stmt, Error> {
+        let token = self.next();
+        if !self.function {
+            return Err(Box::new(ASTError::MisplacedKeyword(token.into_owned())));
+        }
+
+        let mut names = vec![self.parse_name()?];
+        while matches!(self.peek(), Token::Comma(_)) {
+            self.next();
+            names.push(self.parse_name()?);
+        }
+        match token {
+            Token::Keyword(_, Keyword::Nonlocal) => Ok(Stmt::Nonlocal(names)),
+            _ => Ok(Stmt::Global(names)),
+        }
+    }
+
+    /// Parses a struct definition, like `struct Point { x: num, y: num }`.
+    fn parse_struct(&mut self) -> Result<Stmt, Error> {
+        self.next();
+        let name = self.parse_name()?;
+        if !matches!(self.peek(), Token::LeftBrace(_)) {
+            return Err(Box::new(ASTError::UnexpectedToken(
+                self.next().into_owned(),
+            )));
+        }
 
+        let grouped = std::mem::replace(&mut self.grouped, true);
+        let fields = self.separated(
+            |token| matches!(token, Token::RightBrace(_)),
+            Self::parse_field,
+        );
+        self.grouped = grouped;
+
+        Ok(Stmt::Item(Item::Struct(StructDefinition {
+            name,
+            fields: fields?,
+            doc: None,
+        })))
+    }
+
+    /// Parses a field of a struct: a name with an optional type.
+    fn parse_field(&mut self) -> Result<Parameter, Error> {
+        let name = self.parse_name()?;
+        let ty = match self.peek() {
+            Token::Colon(_) => {
+                self.next();
+                Some(self.parse_type()?)
+            }
+            _ => None,
+        };
+        Ok(Parameter {
+            name,
+            ty,
+            pattern: None,
+        })
+    }
+
+    /// Parses an interface definition, like `interface Shape { area(self): num }`, whose
+    /// functions end at line breaks or `;` like statements.
+    fn parse_interface(&mut self) -> Result<Stmt, Error> {
         self.next();
+        let name = self.parse_name()?;
+        let grouped = std::mem::replace(&mut self.grouped, false);
+        let signatures = self.signatures();
+        self.grouped = grouped;
+
+        Ok(Stmt::Item(Item::Interface(InterfaceDefinition {
+            name,
+            signatures: signatures?,
+            doc: None,
+        })))
+    }
+
+    fn signatures(&mut self) -> Result<Vec<Signature>, Error> {
+        let mut signatures = Vec::new();
+        let mut errors = Vec::new();
+
+        match self.next() {
+            Token::LeftBrace(_) => {}
+            token => return Err(Box::new(ASTError::UnexpectedToken(token.into_owned()))),
+        }
+
         loop {
             match self.peek() {
                 Token::RightBrace(_) => {
@@ -353,195 +709,600 @@ impl<'a> Parser<'a> {
                     break;
                 }
 
-                _ => match self.parse_node() {
-                    Ok(statement) => match *statement {
-                        ASTNode::BraceDelimiter => {
-                            self.next();
-                            break;
-                        }
+                Token::Eof(_) => {
+                    self.unclosed(&mut errors);
+                    break;
+                }
 
-                        _ => statements.push(statement),
-                    },
+                Token::Semicolon(_) => {
+                    self.next();
+                }
 
-                    Err(error) => {
-                        errors.push(error);
+                _ => match self.parse_requirement() {
+                    Ok(signature) => {
+                        signatures.push(signature);
+                        if let Err(error) = self.terminate() {
+                            errors.push(error);
+                        }
                     }
+                    Err(error) => errors.push(error),
                 },
             }
         }
 
         if errors.is_empty() {
-            Ok(Box::new(ASTNode::Block(statements)))
+            Ok(signatures)
         } else {
             Err(Box::new(ASTError::Errors(errors)))
         }
     }
 
-    fn parse_variable(&mut self) -> Result<Nodes, Error> {
-        let token = self.next();
-        match token {
-            Token::PlusEqual(_) => {
-                let expression = self.parse_expression()?;
-                Ok(vec![
-                    Box::new(ASTNode::Type(None)),
-                    Box::new(ASTNode::Operator("+".to_string())),
-                    expression,
-                ])
-            }
+    /// Parses a function an interface requires, like `area(self): num`.
+    fn parse_requirement(&mut self) -> Result<Signature, Error> {
+        let name = self.parse_name()?;
+        if !matches!(self.peek(), Token::LeftParenthesis(_)) {
+            return Err(Box::new(ASTError::UnexpectedToken(
+                self.next().into_owned(),
+            )));
+        }
 
-            Token::MinusEqual(_) => {
-                let expression = self.parse_expression()?;
-                Ok(vec![
-                    Box::new(ASTNode::Type(None)),
-                    Box::new(ASTNode::Operator("-".to_string())),
-                    expression,
-                ])
+        let parameters = self.parse_parameters()?;
+        let ret = match self.peek() {
+            Token::Colon(_) => {
+                self.next();
+                Some(self.parse_type()?)
             }
+            _ => None,
+        };
+        Ok(Signature {
+            name,
+            parameters,
+            ret,
+        })
+    }
 
-            Token::AsteriskEqual(_) => {
-                let expression = self.parse_expression()?;
-                Ok(vec![
-                    Box::new(ASTNode::Type(None)),
-                    Box::new(ASTNode::Operator("*".to_string())),
-                    expression,
-                ])
-            }
+    /// Parses the functions of an interface implemented for a struct, like
+    /// `impl Shape for Square { area(self) { self.side ^ 2 } }`, which is a block of function
+    /// definitions.
+    fn parse_impl(&mut self) -> Result<Stmt, Error> {
+        self.next();
+        let interface = self.parse_name()?;
+        match self.next() {
+            Token::Keyword(_, Keyword::For) => {}
+            token => return Err(Box::new(ASTError::UnexpectedToken(token.into_owned()))),
+        }
+        let ty = self.parse_name()?;
+
+        let body = self.parse_scope()?;
+        if let Some(statement) = (body.statements.iter())
+            .find(|statement| !matches!(statement, Stmt::Item(Item::Function(_))))
+        {
+            return Err(Box::new(ASTError::InvalidImplementation(Box::new(
+                statement.clone(),
+            ))));
+        }
 
-            Token::SlashEqual(_) => {
-                let expression = self.parse_expression()?;
-                Ok(vec![
-                    Box::new(ASTNode::Type(None)),
-                    Box::new(ASTNode::Operator("/".to_string())),
-                    expression,
-                ])
-            }
+        Ok(Stmt::Item(Item::Impl(Implementation {
+            interface,
+            ty,
+            body,
+        })))
+    }
 
-            Token::PercentEqual(_) => {
-                let expression = self.parse_expression()?;
-                Ok(vec![
-                    Box::new(ASTNode::Type(None)),
-                    Box::new(ASTNode::Operator("%".to_string())),
-                    expression,
-                ])
-            }
+    /// Parses a `break` or `continue` statement.
+    fn parse_jump(&mut self) -> Result<Stmt, Error> {
+        let token = self.next();
+        match &token {
+            _ if !self.looping => Err(Box::new(ASTError::MisplacedKeyword(token.into_owned()))),
+            Token::Keyword(_, Keyword::Break) => Ok(Stmt::Break),
+            _ => Ok(Stmt::Continue),
+        }
+    }
 
-            Token::CaretEqual(_) => {
-                let expression = self.parse_expression()?;
-                Ok(vec![
-                    Box::new(ASTNode::Type(None)),
-                    Box::new(ASTNode::Operator("^".to_string())),
-                    expression,
-                ])
-            }
+    fn parse_node(&mut self) -> Result<Expr, Error> {
+        // Groups consume their own opening delimiter
+        match self.peek() {
+            Token::Type(_, TypeName::Fn) => return self.parse_lambda(),
+            Token::LeftParenthesis(_) => return Ok(Expr::Group(expressions(self.parse_set()?)?)),
+            Token::LeftBrace(_) => return Ok(Expr::Block(self.parse_scope()?)),
+            Token::LeftBracket(_) => return self.parse_array(),
+            _ => {}
+        }
 
-            Token::Equal(_) => {
-                let expression = self.parse_expression()?;
-                Ok(vec![Box::new(ASTNode::Type(None)), expression])
-            }
+        let token = self.next();
+        // A parenthesis starting the next line does not call the name
+        let line_break = self.line_break();
+        match token.clone() {
+            Token::String(_, string) => Ok(Expr::Literal(Literal::String(string.into_owned()))),
+            Token::Number(_, number) => Ok(Expr::Literal(Literal::Number(number.into_owned()))),
+            Token::Boolean(_, boolean) => Ok(Expr::Literal(Literal::Boolean(boolean == "true"))),
 
-            Token::Colon(_) => {
-                let t = self.parse_type()?;
-                match self.peek() {
-                    Token::Equal(_) => {
-                        self.next();
-                        Ok(vec![
-                            Box::new(ASTNode::Type(Some(t))),
-                            self.parse_expression()?,
-                        ])
+            Token::Identifier(_, name) => match self.peek() {
+                Token::LeftParenthesis(_) if !line_break => Ok(Expr::Call(FunctionCall {
+                    name: name.into_owned(),
+                    arguments: expressions(self.parse_set()?)?,
+                })),
+
+                _ => Ok(Expr::Identifier(name.into_owned())),
+            },
+
+            Token::Keyword(_, keyword) => match keyword {
+                Keyword::If => {
+                    let condition = Box::new(self.parse_expression()?);
+                    let then = self.parse_scope()?;
+
+                    let otherwise = match self.peek() {
+                        Token::Keyword(_, Keyword::Else) => {
+                            self.next();
+                            match self.peek() {
+                                Token::LeftBrace(_) => {
+                                    Some(Box::new(Expr::Block(self.parse_scope()?)))
+                                }
+
+                                Token::Keyword(_, Keyword::If) => {
+                                    Some(Box::new(self.parse_node()?))
+                                }
+
+                                _ => {
+                                    return Err(Box::new(ASTError::UnexpectedToken(
+                                        self.next().into_owned(),
+                                    )))
+                                }
+                            }
+                        }
+
+                        _ => None,
+                    };
+
+                    Ok(Expr::If(If {
+                        condition,
+                        then,
+                        otherwise,
+                    }))
+                }
+                Keyword::While => {
+                    let condition = Box::new(self.parse_expression()?);
+                    let body = self.parse_loop_body()?;
+
+                    Ok(Expr::While(While { condition, body }))
+                }
+                Keyword::Try => {
+                    let body = self.parse_scope()?;
+                    match self.next() {
+                        Token::Keyword(_, Keyword::Catch) => {}
+                        token => {
+                            return Err(Box::new(ASTError::UnexpectedToken(token.into_owned())))
+                        }
+                    }
+                    let variable = match self.next() {
+                        Token::Identifier(_, name) => name.into_owned(),
+                        token => {
+                            return Err(Box::new(ASTError::UnexpectedToken(token.into_owned())))
+                        }
+                    };
+                    let handler = self.parse_scope()?;
+
+                    Ok(Expr::Try(Try {
+                        body,
+                        variable,
+                        handler,
+                    }))
+                }
+                Keyword::For => {
+                    let variable = match self.next() {
+                        Token::Identifier(_, name) => name.into_owned(),
+                        token => {
+                            return Err(Box::new(ASTError::UnexpectedToken(token.into_owned())))
+                        }
+                    };
+                    match self.next() {
+                        Token::In(_) => {}
+                        token => {
+                            return Err(Box::new(ASTError::UnexpectedToken(token.into_owned())))
+                        }
                     }
-                    _ => Ok(vec![Box::new(ASTNode::Type(Some(t)))]),
+                    let iterable = Box::new(self.parse_expression()?);
+                    let body = self.parse_loop_body()?;
+
+                    Ok(Expr::For(For {
+                        variable,
+                        iterable,
+                        body,
+                    }))
                 }
+                Keyword::Else
+                | Keyword::Catch
+                | Keyword::Break
+                | Keyword::Continue
+                | Keyword::Return
+                | Keyword::Const
+                | Keyword::Global
+                | Keyword::Nonlocal
+                | Keyword::Struct
+                | Keyword::Interface
+                | Keyword::Impl => Err(Box::new(ASTError::UnexpectedToken(token.into_owned()))),
+            },
+
+            Token::UnterminatedString(_, _) | Token::UnterminatedComment(_) => {
+                Err(Box::new(ASTError::Unterminated(token.into_owned())))
             }
-            _ => Err(Box::new(ASTError::UnknownToken(token))),
+
+            // Token::Equal(_) => todo!(),
+            // Token::Colon(_) => todo!(),
+            // Token::Dot(_) => todo!(),
+            // Token::Ampersand(_) => todo!(),
+            // Token::DollarSign(_) => todo!(),
+            // Token::Hash(_) => todo!(),
+            _ => Err(Box::new(ASTError::UnexpectedToken(token.into_owned()))),
         }
     }
 
-    fn parse_type(&mut self) -> Result<Node, Error> {
-        let t = self.parse_node()?;
+    /// Parses the statements between parentheses, which are either arguments or parameters.
+    fn parse_set(&mut self) -> Result<Vec<Stmt>, Error> {
+        let grouped = std::mem::replace(&mut self.grouped, true);
+        let elements = self.set();
+        self.grouped = grouped;
+        elements
+    }
 
-        Ok(t)
+    fn set(&mut self) -> Result<Vec<Stmt>, Error> {
+        self.separated(
+            |token| matches!(token, Token::RightParenthesis(_)),
+            Self::parse_statement,
+        )
     }
 
-    fn parse_array(&mut self) -> Result<Node, Error> {
-        let mut element = Vec::new();
+    /// Parses the elements between an opening delimiter and the closing one, separated by
+    /// commas, with an optional comma after the last element.
+    ///
+    /// # Arguments
+    ///
+    /// * `closes` - Whether a token is the closing delimiter.
+    /// * `element` - The parsing function of an element.
+    fn separated<T>(
+        &mut self,
+        closes: fn(&Token) -> bool,
+        element: fn(&mut Self) -> Result<T, Error>,
+    ) -> Result<Vec<T>, Error> {
+        let mut elements = Vec::new();
         let mut errors = Vec::new();
 
         self.next();
         loop {
             match self.peek() {
-                Token::RightBracket(_) => {
+                token if closes(token) => {
                     self.next();
                     break;
                 }
 
-                _ => match self.parse_node() {
-                    Ok(parameter) => match *parameter {
-                        ASTNode::BracketDelimiter => {
-                            self.next();
-                            break;
-                        }
-
-                        ASTNode::Separator => {
-                            continue;
-                        }
+                Token::Eof(_) => {
+                    self.unclosed(&mut errors);
+                    break;
+                }
 
-                        _ => {
-                            element.push(parameter);
-                        }
-                    },
+                _ => match element(self) {
+                    Ok(parsed) => elements.push(parsed),
                     Err(error) => {
                         errors.push(error);
+                        continue;
                     }
                 },
             }
+
+            // The next element is parsed even when the comma before it is missing
+            match self.peek() {
+                Token::Comma(_) => {
+                    self.next();
+                }
+                token if closes(token) => {}
+                // Reported when it is parsed as the next element
+                Token::Eof(_) | Token::UnterminatedString(_, _) | Token::UnterminatedComment(_) => {
+                }
+                token => errors.push(Box::new(ASTError::UnexpectedToken(
+                    token.clone().into_owned(),
+                ))),
+            }
         }
 
         if errors.is_empty() {
-            Ok(Box::new(ASTNode::Array(element)))
+            Ok(elements)
         } else {
             Err(Box::new(ASTError::Errors(errors)))
         }
     }
 
-    fn parse_expression(&mut self) -> Result<Node, Error> {
-        let mut left = self.parse_term()?;
+    /// Parses a block, whose statements end at line breaks again when it is inside parentheses.
+    fn parse_scope(&mut self) -> Result<Block, Error> {
+        let grouped = std::mem::replace(&mut self.grouped, false);
+        let block = self.scope();
+        self.grouped = grouped;
+        block
+    }
 
-        while let Some(op) = self.match_binary_operator() {
-            let right = self.parse_term()?;
-            left = Box::new(ASTNode::BinaryExpression(
-                left,
-                Box::new(ASTNode::Operator(op)),
-                right,
-            ));
+    fn scope(&mut self) -> Result<Block, Error> {
+        let mut statements = Vec::new();
+        let mut rows = Vec::new();
+        let mut end = 0;
+        let mut errors = Vec::new();
+
+        match self.next() {
+            Token::LeftBrace(_) => {}
+            token => return Err(Box::new(ASTError::UnexpectedToken(token.into_owned()))),
         }
 
-        Ok(left)
+        loop {
+            match self.peek() {
+                Token::RightBrace(position) => {
+                    end = position.row;
+                    self.next();
+                    break;
+                }
+
+                Token::Eof(_) => {
+                    self.unclosed(&mut errors);
+                    break;
+                }
+
+                Token::Semicolon(_) => {
+                    self.next();
+                }
+
+                _ => {
+                    let row = self.next_position().row;
+                    match self.parse_statement() {
+                        Ok(statement) => {
+                            statements.push(statement);
+                            rows.push(row);
+                            if let Err(error) = self.terminate() {
+                                errors.push(error);
+                            }
+                        }
+                        Err(error) => errors.push(error),
+                    }
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(Block {
+                statements,
+                rows,
+                end,
+            })
+        } else {
+            Err(Box::new(ASTError::Errors(errors)))
+        }
+    }
+
+    /// Reports the end of the source code reached inside a group, unless the last error is about
+    /// a string or a comment that swallowed the closing delimiter.
+    fn unclosed(&mut self, errors: &mut Errors) {
+        let eof = self.next().into_owned();
+        if !errors.last().is_some_and(|error| unterminated(error)) {
+            errors.push(Box::new(ASTError::UnexpectedToken(eof)));
+        }
+    }
+
+    /// Parses the block of a loop, where `break` and `continue` are allowed.
+    fn parse_loop_body(&mut self) -> Result<Block, Error> {
+        let looping = std::mem::replace(&mut self.looping, true);
+        let body = self.parse_scope();
+        self.looping = looping;
+        body
+    }
+
+    fn parse_variable(&mut self, name: String) -> Result<Stmt, Error> {
+        let token = self.next();
+        let op = match token {
+            Token::PlusEqual(_) => "+",
+            Token::MinusEqual(_) => "-",
+            Token::AsteriskEqual(_) => "*",
+            Token::SlashEqual(_) => "/",
+            Token::PercentEqual(_) => "%",
+            Token::CaretEqual(_) => "^",
+
+            Token::Equal(_) => {
+                return Ok(Stmt::Definition(VariableDefinition {
+                    constant: false,
+                    name,
+                    ty: None,
+                    value: self.parse_expression()?,
+                    compound: false,
+                    doc: None,
+                }));
+            }
+
+            Token::Colon(_) => {
+                let ty = self.parse_type()?;
+                return match self.peek() {
+                    Token::Equal(_) => {
+                        self.next();
+                        Ok(Stmt::Definition(VariableDefinition {
+                            constant: false,
+                            name,
+                            ty: Some(ty),
+                            value: self.parse_expression()?,
+                            compound: false,
+                            doc: None,
+                        }))
+                    }
+                    _ => Ok(Stmt::Declaration(VariableDeclaration { name, ty })),
+                };
+            }
+
+            _ => return Err(Box::new(ASTError::UnknownToken(token.into_owned()))),
+        };
+
+        // `x op= y` is a shorthand for `x = x op y` that never defines `x`
+        let expression = self.parse_expression()?;
+        Ok(Stmt::Definition(VariableDefinition {
+            constant: false,
+            name: name.clone(),
+            ty: None,
+            value: Expr::Binary(BinaryExpression {
+                left: Box::new(Expr::Identifier(name)),
+                op: op.to_string(),
+                right: Box::new(expression),
+            }),
+            compound: true,
+            doc: None,
+        }))
+    }
+
+    fn parse_type(&mut self) -> Result<TypeExpr, Error> {
+        let token = self.next();
+        match &token {
+            Token::Type(_, TypeName::Num) => Ok(TypeExpr::Number),
+            Token::Type(_, TypeName::Str) => Ok(TypeExpr::String),
+            Token::Type(_, TypeName::Bool) => Ok(TypeExpr::Boolean),
+            Token::Type(_, TypeName::Fn) => Ok(TypeExpr::Function),
+            _ => Err(Box::new(ASTError::UnexpectedToken(token.into_owned()))),
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Expr, Error> {
+        let grouped = std::mem::replace(&mut self.grouped, true);
+        let array = self.array();
+        self.grouped = grouped;
+        array
+    }
+
+    fn array(&mut self) -> Result<Expr, Error> {
+        let elements = self.separated(
+            |token| matches!(token, Token::RightBracket(_)),
+            Self::parse_expression,
+        )?;
+        Ok(Expr::Array(elements))
+    }
+
+    fn parse_expression(&mut self) -> Result<Expr, Error> {
+        self.parse_binary(0)
+    }
+
+    /// Parses a chain of binary operations whose operators bind at least as tightly as `min`.
+    fn parse_binary(&mut self, min: u8) -> Result<Expr, Error> {
+        self.nested(|parser| parser.binary(min))
     }
 
-    fn parse_term(&mut self) -> Result<Node, Error> {
-        let mut left = self.parse_factor()?;
+    fn binary(&mut self, min: u8) -> Result<Expr, Error> {
+        let left = self.parse_factor()?;
+        self.parse_operations(left, min)
+    }
+
+    /// Parses the binary operations following an already parsed left operand.
+    fn parse_operations(&mut self, mut left: Expr, min: u8) -> Result<Expr, Error> {
+        while let Some(precedence) = self.binary_precedence() {
+            // An operator starting a line starts another statement
+            if precedence < min || self.line_break() {
+                break;
+            }
+
+            let op = self.next().to_string();
+            if op == "as" {
+                left = Expr::Cast(Cast {
+                    expr: Box::new(left),
+                    ty: self.parse_type()?,
+                });
+                continue;
+            }
+            if op == ".." || op == "..=" {
+                let end = self.parse_binary(precedence + 1)?;
+                left = Expr::Range(Range {
+                    start: Box::new(left),
+                    end: Box::new(end),
+                    inclusive: op == "..=",
+                });
+                continue;
+            }
 
-        while let Some(op) = self.match_binary_operator() {
-            let right = self.parse_factor()?;
-            left = Box::new(ASTNode::BinaryExpression(
-                left,
-                Box::new(ASTNode::Operator(op)),
-                right,
-            ));
+            // `^` is right associative, every other operator is left associative
+            let next = if op == "^" {
+                precedence
+            } else {
+                precedence + 1
+            };
+            let right = self.parse_binary(next)?;
+            left = Expr::Binary(BinaryExpression {
+                left: Box::new(left),
+                op,
+                right: Box::new(right),
+            });
         }
 
         Ok(left)
     }
 
-    fn parse_factor(&mut self) -> Result<Node, Error> {
+    fn parse_factor(&mut self) -> Result<Expr, Error> {
+        self.nested(Self::factor)
+    }
+
+    fn factor(&mut self) -> Result<Expr, Error> {
         if let Some(op) = self.match_unary_operator() {
-            let expression = self.parse_factor()?;
-            Ok(Box::new(ASTNode::UnaryExpression(
-                Box::new(ASTNode::Operator(op)),
-                expression,
-            )))
+            let mut expression = self.parse_factor()?;
+            // `^` binds tighter than unary operators, so `-2 ^ 2` is `-(2 ^ 2)`
+            if matches!(self.peek(), Token::Caret(_)) && !self.line_break() {
+                let op = self.next().to_string();
+                expression = Expr::Binary(BinaryExpression {
+                    left: Box::new(expression),
+                    op,
+                    right: Box::new(self.parse_binary(8)?),
+                });
+            }
+            Ok(Expr::Unary(UnaryExpression {
+                op,
+                expr: Box::new(expression),
+            }))
         } else {
-            self.parse_node()
+            let node = self.parse_node()?;
+            self.parse_postfix(node)
+        }
+    }
+
+    /// Parses the elements and keys taken from an already parsed value, like the `[0]` and the
+    /// `.name` of `items[0].name`.
+    fn parse_postfix(&mut self, mut expression: Expr) -> Result<Expr, Error> {
+        loop {
+            // A bracket starting the next line starts an array
+            if self.line_break() {
+                return Ok(expression);
+            }
+            match self.peek() {
+                Token::LeftBracket(_) => {
+                    self.next();
+                    let grouped = std::mem::replace(&mut self.grouped, true);
+                    let index = self.parse_expression();
+                    self.grouped = grouped;
+                    let index = index?;
+                    match self.next() {
+                        Token::RightBracket(_) => {}
+                        token => {
+                            return Err(Box::new(ASTError::UnexpectedToken(token.into_owned())))
+                        }
+                    }
+                    expression = Expr::Index(Index {
+                        target: Box::new(expression),
+                        index: Box::new(index),
+                    });
+                }
+                Token::Dot(_) => {
+                    self.next();
+                    let name = self.parse_name()?;
+                    // A parenthesis starting the next line does not call the function
+                    let line_break = self.line_break();
+                    expression = match self.peek() {
+                        Token::LeftParenthesis(_) if !line_break => Expr::MethodCall(MethodCall {
+                            target: Box::new(expression),
+                            name,
+                            arguments: expressions(self.parse_set()?)?,
+                        }),
+                        _ => Expr::Member(Member {
+                            target: Box::new(expression),
+                            name,
+                        }),
+                    };
+                }
+                _ => return Ok(expression),
+            }
         }
     }
 
@@ -554,25 +1315,22 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn match_binary_operator(&mut self) -> Option<String> {
+    /// Returns how tightly the next token binds as a binary operator, or None if it is not one.
+    fn binary_precedence(&mut self) -> Option<u8> {
         match self.peek() {
-            Token::At(_)
-            | Token::In(_)
-            | Token::As(_)
-            | Token::And(_)
-            | Token::Or(_)
-            | Token::Equals(_)
-            | Token::NotEqual(_)
-            | Token::GreaterThan(_)
+            Token::Or(_) => Some(1),
+            Token::And(_) => Some(2),
+            Token::Equals(_) | Token::NotEqual(_) => Some(3),
+            Token::GreaterThan(_)
             | Token::GreaterThanOrEqual(_)
             | Token::LessThan(_)
             | Token::LessThanOrEqual(_)
-            | Token::Caret(_)
-            | Token::Percent(_)
-            | Token::Asterisk(_)
-            | Token::Slash(_)
-            | Token::Plus(_)
-            | Token::Minus(_) => Some(self.next().to_string()),
+            | Token::In(_) => Some(4),
+            Token::DotDot(_) | Token::DotDotEqual(_) => Some(5),
+            Token::Plus(_) | Token::Minus(_) => Some(6),
+            Token::Asterisk(_) | Token::Slash(_) | Token::Percent(_) | Token::At(_) => Some(7),
+            Token::Caret(_) => Some(8),
+            Token::As(_) => Some(9),
             _ => None,
         }
     }
@@ -585,6 +1343,296 @@ impl<'a> Parser<'a> {
     // }
 }
 
+/// A change of source code: the bytes it replaces, and the text put in their place
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    /// The bytes of the old source code that are replaced.
+    pub range: std::ops::Range<usize>,
+    /// The text replacing them.
+    pub text: String,
+}
+
+/// A statement of a [`Program`], with where it is in the source code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parsed {
+    /// The position of the first token of the statement.
+    pub start: Position,
+    /// The position right after the last token of the statement.
+    pub end: Position,
+    /// The statement, or the error found parsing it.
+    pub statement: Result<Stmt, Error>,
+}
+
+/// The statements of a program that parse, together with the errors of the statements that do
+/// not, so tools can use the healthy parts of a broken program
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParseResult {
+    /// The statements that parse, in order.
+    pub tree: Tree,
+    /// The position of the first token of every statement of the tree.
+    pub positions: Vec<Position>,
+    /// The errors of the statements that do not parse, in order.
+    pub errors: Errors,
+}
+
+impl ParseResult {
+    /// Returns whether every statement parsed, so the tree is the whole program.
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Returns the tree if every statement parsed.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Tree, Errors>` - Ok(Tree) if every statement parsed, Err(Errors) with every
+    ///   error found otherwise.
+    pub fn into_result(self) -> Result<Tree, Errors> {
+        match self.is_complete() {
+            true => Ok(self.tree),
+            false => Err(self.errors),
+        }
+    }
+}
+
+impl FromIterator<Parsed> for ParseResult {
+    fn from_iter<I: IntoIterator<Item = Parsed>>(iter: I) -> Self {
+        let mut result = Self::default();
+        for parsed in iter {
+            match parsed.statement {
+                Ok(statement) => {
+                    result.tree.push(statement);
+                    result.positions.push(parsed.start);
+                }
+                Err(error) => result.errors.push(error),
+            }
+        }
+        result
+    }
+}
+
+/// A program parsed one statement at a time, keeping where each statement is so that
+/// [`Parser::reparse`] can parse it again after an edit.
+///
+/// # Examples
+/// ```
+/// use hydrogen::hash::parser::{Edit, Parser, Program};
+///
+/// let program = Program::new("a = 1\nb = 2\nc = 3\nd = 4");
+/// let edit = Edit { range: 10..11, text: "20".to_string() };
+/// let edited = Parser::reparse(&program, &edit);
+///
+/// assert_eq!(edited.source, "a = 1\nb = 20\nc = 3\nd = 4");
+/// assert_eq!(edited.statements, Program::new(&edited.source).statements);
+/// assert_eq!(edited.reused, 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    /// The source code of the program.
+    pub source: String,
+    /// The statements of the program, in order.
+    pub statements: Vec<Parsed>,
+    /// The number of statements taken from the program before the edit instead of being parsed
+    /// again.
+    pub reused: usize,
+}
+
+impl Program {
+    /// Parses every statement of a program.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source code of the program.
+    pub fn new(source: &str) -> Self {
+        let mut parser = Parser::new(source);
+        let mut statements = Vec::new();
+        while let Some(parsed) = parser.parse_located() {
+            statements.push(parsed);
+        }
+        Self {
+            source: source.to_string(),
+            statements,
+            reused: 0,
+        }
+    }
+
+    /// Returns the statements of the program that parse, with the errors of those that do not.
+    pub fn tree(&self) -> ParseResult {
+        self.statements.iter().cloned().collect()
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// Creates a new Parser instance that starts at a position of the program source code, which
+    /// must not be inside a token.
+    fn starting_at(program: &'a str, position: Position) -> Self {
+        Self {
+            lexer: Lexer::starting_at(program, position.clone()),
+            position,
+            ..Self::new(program)
+        }
+    }
+
+    /// Parses the next statement of the program, with where it is.
+    ///
+    /// Programs can be parsed one statement at a time this way, without keeping the statements
+    /// before.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Parsed>` - The statement or the error found parsing it, or None at the end of
+    ///   the program.
+    pub fn parse_located(&mut self) -> Option<Parsed> {
+        let start = self.next_position();
+        let statement = match self.parse() {
+            Ok(Some(statement)) => Ok(statement),
+            Ok(None) => return None,
+            Err(error) => Err(error),
+        };
+        Some(Parsed {
+            start,
+            end: self.position(),
+            statement,
+        })
+    }
+
+    /// Parses a program again after an edit, reusing the statements the edit cannot change.
+    ///
+    /// Parsing starts at the last statements before the edit, since the tokens after a
+    /// statement decide where it ends. Once a statement starts where an old statement after the
+    /// edit starts, the old statements from there on are moved to their new positions instead
+    /// of being parsed again.
+    ///
+    /// # Arguments
+    ///
+    /// * `old` - The program before the edit.
+    /// * `edit` - The edit, whose range must be in the old source code.
+    ///
+    /// # Returns
+    ///
+    /// * `Program` - The edited program, parsed the same as by [`Program::new`].
+    pub fn reparse(old: &Program, edit: &Edit) -> Program {
+        let mut source = old.source.clone();
+        source.replace_range(edit.range.clone(), &edit.text);
+
+        // A statement is kept when the statement after it ends before the edit
+        let kept = old
+            .statements
+            .windows(2)
+            .take_while(|pair| pair[1].end.offset < edit.range.start)
+            .count();
+        let mut statements = old.statements[..kept].to_vec();
+        let start = match statements.last() {
+            Some(parsed) => parsed.end.clone(),
+            None => Position::locate(&source, 0, TAB_WIDTH),
+        };
+
+        let moved = Moved {
+            old: Position::locate(&old.source, edit.range.end, TAB_WIDTH),
+            new: Position::locate(&source, edit.range.start + edit.text.len(), TAB_WIDTH),
+        };
+        let mut reused = kept;
+        let mut parser = Parser::starting_at(&source, start);
+        loop {
+            let next = parser.next_position();
+            let resumed = old.statements.iter().position(|parsed| {
+                parsed.start.offset >= moved.old.offset && moved.position(&parsed.start) == next
+            });
+            if let Some(index) = resumed {
+                // Errors hold the positions of their tokens, so they are parsed again
+                let rest = &old.statements[index..];
+                if rest.iter().all(|parsed| parsed.statement.is_ok()) {
+                    statements.extend(rest.iter().map(|parsed| moved.parsed(parsed)));
+                    reused += rest.len();
+                    break;
+                }
+            }
+
+            match parser.parse_located() {
+                Some(parsed) => statements.push(parsed),
+                None => break,
+            }
+        }
+
+        Program {
+            source,
+            statements,
+            reused,
+        }
+    }
+}
+
+/// Where the end of an edit was before and after it, to move what comes after it.
+struct Moved {
+    /// The position of the end of the replaced bytes in the old source code.
+    old: Position,
+    /// The position of the end of the inserted text in the new source code.
+    new: Position,
+}
+
+impl Moved {
+    /// Returns the new position of an old position after the edit.
+    fn position(&self, position: &Position) -> Position {
+        let col = match position.row == self.old.row {
+            true => position.col - self.old.col + self.new.col,
+            false => position.col,
+        };
+        Position {
+            col,
+            row: position.row - self.old.row + self.new.row,
+            offset: position.offset - self.old.offset + self.new.offset,
+        }
+    }
+
+    /// Returns an old statement after the edit, moved to where it is after the edit.
+    fn parsed(&self, parsed: &Parsed) -> Parsed {
+        let statement = parsed.statement.clone().map(|statement| {
+            let mut rows = MoveRows(self);
+            rows.fold_stmt(statement)
+        });
+        Parsed {
+            start: self.position(&parsed.start),
+            end: self.position(&parsed.end),
+            statement,
+        }
+    }
+}
+
+/// Pass moving the rows of the statements of blocks after an edit.
+struct MoveRows<'a>(&'a Moved);
+
+impl Folder for MoveRows<'_> {
+    fn fold_block(&mut self, block: Block) -> Block {
+        let block = walk_block(self, block);
+        let moved = |row: usize| row - self.0.old.row + self.0.new.row;
+        Block {
+            rows: block.rows.iter().map(|&row| moved(row)).collect(),
+            end: moved(block.end),
+            ..block
+        }
+    }
+}
+
+/// Returns whether an error is about a string or a comment running to the end of the source code.
+fn unterminated(error: &ASTError) -> bool {
+    match error {
+        ASTError::Unterminated(_) => true,
+        ASTError::Errors(errors) => errors.last().is_some_and(|error| unterminated(error)),
+        _ => false,
+    }
+}
+
+/// Converts the statements of a group into expressions, failing on anything else.
+fn expressions(elements: Vec<Stmt>) -> Result<Vec<Expr>, Error> {
+    elements
+        .into_iter()
+        .map(|element| match element {
+            Stmt::Expr(expression) => Ok(expression),
+            _ => Err(Box::new(ASTError::UnexpectedStatement(Box::new(element)))),
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -607,7 +1655,7 @@ mod test {
                 var1: num = 1234
                 var2 = var1 + 1234
 
-                var3: num = lambda() {
+                var3: fn = fn() {
                     var: str = "Hello, World!"
                 }
 
@@ -623,4 +1671,477 @@ mod test {
         // TODO: Add more specific assertions based on your expected AST structure
         // For example, you can assert the structure of the AST, the types of nodes, etc.
     }
+
+    #[test]
+    fn test_lookahead() {
+        let mut parser = Parser::new("a b\nc");
+
+        assert_eq!(parser.peek_nth(2).to_string(), "Identifier(c)");
+        assert_eq!(
+            parser.position(),
+            Position {
+                col: 1,
+                row: 1,
+                offset: 0
+            }
+        );
+        assert_eq!(parser.next().to_string(), "Identifier(a)");
+        assert_eq!(
+            parser.position(),
+            Position {
+                col: 2,
+                row: 1,
+                offset: 1
+            }
+        );
+        assert_eq!(parser.peek().to_string(), "Identifier(b)");
+        assert_eq!(
+            parser.next_position(),
+            Position {
+                col: 3,
+                row: 1,
+                offset: 2
+            }
+        );
+        parser.next();
+        assert_eq!(parser.next().to_string(), "Identifier(c)");
+        assert_eq!(
+            parser.position(),
+            Position {
+                col: 2,
+                row: 2,
+                offset: 5
+            }
+        );
+        assert!(matches!(parser.next(), Token::Eof(_)));
+    }
+
+    /// Parses the source code to the end, failing if the parser panics or stops consuming tokens.
+    fn parse_all(source: &str) {
+        let mut parser = Parser::new(source);
+        for _ in 0..source.len() + 2 {
+            if let Ok(None) = parser.parse() {
+                return;
+            }
+        }
+        panic!("the parser stopped making progress on {:?}", source);
+    }
+
+    #[test]
+    fn test_never_panics() {
+        let pieces = [
+            "(", ")", "{", "}", "[", "]", ",", ":", "=", "+=", "^=", "+", "-", "*", "!", "^", "==",
+            "&&", "||", "in", "as", "if", "else", "while", "for", "..", "break", "return", "x",
+            "f", "1", "\"s\"", "\"", "true", "num", "str", "bool", "~", "|", "é", "\n", " ",
+            "// c\n", "/*",
+        ];
+
+        // A fixed xorshift generator keeps the inputs the same on every run
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut random = |bound: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound as u64) as usize
+        };
+
+        for _ in 0..2000 {
+            let length = random(40);
+            let source: String = (0..length).map(|_| pieces[random(pieces.len())]).collect();
+            parse_all(&source);
+        }
+
+        for piece in [
+            "(", "{", "[", "f(", "-", "!", "if x {", "x = ", "1 ^ ", "1 + ",
+        ] {
+            let source = piece.repeat(10_000);
+            parse_all(&source);
+            assert!(Parser::new(&source).parse_program().is_err());
+        }
+
+        let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(Parser::new(&nested(50)).parse_program().is_ok());
+        assert!(Parser::new(&nested(MAX_DEPTH)).parse_program().is_err());
+    }
+
+    #[test]
+    fn test_statements() {
+        let tree = Parser::new("/// Returns `a`\nf(a: num, b) { a }\nx += f(1, 2)")
+            .parse_program()
+            .unwrap();
+
+        assert_eq!(
+            tree,
+            vec![
+                Stmt::Item(Item::Function(FunctionDefinition {
+                    name: "f".to_string(),
+                    parameters: vec![
+                        Parameter {
+                            name: "a".to_string(),
+                            ty: Some(TypeExpr::Number),
+                            pattern: None,
+                        },
+                        Parameter {
+                            name: "b".to_string(),
+                            ty: None,
+                            pattern: None,
+                        },
+                    ],
+                    ret: None,
+                    body: Block::new(vec![Stmt::Expr(Expr::Identifier("a".to_string()))]),
+                    doc: Some("Returns `a`".to_string()),
+                })),
+                Stmt::Definition(VariableDefinition {
+                    constant: false,
+                    name: "x".to_string(),
+                    ty: None,
+                    value: Expr::Binary(BinaryExpression {
+                        left: Box::new(Expr::Identifier("x".to_string())),
+                        op: "+".to_string(),
+                        right: Box::new(Expr::Call(FunctionCall {
+                            name: "f".to_string(),
+                            arguments: vec![
+                                Expr::Literal(Literal::Number("1".to_string())),
+                                Expr::Literal(Literal::Number("2".to_string())),
+                            ],
+                        })),
+                    }),
+                    compound: true,
+                    doc: None,
+                }),
+            ]
+        );
+
+        assert!(Parser::new("f(1) { 2 }").parse_program().is_err());
+        // A comma can follow the last element, but elements need one between them
+        assert_eq!(
+            Parser::new("f(a, b,) { [a, b,] }\nf(1,\n2,\n)").parse_program(),
+            Parser::new("f(a, b) { [a, b] }\nf(1, 2)").parse_program()
+        );
+        for source in ["f(1 2)", "[1 2]", "f(,)", "[,]", "f(1,, 2)"] {
+            assert!(Parser::new(source).parse_program().is_err(), "{}", source);
+        }
+        assert!(Parser::new("print(x: num)").parse_program().is_err());
+        assert!(Parser::new("if true { 1").parse_program().is_err());
+        assert_eq!(
+            Parser::new("while x { if y { break } else { continue } }").parse_program(),
+            Ok(vec![Stmt::Expr(Expr::While(While {
+                condition: Box::new(Expr::Identifier("x".to_string())),
+                body: Block::new(vec![Stmt::Expr(Expr::If(If {
+                    condition: Box::new(Expr::Identifier("y".to_string())),
+                    then: Block::new(vec![Stmt::Break]),
+                    otherwise: Some(Box::new(Expr::Block(Block::new(vec![Stmt::Continue])))),
+                }))]),
+            }))])
+        );
+        assert!(Parser::new("break").parse_program().is_err());
+        assert!(matches!(
+            &Parser::new("while x {\n    a\n\n    b }").parse_program().unwrap()[0],
+            Stmt::Expr(Expr::While(While { body, .. })) if body.rows == vec![2, 4]
+        ));
+        assert!(Parser::new("while x { f() { continue } }")
+            .parse_program()
+            .is_err());
+        assert_eq!(
+            Parser::new("if x { return 1 }").parse_program(),
+            Err(vec![Box::new(ASTError::Errors(vec![Box::new(
+                ASTError::MisplacedKeyword(Token::Keyword(
+                    Position {
+                        col: 8,
+                        row: 1,
+                        offset: 7
+                    },
+                    Keyword::Return
+                ))
+            )]))])
+        );
+        assert_eq!(
+            Parser::new("const x: num").parse_program(),
+            Err(vec![Box::new(ASTError::UninitializedConstant(
+                "x".to_string()
+            ))])
+        );
+        assert!(Parser::new("const x += 1").parse_program().is_err());
+        assert!(matches!(
+            &Parser::new("f() { global x, y }").parse_program().unwrap()[0],
+            Stmt::Item(Item::Function(FunctionDefinition { body, .. }))
+                if body.statements == vec![Stmt::Global(vec!["x".to_string(), "y".to_string()])]
+        ));
+        assert!(matches!(
+            &Parser::new("f() { nonlocal x }").parse_program().unwrap()[0],
+            Stmt::Item(Item::Function(FunctionDefinition { body, .. }))
+                if body.statements == vec![Stmt::Nonlocal(vec!["x".to_string()])]
+        ));
+        assert!(Parser::new("global x").parse_program().is_err());
+        assert!(Parser::new("nonlocal x").parse_program().is_err());
+        assert!(Parser::new("f() { global }").parse_program().is_err());
+        assert!(matches!(
+            &Parser::new("const x = 1").parse_program().unwrap()[0],
+            Stmt::Definition(VariableDefinition { constant: true, .. })
+        ));
+
+        let tree = Parser::new("[a, b] = pair\n{x} = point\nf({x, y}, [z]) { x }\n{x}")
+            .parse_program()
+            .unwrap();
+        assert_eq!(
+            tree[..2],
+            [
+                Stmt::Destructure(Destructuring {
+                    pattern: Pattern::Array(vec!["a".to_string(), "b".to_string()]),
+                    value: Expr::Identifier("pair".to_string()),
+                }),
+                Stmt::Destructure(Destructuring {
+                    pattern: Pattern::Map(vec!["x".to_string()]),
+                    value: Expr::Identifier("point".to_string()),
+                }),
+            ]
+        );
+        assert!(matches!(
+            &tree[2],
+            Stmt::Item(Item::Function(FunctionDefinition { parameters, .. }))
+                if parameters[0].name == "{x, y}"
+                    && parameters[1].pattern == Some(Pattern::Array(vec!["z".to_string()]))
+        ));
+        assert!(matches!(&tree[3], Stmt::Expr(Expr::Block(_))));
+        assert!(Parser::new("[a, 1] = pair").parse_program().is_err());
+        assert!(Parser::new("f([1]) {}").parse_program().is_err());
+    }
+
+    #[test]
+    fn test_items() {
+        let parameter = |name: &str, ty| Parameter {
+            name: name.to_string(),
+            ty,
+            pattern: None,
+        };
+        let tree = Parser::new(
+            "/// A point\nstruct Point { x: num, y, }\n\
+             interface Shape {\n    area(self): num; name(self)\n}\n\
+             impl Shape for Point {\n    area(self): num { 0 }\n    name(self) { \"point\" }\n}\n\
+             p.area(1)\np.x\n(p.name)\n(1)",
+        )
+        .parse_program()
+        .unwrap();
+
+        assert_eq!(
+            tree[..2],
+            [
+                Stmt::Item(Item::Struct(StructDefinition {
+                    name: "Point".to_string(),
+                    fields: vec![parameter("x", Some(TypeExpr::Number)), parameter("y", None)],
+                    doc: Some("A point".to_string()),
+                })),
+                Stmt::Item(Item::Interface(InterfaceDefinition {
+                    name: "Shape".to_string(),
+                    signatures: vec![
+                        Signature {
+                            name: "area".to_string(),
+                            parameters: vec![parameter("self", None)],
+                            ret: Some(TypeExpr::Number),
+                        },
+                        Signature {
+                            name: "name".to_string(),
+                            parameters: vec![parameter("self", None)],
+                            ret: None,
+                        },
+                    ],
+                    doc: None,
+                })),
+            ]
+        );
+        assert!(matches!(
+            &tree[2],
+            Stmt::Item(Item::Impl(implementation))
+                if implementation.interface == "Shape"
+                    && implementation.ty == "Point"
+                    && implementation.functions().count() == 2
+        ));
+        assert_eq!(
+            tree[3],
+            Stmt::Expr(Expr::MethodCall(MethodCall {
+                target: Box::new(Expr::Identifier("p".to_string())),
+                name: "area".to_string(),
+                arguments: vec![Expr::Literal(Literal::Number("1".to_string()))],
+            }))
+        );
+        // A parenthesis starting the next line does not call the member
+        assert!(matches!(tree[4], Stmt::Expr(Expr::Member(_))));
+        assert_eq!(tree.len(), 7);
+
+        assert!(matches!(
+            Parser::new("impl Shape for Point { x = 1 }").parse_program(),
+            Err(errors) if matches!(*errors[0], ASTError::InvalidImplementation(_))
+        ));
+        for source in [
+            "struct { x }",
+            "struct Point(x)",
+            "struct Point { 1 }",
+            "interface Shape { area }",
+            "interface Shape { area() area() }",
+            "impl Shape Point {}",
+            "x = struct",
+        ] {
+            assert!(Parser::new(source).parse_program().is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_termination() {
+        let parse = |source| Parser::new(source).parse_program();
+
+        // Line breaks end statements, so operators and parentheses starting a line do not
+        // continue the one before
+        assert_eq!(parse("a = 1\n-1").unwrap().len(), 2);
+        assert_eq!(parse("a = b\n(1)").unwrap().len(), 2);
+        assert_eq!(parse("a = 1 +\n1").unwrap().len(), 1);
+        assert_eq!(parse("a = (1\n+ 1)\nb = f(1,\n2)").unwrap().len(), 2);
+        assert_eq!(parse("f(fn() {\n    a = 1\n    -a\n})").unwrap().len(), 1);
+
+        // `;` separates statements on the same line, and empty statements are skipped
+        assert_eq!(parse("a = 1; b = 2;;").unwrap().len(), 2);
+        assert!(matches!(
+            &parse("while x { a = 1; break }").unwrap()[0],
+            Stmt::Expr(Expr::While(While { body, .. })) if body.statements.len() == 2
+        ));
+        assert!(matches!(
+            &parse("f() {\n    return\n    1\n}").unwrap()[0],
+            Stmt::Item(Item::Function(FunctionDefinition { body, .. }))
+                if body.statements[0] == Stmt::Return(None)
+        ));
+
+        let errors = parse("a = 1 b = 2").unwrap_err();
+        assert!(matches!(
+            &*errors[0],
+            ASTError::MissingSeparator(Token::Identifier(position, name))
+                if name == "b" && position.col == 7
+        ));
+        assert_eq!(errors[0].code(), "H0008");
+
+        // Elements and keys are read and given values, and the target of a compound assignment
+        // is read by its value
+        let items = Expr::Identifier("items".to_string());
+        let element = Expr::Member(Member {
+            target: Box::new(Expr::Index(Index {
+                target: Box::new(items.clone()),
+                index: Box::new(Expr::Literal(Literal::Number("0".to_string()))),
+            })),
+            name: "name".to_string(),
+        });
+        assert_eq!(
+            parse("items[0].name = \"hi\"\nitems[0].name += x\nitems[0].name").unwrap(),
+            vec![
+                Stmt::Store(Store {
+                    target: element.clone(),
+                    value: Expr::Literal(Literal::String("hi".to_string())),
+                    compound: false,
+                }),
+                Stmt::Store(Store {
+                    target: element.clone(),
+                    value: Expr::Binary(BinaryExpression {
+                        left: Box::new(element.clone()),
+                        op: "+".to_string(),
+                        right: Box::new(Expr::Identifier("x".to_string())),
+                    }),
+                    compound: true,
+                }),
+                Stmt::Expr(element),
+            ]
+        );
+        // A bracket on the next line starts an array of its own
+        assert_eq!(
+            parse("items\n[0]").unwrap()[1],
+            Stmt::Expr(Expr::Array(vec![Expr::Literal(Literal::Number(
+                "0".to_string()
+            ))]))
+        );
+
+        // Only variables, elements, and keys are given values, and the parser goes on after the
+        // assignment
+        let errors = parse("f(1) = 2\n1 += f(x)\n(a) = 1 + 1").unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert!(errors.iter().all(|error| error.code() == "H0010"));
+        assert_eq!(
+            errors[0].to_string(),
+            "ERROR[H0010]: cannot assign to `f(1)`, only variables, elements, and keys can be given values"
+        );
+        // The statement after the missing separator is still parsed
+        let parsed = Parser::new("a = 1 b = 2\nc = 3").parse_partial();
+        assert_eq!((parsed.tree.len(), parsed.errors.len()), (2, 1));
+        assert!(parse("if x { a b }").is_err());
+    }
+
+    #[test]
+    fn test_unterminated() {
+        let parse = |source| Parser::new(source).parse_program().unwrap_err();
+
+        let errors = parse("x = 1\nprint(\"a, x)\ny = 2");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            "ERROR[H0009]: unterminated string literal starting at 7:2"
+        );
+
+        // The missing closing delimiters of the groups are not reported again
+        let errors = parse("f() {\n    x = [1 /* 2]\n}");
+        assert_eq!(
+            errors[0].to_string(),
+            "ERROR[H0009]: unterminated block comment starting at 12:2"
+        );
+        assert_eq!(parse("x = (1 +").len(), 1);
+    }
+
+    #[test]
+    fn test_reparse() {
+        let source = "a = 1\nb = 2\nc = 3\nd = 4\nf(x) {\n    x\n}\ng = 5";
+        let program = Program::new(source);
+        assert_eq!(program.statements.len(), 6);
+
+        // Each edit is checked against parsing the edited program from scratch
+        let edits = [
+            (16..17, "30", 4),
+            (24..24, "\n\n", 5),
+            (0..1, "alpha", 5),
+            (17..17, " +", 3),
+            (6..12, "", 4),
+            (39..44, "gamma = 50", 4),
+            (16..16, "(", 1),
+        ];
+        let rows = |program: &Program| -> Vec<Vec<usize>> {
+            let blocks = program
+                .statements
+                .iter()
+                .filter_map(|parsed| match &parsed.statement {
+                    Ok(Stmt::Item(Item::Function(definition))) => {
+                        Some(definition.body.rows.clone())
+                    }
+                    _ => None,
+                });
+            blocks.collect()
+        };
+        let mut edited = program.clone();
+        for (range, text, reused) in edits {
+            let edit = Edit {
+                range,
+                text: text.to_string(),
+            };
+            edited = Parser::reparse(&program, &edit);
+            let expected = Program::new(&edited.source);
+            assert_eq!(edited.statements, expected.statements, "{:?}", edit);
+            assert_eq!(rows(&edited), rows(&expected), "{:?}", edit);
+            assert_eq!(edited.reused, reused, "{:?}", edit);
+        }
+
+        assert!(!edited.tree().is_complete());
+        let edit = Edit {
+            range: 18..18,
+            text: ")".to_string(),
+        };
+        let fixed = Parser::reparse(&edited, &edit);
+        assert_eq!(
+            fixed.source,
+            "a = 1\nb = 2\nc = (3)\nd = 4\nf(x) {\n    x\n}\ng = 5"
+        );
+        assert_eq!(fixed.tree().into_result().unwrap().len(), 6);
+        assert_eq!(rows(&fixed), vec![vec![6]]);
+    }
 }
@@ -1,11 +1,18 @@
-use std::io;
-
-use crossterm::{
-    style::{Color, ResetColor, SetForegroundColor},
-    ExecutableCommand,
+use std::io::{self, Write};
+
+use crossterm::style::Color;
+
+use super::{
+    ast::{
+        ASTError, Block, Errors, Expr, Item, Parameter, Stmt, Store, Tree, TypeExpr,
+        VariableDefinition,
+    },
+    json,
+    lexer::Lexer,
+    tokens::Position,
+    value::Value,
 };
-
-use super::ast::{ASTNode, Errors, Node, Tree};
+use crate::style::{self, Styled};
 
 /// Prints the abstract syntax tree (AST) to the standard output with color-coding.
 ///
@@ -17,316 +24,937 @@ use super::ast::{ASTNode, Errors, Node, Tree};
 ///
 /// * `io::Result<()>` - Ok(()) if printing is successful, Err(io::Error) otherwise.
 pub fn print_ast(ast: &Tree) -> io::Result<()> {
-    io::stdout().execute(SetForegroundColor(Color::Green))?;
-    dbg!(ast.clone());
-    io::stdout().execute(SetForegroundColor(Color::Blue))?;
-    print_tree(ast);
-    io::stdout().execute(ResetColor)?;
-    Ok(())
+    write_ast(&mut style::stdout(), ast)
+}
+
+/// Writes the abstract syntax tree (AST) with color-coding: its nodes as Rust sees them, then
+/// drawn as a tree.
+///
+/// # Arguments
+///
+/// * `writer` - The writer the tree is written to.
+/// * `ast` - The abstract syntax tree to be written.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - Ok(()) if writing is successful, Err(io::Error) otherwise.
+pub fn write_ast<W: Write>(writer: &mut Styled<W>, ast: &Tree) -> io::Result<()> {
+    writer.paint(&format!("{:#?}\n", ast), Some(Color::Green), None)?;
+    writer.set(Some(Color::Blue), None)?;
+    write_tree(writer, ast)?;
+    writer.reset()?;
+    writer.flush()
 }
 
-/// Prints errors in the abstract syntax tree (AST) to the standard output with color-coding.
+/// Prints errors in the abstract syntax tree (AST) to the standard error with color-coding.
 ///
 /// # Arguments
 ///
-/// * `ast` - The abstract syntax tree containing errors to be printed.
+/// * `errors` - The errors to be printed.
 ///
 /// # Returns
 ///
 /// * `io::Result<()>` - Ok(()) if printing is successful, Err(io::Error) otherwise.
 pub fn print_error(errors: Errors) -> io::Result<()> {
-    io::stdout().execute(SetForegroundColor(Color::Red))?;
-    dbg!(errors.clone());
-    io::stdout().execute(ResetColor)?;
-    Ok(())
+    write_errors(&mut style::stderr(), &errors)
 }
 
-/// Prints the nodes of the abstract syntax tree (AST) in a tree-like structure.
+/// Writes errors in the abstract syntax tree (AST) with color-coding, one per line.
+///
+/// The errors collected while parsing a group are written one by one.
 ///
 /// # Arguments
 ///
-/// * `tree` - The abstract syntax tree to be printed.
-pub fn print_tree(tree: &Tree) {
-    let mut indent = Vec::new();
-    for (i, node) in tree.iter().enumerate() {
-        let last = i == tree.len() - 1;
-        print_node(node, &mut indent, last);
-        println!();
+/// * `writer` - The writer the errors are written to.
+/// * `errors` - The errors to be written.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - Ok(()) if writing is successful, Err(io::Error) otherwise.
+pub fn write_errors<W: Write>(writer: &mut Styled<W>, errors: &[Box<ASTError>]) -> io::Result<()> {
+    for error in errors {
+        match error.as_ref() {
+            ASTError::Errors(errors) => write_errors(writer, errors)?,
+            error => writer.paint(&format!("{}\n", error), Some(Color::Red), None)?,
+        }
     }
+    writer.flush()
+}
 
-    fn print_node(node: &Node, indent: &mut Vec<&str>, last: bool) {
-        match &**node {
-            ASTNode::StringType => {
-                if !indent.is_empty() {
-                    (0..indent.len()).for_each(|i| {
-                        print!("{}", indent[i]);
-                    });
-
-                    if last {
-                        print!("└───");
-                    } else {
-                        print!("├───");
-                    }
-                }
-                println!("str");
-            }
+/// A node of the abstract syntax tree (AST) as it is drawn: a label and the nodes below it.
+struct Branch {
+    label: String,
+    children: Vec<Branch>,
+}
 
-            ASTNode::BooleanType => {
-                if !indent.is_empty() {
-                    (0..indent.len()).for_each(|i| {
-                        print!("{}", indent[i]);
-                    });
-
-                    if last {
-                        print!("└───");
-                    } else {
-                        print!("├───");
-                    }
-                }
-                println!("bool");
-            }
+impl Branch {
+    /// Creates a branch with the given label and children.
+    fn new(label: &str, children: Vec<Branch>) -> Self {
+        Self {
+            label: label.to_string(),
+            children,
+        }
+    }
 
-            ASTNode::NumberType => {
-                if !indent.is_empty() {
-                    (0..indent.len()).for_each(|i| {
-                        print!("{}", indent[i]);
-                    });
-
-                    if last {
-                        print!("└───");
-                    } else {
-                        print!("├───");
-                    }
-                }
-                println!("num");
-            }
+    /// Creates a branch without children.
+    fn leaf(label: String) -> Self {
+        Self {
+            label,
+            children: Vec::new(),
+        }
+    }
+}
 
-            ASTNode::BooleanLiteral(value) => {
-                if !indent.is_empty() {
-                    (0..indent.len()).for_each(|i| {
-                        print!("{}", indent[i]);
-                    });
-
-                    if last {
-                        print!("└───");
-                    } else {
-                        print!("├───");
-                    }
-                }
-                println!("{}", value);
+/// Returns the branch a statement is drawn as.
+fn stmt_branch(statement: &Stmt) -> Branch {
+    match statement {
+        Stmt::Item(Item::Function(definition)) => {
+            let mut children = vec![Branch::leaf(definition.name.clone())];
+            children.extend(signature_branches(
+                &definition.parameters,
+                definition.ret,
+                &definition.body,
+            ));
+            Branch::new("Function Definition", children)
+        }
+        Stmt::Item(Item::Struct(definition)) => Branch::new(
+            "Struct Definition",
+            vec![
+                Branch::leaf(definition.name.clone()),
+                Branch::new(
+                    "Fields",
+                    definition
+                        .fields
+                        .iter()
+                        .map(|field| Branch::leaf(parameter_source(field)))
+                        .collect(),
+                ),
+            ],
+        ),
+        Stmt::Item(Item::Interface(definition)) => Branch::new(
+            "Interface Definition",
+            vec![
+                Branch::leaf(definition.name.clone()),
+                Branch::new(
+                    "Signatures",
+                    definition
+                        .signatures
+                        .iter()
+                        .map(|signature| Branch::leaf(signature.to_string()))
+                        .collect(),
+                ),
+            ],
+        ),
+        Stmt::Item(Item::Impl(implementation)) => Branch::new(
+            "Implementation",
+            vec![
+                Branch::leaf(implementation.interface.clone()),
+                Branch::leaf(implementation.ty.clone()),
+                block_branch(&implementation.body),
+            ],
+        ),
+        Stmt::Definition(definition) => {
+            let mut children = vec![Branch::leaf(definition.name.clone())];
+            if let Some(ty) = definition.ty {
+                children.push(Branch::new("Type", vec![Branch::leaf(ty.to_string())]));
             }
-
-            ASTNode::StringLiteral(value)
-            | ASTNode::NumberLiteral(value)
-            | ASTNode::Identifier(value)
-            | ASTNode::Operator(value) => {
-                if !indent.is_empty() {
-                    (0..indent.len()).for_each(|i| {
-                        print!("{}", indent[i]);
-                    });
-
-                    if last {
-                        print!("└───");
-                    } else {
-                        print!("├───");
-                    }
-                }
-                println!("{}", value);
+            children.push(expr_branch(&definition.value));
+            match definition.constant {
+                true => Branch::new("Constant Definition", children),
+                false => Branch::new("Variable Definition", children),
             }
+        }
+        Stmt::Store(store) => Branch::new(
+            "Store",
+            vec![expr_branch(&store.target), expr_branch(&store.value)],
+        ),
+        Stmt::Global(names) => Branch::new(
+            "Global Declaration",
+            names
+                .iter()
+                .map(|name| Branch::leaf(name.clone()))
+                .collect(),
+        ),
+        Stmt::Nonlocal(names) => Branch::new(
+            "Nonlocal Declaration",
+            names
+                .iter()
+                .map(|name| Branch::leaf(name.clone()))
+                .collect(),
+        ),
+        Stmt::Declaration(declaration) => Branch::new(
+            "Variable Declaration",
+            vec![
+                Branch::leaf(declaration.name.clone()),
+                Branch::new("Type", vec![Branch::leaf(declaration.ty.to_string())]),
+            ],
+        ),
+        Stmt::Destructure(destructuring) => Branch::new(
+            "Destructuring",
+            vec![
+                Branch::leaf(destructuring.pattern.to_string()),
+                expr_branch(&destructuring.value),
+            ],
+        ),
+        Stmt::Expr(expression) => expr_branch(expression),
+        Stmt::Return(value) => Branch::new("Return", value.iter().map(expr_branch).collect()),
+        Stmt::Break => Branch::leaf("break".to_string()),
+        Stmt::Continue => Branch::leaf("continue".to_string()),
+    }
+}
 
-            ASTNode::Type(value) => {
-                if !indent.is_empty() {
-                    (0..indent.len()).for_each(|i| {
-                        print!("{}", indent[i]);
-                    });
-
-                    if last {
-                        print!("└───");
-                    } else {
-                        print!("├───");
-                    }
-                }
-                println!("[Type]");
-                match value {
-                    Some(value) => print_node(value, indent, false),
-                    None => {}
-                };
-            }
+/// Returns the branches the parameters, return type, and body of a function are drawn as.
+fn signature_branches(
+    parameters: &[Parameter],
+    ret: Option<TypeExpr>,
+    body: &Block,
+) -> Vec<Branch> {
+    let mut children = vec![Branch::new(
+        "Parameters",
+        parameters
+            .iter()
+            .map(|parameter| Branch::leaf(parameter_source(parameter)))
+            .collect(),
+    )];
+    if let Some(ret) = ret {
+        children.push(Branch::new(
+            "Return Type",
+            vec![Branch::leaf(ret.to_string())],
+        ));
+    }
+    children.push(block_branch(body));
+    children
+}
 
-            ASTNode::Return(value) => {
-                if !indent.is_empty() {
-                    (0..indent.len()).for_each(|i| {
-                        print!("{}", indent[i]);
-                    });
-
-                    if last {
-                        print!("└───");
-                    } else {
-                        print!("├───");
-                    }
-                }
-                println!("[Return]");
-                match value {
-                    Some(value) => print_node(value, indent, false),
-                    None => {}
-                };
-            }
+/// Returns the branch a block is drawn as.
+fn block_branch(block: &Block) -> Branch {
+    Branch::new("Block", block.statements.iter().map(stmt_branch).collect())
+}
 
-            ASTNode::UnaryExpression(op, expr) => {
-                if !indent.is_empty() {
-                    (0..indent.len()).for_each(|i| {
-                        print!("{}", indent[i]);
-                    });
-
-                    if last {
-                        print!("└───");
-                    } else {
-                        print!("├───");
-                    }
-                }
-                println!("{}{}", op, expr);
+/// Returns the branch an expression is drawn as.
+fn expr_branch(expression: &Expr) -> Branch {
+    let list = |label: &str, elements: &[Expr]| {
+        Branch::new(label, elements.iter().map(expr_branch).collect())
+    };
+
+    match expression {
+        Expr::Literal(literal) => Branch::leaf(literal.to_string()),
+        Expr::Identifier(name) => Branch::leaf(name.clone()),
+        Expr::Group(elements) => list("Group", elements),
+        Expr::Array(elements) => list("Array", elements),
+        Expr::Unary(unary) => Branch::new(
+            "Unary Expression",
+            vec![Branch::leaf(unary.op.clone()), expr_branch(&unary.expr)],
+        ),
+        Expr::Binary(binary) => Branch::new(
+            "Binary Expression",
+            vec![
+                expr_branch(&binary.left),
+                Branch::leaf(binary.op.clone()),
+                expr_branch(&binary.right),
+            ],
+        ),
+        Expr::Call(call) => Branch::new(
+            "Function Call",
+            vec![
+                Branch::leaf(call.name.clone()),
+                list("Arguments", &call.arguments),
+            ],
+        ),
+        Expr::Block(block) => block_branch(block),
+        Expr::If(statement) => {
+            let mut children = vec![
+                expr_branch(&statement.condition),
+                block_branch(&statement.then),
+            ];
+            if let Some(otherwise) = &statement.otherwise {
+                children.push(expr_branch(otherwise));
             }
+            Branch::new("If", children)
+        }
+        Expr::While(statement) => Branch::new(
+            "While",
+            vec![
+                expr_branch(&statement.condition),
+                block_branch(&statement.body),
+            ],
+        ),
+        Expr::For(statement) => Branch::new(
+            "For",
+            vec![
+                Branch::leaf(statement.variable.clone()),
+                expr_branch(&statement.iterable),
+                block_branch(&statement.body),
+            ],
+        ),
+        Expr::Lambda(lambda) => Branch::new(
+            "Lambda",
+            signature_branches(&lambda.parameters, lambda.ret, &lambda.body),
+        ),
+        Expr::Try(statement) => Branch::new(
+            "Try",
+            vec![
+                block_branch(&statement.body),
+                Branch::leaf(statement.variable.clone()),
+                block_branch(&statement.handler),
+            ],
+        ),
+        Expr::Cast(cast) => Branch::new(
+            "Cast",
+            vec![
+                expr_branch(&cast.expr),
+                Branch::new("Type", vec![Branch::leaf(cast.ty.to_string())]),
+            ],
+        ),
+        Expr::Range(range) => Branch::new(
+            match range.inclusive {
+                true => "Inclusive Range",
+                false => "Range",
+            },
+            vec![expr_branch(&range.start), expr_branch(&range.end)],
+        ),
+        Expr::Index(index) => Branch::new(
+            "Index",
+            vec![expr_branch(&index.target), expr_branch(&index.index)],
+        ),
+        Expr::Member(member) => Branch::new(
+            "Member",
+            vec![
+                expr_branch(&member.target),
+                Branch::leaf(member.name.clone()),
+            ],
+        ),
+        Expr::MethodCall(call) => Branch::new(
+            "Method Call",
+            vec![
+                expr_branch(&call.target),
+                Branch::leaf(call.name.clone()),
+                list("Arguments", &call.arguments),
+            ],
+        ),
+    }
+}
 
-            ASTNode::BinaryExpression(left, op, right) => {
-                if !indent.is_empty() {
-                    (0..indent.len()).for_each(|i| {
-                        print!("{}", indent[i]);
-                    });
-
-                    if last {
-                        print!("└───");
-                    } else {
-                        print!("├───");
-                    }
-                }
-                println!("{} {} {}", left, op, right);
-            }
+/// Renders the abstract syntax tree (AST) as a Graphviz DOT graph.
+///
+/// # Arguments
+///
+/// * `tree` - The abstract syntax tree to be rendered.
+///
+/// # Returns
+///
+/// * `String` - The DOT source of a directed graph with one vertex per node.
+pub fn to_dot(tree: &Tree) -> String {
+    let mut dot =
+        String::from("digraph ast {\n    node [shape=box];\n    program [label=\"Program\"];\n");
+    let mut count = 0;
+
+    for statement in tree {
+        let id = add_vertex(&stmt_branch(statement), &mut dot, &mut count);
+        dot.push_str(&format!("    program -> {};\n", id));
+    }
 
-            ASTNode::VariableDeclaration(name, t) => {
-                println!("[Variable Declaration]");
+    dot.push_str("}\n");
+    return dot;
 
-                print_node(name, indent, false);
-                print_node(t, indent, true);
-            }
+    fn add_vertex(branch: &Branch, dot: &mut String, count: &mut usize) -> String {
+        let id = format!("node{}", count);
+        *count += 1;
 
-            ASTNode::VariableDefinition(name, t, expr) => {
-                println!("[Variable Definition]");
+        let label = branch.label.replace('\\', "\\\\").replace('"', "\\\"");
+        dot.push_str(&format!("    {} [label=\"{}\"];\n", id, label));
 
-                print_node(name, indent, false);
-                print_node(t, indent, false);
-                print_node(expr, indent, true);
-            }
+        for child in &branch.children {
+            let child_id = add_vertex(child, dot, count);
+            dot.push_str(&format!("    {} -> {};\n", id, child_id));
+        }
 
-            ASTNode::FunctionDefinition(id, params, ret, body) => {
-                println!("[Function Definition]");
+        id
+    }
+}
 
-                print_node(id, indent, false);
-                print_node(params, indent, false);
-                print_node(ret, indent, false);
-                print_node(body, indent, true);
-            }
+/// The text a block is indented with for every level of nesting.
+pub const INDENT: &str = "    ";
 
-            ASTNode::Parameters(children) => {
-                println!("[Parameters]");
+/// Regenerates Hydrogen source code from the abstract syntax tree (AST).
+///
+/// Every statement is put on its own line, blocks are indented with [`INDENT`], and operators
+/// and separators are surrounded by consistent spacing, so parsing the output yields the same
+/// tree again.
+///
+/// # Arguments
+///
+/// * `tree` - The abstract syntax tree to be printed.
+///
+/// # Returns
+///
+/// * `String` - The source code of the program, ending with a newline.
+pub fn to_source(tree: &Tree) -> String {
+    let mut source = String::new();
 
-                let len = children.len();
-                for (i, child) in children.iter().enumerate() {
-                    let next_last = last && i == len - 1;
-                    let indent_next = if next_last { "    " } else { "│   " };
-                    indent.push(indent_next);
-                    print_node(child, indent, next_last);
-                    indent.pop();
-                }
-            }
+    for statement in tree {
+        source.push_str(&stmt_source(statement, 0));
+        source.push('\n');
+    }
 
-            ASTNode::Block(children) => {
-                println!("[Block]");
+    source
+}
 
-                let len = children.len();
-                for (i, child) in children.iter().enumerate() {
-                    let next_last = last && i == len - 1;
-                    let indent_next = if next_last { "    " } else { "│   " };
-                    indent.push(indent_next);
-                    print_node(child, indent, next_last);
-                    indent.pop();
-                }
-            }
+/// Regenerates the source code of a statement.
+///
+/// # Arguments
+///
+/// * `statement` - The statement to be printed.
+/// * `depth` - The nesting level of the statement, used to indent the lines of its blocks.
+///
+/// # Returns
+///
+/// * `String` - The source code of the statement, without leading indentation or trailing
+///   newline.
+pub fn stmt_source(statement: &Stmt, depth: usize) -> String {
+    Printer { comments: None }.stmt(statement, depth)
+}
 
-            ASTNode::FunctionCall(name, arguments) => {
-                println!("[Function Call]");
+/// Regenerates the source code of a statement parsed from source code, like [`stmt_source`],
+/// with the comments of the source code inside its blocks.
+///
+/// # Arguments
+///
+/// * `statement` - The statement to be printed.
+/// * `depth` - The nesting level of the statement, used to indent the lines of its blocks.
+/// * `comments` - The comments of the source code not written yet.
+///
+/// # Returns
+///
+/// * `String` - The source code of the statement, without leading indentation or trailing
+///   newline.
+pub(crate) fn stmt_source_with(
+    statement: &Stmt,
+    depth: usize,
+    comments: &mut dyn Comments,
+) -> String {
+    Printer {
+        comments: Some(comments),
+    }
+    .stmt(statement, depth)
+}
 
-                print_node(name, indent, false);
-                print_node(arguments, indent, true);
-            }
+/// Regenerates the source code of an expression.
+///
+/// # Arguments
+///
+/// * `expression` - The expression to be printed.
+/// * `depth` - The nesting level of the expression, used to indent the lines of its blocks.
+///
+/// # Returns
+///
+/// * `String` - The source code of the expression.
+pub fn expr_source(expression: &Expr, depth: usize) -> String {
+    Printer { comments: None }.expr(expression, depth)
+}
 
-            ASTNode::Arguments(children) => {
-                println!("[Arguments]");
+/// The comments of source code being regenerated, taken in order as the statements of its
+/// blocks are written.
+pub(crate) trait Comments {
+    /// Takes the comments before a row, and the ones on the row that come before its code.
+    fn before(&mut self, row: usize) -> Vec<String>;
 
-                let len = children.len();
-                for (i, child) in children.iter().enumerate() {
-                    let next_last = last && i == len - 1;
-                    let indent_next = if next_last { "    " } else { "│   " };
-                    indent.push(indent_next);
-                    print_node(child, indent, next_last);
-                    indent.pop();
-                }
-            }
+    /// Takes the comments that come before a row, after the code of a statement, split into the
+    /// ones following code on their line and the ones on lines of their own.
+    fn after(&mut self, row: usize) -> (Vec<String>, Vec<String>);
+}
 
-            ASTNode::If(condition, affermative, negative) => {
-                println!("[If]");
+/// Regenerates source code, writing the comments of the blocks of parsed code if it has them.
+struct Printer<'a> {
+    comments: Option<&'a mut dyn Comments>,
+}
 
-                print_node(condition, indent, false);
-                print_node(affermative, indent, false);
-                print_node(negative, indent, true);
+impl Printer<'_> {
+    /// Regenerates the source code of a statement.
+    fn stmt(&mut self, statement: &Stmt, depth: usize) -> String {
+        match statement {
+            Stmt::Item(Item::Function(definition)) => format!(
+                "{}{}",
+                definition.name,
+                self.signature(
+                    &definition.parameters,
+                    definition.ret,
+                    &definition.body,
+                    depth
+                )
+            ),
+            Stmt::Item(Item::Struct(definition)) => {
+                let fields: Vec<String> = definition.fields.iter().map(parameter_source).collect();
+                match fields.is_empty() {
+                    true => format!("struct {} {{}}", definition.name),
+                    false => format!("struct {} {{ {} }}", definition.name, fields.join(", ")),
+                }
             }
+            Stmt::Item(Item::Interface(definition)) => {
+                if definition.signatures.is_empty() {
+                    return format!("interface {} {{}}", definition.name);
+                }
+                let mut source = format!("interface {} {{\n", definition.name);
+                for signature in &definition.signatures {
+                    source.push_str(&INDENT.repeat(depth + 1));
+                    source.push_str(&signature.to_string());
+                    source.push('\n');
+                }
+                source.push_str(&INDENT.repeat(depth));
+                source.push('}');
+                source
+            }
+            Stmt::Item(Item::Impl(implementation)) => format!(
+                "impl {} for {} {}",
+                implementation.interface,
+                implementation.ty,
+                self.block(&implementation.body, depth)
+            ),
+            Stmt::Definition(VariableDefinition {
+                name,
+                value: Expr::Binary(binary),
+                compound: true,
+                ..
+            }) => format!(
+                "{} {}= {}",
+                name,
+                binary.op,
+                self.expr(&binary.right, depth)
+            ),
+            Stmt::Definition(definition) => {
+                let keyword = if definition.constant { "const " } else { "" };
+                match definition.ty {
+                    Some(ty) => format!(
+                        "{}{}: {} = {}",
+                        keyword,
+                        definition.name,
+                        ty,
+                        self.expr(&definition.value, depth)
+                    ),
+                    None => format!(
+                        "{}{} = {}",
+                        keyword,
+                        definition.name,
+                        self.expr(&definition.value, depth)
+                    ),
+                }
+            }
+            Stmt::Declaration(declaration) => format!("{}: {}", declaration.name, declaration.ty),
+            Stmt::Store(Store {
+                target,
+                value: Expr::Binary(binary),
+                compound: true,
+            }) => format!(
+                "{} {}= {}",
+                self.expr(target, depth),
+                binary.op,
+                self.expr(&binary.right, depth)
+            ),
+            Stmt::Store(store) => format!(
+                "{} = {}",
+                self.expr(&store.target, depth),
+                self.expr(&store.value, depth)
+            ),
+            Stmt::Global(names) => format!("global {}", names.join(", ")),
+            Stmt::Nonlocal(names) => format!("nonlocal {}", names.join(", ")),
+            Stmt::Destructure(destructuring) => format!(
+                "{} = {}",
+                destructuring.pattern,
+                self.expr(&destructuring.value, depth)
+            ),
+            Stmt::Expr(expression) => self.expr(expression, depth),
+            Stmt::Return(Some(value)) => format!("return {}", self.expr(value, depth)),
+            Stmt::Return(None) => "return".to_string(),
+            Stmt::Break => "break".to_string(),
+            Stmt::Continue => "continue".to_string(),
+        }
+    }
 
-            ASTNode::While(condition, body) => {
-                println!("[While]");
+    /// Regenerates the source code of the parameters, return type, and body of a function.
+    fn signature(
+        &mut self,
+        parameters: &[Parameter],
+        ret: Option<TypeExpr>,
+        body: &Block,
+        depth: usize,
+    ) -> String {
+        let parameters: Vec<String> = parameters.iter().map(parameter_source).collect();
+        match ret {
+            Some(ret) => format!(
+                "({}): {} {}",
+                parameters.join(", "),
+                ret,
+                self.block(body, depth)
+            ),
+            None => format!("({}) {}", parameters.join(", "), self.block(body, depth)),
+        }
+    }
 
-                print_node(condition, indent, false);
-                print_node(body, indent, true);
+    /// Regenerates the source code of a block, with its closing brace indented to `depth`.
+    ///
+    /// The comments before each statement are written above it, the ones after it on its line or
+    /// below it, and the ones after the last statement above the closing brace.
+    fn block(&mut self, block: &Block, depth: usize) -> String {
+        let indent = INDENT.repeat(depth + 1);
+        let mut lines = Vec::new();
+        for (i, statement) in block.statements.iter().enumerate() {
+            let row = block.rows.get(i).copied();
+            let next = block.rows.get(i + 1).copied().unwrap_or(block.end);
+            if let (Some(comments), Some(row)) = (self.comments.as_mut(), row) {
+                lines.extend(comments.before(row));
             }
+            let mut line = self.stmt(statement, depth + 1);
+            let mut below = Vec::new();
+            if let (Some(comments), Some(_)) = (self.comments.as_mut(), row) {
+                let (trailing, own) = comments.after(next);
+                for comment in trailing {
+                    line.push(' ');
+                    line.push_str(&comment);
+                }
+                below = own;
+            }
+            lines.push(line);
+            lines.extend(below);
+        }
+        // A block that was not parsed has no comments
+        if let (Some(comments), true) = (self.comments.as_mut(), block.end > 0) {
+            lines.extend(comments.before(block.end));
+        }
+
+        if lines.is_empty() {
+            return "{}".to_string();
+        }
+        let mut source = String::from("{\n");
+        for line in lines {
+            source.push_str(&indent);
+            source.push_str(&line);
+            source.push('\n');
+        }
+        source.push_str(&INDENT.repeat(depth));
+        source.push('}');
+        source
+    }
 
-            ASTNode::Array(children) => {
-                println!("[Array]");
+    /// Regenerates the source code of a list of expressions, separated by commas.
+    fn list(&mut self, elements: &[Expr], depth: usize) -> String {
+        let elements: Vec<String> = elements.iter().map(|e| self.expr(e, depth)).collect();
+        elements.join(", ")
+    }
 
-                let len = children.len();
-                for (i, child) in children.iter().enumerate() {
-                    let next_last = last && i == len - 1;
-                    let indent_next = if next_last { "    " } else { "│   " };
-                    indent.push(indent_next);
-                    print_node(child, indent, next_last);
-                    indent.pop();
+    /// Regenerates the source code of an expression.
+    fn expr(&mut self, expression: &Expr, depth: usize) -> String {
+        match expression {
+            Expr::Literal(literal) => literal.to_string(),
+            Expr::Identifier(name) => name.clone(),
+            Expr::Group(elements) => format!("({})", self.list(elements, depth)),
+            Expr::Array(elements) => format!("[{}]", self.list(elements, depth)),
+            Expr::Unary(unary) => format!("{}{}", unary.op, self.expr(&unary.expr, depth)),
+            Expr::Binary(binary) => format!(
+                "{} {} {}",
+                self.expr(&binary.left, depth),
+                binary.op,
+                self.expr(&binary.right, depth)
+            ),
+            Expr::Call(call) => format!("{}({})", call.name, self.list(&call.arguments, depth)),
+            Expr::Block(block) => self.block(block, depth),
+            Expr::If(statement) => {
+                let mut source = format!(
+                    "if {} {}",
+                    self.expr(&statement.condition, depth),
+                    self.block(&statement.then, depth)
+                );
+                if let Some(otherwise) = &statement.otherwise {
+                    source.push_str(" else ");
+                    source.push_str(&self.expr(otherwise, depth));
                 }
+                source
             }
+            Expr::While(statement) => format!(
+                "while {} {}",
+                self.expr(&statement.condition, depth),
+                self.block(&statement.body, depth)
+            ),
+            Expr::For(statement) => format!(
+                "for {} in {} {}",
+                statement.variable,
+                self.expr(&statement.iterable, depth),
+                self.block(&statement.body, depth)
+            ),
+            Expr::Lambda(lambda) => format!(
+                "fn{}",
+                self.signature(&lambda.parameters, lambda.ret, &lambda.body, depth)
+            ),
+            Expr::Try(statement) => format!(
+                "try {} catch {} {}",
+                self.block(&statement.body, depth),
+                statement.variable,
+                self.block(&statement.handler, depth)
+            ),
+            Expr::Cast(cast) => format!("{} as {}", self.expr(&cast.expr, depth), cast.ty),
+            Expr::Range(range) => format!(
+                "{}{}{}",
+                self.expr(&range.start, depth),
+                if range.inclusive { "..=" } else { ".." },
+                self.expr(&range.end, depth)
+            ),
+            Expr::Index(index) => format!(
+                "{}[{}]",
+                self.expr(&index.target, depth),
+                self.expr(&index.index, depth)
+            ),
+            Expr::Member(member) => format!("{}.{}", self.expr(&member.target, depth), member.name),
+            Expr::MethodCall(call) => format!(
+                "{}.{}({})",
+                self.expr(&call.target, depth),
+                call.name,
+                self.list(&call.arguments, depth)
+            ),
+        }
+    }
+}
+
+/// Regenerates the source code of a function parameter.
+fn parameter_source(parameter: &Parameter) -> String {
+    match parameter.ty {
+        Some(ty) => format!("{}: {}", parameter.name, ty),
+        None => parameter.name.clone(),
+    }
+}
 
-            ASTNode::ParenDelimiter => todo!(),
-            ASTNode::BraceDelimiter => todo!(),
-            ASTNode::BracketDelimiter => todo!(),
-            ASTNode::Separator => todo!(),
-            ASTNode::End => todo!(),
+/// Prints the nodes of the abstract syntax tree (AST) in a tree-like structure to the standard
+/// output.
+///
+/// # Arguments
+///
+/// * `tree` - The abstract syntax tree to be printed.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - Ok(()) if printing is successful, Err(io::Error) otherwise.
+pub fn print_tree(tree: &Tree) -> io::Result<()> {
+    write_tree(&mut io::stdout().lock(), tree)
+}
+
+/// Renders the nodes of the abstract syntax tree (AST) in a tree-like structure.
+///
+/// # Example
+/// ```
+/// use hydrogen::hash::{parser::Parser, print};
+///
+/// let tree = Parser::new("x = 1").parse_program().unwrap();
+///
+/// assert_eq!(
+///     print::to_tree(&tree),
+///     "[Variable Definition]\n├───x\n└───1\n\n"
+/// );
+/// ```
+pub fn to_tree(tree: &Tree) -> String {
+    let mut output = Vec::new();
+    write_tree(&mut output, tree).expect("writing to a vector does not fail");
+    String::from_utf8(output).expect("the tree is written as text")
+}
+
+/// Writes the nodes of the abstract syntax tree (AST) in a tree-like structure, followed by an
+/// empty line after every statement.
+///
+/// # Arguments
+///
+/// * `writer` - The writer the tree is written to.
+/// * `tree` - The abstract syntax tree to be written.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - Ok(()) if writing is successful, Err(io::Error) otherwise.
+pub fn write_tree<W: Write + ?Sized>(writer: &mut W, tree: &Tree) -> io::Result<()> {
+    fn write_branch<W: Write + ?Sized>(
+        writer: &mut W,
+        branch: &Branch,
+        indent: &mut Vec<&str>,
+        last: Option<bool>,
+    ) -> io::Result<()> {
+        for i in indent.iter() {
+            write!(writer, "{}", i)?;
+        }
+        match last {
+            Some(true) => write!(writer, "└───")?,
+            Some(false) => write!(writer, "├───")?,
+            None => {}
         }
+
+        if branch.children.is_empty() {
+            return writeln!(writer, "{}", branch.label);
+        }
+        writeln!(writer, "[{}]", branch.label)?;
+
+        if let Some(last) = last {
+            indent.push(if last { "    " } else { "│   " });
+        }
+        let len = branch.children.len();
+        for (i, child) in branch.children.iter().enumerate() {
+            write_branch(writer, child, indent, Some(i == len - 1))?;
+        }
+        if last.is_some() {
+            indent.pop();
+        }
+        Ok(())
     }
 
-    // fn print_message(message: &str, indent: &mut Vec<&str>, last: bool) {
-    //     if !indent.is_empty() {
-    //         for i in 0..indent.len() {
-    //             print!("{}", indent[i]);
-    //         }
-
-    //         if last {
-    //             print!("└───");
-    //         } else {
-    //             print!("├───");
-    //         }
-    //     }
-    //     println!("[{}]", message);
-    // }
+    for statement in tree {
+        write_branch(writer, &stmt_branch(statement), &mut Vec::new(), None)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Renders the tokens the parser reads from the source code, one per line with the row and the
+/// column where it starts, its kind, and its text.
+///
+/// # Example
+/// ```
+/// use hydrogen::hash::print;
+///
+/// assert_eq!(
+///     print::to_tokens("x = \"a\"\n"),
+///     "1:1 Identifier \"x\"\n1:3 Equal \"=\"\n1:5 String \"\\\"a\\\"\"\n"
+/// );
+/// ```
+pub fn to_tokens(source: &str) -> String {
+    Lexer::new(source)
+        .spans()
+        .map(|(token, span)| {
+            let Position { row, col, .. } = token.position();
+            let text = &source[span.start..span.end()];
+            format!("{}:{} {} {:?}\n", row, col, token.name(), text)
+        })
+        .collect()
+}
+
+/// Renders the tokens the parser reads from the source code as a JSON array, with an object
+/// holding the kind, the text, the row, the column, the byte offset, and the length in bytes of
+/// every token on its own line.
+///
+/// # Example
+/// ```
+/// use hydrogen::hash::print;
+///
+/// assert_eq!(
+///     print::to_tokens_json("x"),
+///     "[\n  {\"col\":1,\"kind\":\"Identifier\",\"len\":1,\"offset\":0,\"row\":1,\"text\":\"x\"}\n]\n"
+/// );
+/// ```
+pub fn to_tokens_json(source: &str) -> String {
+    let tokens: Vec<String> = Lexer::new(source)
+        .spans()
+        .map(|(token, span)| {
+            let position = token.position();
+            let fields = [
+                ("kind", Value::from(token.name())),
+                ("text", Value::from(&source[span.start..span.end()])),
+                ("row", Value::Integer(position.row as i64)),
+                ("col", Value::Integer(position.col as i64)),
+                ("offset", Value::Integer(span.start as i64)),
+                ("len", Value::Integer(span.len as i64)),
+            ];
+            let object = Value::map(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (name.to_string(), value))
+                    .collect(),
+            );
+            json::encode(&object).expect("tokens are encoded as plain JSON")
+        })
+        .map(|token| format!("  {}", token))
+        .collect();
+    match tokens.is_empty() {
+        true => "[]\n".to_string(),
+        false => format!("[\n{}\n]\n", tokens.join(",\n")),
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::hash::{ast::Nodes, parser::Parser};
+    use crate::hash::parser::Parser;
+
+    #[test]
+    fn test_to_source() {
+        let source = "x:num=-1\nadd(a:num,b:num):num{return a+b}\nf(){return}\nif x<(2+3)*4{print([x,\"a\"])}else if x{}else{y:bool}\nwhile false{}\nfor i in 0..n+1{continue}\na||b&&!c\nx as str+\"1\"\ntry{1/0}catch e{print(e)}\nf:fn=fn(x:num):num{x}\nconst k=1\nk*=2+1\n[a,b]=pair\nf({x},[y]){x}\n";
+        let tree = Parser::new(source).parse_program().unwrap();
+        let formatted = to_source(&tree);
+
+        assert_eq!(
+            formatted,
+            "x: num = -1\n\
+             add(a: num, b: num): num {\n    return a + b\n}\n\
+             f() {\n    return\n}\n\
+             if x < (2 + 3) * 4 {\n    print([x, \"a\"])\n} else if x {} else {\n    y: bool\n}\n\
+             while false {}\n\
+             for i in 0..n + 1 {\n    continue\n}\n\
+             a || b && !c\n\
+             x as str + \"1\"\n\
+             try {\n    1 / 0\n} catch e {\n    print(e)\n}\n\
+             f: fn = fn(x: num): num {\n    x\n}\n\
+             const k = 1\n\
+             k *= 2 + 1\n\
+             [a, b] = pair\n\
+             f({x}, [y]) {\n    x\n}\n"
+        );
+        assert_eq!(Parser::new(&formatted).parse_program(), Ok(tree));
+    }
+
+    #[test]
+    fn test_items_to_source() {
+        let source = "struct P{x:num,y}\nstruct E{}\ninterface S{area(self):num;name(self)}\ninterface N{}\nimpl S for P{area(self):num{self.x}}\np.area(1,2).name()\n";
+        let tree = Parser::new(source).parse_program().unwrap();
+        let formatted = to_source(&tree);
+
+        assert_eq!(
+            formatted,
+            "struct P { x: num, y }\n\
+             struct E {}\n\
+             interface S {\n    area(self): num\n    name(self)\n}\n\
+             interface N {}\n\
+             impl S for P {\n    area(self): num {\n        self.x\n    }\n}\n\
+             p.area(1, 2).name()\n"
+        );
+        assert_eq!(Parser::new(&formatted).parse_program(), Ok(tree.clone()));
+        assert!(to_tree(&tree[4..].to_vec()).starts_with("[Implementation]\n├───S\n├───P\n"));
+        assert!(to_tree(&tree[5..].to_vec()).starts_with("[Method Call]\n├───[Method Call]\n"));
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let tree = Parser::new("x = \"ab\" + 1").parse_program().unwrap();
+        let dot = to_dot(&tree);
+
+        assert!(dot.starts_with("digraph ast {"));
+        assert!(dot.contains("node0 [label=\"Variable Definition\"];"));
+        assert!(dot.contains("program -> node0;"));
+        assert!(dot.contains("node0 -> node1;"));
+        assert!(dot.contains("[label=\"Binary Expression\"];"));
+        assert!(dot.contains("[label=\"\\\"ab\\\"\"];"));
+        assert!(dot.trim_end().ends_with('}'));
+    }
+
+    #[test]
+    fn test_to_tokens() {
+        let source = "f(\"a\nb\") // c\n  π ~";
+        assert_eq!(
+            to_tokens(source),
+            "1:1 Identifier \"f\"\n\
+             1:2 LeftParenthesis \"(\"\n\
+             1:3 String \"\\\"a\\nb\\\"\"\n\
+             2:3 RightParenthesis \")\"\n\
+             3:3 Identifier \"π\"\n\
+             3:5 Unknown \"~\"\n"
+        );
+
+        let json = to_tokens_json(source);
+        assert_eq!(json.lines().count(), 8);
+        assert!(json.contains(
+            r#"{"col":3,"kind":"String","len":5,"offset":2,"row":1,"text":"\"a\nb\""},"#
+        ));
+        assert_eq!(to_tokens_json("// nothing"), "[]\n");
+    }
 
     use super::*;
 
@@ -348,7 +976,7 @@ mod test {
                 var1: num = 1234
                 var2 = var1 + 1234
 
-                var3: num = lambda() {
+                var3: fn = fn() {
                     var: str = "Hello, World!"
                 }
 
@@ -356,33 +984,21 @@ mod test {
             }
         "#;
 
-        let mut parser = Parser::new(program);
-
-        let mut results: Nodes = Vec::new();
-        let mut errors: Errors = Vec::new();
-
-        loop {
-            match parser.parse() {
-                Ok(node) => match *node {
-                    ASTNode::End => break,
-                    _ => {
-                        results.push(node);
-                    }
-                },
-                Err(error) => {
-                    errors.push(error);
-                }
-            }
-        }
-
-        if errors.is_empty() {
-            let _ = print_ast(&results);
-            print_tree(&results);
-        } else {
-            let _ = print_error(errors);
-        }
-
-        // TODO: Add more specific assertions based on your expected AST structure
-        // For example, you can assert the structure of the AST, the types of nodes, etc.
+        let results = Parser::new(program).parse_program().unwrap();
+        let mut output = Styled::new(Vec::new(), false);
+        write_ast(&mut output, &results).unwrap();
+        let output = String::from_utf8(output.into_inner()).unwrap();
+
+        let tree = to_tree(&results);
+        assert!(output.starts_with("[\n    Item(\n"));
+        assert!(output.ends_with(&tree));
+        assert!(tree.starts_with("[Function Definition]\n├───hi\n"));
+        assert!(tree.contains("    │       ├───[Variable Definition]\n    │       │   ├───var1\n"));
+
+        let errors = Parser::new("x = (1 +\nreturn").parse_program().unwrap_err();
+        let mut output = Styled::new(Vec::new(), false);
+        write_errors(&mut output, &errors).unwrap();
+        let output = String::from_utf8(output.into_inner()).unwrap();
+        assert!(output.lines().all(|line| line.starts_with("ERROR[H")));
     }
 }
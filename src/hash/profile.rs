@@ -0,0 +1,164 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// The calls of one function recorded by a [`Profiler`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Calls {
+    /// The number of times the function was called.
+    pub count: u64,
+    /// The time spent in the function, including the functions it called.
+    pub total: Duration,
+    /// The time spent in the function itself, leaving out the functions it called.
+    pub own: Duration,
+}
+
+/// A call being made, timed by the [`Profiler`]
+#[derive(Debug)]
+struct Active {
+    /// The name of the function called.
+    name: String,
+    /// The moment the call started.
+    start: Instant,
+    /// The time spent in the calls it made so far.
+    nested: Duration,
+}
+
+/// Profiler counting the calls of the functions defined by a program and timing them.
+///
+/// The total time of a recursive function counts its outermost call only, so time is never
+/// counted twice.
+///
+/// # Examples
+/// ```
+/// use hydrogen::hash::profile::Profiler;
+///
+/// let mut profiler = Profiler::default();
+/// profiler.enter("main");
+/// profiler.enter("helper");
+/// profiler.leave();
+/// profiler.leave();
+///
+/// let calls = profiler.calls();
+/// assert_eq!(calls[0].0, "main");
+/// assert!(calls[0].1.own <= calls[0].1.total);
+/// ```
+#[derive(Debug, Default)]
+pub struct Profiler {
+    functions: HashMap<String, Calls>,
+    active: Vec<Active>,
+}
+
+impl Profiler {
+    /// Starts timing a call.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the function called.
+    pub fn enter(&mut self, name: &str) {
+        self.active.push(Active {
+            name: name.to_string(),
+            start: Instant::now(),
+            nested: Duration::ZERO,
+        });
+    }
+
+    /// Stops timing the latest call that was started.
+    pub fn leave(&mut self) {
+        let Some(call) = self.active.pop() else {
+            return;
+        };
+        let elapsed = call.start.elapsed();
+        if let Some(caller) = self.active.last_mut() {
+            caller.nested += elapsed;
+        }
+
+        let recursive = self.active.iter().any(|active| active.name == call.name);
+        let calls = self.functions.entry(call.name).or_default();
+        calls.count += 1;
+        calls.own += elapsed.saturating_sub(call.nested);
+        if !recursive {
+            calls.total += elapsed;
+        }
+    }
+
+    /// Returns the calls of every function called, the longest total time first.
+    pub fn calls(&self) -> Vec<(String, Calls)> {
+        let mut calls: Vec<_> = self
+            .functions
+            .iter()
+            .map(|(name, calls)| (name.clone(), *calls))
+            .collect();
+        calls.sort_by(|(a, a_calls), (b, b_calls)| {
+            b_calls.total.cmp(&a_calls.total).then_with(|| a.cmp(b))
+        });
+        calls
+    }
+}
+
+/// Writes the calls as a table, the longest total time first.
+impl fmt::Display for Profiler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let milliseconds = |duration: Duration| format!("{:.3}ms", duration.as_secs_f64() * 1e3);
+        let calls = self.calls();
+        let width = calls
+            .iter()
+            .map(|(name, _)| name.len())
+            .chain(["function".len()])
+            .max()
+            .unwrap_or_default();
+
+        writeln!(
+            f,
+            "{:<width$}  {:>8}  {:>12}  {:>12}",
+            "function", "calls", "total", "self"
+        )?;
+        for (name, calls) in calls {
+            writeln!(
+                f,
+                "{:<width$}  {:>8}  {:>12}  {:>12}",
+                name,
+                calls.count,
+                milliseconds(calls.total),
+                milliseconds(calls.own)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hash::{evaluator::Evaluator, parser::Parser};
+
+    #[test]
+    fn test_profile() {
+        let mut evaluator = Evaluator::new();
+        evaluator.set_profiling(true);
+        let program = "fib(n) { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }\n\
+                       twice(f, x) { f(f(x)) }\n\
+                       twice(fn(x) { x + 1 }, fib(10))";
+        for statement in Parser::new(program).parse_program().unwrap() {
+            evaluator.evaluate(&statement).unwrap();
+        }
+
+        let profiler = evaluator.profile().unwrap();
+        let calls: HashMap<_, _> = profiler.calls().into_iter().collect();
+        assert_eq!(calls["fib"].count, 177);
+        assert_eq!(calls["twice"].count, 1);
+        assert_eq!(calls["lambda"].count, 2);
+        for calls in calls.values() {
+            assert!(calls.own <= calls.total);
+        }
+
+        let table = profiler.to_string();
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("function  "));
+        assert!(lines[0].ends_with("self"));
+        assert!(table.contains("fib    ") && table.contains("     177  "));
+    }
+}
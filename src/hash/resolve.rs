@@ -0,0 +1,777 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+};
+
+use super::{
+    ast::{Block, Expr, Item, Parameter, Stmt},
+    lexer::Lexer,
+    parser::{Edit, Parsed, Program},
+    tokens::{text_width, Position, Token},
+};
+
+/// Enum representing what a name is defined as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefinitionKind {
+    /// A function defined by name
+    Function,
+    /// A parameter of a function or a lambda
+    Parameter,
+    /// A variable, including the variables of `for` loops and `catch` blocks
+    Variable,
+    /// A constant
+    Constant,
+    /// A struct, whose name creates its values when called
+    Struct,
+    /// An interface
+    Interface,
+}
+
+/// A place where a program defines a name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    /// The name defined.
+    pub name: String,
+    /// What the name is defined as.
+    pub kind: DefinitionKind,
+    /// The position of the name where it is defined.
+    pub position: Position,
+    /// The index in [`Names::definitions`] of the definition of the same name it hides, if
+    /// there was one in scope.
+    pub shadows: Option<usize>,
+    /// Whether the name is defined by an assignment in a function that hides a global variable,
+    /// which the function would give the value to if it declared the name `global`.
+    pub hides_global: bool,
+}
+
+/// Enum representing the reasons a name cannot be renamed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenameError {
+    /// No name the program defines is at the position
+    NoName,
+    /// The new name is not an identifier: (name)
+    InvalidName(String),
+    /// The new name would make some uses of names refer to other definitions: (name)
+    Conflict(String),
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenameError::NoName => write!(f, "no name the program defines is here"),
+            RenameError::InvalidName(name) => write!(f, "`{}` is not a valid name", name),
+            RenameError::Conflict(name) => write!(
+                f,
+                "renaming to `{}` would change what some names refer to",
+                name
+            ),
+        }
+    }
+}
+
+/// A use of a name, like a call or an assignment, with the definition it refers to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    /// The position of the name where it is used.
+    pub position: Position,
+    /// The index of the definition in [`Names::definitions`].
+    pub definition: usize,
+}
+
+/// The names of a program: where each one is defined, and where the definitions are used.
+///
+/// The statements of a program are walked in the order of its source code, following its
+/// scopes: a block sees the names of the blocks around it, and an assignment to a name the
+/// current function already defines, or declares `global`, uses that definition instead of
+/// defining another. A function can call the
+/// functions the program defines after it, since it is called once they are defined. Names that
+/// are never defined, like builtins, have no references.
+///
+/// # Examples
+/// ```
+/// use hydrogen::hash::{parser::Program, resolve::Names};
+///
+/// let source = "x = 1\nx = x + 1";
+/// let names = Names::new(source, &Program::new(source).statements);
+///
+/// assert_eq!(names.definitions.len(), 1);
+/// assert_eq!(names.references_of(0).len(), 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Names {
+    /// The definitions, in the order of the source code.
+    pub definitions: Vec<Definition>,
+    /// The references, in the order of the source code.
+    pub references: Vec<Reference>,
+}
+
+impl Names {
+    /// Finds the names of a program.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source code of the program.
+    /// * `statements` - The statements parsed from the source code, whose errors are skipped.
+    pub fn new(source: &str, statements: &[Parsed]) -> Self {
+        let mut resolver = Resolver {
+            names: Names::default(),
+            scopes: vec![HashMap::new()],
+            tokens: VecDeque::new(),
+            frames: Vec::new(),
+            globals: Vec::new(),
+            nonlocals: Vec::new(),
+            unresolved: Vec::new(),
+        };
+        for parsed in statements {
+            let Ok(statement) = &parsed.statement else {
+                continue;
+            };
+            resolver.tokens = identifiers(source, parsed);
+            resolver.statement(statement);
+        }
+
+        // Functions can call the functions of the program defined after them
+        let globals = resolver.scopes.swap_remove(0);
+        for (name, position) in std::mem::take(&mut resolver.unresolved) {
+            if let Some(&definition) = globals.get(&name) {
+                resolver.names.references.push(Reference {
+                    position,
+                    definition,
+                });
+            }
+        }
+        resolver
+            .names
+            .references
+            .sort_by_key(|reference| reference.position.offset);
+        resolver.names
+    }
+
+    /// Returns the definition of the name at a position, whether the name is defined or used
+    /// there.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The row of the position, starting at 1.
+    /// * `col` - The column of the position, starting at 1.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<usize>` - The index of the definition in [`Names::definitions`], or None if no
+    ///   name the program defines is at the position.
+    pub fn definition_at(&self, row: usize, col: usize) -> Option<usize> {
+        let covers = |position: &Position, name: &str| {
+            position.row == row && (position.col..position.col + text_width(name)).contains(&col)
+        };
+        let defined = self
+            .definitions
+            .iter()
+            .position(|definition| covers(&definition.position, &definition.name));
+        defined.or_else(|| {
+            self.references
+                .iter()
+                .find(|reference| {
+                    covers(
+                        &reference.position,
+                        &self.definitions[reference.definition].name,
+                    )
+                })
+                .map(|reference| reference.definition)
+        })
+    }
+
+    /// Returns the positions where a definition is used, in the order of the source code.
+    ///
+    /// # Arguments
+    ///
+    /// * `definition` - The index of the definition in [`Names::definitions`].
+    pub fn references_of(&self, definition: usize) -> Vec<Position> {
+        self.references
+            .iter()
+            .filter(|reference| reference.definition == definition)
+            .map(|reference| reference.position.clone())
+            .collect()
+    }
+
+    /// Returns the edits renaming the name at a position where it is defined and everywhere it
+    /// is used.
+    ///
+    /// The renamed program is resolved again, and the rename is refused if any name in it
+    /// would refer to another definition than before, like a use captured by a variable of the
+    /// new name in a block inside, or a builtin hidden by the renamed function.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source code of the program.
+    /// * `row` - The row of the position, starting at 1.
+    /// * `col` - The column of the position, starting at 1.
+    /// * `name` - The new name.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Edit>, RenameError>` - The edits, in the order of the source code, or the
+    ///   reason the name cannot be renamed.
+    ///
+    /// # Examples
+    /// ```
+    /// use hydrogen::hash::{parser::Program, resolve::{Names, RenameError}};
+    ///
+    /// let source = "x = 1\nf(y) { x + y }";
+    /// let names = Names::new(source, &Program::new(source).statements);
+    ///
+    /// let edits = names.rename(source, 1, 1, "total").unwrap();
+    /// assert_eq!(edits.iter().map(|e| e.range.start).collect::<Vec<_>>(), vec![0, 13]);
+    /// assert_eq!(names.rename(source, 2, 3, "x"), Err(RenameError::Conflict("x".to_string())));
+    /// ```
+    pub fn rename(
+        &self,
+        source: &str,
+        row: usize,
+        col: usize,
+        name: &str,
+    ) -> Result<Vec<Edit>, RenameError> {
+        let index = self.definition_at(row, col).ok_or(RenameError::NoName)?;
+        let mut lexer = Lexer::new(name);
+        match (lexer.lex(), lexer.lex()) {
+            (Token::Identifier(_, identifier), Token::Eof(_)) if identifier == name => {}
+            _ => return Err(RenameError::InvalidName(name.to_string())),
+        }
+
+        let old = &self.definitions[index].name;
+        let mut positions = self.references_of(index);
+        positions.push(self.definitions[index].position.clone());
+        positions.sort_by_key(|position| position.offset);
+        let edits: Vec<_> = positions
+            .iter()
+            .map(|position| Edit {
+                range: position.offset..position.offset + old.len(),
+                text: name.to_string(),
+            })
+            .collect();
+
+        let mut renamed = source.to_string();
+        for edit in edits.iter().rev() {
+            renamed.replace_range(edit.range.clone(), &edit.text);
+        }
+        let names = Names::new(&renamed, &Program::new(&renamed).statements);
+
+        // Every offset moves by the change of length of the edits before it
+        let moved = |position: &Position| {
+            let before = edits
+                .iter()
+                .filter(|edit| edit.range.start < position.offset)
+                .count();
+            (position.offset + before * name.len()) - before * old.len()
+        };
+        let same = names.definitions.len() == self.definitions.len()
+            && names.references.len() == self.references.len()
+            && (self.definitions.iter().zip(&names.definitions))
+                .all(|(old, new)| moved(&old.position) == new.position.offset)
+            && (self.references.iter().zip(&names.references)).all(|(old, new)| {
+                moved(&old.position) == new.position.offset && old.definition == new.definition
+            });
+        match same {
+            true => Ok(edits),
+            false => Err(RenameError::Conflict(name.to_string())),
+        }
+    }
+}
+
+/// Returns the identifiers of a statement with their positions, in order.
+fn identifiers(source: &str, parsed: &Parsed) -> VecDeque<(String, Position)> {
+    Lexer::starting_at(source, parsed.start.clone())
+        .take_while(|token| token.position().offset < parsed.end.offset)
+        .filter_map(|token| match token {
+            Token::Identifier(position, name) => Some((name.into_owned(), position)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Pass walking the statements of a program in the order of their source code, matching the
+/// names of the tree with the identifiers of the source code.
+struct Resolver {
+    names: Names,
+    scopes: Vec<HashMap<String, usize>>,
+    // The identifiers of the statement being walked that are not matched yet
+    tokens: VecDeque<(String, Position)>,
+    // The index of the first scope of every function body being walked
+    frames: Vec<usize>,
+    // The names declared `global`, with the index of the scope declaring them
+    globals: Vec<(usize, String)>,
+    // The names declared `nonlocal`, with the index of the scope declaring them
+    nonlocals: Vec<(usize, String)>,
+    // The names used in functions before any definition of them, with where they are used
+    unresolved: Vec<(String, Position)>,
+}
+
+impl Resolver {
+    /// Takes the position of the next identifier if it is the given name.
+    ///
+    /// Names the parser adds, like the `x` of `x = x + 1` written as `x += 1`, have no identifier
+    /// of their own, so they leave the identifiers as they are.
+    fn take(&mut self, name: &str) -> Option<Position> {
+        match self.tokens.front() {
+            Some((next, _)) if next == name => {
+                self.tokens.pop_front().map(|(_, position)| position)
+            }
+            _ => None,
+        }
+    }
+
+    /// Defines a name in the innermost scope.
+    fn define(&mut self, name: &str, kind: DefinitionKind, position: Option<Position>) {
+        self.define_in(self.scopes.len() - 1, name, kind, position);
+    }
+
+    /// Defines a name in the scope with the given index.
+    fn define_in(
+        &mut self,
+        scope: usize,
+        name: &str,
+        kind: DefinitionKind,
+        position: Option<Position>,
+    ) {
+        let Some(position) = position else {
+            return;
+        };
+        let shadows = self.lookup(name);
+        self.names.definitions.push(Definition {
+            name: name.to_string(),
+            kind,
+            position,
+            shadows,
+            hides_global: false,
+        });
+        let index = self.names.definitions.len() - 1;
+        self.scopes[scope].insert(name.to_string(), index);
+    }
+
+    /// Gives a value to a name at a position, using the definition an assignment reaches, or
+    /// defining the name in the scope an assignment defines it in.
+    ///
+    /// Assignments in a function only reach the definitions of the function, the global
+    /// definitions of the names it declares `global`, and the definitions of enclosing functions
+    /// of the names it declares `nonlocal`.
+    fn assign(&mut self, name: &str, position: Option<Position>) {
+        let first = self.frames.last().copied().unwrap_or(0);
+        let mut scope = self.scopes.len() - 1;
+        for i in (first..self.scopes.len()).rev() {
+            if self.scopes[i].contains_key(name) {
+                scope = i;
+                break;
+            }
+            if self.globals.contains(&(i, name.to_string())) {
+                scope = 0;
+                break;
+            }
+            if self.nonlocals.contains(&(i, name.to_string())) {
+                if let Some(enclosing) = self.nonlocal(name) {
+                    scope = enclosing;
+                    break;
+                }
+            }
+        }
+
+        match (self.scopes[scope].get(name).copied(), position) {
+            (Some(definition), Some(position)) => self.names.references.push(Reference {
+                position,
+                definition,
+            }),
+            (Some(_), None) => {}
+            (None, position) => {
+                let global = self.scopes[0].get(name).copied();
+                let defined = self.names.definitions.len();
+                self.define_in(scope, name, DefinitionKind::Variable, position);
+                if let Some(definition) = self.names.definitions.get_mut(defined) {
+                    definition.hides_global = global.is_some() && definition.shadows == global;
+                }
+            }
+        }
+    }
+
+    /// Returns the index of the closest scope of the functions enclosing the function being
+    /// walked that defines a name, leaving out the global scope.
+    fn nonlocal(&self, name: &str) -> Option<usize> {
+        let first = self.frames.last().copied()?;
+        (1..first)
+            .rev()
+            .find(|&i| self.scopes[i].contains_key(name))
+    }
+
+    /// Returns the closest definition of a name.
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+
+    /// Uses a name at the next identifier.
+    fn refer(&mut self, name: &str) {
+        let Some(position) = self.take(name) else {
+            return;
+        };
+        match self.lookup(name) {
+            Some(definition) => self.names.references.push(Reference {
+                position,
+                definition,
+            }),
+            None if !self.frames.is_empty() => self.unresolved.push((name.to_string(), position)),
+            None => {}
+        }
+    }
+
+    /// Walks a statement.
+    fn statement(&mut self, statement: &Stmt) {
+        match statement {
+            Stmt::Item(Item::Function(definition)) => {
+                let position = self.take(&definition.name);
+                // Defined before the body, so that recursive calls find it
+                self.define(&definition.name, DefinitionKind::Function, position);
+                self.function(&definition.parameters, &definition.body);
+            }
+
+            // The names of fields are keys, not variables
+            Stmt::Item(Item::Struct(definition)) => {
+                let position = self.take(&definition.name);
+                self.define(&definition.name, DefinitionKind::Struct, position);
+                for field in &definition.fields {
+                    self.take(&field.name);
+                }
+            }
+
+            // The signatures only name functions and parameters, without defining them
+            Stmt::Item(Item::Interface(definition)) => {
+                let position = self.take(&definition.name);
+                self.define(&definition.name, DefinitionKind::Interface, position);
+                for signature in &definition.signatures {
+                    self.take(&signature.name);
+                    for parameter in &signature.parameters {
+                        match &parameter.pattern {
+                            Some(pattern) => pattern.names().iter().for_each(|name| {
+                                self.take(name);
+                            }),
+                            None => {
+                                self.take(&parameter.name);
+                            }
+                        }
+                    }
+                }
+            }
+
+            // The functions are only called on values of the struct, so their names are not
+            // defined in any scope
+            Stmt::Item(Item::Impl(implementation)) => {
+                self.refer(&implementation.interface);
+                self.refer(&implementation.ty);
+                for definition in implementation.functions() {
+                    self.take(&definition.name);
+                    self.function(&definition.parameters, &definition.body);
+                }
+            }
+
+            Stmt::Definition(definition) => {
+                let position = self.take(&definition.name);
+                self.expression(&definition.value);
+                let kind = match definition.constant {
+                    true => DefinitionKind::Constant,
+                    false => DefinitionKind::Variable,
+                };
+                match definition.ty.is_none() && !definition.constant {
+                    true => self.assign(&definition.name, position),
+                    false => self.define(&definition.name, kind, position),
+                }
+            }
+
+            Stmt::Declaration(declaration) => {
+                let position = self.take(&declaration.name);
+                self.define(&declaration.name, DefinitionKind::Variable, position);
+            }
+
+            // Every name is assigned like the name of a definition without a type
+            Stmt::Destructure(destructuring) => {
+                let names = destructuring.pattern.names();
+                let positions: Vec<_> = names.iter().map(|name| self.take(name)).collect();
+                self.expression(&destructuring.value);
+                for (name, position) in names.iter().zip(positions) {
+                    self.assign(name, position);
+                }
+            }
+
+            // The target of a compound assignment is written once, before the value on the right
+            Stmt::Store(store) => {
+                self.expression(&store.target);
+                match &store.value {
+                    Expr::Binary(binary) if store.compound => self.expression(&binary.right),
+                    value => self.expression(value),
+                }
+            }
+
+            // The names refer to the global definitions, which may come later
+            Stmt::Global(names) => {
+                for name in names {
+                    let scope = self.scopes.len() - 1;
+                    self.globals.push((scope, name.clone()));
+                    let Some(position) = self.take(name) else {
+                        continue;
+                    };
+                    match self.scopes[0].get(name) {
+                        Some(&definition) => self.names.references.push(Reference {
+                            position,
+                            definition,
+                        }),
+                        None => self.unresolved.push((name.clone(), position)),
+                    }
+                }
+            }
+
+            // The names refer to the definitions of enclosing functions, which come before
+            Stmt::Nonlocal(names) => {
+                for name in names {
+                    let scope = self.scopes.len() - 1;
+                    self.nonlocals.push((scope, name.clone()));
+                    let position = self.take(name);
+                    let definition = self.nonlocal(name).map(|scope| self.scopes[scope][name]);
+                    if let (Some(position), Some(definition)) = (position, definition) {
+                        self.names.references.push(Reference {
+                            position,
+                            definition,
+                        });
+                    }
+                }
+            }
+
+            Stmt::Expr(expression) | Stmt::Return(Some(expression)) => self.expression(expression),
+
+            Stmt::Return(None) | Stmt::Break | Stmt::Continue => {}
+        }
+    }
+
+    /// Walks an expression.
+    fn expression(&mut self, expression: &Expr) {
+        match expression {
+            Expr::Literal(_) => {}
+            Expr::Identifier(name) => self.refer(name),
+            Expr::Group(elements) | Expr::Array(elements) => {
+                elements.iter().for_each(|e| self.expression(e))
+            }
+            Expr::Unary(unary) => self.expression(&unary.expr),
+            Expr::Binary(binary) => {
+                self.expression(&binary.left);
+                self.expression(&binary.right);
+            }
+            Expr::Call(call) => {
+                self.refer(&call.name);
+                call.arguments.iter().for_each(|a| self.expression(a));
+            }
+            Expr::Block(block) => self.block(block, Vec::new()),
+            Expr::If(statement) => {
+                self.expression(&statement.condition);
+                self.block(&statement.then, Vec::new());
+                if let Some(otherwise) = &statement.otherwise {
+                    self.expression(otherwise);
+                }
+            }
+            Expr::While(statement) => {
+                self.expression(&statement.condition);
+                self.block(&statement.body, Vec::new());
+            }
+            Expr::For(statement) => {
+                let position = self.take(&statement.variable);
+                self.expression(&statement.iterable);
+                let variable = (&statement.variable, DefinitionKind::Variable, position);
+                self.block(&statement.body, vec![variable]);
+            }
+            Expr::Range(range) => {
+                self.expression(&range.start);
+                self.expression(&range.end);
+            }
+            Expr::Index(index) => {
+                self.expression(&index.target);
+                self.expression(&index.index);
+            }
+            // The name of a member is a key, not a variable
+            Expr::Member(member) => {
+                self.expression(&member.target);
+                self.take(&member.name);
+            }
+            Expr::MethodCall(call) => {
+                self.expression(&call.target);
+                self.take(&call.name);
+                call.arguments.iter().for_each(|a| self.expression(a));
+            }
+            Expr::Cast(cast) => self.expression(&cast.expr),
+            Expr::Try(statement) => {
+                self.block(&statement.body, Vec::new());
+                let position = self.take(&statement.variable);
+                let variable = (&statement.variable, DefinitionKind::Variable, position);
+                self.block(&statement.handler, vec![variable]);
+            }
+            Expr::Lambda(lambda) => self.function(&lambda.parameters, &lambda.body),
+        }
+    }
+
+    /// Walks the body of a function with its parameters in scope.
+    fn function(&mut self, parameters: &[Parameter], body: &Block) {
+        // The names of a pattern are parameters of their own
+        let parameters = parameters
+            .iter()
+            .flat_map(|parameter| match &parameter.pattern {
+                Some(pattern) => pattern.names(),
+                None => std::slice::from_ref(&parameter.name),
+            })
+            .map(|name| {
+                let position = self.take(name);
+                (name, DefinitionKind::Parameter, position)
+            })
+            .collect();
+        self.frames.push(self.scopes.len());
+        self.block(body, parameters);
+        self.frames.pop();
+    }
+
+    /// Walks the statements of a block in a scope holding the given names.
+    fn block(&mut self, block: &Block, names: Vec<(&String, DefinitionKind, Option<Position>)>) {
+        self.scopes.push(HashMap::new());
+        for (name, kind, position) in names {
+            self.define(name, kind, position);
+        }
+        block.statements.iter().for_each(|s| self.statement(s));
+        self.scopes.pop();
+        let depth = self.scopes.len();
+        self.globals.retain(|(scope, _)| *scope < depth);
+        self.nonlocals.retain(|(scope, _)| *scope < depth);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hash::parser::Program;
+
+    #[test]
+    fn test_resolve() {
+        let source = "count = 0\n\
+                      bump(by: num) {\n    global count\n    count = count + by\n    helper()\n}\n\
+                      helper() { global count; for i in 0..3 { count += i } }\n\
+                      bump(2)\n\
+                      try { print(count) } catch count { count }\n\
+                      reset() { count = 0 }";
+        let names = Names::new(source, &Program::new(source).statements);
+
+        let defined: Vec<_> = names
+            .definitions
+            .iter()
+            .map(|d| (d.name.as_str(), d.kind, d.position.row, d.position.col))
+            .collect();
+        assert_eq!(
+            defined,
+            vec![
+                ("count", DefinitionKind::Variable, 1, 1),
+                ("bump", DefinitionKind::Function, 2, 1),
+                ("by", DefinitionKind::Parameter, 2, 6),
+                ("helper", DefinitionKind::Function, 7, 1),
+                ("i", DefinitionKind::Variable, 7, 30),
+                ("count", DefinitionKind::Variable, 9, 28),
+                ("reset", DefinitionKind::Function, 10, 1),
+                ("count", DefinitionKind::Variable, 10, 11),
+            ]
+        );
+
+        let rows = |definition| -> Vec<_> {
+            let references = names.references_of(definition);
+            references.iter().map(|p| (p.row, p.col)).collect()
+        };
+        assert_eq!(
+            rows(0),
+            vec![(3, 12), (4, 5), (4, 13), (7, 19), (7, 42), (9, 13)]
+        );
+        assert_eq!(rows(2), vec![(4, 21)]);
+        assert_eq!(rows(3), vec![(5, 5)]);
+        assert_eq!(rows(4), vec![(7, 51)]);
+        assert_eq!(rows(5), vec![(9, 36)]);
+        let shadows: Vec<_> = names.definitions.iter().map(|d| d.shadows).collect();
+        assert_eq!(
+            shadows,
+            vec![None, None, None, None, None, Some(0), None, Some(0)]
+        );
+        // Only the assignment of a function without `global` hides the global variable
+        let hiding: Vec<_> = names.definitions.iter().map(|d| d.hides_global).collect();
+        assert_eq!(
+            hiding,
+            vec![false, false, false, false, false, false, false, true]
+        );
+
+        assert_eq!(names.definition_at(4, 16), Some(0));
+        assert_eq!(names.definition_at(2, 3), Some(1));
+        assert_eq!(names.definition_at(8, 1), Some(1));
+        assert_eq!(names.definition_at(9, 8), None);
+
+        let edits = names.rename(source, 4, 13, "total").unwrap();
+        let starts: Vec<_> = edits.iter().map(|edit| edit.range.start).collect();
+        assert_eq!(starts, vec![0, 37, 47, 55, 99, 122, 157]);
+        assert!(edits
+            .iter()
+            .all(|edit| edit.range.len() == 5 && edit.text == "total"));
+        assert!(names.rename(source, 2, 1, "step").is_ok());
+
+        let conflict = |name: &str| Err(RenameError::Conflict(name.to_string()));
+        // The parameter would capture the uses of the variable in the body
+        assert_eq!(names.rename(source, 2, 6, "count"), conflict("count"));
+        // The loop variable would capture the variable it is added to
+        assert_eq!(names.rename(source, 7, 30, "count"), conflict("count"));
+        // The function would hide the builtin
+        assert_eq!(names.rename(source, 7, 1, "print"), conflict("print"));
+        assert_eq!(
+            names.rename(source, 1, 1, "for"),
+            Err(RenameError::InvalidName("for".to_string()))
+        );
+        assert_eq!(
+            names.rename(source, 1, 1, "a b"),
+            Err(RenameError::InvalidName("a b".to_string()))
+        );
+        assert_eq!(names.rename(source, 9, 8, "show"), Err(RenameError::NoName));
+
+        // A `nonlocal` name is the variable of the enclosing function
+        let source = "counter() {\n    c = 0\n    fn() { nonlocal c; c += 1 }\n}";
+        let names = Names::new(source, &Program::new(source).statements);
+        let defined: Vec<_> = names.definitions.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(defined, vec!["counter", "c"]);
+        let references = names.references_of(1);
+        let rows: Vec<_> = references.iter().map(|p| (p.row, p.col)).collect();
+        assert_eq!(rows, vec![(3, 21), (3, 24)]);
+
+        // The names of members are keys, and an element is given a value without defining it
+        let source = "x = 0\np = [x]\np[x].x += x";
+        let names = Names::new(source, &Program::new(source).statements);
+        assert_eq!(names.definitions.len(), 2);
+        let references = names.references_of(0);
+        let rows: Vec<_> = references.iter().map(|p| (p.row, p.col)).collect();
+        assert_eq!(rows, vec![(2, 6), (3, 3), (3, 11)]);
+        assert_eq!(names.references_of(1).len(), 1);
+
+        // Structs and interfaces are named by constructors and `impl` blocks
+        let source = "struct P { x }\ninterface S { f(self) }\nimpl S for P { f(self) { self.x } }\nP(1).f()";
+        let names = Names::new(source, &Program::new(source).statements);
+        let defined: Vec<_> = names
+            .definitions
+            .iter()
+            .map(|d| (d.name.as_str(), d.kind))
+            .collect();
+        assert_eq!(
+            defined,
+            vec![
+                ("P", DefinitionKind::Struct),
+                ("S", DefinitionKind::Interface),
+                ("self", DefinitionKind::Parameter),
+            ]
+        );
+        let references = names.references_of(0);
+        let rows: Vec<_> = references.iter().map(|p| (p.row, p.col)).collect();
+        assert_eq!(rows, vec![(3, 12), (4, 1)]);
+        let references = names.references_of(1);
+        let rows: Vec<_> = references.iter().map(|p| (p.row, p.col)).collect();
+        assert_eq!(rows, vec![(3, 6)]);
+    }
+}
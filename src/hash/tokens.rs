@@ -1,10 +1,80 @@
-use std::fmt;
+use std::{borrow::Cow, fmt};
+
+use unicode_width::UnicodeWidthChar;
+
+/// The number of columns between tab stops, which a tab moves a position to the next of, unless
+/// the lexer is given another one with [`Lexer::set_tab_width`](super::lexer::Lexer::set_tab_width).
+pub const TAB_WIDTH: usize = 4;
 
 /// Struct representing the position of a token in the source code
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Position {
+    /// The column of the position, starting at 1, counted in the width the characters before it
+    /// take on a terminal, so wide characters count twice and combining marks not at all. A tab
+    /// moves the column to the next tab stop, and a `\r` takes no column, so lines ending with
+    /// `\r\n` are counted like lines ending with `\n`.
     pub col: usize,
+    /// The row of the position, starting at 1.
     pub row: usize,
+    /// The byte offset of the position from the start of the source code.
+    pub offset: usize,
+}
+
+impl Position {
+    /// Returns the position of a byte offset of the source code, counting rows and columns the
+    /// way the lexer does.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source code.
+    /// * `offset` - The byte offset, which must be at the start of a character.
+    /// * `tab_width` - The number of columns between tab stops.
+    pub fn locate(source: &str, offset: usize, tab_width: usize) -> Self {
+        let mut position = Self {
+            col: 1,
+            row: 1,
+            offset: 0,
+        };
+        for c in source[..offset].chars() {
+            position.advance(c, tab_width);
+        }
+        position
+    }
+
+    /// Moves the position past a character of the source code.
+    ///
+    /// # Arguments
+    ///
+    /// * `c` - The character at the position.
+    /// * `tab_width` - The number of columns between tab stops, at least 1.
+    pub fn advance(&mut self, c: char, tab_width: usize) {
+        self.offset += c.len_utf8();
+        match c {
+            '\n' => {
+                self.row += 1;
+                self.col = 1;
+            }
+            '\r' => {}
+            '\t' => self.col += tab_width - (self.col - 1) % tab_width,
+            // Control characters, which have no width of their own, take one column
+            _ => self.col += c.width().unwrap_or(1),
+        }
+    }
+}
+
+/// Returns the number of columns a text of a single line takes from the start of the line, the
+/// way positions count them.
+///
+/// # Examples
+/// ```
+/// use hydrogen::hash::tokens::text_width;
+///
+/// assert_eq!(text_width("name"), 4);
+/// assert_eq!(text_width("名前"), 4);
+/// assert_eq!(text_width("\tx\r"), 5);
+/// ```
+pub fn text_width(text: &str) -> usize {
+    Position::locate(text, text.len(), TAB_WIDTH).col - 1
 }
 
 impl fmt::Display for Position {
@@ -13,58 +83,450 @@ impl fmt::Display for Position {
     }
 }
 
+/// Struct representing the bytes of the source code a token covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    /// The byte offset where the token starts.
+    pub start: usize,
+    /// The number of bytes the token covers.
+    pub len: usize,
+}
+
+impl Span {
+    /// Returns the byte offset right after the token.
+    pub fn end(&self) -> usize {
+        self.start + self.len
+    }
+}
+
+/// Enum representing the words reserved for the statements and expressions of the language
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Keyword {
+    /// `if`
+    If,
+    /// `else`
+    Else,
+    /// `while`
+    While,
+    /// `for`
+    For,
+    /// `try`
+    Try,
+    /// `catch`
+    Catch,
+    /// `break`
+    Break,
+    /// `continue`
+    Continue,
+    /// `return`
+    Return,
+    /// `const`
+    Const,
+    /// `global`
+    Global,
+    /// `nonlocal`
+    Nonlocal,
+    /// `struct`
+    Struct,
+    /// `interface`
+    Interface,
+    /// `impl`
+    Impl,
+}
+
+impl Keyword {
+    /// Every keyword, in the order they are listed in.
+    pub const ALL: [Keyword; 15] = [
+        Keyword::If,
+        Keyword::Else,
+        Keyword::While,
+        Keyword::For,
+        Keyword::Try,
+        Keyword::Catch,
+        Keyword::Break,
+        Keyword::Continue,
+        Keyword::Return,
+        Keyword::Const,
+        Keyword::Global,
+        Keyword::Nonlocal,
+        Keyword::Struct,
+        Keyword::Interface,
+        Keyword::Impl,
+    ];
+
+    /// Returns the keyword written as a word, or None if the word is not a keyword.
+    pub fn from_word(word: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|keyword| keyword.name() == word)
+    }
+
+    /// Returns the word the keyword is written as.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Keyword::If => "if",
+            Keyword::Else => "else",
+            Keyword::While => "while",
+            Keyword::For => "for",
+            Keyword::Try => "try",
+            Keyword::Catch => "catch",
+            Keyword::Break => "break",
+            Keyword::Continue => "continue",
+            Keyword::Return => "return",
+            Keyword::Const => "const",
+            Keyword::Global => "global",
+            Keyword::Nonlocal => "nonlocal",
+            Keyword::Struct => "struct",
+            Keyword::Interface => "interface",
+            Keyword::Impl => "impl",
+        }
+    }
+}
+
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Enum representing the names of the builtin types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TypeName {
+    /// `num`
+    Num,
+    /// `str`
+    Str,
+    /// `bool`
+    Bool,
+    /// `fn`, which also starts a lambda
+    Fn,
+}
+
+impl TypeName {
+    /// Every type name, in the order they are listed in.
+    pub const ALL: [TypeName; 4] = [TypeName::Num, TypeName::Str, TypeName::Bool, TypeName::Fn];
+
+    /// Returns the type named by a word, or None if the word is not a type name.
+    pub fn from_word(word: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|ty| ty.name() == word)
+    }
+
+    /// Returns the word the type is written as.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TypeName::Num => "num",
+            TypeName::Str => "str",
+            TypeName::Bool => "bool",
+            TypeName::Fn => "fn",
+        }
+    }
+}
+
+impl fmt::Display for TypeName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
 /// Enum representing different types of tokens
+///
+/// The text of identifiers, literals, and comments borrows from the source code when possible,
+/// so lexing does not allocate.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub enum Token {
+pub enum Token<'a> {
+    /// `(`
     LeftParenthesis(Position),
+    /// `)`
     RightParenthesis(Position),
+    /// `{`
     LeftBrace(Position),
+    /// `}`
     RightBrace(Position),
+    /// `[`
     LeftBracket(Position),
+    /// `]`
     RightBracket(Position),
+    /// `+`
     Plus(Position),
+    /// `+=`
     PlusEqual(Position),
+    /// `-`
     Minus(Position),
+    /// `-=`
     MinusEqual(Position),
+    /// `*`
     Asterisk(Position),
+    /// `*=`
     AsteriskEqual(Position),
+    /// `/`
     Slash(Position),
+    /// `/=`
     SlashEqual(Position),
+    /// `=`
     Equal(Position),
+    /// `==`
     Equals(Position),
+    /// `!=`
     NotEqual(Position),
+    /// `>`
     GreaterThan(Position),
+    /// `>=`
     GreaterThanOrEqual(Position),
+    /// `<`
     LessThan(Position),
+    /// `<=`
     LessThanOrEqual(Position),
+    /// `&`
     Ampersand(Position),
+    /// `&&`
     And(Position),
+    /// `||`
     Or(Position),
+    /// `$`
     DollarSign(Position),
+    /// `#`
     Hash(Position),
+    /// `!`
     ExplinationMark(Position),
+    /// `?`
     QuestionMark(Position),
+    /// `:`
     Colon(Position),
+    /// `.`
     Dot(Position),
+    /// `..`
+    DotDot(Position),
+    /// `..=`
+    DotDotEqual(Position),
+    /// `,`
     Comma(Position),
+    /// `;`
+    Semicolon(Position),
+    /// `@`
     At(Position),
+    /// `%`
     Percent(Position),
+    /// `%=`
     PercentEqual(Position),
+    /// `^`
     Caret(Position),
+    /// `^=`
     CaretEqual(Position),
+    /// The `in` keyword
     In(Position),
+    /// The `as` keyword
     As(Position),
-    Identifier(Position, String),
-    Type(Position, String),
-    Keyword(Position, String),
-    String(Position, String),
-    Boolean(Position, String),
-    Number(Position, String),
-    Unknown(Position, String),
+    /// An identifier: (position, name)
+    Identifier(Position, Cow<'a, str>),
+    /// A builtin type name: (position, type)
+    Type(Position, TypeName),
+    /// A keyword: (position, keyword)
+    Keyword(Position, Keyword),
+    /// A string literal: (position, content)
+    String(Position, Cow<'a, str>),
+    /// A boolean literal: (position, value)
+    Boolean(Position, Cow<'a, str>),
+    /// A number literal: (position, digits)
+    Number(Position, Cow<'a, str>),
+    /// A line or block comment, only produced when the lexer keeps comments: (position, text)
+    Comment(Position, Cow<'a, str>),
+    /// Text that is not a valid token: (position, text)
+    Unknown(Position, Cow<'a, str>),
+    /// A string literal without its closing quote, which runs to the end of the source code:
+    /// (position of the opening quote, content)
+    UnterminatedString(Position, Cow<'a, str>),
+    /// A block comment without its closing `*/`, which runs to the end of the source code:
+    /// (position of the opening `/*`)
+    UnterminatedComment(Position),
+    /// The end of the source code
     Eof(Position),
 }
 
-impl fmt::Display for Token {
+impl Token<'_> {
+    /// Returns the name of the kind of the token, like `Identifier` or `LeftParenthesis`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Token::LeftParenthesis(_) => "LeftParenthesis",
+            Token::RightParenthesis(_) => "RightParenthesis",
+            Token::LeftBrace(_) => "LeftBrace",
+            Token::RightBrace(_) => "RightBrace",
+            Token::LeftBracket(_) => "LeftBracket",
+            Token::RightBracket(_) => "RightBracket",
+            Token::Plus(_) => "Plus",
+            Token::PlusEqual(_) => "PlusEqual",
+            Token::Minus(_) => "Minus",
+            Token::MinusEqual(_) => "MinusEqual",
+            Token::Asterisk(_) => "Asterisk",
+            Token::AsteriskEqual(_) => "AsteriskEqual",
+            Token::Slash(_) => "Slash",
+            Token::SlashEqual(_) => "SlashEqual",
+            Token::Equal(_) => "Equal",
+            Token::Equals(_) => "Equals",
+            Token::NotEqual(_) => "NotEqual",
+            Token::GreaterThan(_) => "GreaterThan",
+            Token::GreaterThanOrEqual(_) => "GreaterThanOrEqual",
+            Token::LessThan(_) => "LessThan",
+            Token::LessThanOrEqual(_) => "LessThanOrEqual",
+            Token::Ampersand(_) => "Ampersand",
+            Token::And(_) => "And",
+            Token::Or(_) => "Or",
+            Token::DollarSign(_) => "DollarSign",
+            Token::Hash(_) => "Hash",
+            Token::ExplinationMark(_) => "ExplinationMark",
+            Token::QuestionMark(_) => "QuestionMark",
+            Token::Colon(_) => "Colon",
+            Token::Dot(_) => "Dot",
+            Token::DotDot(_) => "DotDot",
+            Token::DotDotEqual(_) => "DotDotEqual",
+            Token::Comma(_) => "Comma",
+            Token::Semicolon(_) => "Semicolon",
+            Token::At(_) => "At",
+            Token::Percent(_) => "Percent",
+            Token::PercentEqual(_) => "PercentEqual",
+            Token::Caret(_) => "Caret",
+            Token::CaretEqual(_) => "CaretEqual",
+            Token::In(_) => "In",
+            Token::As(_) => "As",
+            Token::Identifier(_, _) => "Identifier",
+            Token::Type(_, _) => "Type",
+            Token::Keyword(_, _) => "Keyword",
+            Token::String(_, _) => "String",
+            Token::Boolean(_, _) => "Boolean",
+            Token::Number(_, _) => "Number",
+            Token::Comment(_, _) => "Comment",
+            Token::Unknown(_, _) => "Unknown",
+            Token::UnterminatedString(_, _) => "UnterminatedString",
+            Token::UnterminatedComment(_) => "UnterminatedComment",
+            Token::Eof(_) => "Eof",
+        }
+    }
+
+    /// Returns the position in the source code where the token starts.
+    pub fn position(&self) -> &Position {
+        match self {
+            Token::LeftParenthesis(position)
+            | Token::RightParenthesis(position)
+            | Token::LeftBrace(position)
+            | Token::RightBrace(position)
+            | Token::LeftBracket(position)
+            | Token::RightBracket(position)
+            | Token::Plus(position)
+            | Token::PlusEqual(position)
+            | Token::Minus(position)
+            | Token::MinusEqual(position)
+            | Token::Asterisk(position)
+            | Token::AsteriskEqual(position)
+            | Token::Slash(position)
+            | Token::SlashEqual(position)
+            | Token::Equal(position)
+            | Token::Equals(position)
+            | Token::NotEqual(position)
+            | Token::GreaterThan(position)
+            | Token::GreaterThanOrEqual(position)
+            | Token::LessThan(position)
+            | Token::LessThanOrEqual(position)
+            | Token::Ampersand(position)
+            | Token::And(position)
+            | Token::Or(position)
+            | Token::DollarSign(position)
+            | Token::Hash(position)
+            | Token::ExplinationMark(position)
+            | Token::QuestionMark(position)
+            | Token::Colon(position)
+            | Token::Dot(position)
+            | Token::DotDot(position)
+            | Token::DotDotEqual(position)
+            | Token::Comma(position)
+            | Token::Semicolon(position)
+            | Token::At(position)
+            | Token::Percent(position)
+            | Token::PercentEqual(position)
+            | Token::Caret(position)
+            | Token::CaretEqual(position)
+            | Token::In(position)
+            | Token::As(position)
+            | Token::Identifier(position, _)
+            | Token::Type(position, _)
+            | Token::Keyword(position, _)
+            | Token::String(position, _)
+            | Token::Boolean(position, _)
+            | Token::Number(position, _)
+            | Token::Comment(position, _)
+            | Token::Unknown(position, _)
+            | Token::UnterminatedString(position, _)
+            | Token::UnterminatedComment(position)
+            | Token::Eof(position) => position,
+        }
+    }
+
+    /// Returns a copy of the token that owns its text, so it can outlive the source code.
+    pub fn into_owned(self) -> Token<'static> {
+        match self {
+            Token::LeftParenthesis(position) => Token::LeftParenthesis(position),
+            Token::RightParenthesis(position) => Token::RightParenthesis(position),
+            Token::LeftBrace(position) => Token::LeftBrace(position),
+            Token::RightBrace(position) => Token::RightBrace(position),
+            Token::LeftBracket(position) => Token::LeftBracket(position),
+            Token::RightBracket(position) => Token::RightBracket(position),
+            Token::Plus(position) => Token::Plus(position),
+            Token::PlusEqual(position) => Token::PlusEqual(position),
+            Token::Minus(position) => Token::Minus(position),
+            Token::MinusEqual(position) => Token::MinusEqual(position),
+            Token::Asterisk(position) => Token::Asterisk(position),
+            Token::AsteriskEqual(position) => Token::AsteriskEqual(position),
+            Token::Slash(position) => Token::Slash(position),
+            Token::SlashEqual(position) => Token::SlashEqual(position),
+            Token::Equal(position) => Token::Equal(position),
+            Token::Equals(position) => Token::Equals(position),
+            Token::NotEqual(position) => Token::NotEqual(position),
+            Token::GreaterThan(position) => Token::GreaterThan(position),
+            Token::GreaterThanOrEqual(position) => Token::GreaterThanOrEqual(position),
+            Token::LessThan(position) => Token::LessThan(position),
+            Token::LessThanOrEqual(position) => Token::LessThanOrEqual(position),
+            Token::Ampersand(position) => Token::Ampersand(position),
+            Token::And(position) => Token::And(position),
+            Token::Or(position) => Token::Or(position),
+            Token::DollarSign(position) => Token::DollarSign(position),
+            Token::Hash(position) => Token::Hash(position),
+            Token::ExplinationMark(position) => Token::ExplinationMark(position),
+            Token::QuestionMark(position) => Token::QuestionMark(position),
+            Token::Colon(position) => Token::Colon(position),
+            Token::Dot(position) => Token::Dot(position),
+            Token::DotDot(position) => Token::DotDot(position),
+            Token::DotDotEqual(position) => Token::DotDotEqual(position),
+            Token::Comma(position) => Token::Comma(position),
+            Token::Semicolon(position) => Token::Semicolon(position),
+            Token::At(position) => Token::At(position),
+            Token::Percent(position) => Token::Percent(position),
+            Token::PercentEqual(position) => Token::PercentEqual(position),
+            Token::Caret(position) => Token::Caret(position),
+            Token::CaretEqual(position) => Token::CaretEqual(position),
+            Token::In(position) => Token::In(position),
+            Token::As(position) => Token::As(position),
+            Token::Identifier(position, text) => {
+                Token::Identifier(position, Cow::Owned(text.into_owned()))
+            }
+            Token::Type(position, ty) => Token::Type(position, ty),
+            Token::Keyword(position, keyword) => Token::Keyword(position, keyword),
+            Token::String(position, text) => Token::String(position, Cow::Owned(text.into_owned())),
+            Token::Boolean(position, text) => {
+                Token::Boolean(position, Cow::Owned(text.into_owned()))
+            }
+            Token::Number(position, text) => Token::Number(position, Cow::Owned(text.into_owned())),
+            Token::Comment(position, text) => {
+                Token::Comment(position, Cow::Owned(text.into_owned()))
+            }
+            Token::Unknown(position, text) => {
+                Token::Unknown(position, Cow::Owned(text.into_owned()))
+            }
+            Token::UnterminatedString(position, text) => {
+                Token::UnterminatedString(position, Cow::Owned(text.into_owned()))
+            }
+            Token::UnterminatedComment(position) => Token::UnterminatedComment(position),
+            Token::Eof(position) => Token::Eof(position),
+        }
+    }
+}
+
+impl fmt::Display for Token<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Token::LeftParenthesis(_) => write!(f, "("),
@@ -89,14 +551,16 @@ impl fmt::Display for Token {
             Token::LessThan(_) => write!(f, "<"),
             Token::LessThanOrEqual(_) => write!(f, "<="),
             Token::Ampersand(_) => write!(f, "&"),
-            Token::And(_) => write!(f, "and"),
-            Token::Or(_) => write!(f, "or"),
+            Token::And(_) => write!(f, "&&"),
+            Token::Or(_) => write!(f, "||"),
             Token::DollarSign(_) => write!(f, "$"),
             Token::Hash(_) => write!(f, "#"),
             Token::ExplinationMark(_) => write!(f, "!"),
             Token::QuestionMark(_) => write!(f, "?"),
             Token::Colon(_) => write!(f, ":"),
             Token::Dot(_) => write!(f, "."),
+            Token::DotDot(_) => write!(f, ".."),
+            Token::DotDotEqual(_) => write!(f, "..="),
             Token::At(_) => write!(f, "@"),
             Token::Percent(_) => write!(f, "%"),
             Token::PercentEqual(_) => write!(f, "%="),
@@ -108,11 +572,19 @@ impl fmt::Display for Token {
             Token::String(_, s) => write!(f, "String(\"{}\")", s),
             Token::Boolean(_, b) => write!(f, "Boolean({})", b),
             Token::Number(_, n) => write!(f, "Number({})", n),
+            Token::Comment(_, c) => write!(f, "{}", c),
             Token::Unknown(_, u) => write!(f, "Unknown({})", u),
+            Token::UnterminatedString(position, _) => {
+                write!(f, "unterminated string literal starting at {}", position)
+            }
+            Token::UnterminatedComment(position) => {
+                write!(f, "unterminated block comment starting at {}", position)
+            }
             Token::Eof(_) => write!(f, "EOF"),
-            Token::In(_) => todo!(),
-            Token::As(_) => todo!(),
+            Token::In(_) => write!(f, "in"),
+            Token::As(_) => write!(f, "as"),
             Token::Comma(_) => write!(f, "Comma"),
+            Token::Semicolon(_) => write!(f, ";"),
         }
     }
 }
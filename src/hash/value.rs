@@ -0,0 +1,449 @@
+use std::{cell::RefCell, collections::BTreeMap, fmt, rc::Rc};
+
+use super::{
+    environment::{Function, Struct},
+    evaluator::RuntimeError,
+};
+
+/// Enum representing the values a Hydrogen program computes with
+///
+/// Strings, arrays, maps, functions, and structs live behind reference counted pointers, so
+/// copying a value is always cheap. Strings and functions never change, but an array, a map, or a
+/// struct is shared by every variable, argument, and collection holding it: a change made through
+/// one of them is seen through all the others. The `clone` builtin makes a deep copy that shares nothing with the
+/// original.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// The absence of a value, produced by statements and empty blocks
+    None,
+    /// Whole number: (value)
+    Integer(i64),
+    /// Whole number too large for an integer, with the `bignum` feature: (value)
+    #[cfg(feature = "bignum")]
+    BigInteger(Rc<num_bigint::BigInt>),
+    /// Floating point number: (value)
+    Float(f64),
+    /// String: (content)
+    String(Rc<str>),
+    /// Boolean: (value)
+    Boolean(bool),
+    /// Array: (elements)
+    Array(Rc<RefCell<Vec<Value>>>),
+    /// Whole numbers from a start up to an end, which are never stored: (start, end, whether the
+    /// end is included)
+    Range(i64, i64, bool),
+    /// Map from strings to values, ordered by key: (entries)
+    Map(Rc<RefCell<BTreeMap<String, Value>>>),
+    /// Function: (function)
+    Function(Rc<Function>),
+    /// Value of a struct defined by the program: (instance)
+    Instance(Rc<Instance>),
+}
+
+/// A value of a struct, holding a value for each of its fields
+#[derive(PartialEq)]
+pub struct Instance {
+    /// The struct the value was created from.
+    pub ty: Rc<Struct>,
+    /// The values of the fields, in the order the struct defines them.
+    pub fields: RefCell<Vec<Value>>,
+}
+
+impl fmt::Debug for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The struct is named only, as it holds the functions implemented for it
+        f.debug_struct("Instance")
+            .field("ty", &self.ty.name)
+            .field("fields", &self.fields)
+            .finish()
+    }
+}
+
+impl Value {
+    /// Creates an array holding the given elements.
+    pub fn array(elements: Vec<Value>) -> Self {
+        Value::Array(Rc::new(RefCell::new(elements)))
+    }
+
+    /// Creates a map holding the given entries.
+    pub fn map(entries: BTreeMap<String, Value>) -> Self {
+        Value::Map(Rc::new(RefCell::new(entries)))
+    }
+
+    /// Creates a value of a struct holding the given values of its fields.
+    pub fn instance(ty: Rc<Struct>, fields: Vec<Value>) -> Self {
+        Value::Instance(Rc::new(Instance {
+            ty,
+            fields: RefCell::new(fields),
+        }))
+    }
+
+    /// Returns a copy of the value that shares no array, map, or struct with it.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Value, RuntimeError>` - The copy, or an error if a collection holds itself,
+    ///   directly or through the collections inside it, since its copy would never end.
+    pub fn deep_clone(&self) -> Result<Self, RuntimeError> {
+        self.copy(&mut Vec::new())
+    }
+
+    /// Copies a value inside the arrays and maps being copied, whose addresses are `open`.
+    fn copy(&self, open: &mut Vec<*const ()>) -> Result<Self, RuntimeError> {
+        let Some(address) = self.address() else {
+            return Ok(self.clone());
+        };
+        if open.contains(&address) {
+            return Err(RuntimeError::CyclicValue("clone".to_string()));
+        }
+
+        open.push(address);
+        let copy = match self {
+            Value::Array(elements) => elements
+                .borrow()
+                .iter()
+                .map(|element| element.copy(open))
+                .collect::<Result<_, _>>()
+                .map(Value::array),
+            Value::Map(entries) => entries
+                .borrow()
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), value.copy(open)?)))
+                .collect::<Result<_, _>>()
+                .map(Value::map),
+            Value::Instance(instance) => instance
+                .fields
+                .borrow()
+                .iter()
+                .map(|field| field.copy(open))
+                .collect::<Result<_, _>>()
+                .map(|fields| Value::instance(Rc::clone(&instance.ty), fields)),
+            _ => unreachable!("only arrays, maps, and structs have an address"),
+        };
+        open.pop();
+        copy
+    }
+
+    /// Returns the address of the elements of an array, a map, or a struct, which tells if a walk
+    /// through the collections inside it comes back to it.
+    pub(crate) fn address(&self) -> Option<*const ()> {
+        match self {
+            Value::Array(elements) => Some(Rc::as_ptr(elements) as *const ()),
+            Value::Map(entries) => Some(Rc::as_ptr(entries) as *const ()),
+            Value::Instance(instance) => Some(Rc::as_ptr(instance) as *const ()),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the Hydrogen type of the value.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::None => "none",
+            Value::Integer(_) | Value::Float(_) => "num",
+            #[cfg(feature = "bignum")]
+            Value::BigInteger(_) => "num",
+            Value::String(_) => "str",
+            Value::Boolean(_) => "bool",
+            Value::Array(_) => "array",
+            Value::Range(..) => "range",
+            Value::Map(_) => "map",
+            Value::Function(_) => "fn",
+            Value::Instance(_) => "struct",
+        }
+    }
+
+    /// Returns the value as a float if it is a number.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Integer(value) => Some(*value as f64),
+            #[cfg(feature = "bignum")]
+            Value::BigInteger(value) => Some(super::bignum::to_float(value)),
+            Value::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value the way it is written in source code, quoting strings.
+    pub fn repr(&self) -> String {
+        let mut text = String::new();
+        let _ = self.write(&mut text, true, &mut Vec::new());
+        text
+    }
+
+    /// Writes a value inside the arrays and maps being written, whose addresses are `open`.
+    ///
+    /// An array, a map, or a struct that holds itself is written as `[...]`, `{...}`, or
+    /// `Point(...)` where it appears inside itself.
+    fn write(
+        &self,
+        f: &mut dyn fmt::Write,
+        quoted: bool,
+        open: &mut Vec<*const ()>,
+    ) -> fmt::Result {
+        if let Some(address) = self.address() {
+            if open.contains(&address) {
+                return match self {
+                    Value::Array(_) => write!(f, "[...]"),
+                    Value::Instance(instance) => write!(f, "{}(...)", instance.ty.name),
+                    _ => write!(f, "{{...}}"),
+                };
+            }
+            open.push(address);
+        }
+
+        let written = match self {
+            Value::String(value) if quoted => write!(f, "\"{}\"", value),
+            Value::Array(elements) => {
+                write!(f, "[")?;
+                for (index, element) in elements.borrow().iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    element.write(f, true, open)?;
+                }
+                write!(f, "]")
+            }
+            Value::Map(entries) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in entries.borrow().iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "\"{}\": ", key)?;
+                    value.write(f, true, open)?;
+                }
+                write!(f, "}}")
+            }
+            Value::Instance(instance) => {
+                write!(f, "{}(", instance.ty.name)?;
+                for (index, field) in instance.fields.borrow().iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    field.write(f, true, open)?;
+                }
+                write!(f, ")")
+            }
+            _ => self.write_scalar(f),
+        };
+
+        if self.address().is_some() {
+            open.pop();
+        }
+        written
+    }
+
+    /// Writes a value that holds no other value.
+    fn write_scalar(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        match self {
+            Value::None => write!(f, "none"),
+            Value::Integer(value) => write!(f, "{}", value),
+            #[cfg(feature = "bignum")]
+            Value::BigInteger(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::String(value) => write!(f, "{}", value),
+            Value::Boolean(value) => write!(f, "{}", value),
+            Value::Range(start, end, false) => write!(f, "{}..{}", start, end),
+            Value::Range(start, end, true) => write!(f, "{}..={}", start, end),
+            Value::Function(function) => write!(f, "<fn {}>", function.name),
+            Value::Array(_) | Value::Map(_) | Value::Instance(_) => {
+                unreachable!("arrays, maps, and structs hold values")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, false, &mut Vec::new())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.into())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value.into())
+    }
+}
+
+impl From<()> for Value {
+    fn from(_: ()) -> Self {
+        Value::None
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(elements: Vec<T>) -> Self {
+        Value::array(elements.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::None, Into::into)
+    }
+}
+
+/// Returns the error for a value converted to a Rust type it does not hold.
+fn mismatch(expected: &str, value: &Value) -> RuntimeError {
+    RuntimeError::TypeMismatch(expected.to_string(), value.type_name().to_string())
+}
+
+impl TryFrom<Value> for i64 {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Integer(value) => Ok(value),
+            _ => Err(mismatch("num", &value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for f64 {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        value.as_float().ok_or_else(|| mismatch("num", &value))
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Boolean(value) => Ok(value),
+            _ => Err(mismatch("bool", &value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(value) => Ok(value.to_string()),
+            _ => Err(mismatch("str", &value)),
+        }
+    }
+}
+
+impl<T: TryFrom<Value, Error = RuntimeError>> TryFrom<Value> for Vec<T> {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Array(elements) => elements.borrow().iter().cloned().map(T::try_from).collect(),
+            _ => Err(mismatch("array", &value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(Value::from(42), Value::Integer(42));
+        assert_eq!(Value::from(1.5), Value::Float(1.5));
+        assert_eq!(Value::from("hi"), Value::String("hi".into()));
+        assert_eq!(Value::from(true), Value::Boolean(true));
+        assert_eq!(Value::from(None::<i64>), Value::None);
+        assert_eq!(
+            Value::from(vec![1, 2]),
+            Value::array(vec![Value::Integer(1), Value::Integer(2)])
+        );
+
+        assert_eq!(i64::try_from(Value::Integer(7)), Ok(7));
+        assert_eq!(f64::try_from(Value::Integer(7)), Ok(7.0));
+        assert_eq!(String::try_from(Value::from("hi")), Ok("hi".to_string()));
+        assert_eq!(
+            Vec::<bool>::try_from(Value::from(vec![true])),
+            Ok(vec![true])
+        );
+        assert_eq!(
+            i64::try_from(Value::from("7")),
+            Err(RuntimeError::TypeMismatch(
+                "num".to_string(),
+                "str".to_string()
+            ))
+        );
+        assert_eq!(
+            Vec::<i64>::try_from(Value::from(vec![Value::from(1), Value::from(false)])),
+            Err(RuntimeError::TypeMismatch(
+                "num".to_string(),
+                "bool".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_sharing() {
+        let inner = Value::from(vec![1]);
+        let array = Value::array(vec![inner.clone(), Value::from("a")]);
+        let alias = array.clone();
+        let copy = array.deep_clone().unwrap();
+
+        let Value::Array(elements) = &inner else {
+            unreachable!()
+        };
+        elements.borrow_mut().push(Value::Integer(2));
+
+        assert_eq!(alias.to_string(), "[[1, 2], \"a\"]");
+        assert_eq!(array, alias);
+        assert_eq!(copy.to_string(), "[[1], \"a\"]");
+        assert_ne!(array, copy);
+    }
+
+    #[test]
+    fn test_cycles() {
+        let array = Value::from(vec![1]);
+        let map = Value::map(BTreeMap::new());
+        let (Value::Array(elements), Value::Map(entries)) = (&array, &map) else {
+            unreachable!()
+        };
+        elements.borrow_mut().push(map.clone());
+        entries.borrow_mut().insert("a".to_string(), array.clone());
+        entries
+            .borrow_mut()
+            .insert("b".to_string(), Value::from("x"));
+
+        assert_eq!(array.to_string(), "[1, {\"a\": [...], \"b\": \"x\"}]");
+        assert_eq!(map.repr(), "{\"a\": [1, {...}], \"b\": \"x\"}");
+        // A collection held twice without holding itself is written out both times
+        let twice = Value::array(vec![Value::from(vec![2]), Value::from(vec![2])]);
+        assert_eq!(twice.to_string(), "[[2], [2]]");
+
+        assert_eq!(
+            array.deep_clone(),
+            Err(RuntimeError::CyclicValue("clone".to_string()))
+        );
+        assert_eq!(twice.deep_clone().unwrap().to_string(), "[[2], [2]]");
+    }
+}
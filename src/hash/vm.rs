@@ -0,0 +1,902 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    io::Write,
+    path::Path,
+    rc::Rc,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use super::{
+    ast::TypeExpr,
+    builtins::{self, Caller, Native},
+    compile::{compile_body, Chunk, Op},
+    environment::{Env, Environment, Function, Interface, Struct},
+    evaluator::{
+        self, bind, cast, check_type, construct, destructure, element, implement, limited, method,
+        numbers, range, store, truthy, Arithmetic, Budget, RuntimeError, StackTrace, MAX_DEPTH,
+    },
+    value::Value,
+};
+
+/// A call being run, or the program itself.
+struct Frame {
+    /// The code being run.
+    chunk: Rc<Chunk>,
+    /// The position of the next instruction.
+    ip: usize,
+    /// The height of the stack when the call started.
+    base: usize,
+    /// The environment of the caller, restored when the call returns.
+    caller: Env,
+    /// The name of the function called.
+    name: String,
+    /// The file the code is written in, if it is known.
+    file: Option<Rc<Path>>,
+    /// The types the value of the call must have, from every function it ran in place of.
+    returns: Vec<TypeExpr>,
+}
+
+/// A `try` block being run.
+struct Handler {
+    /// The position of the code run when an error is caught.
+    target: usize,
+    /// The number of frames when the block started.
+    frames: usize,
+    /// The height of the stack when the block started.
+    stack: usize,
+    /// The environment when the block started.
+    environment: Env,
+}
+
+/// Vm runs programs compiled to bytecode by [`compile`](super::compile::compile).
+///
+/// Programs behave the same as with the [`Evaluator`](super::evaluator::Evaluator), but calls
+/// are kept on a stack of frames instead of the Rust stack, and there is no tree to walk.
+/// Variables and functions defined by a program are kept between runs.
+///
+/// # Examples
+/// ```
+/// use hydrogen::hash::{compile::compile, parser::Parser, value::Value, vm::Vm};
+///
+/// let tree = Parser::new("f(n) { n * 2 }\nf(1 + 2)").parse_program().unwrap();
+/// let mut vm = Vm::new();
+///
+/// assert_eq!(vm.run(compile(&tree).unwrap()), Ok(Value::Integer(6)));
+/// ```
+pub struct Vm {
+    globals: Env,
+    environment: Env,
+    natives: HashMap<String, Native>,
+    max_depth: usize,
+    max_length: usize,
+    arithmetic: Arithmetic,
+    budget: Budget,
+    trace: Option<Box<dyn Write>>,
+    stack: Vec<Value>,
+    frames: Vec<Frame>,
+    handlers: Vec<Handler>,
+    // The file of the programs run next, if it is known
+    file: Option<Rc<Path>>,
+    // The calls active when the error being propagated happened, until it is caught or reported
+    unwound: Option<StackTrace>,
+}
+
+impl fmt::Debug for Vm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Vm")
+            .field("globals", &self.globals)
+            .field("natives", &self.natives.keys().collect::<Vec<_>>())
+            .field("max_depth", &self.max_depth)
+            .field("max_length", &self.max_length)
+            .field("arithmetic", &self.arithmetic)
+            .field("budget", &self.budget)
+            .field("trace", &self.trace.is_some())
+            .field("stack", &self.stack)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Vm {
+    /// Creates a new Vm instance with an empty global environment.
+    pub fn new() -> Self {
+        let globals = Environment::new();
+        Self {
+            environment: Rc::clone(&globals),
+            globals,
+            natives: HashMap::new(),
+            max_depth: MAX_DEPTH,
+            max_length: usize::MAX,
+            arithmetic: Arithmetic::Checked,
+            budget: Budget::default(),
+            trace: None,
+            stack: Vec::new(),
+            frames: Vec::new(),
+            handlers: Vec::new(),
+            file: None,
+            unwound: None,
+        }
+    }
+
+    /// Registers a native function that programs can call by name.
+    ///
+    /// See [`Evaluator::register`](super::evaluator::Evaluator::register).
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name programs call the function with.
+    /// * `function` - The function to be called.
+    pub fn register(&mut self, name: &str, function: Native) {
+        self.natives.insert(name.to_string(), function);
+    }
+
+    /// Sets how deeply calls of functions defined by the program can nest.
+    ///
+    /// See [`Evaluator::set_max_depth`](super::evaluator::Evaluator::set_max_depth).
+    ///
+    /// # Arguments
+    ///
+    /// * `max_depth` - The number of calls that can be active at the same time.
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// Sets how long the strings and arrays built by operators can be.
+    ///
+    /// See [`Evaluator::set_max_length`](super::evaluator::Evaluator::set_max_length).
+    ///
+    /// # Arguments
+    ///
+    /// * `max_length` - The number of elements or bytes a value can hold.
+    pub fn set_max_length(&mut self, max_length: usize) {
+        self.max_length = max_length;
+    }
+
+    /// Sets what arithmetic on whole numbers does when it overflows.
+    ///
+    /// See [`Evaluator::set_arithmetic`](super::evaluator::Evaluator::set_arithmetic).
+    ///
+    /// # Arguments
+    ///
+    /// * `arithmetic` - What overflowing arithmetic does.
+    pub fn set_arithmetic(&mut self, arithmetic: Arithmetic) {
+        self.arithmetic = arithmetic;
+    }
+
+    /// Sets how many instructions every run can execute, which is unlimited unless it is set.
+    ///
+    /// See [`Evaluator::set_max_steps`](super::evaluator::Evaluator::set_max_steps).
+    ///
+    /// # Arguments
+    ///
+    /// * `max_steps` - The number of instructions allowed, or None for no limit.
+    pub fn set_max_steps(&mut self, max_steps: Option<u64>) {
+        self.budget.set_max_steps(max_steps);
+    }
+
+    /// Sets how long every run can take, which is unlimited unless it is set.
+    ///
+    /// See [`Evaluator::set_timeout`](super::evaluator::Evaluator::set_timeout).
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The time allowed, or None for no limit.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.budget.set_timeout(timeout);
+    }
+
+    /// Sets where every executed instruction is written, indented one level for each call
+    /// being made.
+    ///
+    /// # Arguments
+    ///
+    /// * `trace` - The writer of the trace, or None to stop tracing.
+    pub fn set_trace(&mut self, trace: Option<Box<dyn Write>>) {
+        self.trace = trace;
+    }
+
+    /// Forgets the variables and functions programs defined, keeping the native functions and the
+    /// limits.
+    pub fn reset(&mut self) {
+        self.globals = Environment::new();
+        self.environment = Rc::clone(&self.globals);
+        self.stack.clear();
+        self.frames.clear();
+        self.handlers.clear();
+        self.unwound = None;
+    }
+
+    /// Sets the file the programs run next are written in, which the functions they define
+    /// remember for stack traces.
+    ///
+    /// See [`Evaluator::set_file`](super::evaluator::Evaluator::set_file).
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The path of the file, or None for source code that is not in a file.
+    pub fn set_file(&mut self, file: Option<&Path>) {
+        self.file = file.map(Rc::from);
+    }
+
+    /// Returns the calls that were active when the last error returned by [`Vm::run`] happened,
+    /// forgetting them.
+    ///
+    /// The rows of the calls are only known for programs compiled with
+    /// [`compile_at`](super::compile::compile_at).
+    ///
+    /// # Examples
+    /// ```
+    /// use hydrogen::hash::{compile::compile_at, parser::Parser, vm::Vm};
+    ///
+    /// let tree = Parser::new("half(n) { n / 0 }\nhalf(1)").parse_program().unwrap();
+    /// let mut vm = Vm::new();
+    /// assert!(vm.run(compile_at(&tree, &[1, 2]).unwrap()).is_err());
+    ///
+    /// let trace = vm.take_stack_trace();
+    /// assert_eq!(trace.to_string().lines().nth(2), Some("    in `half` at line 1"));
+    /// ```
+    pub fn take_stack_trace(&mut self) -> StackTrace {
+        self.unwound.take().unwrap_or_default()
+    }
+
+    /// Returns the calls active at the instruction being run, innermost first.
+    fn stack_trace(&self) -> StackTrace {
+        let frames = self.frames.iter().enumerate().rev().map(|(i, frame)| {
+            // The frame of the program itself is not a call
+            evaluator::Frame {
+                function: (i > 0).then(|| frame.name.clone()),
+                file: frame.file.as_deref().map(Path::to_path_buf),
+                row: frame.chunk.row(frame.ip.saturating_sub(1)),
+            }
+        });
+        StackTrace::new(frames.collect())
+    }
+
+    /// Remembers the calls active when an error happened, unless an inner call already did.
+    fn unwind(&mut self) {
+        if self.unwound.is_none() {
+            self.unwound = Some(self.stack_trace());
+        }
+    }
+
+    /// Returns the number of instructions the last run ran.
+    pub fn steps(&self) -> u64 {
+        self.budget.steps()
+    }
+
+    /// Sets the flag that stops runs when it is set.
+    ///
+    /// See [`Evaluator::set_interrupt`](super::evaluator::Evaluator::set_interrupt).
+    ///
+    /// # Arguments
+    ///
+    /// * `interrupt` - The flag, or None to stop watching one.
+    pub fn set_interrupt(&mut self, interrupt: Option<Arc<AtomicBool>>) {
+        self.budget.set_interrupt(interrupt);
+    }
+
+    /// Runs a compiled program.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - The compiled program.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Value, RuntimeError>` - The value of the last statement, or the error that
+    ///   stopped the program.
+    pub fn run(&mut self, chunk: Chunk) -> Result<Value, RuntimeError> {
+        self.budget.restart();
+        self.unwound = None;
+        self.frames.push(Frame {
+            chunk: Rc::new(chunk),
+            ip: 0,
+            base: 0,
+            caller: Rc::clone(&self.globals),
+            name: String::new(),
+            file: self.file.clone(),
+            returns: Vec::new(),
+        });
+
+        loop {
+            match self.step() {
+                Ok(Some(value)) => return Ok(value),
+                Ok(None) => {}
+                Err(error) => {
+                    if !self.catch(&error, 0) {
+                        self.unwind();
+                        self.stack.clear();
+                        self.frames.clear();
+                        self.environment = Rc::clone(&self.globals);
+                        return Err(error);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Runs the next instruction.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<Value>, RuntimeError>` - The value of the program if it ended, None if
+    ///   it goes on, or the error raised by the instruction.
+    fn step(&mut self) -> Result<Option<Value>, RuntimeError> {
+        self.budget.tick()?;
+        let frame = self.frame();
+        let chunk = Rc::clone(&frame.chunk);
+        let op = chunk.code[frame.ip];
+        frame.ip += 1;
+        if let Some(trace) = &mut self.trace {
+            let _ = writeln!(trace, "{}{:?}", "  ".repeat(self.frames.len() - 1), op);
+        }
+
+        match op {
+            Op::Constant(constant) => self.stack.push(chunk.constants[constant].clone()),
+            Op::Pop => {
+                self.pop();
+            }
+            Op::Drop(count) => self.stack.truncate(self.stack.len() - count),
+            Op::Copy(count) => {
+                let start = self.stack.len() - count;
+                self.stack.extend_from_within(start..);
+            }
+
+            // A name refers to the closest variable, or function used as a value
+            Op::Get(name) => {
+                let name = &chunk.names[name];
+                let value = self.environment.borrow().lookup(name);
+                match value {
+                    Some(value) => self.stack.push(value),
+                    None => return Err(RuntimeError::UndefinedVariable(name.clone())),
+                }
+            }
+            Op::Define(name) => {
+                let value = self.pop();
+                self.environment
+                    .borrow_mut()
+                    .define(&chunk.names[name], value);
+            }
+            Op::Assign(name) => {
+                let name = &chunk.names[name];
+                let value = self.pop();
+                let assigned = self.environment.borrow_mut().assign(name, value.clone());
+                if !assigned {
+                    self.environment.borrow_mut().define(name, value);
+                }
+            }
+            Op::Unpack(pattern) => {
+                let value = self.pop();
+                let values = destructure(&chunk.patterns[pattern], &value)?;
+                self.stack.extend(values);
+            }
+            Op::Require(name) => {
+                let name = &chunk.names[name];
+                if !self.environment.borrow().assignable(name) {
+                    return Err(RuntimeError::UndefinedAssignment(name.clone()));
+                }
+            }
+            Op::Global(name) => {
+                self.environment
+                    .borrow_mut()
+                    .declare_global(&chunk.names[name]);
+            }
+            Op::Nonlocal(name) => {
+                let name = &chunk.names[name];
+                if !self.environment.borrow_mut().declare_nonlocal(name) {
+                    return Err(RuntimeError::UndefinedVariable(name.clone()));
+                }
+            }
+            Op::Function(function) => {
+                let function = self.closure(&chunk, function);
+                self.environment.borrow_mut().define_function(function);
+            }
+            Op::Closure(function) => {
+                let function = self.closure(&chunk, function);
+                self.stack.push(Value::Function(Rc::new(function)));
+            }
+            Op::Struct(definition) => {
+                let definition = &chunk.structs[definition];
+                self.environment.borrow_mut().define_struct(Struct {
+                    name: definition.name.clone(),
+                    fields: definition.fields.clone(),
+                    methods: Default::default(),
+                    interfaces: Default::default(),
+                    doc: definition.doc.as_deref().map(Rc::from),
+                });
+            }
+            Op::Interface(definition) => {
+                let definition = &chunk.interfaces[definition];
+                self.environment.borrow_mut().define_interface(Interface {
+                    name: definition.name.clone(),
+                    signatures: definition.signatures.clone(),
+                });
+            }
+            Op::Implement(interface, ty, count) => {
+                let functions = self
+                    .stack
+                    .split_off(self.stack.len() - count)
+                    .into_iter()
+                    .map(|function| match function {
+                        Value::Function(function) => function,
+                        _ => unreachable!("an `impl` block pushes functions"),
+                    })
+                    .collect();
+                implement(
+                    &self.environment,
+                    &chunk.names[interface],
+                    &chunk.names[ty],
+                    functions,
+                )?;
+            }
+
+            Op::CheckType(ty) => check_type(ty, self.peek())?,
+            Op::Cast(ty) => {
+                let value = self.pop();
+                self.stack.push(cast(value, ty)?);
+            }
+            Op::Unary(op) => {
+                let value = self.pop();
+                let value = self.arithmetic.unary(&chunk.names[op], value)?;
+                self.stack.push(value);
+            }
+            Op::Binary(op) => {
+                let right = self.pop();
+                let left = self.pop();
+                let value = self.arithmetic.binary(&chunk.names[op], left, right)?;
+                self.stack.push(limited(value, self.max_length)?);
+            }
+            Op::Test => {
+                let value = self.pop();
+                self.stack.push(Value::Boolean(truthy(value)?));
+            }
+
+            Op::Jump(target) => self.frame().ip = target,
+            Op::JumpIfFalse(target) => {
+                if !truthy(self.pop())? {
+                    self.frame().ip = target;
+                }
+            }
+
+            Op::Array(count) => {
+                let elements = self.stack.split_off(self.stack.len() - count);
+                self.stack.push(Value::array(elements));
+            }
+            Op::Range(inclusive) => {
+                let end = self.pop();
+                let start = self.pop();
+                self.stack.push(range(&start, &end, inclusive)?);
+            }
+            Op::Index => {
+                let index = self.pop();
+                let target = self.pop();
+                self.stack.push(element(&target, &index)?);
+            }
+            Op::Store => {
+                let value = self.pop();
+                let index = self.pop();
+                let target = self.pop();
+                store(&target, index, value)?;
+            }
+
+            // A loop keeps the elements it iterates over, or none for numbers and ranges,
+            // followed by the index of the next element and the index of the last one
+            Op::Iterate => {
+                let (elements, start, last) = match self.pop() {
+                    Value::Integer(count) => (Value::None, 0, count.saturating_sub(1)),
+                    Value::Range(start, end, inclusive) => {
+                        let numbers = numbers(start, end, inclusive);
+                        (Value::None, *numbers.start(), *numbers.end())
+                    }
+                    // The loop sees the elements the array holds when it starts
+                    Value::Array(elements) => {
+                        let elements = elements.borrow().clone();
+                        let last = elements.len() as i64 - 1;
+                        (Value::array(elements), 0, last)
+                    }
+                    Value::String(string) => {
+                        let elements: Vec<Value> =
+                            string.chars().map(|c| Value::from(c.to_string())).collect();
+                        let last = elements.len() as i64 - 1;
+                        (Value::array(elements), 0, last)
+                    }
+                    value => {
+                        return Err(RuntimeError::TypeMismatch(
+                            "iterable".to_string(),
+                            value.type_name().to_string(),
+                        ))
+                    }
+                };
+                self.stack.push(elements);
+                self.stack.push(Value::Integer(start));
+                self.stack.push(Value::Integer(last));
+            }
+            Op::Next(target) => {
+                let top = self.stack.len();
+                match (&self.stack[top - 2], &self.stack[top - 1]) {
+                    (Value::Integer(index), Value::Integer(last)) if index <= last => {
+                        let index = *index;
+                        let element = match &self.stack[top - 3] {
+                            Value::Array(elements) => elements.borrow()[index as usize].clone(),
+                            _ => Value::Integer(index),
+                        };
+                        // The index is none after the loop reaches the largest whole number
+                        self.stack[top - 2] =
+                            index.checked_add(1).map_or(Value::None, Value::Integer);
+                        self.stack.push(element);
+                    }
+                    _ => self.frame().ip = target,
+                }
+            }
+
+            Op::PushScope => {
+                self.environment = Environment::with_parent(&self.environment);
+            }
+            Op::PopScope(count) => {
+                for _ in 0..count {
+                    let parent = self.environment.borrow().parent();
+                    self.environment = parent.expect("only entered scopes are left");
+                }
+            }
+
+            Op::Try(target) => self.handlers.push(Handler {
+                target,
+                frames: self.frames.len(),
+                stack: self.stack.len(),
+                environment: Rc::clone(&self.environment),
+            }),
+            Op::EndTry(count) => self.handlers.truncate(self.handlers.len() - count),
+
+            Op::Call(name, count) => {
+                let name = &chunk.names[name];
+                let args = self.stack.split_off(self.stack.len() - count);
+                match self.function(name) {
+                    Some(function) => self.enter(&function, args)?,
+                    None => {
+                        let value = self.call_native(name, &args)?;
+                        self.stack.push(value);
+                    }
+                }
+            }
+            Op::TailCall(name, count) => {
+                let name = &chunk.names[name];
+                let args = self.stack.split_off(self.stack.len() - count);
+                match self.function(name) {
+                    Some(function) => self.replace(&function, args)?,
+                    None => {
+                        let value = self.call_native(name, &args)?;
+                        return self.leave(value);
+                    }
+                }
+            }
+            // The value is given to the function of its struct as the first argument
+            Op::Method(name, count) => {
+                let args = self.stack.split_off(self.stack.len() - count - 1);
+                let function = method(&args[0], &chunk.names[name])?;
+                self.enter(&function, args)?;
+            }
+            Op::TailMethod(name, count) => {
+                let args = self.stack.split_off(self.stack.len() - count - 1);
+                let function = method(&args[0], &chunk.names[name])?;
+                self.replace(&function, args)?;
+            }
+            Op::Return => {
+                let value = self.pop();
+                return self.leave(value);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the frame being run.
+    fn frame(&mut self) -> &mut Frame {
+        self.frames.last_mut().expect("a program is being run")
+    }
+
+    /// Pops the top value of the stack.
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("the compiler balances the stack")
+    }
+
+    /// Returns the top value of the stack.
+    fn peek(&self) -> &Value {
+        self.stack.last().expect("the compiler balances the stack")
+    }
+
+    /// Creates a function of a chunk that captures the current environment.
+    fn closure(&self, chunk: &Chunk, function: usize) -> Function {
+        let prototype = &chunk.functions[function];
+        Function {
+            name: prototype.name.clone(),
+            parameters: prototype.parameters.clone(),
+            ret: prototype.ret,
+            body: prototype.body.clone(),
+            code: Some(Rc::clone(&prototype.code)),
+            closure: Rc::clone(&self.environment),
+            file: self.frames.last().and_then(|frame| frame.file.clone()),
+            doc: prototype.doc.clone(),
+        }
+    }
+
+    /// Creates a value of the struct with the given name, or calls the native function or builtin
+    /// with that name.
+    fn call_native(&mut self, name: &str, args: &[Value]) -> Result<Value, RuntimeError> {
+        if let Some(definition) = self.environment.borrow().get_struct(name) {
+            return construct(&definition, args.to_vec());
+        }
+        match self.natives.get(name) {
+            Some(native) => native(args),
+            None => match builtins::lookup(name) {
+                Some(builtin) => builtin(self, args),
+                None => Err(RuntimeError::UndefinedFunction(name.to_string())),
+            },
+        }
+    }
+
+    /// Binds the arguments of a call to the parameters of a function.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(Rc<Chunk>, Env), RuntimeError>` - The code of the function and the
+    ///   environment it runs in.
+    fn bind(function: &Function, args: Vec<Value>) -> Result<(Rc<Chunk>, Env), RuntimeError> {
+        if function.parameters.len() != args.len() {
+            return Err(RuntimeError::ArgumentCount(
+                function.name.clone(),
+                function.parameters.len(),
+                args.len(),
+            ));
+        }
+
+        // Functions created by the evaluator are compiled when they are first called here
+        let code = match &function.code {
+            Some(code) => Rc::clone(code),
+            None => Rc::new(compile_body(&function.body)?),
+        };
+
+        let environment = Environment::with_caller(&function.closure);
+        bind(&environment, &function.parameters, args)?;
+        Ok((code, environment))
+    }
+
+    /// Starts running a function in a new frame.
+    fn enter(&mut self, function: &Function, args: Vec<Value>) -> Result<(), RuntimeError> {
+        let (chunk, environment) = Self::bind(function, args)?;
+
+        // The frame of the program itself does not count
+        if self.frames.len() > self.max_depth {
+            return Err(RuntimeError::RecursionLimit(self.max_depth));
+        }
+
+        let caller = std::mem::replace(&mut self.environment, environment);
+        self.frames.push(Frame {
+            chunk,
+            ip: 0,
+            base: self.stack.len(),
+            caller,
+            name: function.name.clone(),
+            file: function.file.clone(),
+            returns: function.ret.into_iter().collect(),
+        });
+        Ok(())
+    }
+
+    /// Runs a function in place of the function being run.
+    fn replace(&mut self, function: &Function, args: Vec<Value>) -> Result<(), RuntimeError> {
+        let (chunk, environment) = Self::bind(function, args)?;
+        self.environment = environment;
+
+        let frame = self.frames.last_mut().expect("a function is being run");
+        frame.chunk = chunk;
+        frame.ip = 0;
+        frame.name = function.name.clone();
+        frame.file = function.file.clone();
+        if let Some(ret) = function.ret {
+            if !frame.returns.contains(&ret) {
+                frame.returns.push(ret);
+            }
+        }
+
+        let base = frame.base;
+        self.stack.truncate(base);
+        Ok(())
+    }
+
+    /// Leaves the frame being run with its value.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<Value>, RuntimeError>` - The value if it is the value of the program,
+    ///   None if the caller goes on, or the error for a value of the wrong type.
+    fn leave(&mut self, value: Value) -> Result<Option<Value>, RuntimeError> {
+        let frame = self.frames.pop().expect("a frame is being run");
+        self.stack.truncate(frame.base);
+        self.environment = frame.caller;
+        while self
+            .handlers
+            .last()
+            .is_some_and(|handler| handler.frames > self.frames.len())
+        {
+            self.handlers.pop();
+        }
+
+        for ret in frame.returns {
+            check_type(ret, &value)?;
+        }
+
+        if self.frames.is_empty() {
+            return Ok(Some(value));
+        }
+        self.stack.push(value);
+        Ok(None)
+    }
+
+    /// Continues at the innermost `try` block being run, with the message of an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The error to be caught.
+    /// * `floor` - The number of `try` blocks that cannot catch the error, since they were
+    ///   started outside of the call being run.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if the error was caught, false if no `try` block can catch it or the
+    ///   error cannot be caught.
+    fn catch(&mut self, error: &RuntimeError, floor: usize) -> bool {
+        if self.handlers.len() <= floor || !error.catchable() {
+            return false;
+        }
+        let handler = self.handlers.pop().expect("a handler is above the floor");
+        self.unwound = None;
+
+        self.frames.truncate(handler.frames);
+        self.stack.truncate(handler.stack);
+        self.environment = handler.environment;
+        self.stack.push(Value::from(error.to_string()));
+        self.frame().ip = handler.target;
+        true
+    }
+}
+
+// A builtin calling a function runs it to its end before the builtin goes on
+impl Caller for Vm {
+    fn call_value(&mut self, function: &Value, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        let Value::Function(function) = function else {
+            return Err(RuntimeError::TypeMismatch(
+                "fn".to_string(),
+                function.type_name().to_string(),
+            ));
+        };
+
+        let frames = self.frames.len();
+        let handlers = self.handlers.len();
+        let stack = self.stack.len();
+        let environment = Rc::clone(&self.environment);
+        self.enter(function, args)?;
+
+        loop {
+            match self.step() {
+                Ok(Some(value)) => return Ok(value),
+                Ok(None) if self.frames.len() == frames => return Ok(self.pop()),
+                Ok(None) => {}
+                Err(error) => {
+                    if !self.catch(&error, handlers) {
+                        self.unwind();
+                        self.frames.truncate(frames);
+                        self.handlers.truncate(handlers);
+                        self.stack.truncate(stack);
+                        self.environment = environment;
+                        return Err(error);
+                    }
+                }
+            }
+        }
+    }
+
+    fn function(&self, name: &str) -> Option<Rc<Function>> {
+        match self.environment.borrow().lookup(name) {
+            Some(Value::Function(function)) => Some(function),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hash::{compile::compile, evaluator::Evaluator, parser::Parser};
+
+    #[test]
+    fn test_vm() {
+        let programs = [
+            "1 + 2 * 3",
+            "x = 1\nwhile x < 100 { x = x * 2 }\nx",
+            "total = 0\nfor i in 0..10 { if i == 3 { continue }\nif i == 7 { break }\ntotal = total + i }\ntotal",
+            "s = \"\"\nfor c in \"abc\" { s = c + s }\ns",
+            "n = 0\nfor x in [1, [2], 3] { n = n + 1 }\nfor i in 4 { n = n + i }\nn",
+            "f(n) { if n == 0 { return 0 }\n1 + f(n - 1) }\nf(10)",
+            "count(n, total) { if n == 0 { total } else { count(n - 1, total + n) } }\ncount(100000, 0)",
+            "scale(k) { fn(x: num): num { x * k } }\ntriple = scale(3)\ntriple(4)",
+            "true && 1 < 2 || false",
+            "false || !true",
+            "1 && true",
+            "x: num = \"a\"",
+            "1 / 0",
+            "try { 1 / 0 } catch e { \"caught \" + e }",
+            "f() { try { return g() } catch e { e } }\ng() { 1 / 0 }\nf()",
+            "for i in 0..3 { try { if i == 1 { break }\n1 / 0 } catch e { i } }",
+            "deep(n) { 1 + deep(n + 1) }\ntry { deep(0) } catch e { \"caught\" }",
+            "h(): num { \"a\" }\nh()",
+            "[0..3, 1 in 0..=1, 3 in 0..3]",
+            "f() { 1 }\ng(f) { f() * 10 + 1 }\ng(fn() { 2 })",
+            "s = 0\nfor i in 2..=4 { s += i }\ns",
+            "n = 1\nf() { n = 2 }\ng() { global n\nn += 3 }\nf()\ng()\nn",
+            "h() { m += 1 }\nm = 0\nh()",
+            "counter() { c = 0\nfn() { nonlocal c\nc += 1\nc } }\ninc = counter()\n[inc(), inc()]",
+            "c = 0\nbump() { nonlocal c\nc = 1 }\nbump()",
+            "x as str + (\"1\" as num) as str",
+            "1 in [1, 2] && \"b\" in \"abc\"",
+            "if false { 1 }",
+            "{ y = 1 }\ny",
+            "clone([1, [2]])",
+            "missing(1)",
+            "try { exit(3) } catch e { 0 }",
+            "xs = [3, 1, 2]\npush(xs, 0)\nsort(xs)\n[pop(xs), len(xs), contains(xs, 1), reverse(xs)]",
+            "reduce(map(filter(0..10, fn(x) { x > 4 }), fn(x) { x * x }), fn(a, b) { a + b })",
+            "map([1, 0], fn(x) { try { 1 / x } catch e { e } })",
+            "struct P { x: num, y }\ninterface Shape {\n    area(self): num\n}\nimpl Shape for P {\n    area(self): num { self.x * self.y }\n}\np = P(2, 3)\np.y = 4\n[p.area(), typeof(p), p == P(2, 4)]",
+            "struct N { v: num }\nimpl Compare for N {\n    compare(self, other): num { self.v - other.v }\n}\nxs = [N(3), N(1), N(2)]\nsort(xs)\nmap(xs, fn(n) { n.v })",
+            "struct C { n }\ninterface Step {\n    down(self, k)\n}\nimpl Step for C {\n    down(self, k) { if k == 0 { self.n } else { self.down(k - 1) } }\n}\nC(7).down(100000)",
+            "struct P { x }\nP(1).y",
+            "struct P { x }\nP(1).go()",
+            "try { map([1, 0], fn(x) { 1 / x }) } catch e { \"caught \" + e }",
+            "f(x) { map([x], fn(y) { return y + 1 }) }\nf(1)",
+            "deep(n) { map([n], fn(x) { deep(x + 1) }) }\ndeep(0)",
+            "x = 1\nf() { x += 10\nx *= 2 }\nf()\nx",
+            "total += 1",
+            "[-7 % 3, 7 / 2 % -2, -2 ^ 2, 2 ^ -1, 2 ^ 64]",
+            "[[1, 2] < [1, 3], \"b\" > \"a\", 1 == \"1\", [1] == [1]]",
+            "true < false",
+            "max = 9223372036854775807\n[max, -max - 1, try { max + 1 } catch e { e }]",
+            "[a, b] = [1, [2]]\nswap([x, y]) { [y, x] }\n[b, a] = swap([a, b])\n[a, b]",
+            "[a, b] = [1]",
+            "f({x}) { x }\nf(1)",
+            "p = json_parse(\"{}\")\np.x = 1\nitems = [p, [2]]\nitems[0].x += items[1][0]\nitems[1][0] *= 5\n[p, items]",
+            "items = [1]\nitems[1] = 2",
+            "(0..3)[3]",
+            "max = 9223372036854775807\nn = 0\nfor i in max - 1..=max { n += i - max }\nfor i in max..max { n += 10 }\n[n, 0..=max, (max..=max)[0], -max - 1..-max - 1]",
+            "xs = [1, 2, 3]\n[xs[1..5], xs[2..=2], \"abc\"[1..3], xs[5..9]]",
+            "p = json_parse(\"{}\")\np.x += 1",
+            "x = [1]\npush(x, x)\nm = json_parse(\"{}\")\nset(m, \"m\", m)\n[to_str(x), to_str(m), x == [1, x]]",
+            "x = [1]\npush(x, x)\njson_encode(x)",
+        ];
+
+        for program in programs {
+            let tree = Parser::new(program).parse_program().unwrap();
+
+            let mut evaluator = Evaluator::new();
+            evaluator.set_max_depth(20);
+            let mut expected = Ok(Value::None);
+            for statement in &tree {
+                expected = evaluator.evaluate(statement);
+                if expected.is_err() {
+                    break;
+                }
+            }
+
+            let mut vm = Vm::new();
+            vm.set_max_depth(20);
+            assert_eq!(vm.run(compile(&tree).unwrap()), expected, "{}", program);
+            assert!(vm.stack.is_empty() && vm.frames.is_empty(), "{}", program);
+        }
+    }
+}
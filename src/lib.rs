@@ -0,0 +1,63 @@
+//! Hydrogen is a simple programming language with a REPL (Read-Eval-Print Loop) for interactive use.
+//!
+//! The crate exposes the language pipeline so it can be embedded in other Rust projects:
+//!
+//! - [`hash::lexer`] turns source code into [`hash::tokens::Token`]s.
+//! - [`hash::parser`] turns tokens into an abstract syntax tree made of [`hash::ast::ASTNode`]s.
+//! - [`hash::evaluator`] validates and evaluates the tree.
+//! - [`hash::print`] renders trees and errors for inspection.
+//!
+//! Applications that only want to run Hydrogen code can use the [`Engine`] instead, and the
+//! interactive prompt used by the `hydrogen` binary lives in [`repl`].
+//!
+//! # Examples
+//!
+//! ```
+//! use hydrogen::hash::{ast::Stmt, parser::Parser};
+//!
+//! let mut parser = Parser::new("var1: num = 1234");
+//! let statement = parser.parse().unwrap();
+//!
+//! assert!(matches!(statement, Some(Stmt::Definition(_))));
+//! ```
+//!
+//! ```
+//! use hydrogen::{Engine, Value};
+//!
+//! let mut engine = Engine::new();
+//! engine.eval_str("greeting = \"Hello\"").unwrap();
+//!
+//! let value = engine.eval_str("greeting + \", World!\"").unwrap();
+//! assert_eq!(value, Value::from("Hello, World!"));
+//! ```
+
+#![warn(missing_docs)]
+
+/// Module containing the lexer, parser, and evaluator of the language.
+pub mod hash;
+/// Module containing the interactive Read-Eval-Print Loop.
+pub mod repl;
+
+/// Module containing the runner of the tests written in Hydrogen.
+pub mod testing;
+
+/// Module containing the language server editors talk to.
+pub mod lsp;
+
+/// Module containing the configuration file of the REPL and the interpreter.
+pub mod config;
+
+/// Module containing the colors of the output, which can be turned off.
+pub mod style;
+
+/// Module containing the generator of the documentation of scripts, from their doc comments.
+pub mod doc;
+
+/// Module containing the watcher of the files of a script, to run it again when they change.
+pub mod watch;
+
+/// Module containing the high-level embedding API.
+mod engine;
+
+pub use engine::{Backend, Engine, HydrogenError};
+pub use hash::value::Value;
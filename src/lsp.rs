@@ -0,0 +1,968 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, BufRead, Write},
+};
+
+use crate::hash::{
+    ast::{ASTError, Item, Stmt},
+    checker::Checker,
+    json,
+    lexer::Lexer,
+    parser::{Edit, Program},
+    resolve::{Definition, Names, RenameError},
+    tokens::{text_width, Position, Token, TAB_WIDTH},
+    value::Value,
+};
+
+/// The severity the protocol gives to errors.
+const ERROR: i64 = 1;
+
+/// The error code of the protocol for messages that are not valid JSON.
+const PARSE_ERROR: i64 = -32700;
+
+/// The error code of the protocol for requests the server does not support.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// The error code of the protocol for requests that are valid but cannot be done.
+const REQUEST_FAILED: i64 = -32803;
+
+/// Enum representing the kinds of names a document defines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    /// A function
+    Function,
+    /// A variable
+    Variable,
+    /// A constant
+    Constant,
+    /// A struct
+    Struct,
+    /// An interface
+    Interface,
+}
+
+impl SymbolKind {
+    /// Returns the number the protocol uses for the kind.
+    fn code(self) -> i64 {
+        match self {
+            SymbolKind::Function => 12,
+            SymbolKind::Variable => 13,
+            SymbolKind::Constant => 14,
+            SymbolKind::Struct => 23,
+            SymbolKind::Interface => 11,
+        }
+    }
+}
+
+/// A name defined at the top level of a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// The name.
+    pub name: String,
+    /// The kind of the name.
+    pub kind: SymbolKind,
+    /// The signature of a function, or the name of a variable with its type if it is known.
+    pub detail: String,
+    /// The position where the statement defining the name starts.
+    pub start: Position,
+    /// The position right after the statement defining the name.
+    pub end: Position,
+}
+
+/// A problem found in a document, by the parser or by the checker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// The position of the problem.
+    pub position: Position,
+    /// The description of the problem.
+    pub message: String,
+}
+
+/// What the server knows about a document, found again every time the document changes.
+///
+/// # Examples
+/// ```
+/// use hydrogen::lsp::Analysis;
+///
+/// let analysis = Analysis::new("square(x: num): num { x * x }\nsquare(\"two\")");
+///
+/// assert_eq!(analysis.symbols[0].detail, "square(x: num): num");
+/// assert_eq!(analysis.problems[0].position.row, 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    /// The problems found, in the order of the statements they are in.
+    pub problems: Vec<Problem>,
+    /// The names defined at the top level, in order, each one where it is first defined.
+    pub symbols: Vec<Symbol>,
+    /// Where every name is defined and used.
+    pub names: Names,
+}
+
+impl Analysis {
+    /// Parses and checks a document.
+    ///
+    /// The statements that parse are checked even when others do not, so mistakes show up while
+    /// the document is being edited.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The text of the document.
+    pub fn new(source: &str) -> Self {
+        let program = Program::new(source);
+        let mut problems = Vec::new();
+        for parsed in &program.statements {
+            if let Err(error) = &parsed.statement {
+                parse_problems(error, &parsed.start, &mut problems);
+            }
+        }
+        // A group reports the same error once for every way it failed to parse
+        problems.dedup();
+
+        let statements: Vec<_> = program
+            .statements
+            .iter()
+            .filter_map(|parsed| {
+                Some((&parsed.start, &parsed.end, parsed.statement.as_ref().ok()?))
+            })
+            .collect();
+        let mut checker = Checker::new();
+        for (start, _, statement) in &statements {
+            for error in checker.check(statement) {
+                problems.push(Problem {
+                    position: (*start).clone(),
+                    message: error.to_string(),
+                });
+            }
+        }
+
+        let mut symbols: Vec<Symbol> = Vec::new();
+        for (start, end, statement) in statements {
+            let Some((name, kind, detail)) = describe(&checker, statement) else {
+                continue;
+            };
+            // Later assignments give the variable another value instead of defining it again
+            if symbols.iter().any(|symbol| symbol.name == name) {
+                continue;
+            }
+            symbols.push(Symbol {
+                name,
+                kind,
+                detail,
+                start: start.clone(),
+                end: end.clone(),
+            });
+        }
+
+        let names = Names::new(source, &program.statements);
+        Self {
+            problems,
+            symbols,
+            names,
+        }
+    }
+
+    /// Returns the definition of the name at a position of the document, whether the name is
+    /// defined or used there.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The row of the position, starting at 1.
+    /// * `col` - The column of the position, starting at 1.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&Definition>` - The definition, or None if no name the document defines is at
+    ///   the position.
+    pub fn definition_at(&self, row: usize, col: usize) -> Option<&Definition> {
+        let index = self.names.definition_at(row, col)?;
+        Some(&self.names.definitions[index])
+    }
+
+    /// Returns the positions where a definition of the document is used, in order.
+    ///
+    /// # Arguments
+    ///
+    /// * `definition` - The definition, as returned by [`Analysis::definition_at`].
+    pub fn references_of(&self, definition: &Definition) -> Vec<Position> {
+        let index = self.names.definitions.iter().position(|d| d == definition);
+        index.map_or(Vec::new(), |index| self.names.references_of(index))
+    }
+
+    /// Returns the edits renaming the name under a position of the document everywhere it is
+    /// defined and used.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The text of the document.
+    /// * `row` - The row of the position, starting at 1.
+    /// * `col` - The column of the position, starting at 1.
+    /// * `name` - The new name.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Edit>, RenameError>` - The edits, or the reason the name cannot be renamed.
+    pub fn rename(
+        &self,
+        source: &str,
+        row: usize,
+        col: usize,
+        name: &str,
+    ) -> Result<Vec<Edit>, RenameError> {
+        self.names.rename(source, row, col, name)
+    }
+
+    /// Finds the name under a position of the document, if the document defines it.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The text of the document.
+    /// * `row` - The row of the position, starting at 1.
+    /// * `col` - The column of the position, starting at 1.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<(&Symbol, Position)>` - The symbol, and the position where the name under the
+    ///   position starts.
+    pub fn symbol_at(&self, source: &str, row: usize, col: usize) -> Option<(&Symbol, Position)> {
+        let (position, name) = Lexer::new(source).find_map(|token| match token {
+            Token::Identifier(position, name)
+                if position.row == row
+                    && (position.col..position.col + text_width(&name)).contains(&col) =>
+            {
+                Some((position, name))
+            }
+            _ => None,
+        })?;
+        let symbol = self.symbols.iter().find(|symbol| symbol.name == name)?;
+        Some((symbol, position))
+    }
+}
+
+/// Adds the problems of a parse error, placed at the token it is about if it has one, or at the
+/// start of its statement.
+fn parse_problems(error: &ASTError, start: &Position, problems: &mut Vec<Problem>) {
+    let position = match error {
+        ASTError::Errors(errors) => {
+            errors
+                .iter()
+                .for_each(|error| parse_problems(error, start, problems));
+            return;
+        }
+        ASTError::UnknownToken(token)
+        | ASTError::UnexpectedToken(token)
+        | ASTError::MisplacedKeyword(token)
+        | ASTError::TooDeep(token)
+        | ASTError::MissingSeparator(token)
+        | ASTError::Unterminated(token) => token.position().clone(),
+        ASTError::UnexpectedStatement(_)
+        | ASTError::InvalidParameter(_)
+        | ASTError::UninitializedConstant(_)
+        | ASTError::InvalidAssignment(_)
+        | ASTError::InvalidImplementation(_) => start.clone(),
+    };
+    // The tokens alone do not say what is wrong with them
+    let message = match error {
+        ASTError::UnknownToken(token) => format!("unknown token `{}`", token),
+        ASTError::UnexpectedToken(token) => format!("unexpected `{}`", token),
+        _ => match error.to_string().split_once(": ") {
+            Some((_, message)) => message.to_string(),
+            None => error.to_string(),
+        },
+    };
+    problems.push(Problem { position, message });
+}
+
+/// Returns the name a statement defines, its kind, and its detail, if it defines one.
+fn describe(checker: &Checker, statement: &Stmt) -> Option<(String, SymbolKind, String)> {
+    let typed = |name: &str, ty: Option<_>| match ty {
+        Some(ty) => format!("{}: {}", name, ty),
+        None => name.to_string(),
+    };
+    match statement {
+        Stmt::Item(Item::Function(definition)) => {
+            let parameters: Vec<_> = definition
+                .parameters
+                .iter()
+                .map(|parameter| typed(&parameter.name, parameter.ty))
+                .collect();
+            let signature = typed(
+                &format!("{}({})", definition.name, parameters.join(", ")),
+                definition.ret,
+            );
+            Some((definition.name.clone(), SymbolKind::Function, signature))
+        }
+        Stmt::Item(Item::Struct(definition)) => {
+            let fields: Vec<_> = definition
+                .fields
+                .iter()
+                .map(|field| typed(&field.name, field.ty))
+                .collect();
+            let detail = format!("struct {}({})", definition.name, fields.join(", "));
+            Some((definition.name.clone(), SymbolKind::Struct, detail))
+        }
+        Stmt::Item(Item::Interface(definition)) => Some((
+            definition.name.clone(),
+            SymbolKind::Interface,
+            format!("interface {}", definition.name),
+        )),
+        Stmt::Definition(definition) => {
+            let ty = definition.ty.or_else(|| checker.infer(&definition.value));
+            let (kind, detail) = match definition.constant {
+                true => (
+                    SymbolKind::Constant,
+                    format!("const {}", typed(&definition.name, ty)),
+                ),
+                false => (SymbolKind::Variable, typed(&definition.name, ty)),
+            };
+            Some((definition.name.clone(), kind, detail))
+        }
+        Stmt::Declaration(declaration) => Some((
+            declaration.name.clone(),
+            SymbolKind::Variable,
+            typed(&declaration.name, Some(declaration.ty)),
+        )),
+        _ => None,
+    }
+}
+
+/// Returns the field of a JSON object with the given name, or none if there is no such field.
+fn field(value: &Value, name: &str) -> Value {
+    match value {
+        Value::Map(entries) => entries.borrow().get(name).cloned().unwrap_or(Value::None),
+        _ => Value::None,
+    }
+}
+
+/// Returns the text of a JSON string, or an empty string if the value is not one.
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Returns the number of a JSON number, or 0 if the value is not one.
+fn number(value: &Value) -> usize {
+    match value {
+        Value::Integer(number) => usize::try_from(*number).unwrap_or_default(),
+        _ => 0,
+    }
+}
+
+/// Builds a JSON object from its fields.
+fn object<const N: usize>(fields: [(&str, Value); N]) -> Value {
+    Value::map(
+        fields
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+    )
+}
+
+/// Builds a range of the protocol between two byte offsets of a document.
+///
+/// The protocol counts lines and characters from 0, and characters in UTF-16 code units from
+/// the start of their line, unlike the columns of positions, which are counted for terminals.
+fn range(source: &str, start: usize, end: usize) -> Value {
+    let point = |offset: usize| {
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let character = before[line_start..].encode_utf16().count();
+        object([
+            ("line", Value::Integer(before.matches('\n').count() as i64)),
+            ("character", Value::Integer(character as i64)),
+        ])
+    };
+    object([("start", point(start)), ("end", point(end))])
+}
+
+/// Returns the byte offset of a position of the protocol in a document, or the end of its line
+/// if the character is past it, see [`range`].
+fn offset(source: &str, line: usize, character: usize) -> usize {
+    let mut start = 0;
+    for _ in 0..line {
+        match source[start..].find('\n') {
+            Some(i) => start += i + 1,
+            None => return source.len(),
+        }
+    }
+    let text = &source[start..];
+    let text = text[..text.find('\n').unwrap_or(text.len())].trim_end_matches('\r');
+
+    let mut units = 0;
+    for (i, c) in text.char_indices() {
+        if units >= character {
+            return start + i;
+        }
+        units += c.len_utf16();
+    }
+    start + text.len()
+}
+
+/// Server speaking the Language Server Protocol, so editors can show the problems of Hydrogen
+/// documents, the names they define, and the types of those names.
+///
+/// Messages are read from an input and written to an output, each one after a
+/// `Content-Length` header. Documents are sent whole every time they change.
+///
+/// # Examples
+/// ```
+/// use hydrogen::lsp::Server;
+///
+/// let body = r#"{"jsonrpc": "2.0", "id": 1, "method": "shutdown"}"#;
+/// let input = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+/// let mut output = Vec::new();
+///
+/// let mut server = Server::new(input.as_bytes(), &mut output);
+/// assert!(server.run().unwrap());
+/// assert!(String::from_utf8(output).unwrap().ends_with(r#""result":null}"#));
+/// ```
+pub struct Server<R, W> {
+    input: R,
+    output: W,
+    documents: HashMap<String, String>,
+    shutdown: bool,
+}
+
+impl<R: BufRead, W: Write> Server<R, W> {
+    /// Creates a new Server without open documents.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input the messages of the editor are read from.
+    /// * `output` - The output the messages of the server are written to.
+    pub fn new(input: R, output: W) -> Self {
+        Self {
+            input,
+            output,
+            documents: HashMap::new(),
+            shutdown: false,
+        }
+    }
+
+    /// Answers the messages of the editor until it sends `exit` or closes the input.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<bool>` - Whether the editor asked the server to shut down before it
+    ///   stopped, or the error reading or writing a message.
+    pub fn run(&mut self) -> io::Result<bool> {
+        while let Some(body) = self.read()? {
+            let message = match json::parse(&body) {
+                Ok(message) => message,
+                Err(error) => {
+                    self.fail(Value::None, PARSE_ERROR, &error.to_string())?;
+                    continue;
+                }
+            };
+            let id = field(&message, "id");
+            let params = field(&message, "params");
+            let document = field(&params, "textDocument");
+            let uri = text(&field(&document, "uri"));
+
+            match text(&field(&message, "method")).as_str() {
+                "initialize" => self.respond(id, capabilities())?,
+                "shutdown" => {
+                    self.shutdown = true;
+                    self.respond(id, Value::None)?;
+                }
+                "exit" => return Ok(self.shutdown),
+
+                "textDocument/didOpen" => self.update(uri, text(&field(&document, "text")))?,
+                "textDocument/didChange" => {
+                    // The whole document is sent, so only the last change matters
+                    let changes = field(&params, "contentChanges");
+                    let Value::Array(changes) = changes else {
+                        continue;
+                    };
+                    let last = changes.borrow().last().cloned().unwrap_or(Value::None);
+                    self.update(uri, text(&field(&last, "text")))?;
+                }
+                "textDocument/didClose" => {
+                    self.documents.remove(&uri);
+                    self.publish(&uri, Vec::new())?;
+                }
+
+                "textDocument/documentSymbol" => {
+                    let symbols = self.documents.get(&uri).map_or(Vec::new(), |source| {
+                        let analysis = Analysis::new(source);
+                        analysis.symbols.iter().map(|s| symbol(source, s)).collect()
+                    });
+                    self.respond(id, Value::array(symbols))?;
+                }
+                "textDocument/definition" => {
+                    let location = match self.documents.get(&uri) {
+                        Some(source) => {
+                            let (row, col) = cursor(source, &params);
+                            let analysis = Analysis::new(source);
+                            analysis
+                                .definition_at(row, col)
+                                .map_or(Value::None, |definition| {
+                                    location(source, &uri, &definition.position, &definition.name)
+                                })
+                        }
+                        None => Value::None,
+                    };
+                    self.respond(id, location)?;
+                }
+                "textDocument/references" => {
+                    let declaration = field(&field(&params, "context"), "includeDeclaration");
+                    let locations = match self.documents.get(&uri) {
+                        Some(source) => {
+                            let (row, col) = cursor(source, &params);
+                            let declaration = declaration == Value::Boolean(true);
+                            references(source, &uri, row, col, declaration)
+                        }
+                        None => Vec::new(),
+                    };
+                    self.respond(id, Value::array(locations))?;
+                }
+                "textDocument/rename" => {
+                    let name = text(&field(&params, "newName"));
+                    let renamed = match self.documents.get(&uri) {
+                        Some(source) => {
+                            let (row, col) = cursor(source, &params);
+                            rename(source, &uri, row, col, &name)
+                        }
+                        None => Err(RenameError::NoName),
+                    };
+                    match renamed {
+                        Ok(changes) => self.respond(id, changes)?,
+                        Err(error) => self.fail(id, REQUEST_FAILED, &error.to_string())?,
+                    }
+                }
+                "textDocument/hover" => {
+                    let hover = match self.documents.get(&uri) {
+                        Some(source) => {
+                            let (row, col) = cursor(source, &params);
+                            hover(source, row, col)
+                        }
+                        None => Value::None,
+                    };
+                    self.respond(id, hover)?;
+                }
+
+                // Notifications, like `initialized`, need no answer
+                _ if id == Value::None => {}
+                method => {
+                    let message = format!("unsupported method `{}`", method);
+                    self.fail(id, METHOD_NOT_FOUND, &message)?;
+                }
+            }
+        }
+        Ok(self.shutdown)
+    }
+
+    /// Reads the body of the next message, or None at the end of the input.
+    fn read(&mut self) -> io::Result<Option<String>> {
+        let mut length = None;
+        loop {
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    length = value.trim().parse::<usize>().ok();
+                }
+            }
+        }
+
+        let Some(length) = length else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "message without a Content-Length header",
+            ));
+        };
+        let mut body = vec![0; length];
+        self.input.read_exact(&mut body)?;
+        String::from_utf8(body)
+            .map(Some)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    /// Writes a message with its header.
+    fn send(&mut self, message: Value) -> io::Result<()> {
+        let body = json::encode(&message)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+        write!(
+            self.output,
+            "Content-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )?;
+        self.output.flush()
+    }
+
+    /// Answers a request.
+    fn respond(&mut self, id: Value, result: Value) -> io::Result<()> {
+        self.send(object([
+            ("jsonrpc", Value::from("2.0")),
+            ("id", id),
+            ("result", result),
+        ]))
+    }
+
+    /// Answers a request with an error.
+    fn fail(&mut self, id: Value, code: i64, message: &str) -> io::Result<()> {
+        let error = object([
+            ("code", Value::Integer(code)),
+            ("message", Value::from(message)),
+        ]);
+        self.send(object([
+            ("jsonrpc", Value::from("2.0")),
+            ("id", id),
+            ("error", error),
+        ]))
+    }
+
+    /// Keeps the new text of a document and publishes the problems found in it.
+    fn update(&mut self, uri: String, source: String) -> io::Result<()> {
+        let problems = Analysis::new(&source).problems;
+        let diagnostics = problems
+            .into_iter()
+            .map(|problem| {
+                // The problem covers the rest of its line
+                let start = problem.position.offset;
+                let rest = &source[start..];
+                let end = start
+                    + rest[..rest.find('\n').unwrap_or(rest.len())]
+                        .trim_end_matches('\r')
+                        .len();
+                object([
+                    ("range", range(&source, start, end)),
+                    ("severity", Value::Integer(ERROR)),
+                    ("source", Value::from("hydrogen")),
+                    ("message", Value::from(problem.message)),
+                ])
+            })
+            .collect();
+        self.publish(&uri, diagnostics)?;
+        self.documents.insert(uri, source);
+        Ok(())
+    }
+
+    /// Sends the diagnostics of a document, replacing the ones sent before.
+    fn publish(&mut self, uri: &str, diagnostics: Vec<Value>) -> io::Result<()> {
+        let params = object([
+            ("uri", Value::from(uri)),
+            ("diagnostics", Value::array(diagnostics)),
+        ]);
+        self.send(object([
+            ("jsonrpc", Value::from("2.0")),
+            ("method", Value::from("textDocument/publishDiagnostics")),
+            ("params", params),
+        ]))
+    }
+}
+
+/// Returns the answer to `initialize`, telling the editor what the server can do.
+fn capabilities() -> Value {
+    object([
+        (
+            "capabilities",
+            object([
+                // Documents are sent whole when they change
+                ("textDocumentSync", Value::Integer(1)),
+                ("documentSymbolProvider", Value::Boolean(true)),
+                ("hoverProvider", Value::Boolean(true)),
+                ("definitionProvider", Value::Boolean(true)),
+                ("referencesProvider", Value::Boolean(true)),
+                ("renameProvider", Value::Boolean(true)),
+            ]),
+        ),
+        (
+            "serverInfo",
+            object([
+                ("name", Value::from("hydrogen")),
+                ("version", Value::from(env!("CARGO_PKG_VERSION"))),
+            ]),
+        ),
+    ])
+}
+
+/// Returns the document symbol of the protocol for a symbol of a document.
+fn symbol(source: &str, symbol: &Symbol) -> Value {
+    let range = range(source, symbol.start.offset, symbol.end.offset);
+    object([
+        ("name", Value::from(symbol.name.as_str())),
+        ("detail", Value::from(symbol.detail.as_str())),
+        ("kind", Value::Integer(symbol.kind.code())),
+        ("range", range.clone()),
+        ("selectionRange", range),
+    ])
+}
+
+/// Returns the row and the column, starting at 1, of the position of the protocol a request
+/// about a document is about, counted the way the lexer counts them.
+fn cursor(source: &str, params: &Value) -> (usize, usize) {
+    let position = field(params, "position");
+    let line = number(&field(&position, "line"));
+    let character = number(&field(&position, "character"));
+    let position = Position::locate(source, offset(source, line, character), TAB_WIDTH);
+    (position.row, position.col)
+}
+
+/// Returns the locations of the protocol where the name under a position is used, and where it
+/// is defined if the declaration is included.
+fn references(source: &str, uri: &str, row: usize, col: usize, declaration: bool) -> Vec<Value> {
+    let analysis = Analysis::new(source);
+    let Some(definition) = analysis.definition_at(row, col) else {
+        return Vec::new();
+    };
+    let mut positions = analysis.references_of(definition);
+    if declaration {
+        positions.insert(0, definition.position.clone());
+    }
+    positions
+        .iter()
+        .map(|position| location(source, uri, position, &definition.name))
+        .collect()
+}
+
+/// Returns the workspace edit of the protocol renaming the name under a position.
+fn rename(
+    source: &str,
+    uri: &str,
+    row: usize,
+    col: usize,
+    name: &str,
+) -> Result<Value, RenameError> {
+    let edits = Analysis::new(source).rename(source, row, col, name)?;
+    let edits = edits.iter().map(|edit| {
+        object([
+            ("range", range(source, edit.range.start, edit.range.end)),
+            ("newText", Value::from(edit.text.as_str())),
+        ])
+    });
+    let changes = BTreeMap::from([(uri.to_string(), Value::array(edits.collect()))]);
+    Ok(object([("changes", Value::map(changes))]))
+}
+
+/// Returns the location of the protocol of a name in a document.
+fn location(source: &str, uri: &str, position: &Position, name: &str) -> Value {
+    let end = position.offset + name.len();
+    object([
+        ("uri", Value::from(uri)),
+        ("range", range(source, position.offset, end)),
+    ])
+}
+
+/// Returns the hover of the protocol for the name under a position, or none if there is none.
+fn hover(source: &str, row: usize, col: usize) -> Value {
+    let analysis = Analysis::new(source);
+    let Some((symbol, start)) = analysis.symbol_at(source, row, col) else {
+        return Value::None;
+    };
+    let contents = object([
+        ("kind", Value::from("markdown")),
+        (
+            "value",
+            Value::from(format!("```hydrogen\n{}\n```", symbol.detail)),
+        ),
+    ]);
+    let end = start.offset + symbol.name.len();
+    object([
+        ("contents", contents),
+        ("range", range(source, start.offset, end)),
+    ])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Frames messages the way the editor sends them.
+    fn messages(bodies: &[&str]) -> String {
+        bodies
+            .iter()
+            .map(|body| format!("Content-Length: {}\r\n\r\n{}", body.len(), body))
+            .collect()
+    }
+
+    #[test]
+    fn test_lsp() {
+        let source = "const limit = 10\ndouble(n: num): num { n * 2 }\nx = double(\"two\")\ny = (";
+        let analysis = Analysis::new(source);
+        let details: Vec<_> = analysis.symbols.iter().map(|s| s.detail.as_str()).collect();
+        assert_eq!(
+            details,
+            vec!["const limit: num", "double(n: num): num", "x"]
+        );
+        assert_eq!(analysis.symbols[1].kind, SymbolKind::Function);
+        let rows: Vec<_> = analysis.problems.iter().map(|p| p.position.row).collect();
+        assert_eq!(rows, vec![4, 3]);
+        assert_eq!(analysis.problems[0].message, "unexpected `EOF`");
+        assert_eq!(
+            analysis.problems[1].message,
+            "parameter `n` of `double` expects a value of type `num`, found `str`"
+        );
+        let (symbol, start) = analysis.symbol_at(source, 3, 7).unwrap();
+        assert_eq!((symbol.name.as_str(), start.col), ("double", 5));
+        assert!(analysis.symbol_at(source, 3, 13).is_none());
+
+        let double = analysis.definition_at(3, 7).unwrap();
+        assert_eq!((double.position.row, double.position.col), (2, 1));
+        let uses: Vec<_> = analysis
+            .references_of(double)
+            .iter()
+            .map(|p| p.row)
+            .collect();
+        assert_eq!(uses, vec![3]);
+        let n = analysis.definition_at(2, 23).unwrap();
+        assert_eq!((n.name.as_str(), n.position.col), ("n", 8));
+        assert!(analysis.definition_at(1, 3).is_none());
+
+        let input = messages(&[
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}"#,
+            r#"{"jsonrpc": "2.0", "method": "initialized", "params": {}}"#,
+            r#"{"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {"textDocument": {"uri": "file:///a.hy", "text": "x = (1 +"}}}"#,
+            r#"{"jsonrpc": "2.0", "method": "textDocument/didChange", "params": {"textDocument": {"uri": "file:///a.hy"}, "contentChanges": [{"text": "total: num = 1\ntotal = total + 1"}]}}"#,
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "textDocument/hover", "params": {"textDocument": {"uri": "file:///a.hy"}, "position": {"line": 0, "character": 2}}}"#,
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "textDocument/documentSymbol", "params": {"textDocument": {"uri": "file:///a.hy"}}}"#,
+            r#"{"jsonrpc": "2.0", "id": 4, "method": "textDocument/definition", "params": {"textDocument": {"uri": "file:///a.hy"}, "position": {"line": 1, "character": 9}}}"#,
+            r#"{"jsonrpc": "2.0", "id": 5, "method": "textDocument/references", "params": {"textDocument": {"uri": "file:///a.hy"}, "position": {"line": 0, "character": 0}, "context": {"includeDeclaration": true}}}"#,
+            r#"{"jsonrpc": "2.0", "id": 6, "method": "textDocument/rename", "params": {"textDocument": {"uri": "file:///a.hy"}, "position": {"line": 1, "character": 9}, "newName": "sum"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 7, "method": "textDocument/rename", "params": {"textDocument": {"uri": "file:///a.hy"}, "position": {"line": 0, "character": 0}, "newName": "1x"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 8, "method": "textDocument/formatting", "params": {}}"#,
+            "not json",
+            r#"{"jsonrpc": "2.0", "method": "exit"}"#,
+        ]);
+        let mut output = Vec::new();
+        let mut server = Server::new(input.as_bytes(), &mut output);
+        assert!(!server.run().unwrap());
+
+        let output = String::from_utf8(output).unwrap();
+        let bodies: Vec<_> = output.split("Content-Length: ").skip(1).collect();
+        assert_eq!(bodies.len(), 11);
+        assert!(bodies[0].contains(r#""hoverProvider":true"#));
+        assert!(bodies[1].contains(r#""severity":1"#));
+        assert!(bodies[1].contains(
+            r#""range":{"end":{"character":8,"line":0},"start":{"character":8,"line":0}}"#
+        ));
+        assert!(bodies[2].contains(r#""diagnostics":[]"#));
+        assert!(bodies[3].contains(r#""value":"```hydrogen\ntotal: num\n```""#));
+        assert!(bodies[4].contains(r#""detail":"total: num","kind":13"#));
+        let total = |line, character| {
+            format!(
+                r#"{{"range":{{"end":{{"character":{},"line":{}}},"start":{{"character":{},"line":{}}}}},"uri":"file:///a.hy"}}"#,
+                character + 5,
+                line,
+                character,
+                line
+            )
+        };
+        assert!(bodies[5].contains(&format!(r#""result":{}"#, total(0, 0))));
+        let locations = [total(0, 0), total(1, 0), total(1, 8)].join(",");
+        assert!(bodies[6].contains(&format!(r#""result":[{}]"#, locations)));
+        let sum = |line, character| {
+            format!(
+                r#"{{"newText":"sum","range":{{"end":{{"character":{},"line":{}}},"start":{{"character":{},"line":{}}}}}}}"#,
+                character + 5,
+                line,
+                character,
+                line
+            )
+        };
+        let edits = [sum(0, 0), sum(1, 0), sum(1, 8)].join(",");
+        assert!(bodies[7].contains(&format!(
+            r#""result":{{"changes":{{"file:///a.hy":[{}]}}}}"#,
+            edits
+        )));
+        assert!(bodies[8].contains(r#""code":-32803,"message":"`1x` is not a valid name""#));
+        assert!(bodies[9].contains(r#""code":-32601"#));
+        assert!(bodies[10].contains(r#""code":-32700"#));
+    }
+
+    /// Opens a document and returns the answers to the requests sent after it, which are given
+    /// the document.
+    fn answers(source: &str, requests: &[(&str, &str)]) -> Vec<String> {
+        let text = json::encode(&Value::from(source)).unwrap();
+        let open = format!(
+            r#"{{"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {{"textDocument": {{"uri": "file:///a.hy", "text": {}}}}}}}"#,
+            text
+        );
+        let mut bodies = vec![open];
+        for (i, (method, params)) in requests.iter().enumerate() {
+            bodies.push(format!(
+                r#"{{"jsonrpc": "2.0", "id": {}, "method": "textDocument/{}", "params": {{"textDocument": {{"uri": "file:///a.hy"}}, {}}}}}"#,
+                i + 1,
+                method,
+                params
+            ));
+        }
+        let bodies: Vec<_> = bodies.iter().map(String::as_str).collect();
+        let input = messages(&bodies);
+        let mut output = Vec::new();
+        Server::new(input.as_bytes(), &mut output).run().unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        output
+            .split("Content-Length: ")
+            .skip(2)
+            .map(|body| body.to_string())
+            .collect()
+    }
+
+    /// Returns the range of the protocol on a line between two characters, as JSON text.
+    fn span(line: usize, start: usize, end: usize) -> String {
+        format!(
+            r#""range":{{"end":{{"character":{},"line":{}}},"start":{{"character":{},"line":{}}}}}"#,
+            end, line, start, line
+        )
+    }
+
+    #[test]
+    fn test_tabs() {
+        // A tab is one character of the protocol, wherever its tab stop is
+        let source = "count = 0\nbump() {\n\tglobal count\n\tcount += 1\n}";
+        let answers = answers(
+            source,
+            &[
+                ("definition", r#""position": {"line": 3, "character": 1}"#),
+                (
+                    "rename",
+                    r#""position": {"line": 3, "character": 3}, "newName": "total""#,
+                ),
+            ],
+        );
+        assert!(answers[0].contains(&span(0, 0, 5)));
+        for (line, start) in [(0, 0), (2, 8), (3, 1)] {
+            assert!(
+                answers[1].contains(&span(line, start, start + 5)),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn test_wide_characters() {
+        // Wide characters take two columns of a terminal but one character of the protocol,
+        // and characters outside the basic plane two
+        let source = "count = 0\ny = \"日本\" + to_str(count)\nz = \"😀\" + to_str(count)";
+        let answers = answers(
+            source,
+            &[
+                ("definition", r#""position": {"line": 2, "character": 18}"#),
+                (
+                    "rename",
+                    r#""position": {"line": 1, "character": 20}, "newName": "total""#,
+                ),
+            ],
+        );
+        assert!(answers[0].contains(&span(0, 0, 5)));
+        for (line, start) in [(0, 0), (1, 18), (2, 18)] {
+            assert!(
+                answers[1].contains(&span(line, start, start + 5)),
+                "{}",
+                line
+            );
+        }
+    }
+}
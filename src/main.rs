@@ -8,9 +8,74 @@
 //!
 //! # Usage
 //!
-//! - `hydrogen -m [mode] -r [run]`
+//! - `hydrogen -m [mode] -r [run] [--max-depth N] [--max-steps N] [--timeout T] [--trace] [--wrapping|--bignum] [--color auto|always|never] [--config PATH]`
 //!   - `-m`, `--mode`: Specify the cursor mode for the REPL (default is "normal").
 //!   - `-r`, `--run`: Specify the mode to run the program in ("repl" for REPL, script file path for script mode).
+//!   - `--max-depth`: Specify how deeply function calls can nest (default is 1000). Like
+//!     `--max-steps`, `--timeout`, `--trace`, `--wrapping`, and `--bignum`, it can also be given to `run`,
+//!     `debug`, and `test`, but not to the commands that do not run programs.
+//!   - `--config`: Read the defaults of the options above, and the prompt, colors, history size,
+//!     and tab width of the REPL, from this file instead of `~/.config/hydrogen/config.toml`.
+//!     Options given on the command line win over the file. It can be given to every command.
+//!   - `--max-steps`: Stop a script, or a line of the REPL, after it evaluates this many steps.
+//!   - `--timeout`: Stop a script, or a line of the REPL, after it runs this long (e.g. `5s`,
+//!     `500ms`, or `2m`).
+//!   - `--trace`: Print every statement evaluated and its value to the standard error. In the
+//!     REPL, `:trace on` and `:trace off` do the same.
+//!   - `--wrapping`: Make arithmetic on whole numbers wrap around when its result is too large
+//!     for a whole number, instead of failing with an error programs can catch.
+//!   - `--bignum`: Make whole numbers grow without bound instead, which is slower. It needs the
+//!     `bignum` feature.
+//!   - `--color`: Color the output when it is a terminal and `NO_COLOR` is not set (default),
+//!     always, or never. It can be given to every command.
+//! - `hydrogen run [--backend tree|vm] [--opt] [--sandbox] [--profile] [--coverage] [--no-warnings] [--deny-warnings] [--stream] [--watch] [file|directory] [-- args...]`
+//!   - A directory is run as a project: its other `.hy` files are loaded before its `main.hy`, or
+//!     before the entry named by `entry = "..."` in its `hydrogen.toml`.
+//!   - `--backend`: Walk the parse tree (default) or compile the script and run it on a virtual machine.
+//!   - `--opt`: Simplify the script before running it.
+//!   - `--sandbox`: Run the script without access to files, environment variables, or processes.
+//!   - `--profile`: Print the calls and time of every function to the standard error at exit.
+//!   - `--coverage`: Print the script annotated with how many times each line ran, and a
+//!     summary, to the standard error at exit.
+//!   - `--no-warnings`: Do not print the warnings of the linter before the script runs.
+//!   - `--deny-warnings`: Fail without running the script if the linter finds warnings.
+//!   - `--stream`: Parse, check, and run the script one top-level statement at a time, so large
+//!     generated scripts start at once. The statements before an error have run, and the script
+//!     is not linted.
+//!   - `--watch`: Run the script again, on a cleared screen, every time it changes, or any
+//!     `.hy` file or the manifest of its project does.
+//!   - Arguments after `--` are given to the script, which gets them from `args()`.
+//! - `hydrogen debug [file]`
+//!   - Pause the script before its first statement and read debugger commands, like
+//!     `break LINE`, `step`, `next`, `continue`, `vars`, and `stack`, from the standard input.
+//! - `hydrogen test [file|directory]`
+//!   - Run every `test_*` function without parameters, each on a fresh engine, and every file of
+//!     the `tests` directory, then print a summary, failing if any test failed. `assert(cond,
+//!     message)` fails a test.
+//! - `hydrogen lsp`
+//!   - Serve the Language Server Protocol over the standard input and output, publishing the
+//!     parse and check errors of documents as they change, their symbols, the types of names
+//!     on hover, and where names are defined and used, and renaming names.
+//! - `hydrogen lint [--allow RULE]... [--list] [files...]`
+//!   - Print the likely mistakes the rules of the linter find in the scripts, like unused
+//!     variables or unreachable code, failing if there are any.
+//!   - `--allow`: Turn off a rule, by its identifier.
+//!   - `--list`: Print the rules and what they find instead of linting.
+//! - `hydrogen explain [code]`
+//!   - Print the longer description of a diagnostic code, like `H0101`, with examples, or the
+//!     list of codes without one.
+//! - `hydrogen ast [--format tree|dot] [--opt] [file]`
+//!   - `--format`: Print the parse tree as an indented tree (default) or as a Graphviz DOT graph.
+//!   - `--opt`: Print the parse tree of the simplified script.
+//! - `hydrogen tokens [--format text|json] [file | -e source]`
+//!   - Print the tokens the parser reads, one per line with the row and column where it starts,
+//!     its kind, and its text, or as a JSON array with `--format json`.
+//! - `hydrogen fmt [--check] [files...]`
+//!   - `--check`: Print the changes formatting would make instead of rewriting the files.
+//! - `hydrogen doc [--format markdown|html] [file|directory]`
+//!   - Print the documentation of a script, or of every script of a project, made from the
+//!     `///` comments right above its functions, constants, and variables.
+//!   - `--format`: Print Markdown (default) or a standalone HTML page.
 //!
 //! # Examples
 //!
@@ -22,46 +87,782 @@
 //!   ```
 //!   hydrogen -r path/to/script.hydro
 //!   ```
+//! - Render the parse tree of a script with Graphviz:
+//!   ```text
+//!   hydrogen ast --format dot path/to/script.hy | dot -Tsvg > ast.svg
+//!   ```
 
 #![warn(missing_docs)]
 
-use std::{fs, io::Result, path::Path};
-
-use clap::Parser;
+use std::{
+    fs,
+    io::{self, Error, IsTerminal, Result, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    thread,
+    time::Duration,
+};
 
-mod hash;
-mod repl;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use crossterm::{
+    cursor::MoveTo,
+    style::Color,
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
 
-use hash::evaluator::Evaluator;
-use repl::repl;
+use hydrogen::config::Config;
+use hydrogen::doc::{self, Format};
+use hydrogen::hash::{codes, evaluator::Arithmetic, format, lint::Linter, optimize, parser, print};
+use hydrogen::repl::{debug::Console, repl};
+use hydrogen::style::{self, ColorChoice};
+use hydrogen::{lsp, testing, watch::Watcher, Backend, Engine, HydrogenError};
 
 /// Command-line options for the Hydrogen program.
 #[derive(Parser, Debug)]
 #[clap(name = "hydrogen", about = "A simple programming language!")]
 struct Opt {
-    /// Specify the cursor mode for the REPL (default is "normal").
-    #[clap(short = 'm', long = "mode", default_value = "normal")]
-    mode: String,
+    /// Specify the cursor mode for the REPL (default is "normal", or the configured mode).
+    #[clap(short = 'm', long = "mode")]
+    mode: Option<String>,
     /// Specify the mode to run the program in ("repl" for REPL, script file path for script mode).
-    #[clap(short = 'r', long = "run", default_value = "")]
+    #[clap(short = 'r', long = "run", default_value = "repl")]
     run: String,
+    /// The options of the interpreter for the REPL or the script.
+    #[command(flatten)]
+    interpreter: InterpreterOpt,
+    /// Color the output when it is a terminal and `NO_COLOR` is not set, always, or never.
+    #[clap(long = "color", value_enum, default_value = "auto", global = true)]
+    color: ColorArg,
+    /// Read the defaults of the REPL and the interpreter from this file instead of
+    /// `~/.config/hydrogen/config.toml`.
+    #[clap(long = "config", global = true)]
+    config: Option<PathBuf>,
+    /// The command to run instead of the REPL or a script.
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Command-line options for the interpreter, taken by the REPL and the commands that run
+/// programs.
+#[derive(Args, Clone, Debug, Default)]
+struct InterpreterOpt {
+    /// Specify how deeply function calls can nest before the program fails (default is 1000, or
+    /// the configured depth).
+    #[clap(long = "max-depth")]
+    max_depth: Option<usize>,
+    /// Stop a program after it evaluates this many steps.
+    #[clap(long = "max-steps")]
+    max_steps: Option<u64>,
+    /// Stop a program after it runs this long, like "5s", "500ms", or "2m".
+    #[clap(long = "timeout", value_parser = parse_duration)]
+    timeout: Option<Duration>,
+    /// Print every statement evaluated and its value to the standard error.
+    #[clap(long = "trace")]
+    trace: bool,
+    /// Make arithmetic on whole numbers wrap around when it overflows, instead of failing.
+    #[clap(long = "wrapping")]
+    wrapping: bool,
+    /// Make whole numbers grow without bound, so arithmetic on them never overflows.
+    #[cfg(feature = "bignum")]
+    #[clap(long = "bignum", conflicts_with = "wrapping")]
+    bignum: bool,
+}
+
+impl InterpreterOpt {
+    /// Combines the options given after a command with the ones given before it.
+    ///
+    /// # Arguments
+    ///
+    /// * `before` - The options given before the command.
+    ///
+    /// # Returns
+    ///
+    /// * `InterpreterOpt` - The options, the ones given after the command winning.
+    fn or(self, before: InterpreterOpt) -> InterpreterOpt {
+        InterpreterOpt {
+            max_depth: self.max_depth.or(before.max_depth),
+            max_steps: self.max_steps.or(before.max_steps),
+            timeout: self.timeout.or(before.timeout),
+            trace: self.trace || before.trace,
+            wrapping: self.wrapping || before.wrapping,
+            #[cfg(feature = "bignum")]
+            bignum: self.bignum || before.bignum,
+        }
+    }
+}
+
+/// Commands for running and inspecting Hydrogen programs.
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run a script.
+    Run {
+        #[command(flatten)]
+        interpreter: InterpreterOpt,
+        /// The way the script is run.
+        #[clap(long = "backend", value_enum, default_value = "tree")]
+        backend: BackendArg,
+        /// Simplify the script before running it.
+        #[clap(long = "opt")]
+        opt: bool,
+        /// Run the script without access to files, environment variables, or processes, and
+        /// with lower limits.
+        #[clap(long = "sandbox")]
+        sandbox: bool,
+        /// Print how many times each function was called and the time spent in it, when the
+        /// script ends.
+        #[clap(long = "profile")]
+        profile: bool,
+        /// Print the script with how many times the statements of each line ran, when it ends.
+        #[clap(long = "coverage")]
+        coverage: bool,
+        /// Do not print the warnings of the linter before the script runs.
+        #[clap(long = "no-warnings")]
+        no_warnings: bool,
+        /// Fail without running the script if the linter finds warnings.
+        #[clap(long = "deny-warnings")]
+        deny_warnings: bool,
+        /// Parse, check, and run the script one top-level statement at a time, so large scripts
+        /// start at once. The script is not linted.
+        #[clap(long = "stream", conflicts_with = "deny_warnings")]
+        stream: bool,
+        /// Run the script again, on a cleared screen, every time it or a file of its project
+        /// changes, until interrupted.
+        #[clap(long = "watch")]
+        watch: bool,
+        /// The script file, or the directory of the project, to run.
+        file: String,
+        /// The arguments the script gets from `args()`, after `--`.
+        #[clap(last = true)]
+        args: Vec<String>,
+    },
+    /// Run a script under the debugger, stepping through it from the standard input.
+    Debug {
+        #[command(flatten)]
+        interpreter: InterpreterOpt,
+        /// The script file to debug.
+        file: PathBuf,
+    },
+    /// Run the tests of a script or a directory.
+    Test {
+        #[command(flatten)]
+        interpreter: InterpreterOpt,
+        /// The script file, or the directory, whose `test_*` functions and `tests` directory
+        /// are run.
+        #[clap(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Serve the Language Server Protocol over the standard input and output, for editors.
+    Lsp,
+    /// Print the likely mistakes found in scripts by the rules of the linter.
+    Lint {
+        /// Turn off a rule, by its identifier, like `unused-variable`.
+        #[clap(long = "allow")]
+        allow: Vec<String>,
+        /// Print the rules and what they find instead of linting.
+        #[clap(long = "list")]
+        list: bool,
+        /// The script files to lint.
+        #[clap(required_unless_present = "list")]
+        files: Vec<PathBuf>,
+    },
+    /// Print the longer description of a diagnostic code, with examples.
+    Explain {
+        /// The code, like `H0101`, or none to list the codes.
+        code: Option<String>,
+    },
+    /// Print the parse tree of a script.
+    Ast {
+        /// The format the parse tree is printed in.
+        #[clap(long = "format", value_enum, default_value = "tree")]
+        format: AstFormat,
+        /// Print the parse tree of the simplified script.
+        #[clap(long = "opt")]
+        opt: bool,
+        /// The script file to parse.
+        file: PathBuf,
+    },
+    /// Print the tokens the parser reads from a script, one per line with its position.
+    Tokens {
+        /// The format the tokens are printed in.
+        #[clap(long = "format", value_enum, default_value = "text")]
+        format: TokensFormat,
+        /// Source code to read the tokens of instead of a file.
+        #[clap(short = 'e', long = "eval", conflicts_with = "file")]
+        eval: Option<String>,
+        /// The script file to read the tokens of.
+        #[clap(required_unless_present = "eval")]
+        file: Option<PathBuf>,
+    },
+    /// Format scripts in place.
+    Fmt {
+        /// Print the changes instead of rewriting the files, failing if there are any.
+        #[clap(long = "check")]
+        check: bool,
+        /// The script files to format.
+        #[clap(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Print the documentation of a script or a project, from its doc comments.
+    Doc {
+        /// The format the documentation is printed in.
+        #[clap(long = "format", value_enum, default_value = "markdown")]
+        format: DocFormat,
+        /// The script file, or the directory of the project, to document.
+        #[clap(default_value = ".")]
+        path: PathBuf,
+    },
+}
+
+/// Backends scripts can be run with.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum BackendArg {
+    /// Walk the parse tree.
+    Tree,
+    /// Compile to bytecode and run it on a virtual machine.
+    Vm,
+}
+
+impl From<BackendArg> for Backend {
+    fn from(backend: BackendArg) -> Self {
+        match backend {
+            BackendArg::Tree => Backend::Tree,
+            BackendArg::Vm => Backend::Vm,
+        }
+    }
+}
+
+/// Choices of when the output is colored.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ColorArg {
+    /// Color the output when it is a terminal and `NO_COLOR` is not set.
+    Auto,
+    /// Always color the output.
+    Always,
+    /// Never color the output.
+    Never,
+}
+
+impl From<ColorArg> for ColorChoice {
+    fn from(color: ColorArg) -> Self {
+        match color {
+            ColorArg::Auto => ColorChoice::Auto,
+            ColorArg::Always => ColorChoice::Always,
+            ColorArg::Never => ColorChoice::Never,
+        }
+    }
+}
+
+/// Output formats of the parse tree.
+#[derive(ValueEnum, Clone, Debug)]
+enum AstFormat {
+    /// An indented tree, one node per line.
+    Tree,
+    /// A Graphviz DOT graph.
+    Dot,
+}
+
+/// Output formats of the tokens of a script.
+#[derive(ValueEnum, Clone, Debug)]
+enum TokensFormat {
+    /// A token per line, with its row, column, kind, and text.
+    Text,
+    /// A JSON array of objects, one per token.
+    Json,
+}
+
+/// Output formats of the documentation of scripts.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum DocFormat {
+    /// Markdown, with a heading per file and per definition.
+    Markdown,
+    /// A standalone HTML page.
+    Html,
+}
+
+impl From<DocFormat> for Format {
+    fn from(format: DocFormat) -> Self {
+        match format {
+            DocFormat::Markdown => Format::Markdown,
+            DocFormat::Html => Format::Html,
+        }
+    }
+}
+
+/// Parses a duration made of a number and a unit, `ms`, `s`, or `m`, defaulting to seconds.
+fn parse_duration(text: &str) -> std::result::Result<Duration, String> {
+    let (number, unit) = text.split_at(
+        text.find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len()),
+    );
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("`{}` does not start with a number", text))?;
+    match unit {
+        "ms" => Ok(Duration::from_millis(number)),
+        "" | "s" => Ok(Duration::from_secs(number)),
+        "m" => Ok(Duration::from_secs(number.saturating_mul(60))),
+        _ => Err(format!("unknown unit `{}`, expected ms, s, or m", unit)),
+    }
+}
+
+/// Parses a script and prints its parse tree in the given format, and its errors with columns
+/// counted with tab stops of the given width.
+fn print_ast(format: AstFormat, opt: bool, file: PathBuf, tab_width: usize) -> Result<ExitCode> {
+    let source = fs::read_to_string(file)?;
+
+    // The statements that parse are printed even when others do not
+    let mut parser = parser::Parser::new(&source);
+    parser.set_tab_width(tab_width);
+    let parsed = parser.parse_partial();
+    let tree = if opt {
+        optimize::optimize(parsed.tree)
+    } else {
+        parsed.tree
+    };
+    match format {
+        AstFormat::Tree => print::print_tree(&tree)?,
+        AstFormat::Dot => print!("{}", print::to_dot(&tree)),
+    }
+
+    if parsed.errors.is_empty() {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        print::print_error(parsed.errors)?;
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+/// Prints the tokens of a script, or of source code given on the command line, in the given
+/// format.
+fn print_tokens(format: TokensFormat, eval: Option<String>, file: Option<PathBuf>) -> Result<()> {
+    let source = match (eval, file) {
+        (Some(source), _) => source,
+        (None, Some(file)) => fs::read_to_string(file)?,
+        (None, None) => unreachable!("clap requires a file or source code"),
+    };
+    match format {
+        TokensFormat::Text => print!("{}", print::to_tokens(&source)),
+        TokensFormat::Json => print!("{}", print::to_tokens_json(&source)),
+    }
+    Ok(())
+}
+
+/// Formats scripts in place, or prints the changes formatting would make to them.
+fn format_files(check: bool, files: Vec<PathBuf>) -> Result<ExitCode> {
+    let mut code = ExitCode::SUCCESS;
+
+    for file in files {
+        let source = fs::read_to_string(&file)?;
+        let formatted = match format::format(&source) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                eprintln!("{}: could not be parsed", file.display());
+                print::print_error(errors)?;
+                code = ExitCode::FAILURE;
+                continue;
+            }
+        };
+
+        if formatted == source {
+            continue;
+        }
+
+        if check {
+            println!("{}", file.display());
+            print!("{}", format::diff(&source, &formatted));
+            code = ExitCode::FAILURE;
+        } else {
+            fs::write(&file, formatted)?;
+        }
+    }
+
+    Ok(code)
+}
+
+/// Prints the lints of scripts, with columns counted with tab stops of the given width, or the
+/// rules of the linter.
+fn lint_files(
+    allow: Vec<String>,
+    list: bool,
+    files: Vec<PathBuf>,
+    tab_width: usize,
+) -> Result<ExitCode> {
+    let mut linter = Linter::new();
+    linter.set_tab_width(tab_width);
+    if list {
+        for (rule, _) in linter.rules() {
+            println!("{:<20} {}", rule.id(), rule.description());
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+    for rule in &allow {
+        if !linter.set_enabled(rule, false) {
+            eprintln!("unknown rule `{}`, see `hydrogen lint --list`", rule);
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+
+    let mut code = ExitCode::SUCCESS;
+    for file in files {
+        let source = fs::read_to_string(&file)?;
+        let mut parser = parser::Parser::new(&source);
+        parser.set_tab_width(tab_width);
+        if let Err(errors) = parser.parse_program() {
+            eprintln!("{}: could not be parsed", file.display());
+            print::print_error(errors)?;
+            code = ExitCode::FAILURE;
+        }
+        for lint in linter.lint(&source) {
+            println!("{}: {}", file.display(), lint);
+            code = ExitCode::FAILURE;
+        }
+    }
+
+    Ok(code)
+}
+
+/// Prints the documentation of a script or of the scripts of a project.
+fn document(format: DocFormat, path: PathBuf) -> ExitCode {
+    match doc::document(&path) {
+        Ok(pages) => {
+            print!("{}", doc::render(&pages, format.into()));
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Prints the explanation of a diagnostic code, or the list of codes.
+fn explain(code: Option<String>) -> ExitCode {
+    let Some(code) = code else {
+        for explanation in &codes::EXPLANATIONS {
+            println!("{}  {}", explanation.code, explanation.title);
+        }
+        return ExitCode::SUCCESS;
+    };
+    match codes::explain(&code) {
+        Some(explanation) => {
+            println!(
+                "{}: {}\n\n{}",
+                explanation.code, explanation.title, explanation.text
+            );
+            ExitCode::SUCCESS
+        }
+        None => {
+            eprintln!("unknown code `{}`, see `hydrogen explain`", code);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// How long watched scripts are left alone between two looks at their files.
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The stack reserved for every nested call of a Hydrogen function, enough for debug builds.
+const STACK_PER_CALL: usize = 64 * 1024;
+
+/// Runs a function on a thread whose stack fits `max_depth` nested calls.
+fn on_stack<T, F>(max_depth: usize, function: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    let stack_size = max_depth
+        .saturating_mul(STACK_PER_CALL)
+        .max(8 * 1024 * 1024);
+
+    thread::Builder::new()
+        .stack_size(stack_size)
+        .spawn(function)?
+        .join()
+        .unwrap_or_else(|_| Err(Error::other("the program panicked")))
+}
+
+/// Runs the REPL or a script on a thread whose stack fits the nested calls allowed by the
+/// configuration.
+///
+/// The engine running the REPL or the script is set up by `configure` before it runs.
+fn run<F>(config: Config, run: String, configure: F) -> Result<ExitCode>
+where
+    F: FnOnce(&mut Engine) + Send + 'static,
+{
+    on_stack(config.max_depth, move || {
+        let mut engine = Engine::new();
+        engine.set_max_depth(config.max_depth);
+        configure(&mut engine);
+
+        // Check if the program is running in REPL mode or script mode.
+        if run == "repl" {
+            // Run the REPL with the configured cursor mode, prompt, and colors.
+            repl(&config, engine)?;
+        } else {
+            // Read and evaluate code from the specified script file.
+            let result = if Path::new(&run).is_dir() {
+                engine.eval_project(&run)
+            } else {
+                engine.eval_file(&run)
+            };
+            if let Some(profile) = engine.profile() {
+                eprint!("{}", profile);
+            }
+            if let Some(coverage) = engine.coverage() {
+                match fs::read_to_string(&run) {
+                    Ok(source) => eprint!("{}", coverage.report(&source)),
+                    Err(_) => eprintln!("coverage is only reported for a script file"),
+                }
+            }
+            match result {
+                Ok(_) => {}
+                // Exit codes only keep the low byte of the status, as on Unix
+                Err(HydrogenError::Exit(status)) => return Ok(ExitCode::from(status as u8)),
+                Err(error) => {
+                    eprintln!("{}", error);
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+        }
+
+        Ok(ExitCode::SUCCESS)
+    })
+}
+
+/// Runs a script, or a project, again on a fresh engine every time one of its files changes,
+/// clearing the screen before each run.
+///
+/// The engine of every run is set up by `configure`. It only returns if the screen cannot be
+/// cleared.
+fn watch_script<F>(config: Config, file: String, configure: F) -> Result<ExitCode>
+where
+    F: FnOnce(&mut Engine) + Clone + Send + 'static,
+{
+    let mut watcher = Watcher::new(&file);
+    loop {
+        // The output of the runs before is only cleared from a terminal
+        let mut stdout = io::stdout();
+        if stdout.is_terminal() {
+            stdout
+                .queue(Clear(ClearType::All))?
+                .queue(Clear(ClearType::Purge))?
+                .queue(MoveTo(0, 0))?;
+            stdout.flush()?;
+        }
+        run(config.clone(), file.clone(), configure.clone())?;
+
+        let mut stderr = style::stderr();
+        let waiting = format!("\nwaiting for changes to {}...\n", file);
+        stderr.paint(&waiting, Some(Color::DarkGrey), None)?;
+        watcher.wait(WATCH_INTERVAL);
+    }
+}
+
+/// Runs the tests of a file or a directory, printing whether each passed and a summary.
+///
+/// Every test runs on an engine of its own, set up by `configure`.
+fn run_tests<F>(path: PathBuf, max_depth: usize, configure: F) -> Result<ExitCode>
+where
+    F: Fn(&mut Engine) + Send + 'static,
+{
+    on_stack(max_depth, move || {
+        let configure = |engine: &mut Engine| {
+            engine.set_max_depth(max_depth);
+            configure(engine);
+        };
+
+        let tests = testing::discover(&path)?;
+        let mut failures = Vec::new();
+        for test in &tests {
+            match test.run(&configure) {
+                Ok(()) => println!("test {} ... ok", test),
+                Err(error) => {
+                    println!("test {} ... FAILED", test);
+                    failures.push((test, error));
+                }
+            }
+        }
+
+        if !failures.is_empty() {
+            println!("\nfailures:");
+            for (test, error) in &failures {
+                println!("\n{}\n{}", test, error);
+            }
+        }
+        println!(
+            "\ntest result: {}. {} passed; {} failed",
+            if failures.is_empty() { "ok" } else { "FAILED" },
+            tests.len() - failures.len(),
+            failures.len()
+        );
+
+        Ok(if failures.is_empty() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        })
+    })
 }
 
 /// Main function for the Hydrogen program.
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     // Parse command-line options using Clap.
     let opt = Opt::parse();
+    style::set_color_choice(opt.color.into());
 
-    // Check if the program is running in REPL mode or script mode.
-    if opt.run == "repl" {
-        // Run the REPL with the specified cursor mode.
-        repl(opt.mode)?;
-    } else {
-        // Read and validate code from the specified script file.
-        let path = fs::read_to_string(Path::new("test/hello.hy")).unwrap();
-        let mut evaluator = Evaluator::new(&path);
-        evaluator.eval();
+    // Options given on the command line win over the configuration file
+    let mut config = match Config::load(opt.config.as_deref()) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("invalid configuration: {}", error);
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+    if let Some(mode) = opt.mode {
+        config.mode = mode;
+    }
+    // The options of the interpreter can be given before the command that runs a program, too
+    let interpreter = match &opt.command {
+        Some(Command::Run { interpreter, .. })
+        | Some(Command::Debug { interpreter, .. })
+        | Some(Command::Test { interpreter, .. }) => interpreter.clone().or(opt.interpreter),
+        _ => opt.interpreter,
+    };
+    if let Some(max_depth) = interpreter.max_depth {
+        config.max_depth = max_depth;
     }
+    let arithmetic = match interpreter.wrapping {
+        true => Arithmetic::Wrapping,
+        false => Arithmetic::Checked,
+    };
+    #[cfg(feature = "bignum")]
+    let arithmetic = match interpreter.bignum {
+        true => Arithmetic::Big,
+        false => arithmetic,
+    };
+    let (max_steps, timeout, trace) = (
+        interpreter.max_steps,
+        interpreter.timeout,
+        interpreter.trace,
+    );
+    let tab_width = config.tab_width;
+    let set_up = move |engine: &mut Engine| {
+        engine.set_tab_width(tab_width);
+        engine.set_max_steps(max_steps);
+        engine.set_timeout(timeout);
+        engine.set_trace(trace);
+        engine.set_arithmetic(arithmetic);
+    };
 
-    Ok(())
+    match opt.command {
+        Some(Command::Run {
+            backend,
+            opt: optimize,
+            sandbox,
+            profile,
+            coverage,
+            no_warnings,
+            deny_warnings,
+            stream,
+            watch,
+            file,
+            args,
+            ..
+        }) => {
+            let configure = move |engine: &mut Engine| {
+                set_up(engine);
+                if sandbox {
+                    engine.sandbox();
+                }
+                engine.set_backend(backend.into());
+                engine.set_optimize(optimize);
+                engine.set_args(args.clone());
+                engine.set_profiling(profile);
+                engine.set_coverage(coverage);
+                engine.set_warnings(!no_warnings);
+                engine.set_deny_warnings(deny_warnings);
+                engine.set_streaming(stream);
+            };
+            if watch {
+                return watch_script(config, file, configure);
+            }
+            return run(config, file, configure);
+        }
+        Some(Command::Debug { file, .. }) => {
+            let source = fs::read_to_string(&file)?;
+            let file = file.display().to_string();
+            return run(config, file, move |engine| {
+                set_up(engine);
+                let console = Console::new(&source, io::stdin().lock(), io::stdout());
+                engine.set_debugger(Box::new(console));
+            });
+        }
+        Some(Command::Test { path, .. }) => return run_tests(path, config.max_depth, set_up),
+        Some(Command::Lsp) => {
+            let mut server = lsp::Server::new(io::stdin().lock(), io::stdout().lock());
+            return Ok(match server.run()? {
+                true => ExitCode::SUCCESS,
+                false => ExitCode::FAILURE,
+            });
+        }
+        Some(Command::Lint { allow, list, files }) => {
+            return lint_files(allow, list, files, tab_width)
+        }
+        Some(Command::Explain { code }) => return Ok(explain(code)),
+        Some(Command::Ast { format, opt, file }) => return print_ast(format, opt, file, tab_width),
+        Some(Command::Tokens { format, eval, file }) => {
+            print_tokens(format, eval, file)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Fmt { check, files }) => return format_files(check, files),
+        Some(Command::Doc { format, path }) => return Ok(document(format, path)),
+        None => {}
+    }
+
+    run(config, opt.run, set_up)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_options() {
+        let opt = Opt::try_parse_from(["hydrogen", "run", "--max-depth", "10", "f.hy"]).unwrap();
+        let Some(Command::Run {
+            interpreter, file, ..
+        }) = opt.command
+        else {
+            panic!("expected the run command");
+        };
+        assert_eq!((interpreter.max_depth, file.as_str()), (Some(10), "f.hy"));
+        assert!(Opt::try_parse_from(["hydrogen", "run", "--max-depth", "deep", "f.hy"]).is_err());
+
+        // The options of the interpreter given before a command are combined with its own
+        let opt =
+            Opt::try_parse_from(["hydrogen", "--max-depth", "10", "test", "--max-steps", "5"])
+                .unwrap();
+        let Some(Command::Test { interpreter, .. }) = opt.command else {
+            panic!("expected the test command");
+        };
+        let interpreter = interpreter.or(opt.interpreter);
+        assert_eq!(
+            (interpreter.max_depth, interpreter.max_steps),
+            (Some(10), Some(5))
+        );
+        let opt = Opt::try_parse_from(["hydrogen", "--timeout", "5s"]).unwrap();
+        assert_eq!(opt.interpreter.timeout, Some(Duration::from_secs(5)));
+
+        // The commands that do not run programs do not take them
+        for command in ["fmt", "lint", "doc", "tokens"] {
+            assert!(
+                Opt::try_parse_from(["hydrogen", command, "--max-steps", "5", "f.hy"]).is_err()
+            );
+            assert!(Opt::try_parse_from(["hydrogen", command, "--wrapping", "f.hy"]).is_err());
+        }
+        assert!(Opt::try_parse_from(["hydrogen", "explain", "--timeout", "5s"]).is_err());
+        assert!(Opt::try_parse_from(["hydrogen", "fmt", "--trace", "f.hy"]).is_err());
+        assert!(Opt::try_parse_from(["hydrogen", "debug", "--trace", "f.hy"]).is_ok());
+    }
 }
@@ -1,5 +1,5 @@
 /// Represents a cell in the terminal, defined by its column and row positions.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Cell {
     /// The column position of the cell.
     pub col: u16,
@@ -0,0 +1,90 @@
+/// Enum representing the commands of the REPL, entered as a line starting with a colon
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Turn the trace of evaluated statements on or off: (on)
+    Trace(bool),
+    /// Turn printing how long every evaluated line took on or off: (on)
+    Time(bool),
+    /// Forget every variable and function defined in the session
+    Reset,
+    /// Print the type of the value an expression computes, without evaluating it: (expression)
+    Type(String),
+    /// Print the signature and the description of a function: (name)
+    Doc(String),
+}
+
+impl Command {
+    /// Parses the command entered as a line.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The entered line.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Result<Self, String>>` - None if the line is not a command, otherwise the
+    ///   command, or the reason it is not a valid one.
+    pub fn parse(line: &str) -> Option<Result<Self, String>> {
+        let command = line.trim().strip_prefix(':')?;
+        // The expression of `:type` is kept as it was written
+        if let Some(expression) = command.strip_prefix("type").filter(|e| e.starts_with(' ')) {
+            return Some(Ok(Command::Type(expression.trim().to_string())));
+        }
+
+        let mut words = command.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let args: Vec<&str> = words.collect();
+
+        Some(match (name, args.as_slice()) {
+            ("trace", ["on"]) => Ok(Command::Trace(true)),
+            ("trace", ["off"]) => Ok(Command::Trace(false)),
+            ("trace", _) => Err("usage: :trace on|off".to_string()),
+            ("time", ["on"]) => Ok(Command::Time(true)),
+            ("time", ["off"]) => Ok(Command::Time(false)),
+            ("time", _) => Err("usage: :time on|off".to_string()),
+            ("reset", []) => Ok(Command::Reset),
+            ("reset", _) => Err("usage: :reset".to_string()),
+            ("type", _) => Err("usage: :type <expression>".to_string()),
+            ("doc", [name]) => Ok(Command::Doc(name.to_string())),
+            ("doc", _) => Err("usage: :doc <function>".to_string()),
+            _ => Err(format!("unknown command `:{}`", name)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_command() {
+        assert_eq!(Command::parse("x = 1"), None);
+        assert_eq!(Command::parse(":trace on"), Some(Ok(Command::Trace(true))));
+        assert_eq!(
+            Command::parse("  :trace   off "),
+            Some(Ok(Command::Trace(false)))
+        );
+        assert_eq!(
+            Command::parse(":trace"),
+            Some(Err("usage: :trace on|off".to_string()))
+        );
+        assert_eq!(Command::parse(":time on"), Some(Ok(Command::Time(true))));
+        assert_eq!(Command::parse(":reset"), Some(Ok(Command::Reset)));
+        assert_eq!(
+            Command::parse(":type  var1 + \"a  b\""),
+            Some(Ok(Command::Type("var1 + \"a  b\"".to_string())))
+        );
+        assert_eq!(
+            Command::parse(":doc split"),
+            Some(Ok(Command::Doc("split".to_string())))
+        );
+        assert_eq!(
+            Command::parse(":type"),
+            Some(Err("usage: :type <expression>".to_string()))
+        );
+        assert_eq!(
+            Command::parse(":quit"),
+            Some(Err("unknown command `:quit`".to_string()))
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use unicode_ident::is_xid_continue;
+
+use crate::hash::{
+    ast::{Expr, Item, Stmt},
+    builtins,
+    parser::Parser,
+    tokens::{Keyword, TypeName},
+};
+
+use super::linebuffer::LineBuffer;
+
+/// Operators written as words, offered as completions with the keywords and type names.
+const OPERATORS: [&str; 2] = ["in", "as"];
+
+/// The candidates being cycled through by consecutive presses of Tab.
+#[derive(Debug)]
+struct Cycle {
+    /// The candidates matching the word under the caret.
+    candidates: Vec<String>,
+    /// The index of the candidate currently inserted in the line.
+    index: usize,
+    /// The character index where the completed word starts.
+    start: usize,
+}
+
+/// Completes the word under the caret from keywords, types, builtins, and defined names.
+#[derive(Debug, Default)]
+pub struct Completer {
+    /// Names defined by the lines evaluated so far in the session.
+    names: Vec<String>,
+    /// The active completion cycle, if Tab was the last key pressed.
+    cycle: Option<Cycle>,
+}
+
+impl Completer {
+    /// Creates a new `Completer` with no defined names.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new `Completer` instance.
+    pub fn new() -> Self {
+        Completer::default()
+    }
+
+    /// Records the variables and functions defined by an evaluated line.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source code of the evaluated line.
+    pub fn learn(&mut self, source: &str) {
+        // Names defined before or after a line that does not parse are still offered
+        for statement in Parser::new(source).parse_partial().tree {
+            self.collect(&statement);
+        }
+    }
+
+    /// Collects the names defined by a statement and the blocks it is made of.
+    fn collect(&mut self, statement: &Stmt) {
+        let name = match statement {
+            Stmt::Definition(definition) => &definition.name,
+            Stmt::Declaration(declaration) => &declaration.name,
+            Stmt::Item(Item::Function(definition)) => &definition.name,
+            Stmt::Item(Item::Struct(definition)) => &definition.name,
+            Stmt::Item(Item::Interface(definition)) => &definition.name,
+            // The functions are called by name after a `.`
+            Stmt::Item(Item::Impl(implementation)) => {
+                (implementation.body.statements.iter()).for_each(|s| self.collect(s));
+                return;
+            }
+            Stmt::Destructure(destructuring) => {
+                for name in destructuring.pattern.names() {
+                    if !self.names.contains(name) {
+                        self.names.push(name.clone());
+                    }
+                }
+                return;
+            }
+            Stmt::Expr(Expr::Block(block)) => {
+                block.statements.iter().for_each(|s| self.collect(s));
+                return;
+            }
+            Stmt::Global(_)
+            | Stmt::Nonlocal(_)
+            | Stmt::Store(_)
+            | Stmt::Expr(_)
+            | Stmt::Return(_)
+            | Stmt::Break
+            | Stmt::Continue => return,
+        };
+
+        if !self.names.contains(name) {
+            self.names.push(name.clone());
+        }
+    }
+
+    /// Returns the sorted candidates that start with the given prefix.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The beginning of the word to be completed.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<String>` - The matching candidates without duplicates.
+    pub fn candidates(&self, prefix: &str) -> Vec<String> {
+        let mut candidates: Vec<String> = Keyword::ALL
+            .iter()
+            .map(Keyword::name)
+            .chain(TypeName::ALL.iter().map(TypeName::name))
+            .chain(OPERATORS)
+            .chain(builtins::names())
+            .map(|word| word.to_string())
+            .chain(self.names.iter().cloned())
+            .filter(|word| word.starts_with(prefix))
+            .collect();
+
+        candidates.sort();
+        candidates.dedup();
+        candidates
+    }
+
+    /// Completes the word under the caret, cycling through the candidates on repeated calls.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The line buffer containing the word to be completed.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if the line was changed, false if there was nothing to complete.
+    pub fn complete(&mut self, line: &mut LineBuffer) -> bool {
+        if let Some(cycle) = &mut self.cycle {
+            cycle.index = (cycle.index + 1) % cycle.candidates.len();
+            let (start, candidate) = (cycle.start, cycle.candidates[cycle.index].clone());
+            replace(line, start, &candidate);
+            return true;
+        }
+
+        let chars: Vec<char> = line.buffer.chars().collect();
+        let mut start = line.index;
+        while start > 0 && is_xid_continue(chars[start - 1]) {
+            start -= 1;
+        }
+
+        let prefix: String = chars[start..line.index].iter().collect();
+        if prefix.is_empty() {
+            return false;
+        }
+
+        let candidates = self.candidates(&prefix);
+        if candidates.is_empty() {
+            return false;
+        }
+
+        replace(line, start, &candidates[0]);
+        if candidates.len() > 1 {
+            self.cycle = Some(Cycle {
+                candidates,
+                index: 0,
+                start,
+            });
+        }
+        true
+    }
+
+    /// Ends the current completion cycle, called when any key other than Tab is pressed.
+    pub fn reset(&mut self) {
+        self.cycle = None;
+    }
+}
+
+/// Replaces the text between `start` and the caret with the given word.
+fn replace(line: &mut LineBuffer, start: usize, word: &str) {
+    while line.index > start {
+        line.backspace();
+    }
+    word.chars().for_each(|c| line.insert(c));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_completion() {
+        let mut completer = Completer::new();
+        completer.learn("whale = 1\nwidth(): num { inner = 2 }");
+
+        assert_eq!(completer.candidates("wh"), vec!["whale", "while"]);
+        assert_eq!(completer.candidates("wi"), vec!["width"]);
+
+        let mut line = LineBuffer::new();
+        "x = wh".chars().for_each(|c| line.insert(c));
+
+        assert!(completer.complete(&mut line));
+        assert_eq!(line.buffer, "x = whale");
+        assert!(completer.complete(&mut line));
+        assert_eq!(line.buffer, "x = while");
+        assert!(completer.complete(&mut line));
+        assert_eq!(line.buffer, "x = whale");
+
+        completer.reset();
+        line.insert(' ');
+        assert!(!completer.complete(&mut line));
+
+        // A line that does not parse hides none of the others
+        completer.learn("broken = ~\nwhere = 2");
+        assert_eq!(completer.candidates("whe"), vec!["where"]);
+    }
+}
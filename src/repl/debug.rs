@@ -0,0 +1,314 @@
+use std::{
+    collections::BTreeSet,
+    io::{BufRead, Write},
+};
+
+use crate::hash::{
+    ast::Stmt,
+    evaluator::{Debugger, Evaluator, RuntimeError},
+    parser::Parser,
+    print::stmt_source,
+};
+
+/// The commands of the debugger, shown by `help`.
+const HELP: &str = "\
+step, s          run to the next statement
+next, n          run to the next statement outside the calls made by this one
+continue, c      run to the next breakpoint
+break, b LINE    pause before the statements on a line
+delete, d LINE   remove the breakpoint on a line
+list, l          show the lines around this statement
+vars, v          show the variables
+print, p EXPR    show the value of an expression
+stack, bt        show the calls being made
+quit, q          stop the program";
+
+/// Reports a failure to read a command or write to the output of the debugger.
+fn io_error(error: std::io::Error) -> RuntimeError {
+    RuntimeError::Io("debugger".to_string(), error.to_string())
+}
+
+/// Enum representing when the debugger pauses next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Before the next statement
+    Step,
+    /// Before the next statement made outside the calls of the current one: (call depth)
+    Next(usize),
+    /// Only at breakpoints
+    Continue,
+}
+
+/// Debugger pausing a program to read commands, like `step` or `break 12`, from an input and
+/// write what they show to an output.
+///
+/// The program pauses before its first statement, where breakpoints can be set before it goes
+/// on. Entering an empty line repeats the last command, and the end of the input lets the
+/// program run to its end.
+///
+/// # Examples
+/// ```
+/// use hydrogen::{repl::debug::Console, Engine, Value};
+///
+/// let source = "x = 1\ny = x + 1\ny * 2";
+/// let commands: &[u8] = b"next\nprint x\ncontinue\n";
+/// let mut engine = Engine::new();
+/// engine.set_debugger(Box::new(Console::new(source, commands, Vec::new())));
+///
+/// assert_eq!(engine.eval_str(source).unwrap(), Value::Integer(4));
+/// ```
+pub struct Console<R, W> {
+    lines: Vec<String>,
+    breakpoints: BTreeSet<usize>,
+    mode: Mode,
+    row: Option<usize>,
+    last: String,
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Console<R, W> {
+    /// Creates a new Console for a program.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source code of the program, whose lines are shown when it pauses.
+    /// * `input` - The input the commands are read from.
+    /// * `output` - The output the debugger writes to.
+    pub fn new(source: &str, input: R, output: W) -> Self {
+        Self {
+            lines: source.lines().map(String::from).collect(),
+            breakpoints: BTreeSet::new(),
+            mode: Mode::Step,
+            row: None,
+            last: String::new(),
+            input,
+            output,
+        }
+    }
+
+    /// Writes the line of the source code on the given row, with its number.
+    fn show(&mut self, row: usize, marker: &str) -> std::io::Result<()> {
+        let line = self.lines.get(row - 1).map_or("", String::as_str);
+        writeln!(self.output, "{}{:>4} | {}", marker, row, line)
+    }
+
+    /// Writes the value of an expression evaluated where the program is paused, or why it has
+    /// none.
+    ///
+    /// # Arguments
+    ///
+    /// * `evaluator` - The evaluator of the paused program.
+    /// * `source` - The source code of the expression.
+    fn print(&mut self, evaluator: &mut Evaluator, source: &str) -> std::io::Result<()> {
+        let mut parser = Parser::new(source);
+        let expression = match (parser.parse(), parser.parse()) {
+            (Ok(Some(Stmt::Expr(expression))), Ok(None)) => expression,
+            (Err(error), _) | (_, Err(error)) => return writeln!(self.output, "{}", error),
+            _ => return writeln!(self.output, "usage: print EXPR"),
+        };
+        match evaluator.evaluate(&Stmt::Expr(expression)) {
+            Ok(value) => writeln!(self.output, "{}", value.repr()),
+            Err(error) => {
+                // The calls of the error are not the program's
+                evaluator.take_stack_trace();
+                writeln!(self.output, "{}", error)
+            }
+        }
+    }
+
+    /// Runs a command entered while the program is paused.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<Mode>, RuntimeError>` - The way the program goes on, None to read
+    ///   another command, or the error stopping the program.
+    fn command(
+        &mut self,
+        evaluator: &mut Evaluator,
+        command: &str,
+    ) -> Result<Option<Mode>, RuntimeError> {
+        let mut words = command.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let argument = words.next();
+        let rest = command
+            .trim()
+            .split_once(char::is_whitespace)
+            .map(|(_, rest)| rest);
+        let line = argument.and_then(|argument| argument.parse::<usize>().ok());
+        let depth = evaluator.call_stack().len();
+
+        let written = match (name, argument) {
+            ("step" | "s", _) => return Ok(Some(Mode::Step)),
+            ("next" | "n", _) => return Ok(Some(Mode::Next(depth))),
+            ("continue" | "c", _) => return Ok(Some(Mode::Continue)),
+            ("quit" | "q", _) => return Err(RuntimeError::Interrupted),
+
+            ("break" | "b", _) => match line {
+                Some(line) if line > 0 => {
+                    self.breakpoints.insert(line);
+                    writeln!(self.output, "breakpoint on line {}", line)
+                }
+                _ => writeln!(self.output, "usage: break LINE"),
+            },
+            ("delete" | "d", _) => match line {
+                Some(line) if self.breakpoints.remove(&line) => {
+                    writeln!(self.output, "removed the breakpoint on line {}", line)
+                }
+                _ => writeln!(self.output, "no breakpoint on that line"),
+            },
+
+            ("list" | "l", _) => match self.row {
+                Some(row) => {
+                    let last = self.lines.len().min(row + 2);
+                    (row.saturating_sub(2).max(1)..=last)
+                        .try_for_each(|line| self.show(line, if line == row { "> " } else { "  " }))
+                }
+                None => writeln!(self.output, "the statement has no line"),
+            },
+            ("vars" | "v", _) => evaluator
+                .variables()
+                .iter()
+                .try_for_each(|(name, value)| writeln!(self.output, "{} = {}", name, value.repr())),
+            ("print" | "p", Some(_)) => self.print(evaluator, rest.unwrap_or_default()),
+            ("print" | "p", None) => writeln!(self.output, "usage: print EXPR"),
+            ("stack" | "bt", _) => {
+                let calls = evaluator.call_stack().iter().rev();
+                calls
+                    .map(|name| format!("in `{}`", name))
+                    .chain(["in the program".to_string()])
+                    .try_for_each(|call| writeln!(self.output, "    {}", call))
+            }
+            ("help" | "h", _) => writeln!(self.output, "{}", HELP),
+            _ => writeln!(self.output, "unknown command `{}`, try `help`", name),
+        };
+        written.map_err(io_error)?;
+        Ok(None)
+    }
+}
+
+impl<R: BufRead, W: Write> Debugger for Console<R, W> {
+    fn pause(
+        &mut self,
+        evaluator: &mut Evaluator,
+        row: Option<usize>,
+        statement: &Stmt,
+    ) -> Result<(), RuntimeError> {
+        let breakpoint = row.is_some_and(|row| self.breakpoints.contains(&row));
+        let paused = match self.mode {
+            Mode::Step => true,
+            Mode::Next(depth) => evaluator.call_stack().len() <= depth || breakpoint,
+            Mode::Continue => breakpoint,
+        };
+        if !paused {
+            return Ok(());
+        }
+
+        self.row = row;
+        match row {
+            Some(row) => self.show(row, "").map_err(io_error)?,
+            None => {
+                let source = stmt_source(statement, 0);
+                let first = source.lines().next().unwrap_or_default();
+                writeln!(self.output, "     | {}", first).map_err(io_error)?;
+            }
+        }
+
+        loop {
+            write!(self.output, "(debug) ").map_err(io_error)?;
+            self.output.flush().map_err(io_error)?;
+
+            let mut command = String::new();
+            if self.input.read_line(&mut command).map_err(io_error)? == 0 {
+                // Without more commands the program runs to its end
+                self.breakpoints.clear();
+                self.mode = Mode::Continue;
+                return Ok(());
+            }
+            let command = match command.trim() {
+                "" => self.last.clone(),
+                command => command.to_string(),
+            };
+            self.last = command.clone();
+
+            if let Some(mode) = self.command(evaluator, &command)? {
+                self.mode = mode;
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Engine;
+    use std::{cell::RefCell, rc::Rc};
+
+    /// Writer keeping what is written where the test can read it.
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Runs a program under the debugger with the given commands, returning what it wrote.
+    fn debug(source: &str, commands: &str) -> (Result<(), RuntimeError>, String) {
+        let output = Shared::default();
+        let mut engine = Engine::new();
+        let commands = std::io::Cursor::new(commands.to_string());
+        let console = Console::new(source, commands, output.clone());
+        engine.set_debugger(Box::new(console));
+        let result = match engine.eval_str(source) {
+            Ok(_) => Ok(()),
+            Err(crate::HydrogenError::Runtime(error, _)) => Err(error),
+            Err(error) => panic!("{}", error),
+        };
+        let written = String::from_utf8(output.0.take()).unwrap();
+        (result, written)
+    }
+
+    #[test]
+    fn test_debug() {
+        let source = "double(n) {\n    m = n * 2\n    m\n}\nx = double(2)\ny = x + 1";
+
+        let (result, written) = debug(source, "b 3\nc\nv\nbt\nn\nq\n");
+        assert_eq!(result, Err(RuntimeError::Interrupted));
+        assert_eq!(
+            written,
+            "   1 | double(n) {\n(debug) breakpoint on line 3\n(debug)    3 |     m\n\
+             (debug) m = 4\nn = 2\n(debug)     in `double`\n    in the program\n\
+             (debug)    6 | y = x + 1\n(debug) "
+        );
+
+        let (result, written) = debug(source, "s\n\np n\np z\nl\n");
+        assert_eq!(result, Ok(()));
+        assert!(written.contains("(debug) 2\n(debug) undefined variable `z`\n"));
+        assert_eq!(
+            written,
+            "   1 | double(n) {\n(debug)    5 | x = double(2)\n(debug)    2 |     m = n * 2\n\
+             (debug) 2\n(debug) undefined variable `z`\n\
+             (debug)      1 | double(n) {\n>    2 |     m = n * 2\n     3 |     m\n     4 | }\n\
+             (debug) "
+        );
+
+        let (result, written) = debug(source, "s\ns\np n * 2 + 1\np n = 5\np n\np n n\np (\n");
+        assert_eq!(result, Ok(()));
+        assert!(written.contains(
+            "(debug) 5\n(debug) usage: print EXPR\n(debug) 2\n\
+             (debug) ERROR[H0008]: expected a new line or `;` before Identifier(n)\n"
+        ));
+
+        let (result, written) = debug(source, "break x\nfly\n");
+        assert_eq!(result, Ok(()));
+        assert!(written.contains("usage: break LINE"));
+        assert!(written.contains("unknown command `fly`, try `help`"));
+    }
+}
@@ -0,0 +1,207 @@
+use crossterm::style::Color;
+
+use crate::{
+    hash::{lexer::Lexer, tokens::Token},
+    style::Theme,
+};
+
+/// A run of source text rendered with a single style.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    /// The foreground color of the text, or the terminal default.
+    pub color: Option<Color>,
+    /// The background color of the text, or the terminal default.
+    pub background: Option<Color>,
+    /// The source text covered by the span.
+    pub text: String,
+}
+
+impl Span {
+    /// Creates a new `Span` with the default background.
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - The foreground color of the text.
+    /// * `text` - The source text covered by the span.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new `Span` instance.
+    pub fn new(color: Option<Color>, text: String) -> Self {
+        Self {
+            color,
+            background: None,
+            text,
+        }
+    }
+}
+
+/// A token together with the character range it covers in the source.
+type Lexeme<'a> = (usize, usize, Token<'a>);
+
+/// Lexes the source code and returns every token with its character range.
+fn scan(source: &str) -> Vec<Lexeme<'_>> {
+    // Character index of every byte offset a token can start or end at
+    let mut chars = vec![0; source.len() + 1];
+    for (i, (offset, c)) in source.char_indices().enumerate() {
+        chars[offset..offset + c.len_utf8()].fill(i);
+        chars[offset + c.len_utf8()] = i + 1;
+    }
+
+    Lexer::with_comments(source)
+        .spans()
+        .map(|(token, span)| (chars[span.start], chars[span.end()], token))
+        .collect()
+}
+
+/// Pairs up the brackets of the source code.
+///
+/// # Returns
+///
+/// * `Vec<Option<usize>>` - For every lexeme, the index of the lexeme closing or opening it if
+///   it is a bracket with a partner, None otherwise.
+fn pair(lexemes: &[Lexeme]) -> Vec<Option<usize>> {
+    let mut partners = vec![None; lexemes.len()];
+    let mut stack: Vec<(char, usize)> = Vec::new();
+
+    for (i, (_, _, token)) in lexemes.iter().enumerate() {
+        match token {
+            Token::LeftParenthesis(_) => stack.push((')', i)),
+            Token::LeftBrace(_) => stack.push(('}', i)),
+            Token::LeftBracket(_) => stack.push((']', i)),
+            Token::RightParenthesis(_) | Token::RightBrace(_) | Token::RightBracket(_) => {
+                if let Some(&(close, open)) = stack.last() {
+                    if token.to_string() == close.to_string() {
+                        stack.pop();
+                        partners[i] = Some(open);
+                        partners[open] = Some(i);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    partners
+}
+
+/// Returns true if the token closes a group.
+fn is_closing(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::RightParenthesis(_) | Token::RightBrace(_) | Token::RightBracket(_)
+    )
+}
+
+/// Splits the source code into styled spans using the lexer.
+///
+/// Keywords, types, strings, numbers, and comments get their own colors, and invalid tokens and
+/// closing delimiters without an opening one are rendered red. When the caret sits on or right
+/// after a closing delimiter, it and its matching opening delimiter are highlighted.
+///
+/// # Arguments
+///
+/// * `source` - The source code to be highlighted.
+/// * `caret` - The character index of the caret within the source.
+/// * `theme` - The colors of the syntax.
+///
+/// # Returns
+///
+/// * `Vec<Span>` - The spans that make up the whole source, in order.
+pub fn highlight(source: &str, caret: usize, theme: &Theme) -> Vec<Span> {
+    let chars: Vec<char> = source.chars().collect();
+    let lexemes = scan(source);
+    let partners = pair(&lexemes);
+
+    let selected = lexemes
+        .iter()
+        .position(|(start, _, token)| is_closing(token) && *start + 1 == caret)
+        .or_else(|| {
+            lexemes
+                .iter()
+                .position(|(start, _, token)| is_closing(token) && *start == caret)
+        });
+    let selected = selected.and_then(|i| partners[i].map(|open| [i, open]));
+
+    let mut spans = Vec::new();
+    let mut previous = 0;
+
+    for (i, (start, end, token)) in lexemes.iter().enumerate() {
+        push_gap(&mut spans, &chars[previous..*start]);
+
+        let color = if is_closing(token) && partners[i].is_none() {
+            theme.error
+        } else {
+            color(token, theme)
+        };
+        let mut span = Span::new(color, chars[*start..*end].iter().collect());
+        if selected.is_some_and(|pair| pair.contains(&i)) {
+            span.background = theme.matching;
+        }
+
+        spans.push(span);
+        previous = *end;
+    }
+    push_gap(&mut spans, &chars[previous..]);
+
+    spans
+}
+
+/// Pushes the whitespace between two tokens.
+fn push_gap(spans: &mut Vec<Span>, gap: &[char]) {
+    if !gap.is_empty() {
+        spans.push(Span::new(None, gap.iter().collect()));
+    }
+}
+
+/// Returns the color of the theme a token should be rendered with.
+fn color(token: &Token, theme: &Theme) -> Option<Color> {
+    match token {
+        Token::Keyword(_, _) | Token::In(_) | Token::As(_) => theme.keyword,
+        Token::Type(_, _) => theme.ty,
+        Token::String(_, _) => theme.string,
+        Token::Number(_, _) | Token::Boolean(_, _) => theme.number,
+        Token::Comment(_, _) => theme.comment,
+        Token::Unknown(_, _) | Token::UnterminatedString(_, _) | Token::UnterminatedComment(_) => {
+            theme.error
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_highlight() {
+        let source = "if x == 12 { y: str = \"hi\" } // done ~";
+        let spans = highlight(source, 0, &Theme::DARK);
+
+        let text: String = spans.iter().map(|span| span.text.as_str()).collect();
+        assert_eq!(text, source);
+
+        let span = |color, text: &str| Span::new(Some(color), text.to_string());
+        assert!(spans.contains(&span(Color::Magenta, "if")));
+        assert!(spans.contains(&span(Color::Yellow, "12")));
+        assert!(spans.contains(&span(Color::Cyan, "str")));
+        assert!(spans.contains(&span(Color::Green, "\"hi\"")));
+        assert!(spans.contains(&span(Color::DarkGrey, "// done ~")));
+
+        let spans = highlight("é = \"ñu\" ~", 0, &Theme::DARK);
+        let text: Vec<&str> = spans.iter().map(|span| span.text.as_str()).collect();
+        assert_eq!(text, vec!["é", " ", "=", " ", "\"ñu\"", " ", "~"]);
+
+        let spans = highlight("x = 1 ~", 0, &Theme::DARK);
+        assert_eq!(spans.last(), Some(&span(Color::Red, "~")));
+
+        let spans = highlight("f([1], 2))", 9, &Theme::DARK);
+        let selected: Vec<&str> = spans
+            .iter()
+            .filter(|span| span.background.is_some())
+            .map(|span| span.text.as_str())
+            .collect();
+        assert_eq!(selected, vec!["(", ")"]);
+        assert_eq!(spans.last(), Some(&span(Color::Red, ")")));
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::VecDeque;
+
+/// The lines entered in the REPL, which the arrow keys bring back, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    lines: VecDeque<String>,
+    size: usize,
+    index: Option<usize>, // The line shown, or None while the line being written is
+    draft: String,        // The line being written before moving into the history
+}
+
+impl History {
+    /// Creates an empty history.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - How many lines the history keeps, forgetting the oldest ones.
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            ..Self::default()
+        }
+    }
+
+    /// Adds an entered line, unless it is blank or the same as the line before it, and stops
+    /// browsing.
+    pub fn push(&mut self, line: &str) {
+        self.reset();
+        if line.trim().is_empty() || self.lines.back().is_some_and(|last| last == line) {
+            return;
+        }
+        self.lines.push_back(line.to_string());
+        while self.lines.len() > self.size {
+            self.lines.pop_front();
+        }
+    }
+
+    /// Returns the line before the one shown, if there is one.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The line being written, kept to come back to it.
+    pub fn previous(&mut self, current: &str) -> Option<&str> {
+        let index = match self.index {
+            Some(0) => return None,
+            Some(index) => index - 1,
+            None => {
+                self.draft = current.to_string();
+                self.lines.len().checked_sub(1)?
+            }
+        };
+        self.index = Some(index);
+        Some(&self.lines[index])
+    }
+
+    /// Returns the line after the one shown, or the line being written after the last one.
+    pub fn next(&mut self) -> Option<&str> {
+        let index = self.index? + 1;
+        if index < self.lines.len() {
+            self.index = Some(index);
+            return Some(&self.lines[index]);
+        }
+        self.index = None;
+        Some(&self.draft)
+    }
+
+    /// Returns the most recent line that starts with the line being written and is longer.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The line being written.
+    pub fn suggest(&self, prefix: &str) -> Option<&str> {
+        self.lines
+            .iter()
+            .rev()
+            .find(|line| line.len() > prefix.len() && line.starts_with(prefix))
+            .map(String::as_str)
+    }
+
+    /// Stops browsing, so the next line shown is the last entered one again.
+    pub fn reset(&mut self) {
+        self.index = None;
+        self.draft.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_history() {
+        let mut history = History::new(2);
+        for line in ["a = 1", "", "b = 2", "b = 2", "c = 3"] {
+            history.push(line);
+        }
+
+        assert_eq!(history.next(), None);
+        assert_eq!(history.previous("c"), Some("c = 3"));
+        assert_eq!(history.previous("ignored"), Some("b = 2"));
+        // The oldest line is forgotten
+        assert_eq!(history.previous("ignored"), None);
+        assert_eq!(history.next(), Some("c = 3"));
+        assert_eq!(history.next(), Some("c"));
+        assert_eq!(history.next(), None);
+
+        history.push("d");
+        assert_eq!(history.previous(""), Some("d"));
+
+        history.push("c = 4");
+        assert_eq!(history.suggest("c "), Some("c = 4"));
+        assert_eq!(history.suggest("c = 4"), None);
+        assert_eq!(history.suggest("b"), None);
+    }
+}
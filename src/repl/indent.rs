@@ -0,0 +1,77 @@
+use crate::hash::{lexer::Lexer, tokens::Token};
+
+use super::linebuffer::LineBuffer;
+
+/// The text inserted for every level of nesting.
+const INDENT: &str = "  ";
+
+/// Returns the number of blocks left open in the source code.
+///
+/// # Arguments
+///
+/// * `source` - The source code to be inspected.
+///
+/// # Returns
+///
+/// * `usize` - The number of `{` without a matching `}`.
+pub fn depth(source: &str) -> usize {
+    Lexer::new(source).fold(0, |depth: usize, token| match token {
+        Token::LeftBrace(_) => depth + 1,
+        Token::RightBrace(_) => depth.saturating_sub(1),
+        _ => depth,
+    })
+}
+
+/// Starts a new line indented by the nesting depth at the caret.
+///
+/// # Arguments
+///
+/// * `line` - The line buffer to continue.
+pub fn newline(line: &mut LineBuffer) {
+    let before: String = line.buffer.chars().take(line.index).collect();
+    let indent = INDENT.repeat(depth(&before));
+    line.insert('\n');
+    indent.chars().for_each(|c| line.insert(c));
+}
+
+/// Inserts a character, removing one level of indentation first when it closes a block at the
+/// start of a line.
+///
+/// # Arguments
+///
+/// * `line` - The line buffer to insert into.
+/// * `c` - The character to be inserted.
+pub fn insert(line: &mut LineBuffer, c: char) {
+    if c == '}' {
+        let before: String = line.buffer.chars().take(line.index).collect();
+        let current = before.rsplit('\n').next().unwrap_or_default();
+        if current.len() >= INDENT.len() && current.trim().is_empty() {
+            (0..INDENT.len()).for_each(|_| line.backspace());
+        }
+    }
+    line.insert(c);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_indent() {
+        assert_eq!(depth("main() { if x { \"{\" // {"), 2);
+        assert_eq!(depth("main() { }"), 0);
+
+        let mut line = LineBuffer::new();
+        line.insert_str("main() {");
+        newline(&mut line);
+        line.insert_str("if x {");
+        newline(&mut line);
+        assert_eq!(line.buffer, "main() {\n  if x {\n    ");
+
+        insert(&mut line, '}');
+        newline(&mut line);
+        insert(&mut line, '}');
+        assert_eq!(line.buffer, "main() {\n  if x {\n  }\n}");
+        assert_eq!(depth(&line.buffer), 0);
+    }
+}
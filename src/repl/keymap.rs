@@ -0,0 +1,440 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::repl::mode::CursorMode;
+use crate::repl::prompt::ViMode;
+
+/// Enum representing what a key does to the input of the REPL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Inserts a character typed without Ctrl or Alt: (character)
+    Insert(char),
+    /// Completes the name before the caret, or shows the next completion
+    Complete,
+    /// Evaluates the input, or starts a new line if a bracket of it is still open
+    AcceptLine,
+    /// Starts a new line of the input
+    Newline,
+    /// Removes the character before the caret
+    Backspace,
+    /// Removes the character under the caret
+    Delete,
+    /// Moves the caret one character to the left
+    MoveLeft,
+    /// Moves the caret one character to the right
+    MoveRight,
+    /// Moves the caret to the start of its line
+    LineStart,
+    /// Moves the caret to the end of its line
+    LineEnd,
+    /// Brings back the line entered before the one shown
+    HistoryPrevious,
+    /// Brings back the line entered after the one shown
+    HistoryNext,
+    /// Clears the screen, keeping the input
+    ClearScreen,
+    /// Ends the REPL
+    Exit,
+    /// Makes keys commands, in the Vi cursor mode
+    NormalMode,
+    /// Makes keys inserted, in the Vi cursor mode
+    InsertMode,
+    /// Makes keys inserted after the caret, in the Vi cursor mode
+    Append,
+    /// Removes the text from the caret to the end of its line into the kill ring, or the line
+    /// break if the caret is at the end
+    KillToEnd,
+    /// Removes the text from the start of the line to the caret into the kill ring
+    KillToStart,
+    /// Removes the line of the caret into the kill ring
+    KillLine,
+    /// Inserts the text killed last, or the text of the clipboard
+    Yank,
+    /// Replaces the text just yanked with the text killed before it
+    YankPop,
+    /// Takes back the last edit of the input
+    Undo,
+    /// Makes the last edit taken back again
+    Redo,
+}
+
+/// The actions a key can be bound to by name, every one but inserting a character.
+const ACTIONS: [(&str, Action); 23] = [
+    ("complete", Action::Complete),
+    ("accept-line", Action::AcceptLine),
+    ("newline", Action::Newline),
+    ("backspace", Action::Backspace),
+    ("delete", Action::Delete),
+    ("move-left", Action::MoveLeft),
+    ("move-right", Action::MoveRight),
+    ("line-start", Action::LineStart),
+    ("line-end", Action::LineEnd),
+    ("history-previous", Action::HistoryPrevious),
+    ("history-next", Action::HistoryNext),
+    ("clear-screen", Action::ClearScreen),
+    ("exit", Action::Exit),
+    ("normal-mode", Action::NormalMode),
+    ("insert-mode", Action::InsertMode),
+    ("append", Action::Append),
+    ("kill-to-end", Action::KillToEnd),
+    ("kill-to-start", Action::KillToStart),
+    ("kill-line", Action::KillLine),
+    ("yank", Action::Yank),
+    ("yank-pop", Action::YankPop),
+    ("undo", Action::Undo),
+    ("redo", Action::Redo),
+];
+
+/// The keys with a name, besides the characters.
+const KEYS: [(&str, KeyCode); 12] = [
+    ("enter", KeyCode::Enter),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("esc", KeyCode::Esc),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("space", KeyCode::Char(' ')),
+];
+
+impl Action {
+    /// Returns the action of a name, like `clear-screen`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the action.
+    pub fn named(name: &str) -> Option<Self> {
+        ACTIONS
+            .iter()
+            .find(|(action, _)| *action == name)
+            .map(|(_, action)| *action)
+    }
+}
+
+/// A key pressed with its modifiers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    /// The key.
+    pub code: KeyCode,
+    /// The modifiers held with the key, without Shift for characters.
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    /// Creates a key, leaving out Shift for characters, which already tell whether it was held.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - The key.
+    /// * `modifiers` - The modifiers held with the key.
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+
+    /// Parses a key written like `ctrl-l`, `alt-enter`, `up`, or `x`.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The modifiers, each followed by `-`, then a character or the name of a key.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Key, String>` - The key, or why the text is not one.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text;
+        loop {
+            let (modifier, after) = match rest.split_once('-') {
+                Some((modifier, after)) if !after.is_empty() => (modifier, after),
+                _ => break,
+            };
+            modifiers |= match modifier {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier `{}` in `{}`", modifier, text)),
+            };
+            rest = after;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match KEYS.iter().find(|(name, _)| *name == rest) {
+                Some((_, code)) => *code,
+                None => return Err(format!("unknown key `{}`", text)),
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+
+    /// Parses keys pressed one after the other, separated by spaces, like `d d`.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The keys, each written like for [`Key::parse`].
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Key>, String>` - The keys, or why the text is not a sequence of keys.
+    pub fn parse_sequence(text: &str) -> Result<Vec<Self>, String> {
+        let keys = text
+            .split_whitespace()
+            .map(Key::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        match keys.is_empty() {
+            true => Err("expected a key".to_string()),
+            false => Ok(keys),
+        }
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+/// Keys bound to an action, or unbound, by the configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    /// Whether the keys are bound in the normal mode of the Vi cursor mode, instead of while
+    /// editing.
+    pub normal: bool,
+    /// The keys, pressed one after the other.
+    pub keys: Vec<Key>,
+    /// The action of the key, or None to leave the key without one.
+    pub action: Option<Action>,
+}
+
+/// The actions of the keys of a cursor mode.
+///
+/// An action belongs to a key, or to keys pressed one after the other, like `d d`. Keys are
+/// looked up in the table of the normal mode while the Vi cursor mode is in it, and in
+/// the table of editing otherwise, where characters typed without Ctrl or Alt that are not bound
+/// are inserted.
+#[derive(Debug, Clone, Default)]
+pub struct Keymap {
+    editing: HashMap<Vec<Key>, Action>,
+    normal: HashMap<Vec<Key>, Action>,
+}
+
+impl Keymap {
+    /// Creates the default keymap of a cursor mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The cursor mode: the Emacs mode adds Ctrl keys moving the caret, and the Vi
+    ///   mode adds its normal mode, entered with Esc.
+    pub fn new(mode: &CursorMode) -> Self {
+        let mut keymap = Self::default();
+        let none = KeyModifiers::NONE;
+        let ctrl = KeyModifiers::CONTROL;
+        let common = [
+            (KeyCode::Tab, none, Action::Complete),
+            (KeyCode::Enter, none, Action::AcceptLine),
+            (KeyCode::Enter, KeyModifiers::ALT, Action::Newline),
+            (KeyCode::Backspace, none, Action::Backspace),
+            (KeyCode::Delete, none, Action::Delete),
+            (KeyCode::Left, none, Action::MoveLeft),
+            (KeyCode::Right, none, Action::MoveRight),
+            (KeyCode::Home, none, Action::LineStart),
+            (KeyCode::End, none, Action::LineEnd),
+            (KeyCode::Up, none, Action::HistoryPrevious),
+            (KeyCode::Down, none, Action::HistoryNext),
+            (KeyCode::Char('d'), ctrl, Action::Exit),
+            (KeyCode::Char('l'), ctrl, Action::ClearScreen),
+            (KeyCode::Char('k'), ctrl, Action::KillToEnd),
+            (KeyCode::Char('u'), ctrl, Action::KillToStart),
+            (KeyCode::Char('y'), ctrl, Action::Yank),
+            (KeyCode::Char('_'), ctrl, Action::Undo),
+            // Terminals send Ctrl-_ as the same byte as Ctrl-7
+            (KeyCode::Char('7'), ctrl, Action::Undo),
+            (KeyCode::Char('_'), KeyModifiers::ALT, Action::Redo),
+        ];
+        for (code, modifiers, action) in common {
+            keymap
+                .editing
+                .insert(vec![Key::new(code, modifiers)], action);
+        }
+
+        match mode {
+            CursorMode::Normal => {}
+            CursorMode::Emacs => {
+                let emacs = [
+                    ('a', Action::LineStart),
+                    ('e', Action::LineEnd),
+                    ('b', Action::MoveLeft),
+                    ('f', Action::MoveRight),
+                    ('p', Action::HistoryPrevious),
+                    ('n', Action::HistoryNext),
+                ];
+                for (c, action) in emacs {
+                    let key = Key::new(KeyCode::Char(c), ctrl);
+                    keymap.editing.insert(vec![key], action);
+                }
+                let alt_y = Key::new(KeyCode::Char('y'), KeyModifiers::ALT);
+                keymap.editing.insert(vec![alt_y], Action::YankPop);
+            }
+            CursorMode::Vi => {
+                let escape = vec![Key::new(KeyCode::Esc, none)];
+                keymap.editing.insert(escape.clone(), Action::NormalMode);
+
+                // The normal mode has the keys of editing that are not characters
+                keymap.normal = keymap.editing.clone();
+                keymap.normal.remove(&escape);
+                let vi = [
+                    ('i', Action::InsertMode),
+                    ('a', Action::Append),
+                    ('h', Action::MoveLeft),
+                    ('l', Action::MoveRight),
+                    ('k', Action::HistoryPrevious),
+                    ('j', Action::HistoryNext),
+                    ('0', Action::LineStart),
+                    ('$', Action::LineEnd),
+                    ('x', Action::Delete),
+                    ('D', Action::KillToEnd),
+                    ('p', Action::Yank),
+                ];
+                for (c, action) in vi {
+                    let key = Key::new(KeyCode::Char(c), none);
+                    keymap.normal.insert(vec![key], action);
+                }
+                let d = Key::new(KeyCode::Char('d'), none);
+                keymap.normal.insert(vec![d, d], Action::KillLine);
+                let u = Key::new(KeyCode::Char('u'), none);
+                keymap.normal.insert(vec![u], Action::Undo);
+                let ctrl_r = Key::new(KeyCode::Char('r'), ctrl);
+                keymap.normal.insert(vec![ctrl_r], Action::Redo);
+            }
+        }
+        keymap
+    }
+
+    /// Binds keys to an action, or leaves them without one, replacing their action before.
+    pub fn bind(&mut self, binding: &Binding) {
+        let table = match binding.normal {
+            true => &mut self.normal,
+            false => &mut self.editing,
+        };
+        match binding.action {
+            Some(action) => table.insert(binding.keys.clone(), action),
+            None => table.remove(&binding.keys),
+        };
+    }
+
+    /// Returns the table of the keys of a mode of the Vi cursor mode.
+    fn table(&self, mode: Option<ViMode>) -> &HashMap<Vec<Key>, Action> {
+        match mode {
+            Some(ViMode::Normal) => &self.normal,
+            _ => &self.editing,
+        }
+    }
+
+    /// Returns the action of keys, if they have one.
+    ///
+    /// # Arguments
+    ///
+    /// * `keys` - The keys pressed, one after the other.
+    /// * `mode` - The mode of the Vi cursor mode, or None in the other cursor modes.
+    pub fn action(&self, keys: &[Key], mode: Option<ViMode>) -> Option<Action> {
+        if let Some(action) = self.table(mode).get(keys) {
+            return Some(*action);
+        }
+        match keys {
+            [key] if mode != Some(ViMode::Normal) => match key.code {
+                KeyCode::Char(c)
+                    if !key
+                        .modifiers
+                        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+                {
+                    Some(Action::Insert(c))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns whether keys are the start of longer keys with an action, so the next key has to
+    /// be waited for.
+    ///
+    /// # Arguments
+    ///
+    /// * `keys` - The keys pressed, one after the other.
+    /// * `mode` - The mode of the Vi cursor mode, or None in the other cursor modes.
+    pub fn is_prefix(&self, keys: &[Key], mode: Option<ViMode>) -> bool {
+        self.table(mode)
+            .keys()
+            .any(|bound| bound.len() > keys.len() && bound.starts_with(keys))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_keymap() {
+        let key = |text| Key::parse(text).unwrap();
+        assert_eq!(
+            key("ctrl-alt-x"),
+            Key::new(
+                KeyCode::Char('x'),
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            )
+        );
+        assert_eq!(key("-"), Key::new(KeyCode::Char('-'), KeyModifiers::NONE));
+        assert_eq!(key("shift-X"), key("X"));
+        assert!(Key::parse("hyper-x").is_err());
+        assert!(Key::parse("pageup").is_err());
+
+        let emacs = Keymap::new(&CursorMode::Emacs);
+        assert_eq!(
+            emacs.action(&[key("ctrl-a")], None),
+            Some(Action::LineStart)
+        );
+        assert_eq!(emacs.action(&[key("a")], None), Some(Action::Insert('a')));
+        assert_eq!(emacs.action(&[key("ctrl-q")], None), None);
+
+        let mut vi = Keymap::new(&CursorMode::Vi);
+        let normal = Some(ViMode::Normal);
+        let insert = Some(ViMode::Insert);
+        assert_eq!(vi.action(&[key("esc")], insert), Some(Action::NormalMode));
+        assert_eq!(vi.action(&[key("h")], normal), Some(Action::MoveLeft));
+        assert_eq!(vi.action(&[key("enter")], normal), Some(Action::AcceptLine));
+        assert_eq!(vi.action(&[key("q")], normal), None);
+
+        // Keys pressed one after the other wait for the next one
+        let dd = Key::parse_sequence("d d").unwrap();
+        assert!(vi.is_prefix(&dd[..1], normal));
+        assert_eq!(vi.action(&dd[..1], normal), None);
+        assert_eq!(vi.action(&dd, normal), Some(Action::KillLine));
+        assert!(!vi.is_prefix(&dd[..1], insert));
+        assert!(Key::parse_sequence(" ").is_err());
+
+        // Bindings replace and remove the actions of keys
+        vi.bind(&Binding {
+            normal: true,
+            keys: vec![key("q")],
+            action: Action::named("exit"),
+        });
+        vi.bind(&Binding {
+            normal: false,
+            keys: vec![key("ctrl-l")],
+            action: None,
+        });
+        assert_eq!(vi.action(&[key("q")], normal), Some(Action::Exit));
+        assert_eq!(vi.action(&[key("ctrl-l")], insert), None);
+        assert_eq!(Action::named("paste"), None);
+    }
+}
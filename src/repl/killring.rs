@@ -0,0 +1,97 @@
+use std::collections::VecDeque;
+
+/// How many killed texts the kill ring keeps.
+const KILL_RING_SIZE: usize = 32;
+
+/// The texts removed from the input of the REPL, which can be yanked back, oldest first.
+///
+/// With the `clipboard` feature, killed text is copied to the clipboard of the system too, and
+/// text copied to the clipboard by other programs is yanked before the killed texts.
+pub struct KillRing {
+    texts: VecDeque<String>,
+    index: usize, // The text yanked last, which yanking again pops to the one before it
+    #[cfg(feature = "clipboard")]
+    clipboard: Option<arboard::Clipboard>,
+}
+
+impl KillRing {
+    /// Creates an empty kill ring, connected to the clipboard of the system if there is one.
+    pub fn new() -> Self {
+        Self {
+            texts: VecDeque::new(),
+            index: 0,
+            #[cfg(feature = "clipboard")]
+            clipboard: arboard::Clipboard::new().ok(),
+        }
+    }
+
+    /// Adds a killed text, unless it is empty.
+    pub fn kill(&mut self, text: String) {
+        if text.is_empty() {
+            return;
+        }
+        #[cfg(feature = "clipboard")]
+        if let Some(clipboard) = &mut self.clipboard {
+            // A clipboard that cannot be written to only leaves the text in the ring
+            let _ = clipboard.set_text(text.clone());
+        }
+        self.push(text);
+    }
+
+    /// Adds a text as the most recent one, forgetting the oldest ones past the size of the ring.
+    fn push(&mut self, text: String) {
+        self.texts.push_back(text);
+        while self.texts.len() > KILL_RING_SIZE {
+            self.texts.pop_front();
+        }
+    }
+
+    /// Returns the text to yank: the text of the clipboard if another program changed it, or the
+    /// text killed last.
+    pub fn yank(&mut self) -> Option<&str> {
+        #[cfg(feature = "clipboard")]
+        if let Some(text) = self.clipboard.as_mut().and_then(|c| c.get_text().ok()) {
+            if !text.is_empty() && self.texts.back() != Some(&text) {
+                self.push(text);
+            }
+        }
+        self.index = self.texts.len().checked_sub(1)?;
+        self.texts.get(self.index).map(String::as_str)
+    }
+
+    /// Returns the text killed before the one yanked last, going around to the most recent one
+    /// after the oldest.
+    pub fn pop(&mut self) -> Option<&str> {
+        if self.texts.is_empty() {
+            return None;
+        }
+        self.index = self.index.checked_sub(1).unwrap_or(self.texts.len() - 1);
+        self.texts.get(self.index).map(String::as_str)
+    }
+}
+
+#[cfg(all(test, not(feature = "clipboard")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_kill_ring() {
+        let mut ring = KillRing::new();
+        assert_eq!(ring.yank(), None);
+        assert_eq!(ring.pop(), None);
+
+        ring.kill("one".to_string());
+        ring.kill(String::new());
+        ring.kill("two".to_string());
+        assert_eq!(ring.yank(), Some("two"));
+        assert_eq!(ring.pop(), Some("one"));
+        assert_eq!(ring.pop(), Some("two"));
+        assert_eq!(ring.yank(), Some("two"));
+
+        for i in 0..KILL_RING_SIZE {
+            ring.kill(i.to_string());
+        }
+        assert_eq!(ring.texts.len(), KILL_RING_SIZE);
+        assert_eq!(ring.texts.front().map(String::as_str), Some("0"));
+    }
+}
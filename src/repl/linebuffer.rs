@@ -1,12 +1,37 @@
+use unicode_width::UnicodeWidthChar;
+
 use super::cell::Cell;
 
+/// The text of a buffer and the character index of its caret, as they were before an edit.
+pub type Snapshot = (String, usize);
+
 /// Represents a line buffer in the Read-Eval-Print Loop (REPL).
 #[derive(Debug)]
 pub struct LineBuffer {
     /// The string buffer containing the input line.
     pub buffer: String,
     /// The caret position within the line buffer, represented by a cell in the terminal.
+    ///
+    /// This is the visual position: the column is the terminal column and the row is relative to
+    /// the first row of input, counting the rows created by wrapping long lines.
     pub caret: Cell,
+    /// The logical caret position: the column is the character within the current line and the
+    /// row is the line within the buffer, both starting at 0.
+    pub position: Cell,
+    /// The character index of the caret within the buffer.
+    pub index: usize,
+    /// The terminal cell where the input starts, right after the prompt.
+    pub origin: Cell,
+    /// The caret position as it was last drawn on the terminal.
+    pub drawn: Cell,
+    /// The width of the terminal in columns.
+    pub width: u16,
+    /// The buffer before every edit that can be undone, oldest first.
+    undos: Vec<Snapshot>,
+    /// The buffer before every edit undone, which can be redone, last undone last.
+    redos: Vec<Snapshot>,
+    /// Whether the last edit was typing a character, so typing more is undone with it.
+    typing: bool,
 }
 
 impl LineBuffer {
@@ -18,6 +43,339 @@ impl LineBuffer {
     pub fn new() -> Self {
         LineBuffer::default()
     }
+
+    /// Returns the byte offset of the given character index within the buffer.
+    fn offset(&self, index: usize) -> usize {
+        self.buffer
+            .char_indices()
+            .nth(index)
+            .map(|(offset, _)| offset)
+            .unwrap_or(self.buffer.len())
+    }
+
+    /// Returns the number of characters in the buffer.
+    pub fn len(&self) -> usize {
+        self.buffer.chars().count()
+    }
+
+    /// Returns the number of columns available to the input on every terminal row.
+    pub fn available(&self) -> usize {
+        (self.width as usize + 1)
+            .saturating_sub(self.origin.col as usize)
+            .max(1)
+    }
+
+    /// Returns the number of terminal rows the input occupies after the first one.
+    pub fn rows(&self) -> u16 {
+        let available = self.available();
+        let rows: usize = self
+            .buffer
+            .split('\n')
+            .map(|line| end(line, available).0 + 1)
+            .sum();
+        (rows - 1) as u16
+    }
+
+    /// Recomputes the logical and visual caret cells from the character index.
+    ///
+    /// Every line of the buffer is drawn starting at the origin column, and lines wider than
+    /// the available width wrap onto the following rows.
+    fn update_caret(&mut self) {
+        let available = self.available();
+        let before: String = self.buffer.chars().take(self.index).collect();
+        let lines: Vec<&str> = before.split('\n').collect();
+
+        let last = lines.last().copied().unwrap_or_default();
+        let rows: usize = lines[..lines.len() - 1]
+            .iter()
+            .map(|line| end(line, available).0 + 1)
+            .sum();
+        let (row, col) = end(last, available);
+
+        self.position = Cell::new(last.chars().count() as u16, (lines.len() - 1) as u16);
+        self.caret = Cell::new(self.origin.col + col as u16, (rows + row) as u16);
+    }
+
+    /// Updates the terminal width and recomputes the caret.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The new width of the terminal in columns.
+    pub fn resize(&mut self, width: u16) {
+        self.width = width;
+        self.update_caret();
+    }
+
+    /// Inserts a character at the caret and moves the caret past it.
+    ///
+    /// # Arguments
+    ///
+    /// * `c` - The character to be inserted.
+    pub fn insert(&mut self, c: char) {
+        let offset = self.offset(self.index);
+        self.buffer.insert(offset, c);
+        self.move_right();
+    }
+
+    /// Inserts a whole string at the caret as a single unit, normalizing line endings.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to be inserted.
+    pub fn insert_str(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let offset = self.offset(self.index);
+        self.buffer.insert_str(offset, &text);
+        self.index += text.chars().count();
+        self.update_caret();
+    }
+
+    /// Removes the character before the caret, if any.
+    pub fn backspace(&mut self) {
+        if self.index > 0 {
+            self.move_left();
+            self.delete();
+        }
+    }
+
+    /// Removes the character under the caret, if any.
+    pub fn delete(&mut self) {
+        if self.index < self.len() {
+            let offset = self.offset(self.index);
+            self.buffer.remove(offset);
+        }
+    }
+
+    /// Moves the caret one character to the left.
+    pub fn move_left(&mut self) {
+        if self.index > 0 {
+            self.index -= 1;
+            self.update_caret();
+        }
+    }
+
+    /// Moves the caret one character to the right.
+    pub fn move_right(&mut self) {
+        if self.index < self.len() {
+            self.index += 1;
+            self.update_caret();
+        }
+    }
+
+    /// Returns the character index where the line of the caret starts.
+    fn line_start(&self) -> usize {
+        let chars: Vec<char> = self.buffer.chars().collect();
+        let mut index = self.index;
+        while index > 0 && chars[index - 1] != '\n' {
+            index -= 1;
+        }
+        index
+    }
+
+    /// Returns the character index where the line of the caret ends, before its line break.
+    fn line_end(&self) -> usize {
+        let chars: Vec<char> = self.buffer.chars().collect();
+        let mut index = self.index;
+        while index < chars.len() && chars[index] != '\n' {
+            index += 1;
+        }
+        index
+    }
+
+    /// Moves the caret to the start of its line.
+    pub fn move_home(&mut self) {
+        self.index = self.line_start();
+        self.update_caret();
+    }
+
+    /// Moves the caret to the end of its line.
+    pub fn move_end(&mut self) {
+        self.index = self.line_end();
+        self.update_caret();
+    }
+
+    /// Removes the characters between two character indexes and moves the caret to where they
+    /// were.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - The index of the first character removed.
+    /// * `end` - The index after the last character removed.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The characters removed.
+    pub fn remove(&mut self, start: usize, end: usize) -> String {
+        let (from, to) = (self.offset(start), self.offset(end));
+        let removed = self.buffer[from..to].to_string();
+        self.buffer.replace_range(from..to, "");
+        self.index = start;
+        self.update_caret();
+        removed
+    }
+
+    /// Removes the text from the caret to the end of its line, or the line break after the caret
+    /// if it is at the end.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The text removed.
+    pub fn kill_to_end(&mut self) -> String {
+        let end = match self.line_end() {
+            end if end == self.index && end < self.len() => end + 1,
+            end => end,
+        };
+        self.remove(self.index, end)
+    }
+
+    /// Removes the text from the start of the line of the caret to the caret.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The text removed.
+    pub fn kill_to_start(&mut self) -> String {
+        self.remove(self.line_start(), self.index)
+    }
+
+    /// Removes the line of the caret with a line break around it, if the buffer has other lines.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The text removed.
+    pub fn kill_line(&mut self) -> String {
+        let (start, end) = (self.line_start(), self.line_end());
+        if end < self.len() {
+            self.remove(start, end + 1)
+        } else {
+            self.remove(start.saturating_sub(1), end)
+        }
+    }
+
+    /// Replaces the whole buffer, like with a line of the history, and moves the caret to its
+    /// end.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The new content of the buffer.
+    pub fn replace(&mut self, text: &str) {
+        self.buffer = text.to_string();
+        self.index = self.len();
+        self.update_caret();
+    }
+
+    /// Returns the buffer as it is, to be recorded after an edit with [`LineBuffer::record`].
+    pub fn snapshot(&self) -> Snapshot {
+        (self.buffer.clone(), self.index)
+    }
+
+    /// Records an edit that can be undone, if the buffer changed since a snapshot was taken.
+    ///
+    /// Characters typed one after the other are undone together, until the caret is moved or
+    /// another edit is made.
+    ///
+    /// # Arguments
+    ///
+    /// * `before` - The snapshot of the buffer before the edit.
+    /// * `typing` - Whether the edit is typing a character.
+    pub fn record(&mut self, before: Snapshot, typing: bool) {
+        if before.0 == self.buffer {
+            self.typing = false;
+            return;
+        }
+        if !(typing && self.typing) {
+            self.undos.push(before);
+        }
+        self.redos.clear();
+        self.typing = typing;
+    }
+
+    /// Moves the buffer from one stack of edits to the other, returning whether one was there.
+    fn restore(&mut self, undo: bool) -> bool {
+        let (from, to) = match undo {
+            true => (&mut self.undos, &mut self.redos),
+            false => (&mut self.redos, &mut self.undos),
+        };
+        let Some((buffer, index)) = from.pop() else {
+            return false;
+        };
+        to.push((std::mem::replace(&mut self.buffer, buffer), self.index));
+        self.index = index;
+        self.typing = false;
+        self.update_caret();
+        true
+    }
+
+    /// Takes back the last edit recorded, returning whether there was one.
+    pub fn undo(&mut self) -> bool {
+        self.restore(true)
+    }
+
+    /// Makes the last edit undone again, returning whether there was one.
+    pub fn redo(&mut self) -> bool {
+        self.restore(false)
+    }
+
+    /// Clears the buffer, with its edits, and resets the caret to the origin.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.undos.clear();
+        self.redos.clear();
+        self.typing = false;
+        self.index = 0;
+        self.update_caret();
+        self.drawn = self.caret.clone();
+    }
+
+    /// Starts a new input at the given terminal cell.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - The terminal cell right after the prompt.
+    pub fn start(&mut self, origin: Cell) {
+        self.origin = origin;
+        self.clear();
+    }
+}
+
+/// Moves a terminal column past a character drawn at it, in rows of the available width.
+///
+/// Wide characters take two columns, and one that does not fit on the rest of a row is drawn at
+/// the start of the next row, as terminals do.
+///
+/// # Arguments
+///
+/// * `col` - The column, starting at 0, moved past the character.
+/// * `c` - The character drawn.
+/// * `available` - The number of columns of every row.
+///
+/// # Returns
+///
+/// * `(bool, bool)` - Whether the character starts a new row, and whether its row is full after
+///   it.
+pub(crate) fn wrap(col: &mut usize, c: char, available: usize) -> (bool, bool) {
+    // Control characters, which have no width of their own, take one column like in positions
+    let width = c.width().unwrap_or(1);
+    let before = *col > 0 && *col + width > available;
+    if before {
+        *col = 0;
+    }
+    *col += width;
+    let after = *col >= available;
+    if after {
+        *col = 0;
+    }
+    (before, after)
+}
+
+/// Returns the row and column, starting at 0, after a line of text drawn in rows of the
+/// available width, see [`wrap`].
+fn end(line: &str, available: usize) -> (usize, usize) {
+    let (mut row, mut col) = (0, 0);
+    for c in line.chars() {
+        let (before, after) = wrap(&mut col, c, available);
+        row += before as usize + after as usize;
+    }
+    (row, col)
 }
 
 impl Default for LineBuffer {
@@ -29,7 +387,106 @@ impl Default for LineBuffer {
     fn default() -> Self {
         Self {
             buffer: String::new(),
-            caret: Cell::default(),
+            caret: Cell::new(1, 0),
+            position: Cell::new(0, 0),
+            index: 0,
+            origin: Cell::default(),
+            drawn: Cell::new(1, 0),
+            width: u16::MAX,
+            undos: Vec::new(),
+            redos: Vec::new(),
+            typing: false,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_linebuffer() {
+        let mut line = LineBuffer::new();
+        line.start(Cell::new(3, 1));
+
+        line.insert_str("a = 1\r\nb = 2");
+        assert_eq!(line.buffer, "a = 1\nb = 2");
+        assert_eq!(line.caret, Cell::new(8, 1));
+        assert_eq!(line.rows(), 1);
+
+        (0..6).for_each(|_| line.move_left());
+        assert_eq!(line.caret, Cell::new(8, 0));
+
+        line.backspace();
+        line.delete();
+        assert_eq!(line.buffer, "a = b = 2");
+        assert_eq!(line.caret, Cell::new(7, 0));
+
+        line.resize(7);
+        assert_eq!(line.available(), 5);
+        assert_eq!(line.caret, Cell::new(7, 0));
+
+        line.move_right();
+        assert_eq!(line.caret, Cell::new(3, 1));
+        assert_eq!(line.position, Cell::new(5, 0));
+        assert_eq!(line.rows(), 1);
+
+        line.move_home();
+        assert_eq!(line.index, 0);
+        line.move_end();
+        assert_eq!(line.index, line.len());
+
+        line.replace("a = 1\nb = 2\nc");
+        line.index = 7;
+        assert_eq!(line.kill_to_end(), " = 2");
+        assert_eq!(line.kill_to_end(), "\n");
+        assert_eq!(line.kill_to_start(), "b");
+        assert_eq!(line.kill_line(), "\nc");
+        assert_eq!(line.kill_line(), "a = 1");
+        assert_eq!(line.buffer, "");
+
+        // Typing is undone at once, and other edits one at a time
+        line.clear();
+        for c in "ab".chars() {
+            let before = line.snapshot();
+            line.insert(c);
+            line.record(before, true);
+        }
+        let before = line.snapshot();
+        line.backspace();
+        line.record(before, false);
+        assert!(line.undo());
+        assert_eq!(line.buffer, "ab");
+        assert!(line.undo());
+        assert_eq!(line.buffer, "");
+        assert!(!line.undo());
+        assert!(line.redo());
+        assert_eq!((line.buffer.as_str(), line.index), ("ab", 2));
+    }
+
+    #[test]
+    fn test_wide_characters() {
+        let mut line = LineBuffer::new();
+        line.start(Cell::new(3, 1));
+
+        // Wide characters take two columns, and combining marks none
+        line.insert_str("名前 = e\u{301}");
+        assert_eq!(line.caret, Cell::new(11, 0));
+        assert_eq!(line.position, Cell::new(7, 0));
+
+        line.replace("ab名前");
+        line.resize(6);
+        assert_eq!(line.available(), 4);
+        assert_eq!((line.caret.clone(), line.rows()), (Cell::new(5, 1), 1));
+
+        // A wide character that does not fit on the rest of a row starts the next one
+        line.replace("a名前");
+        assert_eq!((line.caret.clone(), line.rows()), (Cell::new(5, 1), 1));
+        line.move_left();
+        assert_eq!(line.caret, Cell::new(6, 0));
+
+        // The caret after a full row is at the start of the next one
+        line.replace("名前");
+        assert_eq!((line.caret.clone(), line.rows()), (Cell::new(3, 1), 1));
+    }
+}
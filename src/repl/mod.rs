@@ -1,227 +1,544 @@
-use std::io::{stdout, Result, Stdout, Write};
+use std::io::{Error, Result, Stdout, Write};
+use std::sync::{atomic::AtomicBool, Arc};
+use std::time::Instant;
 
-use crossterm::cursor::{position, MoveToColumn};
-use crossterm::event::KeyModifiers;
-use crossterm::terminal;
+use crossterm::cursor::{position, MoveDown, MoveTo, MoveToColumn, MoveUp};
+use crossterm::terminal::{self, Clear, ClearType};
 use crossterm::{
-    event::{read, Event, KeyCode, KeyEvent},
-    style::{Color, Print, ResetColor, SetForegroundColor},
+    event::{read, DisableBracketedPaste, EnableBracketedPaste, Event},
     ExecutableCommand, QueueableCommand,
 };
 
-use crate::hash::evaluator::Evaluator;
+use crate::config::Config;
+use crate::hash::value::Value;
 use crate::repl::cell::Cell;
-use crate::repl::linebuffer::LineBuffer;
+use crate::repl::command::Command;
+use crate::repl::completion::Completer;
+use crate::repl::highlight::{highlight, Span};
+use crate::repl::history::History;
+use crate::repl::keymap::{Action, Key, Keymap};
+use crate::repl::killring::KillRing;
+use crate::repl::linebuffer::{wrap, LineBuffer};
 use crate::repl::mode::CursorMode;
+use crate::repl::prompt::{Prompt, State, ViMode};
+use crate::style::{self, Styled, Theme};
+use crate::{Engine, HydrogenError};
 
 /// Module containing REPL-related functionality.
 mod cell;
+/// Module containing the commands entered after a colon.
+mod command;
+/// Module containing tab completion for the input line.
+mod completion;
+/// Module containing the debugger pausing programs to step through them.
+pub mod debug;
+/// Module containing syntax highlighting for the input line.
+mod highlight;
+/// Module containing the lines entered before, brought back with the arrow keys.
+mod history;
+/// Module containing automatic indentation of multi-line input.
+mod indent;
+/// Module containing the actions of the keys of the REPL, which the configuration can change.
+pub mod keymap;
+/// Module containing the texts removed from the input, which can be yanked back.
+mod killring;
 /// Module containing line buffer implementation.
 mod linebuffer;
 /// Module containing cursor modes for the REPL.
-mod mode;
+pub mod mode;
+/// Module containing the prompt of the REPL, made from a template of the configuration.
+pub mod prompt;
 
-/// Prints a message to the standard output with proper formatting.
+/// Displays the REPL prompt.
 ///
 /// # Arguments
 ///
 /// * `stdout` - The standard output.
-/// * `msg` - The message to be printed.
+/// * `prompt` - The prompt to be displayed.
+/// * `state` - The line number, the Vi mode, and the last status shown by the prompt.
+/// * `theme` - The colors of the REPL.
 ///
 /// # Returns
 ///
-/// * `Result<()>` - Ok(()) if printing is successful, Err(io::Error) otherwise.
-fn print_message(stdout: &mut Stdout, msg: &str) -> Result<()> {
-    stdout
-        .queue(Print("\n"))?
-        .queue(MoveToColumn(0))?
-        .queue(Print(msg))?
-        .queue(Print("\n"))?
-        .queue(MoveToColumn(0))?;
+/// * `Result<()>` - Ok(()) if displaying the prompt is successful, Err(io::Error) otherwise.
+fn prompt(
+    stdout: &mut Styled<Stdout>,
+    prompt: &Prompt,
+    state: &State,
+    theme: &Theme,
+) -> Result<()> {
+    prompt.write(stdout, state, theme)?;
     stdout.flush()?;
     Ok(())
 }
 
-/// Displays the REPL prompt with the provided message.
+/// Displays the row of the prompt before the input line again, after what it shows changed.
+///
+/// The input line has to be rendered after it.
 ///
 /// # Arguments
 ///
 /// * `stdout` - The standard output.
-/// * `prompt` - The prompt message to be displayed.
+/// * `line` - The line buffer after the prompt.
+/// * `prompt` - The prompt to be displayed.
+/// * `state` - The line number, the Vi mode, and the last status shown by the prompt.
+/// * `theme` - The colors of the REPL.
 ///
 /// # Returns
 ///
 /// * `Result<()>` - Ok(()) if displaying the prompt is successful, Err(io::Error) otherwise.
-fn prompt(stdout: &mut Stdout, prompt: &str) -> Result<()> {
-    stdout
-        .execute(SetForegroundColor(Color::Blue))?
-        .execute(Print(prompt))?
-        .execute(ResetColor)?;
-    stdout.flush()?;
+fn reprompt(
+    stdout: &mut Styled<Stdout>,
+    line: &mut LineBuffer,
+    prompt: &Prompt,
+    state: &State,
+    theme: &Theme,
+) -> Result<()> {
+    if line.drawn.row > 0 {
+        stdout.queue(MoveUp(line.drawn.row))?;
+    }
+    stdout.queue(MoveToColumn(0))?;
+    prompt.last_line().write(stdout, state, theme)?;
+    line.drawn.row = 0;
     Ok(())
 }
 
-/// Runs the Read-Eval-Print Loop (REPL) for interactive input.
+/// Re-renders the input line with syntax highlighting and restores the caret.
+///
+/// Lines after the first one are indented to the column where the input starts, and lines longer
+/// than the terminal are wrapped explicitly so the caret can be tracked across rows.
 ///
 /// # Arguments
 ///
-/// * `mode` - The initial cursor mode for the REPL ("normal", "vi", or "emacs").
+/// * `stdout` - The standard output.
+/// * `line` - The line buffer to be rendered.
+/// * `theme` - The colors of the syntax.
+/// * `hint` - The rest of a line of the history suggested after the input, drawn dimmed only
+///   when the output is colored.
 ///
 /// # Returns
 ///
-/// * `Result<()>` - Ok(()) if the REPL runs successfully, Err(io::Error) otherwise.
-pub fn repl(mode: String) -> Result<()> {
-    let edit_mode = CursorMode::new(mode);
-    let mut line = LineBuffer::new();
-    let mut stdout: Stdout = stdout();
+/// * `Result<()>` - Ok(()) if rendering is successful, Err(io::Error) otherwise.
+fn render(
+    stdout: &mut Styled<Stdout>,
+    line: &mut LineBuffer,
+    theme: &Theme,
+    hint: Option<&str>,
+) -> Result<()> {
+    if line.drawn.row > 0 {
+        stdout.queue(MoveUp(line.drawn.row))?;
+    }
+    stdout
+        .queue(MoveToColumn(line.origin.col - 1))?
+        .queue(Clear(ClearType::FromCursorDown))?;
+
+    let available = line.available();
+    let newline = format!("\r\n{}", " ".repeat(line.origin.col as usize - 1));
+    let mut spans = highlight(&line.buffer, line.index, theme);
+    if let (Some(hint), Some(color)) = (hint, theme.suggestion) {
+        if stdout.colored() {
+            spans.push(Span::new(Some(color), hint.to_string()));
+        }
+    }
 
-    terminal::enable_raw_mode()?;
-    'repl: loop {
-        prompt(&mut stdout, "> ")?;
+    // The rows drawn after the first one, counting the rows of the suggestion
+    let mut rows = 0;
+    let mut column = 0;
+    for span in spans {
+        let mut text = String::new();
+        for c in span.text.chars() {
+            if c == '\n' {
+                text.push_str(&newline);
+                rows += 1;
+                column = 0;
+                continue;
+            }
 
-        let mut start: Cell = position()
-            .map(|(col, row)| Cell::new(col, row))
-            .unwrap_or_else(|_| Cell::new(1, 1));
-        start.col += 1;
-        start.row += 1;
-        line.caret.col = start.col;
-        line.caret.row = start.row;
+            let (before, after) = wrap(&mut column, c, available);
+            if before {
+                text.push_str(&newline);
+                rows += 1;
+            }
+            text.push(c);
+            if after {
+                text.push_str(&newline);
+                rows += 1;
+            }
+        }
 
-        stdout.flush()?;
-        'input: loop {
-            match edit_mode {
-                CursorMode::Normal => match read()? {
-                    Event::Key(KeyEvent {
-                        code, modifiers, ..
-                    }) => match code {
-                        KeyCode::Char(c) => {
-                            if modifiers == KeyModifiers::CONTROL && c == 'd' {
-                                break 'repl;
-                            }
+        stdout.paint(&text, span.color, span.background)?;
+    }
 
-                            line.buffer.push(c);
-                            stdout.queue(Print(c))?;
-                            stdout.flush()?;
-                        }
+    if rows > line.caret.row {
+        stdout.queue(MoveUp(rows - line.caret.row))?;
+    }
+    stdout.queue(MoveToColumn(line.caret.col - 1))?;
+    stdout.flush()?;
 
-                        KeyCode::Enter => {
-                            // let (_, ok) = validate(&line.buffer);
-                            // if ok {
-                            break 'input;
-                            // } else {
-                            //     prompt(&mut stdout, "... ")?;
-                            // }
-                        }
+    line.drawn = line.caret.clone();
+    Ok(())
+}
 
-                        KeyCode::Backspace => {}
+/// Returns the rest of the most recent line of the history starting with the input, suggested
+/// while the caret is at the end of the input.
+///
+/// # Arguments
+///
+/// * `history` - The lines entered before.
+/// * `line` - The line buffer being written.
+///
+/// # Returns
+///
+/// * `Option<String>` - The text after the input of the suggested line, if there is one.
+fn suggestion(history: &History, line: &LineBuffer) -> Option<String> {
+    if line.buffer.is_empty() || line.index < line.len() {
+        return None;
+    }
+    let suggested = history.suggest(&line.buffer)?;
+    Some(suggested[line.buffer.len()..].to_string())
+}
 
-                        KeyCode::Delete => {}
+/// Returns the terminal cell of the cursor, right after the prompt.
+fn origin() -> Cell {
+    let mut start: Cell = position()
+        .map(|(col, row)| Cell::new(col, row))
+        .unwrap_or_else(|_| Cell::new(1, 1));
+    start.col += 1;
+    start.row += 1;
+    start
+}
 
-                        KeyCode::Up => {}
+/// Clears the screen and displays the prompt at the top, before the input line is rendered again.
+///
+/// # Arguments
+///
+/// * `stdout` - The standard output.
+/// * `line` - The line buffer after the prompt, which is kept.
+/// * `prompt` - The prompt to be displayed.
+/// * `state` - The line number, the Vi mode, and the last status shown by the prompt.
+/// * `theme` - The colors of the REPL.
+///
+/// # Returns
+///
+/// * `Result<()>` - Ok(()) if clearing is successful, Err(io::Error) otherwise.
+fn clear_screen(
+    stdout: &mut Styled<Stdout>,
+    line: &mut LineBuffer,
+    prompt: &Prompt,
+    state: &State,
+    theme: &Theme,
+) -> Result<()> {
+    stdout
+        .queue(Clear(ClearType::All))?
+        .queue(Clear(ClearType::Purge))?
+        .queue(MoveTo(0, 0))?;
+    prompt.write(stdout, state, theme)?;
+    stdout.flush()?;
 
-                        KeyCode::Left => {}
+    line.origin = origin();
+    line.drawn.row = 0;
+    line.resize(line.width);
+    Ok(())
+}
 
-                        KeyCode::Down => {}
+/// Moves the terminal cursor below the last line of the input.
+///
+/// # Arguments
+///
+/// * `stdout` - The standard output.
+/// * `line` - The line buffer that was rendered.
+///
+/// # Returns
+///
+/// * `Result<()>` - Ok(()) if moving is successful, Err(io::Error) otherwise.
+fn finish(stdout: &mut Styled<Stdout>, line: &LineBuffer) -> Result<()> {
+    let rows = line.rows();
+    if rows > line.drawn.row {
+        stdout.queue(MoveDown(rows - line.drawn.row))?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
 
-                        KeyCode::Right => {}
+/// Prints an error in the color of errors.
+///
+/// # Arguments
+///
+/// * `stdout` - The standard output.
+/// * `message` - The error to be printed.
+/// * `theme` - The colors of the REPL.
+///
+/// # Returns
+///
+/// * `Result<()>` - Ok(()) if printing is successful, Err(io::Error) otherwise.
+fn report(stdout: &mut Styled<Stdout>, message: &str, theme: &Theme) -> Result<()> {
+    stdout.paint(&format!("{}\n", message), theme.error, None)?;
+    stdout.flush()
+}
 
-                        _ => {}
-                    },
+/// Runs the Read-Eval-Print Loop (REPL) for interactive input.
+///
+/// # Arguments
+///
+/// * `config` - The cursor mode, the template of the prompt, the colors, and the size of the
+///   history of the REPL.
+/// * `engine` - The engine evaluating the entered lines, with its limits already set.
+///
+/// Pressing Ctrl-C while a line is evaluated interrupts it, keeping the variables and functions
+/// defined before.
+///
+/// # Returns
+///
+/// * `Result<()>` - Ok(()) if the REPL runs successfully, Err(io::Error) otherwise.
+pub fn repl(config: &Config, mut engine: Engine) -> Result<()> {
+    let edit_mode = CursorMode::new(config.mode.clone());
+    let theme = &config.theme;
+    let template = Prompt::parse(&config.prompt).map_err(Error::other)?;
+    let mut state = State {
+        line: 1,
+        mode: None,
+        ok: true,
+    };
+    let mut line = LineBuffer::new();
+    let mut completer = Completer::new();
+    let mut history = History::new(config.history_size);
+    let mut keymap = Keymap::new(&edit_mode);
+    for binding in &config.bindings {
+        keymap.bind(binding);
+    }
+    let mut kill_ring = KillRing::new();
+    let mut pending = Vec::new(); // The keys pressed that start longer keys with an action
+    let mut yanked = None; // How many characters were just yanked, which popping replaces
+    let mut timing = false; // Whether how long every line took is printed, with `:time on`
+    let mut stdout = style::stdout();
+
+    // The terminal only turns Ctrl-C into a signal while a line is evaluated, outside raw mode
+    let interrupt = Arc::new(AtomicBool::new(false));
+    #[cfg(unix)]
+    signal_hook::flag::register(signal_hook::consts::SIGINT, Arc::clone(&interrupt))?;
+    engine.set_interrupt(Some(interrupt));
 
-                    Event::Mouse(_) => {}
+    'repl: loop {
+        terminal::enable_raw_mode()?;
+        stdout.execute(EnableBracketedPaste)?;
+        // Every line starts in the insert mode of the Vi cursor mode
+        if let CursorMode::Vi = edit_mode {
+            state.mode = Some(ViMode::Insert);
+        }
+        prompt(&mut stdout, &template, &state, theme)?;
+        line.start(origin());
+        line.resize(terminal::size()?.0);
 
-                    Event::Resize(width, height) => {
-                        print_message(&mut stdout, &format!("width: {width}, height: {height}"))?;
+        stdout.flush()?;
+        'input: loop {
+            match read()? {
+                Event::Key(event) => {
+                    pending.push(Key::from(event));
+                    let action = keymap.action(&pending, state.mode);
+                    if action.is_none() && keymap.is_prefix(&pending, state.mode) {
+                        continue;
                     }
-
-                    _ => {}
-                },
-
-                CursorMode::Vi => match read()? {
-                    Event::Key(KeyEvent {
-                        code, modifiers, ..
-                    }) => match code {
-                        KeyCode::Char(c) => {
-                            if modifiers == KeyModifiers::CONTROL && c == 'd' {
-                                break 'repl;
+                    pending.clear();
+                    let Some(action) = action else {
+                        continue;
+                    };
+                    if action != Action::Complete {
+                        completer.reset();
+                    }
+                    let popped = yanked.take();
+                    let before = line.snapshot();
+                    let hint = suggestion(&history, &line);
+
+                    let mode = state.mode;
+                    match action {
+                        Action::Insert(c) => indent::insert(&mut line, c),
+                        Action::Complete => {
+                            completer.complete(&mut line);
+                        }
+                        Action::AcceptLine => {
+                            if indent::depth(&line.buffer) == 0 {
+                                // The suggestion is not part of the line evaluated
+                                render(&mut stdout, &mut line, theme, None)?;
+                                break 'input;
                             }
-
-                            line.buffer.push(c);
-                            stdout.queue(Print(c))?;
-                            stdout.flush()?;
+                            indent::newline(&mut line);
                         }
-
-                        KeyCode::Enter => {}
-
-                        KeyCode::Backspace => {}
-
-                        KeyCode::Delete => {}
-
-                        KeyCode::Up => {}
-
-                        KeyCode::Left => {}
-
-                        KeyCode::Down => {}
-
-                        KeyCode::Right => {}
-
-                        _ => {}
-                    },
-
-                    Event::Mouse(_) => {}
-
-                    Event::Resize(width, height) => {
-                        print_message(&mut stdout, &format!("width: {width}, height: {height}"))?;
-                    }
-                    _ => {}
-                },
-
-                CursorMode::Emacs => match read()? {
-                    Event::Key(KeyEvent {
-                        code, modifiers, ..
-                    }) => match code {
-                        KeyCode::Char(c) => {
-                            if modifiers == KeyModifiers::CONTROL && c == 'd' {
-                                break 'repl;
+                        Action::Newline => indent::newline(&mut line),
+                        Action::Backspace => line.backspace(),
+                        Action::Delete => line.delete(),
+                        // Moving past the end of the input accepts the suggestion
+                        Action::MoveRight | Action::LineEnd if hint.is_some() => {
+                            line.insert_str(hint.as_deref().unwrap_or_default());
+                        }
+                        Action::MoveLeft => line.move_left(),
+                        Action::MoveRight => line.move_right(),
+                        Action::LineStart => line.move_home(),
+                        Action::LineEnd => line.move_end(),
+                        Action::HistoryPrevious => {
+                            if let Some(text) = history.previous(&line.buffer) {
+                                line.replace(text);
                             }
-
-                            line.buffer.push(c);
-                            stdout.queue(Print(c))?;
-                            stdout.flush()?;
                         }
-                        KeyCode::Enter => {}
-
-                        KeyCode::Backspace => {}
-
-                        KeyCode::Delete => {}
-
-                        KeyCode::Up => {}
-
-                        KeyCode::Left => {}
-
-                        KeyCode::Down => {}
-
-                        KeyCode::Right => {}
-
-                        _ => {}
-                    },
-
-                    Event::Mouse(_) => {}
+                        Action::HistoryNext => {
+                            if let Some(text) = history.next() {
+                                line.replace(text);
+                            }
+                        }
+                        Action::ClearScreen => {
+                            clear_screen(&mut stdout, &mut line, &template, &state, theme)?
+                        }
+                        Action::Exit => {
+                            render(&mut stdout, &mut line, theme, None)?;
+                            break 'repl;
+                        }
+                        Action::KillToEnd => kill_ring.kill(line.kill_to_end()),
+                        Action::KillToStart => kill_ring.kill(line.kill_to_start()),
+                        Action::KillLine => kill_ring.kill(line.kill_line()),
+                        Action::Yank => {
+                            if let Some(text) = kill_ring.yank() {
+                                let start = line.index;
+                                line.insert_str(text);
+                                yanked = Some(line.index - start);
+                            }
+                        }
+                        Action::YankPop => {
+                            // Only the text just yanked is replaced
+                            if let Some(count) = popped {
+                                if let Some(text) = kill_ring.pop() {
+                                    let start = line.index - count;
+                                    line.remove(start, line.index);
+                                    line.insert_str(text);
+                                    yanked = Some(line.index - start);
+                                }
+                            }
+                        }
+                        Action::Undo => {
+                            line.undo();
+                        }
+                        Action::Redo => {
+                            line.redo();
+                        }
+                        // The modes only exist in the Vi cursor mode
+                        _ if state.mode.is_none() => {}
+                        Action::NormalMode => {
+                            state.mode = Some(ViMode::Normal);
+                            line.move_left();
+                        }
+                        Action::InsertMode => state.mode = Some(ViMode::Insert),
+                        Action::Append => {
+                            line.move_right();
+                            state.mode = Some(ViMode::Insert);
+                        }
+                    }
 
-                    Event::Resize(width, height) => {
-                        print_message(&mut stdout, &format!("width: {width}, height: {height}"))?;
+                    if !matches!(action, Action::Undo | Action::Redo) {
+                        line.record(before, matches!(action, Action::Insert(_)));
+                    }
+                    if state.mode != mode {
+                        reprompt(&mut stdout, &mut line, &template, &state, theme)?;
                     }
-                    _ => {}
-                },
+                    let hint = suggestion(&history, &line);
+                    render(&mut stdout, &mut line, theme, hint.as_deref())?;
+                }
+
+                Event::Paste(text) => {
+                    completer.reset();
+                    let before = line.snapshot();
+                    line.insert_str(&text);
+                    line.record(before, false);
+                    let hint = suggestion(&history, &line);
+                    render(&mut stdout, &mut line, theme, hint.as_deref())?;
+                }
+
+                Event::Resize(width, _) => {
+                    line.resize(width);
+                    let hint = suggestion(&history, &line);
+                    render(&mut stdout, &mut line, theme, hint.as_deref())?;
+                }
+
+                _ => {}
             }
         }
+        finish(&mut stdout, &line)?;
+        stdout.execute(DisableBracketedPaste)?;
         terminal::disable_raw_mode()?;
         println!();
-        let mut evaluator = Evaluator::new(&line.buffer);
-        evaluator.eval();
-        line.buffer.clear();
+        state.ok = match Command::parse(&line.buffer) {
+            Some(Ok(Command::Trace(on))) => {
+                engine.set_trace(on);
+                true
+            }
+            Some(Ok(Command::Time(on))) => {
+                timing = on;
+                true
+            }
+            // The history is kept, but the names of the session are no longer completed
+            Some(Ok(Command::Reset)) => {
+                engine.reset();
+                completer = Completer::new();
+                true
+            }
+            Some(Ok(Command::Type(expression))) => match engine.infer(&expression) {
+                Ok(ty) => {
+                    let name = ty.map_or("unknown", |ty| ty.name());
+                    stdout.paint(&format!("{}\n", name), theme.ty, None)?;
+                    stdout.flush()?;
+                    true
+                }
+                Err(error) => {
+                    report(&mut stdout, &error.to_string(), theme)?;
+                    false
+                }
+            },
+            Some(Ok(Command::Doc(name))) => match engine.doc(&name) {
+                Some(doc) => {
+                    println!("{}", doc);
+                    true
+                }
+                None => {
+                    report(&mut stdout, &format!("no function named `{}`", name), theme)?;
+                    false
+                }
+            },
+            Some(Err(message)) => {
+                report(&mut stdout, &message, theme)?;
+                false
+            }
+            None => {
+                let start = Instant::now();
+                let result = engine.eval_str(&line.buffer);
+                let elapsed = start.elapsed();
+                let ok = match result {
+                    Ok(Value::None) => true,
+                    Ok(value) => {
+                        println!("{}", value.repr());
+                        true
+                    }
+                    // The terminal is already restored, so the session can end right away
+                    Err(HydrogenError::Exit(_)) => return Ok(()),
+                    Err(error) => {
+                        report(&mut stdout, &error.to_string(), theme)?;
+                        false
+                    }
+                };
+                if timing && !line.buffer.trim().is_empty() {
+                    let time = format!("time: {:.2?}, {} steps\n", elapsed, engine.steps());
+                    stdout.paint(&time, theme.comment, None)?;
+                    stdout.flush()?;
+                }
+                ok
+            }
+        };
+        if !line.buffer.trim().is_empty() {
+            state.line += 1;
+        }
+        history.push(&line.buffer);
+        completer.learn(&line.buffer);
+        line.clear();
     }
 
+    finish(&mut stdout, &line)?;
+    stdout.execute(DisableBracketedPaste)?;
     terminal::disable_raw_mode()?;
     println!();
     Ok(())
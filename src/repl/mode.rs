@@ -1,4 +1,5 @@
 /// Enum representing different cursor modes in the Read-Eval-Print Loop (REPL).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CursorMode {
     /// Normal cursor mode.
     Normal,
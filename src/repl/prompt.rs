@@ -0,0 +1,228 @@
+use std::io::{self, Write};
+
+use crate::style::{Styled, Theme};
+
+/// A part of the template of the prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// Text shown as it is: (text)
+    Text(String),
+    /// `{line}`, the number of the line being entered
+    Line,
+    /// `{mode}`, `[N]` or `[I]` in the Vi cursor mode, and nothing in the others
+    Mode,
+    /// `{status}`, a `❯` colored as a success or an error, after how the last line went
+    Status,
+}
+
+/// Enum representing whether the keys typed in the Vi cursor mode move the caret or are inserted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViMode {
+    /// Keys are commands, like `h` and `l` moving the caret
+    Normal,
+    /// Keys are inserted into the line
+    Insert,
+}
+
+impl ViMode {
+    /// Returns how the prompt shows the mode: `[N]` or `[I]`.
+    pub fn indicator(self) -> &'static str {
+        match self {
+            ViMode::Normal => "[N]",
+            ViMode::Insert => "[I]",
+        }
+    }
+}
+
+/// What the prompt can show about the REPL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct State {
+    /// The number of the line being entered, starting at 1.
+    pub line: usize,
+    /// The mode of the Vi cursor mode, or None in the other cursor modes.
+    pub mode: Option<ViMode>,
+    /// Whether the last line entered was evaluated without an error.
+    pub ok: bool,
+}
+
+/// The prompt of the REPL, made from a template of the configuration.
+///
+/// The template is text with placeholders: `{line}` for the number of the line being entered,
+/// `{mode}` for `[N]` or `[I]` in the Vi cursor mode, and `{status}` for a `❯` in the color of
+/// success or of errors after how the last line went. `{{` and `}}` stand for braces.
+///
+/// # Examples
+/// ```
+/// use hydrogen::repl::prompt::{Prompt, State, ViMode};
+/// use hydrogen::style::{Styled, Theme};
+///
+/// let prompt = Prompt::parse("{line} {mode}> ").unwrap();
+/// let state = State { line: 3, mode: Some(ViMode::Normal), ok: true };
+///
+/// let mut plain = Styled::new(Vec::new(), false);
+/// prompt.write(&mut plain, &state, &Theme::DARK).unwrap();
+/// assert_eq!(plain.into_inner(), b"3 [N]> ");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prompt {
+    segments: Vec<Segment>,
+}
+
+impl Prompt {
+    /// Parses the template of a prompt.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The text of the prompt, with placeholders between braces.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Prompt, String>` - The prompt, or why the template is invalid.
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => {
+                                let problem = "unclosed `{` in the prompt, write `{{` for a brace";
+                                return Err(problem.to_string());
+                            }
+                        }
+                    }
+                    let segment = match name.as_str() {
+                        "line" => Segment::Line,
+                        "mode" => Segment::Mode,
+                        "status" => Segment::Status,
+                        _ => {
+                            return Err(format!("unknown placeholder `{{{}}}` in the prompt", name))
+                        }
+                    };
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(segment);
+                }
+                '}' => {
+                    return Err("unmatched `}` in the prompt, write `}}` for a brace".to_string())
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Returns the part of the prompt after its last line break, which is on the row of the
+    /// input.
+    pub fn last_line(&self) -> Self {
+        let mut segments = Vec::new();
+        for segment in self.segments.iter().rev() {
+            if let Segment::Text(text) = segment {
+                if let Some((_, last)) = text.rsplit_once('\n') {
+                    if !last.is_empty() {
+                        segments.push(Segment::Text(last.to_string()));
+                    }
+                    break;
+                }
+            }
+            segments.push(segment.clone());
+        }
+        segments.reverse();
+        Self { segments }
+    }
+
+    /// Writes the prompt.
+    ///
+    /// # Arguments
+    ///
+    /// * `out` - Where the prompt is written.
+    /// * `state` - What the placeholders show.
+    /// * `theme` - The colors of the prompt and of the status.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<()>` - Ok(()) if writing is successful, Err(io::Error) otherwise.
+    pub fn write<W: Write>(
+        &self,
+        out: &mut Styled<W>,
+        state: &State,
+        theme: &Theme,
+    ) -> io::Result<()> {
+        for segment in &self.segments {
+            match segment {
+                // Rows of the terminal in raw mode need a carriage return
+                Segment::Text(text) => {
+                    out.paint(&text.replace('\n', "\r\n"), theme.prompt, None)?
+                }
+                Segment::Line => out.paint(&state.line.to_string(), theme.prompt, None)?,
+                Segment::Mode => {
+                    let indicator = state.mode.map_or("", ViMode::indicator);
+                    out.paint(indicator, theme.prompt, None)?
+                }
+                Segment::Status => {
+                    let color = if state.ok { theme.success } else { theme.error };
+                    out.paint("❯", color, None)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crossterm::style::Color;
+
+    use super::*;
+
+    #[test]
+    fn test_prompt() {
+        let written = |prompt: &Prompt, state, colored| {
+            let mut out = Styled::new(Vec::new(), colored);
+            prompt.write(&mut out, &state, &Theme::DARK).unwrap();
+            String::from_utf8(out.into_inner()).unwrap()
+        };
+        let state = State {
+            line: 12,
+            mode: None,
+            ok: false,
+        };
+
+        let prompt = Prompt::parse("hy {{{line}}}\n{mode}{status} ").unwrap();
+        assert_eq!(written(&prompt, state, false), "hy {12}\r\n❯ ");
+        assert_eq!(written(&prompt.last_line(), state, false), "❯ ");
+
+        // The status is red after an error, and green after a success
+        let mut red = Styled::new(Vec::new(), true);
+        red.paint("❯", Some(Color::Red), None).unwrap();
+        let red = String::from_utf8(red.into_inner()).unwrap();
+        assert!(written(&prompt, state, true).contains(&red));
+        let ok = State { ok: true, ..state };
+        assert!(!written(&prompt, ok, true).contains(&red));
+
+        assert!(Prompt::parse("{lines}> ").is_err());
+        assert!(Prompt::parse("} ").is_err());
+        assert!(Prompt::parse("{line").is_err());
+        assert_eq!(
+            Prompt::parse("> ").unwrap().last_line(),
+            Prompt::parse("> ").unwrap()
+        );
+    }
+}
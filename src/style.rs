@@ -0,0 +1,291 @@
+use std::{
+    env,
+    io::{self, IsTerminal, Stderr, Stdout, Write},
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use crossterm::{
+    style::{Color, Colored, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+    QueueableCommand,
+};
+
+/// Enum representing when output is colored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color output written to a terminal, unless the `NO_COLOR` environment variable is set
+    #[default]
+    Auto,
+    /// Always color output, even when it is piped
+    Always,
+    /// Never color output
+    Never,
+}
+
+impl ColorChoice {
+    /// Returns whether output written to a stream is colored.
+    ///
+    /// # Arguments
+    ///
+    /// * `terminal` - Whether the stream is a terminal.
+    pub fn enabled(self, terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            // Any value turns colors off, see https://no-color.org
+            ColorChoice::Auto => terminal && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()),
+        }
+    }
+}
+
+/// The choice of the whole program, set once from the command line.
+static CHOICE: AtomicU8 = AtomicU8::new(0);
+
+/// Sets when the output of the whole program is colored.
+///
+/// # Arguments
+///
+/// * `choice` - When output is colored.
+pub fn set_color_choice(choice: ColorChoice) {
+    let value = match choice {
+        ColorChoice::Auto => 0,
+        ColorChoice::Always => 1,
+        ColorChoice::Never => 2,
+    };
+    CHOICE.store(value, Ordering::Relaxed);
+    // Colors are left out by the terminal library itself when `NO_COLOR` is set
+    if choice == ColorChoice::Always {
+        Colored::set_ansi_color_disabled(false);
+    }
+}
+
+/// Returns when the output of the whole program is colored.
+pub fn color_choice() -> ColorChoice {
+    match CHOICE.load(Ordering::Relaxed) {
+        1 => ColorChoice::Always,
+        2 => ColorChoice::Never,
+        _ => ColorChoice::Auto,
+    }
+}
+
+/// The colors of the REPL: of the prompt, of the errors, and of the syntax of the input line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// The color of the prompt.
+    pub prompt: Option<Color>,
+    /// The color of errors, and of invalid tokens and unmatched brackets.
+    pub error: Option<Color>,
+    /// The color of the status of the prompt after a line evaluated without an error.
+    pub success: Option<Color>,
+    /// The color of keywords, and of `in` and `as`.
+    pub keyword: Option<Color>,
+    /// The color of type names.
+    pub ty: Option<Color>,
+    /// The color of string literals.
+    pub string: Option<Color>,
+    /// The color of number and boolean literals.
+    pub number: Option<Color>,
+    /// The color of comments.
+    pub comment: Option<Color>,
+    /// The background of a bracket and its partner under the caret.
+    pub matching: Option<Color>,
+    /// The color of the line of the history suggested after the input, or None to suggest no
+    /// lines.
+    pub suggestion: Option<Color>,
+}
+
+impl Theme {
+    /// The colors for terminals with a dark background.
+    pub const DARK: Theme = Theme {
+        prompt: Some(Color::Blue),
+        error: Some(Color::Red),
+        success: Some(Color::Green),
+        keyword: Some(Color::Magenta),
+        ty: Some(Color::Cyan),
+        string: Some(Color::Green),
+        number: Some(Color::Yellow),
+        comment: Some(Color::DarkGrey),
+        matching: Some(Color::DarkGrey),
+        suggestion: Some(Color::DarkGrey),
+    };
+
+    /// The colors for terminals with a light background, where light colors are hard to read.
+    pub const LIGHT: Theme = Theme {
+        prompt: Some(Color::DarkBlue),
+        error: Some(Color::DarkRed),
+        success: Some(Color::DarkGreen),
+        keyword: Some(Color::DarkMagenta),
+        ty: Some(Color::DarkCyan),
+        string: Some(Color::DarkGreen),
+        number: Some(Color::DarkYellow),
+        comment: Some(Color::Grey),
+        matching: Some(Color::Grey),
+        suggestion: Some(Color::Grey),
+    };
+
+    /// No colors at all, even when colors are on.
+    pub const PLAIN: Theme = Theme {
+        prompt: None,
+        error: None,
+        success: None,
+        keyword: None,
+        ty: None,
+        string: None,
+        number: None,
+        comment: None,
+        matching: None,
+        suggestion: None,
+    };
+
+    /// Returns the theme of a name: `dark`, `light`, or `plain`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the theme.
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Theme::DARK),
+            "light" => Some(Theme::LIGHT),
+            "plain" => Some(Theme::PLAIN),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::DARK
+    }
+}
+
+/// Writer that styles text only when colors are on for what it writes to.
+///
+/// All colored output goes through it, so piped output and `NO_COLOR` get plain text.
+///
+/// # Examples
+/// ```
+/// use crossterm::style::Color;
+/// use hydrogen::style::Styled;
+///
+/// let mut plain = Styled::new(Vec::new(), false);
+/// plain.paint("error", Some(Color::Red), None).unwrap();
+/// assert_eq!(plain.into_inner(), b"error");
+/// ```
+#[derive(Debug)]
+pub struct Styled<W: Write> {
+    writer: W,
+    colored: bool,
+}
+
+/// Returns the standard output, colored if the choice of the program allows it.
+pub fn stdout() -> Styled<Stdout> {
+    let stdout = io::stdout();
+    let colored = color_choice().enabled(stdout.is_terminal());
+    Styled::new(stdout, colored)
+}
+
+/// Returns the standard error, colored if the choice of the program allows it.
+pub fn stderr() -> Styled<Stderr> {
+    let stderr = io::stderr();
+    let colored = color_choice().enabled(stderr.is_terminal());
+    Styled::new(stderr, colored)
+}
+
+impl<W: Write> Styled<W> {
+    /// Creates a writer that styles text if colors are on.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The writer the text goes to.
+    /// * `colored` - Whether text is styled.
+    pub fn new(writer: W, colored: bool) -> Self {
+        Self { writer, colored }
+    }
+
+    /// Returns whether text is styled.
+    pub fn colored(&self) -> bool {
+        self.colored
+    }
+
+    /// Styles the text written after, until [`Styled::reset`].
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - The color of the text, if it has one.
+    /// * `background` - The color behind the text, if it has one.
+    pub fn set(&mut self, color: Option<Color>, background: Option<Color>) -> io::Result<()> {
+        if !self.colored {
+            return Ok(());
+        }
+        if let Some(color) = color {
+            self.writer.queue(SetForegroundColor(color))?;
+        }
+        if let Some(background) = background {
+            self.writer.queue(SetBackgroundColor(background))?;
+        }
+        Ok(())
+    }
+
+    /// Writes the text after this without a style.
+    pub fn reset(&mut self) -> io::Result<()> {
+        if self.colored {
+            self.writer.queue(ResetColor)?;
+        }
+        Ok(())
+    }
+
+    /// Writes text with a style.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text.
+    /// * `color` - The color of the text, if it has one.
+    /// * `background` - The color behind the text, if it has one.
+    pub fn paint(
+        &mut self,
+        text: &str,
+        color: Option<Color>,
+        background: Option<Color>,
+    ) -> io::Result<()> {
+        self.set(color, background)?;
+        self.writer.queue(Print(text))?;
+        self.reset()
+    }
+
+    /// Returns the writer the text goes to.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Write for Styled<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_styled() {
+        assert!(ColorChoice::Always.enabled(false));
+        assert!(!ColorChoice::Never.enabled(true));
+        assert!(!ColorChoice::Auto.enabled(false));
+
+        let mut colored = Styled::new(Vec::new(), true);
+        colored.paint("> ", Some(Color::Blue), None).unwrap();
+        let output = String::from_utf8(colored.into_inner()).unwrap();
+        assert!(output.contains("> ") && output.ends_with("\x1b[0m"));
+
+        let mut plain = Styled::new(Vec::new(), false);
+        plain.set(Some(Color::Red), Some(Color::DarkGrey)).unwrap();
+        write!(plain, "text").unwrap();
+        plain.reset().unwrap();
+        assert_eq!(plain.into_inner(), b"text");
+    }
+}
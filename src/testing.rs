@@ -0,0 +1,186 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    hash::{
+        ast::{Item, Stmt, Tree},
+        parser::Parser,
+    },
+    Engine, HydrogenError,
+};
+
+/// The directory of a project holding files that are tests of their own.
+const TESTS: &str = "tests";
+
+/// The prefix of the names of the functions that are tests.
+const PREFIX: &str = "test_";
+
+/// A test found in a Hydrogen file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Test {
+    /// The file the test is in.
+    pub file: PathBuf,
+    /// The function the test calls, or None if running the file is the test.
+    pub function: Option<String>,
+}
+
+impl Test {
+    /// Runs the test on a new engine, so it does not see what other tests defined.
+    ///
+    /// The file of the test is evaluated, and then the function of the test is called if it has
+    /// one.
+    ///
+    /// # Arguments
+    ///
+    /// * `configure` - Sets up the engine before the test runs.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), HydrogenError>` - Ok(()) if the test passed, or the error failing it.
+    pub fn run(&self, configure: &dyn Fn(&mut Engine)) -> Result<(), HydrogenError> {
+        let mut engine = Engine::new();
+        configure(&mut engine);
+        engine.eval_file(&self.file)?;
+        if let Some(function) = &self.function {
+            engine.eval_str(&format!("{}()", function))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Test {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.function {
+            Some(function) => write!(f, "{}::{}", self.file.display(), function),
+            None => write!(f, "{}", self.file.display()),
+        }
+    }
+}
+
+/// Finds the tests of a file or a directory.
+///
+/// Every function named `test_*` without parameters is a test. The `.hy` files of a directory
+/// and of its `tests` directory are searched, and a file of the `tests` directory without test
+/// functions is a test of its own, passing if it runs without errors. A file that does not parse
+/// is a test failing with the parse errors.
+///
+/// # Arguments
+///
+/// * `path` - The file or the directory to search.
+///
+/// # Returns
+///
+/// * `io::Result<Vec<Test>>` - The tests, in the order of their files and then of their
+///   definitions, or the error reading the files.
+pub fn discover(path: &Path) -> io::Result<Vec<Test>> {
+    let files = if path.is_dir() {
+        let mut files: Vec<_> = scripts(path)?.into_iter().map(|f| (f, false)).collect();
+        let tests = path.join(TESTS);
+        if tests.is_dir() {
+            files.extend(scripts(&tests)?.into_iter().map(|f| (f, true)));
+        }
+        files
+    } else {
+        vec![(path.to_path_buf(), false)]
+    };
+
+    let mut tests = Vec::new();
+    for (file, whole) in files {
+        let (functions, parsed) = match Parser::new(&fs::read_to_string(&file)?).parse_program() {
+            Ok(tree) => (test_functions(tree), true),
+            Err(_) => (Vec::new(), false),
+        };
+
+        if functions.is_empty() && (whole || !parsed || path.is_file()) {
+            tests.push(Test {
+                file,
+                function: None,
+            });
+            continue;
+        }
+        for function in functions {
+            tests.push(Test {
+                file: file.clone(),
+                function: Some(function),
+            });
+        }
+    }
+    Ok(tests)
+}
+
+/// Returns the names of the test functions defined by a program, in order.
+fn test_functions(tree: Tree) -> Vec<String> {
+    tree.into_iter()
+        .filter_map(|statement| match statement {
+            Stmt::Item(Item::Function(definition))
+                if definition.name.starts_with(PREFIX) && definition.parameters.is_empty() =>
+            {
+                Some(definition.name)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the `.hy` files of a directory, sorted by name.
+fn scripts(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for file in fs::read_dir(directory)? {
+        let file = file?.path();
+        if file.is_file() && file.extension().is_some_and(|extension| extension == "hy") {
+            files.push(file);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hash::evaluator::RuntimeError;
+
+    #[test]
+    fn test_discover() {
+        let tests = discover(Path::new("test/suite")).unwrap();
+        let names: Vec<_> = tests.iter().map(|test| test.to_string()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "test/suite/math.hy::test_square",
+                "test/suite/math.hy::test_negative",
+                "test/suite/tests/smoke.hy",
+            ]
+        );
+
+        assert!(tests[0].run(&|_| {}).is_ok());
+        let error = tests[1].run(&|_| {}).unwrap_err();
+        assert!(matches!(
+            &error,
+            HydrogenError::Runtime(RuntimeError::Assertion(message), _)
+                if message == "assertion failed: squares are never negative"
+        ));
+        assert!(error
+            .to_string()
+            .ends_with("\n    in `test_negative` at test/suite/math.hy:8\n    at line 1"));
+        assert!(tests[2].run(&|_| {}).is_ok());
+
+        let broken = discover(Path::new("test/broken")).unwrap();
+        assert_eq!(broken.len(), 1);
+        assert!(matches!(
+            broken[0].run(&|_| {}),
+            Err(HydrogenError::Parse(_))
+        ));
+
+        let file = discover(Path::new("test/project/main.hy")).unwrap();
+        assert_eq!(
+            file,
+            vec![Test {
+                file: PathBuf::from("test/project/main.hy"),
+                function: None
+            }]
+        );
+    }
+}
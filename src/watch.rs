@@ -0,0 +1,130 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::engine::MANIFEST;
+
+/// Watcher noticing when a script, or a file of a project, is changed, added, or removed.
+///
+/// Files are polled for their modification times, so no support of the operating system is
+/// needed.
+///
+/// # Examples
+/// ```
+/// use std::{env, fs};
+///
+/// use hydrogen::watch::Watcher;
+///
+/// let path = env::temp_dir().join("hydrogen_watch_example.hy");
+/// fs::write(&path, "print(1)").unwrap();
+///
+/// let mut watcher = Watcher::new(&path);
+/// assert!(!watcher.changed());
+///
+/// fs::remove_file(&path).unwrap();
+/// assert!(watcher.changed());
+/// assert!(!watcher.changed());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Watcher {
+    path: PathBuf,
+    stamps: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl Watcher {
+    /// Creates a watcher of a script, or of the scripts and the manifest of a project, as they
+    /// are now.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The script file, or the directory of the project.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let stamps = stamps(&path);
+        Self { path, stamps }
+    }
+
+    /// Returns whether a watched file changed since the watcher was created or last asked.
+    pub fn changed(&mut self) -> bool {
+        let stamps = stamps(&self.path);
+        let changed = stamps != self.stamps;
+        self.stamps = stamps;
+        changed
+    }
+
+    /// Waits until a watched file changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - How long to wait between two looks at the files.
+    pub fn wait(&mut self, interval: Duration) {
+        while !self.changed() {
+            thread::sleep(interval);
+        }
+    }
+}
+
+/// Returns the watched files of a script or a project, in order, with when they were last
+/// modified, or None for a file that cannot be read.
+fn stamps(path: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let files = match path.is_dir() {
+        true => project_files(path).unwrap_or_default(),
+        false => vec![path.to_path_buf()],
+    };
+    files
+        .into_iter()
+        .map(|file| {
+            let modified = fs::metadata(&file).and_then(|m| m.modified()).ok();
+            (file, modified)
+        })
+        .collect()
+}
+
+/// Returns the scripts and the manifest of a project, in the order of their names.
+fn project_files(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for file in fs::read_dir(directory)? {
+        let file = file?.path();
+        let script = file.extension().is_some_and(|extension| extension == "hy");
+        if script || file.file_name().is_some_and(|name| name == MANIFEST) {
+            files.push(file);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn test_watcher() {
+        let directory = env::temp_dir().join("hydrogen_test_watcher");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("main.hy"), "print(1)").unwrap();
+        fs::write(directory.join("notes.txt"), "").unwrap();
+
+        let mut watcher = Watcher::new(&directory);
+        assert!(!watcher.changed());
+
+        // Other files of the directory are not watched
+        fs::write(directory.join("notes.txt"), "changed").unwrap();
+        assert!(!watcher.changed());
+
+        // Scripts added to the project and the manifest are
+        fs::write(directory.join("util.hy"), "x = 1").unwrap();
+        assert!(watcher.changed());
+        fs::write(directory.join(MANIFEST), "entry = \"main.hy\"").unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}